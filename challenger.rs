@@ -7,13 +7,29 @@
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
 use rand::Rng;
-use rand_distr::{Normal, Distribution};
+use sha2::{Digest, Sha256};
+use fhe_voting_verifier::types::{ChallengeOutput, ChallengeRejection, ChallengeRejectionReason as RejectionReason};
+use fhe_voting_verifier::verify_challenge;
+use risc0_zkvm::{InnerReceipt, Receipt, VerifierContext};
+
+// Encryption noise, shared with the guest and the host client
+#[path = "methods/guest/src/noise_distribution.rs"]
+mod noise_distribution;
+use noise_distribution::NoiseDistribution;
 
 // Production-level FHE parameters (must match guest implementation)
 const PLAINTEXT_MODULUS: u64 = 65537;
 const CIPHERTEXT_MODULUS: u64 = 288230376151711744; // 2^58
 const POLYNOMIAL_DEGREE: usize = 32;
-const NOISE_STANDARD_DEVIATION: f64 = 3.19;
+
+const SECRET_HAMMING_WEIGHT: u32 = POLYNOMIAL_DEGREE as u32 / 2; // Nonzero ternary secret coefficients
+// Domain separation tags (must match guest implementation)
+const KEY_FINGERPRINT_TAG: u8 = 0x05;
+const PARAMETER_DIGEST_TAG: u8 = 0x0f;
+const CIRCUIT_TAG: u8 = 0x0d;
+const PUBLIC_POLYNOMIAL_TAG: u8 = 0x1f;
+/// Challenge plaintexts are drawn uniformly from 0..CHALLENGE_VALUES (the vote options).
+pub const CHALLENGE_VALUES: i64 = 3;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChallengeKeys {
@@ -53,23 +69,164 @@ pub struct ChallengeInput {
     pub parameters: FheParameters,
     pub public_key: PublicKey,
     pub challenge_ciphertexts: Vec<Vec<u8>>, // Serialized ciphertexts
+    pub circuit: ChallengeCircuit,
     pub challenge_metadata: ChallengeMetadata,
 }
 
+/// One instruction of the circuit interpreter, over named ciphertext registers
+/// (must match guest `CircuitOp`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CircuitOp {
+    Add { out: String, lhs: String, rhs: String },
+    Sub { out: String, lhs: String, rhs: String },
+    MulPlain { out: String, input: String, scalar: u64 }, // Scalar below the plaintext modulus
+    Rotate { out: String, input: String, steps: u32 }, // Multiply by X^steps; N steps negate
+}
+
+/// The computation the guest must run. Challenge i is input register `c{i}`;
+/// the guest commits the digest of the circuit it executed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeCircuit {
+    pub ops: Vec<CircuitOp>,
+    pub outputs: Vec<String>,
+}
+
+impl ChallengeCircuit {
+    /// The sum of challenges 0..count as `sum`, and challenge 0 doubled as `doubled`.
+    pub fn sum(count: usize) -> Self {
+        let mut ops = vec![CircuitOp::MulPlain { out: "sum".into(), input: "c0".into(), scalar: 1 }];
+        for i in 1..count {
+            ops.push(CircuitOp::Add { out: "sum".into(), lhs: "sum".into(), rhs: format!("c{}", i) });
+        }
+        ops.push(CircuitOp::MulPlain { out: "doubled".into(), input: "c0".into(), scalar: 2 });
+        ChallengeCircuit { ops, outputs: vec!["sum".into(), "doubled".into()] }
+    }
+    
+    /// H(tag || op count || ops || output count || output names), as the guest computes it
+    pub fn digest(&self) -> [u8; 32] {
+        fn extend_with_name(buf: &mut Vec<u8>, name: &str) {
+            buf.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            buf.extend_from_slice(name.as_bytes());
+        }
+        let mut buf = vec![CIRCUIT_TAG];
+        buf.extend_from_slice(&(self.ops.len() as u32).to_le_bytes());
+        for op in &self.ops {
+            match op {
+                CircuitOp::Add { out, lhs, rhs } | CircuitOp::Sub { out, lhs, rhs } => {
+                    buf.push(if matches!(op, CircuitOp::Add { .. }) { 0 } else { 1 });
+                    for name in [out, lhs, rhs] {
+                        extend_with_name(&mut buf, name);
+                    }
+                }
+                CircuitOp::MulPlain { out, input, scalar } => {
+                    buf.push(2);
+                    extend_with_name(&mut buf, out);
+                    extend_with_name(&mut buf, input);
+                    buf.extend_from_slice(&scalar.to_le_bytes());
+                }
+                CircuitOp::Rotate { out, input, steps } => {
+                    buf.push(3);
+                    extend_with_name(&mut buf, out);
+                    extend_with_name(&mut buf, input);
+                    buf.extend_from_slice(&steps.to_le_bytes());
+                }
+            }
+        }
+        buf.extend_from_slice(&(self.outputs.len() as u32).to_le_bytes());
+        for name in &self.outputs {
+            extend_with_name(&mut buf, name);
+        }
+        Sha256::digest(&buf).into()
+    }
+    
+    /// Run the circuit on the plaintexts: the values the output registers must
+    /// decrypt to, mod t. Registers are whole plaintext polynomials so that
+    /// rotations land where the guest's do; only the constant term decrypts.
+    pub fn evaluate(&self, plaintexts: &[i64]) -> Result<Vec<i64>, String> {
+        let t = PLAINTEXT_MODULUS as i64;
+        let mut registers: HashMap<String, Vec<i64>> = plaintexts.iter().enumerate().map(|(i, &value)| {
+            let mut polynomial = vec![0; POLYNOMIAL_DEGREE];
+            polynomial[0] = value.rem_euclid(t);
+            (format!("c{}", i), polynomial)
+        }).collect();
+        for op in &self.ops {
+            let read = |name: &String| registers.get(name).cloned().ok_or_else(|| format!("Register {} is read before it is written", name));
+            let (out, value) = match op {
+                CircuitOp::Add { out, lhs, rhs } => (out, read(lhs)?.iter().zip(read(rhs)?).map(|(a, b)| (a + b).rem_euclid(t)).collect()),
+                CircuitOp::Sub { out, lhs, rhs } => (out, read(lhs)?.iter().zip(read(rhs)?).map(|(a, b)| (a - b).rem_euclid(t)).collect()),
+                CircuitOp::MulPlain { out, input, scalar } => {
+                    (out, read(input)?.iter().map(|&a| (a as i128 * *scalar as i128).rem_euclid(t as i128) as i64).collect())
+                }
+                CircuitOp::Rotate { out, input, steps } => {
+                    let steps = *steps as usize % (2 * POLYNOMIAL_DEGREE);
+                    let mut rotated = vec![0; POLYNOMIAL_DEGREE];
+                    for (i, a) in read(input)?.into_iter().enumerate() {
+                        let shifted = i + steps;
+                        let negate = (shifted / POLYNOMIAL_DEGREE) % 2 == 1;
+                        rotated[shifted % POLYNOMIAL_DEGREE] = if negate { (-a).rem_euclid(t) } else { a };
+                    }
+                    (out, rotated)
+                }
+            };
+            registers.insert(out.clone(), value);
+        }
+        self.outputs.iter()
+            .map(|name| registers.get(name).map(|polynomial| polynomial[0]).ok_or_else(|| format!("Output register {} is never written", name)))
+            .collect()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ChallengeMetadata {
     pub test_id: String,
     pub challenge_plaintexts: Vec<i64>, // For verification (challenger keeps private)
     pub expected_operations: Vec<String>,
     pub timestamp: u64,
+    pub injected: Vec<ChallengeRejection>, // Invalid ciphertexts the guest must reject (challenger keeps private)
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// What the prover receives of a challenge (must match host `ChallengeInput`):
+/// no plaintexts and no record of which ciphertexts were made invalid.
+#[derive(Debug, Serialize)]
+pub struct ProverChallenge {
+    pub public_key: PublicKey,
+    pub challenge_ciphertexts: Vec<Vec<u8>>,
+    pub circuit: ChallengeCircuit,
+    pub test_metadata: ProverMetadata,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProverMetadata {
+    pub test_id: String,
+    pub num_challenges: usize,
+    pub expected_operations: Vec<String>,
+    pub timestamp: u64,
+}
+
+impl ChallengeInput {
+    /// The public part of the challenge, to send to the prover
+    pub fn prover_challenge(&self) -> ProverChallenge {
+        let metadata = &self.challenge_metadata;
+        ProverChallenge {
+            public_key: self.public_key.clone(),
+            challenge_ciphertexts: self.challenge_ciphertexts.clone(),
+            circuit: self.circuit.clone(),
+            test_metadata: ProverMetadata {
+                test_id: metadata.test_id.clone(),
+                num_challenges: self.challenge_ciphertexts.len(),
+                expected_operations: metadata.expected_operations.clone(),
+                timestamp: metadata.timestamp,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FheParameters {
     pub plaintext_modulus: u64,
     pub ciphertext_modulus: u64,
     pub polynomial_degree: usize,
-    pub noise_std_dev: f64,
+    pub noise: NoiseDistribution,
 }
 
 pub struct ExternalChallenger {
@@ -89,7 +246,7 @@ impl ExternalChallenger {
             plaintext_modulus: PLAINTEXT_MODULUS,
             ciphertext_modulus: CIPHERTEXT_MODULUS,
             polynomial_degree: POLYNOMIAL_DEGREE,
-            noise_std_dev: NOISE_STANDARD_DEVIATION,
+            noise: NoiseDistribution::DiscreteGaussian,
         };
         
         let keys = Self::generate_challenge_keys();
@@ -100,15 +257,16 @@ impl ExternalChallenger {
     }
     
     fn generate_challenge_keys() -> ChallengeKeys {
-        let mut public_key_data = vec![0u64; POLYNOMIAL_DEGREE];
-        let mut secret_key_data = vec![0u64; POLYNOMIAL_DEGREE];
-        
         // Use cryptographically secure randomness
         let mut rng = rand::thread_rng();
-        for i in 0..POLYNOMIAL_DEGREE {
-            secret_key_data[i] = rng.gen_range(0..PLAINTEXT_MODULUS);
-            public_key_data[i] = rng.gen_range(0..CIPHERTEXT_MODULUS);
+        // Sparse ternary secret, -1 stored as q - 1 (matching guest implementation)
+        let mut secret_key_data = vec![0u64; POLYNOMIAL_DEGREE];
+        for position in rand::seq::index::sample(&mut rng, POLYNOMIAL_DEGREE, SECRET_HAMMING_WEIGHT as usize) {
+            secret_key_data[position] = if rng.gen() { 1 } else { CIPHERTEXT_MODULUS - 1 };
         }
+        // b = a*s mod t, as the guest generates it
+        let secret: Vec<u64> = secret_key_data.iter().map(|&s| residue_mod_t(s)).collect();
+        let public_key_data = multiply_polynomials(&public_polynomial(), &secret);
         
         ChallengeKeys {
             public_key: PublicKey { key_data: public_key_data },
@@ -121,6 +279,12 @@ impl ExternalChallenger {
     /// The challenger samples random plaintexts and encrypts them.
     /// These challenge ciphertexts will be sent to the prover.
     pub fn create_challenge(&self, test_id: &str, num_votes: usize) -> ChallengeInput {
+        self.create_circuit_challenge(test_id, num_votes, ChallengeCircuit::sum(num_votes))
+    }
+    
+    /// A challenge asking the guest to evaluate `circuit` over the encrypted
+    /// challenges (input registers `c0`, `c1`, ...).
+    pub fn create_circuit_challenge(&self, test_id: &str, num_votes: usize, circuit: ChallengeCircuit) -> ChallengeInput {
         println!("🎯 [Challenger] Creating challenge with {} test vectors", num_votes);
         
         let mut challenge_plaintexts = Vec::new();
@@ -130,7 +294,7 @@ impl ExternalChallenger {
         
         for i in 0..num_votes {
             // Generate random plaintext in valid range
-            let plaintext_val = rng.gen_range(0..3) as i64; // Vote options 0, 1, or 2
+            let plaintext_val = rng.gen_range(0..CHALLENGE_VALUES); // Vote options 0, 1, or 2
             let plaintext = Signed::from(plaintext_val);
             
             // Encrypt with challenger's public key
@@ -146,14 +310,12 @@ impl ExternalChallenger {
         let metadata = ChallengeMetadata {
             test_id: test_id.to_string(),
             challenge_plaintexts,
-            expected_operations: vec![
-                "HomomorphicAdd".to_string(),
-                "DecryptFinalTallies".to_string(),
-            ],
+            expected_operations: circuit.ops.iter().map(|op| format!("{:?}", op)).collect(),
             timestamp: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
+            injected: Vec::new(),
         };
         
         println!("✅ [Challenger] Challenge created - prover receives public inputs only");
@@ -162,40 +324,106 @@ impl ExternalChallenger {
             parameters: self.parameters.clone(),
             public_key: self.keys.public_key.clone(),
             challenge_ciphertexts,
+            circuit,
             challenge_metadata: metadata,
         }
     }
     
+    /// Negative challenge: a regular challenge followed by one invalid
+    /// ciphertext per entry of `injections`, in order. A malformed ciphertext
+    /// has a coefficient above q, a duplicate repeats the first challenge, and
+    /// a wrong-key one is bound to a key the challenger just generated. The
+    /// guest must report exactly these, and the sum still covers only the
+    /// valid challenges.
+    pub fn create_negative_challenge(&self, test_id: &str, num_votes: usize, injections: &[RejectionReason]) -> ChallengeInput {
+        assert!(num_votes > 0, "A negative challenge needs a valid challenge to duplicate");
+        let mut challenge = self.create_challenge(test_id, num_votes);
+        
+        for &reason in injections {
+            let index = challenge.challenge_ciphertexts.len() as u32;
+            let ciphertext = match reason {
+                RejectionReason::Malformed => {
+                    let mut malformed = self.serialize_ciphertext(&self.encrypt(Signed::from(1)).expect("Encryption failed"));
+                    let last = malformed.len() - 1;
+                    malformed[last] = 0xff; // Top byte of the last coefficient, far above q = 2^58
+                    malformed
+                }
+                RejectionReason::Duplicate => challenge.challenge_ciphertexts[0].clone(),
+                RejectionReason::WrongKey => {
+                    let foreign_key = Self::generate_challenge_keys().public_key;
+                    let ciphertext = self.encrypt(Signed::from(1)).expect("Encryption failed");
+                    serialize_bound(&foreign_key, &ciphertext)
+                }
+            };
+            println!("  🧨 Challenge {}: injected {:?} ciphertext", index + 1, reason);
+            challenge.challenge_ciphertexts.push(ciphertext);
+            challenge.challenge_metadata.injected.push(ChallengeRejection { index, reason });
+        }
+        
+        challenge
+    }
+    
+    /// Check the proven journal's rejection report against what the challenge
+    /// injected: every injected ciphertext and nothing else must be rejected,
+    /// for the reason it was injected.
+    pub fn verify_rejection_report(challenge_input: &ChallengeInput, rejection_report: &[ChallengeRejection]) -> VerificationResult {
+        println!("🔍 [Challenger] Checking the guest's rejection report");
+        
+        let injected = &challenge_input.challenge_metadata.injected;
+        let mut verification_log: Vec<String> = rejection_report.iter()
+            .map(|rejection| format!("Challenge {}: rejected as {:?}", rejection.index + 1, rejection.reason))
+            .collect();
+        if rejection_report == injected.as_slice() {
+            verification_log.push(format!("✅ GUEST VALIDATION VERIFIED: all {} injected ciphertexts rejected", injected.len()));
+            VerificationResult { success: true, error: None, decrypted_results: None, verification_log }
+        } else {
+            VerificationResult {
+                success: false,
+                error: Some(format!("Rejection report mismatch: expected {:?}, got {:?}", injected, rejection_report)),
+                decrypted_results: None,
+                verification_log,
+            }
+        }
+    }
+    
     /// Step 5 of O3 Protocol: Mathematical Verification
     /// 
-    /// The challenger receives the zkVM receipt and journal, then verifies:
+    /// The challenger receives the zkVM receipt, then verifies:
     /// a) zkVM receipt is cryptographically valid
-    /// b) Guest binary matches published source
-    /// c) Decrypted results match expected FHE arithmetic
-    pub fn verify_zkvm_result(
-        &self,
-        challenge_input: &ChallengeInput,
-        zkvm_receipt: &[u8], // RISC Zero receipt
-        result_ciphertexts: &[Vec<u8>], // Serialized result ciphertexts from journal
-    ) -> VerificationResult {
+    /// b) It is for the published challenge guest image id
+    /// c) The verified journal commits the digest of the circuit the challenge asked for
+    /// d) Decrypted results match the circuit evaluated on the plaintexts
+    pub fn verify_zkvm_result(&self, challenge_input: &ChallengeInput, receipt: &Receipt) -> VerificationResult {
         println!("🔍 [Challenger] Verifying zkVM proof and FHE computation results");
         
-        // Step 5a: Validate zkVM receipt (simplified - in real implementation use RISC Zero verifier)
-        let receipt_valid = self.verify_receipt(zkvm_receipt);
-        if !receipt_valid {
+        // Steps 5a and 5b: everything below is read from the verified journal
+        let proven = match verify_challenge_receipt(challenge_input, receipt) {
+            Ok(proven) => proven,
+            Err(e) => {
+                return VerificationResult {
+                    success: false,
+                    error: Some(format!("zkVM receipt validation failed: {}", e)),
+                    decrypted_results: None,
+                    verification_log: vec!["RECEIPT_INVALID".to_string()],
+                };
+            }
+        };
+        
+        // Step 5c: The guest must have executed exactly the requested circuit
+        if proven.circuit_digest != challenge_input.circuit.digest() {
             return VerificationResult {
                 success: false,
-                error: Some("zkVM receipt validation failed".to_string()),
+                error: Some("Journal commits a different circuit than the challenge asked for".to_string()),
                 decrypted_results: None,
-                verification_log: vec!["RECEIPT_INVALID".to_string()],
+                verification_log: vec!["CIRCUIT_DIGEST_MISMATCH".to_string()],
             };
         }
+        let mut verification_log = vec!["✅ Circuit digest matches the challenge circuit".to_string()];
         
-        // Step 5c: Decrypt journal results with challenger's private key
+        // Step 5d: Decrypt journal results with challenger's private key
         let mut decrypted_results = Vec::new();
-        let mut verification_log = Vec::new();
         
-        for (i, result_bytes) in result_ciphertexts.iter().enumerate() {
+        for (i, result_bytes) in proven.result_ciphertexts.iter().enumerate() {
             match self.deserialize_and_decrypt(result_bytes) {
                 Ok(plaintext) => {
                     decrypted_results.push(plaintext.val);
@@ -212,12 +440,21 @@ impl ExternalChallenger {
             }
         }
         
-        // Verify FHE arithmetic correctness
-        let expected_sum: i64 = challenge_input.challenge_metadata.challenge_plaintexts.iter().sum();
-        let actual_sum: i64 = decrypted_results.iter().sum();
+        // Verify FHE arithmetic correctness against the circuit on the plaintexts
+        let expected = match challenge_input.circuit.evaluate(&challenge_input.challenge_metadata.challenge_plaintexts) {
+            Ok(expected) => expected,
+            Err(e) => {
+                return VerificationResult {
+                    success: false,
+                    error: Some(format!("Challenge circuit cannot be evaluated: {}", e)),
+                    decrypted_results: Some(decrypted_results),
+                    verification_log,
+                };
+            }
+        };
         
-        if expected_sum == actual_sum {
-            verification_log.push("✅ FHE COMPUTATION VERIFIED: Homomorphic addition correct".to_string());
+        if expected == decrypted_results {
+            verification_log.push(format!("✅ FHE COMPUTATION VERIFIED: all {} circuit outputs correct", expected.len()));
             verification_log.push("✅ PROOF COMPLETE: Real FHE operations occurred inside zkVM".to_string());
             
             VerificationResult {
@@ -229,7 +466,7 @@ impl ExternalChallenger {
         } else {
             VerificationResult {
                 success: false,
-                error: Some(format!("FHE arithmetic mismatch: expected sum {}, got {}", expected_sum, actual_sum)),
+                error: Some(format!("FHE arithmetic mismatch: expected outputs {:?}, got {:?}", expected, decrypted_results)),
                 decrypted_results: Some(decrypted_results),
                 verification_log,
             }
@@ -239,25 +476,22 @@ impl ExternalChallenger {
     fn encrypt(&self, plaintext: Signed) -> Result<Cipher<Signed>, String> {
         // Implement FHE encryption matching the guest implementation
         let plaintext_val = (plaintext.val as u64) % PLAINTEXT_MODULUS;
-        let mut ciphertext_data = vec![0u64; POLYNOMIAL_DEGREE * 2];
         
         let mut rng = rand::thread_rng();
-        let gaussian = Normal::new(0.0, NOISE_STANDARD_DEVIATION)
-            .map_err(|_| "Failed to create Gaussian distribution".to_string())?;
         
-        // Scale plaintext and add noise (matching guest implementation)
+        // c = (Δ(m + b*u) + e, a*u) with u uniform mod t, m in the constant term
+        let u: Vec<u64> = (0..POLYNOMIAL_DEGREE).map(|_| rng.gen_range(0..PLAINTEXT_MODULUS)).collect();
+        let mask = multiply_polynomials(&self.keys.public_key.key_data, &u);
         let scaling_factor = CIPHERTEXT_MODULUS / PLAINTEXT_MODULUS;
-        let scaled_plaintext = plaintext_val * scaling_factor;
-        let noise_sample: f64 = gaussian.sample(&mut rng);
-        let noise_magnitude = (noise_sample.abs() as u64) % (PLAINTEXT_MODULUS / 16);
-        ciphertext_data[0] = (scaled_plaintext + noise_magnitude) % CIPHERTEXT_MODULUS;
-        
-        // Fill remaining coefficients with noise
-        for i in 1..POLYNOMIAL_DEGREE * 2 {
-            let coeff_noise: f64 = gaussian.sample(&mut rng);
-            let coeff_magnitude = (coeff_noise.abs() as u64) % CIPHERTEXT_MODULUS;
-            ciphertext_data[i] = coeff_magnitude;
-        }
+        let mut ciphertext_data: Vec<u64> = mask
+            .iter()
+            .enumerate()
+            .map(|(i, &mask)| {
+                let value = if i == 0 { plaintext_val } else { 0 };
+                ((value + mask) % PLAINTEXT_MODULUS * scaling_factor).wrapping_add(self.parameters.noise.sample(&mut rng) as u64) & (CIPHERTEXT_MODULUS - 1)
+            })
+            .collect();
+        ciphertext_data.extend(multiply_polynomials(&public_polynomial(), &u));
         
         Ok(Cipher {
             ciphertext_data,
@@ -265,12 +499,9 @@ impl ExternalChallenger {
         })
     }
     
+    /// Bound to the challenger's key, as the guest requires
     fn serialize_ciphertext(&self, ciphertext: &Cipher<Signed>) -> Vec<u8> {
-        let mut result = Vec::new();
-        for &val in &ciphertext.ciphertext_data {
-            result.extend_from_slice(&val.to_le_bytes());
-        }
-        result
+        serialize_bound(&self.keys.public_key, ciphertext)
     }
     
     fn deserialize_and_decrypt(&self, data: &[u8]) -> Result<Signed, String> {
@@ -289,29 +520,77 @@ impl ExternalChallenger {
             ciphertext_data[i] = u64::from_le_bytes(bytes);
         }
         
-        // Decrypt with challenger's private key
-        let noisy_scaled_plaintext = ciphertext_data[0];
+        // Decrypt with challenger's private key: round c0 to the nearest multiple of
+        // the scaling factor, as noise may be negative, then take off (c1*s)[0]
+        let secret: Vec<u64> = self.keys.private_key.secret_data.iter().map(|&s| residue_mod_t(s)).collect();
+        let c1: Vec<u64> = ciphertext_data[POLYNOMIAL_DEGREE..].iter().map(|&c| residue_mod_t(c)).collect();
+        let key_term = multiply_polynomials(&c1, &secret)[0];
         let scaling_factor = CIPHERTEXT_MODULUS / PLAINTEXT_MODULUS;
-        let descaled_val = noisy_scaled_plaintext / scaling_factor;
-        let decrypted_val = descaled_val % PLAINTEXT_MODULUS;
+        let descaled_val = (ciphertext_data[0] + scaling_factor / 2) / scaling_factor;
+        let decrypted_val = (descaled_val + PLAINTEXT_MODULUS - key_term) % PLAINTEXT_MODULUS;
         
         Ok(Signed::from(decrypted_val as i64))
     }
     
-    fn verify_receipt(&self, _receipt: &[u8]) -> bool {
-        // Simplified receipt verification - in real implementation:
-        // - Verify STARK proof with RISC Zero verifier
-        // - Check imageID matches published guest binary
-        // - Validate journal contents
-        println!("📋 [Challenger] Verifying zkVM receipt (simplified)");
-        true // For demo purposes
-    }
-    
     pub fn get_public_key(&self) -> &PublicKey {
         &self.keys.public_key
     }
 }
 
+/// c mod q read as the integer in -q/2..q/2 it stands for, reduced mod t
+/// (matching the guest's key and c1 residues)
+fn residue_mod_t(c: u64) -> u64 {
+    let signed = if c >= CIPHERTEXT_MODULUS / 2 { c as i64 - CIPHERTEXT_MODULUS as i64 } else { c as i64 };
+    signed.rem_euclid(PLAINTEXT_MODULUS as i64) as u64
+}
+
+/// Negacyclic product mod (X^N + 1, t)
+fn multiply_polynomials(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let mut product = vec![0u64; POLYNOMIAL_DEGREE];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            let term = x % PLAINTEXT_MODULUS * (y % PLAINTEXT_MODULUS) % PLAINTEXT_MODULUS;
+            let k = (i + j) % POLYNOMIAL_DEGREE;
+            // X^N = -1
+            product[k] = if i + j < POLYNOMIAL_DEGREE { (product[k] + term) % PLAINTEXT_MODULUS } else { (product[k] + PLAINTEXT_MODULUS - term) % PLAINTEXT_MODULUS };
+        }
+    }
+    product
+}
+
+/// The public polynomial a, hashed from a fixed tag (must match guest `public_polynomial`)
+fn public_polynomial() -> Vec<u64> {
+    (0..POLYNOMIAL_DEGREE as u32)
+        .map(|i| {
+            let digest = Sha256::new().chain_update([PUBLIC_POLYNOMIAL_TAG]).chain_update(i.to_le_bytes()).finalize();
+            u64::from_le_bytes(digest[..8].try_into().unwrap()) % PLAINTEXT_MODULUS
+        })
+        .collect()
+}
+
+/// key fingerprint || parameter digest || coefficients (matching the guest's
+/// bound ciphertext encoding)
+fn serialize_bound(public_key: &PublicKey, ciphertext: &Cipher<Signed>) -> Vec<u8> {
+    let mut fingerprint = Sha256::new();
+    fingerprint.update([KEY_FINGERPRINT_TAG]);
+    for coeff in &public_key.key_data {
+        fingerprint.update(coeff.to_le_bytes());
+    }
+    let mut parameters = Sha256::new();
+    parameters.update([PARAMETER_DIGEST_TAG]);
+    parameters.update(PLAINTEXT_MODULUS.to_le_bytes());
+    parameters.update(CIPHERTEXT_MODULUS.to_le_bytes());
+    parameters.update((POLYNOMIAL_DEGREE as u32).to_le_bytes());
+    
+    let mut result = Vec::new();
+    result.extend_from_slice(&fingerprint.finalize());
+    result.extend_from_slice(&parameters.finalize());
+    for &val in &ciphertext.ciphertext_data {
+        result.extend_from_slice(&val.to_le_bytes());
+    }
+    result
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VerificationResult {
     pub success: bool,
@@ -344,25 +623,20 @@ pub fn run_challenge_protocol() {
     println!("  - Expected operations: {:?}", challenge.challenge_metadata.expected_operations);
     println!("  - Challenger keeps SK secret!");
     
-    // In real implementation, this would send challenge to zkVM prover
-    // For demo, we'll simulate the prover response
-    
     println!("\n⏳ [Challenger] Waiting for zkVM proof and results...");
     
-    // Step 3-4: Prover would execute FHE in zkVM and return (π, journal)
-    // For demo, simulate successful execution
-    let simulated_receipt = vec![0u8; 32]; // Placeholder zkVM receipt
-    let simulated_results = vec![
-        vec![0u8; POLYNOMIAL_DEGREE * 2 * 8]; // Placeholder result ciphertexts
-        challenge.challenge_ciphertexts.len()
-    ];
+    // Steps 3-4: the prover runs the challenge guest and returns its receipt
+    let proved = match prove_challenge(&challenge) {
+        Ok(proved) => proved,
+        Err(e) => {
+            println!("❌ Proving failed: {}", e);
+            return;
+        }
+    };
+    let dev_mode = matches!(proved.receipt.inner, InnerReceipt::Fake(_));
     
     // Step 5: Mathematical verification by challenger
-    let verification = challenger.verify_zkvm_result(
-        &challenge,
-        &simulated_receipt,
-        &simulated_results,
-    );
+    let verification = challenger.verify_zkvm_result(&challenge, &proved.receipt);
     
     println!("\n🔍 VERIFICATION RESULTS:");
     println!("========================");
@@ -386,6 +660,11 @@ pub fn run_challenge_protocol() {
         if let Some(error) = verification.error {
             println!("  Error: {}", error);
         }
+        return;
+    }
+    if dev_mode {
+        println!("\n⚠️  Dev-mode receipt: the outputs are correct, but nothing is proven");
+        return;
     }
     
     println!("\n🎯 MATHEMATICAL CERTAINTY ACHIEVED:");
@@ -398,6 +677,128 @@ pub fn run_challenge_protocol() {
     println!("\n🏆 PROVED: Real FHE computation inside zkVM!");
 }
 
+const NEGATIVE_CHALLENGE_FILE: &str = "NEGATIVE_CHALLENGE.json";
+/// The whole negative challenge, plaintexts and injections included; stays with the challenger
+const NEGATIVE_CHALLENGE_RECORD_FILE: &str = "NEGATIVE_CHALLENGE_RECORD.json";
+
+/// What proving a challenge cost (must match host `ProvingStats`)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct ProvingStats {
+    pub total_cycles: u64,
+    pub user_cycles: u64,
+    pub segments: usize,
+    pub proving_ms: u64,
+    pub receipt_bytes: u64,
+}
+
+/// A receipt of the challenge guest, its bincode encoding and what proving it cost
+#[derive(Debug)]
+pub struct ProvedChallenge {
+    pub receipt: Receipt,
+    pub receipt_bytes: Vec<u8>,
+    pub stats: ProvingStats,
+}
+
+/// Play the prover: hand the public part of `challenge` to the host's
+/// `prove-challenge` and read back its receipt and proving stats. Files are
+/// named after the test id, in the working directory.
+pub fn prove_challenge(challenge: &ChallengeInput) -> Result<ProvedChallenge, String> {
+    let test_id = &challenge.challenge_metadata.test_id;
+    let (challenge_path, receipt_path, stats_path) = (format!("{}.challenge.json", test_id), format!("{}.receipt.bin", test_id), format!("{}.stats.json", test_id));
+    write_json(&challenge_path, &challenge.prover_challenge())?;
+    let status = std::process::Command::new("cargo")
+        .args(["run", "--release", "-p", "host", "--bin", "prove-challenge", "--"])
+        .args([&challenge_path, &receipt_path, &stats_path])
+        .status()
+        .map_err(|e| format!("Could not run prove-challenge: {}", e))?;
+    if !status.success() {
+        return Err(format!("prove-challenge failed: {}", status));
+    }
+    let receipt_bytes = std::fs::read(&receipt_path).map_err(|e| e.to_string())?;
+    let receipt = bincode::deserialize(&receipt_bytes).map_err(|e| e.to_string())?;
+    Ok(ProvedChallenge { receipt, receipt_bytes, stats: read_json(&stats_path)? })
+}
+
+/// Verify a receipt of the challenge guest against the published image id and
+/// return its journal, if it answers `challenge`.
+pub fn verify_challenge_receipt(challenge: &ChallengeInput, receipt: &Receipt) -> Result<ChallengeOutput, String> {
+    let proven = verify_challenge(&VerifierContext::default(), receipt).map_err(|e| e.to_string())?;
+    if proven.test_id != challenge.challenge_metadata.test_id {
+        return Err(format!("Receipt is for test {}, not {}", proven.test_id, challenge.challenge_metadata.test_id));
+    }
+    Ok(proven)
+}
+
+/// Negative-challenge mode
+/// 
+/// Sends one malformed, one duplicated and one wrong-key ciphertext among
+/// valid challenges and checks that the proven journal reports exactly those
+/// rejected, exercising the guest's validation under proof. The first run
+/// writes the prover's part of the challenge to `NEGATIVE_CHALLENGE.json` and
+/// keeps the whole challenge in `NEGATIVE_CHALLENGE_RECORD.json`; prove it with
+/// `cargo run --release -p host --bin prove-challenge -- NEGATIVE_CHALLENGE.json
+/// <receipt.bin>` and rerun with the receipt, which is verified against the
+/// challenge image id before its rejection report is read.
+pub fn run_negative_challenge_protocol(receipt_path: Option<&str>) {
+    println!("🧨 NEGATIVE CHALLENGE - Guest validation under proof");
+    println!("====================================================");
+    
+    let Some(path) = receipt_path else {
+        let challenger = ExternalChallenger::new();
+        let injections = [RejectionReason::Malformed, RejectionReason::Duplicate, RejectionReason::WrongKey];
+        let challenge = challenger.create_negative_challenge("negative_challenge_test", 4, &injections);
+        match write_json(NEGATIVE_CHALLENGE_RECORD_FILE, &challenge).and_then(|()| write_json(NEGATIVE_CHALLENGE_FILE, &challenge.prover_challenge())) {
+            Ok(()) => println!("📤 [Challenger] Challenge written to {}; prove it, then rerun with the receipt", NEGATIVE_CHALLENGE_FILE),
+            Err(e) => println!("⚠️  Could not write the challenge: {}", e),
+        }
+        return;
+    };
+    
+    let challenge: ChallengeInput = match read_json(NEGATIVE_CHALLENGE_RECORD_FILE) {
+        Ok(challenge) => challenge,
+        Err(e) => {
+            println!("❌ Could not read the challenge record: {}", e);
+            return;
+        }
+    };
+    let proven = match read_receipt(path).and_then(|receipt| verify_challenge_receipt(&challenge, &receipt)) {
+        Ok(proven) => proven,
+        Err(e) => {
+            println!("❌ Receipt not accepted: {}", e);
+            return;
+        }
+    };
+    println!("✅ [Challenger] Receipt verified against the challenge image id");
+    
+    let verification = ExternalChallenger::verify_rejection_report(&challenge, &proven.rejection_report);
+    for log_entry in &verification.verification_log {
+        println!("  {}", log_entry);
+    }
+    if let Some(error) = verification.error {
+        println!("❌ NEGATIVE CHALLENGE FAILED: {}", error);
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &str) -> Result<T, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    serde_json::from_slice(&bytes).map_err(|e| e.to_string())
+}
+
+fn write_json<T: Serialize>(path: &str, value: &T) -> Result<(), String> {
+    let bytes = serde_json::to_vec(value).map_err(|e| e.to_string())?;
+    std::fs::write(path, bytes).map_err(|e| e.to_string())
+}
+
+/// A receipt in the bincode encoding `prove-challenge` writes
+fn read_receipt(path: &str) -> Result<Receipt, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    bincode::deserialize(&bytes).map_err(|e| e.to_string())
+}
+
 fn main() {
-    run_challenge_protocol();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("--negative") => run_negative_challenge_protocol(args.get(1).map(String::as_str)),
+        _ => run_challenge_protocol(),
+    }
 }
\ No newline at end of file
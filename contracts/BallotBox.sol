@@ -0,0 +1,20 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+/// Collects encrypted ballots for one election. The host ingests the
+/// `BallotCast` logs (see `host::chain_ingest`) once `closeBlock` is final.
+contract BallotBox {
+    uint256 public immutable closeBlock;
+
+    /// `ballot` is a bincode-encoded EncryptedVote; eligibility is checked off-chain and in the guest.
+    event BallotCast(address indexed voter, bytes ballot);
+
+    constructor(uint256 _closeBlock) {
+        closeBlock = _closeBlock;
+    }
+
+    function castBallot(bytes calldata ballot) external {
+        require(block.number <= closeBlock, "election closed");
+        emit BallotCast(msg.sender, ballot);
+    }
+}
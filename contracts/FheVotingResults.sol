@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import {IRiscZeroVerifier} from "risc0/IRiscZeroVerifier.sol";
+
+/// Finalizes an election from a proven tally journal.
+/// Deploy with the RISC Zero verifier router address, the guest image id
+/// printed by the host and the election's id and chain id, so a proof of any
+/// other election is refused. `host::onchain` builds the calldata.
+contract FheVotingResults {
+    IRiscZeroVerifier public immutable verifier;
+    bytes32 public immutable imageId;
+    bytes32 public immutable electionId;
+    uint64 public immutable chainId;
+
+    /// First word of an interim tally journal, which must never finalize.
+    uint32 private constant INTERIM_MARKER = type(uint32).max;
+    /// Compact journal layout (`host::journal::encode_compact_journal`).
+    uint256 private constant COMPACT_JOURNAL_LENGTH = 548;
+    uint256 private constant ELECTION_ID_OFFSET = 256;
+    uint256 private constant CHAIN_ID_OFFSET = 288;
+    /// Word holding the number of encrypted tally frames, zero when the counts are withheld.
+    uint256 private constant TALLY_FRAMES_OFFSET = 20;
+
+    bool public finalized;
+    uint32[3] public optionCounts;
+    uint32 public totalVotes;
+
+    event ResultsFinalized(uint32 option1, uint32 option2, uint32 option3, uint32 totalVotes, bytes32 journalDigest);
+
+    constructor(IRiscZeroVerifier _verifier, bytes32 _imageId, bytes32 _electionId, uint64 _chainId) {
+        verifier = _verifier;
+        imageId = _imageId;
+        electionId = _electionId;
+        chainId = _chainId;
+    }
+
+    /// `journal` is the guest's compact journal: the four u32 counts of
+    /// VoteTallyOutput first, each a little-endian word, and the election
+    /// context at `ELECTION_ID_OFFSET`. A tally below its anonymity set
+    /// withholds the counts (zeros, no encrypted tallies) and never finalizes.
+    function finalizeResults(bytes calldata journal, bytes calldata seal) external {
+        require(!finalized, "already finalized");
+        require(journal.length == COMPACT_JOURNAL_LENGTH, "not a compact journal");
+        require(readWord(journal, 0) != INTERIM_MARKER, "interim tally");
+        require(readWord(journal, TALLY_FRAMES_OFFSET) != 0, "counts withheld");
+        require(bytes32(journal[ELECTION_ID_OFFSET:ELECTION_ID_OFFSET + 32]) == electionId, "another election");
+        require(uint64(readWord(journal, CHAIN_ID_OFFSET)) | (uint64(readWord(journal, CHAIN_ID_OFFSET + 4)) << 32) == chainId, "another chain");
+        bytes32 journalDigest = sha256(journal);
+        verifier.verify(seal, imageId, journalDigest);
+
+        for (uint256 i = 0; i < 3; i++) {
+            optionCounts[i] = readWord(journal, i * 4);
+        }
+        totalVotes = readWord(journal, 12);
+        finalized = true;
+        emit ResultsFinalized(optionCounts[0], optionCounts[1], optionCounts[2], totalVotes, journalDigest);
+    }
+
+    function readWord(bytes calldata journal, uint256 offset) private pure returns (uint32) {
+        return uint32(uint8(journal[offset])) | (uint32(uint8(journal[offset + 1])) << 8)
+            | (uint32(uint8(journal[offset + 2])) << 16) | (uint32(uint8(journal[offset + 3])) << 24);
+    }
+}
@@ -4,9 +4,12 @@
 //! This demonstrates the full mathematical proof that FHE computation
 //! occurs inside zkVM with cryptographic certainty.
 
-use std::process::Command;
 use std::fs;
-use serde::{Serialize, Deserialize};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use fhe_voting_verifier::image_ids::CHALLENGE_ID;
+use fhe_voting_verifier::types::ChallengeOutput;
+use risc0_zkvm::InnerReceipt;
 
 // Import the challenger from our external program
 mod challenger_inline {
@@ -14,14 +17,14 @@ mod challenger_inline {
     include!("challenger.rs");
 }
 
-use challenger_inline::{ExternalChallenger, ChallengeInput, VerificationResult};
+use challenger_inline::{prove_challenge, verify_challenge_receipt, ExternalChallenger, ChallengeInput, ProvedChallenge, VerificationResult, CHALLENGE_VALUES};
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A challenge receipt from the host's `prove-challenge`, with its verified journal
+#[derive(Debug)]
 pub struct ZkVmProofResult {
-    pub receipt: Vec<u8>,
-    pub journal_data: Vec<u8>,
-    pub execution_log: Vec<String>,
-    pub image_id: String,
+    pub proved: ProvedChallenge,
+    pub output: ChallengeOutput, // Journal of the receipt, verified against the challenge image id
+    pub dev_mode: bool, // A fake receipt: the guest was executed, not proven
 }
 
 /// Complete O3 Mathematical Proof Protocol
@@ -37,6 +40,36 @@ pub struct FheProofProtocol {
     test_results: Vec<ProtocolTestResult>,
 }
 
+/// Quantitative results of every proof run, for `FHE_MATHEMATICAL_PROOF_REPORT.json`.
+#[derive(Debug, Serialize)]
+pub struct ProofReport {
+    pub total_tests: usize,
+    pub successful_tests: usize,
+    pub proven_tests: usize, // Successful with a real proof rather than a dev-mode receipt
+    pub forgery_probability: f64, // Chance of passing every test by guessing the sums
+    pub security_bits: f64,
+    pub tests: Vec<TestReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TestReport {
+    pub test_id: String,
+    pub challenges: usize,
+    pub proof_valid: bool,
+    pub challenge_plaintexts: Vec<i64>,
+    pub expected_sum: i64,
+    pub decrypted_results: Option<Vec<i64>>, // One per result ciphertext, in journal order
+    pub forgery_probability: f64, // Chance of passing this test by guessing the sum
+    pub image_id: Option<String>, // The challenge image id the receipt verified against
+    pub receipt_digest: Option<String>, // SHA-256 of the receipt bytes
+    pub journal_digest: Option<String>, // SHA-256 of the journal bytes
+    pub dev_mode: Option<bool>,
+    pub total_cycles: Option<u64>,
+    pub segments: Option<usize>,
+    pub proving_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
 #[derive(Debug)]
 pub struct ProtocolTestResult {
     pub test_id: String,
@@ -63,9 +96,9 @@ impl FheProofProtocol {
     
     /// Execute a complete proof run with specified parameters
     pub fn run_proof_test(&mut self, test_id: &str, num_challenges: usize) -> bool {
-        println!("\n" + "=".repeat(80));
+        println!("\n{}", "=".repeat(80));
         println!("🧪 RUNNING PROOF TEST: {}", test_id);
-        println!("=".repeat(80));
+        println!("{}", "=".repeat(80));
         
         // Step 1 & 2: Create challenge with external keys
         let challenge_input = self.challenger.create_challenge(test_id, num_challenges);
@@ -102,7 +135,10 @@ impl FheProofProtocol {
         
         self.test_results.push(test_result);
         
-        if proof_valid {
+        let dev_mode = self.test_results.last().and_then(|test| test.zkvm_result.as_ref()).is_some_and(|result| result.dev_mode);
+        if proof_valid && dev_mode {
+            println!("⚠️  TEST PASSED IN DEV MODE: outputs correct, but the receipt is fake and proves nothing");
+        } else if proof_valid {
             println!("✅ PROOF TEST PASSED: Mathematical certainty achieved!");
         } else {
             println!("❌ PROOF TEST FAILED: Cannot establish mathematical certainty");
@@ -111,149 +147,156 @@ impl FheProofProtocol {
         proof_valid
     }
     
-    /// Step 3: Execute zkVM with challenge input
+    /// Step 3: Prove the challenge in the zkVM
     /// 
-    /// This step is critical - it must execute the actual FHE computation
-    /// inside the zkVM and generate a cryptographic proof of execution.
+    /// The host's `prove-challenge` runs the challenge guest on the public part
+    /// of the challenge; its receipt is verified against the challenge image id
+    /// and the journal read from it.
     fn execute_zkvm_with_challenge(&self, challenge: &ChallengeInput) -> Option<ZkVmProofResult> {
-        println!("\n🔮 STEP 3: Executing FHE computation inside zkVM...");
-        
-        // In a real implementation, this would:
-        // 1. Serialize challenge input for zkVM
-        // 2. Execute RISC Zero guest program with challenge
-        // 3. Generate STARK proof of execution
-        // 4. Extract journal with FHE results
-        
-        // For demonstration, we'll simulate the zkVM execution
-        println!("⚙️  [Simulation] Starting RISC Zero zkVM execution...");
-        println!("📥 [Simulation] Loading challenge input into guest program...");
-        println!("🔐 [Simulation] Guest performing FHE operations...");
-        println!("🧮 [Simulation] Homomorphic addition of {} challenge ciphertexts...", 
-                challenge.challenge_ciphertexts.len());
-        println!("📊 [Simulation] Generating cryptographic proof...");
-        
-        // Simulate successful execution
-        let simulated_result = ZkVmProofResult {
-            receipt: vec![0u8; 32], // Placeholder STARK proof
-            journal_data: self.simulate_fhe_results(challenge),
-            execution_log: vec![
-                "FHE runtime initialized".to_string(),
-                "External public key loaded".to_string(),
-                "Challenge ciphertexts deserialized".to_string(),
-                "Homomorphic addition performed".to_string(),
-                "Results serialized to journal".to_string(),
-                "Proof generation completed".to_string(),
-            ],
-            image_id: "sha256:abcd1234...".to_string(), // Placeholder image ID
-        };
+        println!("\n🔮 STEP 3: Proving the challenge in the zkVM...");
         
-        println!("✅ [Simulation] zkVM execution completed successfully");
-        println!("📋 [Simulation] STARK proof generated: {} bytes", simulated_result.receipt.len());
-        println!("📄 [Simulation] Journal data: {} bytes", simulated_result.journal_data.len());
-        
-        Some(simulated_result)
-    }
-    
-    /// Simulate FHE computation results for demonstration
-    /// 
-    /// In real implementation, this would be the actual FHE computation
-    /// performed inside the zkVM guest program.
-    fn simulate_fhe_results(&self, challenge: &ChallengeInput) -> Vec<u8> {
-        // This simulates the serialized FHE results that would come from
-        // the zkVM journal after real FHE computation
-        
-        let num_results = challenge.challenge_ciphertexts.len() + 1; // +1 for final sum
-        let result_size = 32 * 2 * 8; // POLYNOMIAL_DEGREE * 2 * 8 bytes per u64
-        
-        vec![0u8; num_results * result_size]
+        let result = prove_challenge(challenge).and_then(|proved| {
+            let output = verify_challenge_receipt(challenge, &proved.receipt)?;
+            let dev_mode = matches!(proved.receipt.inner, InnerReceipt::Fake(_));
+            Ok(ZkVmProofResult { proved, output, dev_mode })
+        });
+        match result {
+            Ok(result) => {
+                let stats = &result.proved.stats;
+                println!("✅ Receipt verified: {} cycles in {} segments, {:.1}s", stats.total_cycles, stats.segments, stats.proving_ms as f64 / 1000.0);
+                println!("📄 Journal: {} bytes, {} result ciphertexts", result.proved.receipt.journal.bytes.len(), result.output.result_ciphertexts.len());
+                if result.dev_mode {
+                    println!("⚠️  Dev-mode receipt: the guest was executed, not proven");
+                }
+                Some(result)
+            }
+            Err(e) => {
+                println!("❌ Proving failed: {}", e);
+                None
+            }
+        }
     }
     
     /// Step 5: Mathematical verification by challenger
     /// 
     /// This is where the mathematical proof is validated:
-    /// - Verify zkVM receipt cryptographically
     /// - Decrypt all results with challenger's secret key
     /// - Validate FHE arithmetic correctness
     fn verify_zkvm_proof(&self, challenge: &ChallengeInput, zkvm_result: &ZkVmProofResult) -> VerificationResult {
         println!("\n🔍 STEP 5: Mathematical verification by challenger...");
         
-        // Extract result ciphertexts from journal (simulation)
-        let result_ciphertexts = self.extract_result_ciphertexts(&zkvm_result.journal_data);
-        
-        // Use challenger's verification method
-        self.challenger.verify_zkvm_result(
-            challenge,
-            &zkvm_result.receipt,
-            &result_ciphertexts,
-        )
+        self.challenger.verify_zkvm_result(challenge, &zkvm_result.proved.receipt)
     }
     
-    /// Extract result ciphertexts from zkVM journal
-    fn extract_result_ciphertexts(&self, journal_data: &[u8]) -> Vec<Vec<u8>> {
-        // In real implementation, this would deserialize the actual journal
-        // For simulation, create placeholder result ciphertexts
-        
-        let result_size = 32 * 2 * 8; // Match expected ciphertext size
-        let num_results = journal_data.len() / result_size;
-        
-        (0..num_results)
-            .map(|i| {
-                let start = i * result_size;
-                let end = start + result_size;
-                journal_data.get(start..end).unwrap_or(&vec![0u8; result_size]).to_vec()
-            })
-            .collect()
+    /// Quantitative data from the runs so far: per-test decrypted values and
+    /// receipt digests, and the forgery probability their challenge counts give.
+    pub fn proof_report(&self) -> ProofReport {
+        let tests: Vec<TestReport> = self.test_results.iter().map(|test| {
+            let plaintexts = &test.challenge_input.challenge_metadata.challenge_plaintexts;
+            let verification = test.verification.as_ref();
+            TestReport {
+                test_id: test.test_id.clone(),
+                challenges: test.challenge_input.challenge_ciphertexts.len(),
+                proof_valid: test.proof_valid,
+                challenge_plaintexts: plaintexts.clone(),
+                expected_sum: plaintexts.iter().sum(),
+                decrypted_results: verification.and_then(|v| v.decrypted_results.clone()),
+                forgery_probability: guessing_probability(plaintexts.len()),
+                image_id: test.zkvm_result.as_ref().map(|_| image_id_hex()),
+                receipt_digest: test.zkvm_result.as_ref().map(|result| sha256_hex(&result.proved.receipt_bytes)),
+                journal_digest: test.zkvm_result.as_ref().map(|result| sha256_hex(&result.proved.receipt.journal.bytes)),
+                dev_mode: test.zkvm_result.as_ref().map(|result| result.dev_mode),
+                total_cycles: test.zkvm_result.as_ref().map(|result| result.proved.stats.total_cycles),
+                segments: test.zkvm_result.as_ref().map(|result| result.proved.stats.segments),
+                proving_ms: test.zkvm_result.as_ref().map(|result| result.proved.stats.proving_ms),
+                error: verification.and_then(|v| v.error.clone()),
+            }
+        }).collect();
+        // Challenges are drawn independently, so a forger must guess every test
+        let forgery_probability: f64 = tests.iter().map(|test| test.forgery_probability).product();
+        ProofReport {
+            total_tests: tests.len(),
+            successful_tests: tests.iter().filter(|test| test.proof_valid).count(),
+            proven_tests: tests.iter().filter(|test| test.proof_valid && test.dev_mode == Some(false)).count(),
+            forgery_probability,
+            security_bits: -forgery_probability.log2(),
+            tests,
+        }
+    }
+    
+    /// The proof report as JSON, alongside the markdown of `generate_proof_report`
+    pub fn generate_proof_report_json(&self) -> String {
+        serde_json::to_string_pretty(&self.proof_report()).expect("Proof report serializes")
     }
     
     /// Generate comprehensive proof report
     pub fn generate_proof_report(&self) -> String {
+        let data = self.proof_report();
         let mut report = String::new();
         
         report.push_str("🏆 FHE-ZKVM MATHEMATICAL PROOF REPORT\n");
         report.push_str("===================================\n\n");
         
         report.push_str("## Proof Protocol Summary\n");
-        report.push_str(&format!("- Total tests executed: {}\n", self.test_results.len()));
+        report.push_str(&format!("- Total tests executed: {}\n", data.total_tests));
         
-        let successful_tests = self.test_results.iter().filter(|t| t.proof_valid).count();
+        let successful_tests = data.successful_tests;
         report.push_str(&format!("- Successful proofs: {}\n", successful_tests));
-        report.push_str(&format!("- Failed proofs: {}\n", self.test_results.len() - successful_tests));
+        report.push_str(&format!("- Failed proofs: {}\n", data.total_tests - successful_tests));
+        report.push_str(&format!("- Passed with a real proof: {} (the rest of the passes used dev-mode receipts)\n", data.proven_tests));
+        report.push_str(&format!("- Forgery probability (guessing every sum): {:.3e} ({:.1} bits)\n",
+            data.forgery_probability, data.security_bits));
         
         report.push_str("\n## Mathematical Certainty Achieved\n");
-        if successful_tests > 0 {
+        if data.proven_tests > 0 {
             report.push_str("✅ **PROVED**: Real FHE computation occurs inside zkVM\n");
             report.push_str("✅ **PROVED**: External challenger controls secret key exclusively\n");
-            report.push_str("✅ **PROVED**: Prover cannot forge results (2^-λ probability)\n");
+            report.push_str(&format!("✅ **PROVED**: Prover cannot forge results by guessing (probability {:.3e})\n", data.forgery_probability));
             report.push_str("✅ **PROVED**: zkVM proof guarantees exact program execution\n");
             report.push_str("✅ **PROVED**: Decryption validates correct FHE arithmetic\n");
+        } else if successful_tests > 0 {
+            report.push_str("⚠️ **NOT PROVED**: outputs decrypted correctly, but every receipt was a dev-mode receipt\n");
         } else {
             report.push_str("❌ **FAILED**: Mathematical proof could not be established\n");
         }
         
         report.push_str("\n## Test Results Detail\n");
-        for (i, test) in self.test_results.iter().enumerate() {
+        for (i, test) in data.tests.iter().enumerate() {
             report.push_str(&format!("\n### Test {}: {}\n", i + 1, test.test_id));
-            report.push_str(&format!("- Challenges: {}\n", test.challenge_input.challenge_ciphertexts.len()));
-            report.push_str(&format!("- zkVM execution: {}\n", 
-                if test.zkvm_result.is_some() { "SUCCESS" } else { "FAILED" }));
+            report.push_str(&format!("- Challenges: {}\n", test.challenges));
+            report.push_str(&format!("- zkVM proof: {}\n", match test.dev_mode {
+                Some(false) => "VERIFIED",
+                Some(true) => "DEV MODE (fake receipt, nothing proven)",
+                None => "FAILED",
+            }));
             report.push_str(&format!("- Mathematical verification: {}\n",
                 if test.proof_valid { "PASSED" } else { "FAILED" }));
-                
-            if let Some(verification) = &test.verification {
-                if let Some(error) = &verification.error {
-                    report.push_str(&format!("- Error: {}\n", error));
-                }
+            report.push_str(&format!("- Challenge plaintexts: {:?} (sum {})\n", test.challenge_plaintexts, test.expected_sum));
+            if let Some(decrypted) = &test.decrypted_results {
+                report.push_str(&format!("- Decrypted results: {:?} (sum {})\n", decrypted, decrypted.iter().sum::<i64>()));
+            }
+            report.push_str(&format!("- Forgery probability: {:.3e}\n", test.forgery_probability));
+            if let (Some(image_id), Some(receipt), Some(journal)) = (&test.image_id, &test.receipt_digest, &test.journal_digest) {
+                report.push_str(&format!("- Image id: {}\n", image_id));
+                report.push_str(&format!("- Receipt SHA-256: {}\n", receipt));
+                report.push_str(&format!("- Journal SHA-256: {}\n", journal));
+            }
+            if let (Some(cycles), Some(segments), Some(ms)) = (test.total_cycles, test.segments, test.proving_ms) {
+                report.push_str(&format!("- Proving: {} cycles in {} segments, {:.1}s\n", cycles, segments, ms as f64 / 1000.0));
+            }
+            if let Some(error) = &test.error {
+                report.push_str(&format!("- Error: {}\n", error));
             }
         }
         
         report.push_str("\n## Cryptographic Security Analysis\n");
         report.push_str("- **Secret Key Security**: Challenger generates keys after guest binary published\n");
-        report.push_str("- **Forgery Resistance**: FHE ciphertext space >> plaintext space\n");
+        report.push_str(&format!("- **Forgery Resistance**: a prover skipping the computation passes only by guessing each sum of {}-valued plaintexts\n", CHALLENGE_VALUES));
         report.push_str("- **Execution Integrity**: STARK proof with ~128-bit security\n");
         report.push_str("- **Verification Completeness**: All results decryptable by challenger\n");
         
         report.push_str("\n## Conclusion\n");
-        if successful_tests > 0 {
+        if data.proven_tests > 0 {
             report.push_str("🎯 **MATHEMATICAL CERTAINTY ACHIEVED**\n");
             report.push_str("The protocol successfully proves that genuine FHE computation\n");
             report.push_str("occurs inside the zkVM with cryptographic guarantees.\n");
@@ -266,6 +309,33 @@ impl FheProofProtocol {
     }
 }
 
+/// Chance that a prover who skips the computation passes one test by guessing
+/// the sum of `challenges` plaintexts uniform over 0..CHALLENGE_VALUES: the
+/// probability of the most likely sum.
+fn guessing_probability(challenges: usize) -> f64 {
+    let values = CHALLENGE_VALUES as usize;
+    let mut distribution = vec![1.0];
+    for _ in 0..challenges {
+        let mut next = vec![0.0; distribution.len() + values - 1];
+        for (sum, p) in distribution.iter().enumerate() {
+            for value in 0..values {
+                next[sum + value] += p / values as f64;
+            }
+        }
+        distribution = next;
+    }
+    distribution.into_iter().fold(0.0, f64::max)
+}
+
+/// The published challenge image id receipts are verified against
+fn image_id_hex() -> String {
+    CHALLENGE_ID.iter().flat_map(|word| word.to_le_bytes()).map(|b| format!("{:02x}", b)).collect()
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
 /// Main execution function demonstrating complete protocol
 pub fn demonstrate_mathematical_proof() {
     let mut protocol = FheProofProtocol::new();
@@ -285,21 +355,30 @@ pub fn demonstrate_mathematical_proof() {
     // Generate comprehensive report
     let report = protocol.generate_proof_report();
     
-    println!("\n" + "=".repeat(80));
+    println!("\n{}", "=".repeat(80));
     println!("{}", report);
-    println!("=".repeat(80));
+    println!("{}", "=".repeat(80));
     
-    // Save report to file
+    // Save report to file, with the machine-readable data alongside
     if let Err(e) = fs::write("FHE_MATHEMATICAL_PROOF_REPORT.md", report) {
         println!("⚠️  Warning: Could not save report to file: {}", e);
     } else {
         println!("📄 Report saved to: FHE_MATHEMATICAL_PROOF_REPORT.md");
     }
+    if let Err(e) = fs::write("FHE_MATHEMATICAL_PROOF_REPORT.json", protocol.generate_proof_report_json()) {
+        println!("⚠️  Warning: Could not save report data to file: {}", e);
+    } else {
+        println!("📄 Report data saved to: FHE_MATHEMATICAL_PROOF_REPORT.json");
+    }
     
     // Final validation
     let all_tests_passed = test1_passed && test2_passed && test3_passed;
+    let all_tests_proven = protocol.proof_report().proven_tests == 3;
     
-    if all_tests_passed {
+    if all_tests_passed && !all_tests_proven {
+        println!("\n⚠️  ALL OUTPUTS CORRECT, BUT DEV-MODE RECEIPTS PROVE NOTHING");
+        println!("   Rerun without RISC0_DEV_MODE to prove the challenges");
+    } else if all_tests_passed {
         println!("\n🏆 ULTIMATE CONCLUSION:");
         println!("======================");
         println!("✅ MATHEMATICAL PROOF COMPLETE");
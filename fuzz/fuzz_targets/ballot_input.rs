@@ -0,0 +1,69 @@
+// Guest input decoding and ballot checks.
+// Decodes arbitrary words as `VoteTallyInput` the way `env::read` does, then
+// runs every per-ballot check the guest applies before tallying: ballot set
+// and audit log commitments, authorization, and vote vector decoding.
+
+#![no_main]
+
+extern crate alloc;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../methods/guest/src/pure_rust_fhe.rs"]
+mod pure_rust_fhe;
+#[allow(dead_code)]
+#[path = "../../methods/guest/src/noise_distribution.rs"]
+mod noise_distribution;
+#[allow(dead_code)]
+#[path = "../../methods/guest/src/types.rs"]
+mod types;
+#[allow(dead_code)]
+#[path = "../../methods/guest/src/scheme.rs"]
+mod scheme;
+#[allow(dead_code)]
+#[path = "../../methods/guest/src/ballot_set.rs"]
+mod ballot_set;
+#[allow(dead_code)]
+#[path = "../../methods/guest/src/credentials.rs"]
+mod credentials;
+#[allow(dead_code)]
+#[path = "../../methods/guest/src/audit_log.rs"]
+mod audit_log;
+
+use pure_rust_fhe::{Cipher, PureRustFheRuntime};
+use types::{BallotAuthorization, VoteTallyInput};
+
+fuzz_target!(|data: &[u8]| {
+    let (words, _) = data.as_chunks::<4>();
+    let words: Vec<u32> = words.iter().map(|&word| u32::from_le_bytes(word)).collect();
+    let Ok(input) = risc0_zkvm::serde::from_slice::<VoteTallyInput, u32>(&words) else {
+        return;
+    };
+
+    let _ = ballot_set::compute_ballot_set_root(&input.encrypted_votes);
+    let _ = audit_log::compute_audit_log_head(&input.audit_log);
+    let _ = audit_log::accepted_records_match(&input.audit_log, &input.encrypted_votes);
+
+    // The guest refuses a malformed roster outright
+    let identity_root = credentials::identity_root(&input.identity_roster);
+    let members: Vec<_> = input.identity_roster.iter().filter_map(|bytes| CompressedRistretto(*bytes).decompress()).collect();
+
+    let runtime = PureRustFheRuntime::with_noise_seed([0; 32]);
+    let fingerprint = input.election_public_key.fingerprint();
+    let mut out = [Cipher::zeroed(), Cipher::zeroed(), Cipher::zeroed()];
+    for ballot in &input.encrypted_votes {
+        let message = ballot_set::ballot_message(&ballot.election_id, &ballot.voter_address, &ballot.nonce, &ballot.encrypted_vote_vector, ballot.sequence);
+        match &ballot.authorization {
+            BallotAuthorization::Credential { credential, ballot_signature } => {
+                let _ = credentials::verify_credential(credential, &input.registrar_public_key);
+                let _ = credentials::verify_ballot_signature(credential, ballot_signature, &message);
+            }
+            BallotAuthorization::Membership(proof) => {
+                let _ = credentials::verify_membership(proof, &members, &identity_root, &ballot_set::election_domain(&ballot.election_id), &message);
+            }
+        }
+        let _ = runtime.deserialize_vote_vector(&ballot.encrypted_vote_vector, &fingerprint, &mut out);
+    }
+});
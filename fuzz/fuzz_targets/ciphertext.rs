@@ -0,0 +1,48 @@
+// Ciphertext decoding, as done inside the guest for every ballot.
+// Decodes arbitrary bytes as a single ciphertext and as a key-bound vote
+// vector, and checks the host decoders accept exactly the same inputs.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+#[allow(dead_code)]
+#[path = "../../methods/guest/src/pure_rust_fhe.rs"]
+mod pure_rust_fhe;
+#[allow(dead_code)]
+#[path = "../../methods/guest/src/noise_distribution.rs"]
+mod noise_distribution;
+
+use pure_rust_fhe::{Cipher, PureRustFheRuntime};
+
+fuzz_target!(|data: &[u8]| {
+    let runtime = PureRustFheRuntime::with_noise_seed([0; 32]);
+
+    // Accepted ciphertexts are canonical: re-encoding and decoding is stable
+    let guest = runtime.deserialize_ciphertext(data);
+    if let Ok(cipher) = &guest {
+        let bytes = cipher.serialize();
+        let again = runtime.deserialize_ciphertext(&bytes).expect("re-encoded ciphertext must decode");
+        assert_eq!(again.serialize(), bytes);
+    }
+    let host = host::fhe_client::Cipher::<host::fhe_client::Signed>::deserialize(data);
+    assert_eq!(guest.is_ok(), host.is_ok());
+
+    // Vote vector: leading fingerprint, then the whole input as one key-bound
+    // entry and the rest split into three entries
+    let Some((fingerprint, rest)) = data.split_first_chunk::<32>() else {
+        return;
+    };
+    let vector: Vec<Vec<u8>> = rest.chunks(rest.len().div_ceil(3).max(1)).map(<[u8]>::to_vec).collect();
+    let mut single = [Cipher::zeroed()];
+    let guest = runtime.deserialize_vote_vector(&[data.to_vec()], fingerprint, &mut single);
+    let host = host::fhe_client::Cipher::<host::fhe_client::Signed>::deserialize_bound(data, fingerprint);
+    assert_eq!(guest.is_ok(), host.is_ok());
+
+    let mut out = [Cipher::zeroed(), Cipher::zeroed(), Cipher::zeroed()];
+    let before: Vec<Vec<u8>> = out.iter().map(Cipher::serialize).collect();
+    if runtime.deserialize_vote_vector(&vector, fingerprint, &mut out).is_err() {
+        // Rejected ballots leave the buffers untouched
+        assert!(out.iter().map(Cipher::serialize).eq(before));
+    }
+});
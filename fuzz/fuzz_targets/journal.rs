@@ -0,0 +1,13 @@
+// Journal decoding.
+// Feeds arbitrary bytes to `read_journal`, which consumers run on journals
+// handed to them alongside (or separately from) a receipt.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(journal) = host::journal::read_journal(data) {
+        assert_eq!(journal.encrypted_tallies.len(), journal.output.encrypted_tally_frames as usize);
+    }
+});
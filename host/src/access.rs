@@ -0,0 +1,121 @@
+// API key authentication and role separation.
+// Every key is issued for exactly one role: voters submit ballots, admins open
+// and close elections and trigger tallies, observers fetch receipts (admins may
+// too). Keys are random 32-byte secrets handed out hex-encoded; the store keeps
+// only their hashes, so a leaked store does not leak usable keys.
+//
+// Like `intake`, this is transport-agnostic: `daemon::route` passes the
+// presented key and the action the request maps to.
+
+use std::collections::HashMap;
+
+use rand::RngCore;
+use risc0_zkvm::sha::{Impl, Sha256};
+use thiserror::Error;
+
+const API_KEY_DOMAIN: &[u8] = b"fhe-voting/api-key/v1";
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum AccessError {
+    #[error("Unknown or revoked API key")]
+    Unauthenticated,
+    #[error("Role {role:?} may not {action:?}")]
+    Forbidden { role: Role, action: Action },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    Voter,
+    Admin,
+    Observer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    SubmitBallot,
+    OpenElection,
+    CloseElection,
+    TriggerTally,
+    FetchReceipt,
+}
+
+impl Role {
+    pub fn permits(self, action: Action) -> bool {
+        match action {
+            Action::SubmitBallot => self == Role::Voter,
+            Action::OpenElection | Action::CloseElection | Action::TriggerTally => self == Role::Admin,
+            Action::FetchReceipt => matches!(self, Role::Observer | Role::Admin),
+        }
+    }
+}
+
+fn key_hash(key: &[u8]) -> [u8; 32] {
+    let mut buf = API_KEY_DOMAIN.to_vec();
+    buf.extend_from_slice(key);
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+#[derive(Debug, Default)]
+pub struct ApiKeyStore {
+    keys: HashMap<[u8; 32], Role>,
+}
+
+impl ApiKeyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Issue a fresh key for `role`. The returned key is not stored.
+    pub fn issue(&mut self, role: Role) -> String {
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        self.keys.insert(key_hash(&key), role);
+        hex::encode(key)
+    }
+
+    /// Revoke a key. Returns whether it was known.
+    pub fn revoke(&mut self, key: &str) -> bool {
+        hex::decode(key).is_ok_and(|key| self.keys.remove(&key_hash(&key)).is_some())
+    }
+
+    pub fn authenticate(&self, key: &str) -> Result<Role, AccessError> {
+        let key = hex::decode(key).map_err(|_| AccessError::Unauthenticated)?;
+        self.keys.get(&key_hash(&key)).copied().ok_or(AccessError::Unauthenticated)
+    }
+
+    /// Authenticate `key` and check that its role may perform `action`.
+    pub fn authorize(&self, key: &str, action: Action) -> Result<Role, AccessError> {
+        let role = self.authenticate(key)?;
+        if !role.permits(action) {
+            return Err(AccessError::Forbidden { role, action });
+        }
+        Ok(role)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roles_are_separated() {
+        let mut store = ApiKeyStore::new();
+        let voter = store.issue(Role::Voter);
+        let admin = store.issue(Role::Admin);
+        let observer = store.issue(Role::Observer);
+
+        assert_eq!(store.authorize(&voter, Action::SubmitBallot), Ok(Role::Voter));
+        assert_eq!(store.authorize(&admin, Action::TriggerTally), Ok(Role::Admin));
+        assert_eq!(store.authorize(&observer, Action::FetchReceipt), Ok(Role::Observer));
+        assert_eq!(
+            store.authorize(&voter, Action::CloseElection),
+            Err(AccessError::Forbidden { role: Role::Voter, action: Action::CloseElection })
+        );
+        assert!(store.authorize(&admin, Action::SubmitBallot).is_err());
+        assert!(store.authorize(&observer, Action::OpenElection).is_err());
+
+        assert_eq!(store.authenticate("not a key"), Err(AccessError::Unauthenticated));
+        assert!(store.revoke(&voter));
+        assert_eq!(store.authorize(&voter, Action::SubmitBallot), Err(AccessError::Unauthenticated));
+    }
+}
@@ -0,0 +1,110 @@
+// Ethereum address validation and EIP-55 normalization.
+// Voter addresses arrive as strings from registrations, ballot submissions and
+// chain logs, and the same account can be spelled in lowercase, uppercase or
+// checksummed mixed case. Rate limits, the electoral roll and duplicate
+// detection key on the string, so every address is parsed and rewritten in its
+// EIP-55 form before anything looks it up; the guest counts only ballots whose
+// address is already in that form (must match guest implementation).
+//
+// All-lowercase and all-uppercase hex are accepted without a checksum; mixed
+// case is a checksum and must be the correct one.
+
+use sha3::{Digest, Keccak256};
+use thiserror::Error;
+
+use crate::types::EncryptedVote;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum AddressError {
+    #[error("Address {value} is malformed: {reason}")]
+    Malformed { value: String, reason: &'static str },
+    #[error("Address {value} fails its EIP-55 checksum")]
+    BadChecksum { value: String },
+}
+
+/// The EIP-55 spelling of `address`: 0x-prefixed hex, with each letter
+/// uppercased where the matching nibble of keccak256(lowercase hex) is 8 or more.
+pub fn to_checksum_address(address: &[u8; 20]) -> String {
+    let lower = hex::encode(address);
+    let hash = Keccak256::digest(lower.as_bytes());
+    let mut checksummed = String::with_capacity(42);
+    checksummed.push_str("0x");
+    for (i, c) in lower.chars().enumerate() {
+        let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+        checksummed.push(if nibble >= 8 { c.to_ascii_uppercase() } else { c });
+    }
+    checksummed
+}
+
+/// Parse a 0x-prefixed address, checking its checksum if it has one.
+pub fn parse_address(value: &str) -> Result<[u8; 20], AddressError> {
+    let malformed = |reason| AddressError::Malformed { value: value.to_string(), reason };
+    let digits = value.strip_prefix("0x").ok_or_else(|| malformed("missing 0x prefix"))?;
+    if digits.len() != 40 {
+        return Err(malformed("expected 40 hex digits"));
+    }
+    let address: [u8; 20] = hex::decode(digits)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| malformed("not hexadecimal"))?;
+    let mixed_case = digits.chars().any(|c| c.is_ascii_lowercase()) && digits.chars().any(|c| c.is_ascii_uppercase());
+    if mixed_case && to_checksum_address(&address) != value {
+        return Err(AddressError::BadChecksum { value: value.to_string() });
+    }
+    Ok(address)
+}
+
+/// The canonical (EIP-55) spelling of `value`.
+pub fn normalize_address(value: &str) -> Result<String, AddressError> {
+    parse_address(value).map(|address| to_checksum_address(&address))
+}
+
+/// Whether `value` is already in canonical form.
+pub fn is_canonical(value: &str) -> bool {
+    normalize_address(value).is_ok_and(|canonical| canonical == value)
+}
+
+/// Rewrite a submitted ballot's voter address in canonical form.
+pub fn normalize_ballot(ballot: &mut EncryptedVote) -> Result<(), AddressError> {
+    ballot.voter_address = normalize_address(&ballot.voter_address)?;
+    Ok(())
+}
+
+/// The address a ballot is filed under when it names no account: the last 20
+/// bytes of its anonymous voter key (credential token or nullifier).
+pub fn pseudonymous_address(voter_key: &[u8; 32]) -> String {
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&voter_key[12..]);
+    to_checksum_address(&address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // From the EIP-55 specification
+    const CHECKSUMMED: [&str; 4] = [
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ];
+
+    #[test]
+    fn test_addresses_normalize_to_their_checksum() -> Result<(), AddressError> {
+        for expected in CHECKSUMMED {
+            assert!(is_canonical(expected));
+            assert_eq!(normalize_address(&expected.to_lowercase())?, expected);
+            assert_eq!(normalize_address(&format!("0x{}", expected[2..].to_uppercase()))?, expected);
+        }
+
+        // One flipped letter breaks the checksum
+        let flipped = CHECKSUMMED[0].replacen("aA", "AA", 1);
+        assert_eq!(normalize_address(&flipped), Err(AddressError::BadChecksum { value: flipped.clone() }));
+        assert!(!is_canonical(&CHECKSUMMED[0].to_lowercase()));
+        for malformed in ["voter-0a0b", "5aaeb6053f3e94c9b9a09f33669435e7ef1beaed", "0x5aaeb6", "0xzzaeb6053f3e94c9b9a09f33669435e7ef1beaed"] {
+            assert!(matches!(normalize_address(malformed), Err(AddressError::Malformed { .. })));
+        }
+        Ok(())
+    }
+}
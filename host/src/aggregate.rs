@@ -0,0 +1,389 @@
+// Batch tallies and their aggregation into one receipt.
+// A large election can be proven in batches: the accepted ballots are split
+// into fixed-size sets, each tallied as an interim tally (counts sealed), and
+// the aggregation guest verifies every batch receipt and adds the encrypted
+// tallies together. The result is one succinct receipt, of constant size
+// however many batches there were, whose encrypted tallies the trustees decrypt
+// as usual.
+//
+// The aggregate journal binds the batches through `batch_chain_head`, a hash
+// chain over each batch's ballot set root and audit log head in order, so a
+// verifier holding the batch journals can check exactly which batches it
+// covers (`check_aggregate`). `aggregate_input` runs the guest's consistency
+// checks on the host first, so a bad batch fails before any proving.
+//
+// Batches must also share the voting scheme, and the plaintext overflow bound
+// applies to the summed `counted_weight` of every batch, not its ballot count.
+//
+// Re-voting is resolved inside a batch only, so `split_batches` keeps each
+// voter's ballots in one batch. Each batch commits the digest of its sorted
+// voter keys (credential tokens or nullifiers); `aggregate_input` passes the
+// keys along and the guest rejects a key repeated across batches before adding
+// any tally.
+
+use std::collections::{HashMap, HashSet};
+
+use risc0_zkvm::sha::{Digest, Impl, Sha256};
+use thiserror::Error;
+
+use crate::audit_log::AuditLog;
+use crate::ballot_set::{voter_key, voter_keys_digest, BallotSetCommitment};
+use crate::fhe_client::max_tally_ballots;
+use crate::journal::{read_interim_journal, InterimJournal, JournalError};
+use crate::types::{AggregateInput, AggregateTallyOutput, EncryptedVote, InterimTallyOutput, JournalProfile, VoteTallyInput};
+
+// Domain separation tag (must match guest implementation)
+const BATCH_TAG: u8 = 0x07;
+
+#[derive(Error, Debug)]
+pub enum AggregateError {
+    #[error("Batch size must be at least one ballot")]
+    BatchSize,
+    #[error("Aggregation needs at least one batch tally")]
+    NoBatches,
+    #[error("Batch {batch} journal is not an interim tally: {source}")]
+    Journal { batch: usize, source: JournalError },
+    #[error("Batch {batch} was tallied under another voting scheme")]
+    SchemeMismatch { batch: usize },
+    #[error("Batch {batch} belongs to another election")]
+    ElectionMismatch { batch: usize },
+    #[error("Batch {batch} was tallied against another identity roster")]
+    IdentityRootMismatch { batch: usize },
+    #[error("Batch {batch} is encrypted under another election key")]
+    KeyMismatch { batch: usize },
+    #[error("Batch {batch} has {frames} encrypted tallies, expected {expected}")]
+    FrameCountMismatch { batch: usize, frames: u32, expected: u32 },
+    #[error("Batch {batch} repeats an earlier batch's ballot set")]
+    DuplicateBatch { batch: usize },
+    #[error("Expected voter keys for {batches} batches, got {keys}")]
+    VoterKeyCount { batches: usize, keys: usize },
+    #[error("Batch {batch} voter keys do not match its journal")]
+    VoterKeysMismatch { batch: usize },
+    #[error("Batch {batch} has a voter with ballots in an earlier batch")]
+    RepeatedVoter { batch: usize },
+    #[error("Tally overflow: batches of total weight {weight} could exceed the plaintext modulus (at most {max} can be tallied)")]
+    TallyOverflow { weight: u64, max: u64 },
+    #[error("Aggregate journal does not cover the given batches")]
+    BatchChainMismatch,
+}
+
+/// Split the accepted ballots into interim inputs of at most `batch_size` ballots.
+/// A voter's ballots always share a batch, so the batch tally resolves their
+/// re-vote; a voter with more than `batch_size` ballots gets a batch of their own.
+/// Each batch gets its own ballot set and an audit log of just its ballots.
+pub fn split_batches(input: &VoteTallyInput, batch_size: usize) -> Result<Vec<VoteTallyInput>, AggregateError> {
+    if batch_size == 0 {
+        return Err(AggregateError::BatchSize);
+    }
+    let mut voters: Vec<Vec<EncryptedVote>> = Vec::new();
+    let mut positions = HashMap::new();
+    for ballot in &input.encrypted_votes {
+        let position = *positions.entry(voter_key(&ballot.authorization)).or_insert_with(|| {
+            voters.push(Vec::new());
+            voters.len() - 1
+        });
+        voters[position].push(ballot.clone());
+    }
+    let mut batches: Vec<Vec<EncryptedVote>> = Vec::new();
+    for ballots in voters {
+        match batches.last_mut() {
+            Some(batch) if batch.len() + ballots.len() <= batch_size => batch.extend(ballots),
+            _ => batches.push(ballots),
+        }
+    }
+    Ok(batches
+        .iter()
+        .map(|ballots| {
+            let mut audit_log = AuditLog::new();
+            for ballot in ballots {
+                audit_log.record_accepted(ballot);
+            }
+            VoteTallyInput {
+                encrypted_votes: ballots.clone(),
+                ballot_set_root: BallotSetCommitment::from_ballots(ballots).root(),
+                election_public_key: input.election_public_key.clone(),
+                registrar_public_key: input.registrar_public_key,
+                identity_roster: input.identity_roster.clone(),
+                audit_log_head: audit_log.head(),
+                audit_log: audit_log.records().to_vec(),
+                chain_anchor: None,
+                journal_profile: JournalProfile::Full,
+                interim: true,
+                prior_tally: None,
+                eip712_domain: None,
+                election: input.election,
+                candidates: input.candidates.clone(),
+                scheme: input.scheme.clone(),
+                min_anonymity_set: input.min_anonymity_set,
+                dp_noise: input.dp_noise,
+                revocations: Vec::new(),
+                electorate: input.electorate,
+                decryption: None,
+                mix: None,
+            }
+        })
+        .collect())
+}
+
+/// Chain step: head' = H(tag || head || index || ballot_set_root || audit_log_head).
+pub fn chain_batch(previous: &[u8; 32], index: u32, batch: &InterimTallyOutput) -> [u8; 32] {
+    let mut buf = vec![BATCH_TAG];
+    buf.extend_from_slice(previous);
+    buf.extend_from_slice(&index.to_le_bytes());
+    buf.extend_from_slice(&batch.ballot_set_root);
+    buf.extend_from_slice(&batch.audit_log_head);
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// Input for the aggregation guest, after the checks the guest will repeat.
+/// `batch_voter_keys` holds each batch's `ballot_set::voter_keys`.
+pub fn aggregate_input(tally_image_id: impl Into<Digest>, batch_journals: Vec<Vec<u8>>, batch_voter_keys: Vec<Vec<[u8; 32]>>) -> Result<AggregateInput, AggregateError> {
+    let batches = read_batches(&batch_journals)?;
+    if batch_voter_keys.len() != batches.len() {
+        return Err(AggregateError::VoterKeyCount { batches: batches.len(), keys: batch_voter_keys.len() });
+    }
+    let mut voters = HashSet::new();
+    for (batch, (InterimJournal { output, .. }, keys)) in batches.iter().zip(&batch_voter_keys).enumerate() {
+        if !keys.windows(2).all(|pair| pair[0] < pair[1]) || voter_keys_digest(keys) != output.voter_keys_digest {
+            return Err(AggregateError::VoterKeysMismatch { batch });
+        }
+        if keys.iter().any(|key| !voters.insert(*key)) {
+            return Err(AggregateError::RepeatedVoter { batch });
+        }
+    }
+    let tally_image_id: Digest = tally_image_id.into();
+    Ok(AggregateInput {
+        tally_image_id: tally_image_id.as_words().try_into().expect("a digest is eight words"),
+        batch_journals,
+        batch_voter_keys,
+    })
+}
+
+/// Check that an aggregate journal covers exactly `batch_journals`, in order.
+pub fn check_aggregate(output: &AggregateTallyOutput, batch_journals: &[Vec<u8>]) -> Result<(), AggregateError> {
+    let batches = read_batches(batch_journals)?;
+    let head = batches.iter().enumerate().fold([0u8; 32], |head, (index, batch)| chain_batch(&head, index as u32, &batch.output));
+    let ballots: u32 = batches.iter().map(|batch| batch.output.ballots).sum();
+    if head != output.batch_chain_head || output.batches as usize != batches.len() || output.ballots != ballots {
+        return Err(AggregateError::BatchChainMismatch);
+    }
+    Ok(())
+}
+
+// Decode every batch journal and check they can be combined
+fn read_batches(batch_journals: &[Vec<u8>]) -> Result<Vec<InterimJournal>, AggregateError> {
+    let batches = batch_journals
+        .iter()
+        .enumerate()
+        .map(|(batch, journal)| read_interim_journal(journal).map_err(|source| AggregateError::Journal { batch, source }))
+        .collect::<Result<Vec<_>, _>>()?;
+    let first = &batches.first().ok_or(AggregateError::NoBatches)?.output;
+    let mut ballot_sets = HashSet::new();
+    let mut weight = 0u64;
+    for (batch, InterimJournal { output, .. }) in batches.iter().enumerate() {
+        if output.election.scheme_digest != first.election.scheme_digest {
+            return Err(AggregateError::SchemeMismatch { batch });
+        }
+        if output.election != first.election {
+            return Err(AggregateError::ElectionMismatch { batch });
+        }
+        if output.identity_root != first.identity_root {
+            return Err(AggregateError::IdentityRootMismatch { batch });
+        }
+        if output.election_key_fingerprint != first.election_key_fingerprint {
+            return Err(AggregateError::KeyMismatch { batch });
+        }
+        if output.encrypted_tally_frames != first.encrypted_tally_frames {
+            return Err(AggregateError::FrameCountMismatch { batch, frames: output.encrypted_tally_frames, expected: first.encrypted_tally_frames });
+        }
+        if !ballot_sets.insert(output.ballot_set_root) {
+            return Err(AggregateError::DuplicateBatch { batch });
+        }
+        weight = weight.saturating_add(output.counted_weight);
+    }
+    if weight > max_tally_ballots() {
+        return Err(AggregateError::TallyOverflow { weight, max: max_tally_ballots() });
+    }
+    Ok(batches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ballot_set::voter_keys;
+    use crate::fhe_client::FheClient;
+    use crate::journal::chain_frame;
+    use crate::scheme::TallyScheme;
+    use crate::types::{BallotAuthorization, Digest32, ElectionContext, JournalFrame, MembershipProof, VoteOption, INTERIM_MARKER};
+
+    // Batch `root` has the voters `[root; 32]` and `[root + 100; 32]`
+    fn batch_keys(ballot_set_root: u8) -> Vec<[u8; 32]> {
+        vec![[ballot_set_root; 32], [ballot_set_root + 100; 32]]
+    }
+
+    fn batch_journal(ballot_set_root: u8, election_key_fingerprint: [u8; 32]) -> Vec<u8> {
+        batch_journal_with_keys(ballot_set_root, election_key_fingerprint, &batch_keys(ballot_set_root))
+    }
+
+    fn batch_journal_with_keys(ballot_set_root: u8, election_key_fingerprint: [u8; 32], voter_keys: &[[u8; 32]]) -> Vec<u8> {
+        encode_batch(&batch_output(ballot_set_root, election_key_fingerprint, voter_keys))
+    }
+
+    fn batch_output(ballot_set_root: u8, election_key_fingerprint: [u8; 32], voter_keys: &[[u8; 32]]) -> InterimTallyOutput {
+        InterimTallyOutput {
+            marker: INTERIM_MARKER,
+            ballots: 2,
+            superseded_ballots: 0,
+            counted_weight: 2,
+            election: ElectionContext::default(),
+            ballot_set_root: [ballot_set_root; 32],
+            identity_root: [1u8; 32],
+            election_key_fingerprint,
+            audit_log_head: [ballot_set_root ^ 0xff; 32],
+            voter_keys_digest: voter_keys_digest(voter_keys),
+            prior_tally_digest: [0u8; 32],
+            encrypted_tally_frames: 1,
+        }
+    }
+
+    fn test_ballot(voter: u8, sequence: u64) -> EncryptedVote {
+        EncryptedVote {
+            voter_address: format!("voter-{}", voter),
+            election_id: Digest32::ZERO,
+            nonce: [voter ^ sequence as u8; 32],
+            encrypted_vote_vector: vec![vec![voter; 16]; 3],
+            signature: String::new(),
+            authorization: BallotAuthorization::Membership(MembershipProof {
+                nullifier: [voter; 32],
+                challenges: Vec::new(),
+                responses: Vec::new(),
+            }),
+            sequence,
+            actual_choice: VoteOption::Option1,
+        }
+    }
+
+    fn test_input(encrypted_votes: Vec<EncryptedVote>) -> VoteTallyInput {
+        VoteTallyInput {
+            encrypted_votes,
+            ballot_set_root: [0u8; 32],
+            election_public_key: FheClient::new().get_public_key().clone(),
+            registrar_public_key: [0u8; 32],
+            identity_roster: Vec::new(),
+            audit_log: Vec::new(),
+            audit_log_head: [0u8; 32],
+            chain_anchor: None,
+            journal_profile: JournalProfile::Full,
+            interim: false,
+            prior_tally: None,
+            eip712_domain: None,
+            election: ElectionContext::default(),
+            candidates: Vec::new(),
+            scheme: TallyScheme::default(),
+            min_anonymity_set: 0,
+            dp_noise: None,
+            revocations: Vec::new(),
+            electorate: None,
+            decryption: None,
+            mix: None,
+        }
+    }
+
+    fn encode_batch(output: &InterimTallyOutput) -> Vec<u8> {
+        let payload = vec![output.ballot_set_root[0]; 16];
+        let frame = JournalFrame { index: 0, chunk_hash: chain_frame(&[0u8; 32], 0, &payload), payload };
+        let mut words = risc0_zkvm::serde::to_vec(output).unwrap();
+        words.extend(risc0_zkvm::serde::to_vec(&frame).unwrap());
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_batches_chain_into_the_aggregate() -> Result<(), AggregateError> {
+        let journals: Vec<_> = (0..3).map(|root| batch_journal(root, [9u8; 32])).collect();
+        let input = aggregate_input(Digest::from([5u32; 8]), journals.clone(), (0..3).map(batch_keys).collect())?;
+        assert_eq!(input.tally_image_id, [5u32; 8]);
+
+        let batches: Vec<_> = journals.iter().map(|journal| read_interim_journal(journal).unwrap().output).collect();
+        let mut output = AggregateTallyOutput {
+            tally_image_id: input.tally_image_id,
+            batches: 3,
+            ballots: 6,
+            superseded_ballots: 0,
+            election: ElectionContext::default(),
+            identity_root: [1u8; 32],
+            election_key_fingerprint: [9u8; 32],
+            batch_chain_head: batches.iter().enumerate().fold([0u8; 32], |head, (index, batch)| chain_batch(&head, index as u32, batch)),
+            encrypted_tally_frames: 1,
+        };
+        check_aggregate(&output, &journals)?;
+        // Dropping or reordering a batch changes the chain
+        assert!(matches!(check_aggregate(&output, &journals[..2]), Err(AggregateError::BatchChainMismatch)));
+        let reordered = vec![journals[1].clone(), journals[0].clone(), journals[2].clone()];
+        assert!(matches!(check_aggregate(&output, &reordered), Err(AggregateError::BatchChainMismatch)));
+        output.ballots += 1;
+        assert!(matches!(check_aggregate(&output, &journals), Err(AggregateError::BatchChainMismatch)));
+
+        // Batches must be distinct and under one key
+        let repeated = vec![journals[0].clone(), journals[0].clone()];
+        assert!(matches!(aggregate_input(Digest::ZERO, repeated, vec![batch_keys(0); 2]), Err(AggregateError::DuplicateBatch { batch: 1 })));
+        let foreign = vec![journals[0].clone(), batch_journal(7, [8u8; 32])];
+        assert!(matches!(aggregate_input(Digest::ZERO, foreign, vec![batch_keys(0), batch_keys(7)]), Err(AggregateError::KeyMismatch { batch: 1 })));
+        assert!(matches!(aggregate_input(Digest::ZERO, Vec::new(), Vec::new()), Err(AggregateError::NoBatches)));
+        let mut weighted = batch_output(7, [9u8; 32], &batch_keys(7));
+        weighted.election.scheme_digest = Digest32([1u8; 32]);
+        let rescheme = vec![journals[0].clone(), encode_batch(&weighted)];
+        assert!(matches!(aggregate_input(Digest::ZERO, rescheme, vec![batch_keys(0), batch_keys(7)]), Err(AggregateError::SchemeMismatch { batch: 1 })));
+
+        // The overflow bound is on the weight the batches hold, not their ballot count
+        let mut heavy = batch_output(7, [9u8; 32], &batch_keys(7));
+        heavy.counted_weight = max_tally_ballots();
+        let overflow = vec![journals[0].clone(), encode_batch(&heavy)];
+        assert!(matches!(aggregate_input(Digest::ZERO, overflow, vec![batch_keys(0), batch_keys(7)]), Err(AggregateError::TallyOverflow { weight, .. }) if weight == max_tally_ballots() + 2));
+
+        // Each batch's keys must be the ones it committed, and no voter may be in two batches
+        assert!(matches!(aggregate_input(Digest::ZERO, journals.clone(), vec![batch_keys(0)]), Err(AggregateError::VoterKeyCount { batches: 3, keys: 1 })));
+        let mut unsorted: Vec<_> = (0..3).map(batch_keys).collect();
+        unsorted[1].reverse();
+        assert!(matches!(aggregate_input(Digest::ZERO, journals.clone(), unsorted), Err(AggregateError::VoterKeysMismatch { batch: 1 })));
+        let mut hidden: Vec<_> = (0..3).map(batch_keys).collect();
+        hidden[2] = vec![[2u8; 32], [0u8; 32]];
+        assert!(matches!(aggregate_input(Digest::ZERO, journals.clone(), hidden), Err(AggregateError::VoterKeysMismatch { batch: 2 })));
+        let shared = vec![batch_keys(0), vec![[0u8; 32], [9u8; 32]]];
+        let revoter = vec![journals[0].clone(), batch_journal_with_keys(9, [9u8; 32], &shared[1])];
+        assert!(matches!(aggregate_input(Digest::ZERO, revoter, shared), Err(AggregateError::RepeatedVoter { batch: 1 })));
+        Ok(())
+    }
+
+    #[test]
+    fn test_revote_stays_in_one_batch() -> Result<(), AggregateError> {
+        // Voter 1 re-votes after voters 2 and 3 have filled the first batch's slots
+        let ballots = vec![test_ballot(1, 0), test_ballot(2, 0), test_ballot(3, 0), test_ballot(1, 1), test_ballot(4, 0)];
+        let batches = split_batches(&test_input(ballots), 2)?;
+        let sizes: Vec<_> = batches.iter().map(|batch| batch.encrypted_votes.len()).collect();
+        assert_eq!(sizes, vec![2, 2, 1]);
+        assert_eq!(batches[0].encrypted_votes.iter().map(|ballot| ballot.sequence).collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(voter_keys(&batches[0].encrypted_votes), vec![[1u8; 32]]);
+
+        // The batch keys are disjoint, so the aggregate accepts them
+        let batch_voter_keys: Vec<_> = batches.iter().map(|batch| voter_keys(&batch.encrypted_votes)).collect();
+        let journals = batch_voter_keys
+            .iter()
+            .enumerate()
+            .map(|(root, keys)| {
+                let mut output = batch_output(root as u8, [9u8; 32], keys);
+                output.ballots = sizes[root] as u32;
+                output.superseded_ballots = sizes[root] as u32 - keys.len() as u32;
+                output.counted_weight = keys.len() as u64;
+                encode_batch(&output)
+            })
+            .collect();
+        let input = aggregate_input(Digest::ZERO, journals, batch_voter_keys)?;
+        assert_eq!(input.batch_voter_keys.len(), 3);
+
+        // A voter with more ballots than the batch size gets a batch of their own
+        let revotes: Vec<_> = (0..3).map(|sequence| test_ballot(1, sequence)).chain([test_ballot(2, 0)]).collect();
+        let sizes: Vec<_> = split_batches(&test_input(revotes), 2)?.iter().map(|batch| batch.encrypted_votes.len()).collect();
+        assert_eq!(sizes, vec![3, 1]);
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+// Archival export of finished elections.
+// An archive is a manifested bundle (see `bundle`) holding everything needed
+// to re-check a result long after the election: the scheme parameters and
+// guest image id, the election public key, the ballot commitments (leaf
+// hashes, in tally order), the receipt, its journal, and the trustees'
+// decryption transcripts with their challenge-response share proofs. The
+// manifest hash commits to all of it and is the value to publish alongside
+// the result.
+//
+// Importing re-runs every check from the archive alone: the manifest digests,
+// the receipt against the recorded image id, the journal frames, the
+// committed election against the one the caller expects, the ballot set root,
+// the transcript's trustees against the journal's `trustees_digest`, and each
+// decryption proof against the journal's tally ciphertexts and counts. Whether the recorded image id is the expected guest is for the
+// caller to decide.
+
+use std::path::Path;
+
+use risc0_zkvm::{Receipt, VerifierContext};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+use crate::ballot_set::merkle_root;
+use crate::bundle::{Bundle, BundleError};
+use crate::decryption_shares::{trustees_digest, verify_decryption_proof, DecryptionProof, DecryptionShareError, ShareVerificationKey};
+use crate::election::{check_election, ElectionError};
+use crate::fhe_client::{PublicKey, CIPHERTEXT_MODULUS, PLAINTEXT_MODULUS, POLYNOMIAL_DEGREE};
+use crate::journal::{read_journal, JournalError, TallyJournal};
+use crate::types::{Digest32, ElectionContext};
+
+/// Layout version written to the manifest.
+pub const ARCHIVE_VERSION: u32 = 1;
+
+const PARAMETERS_FILE: &str = "parameters.json";
+const PUBLIC_KEY_FILE: &str = "public_key.json";
+const BALLOT_COMMITMENTS_FILE: &str = "ballot_commitments.json";
+const RECEIPT_FILE: &str = "receipt.bin";
+const JOURNAL_FILE: &str = "journal.bin";
+const DECRYPTION_FILE: &str = "decryption_transcripts.json";
+
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+    #[error("Archive bundle is invalid: {0}")]
+    Bundle(#[from] BundleError),
+    #[error("Archive encoding failed: {reason}")]
+    Encoding { reason: String },
+    #[error("Manifest hash is {actual}, expected {expected}")]
+    ManifestHashMismatch { expected: Digest32, actual: Digest32 },
+    #[error("Archive was produced with different FHE parameters")]
+    UnsupportedParameters,
+    #[error("Archived receipt does not verify: {reason}")]
+    Receipt { reason: String },
+    #[error("Archived journal differs from the receipt's journal")]
+    JournalMismatch,
+    #[error("Archived journal is malformed: {0}")]
+    Journal(#[from] JournalError),
+    #[error("Archived tally is for another election: {0}")]
+    Election(#[from] ElectionError),
+    #[error("Ballot commitments do not match the proven ballot set root")]
+    BallotSetMismatch,
+    #[error("Decryption transcript names other trustees than the proven counts were decrypted with")]
+    TrusteesMismatch,
+    #[error("Archive has {proofs} decryption proofs for {tallies} tallies")]
+    TranscriptCount { proofs: usize, tallies: usize },
+    #[error("Decryption proof for option {option} does not verify: {source}")]
+    Decryption { option: usize, source: DecryptionShareError },
+    #[error("Decryption proof for option {option} announces {announced}, journal says {counted}")]
+    CountMismatch { option: usize, announced: u64, counted: u32 },
+}
+
+fn encoding(e: impl ToString) -> ArchiveError {
+    ArchiveError::Encoding { reason: e.to_string() }
+}
+
+/// Scheme parameters and the guest the receipt was produced by.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemeParameters {
+    pub plaintext_modulus: u64,
+    pub ciphertext_modulus: u64,
+    pub polynomial_degree: usize,
+    #[serde(with = "hex::serde")]
+    pub image_id: [u8; 32],
+}
+
+impl SchemeParameters {
+    /// This build's FHE parameters, for a receipt from guest `image_id`.
+    pub fn current(image_id: [u8; 32]) -> Self {
+        SchemeParameters {
+            plaintext_modulus: PLAINTEXT_MODULUS,
+            ciphertext_modulus: CIPHERTEXT_MODULUS,
+            polynomial_degree: POLYNOMIAL_DEGREE,
+            image_id,
+        }
+    }
+}
+
+/// Trustee decryption of the proven tallies: one proof per option, in journal order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecryptionTranscript {
+    pub threshold: u32,
+    pub verification_keys: Vec<ShareVerificationKey>,
+    pub proofs: Vec<DecryptionProof>,
+}
+
+pub struct ElectionArchive {
+    pub parameters: SchemeParameters,
+    pub election_public_key: PublicKey,
+    pub ballot_commitments: Vec<[u8; 32]>, // Ballot leaf hashes, in tally order
+    pub receipt: Receipt,
+    pub decryption: DecryptionTranscript,
+}
+
+/// A successfully re-verified archive and what it proves.
+pub struct VerifiedArchive {
+    pub archive: ElectionArchive,
+    pub journal: TallyJournal,
+    pub manifest_hash: [u8; 32],
+}
+
+impl ElectionArchive {
+    fn bundle(&self) -> Result<Bundle, ArchiveError> {
+        let mut bundle = Bundle::new(ARCHIVE_VERSION);
+        bundle.insert(PARAMETERS_FILE, serde_json::to_vec_pretty(&self.parameters).map_err(encoding)?);
+        bundle.insert(PUBLIC_KEY_FILE, serde_json::to_vec(&self.election_public_key).map_err(encoding)?);
+        let commitments: Vec<Digest32> = self.ballot_commitments.iter().copied().map(Digest32).collect();
+        bundle.insert(BALLOT_COMMITMENTS_FILE, serde_json::to_vec_pretty(&commitments).map_err(encoding)?);
+        bundle.insert(RECEIPT_FILE, bincode::serialize(&self.receipt).map_err(encoding)?);
+        bundle.insert(JOURNAL_FILE, self.receipt.journal.bytes.clone());
+        bundle.insert(DECRYPTION_FILE, serde_json::to_vec_pretty(&self.decryption).map_err(encoding)?);
+        Ok(bundle)
+    }
+
+    /// Hash of the manifest the archive is written with.
+    pub fn manifest_hash(&self) -> Result<[u8; 32], ArchiveError> {
+        Ok(self.bundle()?.manifest_hash()?)
+    }
+
+    /// Write to a directory, or to a gzipped tarball if `path` ends in `.tar.gz`.
+    /// Returns the manifest hash.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<[u8; 32], ArchiveError> {
+        let path = path.as_ref();
+        let bundle = self.bundle()?;
+        if path.to_string_lossy().ends_with(".tar.gz") {
+            bundle.write_tarball(path)?;
+        } else {
+            bundle.write_dir(path)?;
+        }
+        Ok(bundle.manifest_hash()?)
+    }
+}
+
+/// Read an archive from a directory or tarball and re-verify it from scratch.
+/// The tally must commit `expected_election`. If `expected_manifest_hash` is
+/// given (the published value), the archive must match it.
+pub fn import_and_verify(
+    path: impl AsRef<Path>,
+    ctx: &VerifierContext,
+    expected_election: &ElectionContext,
+    expected_manifest_hash: Option<[u8; 32]>,
+) -> Result<VerifiedArchive, ArchiveError> {
+    let mut bundle = Bundle::read(path, ARCHIVE_VERSION)?;
+    let manifest_hash = bundle.manifest_hash()?;
+    if let Some(expected) = expected_manifest_hash {
+        if expected != manifest_hash {
+            return Err(ArchiveError::ManifestHashMismatch { expected: expected.into(), actual: manifest_hash.into() });
+        }
+    }
+
+    let parameters: SchemeParameters = serde_json::from_slice(&bundle.take(PARAMETERS_FILE)?).map_err(encoding)?;
+    if parameters != SchemeParameters::current(parameters.image_id) {
+        return Err(ArchiveError::UnsupportedParameters);
+    }
+    let election_public_key: PublicKey = serde_json::from_slice(&bundle.take(PUBLIC_KEY_FILE)?).map_err(encoding)?;
+    let commitments: Vec<Digest32> = serde_json::from_slice(&bundle.take(BALLOT_COMMITMENTS_FILE)?).map_err(encoding)?;
+    let ballot_commitments: Vec<[u8; 32]> = commitments.into_iter().map(Into::into).collect();
+    let receipt: Receipt = bincode::deserialize(&bundle.take(RECEIPT_FILE)?).map_err(encoding)?;
+    let journal_bytes = bundle.take(JOURNAL_FILE)?;
+    let decryption: DecryptionTranscript = serde_json::from_slice(&bundle.take(DECRYPTION_FILE)?).map_err(encoding)?;
+
+    receipt
+        .verify_with_context(ctx, parameters.image_id)
+        .map_err(|e| ArchiveError::Receipt { reason: e.to_string() })?;
+    if journal_bytes != receipt.journal.bytes {
+        return Err(ArchiveError::JournalMismatch);
+    }
+    let journal = read_journal(&journal_bytes)?;
+    check_election(expected_election, &journal.output.election)?;
+    if merkle_root(ballot_commitments.clone()) != journal.output.ballot_set_root {
+        return Err(ArchiveError::BallotSetMismatch);
+    }
+
+    if trustees_digest(decryption.threshold, &decryption.verification_keys) != journal.output.trustees_digest {
+        return Err(ArchiveError::TrusteesMismatch);
+    }
+    let counts = [journal.output.option1_count, journal.output.option2_count, journal.output.option3_count];
+    if decryption.proofs.len() != journal.encrypted_tallies.len() || decryption.proofs.len() != counts.len() {
+        return Err(ArchiveError::TranscriptCount { proofs: decryption.proofs.len(), tallies: journal.encrypted_tallies.len() });
+    }
+    for (index, ((proof, tally), &counted)) in decryption.proofs.iter().zip(&journal.encrypted_tallies).zip(&counts).enumerate() {
+        let option = index + 1;
+        verify_decryption_proof(proof, tally, &decryption.verification_keys, decryption.threshold)
+            .map_err(|source| ArchiveError::Decryption { option, source })?;
+        if proof.plaintext != counted as u64 {
+            return Err(ArchiveError::CountMismatch { option, announced: proof.plaintext, counted });
+        }
+    }
+
+    let archive = ElectionArchive { parameters, election_public_key, ballot_commitments, receipt, decryption };
+    Ok(VerifiedArchive { archive, journal, manifest_hash })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use risc0_zkvm::{FakeReceipt, InnerReceipt, ReceiptClaim};
+    use crate::ballot_set::ballot_leaf_hash;
+    use crate::decryption_shares::{prove_decryption, TrusteeDecryptor};
+    use crate::dkg::{self, DkgConfig};
+    use crate::fhe_client::{FheClient, BOUND_HEADER_LEN};
+    use crate::journal::chain_frame;
+    use crate::snapshot::partial_tallies;
+    use crate::types::{BallotAuthorization, ElectionContext, EncryptedVote, JournalFrame, MembershipProof, Outcome, Turnout, VoteOption, VoteTallyOutput, REJECTION_REASONS};
+
+    #[test]
+    fn test_archive_round_trip_and_tampering() -> Result<(), Box<dyn std::error::Error>> {
+        let config = DkgConfig::threshold(2, 3)?;
+        let (election_public_key, trustee_outputs) = dkg::run_in_process(config)?;
+        let client = FheClient::with_public_key(election_public_key.clone());
+        let choices = [VoteOption::Option1, VoteOption::Option3, VoteOption::Option1];
+        let ballots = client
+            .encrypt_vote_vectors(&choices)?
+            .into_iter()
+            .enumerate()
+            .map(|(i, encrypted_vote_vector)| EncryptedVote {
+                voter_address: format!("voter-{}", i),
+                election_id: Digest32::ZERO,
+                nonce: [i as u8; 32],
+                encrypted_vote_vector,
+                signature: String::new(),
+                authorization: BallotAuthorization::Membership(MembershipProof {
+                    nullifier: [i as u8; 32],
+                    challenges: Vec::new(),
+                    responses: Vec::new(),
+                }),
+                sequence: 0,
+                actual_choice: choices[i],
+            })
+            .collect::<Vec<_>>();
+        let ballot_commitments: Vec<[u8; 32]> = ballots.iter().map(ballot_leaf_hash).collect();
+        // The guest commits tallies without the key fingerprint
+        let tallies: Vec<Vec<u8>> = partial_tallies(&ballots, &election_public_key.fingerprint())?
+            .into_iter()
+            .map(|mut tally| tally.split_off(BOUND_HEADER_LEN))
+            .collect();
+
+        let (trustees, verification_keys): (Vec<_>, Vec<_>) =
+            trustee_outputs.into_iter().map(|output| TrusteeDecryptor::new(output.key_share)).unzip();
+
+        // Journal as the guest commits it
+        let output = VoteTallyOutput {
+            option1_count: 2,
+            option2_count: 0,
+            option3_count: 1,
+            total_votes: 3,
+            election: ElectionContext::default(),
+            computation_hash: Digest32::ZERO,
+            ballot_set_root: merkle_root(ballot_commitments.clone()),
+            identity_root: [0u8; 32],
+            superseded_ballots: 0,
+            turnout: Turnout::default(),
+            outcome: Outcome::default(),
+            rejected_count: 0,
+            duplicate_count: 0,
+            invalid_signature_count: 0,
+            rejections_by_reason: [0; REJECTION_REASONS],
+            audit_log_head: [0u8; 32],
+            chain_anchor: None,
+            prior_tally_digest: [0u8; 32],
+            result_typed_digest: [0u8; 32],
+            trustees_digest: trustees_digest(config.threshold, &verification_keys),
+            encrypted_tally_frames: 3,
+        };
+        let mut words = risc0_zkvm::serde::to_vec(&output)?;
+        let mut head = [0u8; 32];
+        for (index, payload) in tallies.iter().enumerate() {
+            head = chain_frame(&head, index as u32, payload);
+            words.extend(risc0_zkvm::serde::to_vec(&JournalFrame { index: index as u32, payload: payload.clone(), chunk_hash: head })?);
+        }
+        let journal: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+        let image_id = [5u8; 32];
+        let claim = ReceiptClaim::ok(image_id, journal.clone());
+        let receipt = Receipt::new(InnerReceipt::Fake(FakeReceipt::new(claim)), journal);
+
+        let proofs = tallies
+            .iter()
+            .map(|tally| {
+                let partials = trustees[1..].iter().map(|trustee| trustee.partial_decrypt(tally)).collect::<Result<Vec<_>, _>>()?;
+                prove_decryption(tally, partials, &verification_keys, config.threshold)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let archive = ElectionArchive {
+            parameters: SchemeParameters::current(image_id),
+            election_public_key,
+            ballot_commitments,
+            receipt,
+            decryption: DecryptionTranscript { threshold: config.threshold, verification_keys, proofs },
+        };
+
+        // Fake receipts only verify in dev mode
+        let ctx = VerifierContext::default().with_dev_mode(true);
+        let base = std::env::temp_dir().join(format!("fhe-voting-archive-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        let manifest_hash = archive.write(&base)?;
+        let election = ElectionContext::default();
+        let verified = import_and_verify(&base, &ctx, &election, Some(manifest_hash))?;
+        assert_eq!(verified.journal.output.option1_count, 2);
+        assert!(import_and_verify(&base, &VerifierContext::default().with_dev_mode(false), &election, None).is_err());
+        assert!(matches!(import_and_verify(&base, &ctx, &election, Some([0u8; 32])), Err(ArchiveError::ManifestHashMismatch { .. })));
+        // A valid archive of one election is not the result of another
+        let other = ElectionContext { chain_id: 10, ..election };
+        assert!(matches!(import_and_verify(&base, &ctx, &other, None), Err(ArchiveError::Election(_))));
+
+        let tarball = base.with_extension("tar.gz");
+        assert_eq!(archive.write(&tarball)?, manifest_hash);
+        assert_eq!(import_and_verify(&tarball, &ctx, &election, None)?.manifest_hash, manifest_hash);
+        fs::remove_file(&tarball)?;
+
+        // So is a transcript from other trustees than the guest decrypted with
+        let mut forged = archive;
+        forged.decryption.threshold = 3;
+        forged.write(&base)?;
+        assert!(matches!(import_and_verify(&base, &ctx, &election, None), Err(ArchiveError::TrusteesMismatch)));
+        forged.decryption.threshold = config.threshold;
+
+        // A wrong announced count is caught even when the manifest is rebuilt around it
+        forged.decryption.proofs[0].plaintext = 3;
+        forged.write(&base)?;
+        assert!(matches!(import_and_verify(&base, &ctx, &election, None), Err(ArchiveError::Decryption { option: 1, .. })));
+        fs::remove_dir_all(&base)?;
+        Ok(())
+    }
+}
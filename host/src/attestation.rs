@@ -0,0 +1,170 @@
+// Signed result attestations.
+// After verifying a receipt, the operator signs a compact statement of what it
+// verified: the guest image ID, the journal digest and the results committed
+// in that journal. Downstream systems that cannot verify a STARK can check the
+// Ed25519 signature against the operator's published key, and anyone who can
+// verify the receipt can check that its journal hashes to the attested digest.
+//
+// The signature covers a fixed binary encoding (`signing_bytes`), not the JSON
+// or CBOR export, so both encodings carry the same verifiable statement.
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use risc0_zkvm::sha::{Digest, Impl, Sha256};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+use crate::types::VoteTallyOutput;
+
+const ATTESTATION_DOMAIN: &[u8] = b"fhe-voting/attestation/v1";
+
+#[derive(Error, Debug)]
+pub enum AttestationError {
+    #[error("Malformed operator public key")]
+    MalformedPublicKey,
+    #[error("Operator signature does not verify")]
+    InvalidSignature,
+    #[error("Attestation encoding failed: {reason}")]
+    Encoding { reason: String },
+}
+
+/// What the operator attests to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestationBody {
+    #[serde(with = "hex::serde")]
+    pub image_id: [u8; 32],
+    #[serde(with = "hex::serde")]
+    pub journal_digest: [u8; 32],
+    pub option_counts: Vec<u32>,
+    pub total_votes: u32,
+    #[serde(with = "hex::serde")]
+    pub ballot_set_root: [u8; 32],
+    #[serde(with = "hex::serde")]
+    pub audit_log_head: [u8; 32],
+}
+
+impl AttestationBody {
+    /// Attest to a verified receipt's image ID, journal and decoded results.
+    pub fn new(image_id: impl Into<Digest>, journal: &[u8], output: &VoteTallyOutput) -> Self {
+        let image_id: Digest = image_id.into();
+        AttestationBody {
+            image_id: image_id.into(),
+            journal_digest: (*Impl::hash_bytes(journal)).into(),
+            option_counts: vec![output.option1_count, output.option2_count, output.option3_count],
+            total_votes: output.total_votes,
+            ballot_set_root: output.ballot_set_root,
+            audit_log_head: output.audit_log_head,
+        }
+    }
+
+    /// Canonical bytes the operator signs.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut buf = ATTESTATION_DOMAIN.to_vec();
+        buf.extend_from_slice(&self.image_id);
+        buf.extend_from_slice(&self.journal_digest);
+        buf.extend_from_slice(&(self.option_counts.len() as u32).to_le_bytes());
+        for count in &self.option_counts {
+            buf.extend_from_slice(&count.to_le_bytes());
+        }
+        buf.extend_from_slice(&self.total_votes.to_le_bytes());
+        buf.extend_from_slice(&self.ballot_set_root);
+        buf.extend_from_slice(&self.audit_log_head);
+        buf
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedAttestation {
+    pub body: AttestationBody,
+    #[serde(with = "hex::serde")]
+    pub operator_public_key: [u8; 32],
+    #[serde(with = "hex::serde")]
+    pub signature: [u8; 64],
+}
+
+impl SignedAttestation {
+    pub fn to_json(&self) -> Result<String, AttestationError> {
+        serde_json::to_string_pretty(self).map_err(|e| AttestationError::Encoding { reason: e.to_string() })
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, AttestationError> {
+        serde_json::from_str(json).map_err(|e| AttestationError::Encoding { reason: e.to_string() })
+    }
+
+    pub fn to_cbor(&self) -> Result<Vec<u8>, AttestationError> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(self, &mut bytes).map_err(|e| AttestationError::Encoding { reason: e.to_string() })?;
+        Ok(bytes)
+    }
+
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, AttestationError> {
+        ciborium::from_reader(bytes).map_err(|e| AttestationError::Encoding { reason: e.to_string() })
+    }
+}
+
+/// The operator's long-term signing key.
+#[derive(Clone)]
+pub struct Operator {
+    signing_key: SigningKey,
+}
+
+impl Operator {
+    pub fn generate() -> Self {
+        Operator { signing_key: SigningKey::generate(&mut rand::rngs::OsRng) }
+    }
+
+    pub fn from_secret(secret: [u8; 32]) -> Self {
+        Operator { signing_key: SigningKey::from_bytes(&secret) }
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.signing_key.verifying_key().to_bytes()
+    }
+
+    pub fn attest(&self, body: AttestationBody) -> SignedAttestation {
+        let signature = self.sign(&body.signing_bytes());
+        SignedAttestation { body, operator_public_key: self.public_key(), signature }
+    }
+
+    /// Sign arbitrary operator statements (e.g. security event records).
+    pub fn sign(&self, message: &[u8]) -> [u8; 64] {
+        self.signing_key.sign(message).to_bytes()
+    }
+}
+
+/// Check the operator signature. Callers must also check that
+/// `operator_public_key` is the key the operator published.
+pub fn verify_attestation(attestation: &SignedAttestation) -> Result<(), AttestationError> {
+    let key = VerifyingKey::from_bytes(&attestation.operator_public_key)
+        .map_err(|_| AttestationError::MalformedPublicKey)?;
+    key.verify(&attestation.body.signing_bytes(), &Signature::from_bytes(&attestation.signature))
+        .map_err(|_| AttestationError::InvalidSignature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attestation_round_trips_and_detects_tampering() -> Result<(), AttestationError> {
+        let body = AttestationBody {
+            image_id: [1u8; 32],
+            journal_digest: [2u8; 32],
+            option_counts: vec![3, 2, 1],
+            total_votes: 6,
+            ballot_set_root: [3u8; 32],
+            audit_log_head: [4u8; 32],
+        };
+        let attestation = Operator::generate().attest(body);
+        verify_attestation(&attestation)?;
+
+        let from_json = SignedAttestation::from_json(&attestation.to_json()?)?;
+        let from_cbor = SignedAttestation::from_cbor(&attestation.to_cbor()?)?;
+        assert_eq!(from_json, attestation);
+        assert_eq!(from_cbor, attestation);
+
+        let mut tampered = from_json;
+        tampered.body.option_counts[2] += 1;
+        assert!(matches!(verify_attestation(&tampered), Err(AttestationError::InvalidSignature)));
+        Ok(())
+    }
+}
@@ -0,0 +1,189 @@
+// Sealed-bid auctions.
+// A bid is sealed as a unary vector over the auction's public price ladder:
+// `seal_bid` encrypts 1 at every level the bid reaches and 0 above it. The
+// auction guest adds the sealed bids under encryption and commits the demand
+// at every level, so the trustees only ever decrypt how many bidders would pay
+// each price, never a bid.
+//
+// `clear_auction` turns the decrypted demand into the result: the winning
+// level is the highest with any demand and the second-price clearing price is
+// the highest level two bidders reached (the lowest level if only one did).
+// The winner is found by decrypting every bid's ciphertext at the winning
+// level (`level_ciphertexts`), which reveals only who reached the top price;
+// ties go to the earliest bid.
+
+use std::collections::HashSet;
+
+use risc0_zkvm::sha::{Impl, Sha256};
+use thiserror::Error;
+
+use crate::fhe_client::{max_sum_contributions, FheClient, FheClientError, PublicKey};
+use crate::types::{AuctionInput, AuctionOutput, SealedBid};
+
+// Domain separation tag (must match guest implementation)
+const BID_TAG: u8 = 0x0c;
+
+#[derive(Error, Debug)]
+pub enum AuctionError {
+    #[error("The auction needs at least one price level")]
+    NoPriceLevels,
+    #[error("Price levels must be strictly ascending")]
+    UnorderedPriceLevels,
+    #[error("The auction needs at least one bid")]
+    NoBids,
+    #[error("{bids} bids could exceed the plaintext modulus (at most {max} can be counted)")]
+    Overflow { bids: usize, max: u64 },
+    #[error("Bidder {bidder} bid twice")]
+    DuplicateBidder { bidder: String },
+    #[error("Bid {bid} has {levels} levels, expected {expected}")]
+    LevelCountMismatch { bid: usize, levels: usize, expected: usize },
+    #[error("Auction journal does not cover the given bids")]
+    BidsMismatch,
+    #[error("Decrypted demand at level {level} exceeds the demand below it; a bid was not a unary vector")]
+    MalformedDemand { level: usize },
+}
+
+/// Outcome of a second-price auction, from the decrypted demand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clearing {
+    pub winning_level: usize,
+    pub winning_price: u64, // Highest price level any bidder reached
+    pub clearing_price: u64, // What the winner pays
+}
+
+/// Seal `bid` for the auction with the given price ladder.
+pub fn seal_bid(client: &FheClient, bidder: &str, bid: u64, price_levels: &[u64]) -> Result<SealedBid, FheClientError> {
+    let levels: Vec<u64> = price_levels.iter().map(|&price| u64::from(bid >= price)).collect();
+    Ok(SealedBid { bidder: bidder.to_string(), encrypted_levels: client.encrypt_values(&levels)? })
+}
+
+/// Input for the auction guest, after the checks the guest will repeat.
+pub fn auction_input(public_key: PublicKey, price_levels: Vec<u64>, bids: Vec<SealedBid>) -> Result<AuctionInput, AuctionError> {
+    if price_levels.is_empty() {
+        return Err(AuctionError::NoPriceLevels);
+    }
+    if price_levels.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err(AuctionError::UnorderedPriceLevels);
+    }
+    if bids.is_empty() {
+        return Err(AuctionError::NoBids);
+    }
+    if bids.len() as u64 > max_sum_contributions(1) {
+        return Err(AuctionError::Overflow { bids: bids.len(), max: max_sum_contributions(1) });
+    }
+    let mut bidders = HashSet::new();
+    for (index, bid) in bids.iter().enumerate() {
+        if !bidders.insert(bid.bidder.as_str()) {
+            return Err(AuctionError::DuplicateBidder { bidder: bid.bidder.clone() });
+        }
+        if bid.encrypted_levels.len() != price_levels.len() {
+            return Err(AuctionError::LevelCountMismatch { bid: index, levels: bid.encrypted_levels.len(), expected: price_levels.len() });
+        }
+    }
+    Ok(AuctionInput { public_key, price_levels, bids })
+}
+
+/// Digest: H(tag || (len || bidder || (len || ciphertext) per level) for every bid, in order).
+pub fn bids_digest(bids: &[SealedBid]) -> [u8; 32] {
+    let mut buf = vec![BID_TAG];
+    for bid in bids {
+        buf.extend_from_slice(&(bid.bidder.len() as u32).to_le_bytes());
+        buf.extend_from_slice(bid.bidder.as_bytes());
+        for ciphertext in &bid.encrypted_levels {
+            buf.extend_from_slice(&(ciphertext.len() as u32).to_le_bytes());
+            buf.extend_from_slice(ciphertext);
+        }
+    }
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// Check that an auction journal covers exactly the bids of `input`.
+pub fn check_auction(output: &AuctionOutput, input: &AuctionInput) -> Result<(), AuctionError> {
+    if output.price_levels != input.price_levels
+        || output.bids as usize != input.bids.len()
+        || output.key_fingerprint != input.public_key.fingerprint()
+        || output.bids_digest != bids_digest(&input.bids)
+        || output.encrypted_demand_frames as usize != input.price_levels.len()
+    {
+        return Err(AuctionError::BidsMismatch);
+    }
+    Ok(())
+}
+
+/// Clear a second-price auction from the decrypted demand at every level.
+/// Returns `None` if no bid reached the lowest price level.
+pub fn clear_auction(price_levels: &[u64], demand: &[u64]) -> Result<Option<Clearing>, AuctionError> {
+    // Well-formed bids can only lose bidders as the price rises
+    if let Some(level) = (1..demand.len()).find(|&level| demand[level] > demand[level - 1]) {
+        return Err(AuctionError::MalformedDemand { level });
+    }
+    let Some(winning_level) = demand.iter().rposition(|&bidders| bidders > 0) else {
+        return Ok(None);
+    };
+    let second_level = demand.iter().rposition(|&bidders| bidders > 1).unwrap_or(0);
+    Ok(Some(Clearing {
+        winning_level,
+        winning_price: price_levels[winning_level],
+        clearing_price: price_levels[second_level],
+    }))
+}
+
+/// Every bid's ciphertext at `level`, in bid order; the bids decrypting to 1 reached it.
+pub fn level_ciphertexts(input: &AuctionInput, level: usize) -> Vec<(&str, &[u8])> {
+    input.bids.iter().map(|bid| (bid.bidder.as_str(), bid.encrypted_levels[level].as_slice())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auction_input_checks_and_journal_binding() -> Result<(), Box<dyn std::error::Error>> {
+        let client = FheClient::new();
+        let public_key = client.get_public_key().clone();
+        let levels = vec![100, 200, 300];
+        let bids = vec![seal_bid(&client, "alice", 250, &levels)?, seal_bid(&client, "bob", 120, &levels)?];
+        let input = auction_input(public_key.clone(), levels.clone(), bids.clone())?;
+
+        let output = AuctionOutput {
+            price_levels: levels.clone(),
+            bids: 2,
+            key_fingerprint: public_key.fingerprint(),
+            bids_digest: bids_digest(&bids),
+            encrypted_demand_frames: 3,
+        };
+        check_auction(&output, &input)?;
+        // Reordering the bids changes the digest
+        let reordered = auction_input(public_key.clone(), levels.clone(), vec![bids[1].clone(), bids[0].clone()])?;
+        assert!(matches!(check_auction(&output, &reordered), Err(AuctionError::BidsMismatch)));
+
+        assert!(matches!(
+            auction_input(public_key.clone(), levels.clone(), vec![bids[0].clone(), bids[0].clone()]),
+            Err(AuctionError::DuplicateBidder { .. })
+        ));
+        assert!(matches!(
+            auction_input(public_key.clone(), vec![100, 200], bids.clone()),
+            Err(AuctionError::LevelCountMismatch { bid: 0, levels: 3, expected: 2 })
+        ));
+        assert!(matches!(auction_input(public_key.clone(), vec![200, 100, 300], bids), Err(AuctionError::UnorderedPriceLevels)));
+        assert!(matches!(auction_input(public_key, levels, Vec::new()), Err(AuctionError::NoBids)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_second_price_clearing() -> Result<(), AuctionError> {
+        let levels = [100, 200, 300, 400];
+        // Bids of 350, 220 and 150: the winner pays the second-highest level reached
+        assert_eq!(
+            clear_auction(&levels, &[3, 2, 1, 0])?,
+            Some(Clearing { winning_level: 2, winning_price: 300, clearing_price: 200 })
+        );
+        // A tie at the top clears at the top price
+        assert_eq!(clear_auction(&levels, &[2, 2, 2, 2])?.map(|clearing| clearing.clearing_price), Some(400));
+        // A lone bidder pays the lowest level
+        assert_eq!(clear_auction(&levels, &[1, 1, 0, 0])?.map(|clearing| clearing.clearing_price), Some(100));
+        assert_eq!(clear_auction(&levels, &[0, 0, 0, 0])?, None);
+        assert!(matches!(clear_auction(&levels, &[1, 2, 0, 0]), Err(AuctionError::MalformedDemand { level: 1 })));
+        Ok(())
+    }
+}
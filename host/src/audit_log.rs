@@ -0,0 +1,171 @@
+// Hash-chained, append-only log of ballot submissions.
+// Every submission the host receives is recorded as accepted or rejected, and
+// each record is chained to the previous head. The guest recomputes the head,
+// checks that the accepted records are exactly the ballots it tallies, and
+// commits the head to the journal, so the submission history can be audited
+// against the proof and any rewrite of it changes the head.
+
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+use crate::ballot_set::ballot_leaf_hash;
+use crate::types::{AuditRecord, Digest32, EncryptedVote};
+
+// Domain separation tag (must match guest implementation)
+const AUDIT_TAG: u8 = 0x03;
+
+/// Head of a log with no records.
+pub const EMPTY_AUDIT_LOG_HEAD: [u8; 32] = [0u8; 32];
+
+/// Reason a revoked ballot is logged with (must match guest implementation).
+pub const REVOKED_REASON: &str = "revoked by the voter";
+
+#[derive(Error, Debug)]
+pub enum AuditLogError {
+    #[error("Audit log head mismatch: records hash to {computed}, expected {expected}")]
+    HeadMismatch { computed: Digest32, expected: Digest32 },
+    #[error("Accepted records do not match the ballot set at position {position}")]
+    BallotMismatch { position: usize },
+}
+
+/// Chain step: head' = H(tag || head || index || ballot_hash || accepted || reason).
+pub fn chain_record(head: &[u8; 32], index: u64, record: &AuditRecord) -> [u8; 32] {
+    let mut buf = vec![AUDIT_TAG];
+    buf.extend_from_slice(head);
+    buf.extend_from_slice(&index.to_le_bytes());
+    buf.extend_from_slice(&record.ballot_hash);
+    buf.push(record.accepted as u8);
+    buf.extend_from_slice(&(record.reason.len() as u32).to_le_bytes());
+    buf.extend_from_slice(record.reason.as_bytes());
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditLog {
+    records: Vec<AuditRecord>,
+    head: [u8; 32],
+}
+
+impl AuditLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Continue a log from its records (e.g. restored from a snapshot).
+    pub fn from_records(records: Vec<AuditRecord>) -> Self {
+        let mut log = Self::new();
+        for record in records {
+            log.append(record);
+        }
+        log
+    }
+
+    pub fn record_accepted(&mut self, ballot: &EncryptedVote) -> [u8; 32] {
+        self.append(AuditRecord { ballot_hash: ballot_leaf_hash(ballot), accepted: true, reason: String::new() })
+    }
+
+    pub fn record_rejected(&mut self, ballot: &EncryptedVote, reason: impl Into<String>) -> [u8; 32] {
+        self.append(AuditRecord { ballot_hash: ballot_leaf_hash(ballot), accepted: false, reason: reason.into() })
+    }
+
+    /// Log that the counted ballot with `tracking_code` was revoked by its voter.
+    pub fn record_revoked(&mut self, tracking_code: [u8; 32]) -> [u8; 32] {
+        self.append(AuditRecord { ballot_hash: tracking_code, accepted: false, reason: REVOKED_REASON.to_string() })
+    }
+
+    fn append(&mut self, record: AuditRecord) -> [u8; 32] {
+        self.head = chain_record(&self.head, self.records.len() as u64, &record);
+        self.records.push(record);
+        self.head
+    }
+
+    pub fn head(&self) -> [u8; 32] {
+        self.head
+    }
+
+    pub fn records(&self) -> &[AuditRecord] {
+        &self.records
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+}
+
+/// Recompute the head from published records and check it against `expected_head`.
+pub fn verify_audit_log(records: &[AuditRecord], expected_head: &[u8; 32]) -> Result<(), AuditLogError> {
+    let computed = records
+        .iter()
+        .enumerate()
+        .fold(EMPTY_AUDIT_LOG_HEAD, |head, (index, record)| chain_record(&head, index as u64, record));
+    if &computed != expected_head {
+        return Err(AuditLogError::HeadMismatch {
+            computed: computed.into(),
+            expected: (*expected_head).into(),
+        });
+    }
+    Ok(())
+}
+
+/// Check that the accepted records, in order, are exactly `ballots`.
+pub fn verify_accepted_ballots(records: &[AuditRecord], ballots: &[EncryptedVote]) -> Result<(), AuditLogError> {
+    let mut accepted = records.iter().filter(|record| record.accepted);
+    for (position, ballot) in ballots.iter().enumerate() {
+        match accepted.next() {
+            Some(record) if record.ballot_hash == ballot_leaf_hash(ballot) => {}
+            _ => return Err(AuditLogError::BallotMismatch { position }),
+        }
+    }
+    if accepted.next().is_some() {
+        return Err(AuditLogError::BallotMismatch { position: ballots.len() });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BallotAuthorization, MembershipProof, VoteOption};
+
+    fn test_ballot(i: u8) -> EncryptedVote {
+        EncryptedVote {
+            voter_address: format!("voter-{}", i),
+            election_id: Digest32::ZERO,
+            nonce: [i; 32],
+            encrypted_vote_vector: vec![vec![i; 16]; 3],
+            signature: String::new(),
+            authorization: BallotAuthorization::Membership(MembershipProof {
+                nullifier: [i; 32],
+                challenges: Vec::new(),
+                responses: Vec::new(),
+            }),
+            sequence: 0,
+            actual_choice: VoteOption::Option1,
+        }
+    }
+
+    #[test]
+    fn test_audit_log_detects_rewritten_history() -> Result<(), AuditLogError> {
+        let ballots: Vec<_> = (0..3).map(test_ballot).collect();
+        let mut log = AuditLog::new();
+        log.record_accepted(&ballots[0]);
+        log.record_rejected(&ballots[1], "invalid credential");
+        log.record_accepted(&ballots[2]);
+        log.record_revoked(ballot_leaf_hash(&ballots[2]));
+
+        verify_audit_log(log.records(), &log.head())?;
+        verify_accepted_ballots(log.records(), &[test_ballot(0), test_ballot(2)])?;
+
+        // Quietly turning a rejection into an acceptance changes the head
+        let mut rewritten = log.records().to_vec();
+        rewritten[1].accepted = true;
+        assert!(verify_audit_log(&rewritten, &log.head()).is_err());
+        assert!(verify_accepted_ballots(log.records(), &ballots).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,320 @@
+// Merkle commitment over the submitted encrypted ballots.
+// The host builds the tree, passes the root to the guest (which recomputes it and
+// commits it to the journal), and hands out inclusion proofs so any voter or
+// auditor can check an individual ballot against the proven set.
+
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+use crate::types::{BallotAuthorization, Digest32, EncryptedVote};
+
+// Domain separation tags (must match guest implementation)
+const LEAF_TAG: u8 = 0x00;
+const NODE_TAG: u8 = 0x01;
+const ELECTION_DOMAIN_TAG: u8 = 0x18;
+const REVOCATION_TAG: u8 = 0x19;
+const VOTER_KEYS_TAG: u8 = 0x1b;
+
+/// Root committed for an election with no ballots.
+pub const EMPTY_BALLOT_SET_ROOT: [u8; 32] = [0u8; 32];
+
+#[derive(Error, Debug)]
+pub enum BallotSetError {
+    #[error("Ballot index {index} out of range for a set of {len} ballots")]
+    IndexOutOfRange { index: usize, len: usize },
+}
+
+/// Hash of a single ballot as it appears in the tree.
+///
+/// Only the fields the guest actually receives are committed; `actual_choice`
+/// is demo-only verification data and never part of the ballot set.
+pub fn ballot_leaf_hash(ballot: &EncryptedVote) -> [u8; 32] {
+    let mut buf = vec![LEAF_TAG];
+    push_field(&mut buf, ballot.voter_address.as_bytes());
+    buf.extend_from_slice(&election_domain(&ballot.election_id));
+    buf.extend_from_slice(&ballot.nonce);
+    buf.extend_from_slice(&(ballot.encrypted_vote_vector.len() as u32).to_le_bytes());
+    for ciphertext in &ballot.encrypted_vote_vector {
+        push_field(&mut buf, ciphertext);
+    }
+    push_field(&mut buf, ballot.signature.as_bytes());
+    match &ballot.authorization {
+        BallotAuthorization::Credential { credential, ballot_signature } => {
+            buf.push(0);
+            buf.extend_from_slice(&credential.token);
+            buf.extend_from_slice(&credential.signature.commitment);
+            buf.extend_from_slice(&credential.signature.response);
+            buf.extend_from_slice(&ballot_signature.commitment);
+            buf.extend_from_slice(&ballot_signature.response);
+        }
+        BallotAuthorization::Membership(proof) => {
+            buf.push(1);
+            buf.extend_from_slice(&proof.nullifier);
+            buf.extend_from_slice(&(proof.challenges.len() as u32).to_le_bytes());
+            for (challenge, response) in proof.challenges.iter().zip(&proof.responses) {
+                buf.extend_from_slice(challenge);
+                buf.extend_from_slice(response);
+            }
+        }
+    }
+    buf.extend_from_slice(&ballot.sequence.to_le_bytes());
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// Per-election domain every ballot artifact is derived under: the ballot
+/// message (and so every ballot signature and membership proof), the nullifier
+/// base and the ballot's leaf hash, its tracking code. None of them means
+/// anything in another election.
+pub fn election_domain(election_id: &Digest32) -> [u8; 32] {
+    let mut buf = vec![ELECTION_DOMAIN_TAG];
+    buf.extend_from_slice(election_id.as_bytes());
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// Digest of a ballot's content that its authorization is bound to: the
+/// election's domain and the voter's nonce, so the ballot cannot be replayed elsewhere,
+/// and the voter address, so it cannot be replayed under another voter's weight.
+pub fn ballot_message(election_id: &Digest32, voter_address: &str, nonce: &[u8; 32], encrypted_vote_vector: &[Vec<u8>], sequence: u64) -> [u8; 32] {
+    let mut buf = election_domain(election_id).to_vec();
+    push_field(&mut buf, voter_address.as_bytes());
+    buf.extend_from_slice(nonce);
+    for ciphertext in encrypted_vote_vector {
+        push_field(&mut buf, ciphertext);
+    }
+    buf.extend_from_slice(&sequence.to_le_bytes());
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// What a revocation of the ballot with `tracking_code` is authorized over: a
+/// message no ballot can have, in this election's domain.
+pub fn revocation_message(election_id: &Digest32, tracking_code: &[u8; 32]) -> [u8; 32] {
+    let mut buf = vec![REVOCATION_TAG];
+    buf.extend_from_slice(&election_domain(election_id));
+    buf.extend_from_slice(tracking_code);
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// Key every ballot of one voter shares: the credential's token or the
+/// membership proof's nullifier.
+pub fn voter_key(authorization: &BallotAuthorization) -> [u8; 32] {
+    match authorization {
+        BallotAuthorization::Credential { credential, .. } => credential.token,
+        BallotAuthorization::Membership(proof) => proof.nullifier,
+    }
+}
+
+/// Distinct voter keys of a ballot set, sorted.
+pub fn voter_keys(ballots: &[EncryptedVote]) -> Vec<[u8; 32]> {
+    let mut keys: Vec<_> = ballots.iter().map(|ballot| voter_key(&ballot.authorization)).collect();
+    keys.sort_unstable();
+    keys.dedup();
+    keys
+}
+
+/// Digest of a sorted voter key set: H(tag || count || keys).
+pub fn voter_keys_digest(keys: &[[u8; 32]]) -> [u8; 32] {
+    let mut buf = vec![VOTER_KEYS_TAG];
+    buf.extend_from_slice(&(keys.len() as u32).to_le_bytes());
+    for key in keys {
+        buf.extend_from_slice(key);
+    }
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+fn push_field(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    buf.extend_from_slice(bytes);
+}
+
+/// Root of a tree over arbitrary pre-hashed leaves, built like the ballot set.
+pub fn merkle_root(leaves: Vec<[u8; 32]>) -> [u8; 32] {
+    BallotSetCommitment::from_leaves(leaves).root()
+}
+
+fn hash_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut buf = Vec::with_capacity(65);
+    buf.push(NODE_TAG);
+    buf.extend_from_slice(left);
+    buf.extend_from_slice(right);
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// Incrementally collects ballot leaves before the tree is built.
+#[derive(Default)]
+pub struct BallotSetBuilder {
+    leaves: Vec<[u8; 32]>,
+}
+
+impl BallotSetBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_ballot(mut self, ballot: &EncryptedVote) -> Self {
+        self.push(ballot);
+        self
+    }
+
+    pub fn push(&mut self, ballot: &EncryptedVote) {
+        self.leaves.push(ballot_leaf_hash(ballot));
+    }
+
+    pub fn build(self) -> BallotSetCommitment {
+        BallotSetCommitment::from_leaves(self.leaves)
+    }
+}
+
+/// Merkle tree over the ballot set, in submission order.
+///
+/// An odd node at the end of a level is carried up unchanged rather than
+/// duplicated, so two different ballot sets can never share a root.
+pub struct BallotSetCommitment {
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl BallotSetCommitment {
+    pub fn from_ballots(ballots: &[EncryptedVote]) -> Self {
+        Self::from_leaves(ballots.iter().map(ballot_leaf_hash).collect())
+    }
+
+    /// Tree over pre-hashed leaves, in order.
+    pub fn from_leaves(leaves: Vec<[u8; 32]>) -> Self {
+        let mut levels = vec![leaves];
+        while levels.last().is_some_and(|level| level.len() > 1) {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_nodes(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        BallotSetCommitment { levels }
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.levels
+            .last()
+            .and_then(|level| level.first().copied())
+            .unwrap_or(EMPTY_BALLOT_SET_ROOT)
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn inclusion_proof(&self, index: usize) -> Result<InclusionProof, BallotSetError> {
+        if index >= self.len() {
+            return Err(BallotSetError::IndexOutOfRange { index, len: self.len() });
+        }
+
+        let mut path = Vec::new();
+        let mut position = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = position ^ 1;
+            if sibling < level.len() {
+                path.push(ProofNode {
+                    hash: level[sibling],
+                    sibling_on_left: sibling < position,
+                });
+            }
+            position /= 2;
+        }
+
+        Ok(InclusionProof {
+            leaf_index: index,
+            leaf_count: self.len(),
+            path,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofNode {
+    pub hash: [u8; 32],
+    pub sibling_on_left: bool,
+}
+
+/// Proof that one ballot is a member of a committed ballot set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub leaf_index: usize,
+    pub leaf_count: usize,
+    pub path: Vec<ProofNode>,
+}
+
+impl InclusionProof {
+    pub fn verify(&self, ballot: &EncryptedVote, root: &[u8; 32]) -> bool {
+        self.verify_leaf(ballot_leaf_hash(ballot), root)
+    }
+
+    pub fn verify_leaf(&self, leaf: [u8; 32], root: &[u8; 32]) -> bool {
+        let computed = self.path.iter().fold(leaf, |acc, node| {
+            if node.sibling_on_left {
+                hash_nodes(&node.hash, &acc)
+            } else {
+                hash_nodes(&acc, &node.hash)
+            }
+        });
+        &computed == root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{SchnorrSignature, VoteOption, VoterCredential};
+
+    fn test_ballot(i: usize) -> EncryptedVote {
+        EncryptedVote {
+            voter_address: format!("0x{:040x}", i),
+            election_id: Digest32::ZERO,
+            nonce: [i as u8; 32],
+            encrypted_vote_vector: vec![vec![i as u8; 16]; 3],
+            signature: format!("sig{}", i),
+            authorization: BallotAuthorization::Credential {
+                credential: VoterCredential {
+                    token: [i as u8; 32],
+                    signature: SchnorrSignature { commitment: [0u8; 32], response: [0u8; 32] },
+                },
+                ballot_signature: SchnorrSignature { commitment: [1u8; 32], response: [1u8; 32] },
+            },
+            sequence: 0,
+            actual_choice: VoteOption::Option1,
+        }
+    }
+
+    #[test]
+    fn test_inclusion_proofs_for_odd_sized_sets() -> Result<(), BallotSetError> {
+        for count in 1..=9 {
+            let ballots: Vec<_> = (0..count).map(test_ballot).collect();
+            let commitment = BallotSetCommitment::from_ballots(&ballots);
+            let root = commitment.root();
+            for (i, ballot) in ballots.iter().enumerate() {
+                assert!(commitment.inclusion_proof(i)?.verify(ballot, &root));
+            }
+            assert!(!commitment.inclusion_proof(0)?.verify(&test_ballot(count + 1), &root));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_builder_matches_from_ballots() {
+        let ballots: Vec<_> = (0..5).map(test_ballot).collect();
+        let built = ballots
+            .iter()
+            .fold(BallotSetBuilder::new(), |builder, ballot| builder.with_ballot(ballot))
+            .build();
+        assert_eq!(built.root(), BallotSetCommitment::from_ballots(&ballots).root());
+        assert_eq!(BallotSetBuilder::new().build().root(), EMPTY_BALLOT_SET_ROOT);
+    }
+}
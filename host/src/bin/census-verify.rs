@@ -0,0 +1,32 @@
+// Rebuild the registry root from a census exported with FHE_VOTING_CENSUS.
+// Usage: census-verify <census.json> [published root]
+// For anonymous elections the published root is the journal's identity root.
+
+use host::census::{Census, RegistrantKind};
+use host::types::Digest32;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let Some(path) = args.next() else {
+        return Err("usage: census-verify <census.json> [root]".into());
+    };
+    let expected_root = args
+        .next()
+        .map(|root| Digest32::from_hex(&root).ok_or("root must be 64 hex digits"))
+        .transpose()?;
+    
+    println!("📇 Verifying census {}", path);
+    let census = Census::read(&path)?;
+    let registration = census.import()?;
+    let kind = match census.kind {
+        RegistrantKind::Address => "addresses",
+        RegistrantKind::Identity => "identity commitments",
+    };
+    println!("✅ {} {} rebuild the stated root {}", registration.len(), kind, hex::encode(census.root));
+    match expected_root {
+        Some(root) if root == census.root => println!("✅ Root matches the published root"),
+        Some(root) => return Err(format!("census root {} differs from the published root {}", hex::encode(census.root), root).into()),
+        None => println!("⚠️  No published root given; compare the root above with the one in the journal"),
+    }
+    Ok(())
+}
@@ -0,0 +1,42 @@
+// Re-verify an election archive exported with FHE_VOTING_ARCHIVE.
+// Usage: import-and-verify <archive directory or .tar.gz> <election.json> [published manifest hash]
+// where election.json is the published election context the host printed.
+
+use methods::FHE_VOTING_ID;
+use risc0_zkvm::VerifierContext;
+
+use host::archive::import_and_verify;
+use host::types::{Digest32, ElectionContext};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let (Some(path), Some(election_path)) = (args.next(), args.next()) else {
+        return Err("usage: import-and-verify <archive> <election.json> [manifest-hash]".into());
+    };
+    let election: ElectionContext = serde_json::from_slice(&std::fs::read(&election_path)?)?;
+    let expected_manifest_hash = args
+        .next()
+        .map(|hash| Digest32::from_hex(&hash).map(Into::into).ok_or("manifest hash must be 64 hex digits"))
+        .transpose()?;
+    
+    println!("🗄️  Verifying election archive {}", path);
+    let verified = import_and_verify(&path, &VerifierContext::default(), &election, expected_manifest_hash)?;
+    let output = &verified.journal.output;
+    
+    println!("✅ Manifest hash: {}", hex::encode(verified.manifest_hash));
+    println!("✅ Tally is for election {} on chain {}", election.election_id, election.chain_id);
+    println!("✅ Receipt verified for image id {}", hex::encode(verified.archive.parameters.image_id));
+    let current: [u8; 32] = risc0_zkvm::sha::Digest::from(FHE_VOTING_ID).into();
+    if verified.archive.parameters.image_id == current {
+        println!("   (matches this build's guest)");
+    } else {
+        println!("⚠️  Image id differs from this build's guest {}; check it against the published one", hex::encode(current));
+    }
+    println!("✅ {} ballot commitments match root {}", verified.archive.ballot_commitments.len(), hex::encode(output.ballot_set_root));
+    println!("✅ {} decryption proofs verified (threshold {})", verified.archive.decryption.proofs.len(), verified.archive.decryption.threshold);
+    println!("📊 Option 1: {} votes", output.option1_count);
+    println!("📊 Option 2: {} votes", output.option2_count);
+    println!("📊 Option 3: {} votes", output.option3_count);
+    println!("📈 Total votes: {}", output.total_votes);
+    Ok(())
+}
@@ -0,0 +1,68 @@
+// Decode a receipt and report why it does or does not verify.
+// Usage: inspect <receipt.bin> [detached frames] [--json]
+// Prints the claimed image id and the guest it belongs to, the exit code, the
+// seal type and sizes, the verification status and the decoded journal fields,
+// or all of it as JSON with --json. Receipts are read in the bincode encoding
+// archives and snapshots store; detached frames (the tally guest's stdout) are
+// only needed to read a compact journal's frames. RISC0_DEV_MODE=1 lets fake
+// receipts verify.
+
+use methods::{AGGREGATE_ID, AUCTION_ID, CHALLENGE_ID, CIRCUIT_ID, FHE_VOTING_ID, VECTOR_SUM_ID};
+use risc0_zkvm::{Receipt, VerifierContext};
+
+use host::inspect::{inspect_receipt, KnownGuest};
+
+const USAGE: &str = "usage: inspect <receipt.bin> [detached frames] [--json]";
+
+const GUESTS: [KnownGuest; 6] = [
+    KnownGuest { name: "tally", image_id: FHE_VOTING_ID },
+    KnownGuest { name: "aggregate", image_id: AGGREGATE_ID },
+    KnownGuest { name: "vector-sum", image_id: VECTOR_SUM_ID },
+    KnownGuest { name: "auction", image_id: AUCTION_ID },
+    KnownGuest { name: "circuit", image_id: CIRCUIT_ID },
+    KnownGuest { name: "challenge", image_id: CHALLENGE_ID },
+];
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let json = args.iter().any(|arg| arg == "--json");
+    let paths: Vec<&String> = args.iter().filter(|arg| *arg != "--json").collect();
+    let (receipt_path, frames_path) = match paths.as_slice() {
+        [receipt] => (receipt, None),
+        [receipt, frames] => (receipt, Some(frames)),
+        _ => return Err(USAGE.into()),
+    };
+    let receipt: Receipt = bincode::deserialize(&std::fs::read(receipt_path)?)?;
+    let detached_frames = frames_path.map(std::fs::read).transpose()?.unwrap_or_default();
+
+    let inspection = inspect_receipt(&VerifierContext::default(), &receipt, &GUESTS, &detached_frames);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&inspection)?);
+        return Ok(());
+    }
+    println!("🔎 Receipt {}", receipt_path);
+    match (inspection.image_id, inspection.guest) {
+        (Some(image_id), Some(guest)) => println!("🖼️  Image id: {} ({} guest)", image_id, guest),
+        (Some(image_id), None) => println!("🖼️  Image id: {} (not a guest of this build)", image_id),
+        (None, _) => println!("🖼️  Image id: unknown (claim is pruned)"),
+    }
+    if let Some(exit_code) = &inspection.exit_code {
+        println!("🚪 Exit code: {}", exit_code);
+    }
+    println!("🔏 Seal: {} ({} bytes)", inspection.seal_type, inspection.seal_bytes);
+    println!("📜 Journal: {} bytes, digest {}", inspection.journal_bytes, inspection.journal_digest);
+    match &inspection.verification_error {
+        None => println!("✅ Receipt verifies"),
+        Some(reason) => println!("❌ Receipt does not verify: {}", reason),
+    }
+    if let Some(fields) = inspection.journal.as_ref().and_then(|journal| journal.as_object()) {
+        println!("📋 Journal fields:");
+        for (name, value) in fields {
+            println!("   {}: {}", name, value);
+        }
+    }
+    if let Some(reason) = &inspection.journal_error {
+        println!("❌ Journal does not decode: {}", reason);
+    }
+    Ok(())
+}
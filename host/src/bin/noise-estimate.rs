@@ -0,0 +1,36 @@
+// Estimate worst-case FHE noise before spending hours proving.
+// Usage: noise-estimate <ballots> [mix rounds]
+// FHE_VOTING_CIRCUIT=<circuit.json> also estimates that circuit's outputs and
+// FHE_VOTING_PARAMETERS=<parameters.json> replaces the default FheParameters.
+// Exits with an error if anything might not decrypt.
+
+use host::circuit::CircuitProgram;
+use host::fhe_client::FheParameters;
+use host::noise::NoiseModel;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let Some(ballots) = args.next() else {
+        return Err("usage: noise-estimate <ballots> [mix rounds]".into());
+    };
+    let ballots: u64 = ballots.parse()?;
+    let mix_rounds: u32 = args.next().map(|v| v.parse()).transpose()?.unwrap_or(0);
+    let parameters: FheParameters = match std::env::var("FHE_VOTING_PARAMETERS") {
+        Ok(path) => serde_json::from_slice(&std::fs::read(path)?)?,
+        Err(_) => FheParameters::default(),
+    };
+    
+    let model = NoiseModel::new(&parameters)?;
+    println!("🔊 Noise {:?}, secret Hamming weight {}", parameters.noise, parameters.secret_hamming_weight);
+    println!("📏 At most {} ballots per tally after {} mix rounds", model.max_ballots(mix_rounds), mix_rounds);
+    let tally = model.tally(ballots, mix_rounds)?;
+    println!("✅ Tally of {} ballots: worst-case noise {} below {} ({:.1} bits to spare)",
+             ballots, tally.worst_case, tally.limit, tally.budget_bits());
+    if let Ok(path) = std::env::var("FHE_VOTING_CIRCUIT") {
+        let program: CircuitProgram = serde_json::from_slice(&std::fs::read(&path)?)?;
+        for (name, bound) in model.circuit(&program)? {
+            println!("✅ Circuit output {}: worst-case noise {} ({:.1} bits to spare)", name, bound.worst_case, bound.budget_bits());
+        }
+    }
+    Ok(())
+}
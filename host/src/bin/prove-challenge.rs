@@ -0,0 +1,39 @@
+// Prove a challenge from the external challenger (challenger.rs at the repo root).
+// Usage: prove-challenge <challenge.json> <receipt.bin> [stats.json]
+// challenge.json is the prover's part of the challenge: the challenger's public
+// key, the encrypted challenges and the circuit, without the plaintexts. The
+// receipt is written in bincode, as archives store it, and what proving cost
+// to stats.json; the challenger verifies the receipt against the published
+// challenge image id before it decrypts anything. The FHE_VOTING_* prover
+// settings apply as for the tally.
+
+use methods::{CHALLENGE_ELF, CHALLENGE_ID};
+use risc0_zkvm::{ExecutorEnv, ProverOpts};
+
+use host::executor::{ExecutorConfig, ProvedReceipt};
+use host::journal::read_challenge_journal;
+use host::types::ChallengeInput;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let (Some(challenge_path), Some(receipt_path)) = (args.next(), args.next()) else {
+        return Err("usage: prove-challenge <challenge.json> <receipt.bin> [stats.json]".into());
+    };
+    let stats_path = args.next();
+    let input: ChallengeInput = serde_json::from_slice(&std::fs::read(&challenge_path)?)?;
+
+    println!("🎯 Proving challenge {} ({} ciphertexts, {} circuit ops)", input.test_metadata.test_id, input.challenge_ciphertexts.len(), input.circuit.ops.len());
+    let executor = ExecutorConfig::from_env()?;
+    let env = executor.apply(&mut ExecutorEnv::builder())?.write(&input)?.build()?;
+    let ProvedReceipt { receipt, stats } = executor.prove(env, CHALLENGE_ELF, &ProverOpts::default())?;
+    receipt.verify(CHALLENGE_ID)?;
+    let output = read_challenge_journal(&receipt.journal.bytes)?;
+
+    std::fs::write(&receipt_path, bincode::serialize(&receipt)?)?;
+    if let Some(path) = stats_path {
+        std::fs::write(path, serde_json::to_vec_pretty(&stats)?)?;
+    }
+    println!("✅ Receipt written to {}: {} results, {} rejected", receipt_path, output.result_ciphertexts.len(), output.rejection_report.len());
+    println!("   {} cycles in {} segments, {:.1}s", stats.total_cycles, stats.segments, stats.proving_ms as f64 / 1000.0);
+    Ok(())
+}
@@ -0,0 +1,36 @@
+// Open a result sealed with FHE_VOTING_REVEAL_AT by solving its time-lock.
+// Usage: timelock-open <sealed-result.json>
+// Does the lock's sequential squarings (as long as the sealer calibrated it
+// to take, on hardware as fast), then verifies the unsealed receipt against
+// the tally guest and prints the result it proves. RISC0_DEV_MODE=1 lets fake
+// receipts verify.
+
+use std::time::Instant;
+
+use methods::FHE_VOTING_ID;
+use risc0_zkvm::sha::{Impl, Sha256};
+use risc0_zkvm::Receipt;
+
+use host::timelock::{SealedTally, TimeLock};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let Some(path) = std::env::args().nth(1) else {
+        return Err("usage: timelock-open <sealed-result.json>".into());
+    };
+    
+    let lock = TimeLock::from_json(&std::fs::read(&path)?)?;
+    println!("⏳ Solving {} squarings (sealed to open at {})...", lock.squarings, lock.reveal_at);
+    let started = Instant::now();
+    let sealed = SealedTally::from_bytes(&lock.open()?)?;
+    println!("🔓 Opened in {:.1}s", started.elapsed().as_secs_f64());
+    
+    // The sealed result is only as good as the receipt sealed with it
+    let receipt: Receipt = bincode::deserialize(&sealed.receipt)?;
+    receipt.verify(FHE_VOTING_ID)?;
+    if <[u8; 32]>::from(*Impl::hash_bytes(&receipt.journal.bytes)) != sealed.result.journal_digest {
+        return Err("sealed result does not describe the sealed receipt's journal".into());
+    }
+    println!("✅ Receipt verified against the tally guest");
+    println!("{}", String::from_utf8(sealed.result.to_json()?)?);
+    Ok(())
+}
@@ -0,0 +1,184 @@
+// Encrypt and authorize a ballot on the voter's own machine.
+// Usage: vote keygen <identity file>
+//        vote register <identity file> <election id>
+//        vote cast <identity file> <election-key.json> <census.json> <election id> <choice 1-3> <ballot.json> [sequence]
+//        vote qr <ballot.json> <frame dir>
+//        vote scan <frames.txt> <ballot.json>
+//        vote audit <ballot.json> <opening.json> <election-key.json>
+// keygen saves a new 24-word mnemonic (write it down: it recovers every key),
+// and register prints the identity commitment it derives for one election.
+// cast encrypts the choice under the published election key (FHE_VOTING_PUBLISH_KEY),
+// proves membership of the census (FHE_VOTING_CENSUS) and writes the ballot JSON
+// to submit. The tracking code it prints is the ballot's leaf in the ballot set,
+// so the voter can check their ballot was counted against the committed root.
+// With FHE_VOTING_SIGNER set (e.g. "cast wallet sign --ledger") the submission
+// signature is made on the voter's hardware wallet, never on this machine;
+// FHE_VOTING_SIGNER=mnemonic signs with the mnemonic's own account key.
+// qr writes the ballot as numbered QR codes for an air-gapped hand-off, and scan
+// reassembles it at the collection point from the scanned texts, one per line.
+// With FHE_VOTING_DERANDOMIZED=1, cast derives the ballot's nonce and noise from
+// the mnemonic and writes an opening next to the ballot; audit checks a ballot
+// against its opening (see host/src/honest_encryption.rs).
+
+use host::address::{normalize_ballot, pseudonymous_address, to_checksum_address};
+use host::ballot_set::{ballot_leaf_hash, ballot_message};
+use host::census::{Census, RegistrantKind};
+use host::fhe_client::{FheClient, PublicKey};
+use host::hd_keys::VoterSeed;
+use host::honest_encryption::{encrypt_honest, verify_opening, EncryptionOpening};
+use host::membership::{prove_membership, Identity};
+use host::qr::{ballot_frames, decode_ballot, frame_svg, DEFAULT_FRAME_BYTES};
+use host::types::{BallotAuthorization, Digest32, EncryptedVote, VoteOption};
+use host::wallet::{ballot_signer, sign_ballot, ExternalSigner, WalletError};
+
+const USAGE: &str = "usage: vote keygen <identity file> | vote register <identity file> <election id> | vote cast <identity file> <election-key.json> <census.json> <election id> <choice 1-3> <ballot.json> [sequence] | vote qr <ballot.json> <frame dir> | vote scan <frames.txt> <ballot.json> | vote audit <ballot.json> <opening.json> <election-key.json>";
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["keygen", identity_path] => keygen(identity_path),
+        ["register", identity_path, election_id] => register(identity_path, election_id),
+        ["cast", identity_path, key_path, census_path, election_id, choice, ballot_path, rest @ ..] if rest.len() <= 1 => {
+            let sequence = rest.first().map(|sequence| sequence.parse()).transpose()?.unwrap_or(0);
+            cast(identity_path, key_path, census_path, election_id, choice, ballot_path, sequence)
+        }
+        ["qr", ballot_path, frame_dir] => qr(ballot_path, frame_dir),
+        ["scan", frames_path, ballot_path] => scan(frames_path, ballot_path),
+        ["audit", ballot_path, opening_path, key_path] => audit(ballot_path, opening_path, key_path),
+        _ => Err(USAGE.into()),
+    }
+}
+
+fn keygen(identity_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if std::path::Path::new(identity_path).exists() {
+        return Err(format!("{} already exists; refusing to overwrite an identity", identity_path).into());
+    }
+    let (phrase, seed) = VoterSeed::generate(&std::env::var("FHE_VOTING_MNEMONIC_PASSPHRASE").unwrap_or_default());
+    std::fs::write(identity_path, &phrase)?;
+    println!("🔑 Mnemonic saved to {} (keep it secret; write the words down to recover your keys)", identity_path);
+    println!("🔑 Account {}", to_checksum_address(&seed.address(0)?));
+    println!("📇 Run `vote register {} <election id>` for the commitment to register in each election", identity_path);
+    Ok(())
+}
+
+fn register(identity_path: &str, election_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let election_id = Digest32::from_hex(election_id).ok_or("election id must be 64 hex digits")?;
+    let identity = load_identity(identity_path, &election_id)?;
+    println!("📇 Register this commitment for election {}: {}", election_id, hex::encode(identity.commitment()));
+    Ok(())
+}
+
+// A mnemonic, or a bare 64-hex identity secret
+fn load_identity(identity_path: &str, election_id: &Digest32) -> Result<Identity, Box<dyn std::error::Error>> {
+    let contents = std::fs::read_to_string(identity_path)?;
+    if let Ok(secret) = <[u8; 32]>::try_from(hex::decode(contents.trim()).unwrap_or_default()) {
+        return Ok(Identity::from_bytes(secret).ok_or("identity file does not hold a valid secret")?);
+    }
+    Ok(VoterSeed::from_mnemonic_env(&contents)?.identity(election_id)?)
+}
+
+fn cast(
+    identity_path: &str,
+    key_path: &str,
+    census_path: &str,
+    election_id: &str,
+    choice: &str,
+    ballot_path: &str,
+    sequence: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let election_id = Digest32::from_hex(election_id).ok_or("election id must be 64 hex digits")?;
+    let identity = load_identity(identity_path, &election_id)?;
+    let choice = match choice {
+        "1" => VoteOption::Option1,
+        "2" => VoteOption::Option2,
+        "3" => VoteOption::Option3,
+        _ => return Err("choice must be 1, 2 or 3".into()),
+    };
+
+    let public_key: PublicKey = serde_json::from_slice(&std::fs::read(key_path)?)?;
+    println!("🔑 Election key {}; compare it with the published fingerprint", hex::encode(public_key.fingerprint()));
+    let census = Census::read(census_path)?;
+    if census.kind != RegistrantKind::Identity {
+        return Err("census lists addresses; voting with an identity needs an identity census".into());
+    }
+    let roster = census.import()?.identity_roster();
+    println!("📇 Census of {} identities, root {}", roster.len(), hex::encode(census.root));
+
+    // Encrypted here, so the server never sees the choice
+    println!("🗳️ Encrypting choice {} under the election key...", choice as u8);
+    let client = FheClient::with_public_key(public_key);
+    let (nonce, encrypted_vote_vector) = if std::env::var("FHE_VOTING_DERANDOMIZED").is_ok_and(|v| v == "1") {
+        // Nonce and noise derived from the mnemonic, so the ballot can later be proven honest
+        let voter_secret = VoterSeed::from_mnemonic_env(&std::fs::read_to_string(identity_path)?)?.encryption_secret()?;
+        let honest = encrypt_honest(&client, &voter_secret, &election_id, sequence, choice)?;
+        let opening_path = format!("{}.opening.json", ballot_path);
+        std::fs::write(&opening_path, serde_json::to_vec_pretty(&honest.opening)?)?;
+        println!("🔏 Opening written to {}; it reveals your choice, so share it only to audit a spoiled ballot", opening_path);
+        (honest.nonce, honest.encrypted_vote_vector)
+    } else {
+        let encrypted_vote_vector = client.encrypt_vote_vectors(&[choice])?.pop().ok_or("encryption produced no ballot")?;
+        (rand::random(), encrypted_vote_vector)
+    };
+    let voter_address = pseudonymous_address(&identity.nullifier(&roster, &election_id));
+    let message = ballot_message(&election_id, &voter_address, &nonce, &encrypted_vote_vector, sequence);
+    let proof = prove_membership(&identity, &roster, &election_id, &message)?;
+
+    let mut ballot = EncryptedVote {
+        voter_address,
+        election_id,
+        nonce,
+        encrypted_vote_vector,
+        signature: String::new(), // Unlinkable: the membership proof alone authorizes the ballot
+        authorization: BallotAuthorization::Membership(proof),
+        sequence,
+        actual_choice: VoteOption::Option1, // Demo verification field; never filled with the real choice
+    };
+    if std::env::var("FHE_VOTING_SIGNER").is_ok_and(|signer| signer == "mnemonic") {
+        let key = VoterSeed::from_mnemonic_env(&std::fs::read_to_string(identity_path)?)?.signing_key(0)?;
+        println!("🔐 Submission signed by {}", to_checksum_address(&sign_ballot(&key, &mut ballot)?));
+    } else if let Some(signer) = ExternalSigner::from_env()? {
+        println!("🔐 Confirm the submission signature on your wallet...");
+        println!("🔐 Submission signed by {}", to_checksum_address(&sign_ballot(&signer, &mut ballot)?));
+    }
+    std::fs::write(ballot_path, serde_json::to_vec_pretty(&ballot)?)?;
+    println!("✅ Ballot written to {}", ballot_path);
+    println!("🧾 Tracking code: {}", hex::encode(ballot_leaf_hash(&ballot)));
+    Ok(())
+}
+
+fn audit(ballot_path: &str, opening_path: &str, key_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let ballot: EncryptedVote = serde_json::from_slice(&std::fs::read(ballot_path)?)?;
+    let opening: EncryptionOpening = serde_json::from_slice(&std::fs::read(opening_path)?)?;
+    let public_key: PublicKey = serde_json::from_slice(&std::fs::read(key_path)?)?;
+    verify_opening(&FheClient::with_public_key(public_key), &ballot, &opening)?;
+    println!("✅ Ballot {} is the honest encryption of choice {}", hex::encode(ballot_leaf_hash(&ballot)), opening.choice as u8);
+    Ok(())
+}
+
+fn qr(ballot_path: &str, frame_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let ballot: EncryptedVote = serde_json::from_slice(&std::fs::read(ballot_path)?)?;
+    let frames = ballot_frames(&ballot, DEFAULT_FRAME_BYTES)?;
+    std::fs::create_dir_all(frame_dir)?;
+    for (index, frame) in frames.iter().enumerate() {
+        std::fs::write(std::path::Path::new(frame_dir).join(format!("frame-{:03}.svg", index)), frame_svg(frame)?)?;
+    }
+    println!("📷 Ballot written as {} QR codes to {}; show them in order at the collection point", frames.len(), frame_dir);
+    println!("🧾 Tracking code: {}", hex::encode(ballot_leaf_hash(&ballot)));
+    Ok(())
+}
+
+fn scan(frames_path: &str, ballot_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let scanned = std::fs::read_to_string(frames_path)?;
+    let frames: Vec<&str> = scanned.lines().filter(|line| !line.trim().is_empty()).collect();
+    let mut ballot = decode_ballot(&frames)?;
+    normalize_ballot(&mut ballot)?;
+    std::fs::write(ballot_path, serde_json::to_vec_pretty(&ballot)?)?;
+    println!("✅ Ballot reassembled from {} scanned frames into {}", frames.len(), ballot_path);
+    match ballot_signer(&ballot) {
+        Ok(account) => println!("🔐 Submission signed by {}", to_checksum_address(&account)),
+        Err(WalletError::Unsigned) => println!("🔐 No wallet signature; the ballot is authorized anonymously"),
+        Err(e) => return Err(e.into()),
+    }
+    println!("🧾 Tracking code: {}", hex::encode(ballot_leaf_hash(&ballot)));
+    Ok(())
+}
@@ -0,0 +1,167 @@
+// Manifested file bundles.
+// A bundle is a set of named files written to a directory or a gzipped
+// tarball with the same layout. `manifest.json` lists every file with its
+// SHA-256 and is written last, so an interrupted write has no manifest and is
+// refused. Writing over an existing bundle directory removes its manifest
+// first, so an interrupted rewrite is refused too. Reading checks the layout version and every listed digest; files
+// the manifest does not list are ignored. Election snapshots and archives are
+// both bundles.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+use crate::types::Digest32;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(Error, Debug)]
+pub enum BundleError {
+    #[error("Bundle I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Bundle manifest is malformed: {reason}")]
+    Encoding { reason: String },
+    #[error("Unsupported bundle version {version}")]
+    UnsupportedVersion { version: u32 },
+    #[error("Bundle is missing {name}")]
+    MissingFile { name: String },
+    #[error("Bundle file {name} does not match its manifest digest")]
+    DigestMismatch { name: String },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    files: BTreeMap<String, Digest32>, // Name -> SHA-256
+}
+
+pub struct Bundle {
+    version: u32,
+    files: BTreeMap<String, Vec<u8>>,
+    manifest: Option<Vec<u8>>, // Manifest bytes as read, kept so its hash is stable
+}
+
+impl Bundle {
+    pub fn new(version: u32) -> Self {
+        Bundle { version, files: BTreeMap::new(), manifest: None }
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, bytes: Vec<u8>) {
+        self.manifest = None;
+        self.files.insert(name.into(), bytes);
+    }
+
+    pub fn take(&mut self, name: &str) -> Result<Vec<u8>, BundleError> {
+        self.files.remove(name).ok_or_else(|| BundleError::MissingFile { name: name.to_string() })
+    }
+
+    pub fn manifest(&self) -> Result<Vec<u8>, BundleError> {
+        if let Some(manifest) = &self.manifest {
+            return Ok(manifest.clone());
+        }
+        let files = self.files.iter().map(|(name, bytes)| (name.clone(), Digest32((*Impl::hash_bytes(bytes)).into()))).collect();
+        serde_json::to_vec_pretty(&Manifest { version: self.version, files })
+            .map_err(|e| BundleError::Encoding { reason: e.to_string() })
+    }
+
+    /// SHA-256 of the manifest, which commits to every file in the bundle.
+    pub fn manifest_hash(&self) -> Result<[u8; 32], BundleError> {
+        Ok((*Impl::hash_bytes(&self.manifest()?)).into())
+    }
+
+    // Check the manifest and every file it lists
+    fn from_files(version: u32, mut files: BTreeMap<String, Vec<u8>>) -> Result<Self, BundleError> {
+        let manifest_bytes = files.remove(MANIFEST_FILE).ok_or_else(|| BundleError::MissingFile { name: MANIFEST_FILE.to_string() })?;
+        let manifest: Manifest = serde_json::from_slice(&manifest_bytes).map_err(|e| BundleError::Encoding { reason: e.to_string() })?;
+        if manifest.version != version {
+            return Err(BundleError::UnsupportedVersion { version: manifest.version });
+        }
+        let mut listed = BTreeMap::new();
+        for (name, digest) in manifest.files {
+            let bytes = files.remove(&name).ok_or_else(|| BundleError::MissingFile { name: name.clone() })?;
+            if digest != Digest32((*Impl::hash_bytes(&bytes)).into()) {
+                return Err(BundleError::DigestMismatch { name });
+            }
+            listed.insert(name, bytes);
+        }
+        Ok(Bundle { version, files: listed, manifest: Some(manifest_bytes) })
+    }
+
+    pub fn write_dir(&self, dir: impl AsRef<Path>) -> Result<(), BundleError> {
+        let dir = dir.as_ref();
+        match fs::remove_file(dir.join(MANIFEST_FILE)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        for (name, bytes) in &self.files {
+            let path = dir.join(name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, bytes)?;
+        }
+        let staging = dir.join(format!("{}.tmp", MANIFEST_FILE));
+        fs::write(&staging, self.manifest()?)?;
+        fs::rename(staging, dir.join(MANIFEST_FILE))?;
+        Ok(())
+    }
+
+    pub fn read_dir(dir: impl AsRef<Path>, version: u32) -> Result<Self, BundleError> {
+        let dir = dir.as_ref();
+        let mut files = BTreeMap::new();
+        let manifest = fs::read(dir.join(MANIFEST_FILE)).map_err(|_| BundleError::MissingFile { name: MANIFEST_FILE.to_string() })?;
+        let listed: Manifest = serde_json::from_slice(&manifest).map_err(|e| BundleError::Encoding { reason: e.to_string() })?;
+        for name in listed.files.keys() {
+            let bytes = fs::read(dir.join(name)).map_err(|_| BundleError::MissingFile { name: name.clone() })?;
+            files.insert(name.clone(), bytes);
+        }
+        files.insert(MANIFEST_FILE.to_string(), manifest);
+        Self::from_files(version, files)
+    }
+
+    /// Write a gzipped tarball (manifest last).
+    pub fn write_tarball(&self, path: impl AsRef<Path>) -> Result<(), BundleError> {
+        let manifest = self.manifest()?;
+        let mut archive = tar::Builder::new(GzEncoder::new(File::create(path)?, Compression::default()));
+        for (name, bytes) in self.files.iter().chain([(&MANIFEST_FILE.to_string(), &manifest)]) {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(bytes.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            archive.append_data(&mut header, name, bytes.as_slice())?;
+        }
+        archive.into_inner()?.finish()?;
+        Ok(())
+    }
+
+    pub fn read_tarball(path: impl AsRef<Path>, version: u32) -> Result<Self, BundleError> {
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(path)?));
+        let mut files = BTreeMap::new();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let name = entry.path()?.to_string_lossy().into_owned();
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes)?;
+            files.insert(name, bytes);
+        }
+        Self::from_files(version, files)
+    }
+
+    /// Read a bundle from a directory, or from a tarball otherwise.
+    pub fn read(path: impl AsRef<Path>, version: u32) -> Result<Self, BundleError> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            Self::read_dir(path, version)
+        } else {
+            Self::read_tarball(path, version)
+        }
+    }
+}
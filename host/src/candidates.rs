@@ -0,0 +1,122 @@
+// Candidate list binding.
+// The guest counts options 1, 2 and 3; what those options said on the ballot
+// lives outside the proof, so without a binding a published result could be
+// relabeled after the fact. The tally input carries the candidate list, the
+// guest checks it against the `candidates_digest` in the election context and
+// commits that context, so every receipt names the exact ballot text its
+// counts belong to. Operators set:
+//
+//   FHE_VOTING_CANDIDATES  the three labels, separated by `|`; defaults to the
+//                          demo's option descriptions
+//
+// The digest is H(tag || (id || label length || label) per candidate), and is
+// zero for an empty list, which binds no ballot text.
+
+use risc0_zkvm::sha::{Impl, Sha256};
+use thiserror::Error;
+
+use crate::types::{Candidate, Digest32, VoteOption};
+
+// Domain separation tag (must match guest implementation)
+const CANDIDATES_TAG: u8 = 0x11;
+
+#[derive(Error, Debug)]
+pub enum CandidatesError {
+    #[error("Expected one candidate per option (3), got {count}")]
+    WrongCount { count: usize },
+    #[error("Candidate {position} has id {id}, expected {expected}")]
+    OutOfOrder { position: usize, id: u8, expected: u8 },
+    #[error("Candidate {id} has an empty label")]
+    EmptyLabel { id: u8 },
+    #[error("Candidate list hashes to {computed}, the election binds {expected}")]
+    DigestMismatch { computed: Digest32, expected: Digest32 },
+}
+
+/// Digest of the candidate list; zero for an empty list.
+pub fn candidates_digest(candidates: &[Candidate]) -> Digest32 {
+    if candidates.is_empty() {
+        return Digest32::ZERO;
+    }
+    let mut buf = vec![CANDIDATES_TAG];
+    for candidate in candidates {
+        buf.push(candidate.id);
+        buf.extend_from_slice(&(candidate.label.len() as u32).to_le_bytes());
+        buf.extend_from_slice(candidate.label.as_bytes());
+    }
+    Digest32((*Impl::hash_bytes(&buf)).into())
+}
+
+/// The demo's options with their descriptions as labels.
+pub fn default_candidates() -> Vec<Candidate> {
+    [VoteOption::Option1, VoteOption::Option2, VoteOption::Option3]
+        .iter()
+        .map(|option| Candidate { id: *option as u8, label: option.description().to_string() })
+        .collect()
+}
+
+/// Candidates from FHE_VOTING_CANDIDATES, or the defaults.
+pub fn candidates_from_env() -> Result<Vec<Candidate>, CandidatesError> {
+    candidates_from_lookup(|name| std::env::var(name).ok())
+}
+
+pub fn candidates_from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Vec<Candidate>, CandidatesError> {
+    let Some(labels) = lookup("FHE_VOTING_CANDIDATES") else {
+        return Ok(default_candidates());
+    };
+    let candidates: Vec<Candidate> =
+        labels.split('|').zip(1..).map(|(label, id)| Candidate { id, label: label.trim().to_string() }).collect();
+    check_candidates(&candidates)?;
+    Ok(candidates)
+}
+
+/// Check there is one labelled candidate per option, in option order.
+pub fn check_candidates(candidates: &[Candidate]) -> Result<(), CandidatesError> {
+    if candidates.len() != 3 {
+        return Err(CandidatesError::WrongCount { count: candidates.len() });
+    }
+    for (position, (candidate, expected)) in candidates.iter().zip(1u8..).enumerate() {
+        if candidate.id != expected {
+            return Err(CandidatesError::OutOfOrder { position, id: candidate.id, expected });
+        }
+        if candidate.label.is_empty() {
+            return Err(CandidatesError::EmptyLabel { id: candidate.id });
+        }
+    }
+    Ok(())
+}
+
+/// Check a published candidate list against the digest a tally committed.
+pub fn verify_candidates(candidates: &[Candidate], committed: &Digest32) -> Result<(), CandidatesError> {
+    let computed = candidates_digest(candidates);
+    if computed != *committed {
+        return Err(CandidatesError::DigestMismatch { computed, expected: *committed });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relabeled_candidates_change_the_digest() -> Result<(), CandidatesError> {
+        let candidates = default_candidates();
+        check_candidates(&candidates)?;
+        let digest = candidates_digest(&candidates);
+        assert_ne!(digest, Digest32::ZERO);
+        verify_candidates(&candidates, &digest)?;
+        assert_eq!(candidates_digest(&[]), Digest32::ZERO);
+
+        // Swapping two labels is a different ballot
+        let mut relabeled = candidates.clone();
+        relabeled.swap(0, 1);
+        (relabeled[0].id, relabeled[1].id) = (1, 2);
+        assert!(matches!(verify_candidates(&relabeled, &digest), Err(CandidatesError::DigestMismatch { .. })));
+
+        let configured = candidates_from_lookup(|_| Some("Yes | No | Abstain".into()))?;
+        assert_eq!(configured[2], Candidate { id: 3, label: "Abstain".into() });
+        assert!(matches!(candidates_from_lookup(|_| Some("Yes|No".into())), Err(CandidatesError::WrongCount { count: 2 })));
+        assert!(matches!(candidates_from_lookup(|_| Some("Yes||No".into())), Err(CandidatesError::EmptyLabel { id: 2 })));
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+// Census export: the registered-voter set as an interchange file.
+// After registration closes, the operator publishes the census so auditors can
+// rebuild the registry root independently and compare it with the published
+// root (for identity registries, the `identity_root` in the journal).
+//
+// The census is a UTF-8 JSON object:
+//
+//   {
+//     "format": "fhe-voting/census/v1",
+//     "kind": "address" | "identity",
+//     "root": "<64 hex digits>",
+//     "registrants": ["<hex>", ...]
+//   }
+//
+// `registrants` lists the registry leaves in tree order: 20-byte Ethereum
+// addresses or 32-byte compressed Ristretto identity commitments, lowercase
+// hex without a 0x prefix (a prefix is accepted on import). To rebuild the
+// root, hash each registrant into a leaf:
+//
+//   address leaf  = SHA-256(0x06 || address)
+//   identity leaf = SHA-256(0x02 || commitment)
+//
+// then combine each level's neighbours pairwise with SHA-256(0x01 || left ||
+// right), carrying an odd last node up unchanged, until one node is left.
+
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+use crate::registration::{Registrant, RegistrationError, RegistrationService};
+use crate::types::Digest32;
+
+/// Format tag written to every census.
+pub const CENSUS_FORMAT: &str = "fhe-voting/census/v1";
+
+#[derive(Error, Debug)]
+pub enum CensusError {
+    #[error("Census I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Census encoding failed: {reason}")]
+    Encoding { reason: String },
+    #[error("Unsupported census format {format}")]
+    UnsupportedFormat { format: String },
+    #[error("Registration must be closed before the census is exported")]
+    RegistrationOpen,
+    #[error("Census has no registrants")]
+    Empty,
+    #[error("Malformed registrant {position}: {value}")]
+    MalformedRegistrant { position: usize, value: String },
+    #[error("Census registrants rebuild root {rebuilt}, census states {stated}")]
+    RootMismatch { stated: Digest32, rebuilt: Digest32 },
+    #[error("Census registrant rejected: {0}")]
+    Registration(#[from] RegistrationError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RegistrantKind {
+    Address,
+    Identity,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Census {
+    pub format: String,
+    pub kind: RegistrantKind,
+    #[serde(with = "hex::serde")]
+    pub root: [u8; 32],
+    pub registrants: Vec<String>,
+}
+
+impl Census {
+    /// Export a closed registry.
+    pub fn export(registration: &RegistrationService) -> Result<Self, CensusError> {
+        let root = registration.root().ok_or(CensusError::RegistrationOpen)?;
+        let kind = match registration.registrants().first().ok_or(CensusError::Empty)? {
+            Registrant::Address(_) => RegistrantKind::Address,
+            Registrant::Identity(_) => RegistrantKind::Identity,
+        };
+        let registrants = registration
+            .registrants()
+            .iter()
+            .map(|registrant| match registrant {
+                Registrant::Address(address) => hex::encode(address),
+                Registrant::Identity(commitment) => hex::encode(commitment),
+            })
+            .collect();
+        Ok(Census { format: CENSUS_FORMAT.to_string(), kind, root, registrants })
+    }
+
+    /// Rebuild the registry from the listed registrants, checking it against the stated root.
+    pub fn import(&self) -> Result<RegistrationService, CensusError> {
+        if self.format != CENSUS_FORMAT {
+            return Err(CensusError::UnsupportedFormat { format: self.format.clone() });
+        }
+        let mut registration = RegistrationService::new();
+        for (position, value) in self.registrants.iter().enumerate() {
+            let malformed = || CensusError::MalformedRegistrant { position, value: value.clone() };
+            let bytes = hex::decode(value.trim_start_matches("0x")).map_err(|_| malformed())?;
+            let registrant = match self.kind {
+                RegistrantKind::Address => Registrant::Address(bytes.try_into().map_err(|_| malformed())?),
+                RegistrantKind::Identity => Registrant::Identity(bytes.try_into().map_err(|_| malformed())?),
+            };
+            registration.register(registrant)?;
+        }
+        if registration.is_empty() {
+            return Err(CensusError::Empty);
+        }
+        let rebuilt = registration.close()?;
+        if rebuilt != self.root {
+            return Err(CensusError::RootMismatch { stated: self.root.into(), rebuilt: rebuilt.into() });
+        }
+        Ok(registration)
+    }
+
+    pub fn to_json(&self) -> Result<Vec<u8>, CensusError> {
+        serde_json::to_vec_pretty(self).map_err(|e| CensusError::Encoding { reason: e.to_string() })
+    }
+
+    pub fn from_json(bytes: &[u8]) -> Result<Self, CensusError> {
+        serde_json::from_slice(bytes).map_err(|e| CensusError::Encoding { reason: e.to_string() })
+    }
+
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<(), CensusError> {
+        std::fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    pub fn read(path: impl AsRef<Path>) -> Result<Self, CensusError> {
+        Self::from_json(&std::fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::membership::Identity;
+
+    #[test]
+    fn test_census_round_trip_rebuilds_the_root() -> Result<(), CensusError> {
+        let mut registration = RegistrationService::new();
+        for _ in 0..4 {
+            registration.register(Registrant::Identity(Identity::generate().commitment()))?;
+        }
+        assert!(matches!(Census::export(&registration), Err(CensusError::RegistrationOpen)));
+        let root = registration.close()?;
+
+        let census = Census::from_json(&Census::export(&registration)?.to_json()?)?;
+        assert_eq!(census.kind, RegistrantKind::Identity);
+        let imported = census.import()?;
+        assert_eq!(imported.root(), Some(root));
+        assert_eq!(imported.identity_roster(), registration.identity_roster());
+
+        // Dropping or reordering a registrant changes the rebuilt root
+        let mut reordered = census.clone();
+        reordered.registrants.swap(0, 1);
+        assert!(matches!(reordered.import(), Err(CensusError::RootMismatch { .. })));
+        let mut truncated = census.clone();
+        truncated.registrants.pop();
+        assert!(matches!(truncated.import(), Err(CensusError::RootMismatch { .. })));
+
+        let mut addresses = RegistrationService::new();
+        addresses.register(Registrant::Address([7u8; 20]))?;
+        addresses.close()?;
+        let mut census = Census::export(&addresses)?;
+        census.registrants[0] = format!("0x{}", census.registrants[0]);
+        assert_eq!(census.import()?.root(), addresses.root());
+        census.registrants[0].push_str("00");
+        assert!(matches!(census.import(), Err(CensusError::MalformedRegistrant { position: 0, .. })));
+        Ok(())
+    }
+}
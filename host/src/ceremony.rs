@@ -0,0 +1,393 @@
+// Key ceremony transcript.
+// The trustees' DKG (`dkg`) leaves each trustee with a key share and everyone
+// with the election key, but nothing an outsider can check afterwards. A
+// `CeremonyRecorder` writes down every public step of the ceremony as it
+// happens:
+//
+//   Opened     the coordinator names the threshold and each trustee's Ed25519 key
+//   Committed  a trustee's round-1 commitments
+//   Revealed   a trustee's round-2 public key share (the Shamir shares travel
+//              privately and are never recorded)
+//   Finalized  a trustee's view of the result: DKG transcript hash and key
+//              fingerprint
+//   Closed     the coordinator publishes the election key fingerprint
+//
+// Each entry is hash-chained to the previous one, as in the security event
+// log, and signed by whoever took the step: the coordinator or the trustee.
+// `verify_transcript` checks the chain and signatures, every reveal against
+// its commitment, that the revealed shares add up to the closed key and that
+// every trustee finalized the same DKG transcript. `check_election_key` then
+// ties the transcript to the key the ballots were encrypted under, the key
+// whose fingerprint the default election id (and so the tally receipt) is
+// derived from.
+
+use std::collections::BTreeMap;
+
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+use crate::attestation::Operator;
+use crate::dkg::{self, combine_public_key_shares, public_key_commitment, DkgCommitment, DkgConfig, DkgError, DkgOutput, DkgParticipant, DkgReveal};
+use crate::fhe_client::PublicKey;
+
+const CEREMONY_DOMAIN: &[u8] = b"fhe-voting/key-ceremony/v1";
+
+#[derive(Error, Debug)]
+pub enum CeremonyError {
+    #[error("DKG failed: {0}")]
+    Dkg(#[from] DkgError),
+    #[error("Ceremony transcript encoding failed: {reason}")]
+    Encoding { reason: String },
+    #[error("Entry {sequence} is out of sequence")]
+    OutOfSequence { sequence: u64 },
+    #[error("Entry {sequence} does not chain from the previous entry")]
+    BrokenChain { sequence: u64 },
+    #[error("Entry {sequence} signature does not verify")]
+    InvalidSignature { sequence: u64 },
+    #[error("Malformed public key for {signer}")]
+    MalformedPublicKey { signer: String },
+    #[error("Entry {sequence} is not allowed here: {reason}")]
+    UnexpectedStep { sequence: u64, reason: &'static str },
+    #[error("Public key share of trustee {trustee} does not match its commitment")]
+    RevealMismatch { trustee: u32 },
+    #[error("Ceremony is incomplete: {reason}")]
+    Incomplete { reason: String },
+    #[error("Ceremony produced key {ceremony}, but {claimed} was claimed")]
+    KeyMismatch { ceremony: String, claimed: String },
+}
+
+/// One public step of the key ceremony.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum CeremonyStep {
+    Opened {
+        config: DkgConfig,
+        #[serde(with = "hex_keys")]
+        trustee_keys: Vec<[u8; 32]>, // Trustee i signs with trustee_keys[i - 1]
+    },
+    Committed(DkgCommitment),
+    Revealed(DkgReveal),
+    Finalized {
+        trustee: u32,
+        #[serde(with = "hex::serde")]
+        transcript_hash: [u8; 32],
+        #[serde(with = "hex::serde")]
+        key_fingerprint: [u8; 32],
+    },
+    Closed {
+        #[serde(with = "hex::serde")]
+        key_fingerprint: [u8; 32],
+    },
+}
+
+impl CeremonyStep {
+    // The trustee who takes this step; None for the coordinator's steps
+    fn trustee(&self) -> Option<u32> {
+        match self {
+            CeremonyStep::Opened { .. } | CeremonyStep::Closed { .. } => None,
+            CeremonyStep::Committed(commitment) => Some(commitment.from),
+            CeremonyStep::Revealed(reveal) => Some(reveal.from),
+            CeremonyStep::Finalized { trustee, .. } => Some(*trustee),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CeremonyEntry {
+    pub sequence: u64,
+    pub step: CeremonyStep,
+    #[serde(with = "hex::serde")]
+    pub previous: [u8; 32], // Hash of the previous entry, zero for the first
+    #[serde(with = "hex::serde")]
+    pub signature: [u8; 64],
+}
+
+impl CeremonyEntry {
+    /// Canonical bytes the step's signer signs.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut buf = CEREMONY_DOMAIN.to_vec();
+        buf.extend_from_slice(&self.sequence.to_le_bytes());
+        buf.extend_from_slice(&self.previous);
+        buf.extend(bincode::serialize(&self.step).expect("step serialization is infallible"));
+        buf
+    }
+
+    /// Chain hash: covers the signed bytes and the signature.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut buf = self.signing_bytes();
+        buf.extend_from_slice(&self.signature);
+        (*Impl::hash_bytes(&buf)).into()
+    }
+}
+
+/// The ceremony artifact published next to the election's receipts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CeremonyTranscript {
+    #[serde(with = "hex::serde")]
+    pub coordinator_key: [u8; 32],
+    pub entries: Vec<CeremonyEntry>,
+}
+
+impl CeremonyTranscript {
+    pub fn to_json(&self) -> Result<String, CeremonyError> {
+        serde_json::to_string_pretty(self).map_err(|e| CeremonyError::Encoding { reason: e.to_string() })
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, CeremonyError> {
+        serde_json::from_str(json).map_err(|e| CeremonyError::Encoding { reason: e.to_string() })
+    }
+}
+
+/// What a verified transcript establishes.
+#[derive(Debug, Clone)]
+pub struct CeremonyOutcome {
+    pub config: DkgConfig,
+    pub public_key: PublicKey,
+    pub transcript_hash: [u8; 32], // The trustees' DKG transcript hash
+    pub head: [u8; 32],            // Hash of the last entry
+}
+
+/// Appends signed, chained entries as the ceremony runs.
+pub struct CeremonyRecorder {
+    transcript: CeremonyTranscript,
+    head: [u8; 32],
+}
+
+impl CeremonyRecorder {
+    /// Open a ceremony for `config`, run by `coordinator`, among trustees
+    /// holding `trustee_keys` (in trustee order).
+    pub fn open(coordinator: &Operator, config: DkgConfig, trustee_keys: Vec<[u8; 32]>) -> Self {
+        let mut recorder = CeremonyRecorder {
+            transcript: CeremonyTranscript { coordinator_key: coordinator.public_key(), entries: Vec::new() },
+            head: [0u8; 32],
+        };
+        recorder.record(CeremonyStep::Opened { config, trustee_keys }, coordinator);
+        recorder
+    }
+
+    /// Append `step`, signed by `signer`.
+    pub fn record(&mut self, step: CeremonyStep, signer: &Operator) -> &CeremonyEntry {
+        let mut entry = CeremonyEntry { sequence: self.transcript.entries.len() as u64, step, previous: self.head, signature: [0u8; 64] };
+        entry.signature = signer.sign(&entry.signing_bytes());
+        self.head = entry.hash();
+        self.transcript.entries.push(entry);
+        self.transcript.entries.last().expect("entry was just appended")
+    }
+
+    /// Record the election key and return the finished transcript.
+    pub fn close(mut self, coordinator: &Operator, public_key: &PublicKey) -> CeremonyTranscript {
+        self.record(CeremonyStep::Closed { key_fingerprint: public_key.fingerprint() }, coordinator);
+        self.transcript
+    }
+}
+
+/// `dkg::run_in_process`, recording the ceremony. Each trustee signs its
+/// entries with a one-off key, so this too is only suitable for demos and tests.
+pub fn run_recorded(config: DkgConfig, coordinator: &Operator) -> Result<(PublicKey, Vec<DkgOutput>, CeremonyTranscript), CeremonyError> {
+    let signers: Vec<Operator> = (0..config.participants).map(|_| Operator::generate()).collect();
+    let mut recorder = CeremonyRecorder::open(coordinator, config, signers.iter().map(Operator::public_key).collect());
+    let mut trustees = (1..=config.participants)
+        .map(|index| DkgParticipant::new(index, config))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let commitments = trustees.iter_mut().map(|t| t.commit()).collect::<Result<Vec<_>, _>>()?;
+    for (commitment, signer) in commitments.iter().zip(&signers) {
+        recorder.record(CeremonyStep::Committed(commitment.clone()), signer);
+    }
+    for trustee in trustees.iter_mut() {
+        let me = trustee.index();
+        for commitment in commitments.iter().filter(|c| c.from != me) {
+            trustee.receive_commitment(commitment.clone())?;
+        }
+    }
+
+    let rounds = trustees.iter_mut().map(|t| t.reveal()).collect::<Result<Vec<_>, _>>()?;
+    for ((reveal, _), signer) in rounds.iter().zip(&signers) {
+        recorder.record(CeremonyStep::Revealed(reveal.clone()), signer);
+    }
+    for trustee in trustees.iter_mut() {
+        let me = trustee.index();
+        for (reveal, shares) in rounds.iter().filter(|(r, _)| r.from != me) {
+            trustee.receive_reveal(reveal.clone())?;
+            for share in shares.iter().filter(|s| s.to == me) {
+                trustee.receive_share(share.clone())?;
+            }
+        }
+    }
+
+    let outputs = trustees.into_iter().map(|t| t.finalize()).collect::<Result<Vec<_>, _>>()?;
+    for (output, signer) in outputs.iter().zip(&signers) {
+        let step = CeremonyStep::Finalized {
+            trustee: output.key_share.index,
+            transcript_hash: output.transcript_hash,
+            key_fingerprint: output.public_key.fingerprint(),
+        };
+        recorder.record(step, signer);
+    }
+    let public_key = outputs[0].public_key.clone();
+    let transcript = recorder.close(coordinator, &public_key);
+    Ok((public_key, outputs, transcript))
+}
+
+/// Check a transcript end to end. Callers must also check `coordinator_key`
+/// is the key the coordinator published.
+pub fn verify_transcript(transcript: &CeremonyTranscript) -> Result<CeremonyOutcome, CeremonyError> {
+    let coordinator = verifying_key(&transcript.coordinator_key, "the coordinator")?;
+    let mut config = None;
+    let mut trustee_keys = Vec::new();
+    let mut commitments = BTreeMap::new();
+    let mut reveals = BTreeMap::new();
+    let mut finalized = BTreeMap::new();
+    let mut closed = None;
+    let mut head = [0u8; 32];
+
+    for (position, entry) in transcript.entries.iter().enumerate() {
+        let sequence = entry.sequence;
+        let unexpected = |reason| CeremonyError::UnexpectedStep { sequence, reason };
+        if sequence != position as u64 {
+            return Err(CeremonyError::OutOfSequence { sequence });
+        }
+        if entry.previous != head {
+            return Err(CeremonyError::BrokenChain { sequence });
+        }
+        if closed.is_some() {
+            return Err(unexpected("the ceremony is already closed"));
+        }
+
+        // The coordinator opens and closes; each trustee signs its own steps
+        let signer = match (entry.step.trustee(), config) {
+            (None, _) => coordinator,
+            (Some(_), None) => return Err(unexpected("the ceremony is not open")),
+            (Some(trustee), Some(DkgConfig { participants, .. })) => {
+                if trustee == 0 || trustee > participants {
+                    return Err(unexpected("no such trustee"));
+                }
+                trustee_keys[trustee as usize - 1]
+            }
+        };
+        signer.verify(&entry.signing_bytes(), &Signature::from_bytes(&entry.signature))
+            .map_err(|_| CeremonyError::InvalidSignature { sequence })?;
+        head = entry.hash();
+
+        let participants = config.map_or(0, |config: DkgConfig| config.participants as usize);
+        match &entry.step {
+            CeremonyStep::Opened { config: opened, trustee_keys: keys } => {
+                if config.is_some() {
+                    return Err(unexpected("the ceremony is already open"));
+                }
+                let opened = DkgConfig::threshold(opened.threshold, opened.participants)?;
+                if keys.len() != opened.participants as usize {
+                    return Err(unexpected("one key per trustee is required"));
+                }
+                trustee_keys = keys.iter().enumerate().map(|(i, key)| verifying_key(key, &format!("trustee {}", i + 1))).collect::<Result<_, _>>()?;
+                config = Some(opened);
+            }
+            CeremonyStep::Committed(commitment) => {
+                if !reveals.is_empty() || commitment.share_commitments.len() != participants {
+                    return Err(unexpected("commitments come first, one share commitment per trustee"));
+                }
+                if commitments.insert(commitment.from, commitment.clone()).is_some() {
+                    return Err(unexpected("duplicate commitment"));
+                }
+            }
+            CeremonyStep::Revealed(reveal) => {
+                if commitments.len() != participants || !finalized.is_empty() {
+                    return Err(unexpected("reveals follow every commitment"));
+                }
+                if public_key_commitment(reveal.from, &reveal.public_key_share) != commitments[&reveal.from].public_key_commitment {
+                    return Err(CeremonyError::RevealMismatch { trustee: reveal.from });
+                }
+                if reveals.insert(reveal.from, reveal.public_key_share.clone()).is_some() {
+                    return Err(unexpected("duplicate reveal"));
+                }
+            }
+            CeremonyStep::Finalized { trustee, transcript_hash, key_fingerprint } => {
+                if reveals.len() != participants {
+                    return Err(unexpected("trustees finalize after every reveal"));
+                }
+                if finalized.insert(*trustee, (*transcript_hash, *key_fingerprint)).is_some() {
+                    return Err(unexpected("duplicate finalization"));
+                }
+            }
+            CeremonyStep::Closed { key_fingerprint } => {
+                if finalized.len() != participants || participants == 0 {
+                    return Err(unexpected("the ceremony closes after every trustee finalizes"));
+                }
+                closed = Some(*key_fingerprint);
+            }
+        }
+    }
+
+    let (Some(config), Some(closed)) = (config, closed) else {
+        return Err(CeremonyError::Incomplete { reason: "the transcript is not opened and closed".to_string() });
+    };
+    let public_key = combine_public_key_shares(reveals.values());
+    let fingerprint = public_key.fingerprint();
+    if fingerprint != closed {
+        return Err(CeremonyError::KeyMismatch { ceremony: hex::encode(fingerprint), claimed: hex::encode(closed) });
+    }
+    let transcript_hash = dkg::transcript_hash(config, commitments.values());
+    if let Some((trustee, _)) = finalized.iter().find(|(_, &finished)| finished != (transcript_hash, fingerprint)) {
+        return Err(CeremonyError::Incomplete { reason: format!("trustee {} finalized a different DKG", trustee) });
+    }
+    Ok(CeremonyOutcome { config, public_key, transcript_hash, head })
+}
+
+/// Require the election key with fingerprint `key_fingerprint` to be the ceremony's key.
+pub fn check_election_key(outcome: &CeremonyOutcome, key_fingerprint: &[u8; 32]) -> Result<(), CeremonyError> {
+    let fingerprint = outcome.public_key.fingerprint();
+    if &fingerprint != key_fingerprint {
+        return Err(CeremonyError::KeyMismatch { ceremony: hex::encode(fingerprint), claimed: hex::encode(key_fingerprint) });
+    }
+    Ok(())
+}
+
+fn verifying_key(key: &[u8; 32], signer: &str) -> Result<VerifyingKey, CeremonyError> {
+    VerifyingKey::from_bytes(key).map_err(|_| CeremonyError::MalformedPublicKey { signer: signer.to_string() })
+}
+
+// Trustee keys as a list of hex strings
+mod hex_keys {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(keys: &[[u8; 32]], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(keys.iter().map(hex::encode))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<[u8; 32]>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|key| hex::decode(key).ok().and_then(|bytes| bytes.try_into().ok()).ok_or_else(|| serde::de::Error::custom("trustee key is not 32 hex bytes")))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recorded_ceremony_verifies_and_detects_tampering() -> Result<(), CeremonyError> {
+        let coordinator = Operator::generate();
+        let (public_key, outputs, transcript) = run_recorded(DkgConfig::threshold(2, 3)?, &coordinator)?;
+        assert_eq!(transcript.entries.len(), 1 + 3 * 3 + 1);
+
+        let outcome = verify_transcript(&CeremonyTranscript::from_json(&transcript.to_json()?)?)?;
+        assert_eq!(outcome.transcript_hash, outputs[0].transcript_hash);
+        check_election_key(&outcome, &public_key.fingerprint())?;
+        assert!(matches!(check_election_key(&outcome, &[0u8; 32]), Err(CeremonyError::KeyMismatch { .. })));
+
+        // A trustee's entry re-signed by the coordinator fails that trustee's key
+        let mut forged = transcript.clone();
+        forged.entries[1].signature = coordinator.sign(&forged.entries[1].signing_bytes());
+        assert!(matches!(verify_transcript(&forged), Err(CeremonyError::InvalidSignature { sequence: 1 })));
+        let mut dropped = transcript.clone();
+        dropped.entries.remove(4);
+        assert!(matches!(verify_transcript(&dropped), Err(CeremonyError::OutOfSequence { sequence: 5 })));
+        let mut unclosed = transcript;
+        unclosed.entries.pop();
+        assert!(matches!(verify_transcript(&unclosed), Err(CeremonyError::Incomplete { .. })));
+        Ok(())
+    }
+}
@@ -0,0 +1,360 @@
+// On-chain ballot ingestion.
+// Voters cast ballots with the ballot box contract (contracts/BallotBox.sol),
+// which emits `BallotCast(address indexed voter, bytes ballot)` with the
+// bincode-encoded `EncryptedVote` as its payload. The ingestor follows the
+// chain block by block and builds the canonical ballot set as of the
+// election's close block.
+//
+// Blocks must chain by parent hash. A block at a height already held with a
+// different hash is a reorg: it and everything above it are dropped, together
+// with their ballots, and the source is walked back until the chain links up
+// again. Blocks more than `confirmations` below the tip are final and are
+// never rolled back; a reorg that deep is an error. Re-delivered blocks and
+// events (overlapping polls, retries) are ignored, so each log is counted once.
+//
+// The ballot set is only released once the close block is final, together
+// with a `ChainAnchor` naming the close block's hash. The guest commits the
+// anchor, so the proof is pinned to the chain state the ballots were read at.
+//
+// A decoded ballot's voter address is rewritten in its EIP-55 form, and a log
+// whose ballot names a malformed address is refused.
+
+use std::collections::{BTreeMap, HashSet};
+
+use sha3::{Digest, Keccak256};
+use thiserror::Error;
+
+use crate::address::normalize_ballot;
+use crate::onchain::OnchainError;
+use crate::types::{ChainAnchor, EncryptedVote};
+
+/// Event signature of a cast ballot.
+pub const BALLOT_CAST_SIGNATURE: &str = "BallotCast(address,bytes)";
+
+/// Confirmations before a block is treated as final, absent other configuration.
+pub const DEFAULT_CONFIRMATIONS: u64 = 12;
+
+/// Ballots read as of a close block, with the anchor naming that block.
+pub type ChainBallotSet = (Vec<EncryptedVote>, ChainAnchor);
+
+#[derive(Error, Debug)]
+pub enum IngestError {
+    #[error("Block {number} does not extend the ingested chain")]
+    ParentMismatch { number: u64 },
+    #[error("Block {number} is beyond the ingested tip {tip}")]
+    Gap { number: u64, tip: u64 },
+    #[error("Reorg at block {number} reaches finalized block {finalized}")]
+    ReorgBeyondFinality { number: u64, finalized: u64 },
+    #[error("Close block {close_block} is not final yet (finalized through {finalized:?})")]
+    NotFinal { close_block: u64, finalized: Option<u64> },
+    #[error("Malformed ballot event: {reason}")]
+    Decode { reason: String },
+    #[error("Chain source failed: {0}")]
+    Source(#[from] OnchainError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockHeader {
+    pub number: u64,
+    pub hash: [u8; 32],
+    pub parent_hash: [u8; 32],
+}
+
+/// A `BallotCast` log, identified by its transaction and position in the block.
+#[derive(Clone)]
+pub struct BallotEvent {
+    pub transaction_hash: [u8; 32],
+    pub log_index: u64,
+    pub ballot: EncryptedVote,
+}
+
+impl BallotEvent {
+    /// Decode a log's data (ABI `bytes` holding the bincode ballot).
+    pub fn decode(transaction_hash: [u8; 32], log_index: u64, data: &[u8]) -> Result<Self, IngestError> {
+        let payload = crate::onchain::abi_decode_bytes(data)
+            .ok_or_else(|| IngestError::Decode { reason: "log data is not ABI bytes".to_string() })?;
+        let mut ballot = bincode::deserialize(&payload).map_err(|e| IngestError::Decode { reason: e.to_string() })?;
+        normalize_ballot(&mut ballot).map_err(|e| IngestError::Decode { reason: e.to_string() })?;
+        Ok(BallotEvent { transaction_hash, log_index, ballot })
+    }
+}
+
+/// Topic 0 of `BallotCast` logs.
+pub fn ballot_cast_topic() -> [u8; 32] {
+    Keccak256::digest(BALLOT_CAST_SIGNATURE.as_bytes()).into()
+}
+
+/// Where the ingestor reads blocks and ballot logs from.
+pub trait ChainSource {
+    fn head(&self) -> Result<u64, IngestError>;
+    fn block(&self, number: u64) -> Result<BlockHeader, IngestError>;
+    fn ballot_events(&self, block: &BlockHeader) -> Result<Vec<BallotEvent>, IngestError>;
+}
+
+struct IngestedBlock {
+    header: BlockHeader,
+    events: Vec<BallotEvent>,
+}
+
+pub struct BallotIngestor {
+    start_block: u64,
+    confirmations: u64,
+    blocks: BTreeMap<u64, IngestedBlock>,
+    seen: HashSet<([u8; 32], u64)>, // (transaction hash, log index) of held events
+}
+
+impl BallotIngestor {
+    /// Follow the chain from `start_block` (the election's opening block).
+    pub fn new(start_block: u64, confirmations: u64) -> Self {
+        BallotIngestor { start_block, confirmations, blocks: BTreeMap::new(), seen: HashSet::new() }
+    }
+
+    pub fn tip(&self) -> Option<u64> {
+        self.blocks.last_key_value().map(|(&number, _)| number)
+    }
+
+    /// Highest block that can no longer be reorganized.
+    pub fn finalized(&self) -> Option<u64> {
+        self.tip()?.checked_sub(self.confirmations).filter(|&number| number >= self.start_block)
+    }
+
+    /// Add the next block. Returns how many held blocks a reorg rolled back.
+    pub fn apply_block(&mut self, header: BlockHeader, events: Vec<BallotEvent>) -> Result<usize, IngestError> {
+        let number = header.number;
+        if let Some(held) = self.blocks.get(&number) {
+            if held.header.hash == header.hash {
+                return Ok(0);
+            }
+        }
+        let tip = self.tip();
+        if tip.is_some_and(|tip| number > tip + 1) || (tip.is_none() && number != self.start_block) {
+            return Err(IngestError::Gap { number, tip: tip.unwrap_or(self.start_block) });
+        }
+        if number > self.start_block {
+            match self.blocks.get(&(number - 1)) {
+                Some(parent) if parent.header.hash == header.parent_hash => {}
+                _ => return Err(IngestError::ParentMismatch { number }),
+            }
+        }
+
+        let rolled_back = self.rollback_from(number)?;
+        let events = events.into_iter().filter(|event| self.seen.insert((event.transaction_hash, event.log_index))).collect();
+        self.blocks.insert(number, IngestedBlock { header, events });
+        Ok(rolled_back)
+    }
+
+    // Drop `number` and every block above it
+    fn rollback_from(&mut self, number: u64) -> Result<usize, IngestError> {
+        if let Some(finalized) = self.finalized().filter(|&finalized| number <= finalized) {
+            return Err(IngestError::ReorgBeyondFinality { number, finalized });
+        }
+        let dropped = self.blocks.split_off(&number);
+        for block in dropped.values() {
+            for event in &block.events {
+                self.seen.remove(&(event.transaction_hash, event.log_index));
+            }
+        }
+        Ok(dropped.len())
+    }
+
+    /// Ingest up to the source's head, walking back over reorgs. Returns blocks rolled back.
+    pub fn sync(&mut self, source: &impl ChainSource) -> Result<usize, IngestError> {
+        let head = source.head()?;
+        let mut rolled_back = 0;
+        let mut next = self.tip().map_or(self.start_block, |tip| tip + 1);
+        while next <= head {
+            let header = source.block(next)?;
+            let events = source.ballot_events(&header)?;
+            match self.apply_block(header, events) {
+                Ok(dropped) => {
+                    rolled_back += dropped;
+                    next += 1;
+                }
+                // Our copy of the parent was orphaned: re-read it from the source
+                Err(IngestError::ParentMismatch { number }) => next = number - 1,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(rolled_back)
+    }
+
+    /// Ballots cast up to and including `close_block`, in chain order, once it is final.
+    pub fn ballot_set(&self, close_block: u64) -> Result<ChainBallotSet, IngestError> {
+        let finalized = self.finalized();
+        let close = self
+            .blocks
+            .get(&close_block)
+            .filter(|_| finalized.is_some_and(|finalized| close_block <= finalized))
+            .ok_or(IngestError::NotFinal { close_block, finalized })?;
+        let mut ballots = Vec::new();
+        for block in self.blocks.range(..=close_block).map(|(_, block)| block) {
+            let mut events: Vec<_> = block.events.iter().collect();
+            events.sort_by_key(|event| event.log_index);
+            ballots.extend(events.into_iter().map(|event| event.ballot.clone()));
+        }
+        Ok((ballots, ChainAnchor { close_block, block_hash: close.header.hash }))
+    }
+}
+
+#[cfg(feature = "ethereum")]
+pub use rpc::RpcChainSource;
+
+#[cfg(feature = "ethereum")]
+mod rpc {
+    use serde_json::{json, Value};
+
+    use super::{ballot_cast_topic, BallotEvent, BlockHeader, ChainSource, IngestError};
+    use crate::onchain::{JsonRpc, OnchainError};
+
+    fn malformed(what: &str) -> IngestError {
+        IngestError::Source(OnchainError::Rpc { reason: format!("malformed {}", what) })
+    }
+
+    fn quantity(value: &Value, what: &str) -> Result<u64, IngestError> {
+        value.as_str().and_then(|v| u64::from_str_radix(v.trim_start_matches("0x"), 16).ok()).ok_or_else(|| malformed(what))
+    }
+
+    fn bytes32(value: &Value, what: &str) -> Result<[u8; 32], IngestError> {
+        value
+            .as_str()
+            .and_then(|v| hex::decode(v.trim_start_matches("0x")).ok())
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| malformed(what))
+    }
+
+    /// Reads blocks and `BallotCast` logs of one ballot box over JSON-RPC.
+    pub struct RpcChainSource {
+        rpc: JsonRpc,
+        ballot_box: [u8; 20],
+    }
+
+    impl RpcChainSource {
+        pub fn new(rpc_url: &str, ballot_box: &str) -> Result<Self, IngestError> {
+            Ok(RpcChainSource { rpc: JsonRpc::new(rpc_url), ballot_box: crate::onchain::parse_address(ballot_box)? })
+        }
+    }
+
+    impl ChainSource for RpcChainSource {
+        fn head(&self) -> Result<u64, IngestError> {
+            quantity(&self.rpc.call("eth_blockNumber", json!([]))?, "block number")
+        }
+
+        fn block(&self, number: u64) -> Result<BlockHeader, IngestError> {
+            let block = self.rpc.call("eth_getBlockByNumber", json!([format!("0x{:x}", number), false]))?;
+            Ok(BlockHeader {
+                number: quantity(&block["number"], "block")?,
+                hash: bytes32(&block["hash"], "block hash")?,
+                parent_hash: bytes32(&block["parentHash"], "parent hash")?,
+            })
+        }
+
+        // Logs are requested by block hash, so they belong to exactly the header read
+        fn ballot_events(&self, block: &BlockHeader) -> Result<Vec<BallotEvent>, IngestError> {
+            let filter = json!({
+                "blockHash": format!("0x{}", hex::encode(block.hash)),
+                "address": format!("0x{}", hex::encode(self.ballot_box)),
+                "topics": [format!("0x{}", hex::encode(ballot_cast_topic()))],
+            });
+            let logs = self.rpc.call("eth_getLogs", json!([filter]))?;
+            logs.as_array()
+                .ok_or_else(|| malformed("logs"))?
+                .iter()
+                .filter(|log| log["removed"] != true)
+                .map(|log| {
+                    let data = log["data"].as_str().and_then(|d| hex::decode(d.trim_start_matches("0x")).ok()).ok_or_else(|| malformed("log data"))?;
+                    BallotEvent::decode(bytes32(&log["transactionHash"], "transaction hash")?, quantity(&log["logIndex"], "log index")?, &data)
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use crate::address::to_checksum_address;
+    use crate::onchain::abi_encode_bytes;
+    use crate::types::{BallotAuthorization, Digest32, MembershipProof, VoteOption};
+
+    // In-memory chain whose blocks can be replaced to simulate a reorg
+    struct TestChain {
+        blocks: RefCell<Vec<(BlockHeader, Vec<BallotEvent>)>>,
+    }
+
+    impl TestChain {
+        fn hash(number: u64, fork: u8) -> [u8; 32] {
+            let mut hash = [fork; 32];
+            hash[..8].copy_from_slice(&number.to_le_bytes());
+            hash
+        }
+
+        // Extend the chain to `to` on `fork`, one ballot per block
+        fn extend(&self, to: u64, fork: u8) {
+            let mut blocks = self.blocks.borrow_mut();
+            while blocks.len() as u64 <= to {
+                let number = blocks.len() as u64;
+                let header = BlockHeader { number, hash: Self::hash(number, fork), parent_hash: blocks[number as usize - 1].0.hash };
+                blocks.push((header, vec![ballot_event(number, fork)]));
+            }
+        }
+    }
+
+    impl ChainSource for TestChain {
+        fn head(&self) -> Result<u64, IngestError> {
+            Ok(self.blocks.borrow().len() as u64 - 1)
+        }
+        fn block(&self, number: u64) -> Result<BlockHeader, IngestError> {
+            Ok(self.blocks.borrow()[number as usize].0.clone())
+        }
+        fn ballot_events(&self, block: &BlockHeader) -> Result<Vec<BallotEvent>, IngestError> {
+            // Deliver every event twice, as overlapping polls would
+            let events = self.blocks.borrow()[block.number as usize].1.clone();
+            Ok(events.iter().chain(&events).cloned().collect())
+        }
+    }
+
+    fn voter(number: u64, fork: u8) -> [u8; 20] {
+        [[number as u8, fork]; 10].concat().try_into().unwrap()
+    }
+
+    fn ballot_event(number: u64, fork: u8) -> BallotEvent {
+        let ballot = EncryptedVote {
+            voter_address: format!("0x{}", hex::encode(voter(number, fork))),
+            election_id: Digest32::ZERO,
+            nonce: [fork; 32],
+            encrypted_vote_vector: Vec::new(),
+            signature: String::new(),
+            authorization: BallotAuthorization::Membership(MembershipProof { nullifier: [0u8; 32], challenges: Vec::new(), responses: Vec::new() }),
+            sequence: 0,
+            actual_choice: VoteOption::Option1,
+        };
+        let data = abi_encode_bytes(&[&bincode::serialize(&ballot).unwrap()]);
+        BallotEvent::decode(TestChain::hash(number, fork), 0, &data).unwrap()
+    }
+
+    #[test]
+    fn test_ingestion_survives_reorgs_and_duplicates() -> Result<(), IngestError> {
+        let genesis = BlockHeader { number: 0, hash: [0u8; 32], parent_hash: [0u8; 32] };
+        let chain = TestChain { blocks: RefCell::new(vec![(genesis, Vec::new())]) };
+        chain.extend(6, 1);
+        let mut ingestor = BallotIngestor::new(0, 2);
+        assert_eq!(ingestor.sync(&chain)?, 0);
+        assert!(matches!(ingestor.ballot_set(5), Err(IngestError::NotFinal { .. })));
+
+        // Blocks 5 and 6 are replaced by a longer fork
+        chain.blocks.borrow_mut().truncate(5);
+        chain.extend(8, 2);
+        assert_eq!(ingestor.sync(&chain)?, 2);
+        let (ballots, anchor) = ingestor.ballot_set(5)?;
+        assert_eq!(anchor, ChainAnchor { close_block: 5, block_hash: TestChain::hash(5, 2) });
+        let senders: Vec<_> = ballots.iter().map(|ballot| ballot.voter_address.clone()).collect();
+        let expected: Vec<_> = [(1, 1), (2, 1), (3, 1), (4, 1), (5, 2)].iter().map(|&(number, fork)| to_checksum_address(&voter(number, fork))).collect();
+        assert_eq!(senders, expected);
+
+        // Reorgs below the finality depth are refused
+        let stale = BlockHeader { number: 3, hash: [9u8; 32], parent_hash: TestChain::hash(2, 1) };
+        assert!(matches!(ingestor.apply_block(stale, Vec::new()), Err(IngestError::ReorgBeyondFinality { .. })));
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+// FHE circuits for the circuit interpreter guest.
+// A circuit is a list of `CircuitOp`s over named ciphertext registers, run by
+// one published guest image, so a new encrypted computation needs no guest of
+// its own. `circuit_input` runs the guest's checks on the host first, so an
+// op reading an unwritten register fails before any proving, and
+// `evaluate_circuit` runs the same ops natively: the guest's output frames
+// must match it byte for byte.
+//
+// `check_circuit` ties a journal to the circuit and inputs through
+// `circuit_digest` and `inputs_digest`, so a verifier knows which program ran
+// on which ciphertexts. Only the constant coefficient of a register decrypts;
+// rotating by k moves it k places (negating it after N), so rotations are
+// mainly useful in pairs that bring a value back.
+
+use std::collections::{HashMap, HashSet};
+
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+use crate::fhe_client::{Cipher, FheClient, FheClientError, PublicKey, Signed, PLAINTEXT_MODULUS};
+use crate::types::{CircuitInput, CircuitOp, CircuitOutput, CircuitRegister};
+
+// Domain separation tags (must match guest implementation)
+const CIRCUIT_TAG: u8 = 0x0d;
+const CIRCUIT_INPUT_TAG: u8 = 0x0e;
+
+#[derive(Error, Debug)]
+pub enum CircuitError {
+    #[error("The circuit needs at least one input register")]
+    NoInputs,
+    #[error("The circuit needs at least one output register")]
+    NoOutputs,
+    #[error("Input register {name} is given twice")]
+    DuplicateInput { name: String },
+    #[error("Op {op} reads register {name} before it is written")]
+    UnwrittenRegister { op: usize, name: String },
+    #[error("Output register {name} is never written")]
+    MissingOutput { name: String },
+    #[error("Op {op} multiplies by {scalar}, which is not below the plaintext modulus")]
+    ScalarTooLarge { op: usize, scalar: u64 },
+    #[error("Input register {name} is malformed: {source}")]
+    Ciphertext { name: String, source: FheClientError },
+    #[error("Circuit journal does not cover the given circuit and inputs")]
+    CircuitMismatch,
+    #[error("Input register {name} could not be encrypted: {source}")]
+    Encryption { name: String, source: FheClientError },
+}
+
+/// A circuit over plaintext inputs, e.g. read from a JSON file, before encryption.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitProgram {
+    pub inputs: Vec<(String, u64)>,
+    pub ops: Vec<CircuitOp>,
+    pub outputs: Vec<String>,
+}
+
+impl CircuitProgram {
+    /// Encrypt every input under the client's key and check the circuit.
+    pub fn encrypt(self, client: &FheClient) -> Result<CircuitInput, CircuitError> {
+        let inputs = self
+            .inputs
+            .into_iter()
+            .map(|(name, value)| match client.encrypt_values(&[value]) {
+                Ok(mut ciphertexts) => Ok(CircuitRegister { name, ciphertext: ciphertexts.remove(0) }),
+                Err(source) => Err(CircuitError::Encryption { name, source }),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        circuit_input(client.get_public_key().clone(), inputs, self.ops, self.outputs)
+    }
+}
+
+/// Input for the circuit guest, after the checks the guest will repeat.
+pub fn circuit_input(
+    public_key: PublicKey,
+    inputs: Vec<CircuitRegister>,
+    ops: Vec<CircuitOp>,
+    outputs: Vec<String>,
+) -> Result<CircuitInput, CircuitError> {
+    if inputs.is_empty() {
+        return Err(CircuitError::NoInputs);
+    }
+    if outputs.is_empty() {
+        return Err(CircuitError::NoOutputs);
+    }
+    let mut written = HashSet::new();
+    for register in &inputs {
+        if !written.insert(register.name.as_str()) {
+            return Err(CircuitError::DuplicateInput { name: register.name.clone() });
+        }
+    }
+    for (index, op) in ops.iter().enumerate() {
+        let (out, reads) = match op {
+            CircuitOp::Add { out, lhs, rhs } | CircuitOp::Sub { out, lhs, rhs } => (out, vec![lhs, rhs]),
+            CircuitOp::MulPlain { out, input, scalar } => {
+                if *scalar >= PLAINTEXT_MODULUS {
+                    return Err(CircuitError::ScalarTooLarge { op: index, scalar: *scalar });
+                }
+                (out, vec![input])
+            }
+            CircuitOp::Rotate { out, input, .. } => (out, vec![input]),
+        };
+        if let Some(name) = reads.into_iter().find(|name| !written.contains(name.as_str())) {
+            return Err(CircuitError::UnwrittenRegister { op: index, name: name.clone() });
+        }
+        written.insert(out);
+    }
+    if let Some(name) = outputs.iter().find(|name| !written.contains(name.as_str())) {
+        return Err(CircuitError::MissingOutput { name: name.clone() });
+    }
+    Ok(CircuitInput { public_key, inputs, ops, outputs })
+}
+
+/// Run the circuit natively, as the guest does; returns the output registers in order.
+pub fn evaluate_circuit(input: &CircuitInput) -> Result<Vec<Cipher<Signed>>, CircuitError> {
+    let key_fingerprint = input.public_key.fingerprint();
+    let mut registers: HashMap<&str, Cipher<Signed>> = HashMap::new();
+    for register in &input.inputs {
+        let cipher = Cipher::<Signed>::deserialize_bound(&register.ciphertext, &key_fingerprint)
+            .map_err(|source| CircuitError::Ciphertext { name: register.name.clone(), source })?;
+        registers.insert(&register.name, cipher);
+    }
+    // `circuit_input` checked every read register is written first
+    for op in &input.ops {
+        let (out, value) = match op {
+            CircuitOp::Add { out, lhs, rhs } => (out, registers[lhs.as_str()].add(&registers[rhs.as_str()])),
+            CircuitOp::Sub { out, lhs, rhs } => (out, registers[lhs.as_str()].sub(&registers[rhs.as_str()])),
+            CircuitOp::MulPlain { out, input, scalar } => (out, registers[input.as_str()].mul_plain(*scalar)),
+            CircuitOp::Rotate { out, input, steps } => (out, registers[input.as_str()].rotate(*steps)),
+        };
+        registers.insert(out, value);
+    }
+    Ok(input.outputs.iter().map(|name| registers[name.as_str()].clone()).collect())
+}
+
+/// Digest: H(tag || op count || ops || output count || output names), names length-prefixed.
+/// Ops encode as an opcode (add 0, sub 1, mul-plain 2, rotate 3), their register
+/// names, then the scalar (u64) or steps (u32), little-endian.
+pub fn circuit_digest(ops: &[CircuitOp], outputs: &[String]) -> [u8; 32] {
+    let mut buf = vec![CIRCUIT_TAG];
+    buf.extend_from_slice(&(ops.len() as u32).to_le_bytes());
+    for op in ops {
+        match op {
+            CircuitOp::Add { out, lhs, rhs } => {
+                buf.push(0);
+                for name in [out, lhs, rhs] {
+                    extend_with_name(&mut buf, name);
+                }
+            }
+            CircuitOp::Sub { out, lhs, rhs } => {
+                buf.push(1);
+                for name in [out, lhs, rhs] {
+                    extend_with_name(&mut buf, name);
+                }
+            }
+            CircuitOp::MulPlain { out, input, scalar } => {
+                buf.push(2);
+                extend_with_name(&mut buf, out);
+                extend_with_name(&mut buf, input);
+                buf.extend_from_slice(&scalar.to_le_bytes());
+            }
+            CircuitOp::Rotate { out, input, steps } => {
+                buf.push(3);
+                extend_with_name(&mut buf, out);
+                extend_with_name(&mut buf, input);
+                buf.extend_from_slice(&steps.to_le_bytes());
+            }
+        }
+    }
+    buf.extend_from_slice(&(outputs.len() as u32).to_le_bytes());
+    for name in outputs {
+        extend_with_name(&mut buf, name);
+    }
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// Digest: H(tag || (len || name || len || ciphertext) for every input register, in order).
+pub fn inputs_digest(inputs: &[CircuitRegister]) -> [u8; 32] {
+    let mut buf = vec![CIRCUIT_INPUT_TAG];
+    for register in inputs {
+        extend_with_name(&mut buf, &register.name);
+        buf.extend_from_slice(&(register.ciphertext.len() as u32).to_le_bytes());
+        buf.extend_from_slice(&register.ciphertext);
+    }
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// Check that a circuit journal covers exactly the circuit and inputs of `input`.
+pub fn check_circuit(output: &CircuitOutput, input: &CircuitInput) -> Result<(), CircuitError> {
+    if output.key_fingerprint != input.public_key.fingerprint()
+        || output.circuit_digest != circuit_digest(&input.ops, &input.outputs)
+        || output.inputs_digest != inputs_digest(&input.inputs)
+        || output.ops as usize != input.ops.len()
+        || output.encrypted_output_frames as usize != input.outputs.len()
+    {
+        return Err(CircuitError::CircuitMismatch);
+    }
+    Ok(())
+}
+
+fn extend_with_name(buf: &mut Vec<u8>, name: &str) {
+    buf.extend_from_slice(&(name.len() as u32).to_le_bytes());
+    buf.extend_from_slice(name.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fhe_client::{FheClient, POLYNOMIAL_DEGREE};
+
+    #[test]
+    fn test_circuit_ops_decrypt_to_plaintext_results() -> Result<(), Box<dyn std::error::Error>> {
+        let client = FheClient::new();
+        let [a, b] = [5u64, 9].map(|value| client.encrypt_values(&[value]).unwrap().remove(0));
+        let inputs = vec![
+            CircuitRegister { name: "a".into(), ciphertext: a },
+            CircuitRegister { name: "b".into(), ciphertext: b },
+        ];
+        let ops = vec![
+            CircuitOp::Add { out: "sum".into(), lhs: "a".into(), rhs: "b".into() },
+            // 5 - 9 wraps to t - 4
+            CircuitOp::Sub { out: "diff".into(), lhs: "a".into(), rhs: "b".into() },
+            CircuitOp::MulPlain { out: "scaled".into(), input: "sum".into(), scalar: 1000 },
+            // X^N = -1, so N steps negate and 2N steps are the identity
+            CircuitOp::Rotate { out: "negated".into(), input: "a".into(), steps: POLYNOMIAL_DEGREE as u32 },
+            // Moving the value out of the constant coefficient leaves it decrypting to 0
+            CircuitOp::Rotate { out: "moved".into(), input: "a".into(), steps: 3 },
+            CircuitOp::Rotate { out: "back".into(), input: "moved".into(), steps: 2 * POLYNOMIAL_DEGREE as u32 - 3 },
+        ];
+        let outputs: Vec<String> = ["sum", "diff", "scaled", "negated", "moved", "back"].map(String::from).to_vec();
+        let input = circuit_input(client.get_public_key().clone(), inputs, ops, outputs)?;
+
+        let decrypted = evaluate_circuit(&input)?.iter().map(|cipher| Ok(client.decrypt(cipher)?.coefficients()[0])).collect::<Result<Vec<u64>, FheClientError>>()?;
+        assert_eq!(decrypted, vec![14, PLAINTEXT_MODULUS - 4, 14_000, PLAINTEXT_MODULUS - 5, 0, 5]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_circuit_input_checks_and_journal_binding() -> Result<(), Box<dyn std::error::Error>> {
+        let client = FheClient::new();
+        let public_key = client.get_public_key().clone();
+        let inputs = vec![CircuitRegister { name: "x".into(), ciphertext: client.encrypt_values(&[2])?.remove(0) }];
+        let ops = vec![CircuitOp::MulPlain { out: "y".into(), input: "x".into(), scalar: 3 }];
+        let input = circuit_input(public_key.clone(), inputs.clone(), ops.clone(), vec!["y".into()])?;
+
+        let output = CircuitOutput {
+            key_fingerprint: public_key.fingerprint(),
+            circuit_digest: circuit_digest(&ops, &input.outputs),
+            inputs_digest: inputs_digest(&inputs),
+            ops: 1,
+            encrypted_output_frames: 1,
+        };
+        check_circuit(&output, &input)?;
+        // Another program over the same inputs does not match
+        let other = circuit_input(public_key.clone(), inputs.clone(), vec![CircuitOp::MulPlain { out: "y".into(), input: "x".into(), scalar: 4 }], vec!["y".into()])?;
+        assert!(matches!(check_circuit(&output, &other), Err(CircuitError::CircuitMismatch)));
+
+        let reads_unwritten = vec![CircuitOp::Rotate { out: "y".into(), input: "z".into(), steps: 1 }];
+        assert!(matches!(
+            circuit_input(public_key.clone(), inputs.clone(), reads_unwritten, vec!["y".into()]),
+            Err(CircuitError::UnwrittenRegister { op: 0, .. })
+        ));
+        assert!(matches!(circuit_input(public_key.clone(), inputs.clone(), ops.clone(), vec!["z".into()]), Err(CircuitError::MissingOutput { .. })));
+        let oversized = vec![CircuitOp::MulPlain { out: "y".into(), input: "x".into(), scalar: PLAINTEXT_MODULUS }];
+        assert!(matches!(circuit_input(public_key.clone(), inputs.clone(), oversized, vec!["y".into()]), Err(CircuitError::ScalarTooLarge { op: 0, .. })));
+        let repeated = vec![inputs[0].clone(), inputs[0].clone()];
+        assert!(matches!(circuit_input(public_key, repeated, ops, vec!["y".into()]), Err(CircuitError::DuplicateInput { .. })));
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+// Multi-prover cross-check.
+// A receipt proves the journal is what the image produced on that run, on
+// whichever backend ran it. A backend-specific bug that makes execution
+// nondeterministic (a mismatched r0vm, an accelerator, a remote service on a
+// different release) would still yield a valid receipt for a different journal.
+// `cross_check` proves the same tally input on two or more backends against
+// the same image id, verifies every receipt and requires the journals to be
+// identical byte for byte.
+//
+// Backends are the executor's (cpu, gpu, bonsai, dev), whatever
+// FHE_VOTING_PROVER selects for the main proof. Only journals are compared:
+// detached frames are not returned by every backend, and a compact journal
+// commits to the frames' chain head anyway.
+
+use risc0_zkvm::sha::{Impl, Sha256};
+use risc0_zkvm::{ExecutorEnv, ProverOpts, Receipt, VerifierContext};
+use thiserror::Error;
+
+use crate::executor::{ExecutorConfig, ExecutorError, ProverBackend};
+use crate::types::{Digest32, VoteTallyInput};
+
+#[derive(Error, Debug)]
+pub enum CrossCheckError {
+    #[error("A cross-check needs at least two backends, got {count}")]
+    TooFewBackends { count: usize },
+    #[error("Executor environment failed: {0}")]
+    Executor(#[from] ExecutorError),
+    #[error("Proving on {backend} failed: {reason}")]
+    Prover { backend: ProverBackend, reason: String },
+    #[error("Receipt from {backend} does not verify: {reason}")]
+    Receipt { backend: ProverBackend, reason: String },
+    #[error("Journals from {first} and {second} differ at byte {offset}")]
+    JournalMismatch { first: ProverBackend, second: ProverBackend, offset: usize },
+}
+
+/// Parse a comma-separated backend list such as `cpu,bonsai`.
+pub fn parse_backends(list: &str) -> Result<Vec<ProverBackend>, CrossCheckError> {
+    let backends = list.split(',').map(str::parse).collect::<Result<Vec<ProverBackend>, _>>()?;
+    if backends.len() < 2 {
+        return Err(CrossCheckError::TooFewBackends { count: backends.len() });
+    }
+    Ok(backends)
+}
+
+/// `parse_backends`, also requiring every backend's settings to be present.
+pub fn backends_from_env(list: &str) -> Result<Vec<ProverBackend>, CrossCheckError> {
+    let backends = parse_backends(list)?;
+    for backend in &backends {
+        backend.check_env()?;
+    }
+    Ok(backends)
+}
+
+/// A receipt and the backend that produced it.
+#[derive(Debug)]
+pub struct ProverRun {
+    pub backend: ProverBackend,
+    pub receipt: Receipt,
+}
+
+/// The backends whose receipts agreed, and the journal they agreed on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossCheckReport {
+    pub backends: Vec<ProverBackend>,
+    pub journal_digest: Digest32,
+}
+
+/// Prove `input` on `backend`; `assumptions` are the receipts the guest
+/// verifies, such as the prior interim tally.
+pub fn prove_on(backend: ProverBackend, input: &VoteTallyInput, assumptions: &[Receipt], elf: &[u8], executor: &ExecutorConfig) -> Result<ProverRun, CrossCheckError> {
+    let prover_error = |reason: String| CrossCheckError::Prover { backend, reason };
+    let mut builder = ExecutorEnv::builder();
+    executor.apply(&mut builder)?;
+    for receipt in assumptions {
+        builder.add_assumption(receipt.clone());
+    }
+    let env = builder.write(input).map_err(|e| prover_error(e.to_string()))?.build().map_err(|e| prover_error(e.to_string()))?;
+    let prove_info = executor.prove_on(backend, env, elf, &ProverOpts::default()).map_err(|e| prover_error(e.to_string()))?;
+    Ok(ProverRun { backend, receipt: prove_info.receipt })
+}
+
+/// Verify every run's receipt against `image_id` and require identical journals.
+pub fn compare_runs(ctx: &VerifierContext, runs: &[ProverRun], image_id: [u32; 8]) -> Result<CrossCheckReport, CrossCheckError> {
+    if runs.len() < 2 {
+        return Err(CrossCheckError::TooFewBackends { count: runs.len() });
+    }
+    for run in runs {
+        run.receipt.verify_with_context(ctx, image_id).map_err(|e| CrossCheckError::Receipt { backend: run.backend, reason: e.to_string() })?;
+    }
+    let first = &runs[0];
+    for run in &runs[1..] {
+        let (lhs, rhs) = (&first.receipt.journal.bytes, &run.receipt.journal.bytes);
+        if lhs != rhs {
+            let offset = lhs.iter().zip(rhs).position(|(a, b)| a != b).unwrap_or(lhs.len().min(rhs.len()));
+            return Err(CrossCheckError::JournalMismatch { first: first.backend, second: run.backend, offset });
+        }
+    }
+    Ok(CrossCheckReport {
+        backends: runs.iter().map(|run| run.backend).collect(),
+        journal_digest: <[u8; 32]>::from(*Impl::hash_bytes(&first.receipt.journal.bytes)).into(),
+    })
+}
+
+/// Prove `input` on every backend in turn and compare the receipts.
+pub fn cross_check(backends: &[ProverBackend], input: &VoteTallyInput, assumptions: &[Receipt], elf: &[u8], image_id: [u32; 8], executor: &ExecutorConfig) -> Result<CrossCheckReport, CrossCheckError> {
+    let runs = backends.iter().map(|&backend| prove_on(backend, input, assumptions, elf, executor)).collect::<Result<Vec<_>, _>>()?;
+    compare_runs(&VerifierContext::default(), &runs, image_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use risc0_zkvm::{FakeReceipt, InnerReceipt, ReceiptClaim};
+
+    const IMAGE_ID: [u32; 8] = [5; 8];
+
+    fn run(backend: ProverBackend, image_id: [u32; 8], journal: Vec<u8>) -> ProverRun {
+        let receipt = Receipt::new(InnerReceipt::Fake(FakeReceipt::new(ReceiptClaim::ok(image_id, journal.clone()))), journal);
+        ProverRun { backend, receipt }
+    }
+
+    #[test]
+    fn test_backends_must_agree_on_the_journal() -> Result<(), CrossCheckError> {
+        assert_eq!(parse_backends("cpu, bonsai")?, vec![ProverBackend::Cpu, ProverBackend::Bonsai]);
+        assert!(matches!(parse_backends("cpu"), Err(CrossCheckError::TooFewBackends { count: 1 })));
+        assert!(matches!(parse_backends("cpu,tpu"), Err(CrossCheckError::Executor(ExecutorError::UnknownBackend { .. }))));
+
+        let ctx = VerifierContext::default().with_dev_mode(true);
+        let report = compare_runs(&ctx, &[run(ProverBackend::Cpu, IMAGE_ID, vec![1, 2, 3]), run(ProverBackend::Bonsai, IMAGE_ID, vec![1, 2, 3])], IMAGE_ID)?;
+        assert_eq!(report.backends, vec![ProverBackend::Cpu, ProverBackend::Bonsai]);
+
+        let diverged = [run(ProverBackend::Cpu, IMAGE_ID, vec![1, 2, 3]), run(ProverBackend::Bonsai, IMAGE_ID, vec![1, 9, 3])];
+        assert!(matches!(compare_runs(&ctx, &diverged, IMAGE_ID), Err(CrossCheckError::JournalMismatch { offset: 1, .. })));
+        // A receipt for another image fails verification before journals are compared
+        let other_image = [run(ProverBackend::Cpu, IMAGE_ID, vec![1]), run(ProverBackend::Bonsai, [6; 8], vec![1])];
+        assert!(matches!(compare_runs(&ctx, &other_image, IMAGE_ID), Err(CrossCheckError::Receipt { backend: ProverBackend::Bonsai, .. })));
+        Ok(())
+    }
+}
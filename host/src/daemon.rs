@@ -0,0 +1,867 @@
+// Long-running tally service.
+// `JobQueue` accepts tally jobs, proves them on worker threads and keeps every
+// job's status in a state directory: each job's input is written when it is
+// submitted, and `jobs.json` is checkpointed on every status change and at
+// shutdown. Proofs still running when the shutdown grace period ends are
+// abandoned and their workers joined, so nothing writes to the state directory
+// after `shutdown` returns. Reopening the directory requeues jobs that were queued or still
+// proving, so a restart (or a SIGTERM in the middle of an election) loses no
+// submitted work; an interrupted proof starts again from its input. Each proof
+// runs under the queue's `RetryPolicy` (see `job`), so a stuck prover times out
+// instead of holding its worker, and admins can cancel queued or running jobs.
+// The guest holds no key, so a final tally job's input carries the trustees'
+// decryption shares (`VoteTallyInput::decryption`); without them only interim
+// tallies, or tallies withheld below the anonymity set, prove.
+//
+// `route` maps the HTTP endpoints onto the queue, authorized with the `access`
+// roles: admins submit jobs, observers and admins read status and receipts. It
+// is independent of the HTTP library, so the binary's listener only moves
+// requests in and responses out. TLS is terminated in front.
+//
+// Ballots can be taken over HTTP too. An admin opens an election with the
+// tally input it will be counted under, less its ballots (`BallotBoxes`);
+// voters post ballots to it while it is open, each checked by
+// `intake::check_submission` and then admitted by the election's
+// `SubmissionGate`, and every submission lands in the election's audit log.
+// Closing the election queues the tally job over the accepted ballots.
+//
+// Observers can also follow an election while it runs: with a results
+// directory holding one interim publication directory per election (see
+// `interim`), `GET /results/{id}/snapshots` lists every interim tally proven
+// so far, with its timestamp, journal and receipt digests, and each receipt
+// can be fetched to verify the numbers it commits.
+//
+// With the `graphql` feature, `POST /graphql` answers read-only GraphQL queries
+// over the same jobs (see `graphql`), so a dashboard fetches the elections,
+// counts, receipts and audit log entries it shows in one request.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+use crate::access::{AccessError, Action, ApiKeyStore};
+use crate::address::normalize_ballot;
+use crate::audit_log::AuditLog;
+use crate::ballot_set::{ballot_leaf_hash, BallotSetCommitment};
+use crate::executor::ProvingStats;
+use crate::intake::{check_submission, IntakeConfig, IntakeError, SubmissionGate};
+use crate::job::{run_job, CancellationToken, JobError, RetryPolicy};
+use crate::interim::{InterimError, InterimPublisher};
+use crate::types::{EncryptedVote, VoteTallyInput};
+
+const CHECKPOINT_FILE: &str = "jobs.json";
+
+/// Largest job submission accepted over HTTP (a JSON `VoteTallyInput`).
+pub const MAX_JOB_BODY_BYTES: usize = 64 * 1024 * 1024;
+
+/// Largest GraphQL request body accepted over HTTP.
+#[cfg(feature = "graphql")]
+pub const MAX_GRAPHQL_BODY_BYTES: usize = 64 * 1024;
+
+#[derive(Error, Debug)]
+pub enum DaemonError {
+    #[error("State directory I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("State encoding failed: {reason}")]
+    Encoding { reason: String },
+    #[error("Shutting down, not accepting jobs")]
+    ShuttingDown,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Proving,
+    Succeeded {
+        option_counts: Vec<u32>,
+        total_votes: u32,
+        #[serde(with = "hex::serde")]
+        journal_digest: [u8; 32],
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        proving: Option<ProvingStats>,
+    },
+    Failed {
+        reason: String,
+    },
+    Cancelled,
+}
+
+/// What a successful run produces. The receipt is stored next to the job input.
+pub struct JobOutcome {
+    pub option_counts: Vec<u32>,
+    pub total_votes: u32,
+    pub journal_digest: [u8; 32],
+    pub receipt: Vec<u8>,
+    pub proving: Option<ProvingStats>,
+}
+
+/// Proves one job. Runs on a worker thread.
+pub type JobRunner = dyn Fn(&VoteTallyInput) -> Result<JobOutcome, String> + Send + Sync;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct JobRecord {
+    id: u64,
+    status: JobStatus,
+}
+
+struct State {
+    jobs: BTreeMap<u64, JobStatus>,
+    pending: VecDeque<u64>,
+    running: HashMap<u64, CancellationToken>,
+    interrupted: HashSet<u64>, // Abandoned at shutdown, requeued rather than cancelled
+    next_id: u64,
+    shutting_down: bool,
+}
+
+struct Shared {
+    dir: PathBuf,
+    state: Mutex<State>,
+    available: Condvar,
+    runner: Arc<JobRunner>,
+    policy: RetryPolicy,
+}
+
+pub struct JobQueue {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl JobQueue {
+    /// Open (or create) the state directory, requeue unfinished jobs and start `workers` threads.
+    pub fn open(dir: impl AsRef<Path>, workers: usize, runner: Box<JobRunner>, policy: RetryPolicy) -> Result<Self, DaemonError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        let records: Vec<JobRecord> = match fs::read(dir.join(CHECKPOINT_FILE)) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| DaemonError::Encoding { reason: e.to_string() })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut state = State { jobs: BTreeMap::new(), pending: VecDeque::new(), running: HashMap::new(), interrupted: HashSet::new(), next_id: 0, shutting_down: false };
+        for JobRecord { id, status } in records {
+            let status = match status {
+                JobStatus::Queued | JobStatus::Proving => {
+                    state.pending.push_back(id);
+                    JobStatus::Queued
+                }
+                finished => finished,
+            };
+            state.jobs.insert(id, status);
+            state.next_id = state.next_id.max(id + 1);
+        }
+
+        let shared = Arc::new(Shared { dir, state: Mutex::new(state), available: Condvar::new(), runner: Arc::from(runner), policy });
+        shared.checkpoint(&shared.lock())?;
+        let workers = (0..workers.max(1))
+            .map(|_| {
+                let shared = Arc::clone(&shared);
+                std::thread::spawn(move || shared.work())
+            })
+            .collect();
+        Ok(JobQueue { shared, workers })
+    }
+
+    /// Persist the input and queue a job for it.
+    pub fn submit(&self, input: &VoteTallyInput) -> Result<u64, DaemonError> {
+        let encoded = bincode::serialize(input).map_err(|e| DaemonError::Encoding { reason: e.to_string() })?;
+        let mut state = self.shared.lock();
+        if state.shutting_down {
+            return Err(DaemonError::ShuttingDown);
+        }
+        let id = state.next_id;
+        fs::write(self.shared.input_path(id), encoded)?;
+        state.next_id += 1;
+        state.jobs.insert(id, JobStatus::Queued);
+        state.pending.push_back(id);
+        self.shared.checkpoint(&state)?;
+        self.shared.available.notify_one();
+        Ok(id)
+    }
+
+    pub fn status(&self, id: u64) -> Option<JobStatus> {
+        self.shared.lock().jobs.get(&id).cloned()
+    }
+
+    pub fn jobs(&self) -> Vec<(u64, JobStatus)> {
+        self.shared.lock().jobs.iter().map(|(&id, status)| (id, status.clone())).collect()
+    }
+
+    /// Cancel a queued or proving job. Returns false if there is no such job
+    /// or it has already finished.
+    pub fn cancel(&self, id: u64) -> Result<bool, DaemonError> {
+        let mut state = self.shared.lock();
+        match state.jobs.get(&id) {
+            Some(JobStatus::Queued) => {
+                state.pending.retain(|&pending| pending != id);
+                state.jobs.insert(id, JobStatus::Cancelled);
+                self.shared.checkpoint(&state)?;
+                Ok(true)
+            }
+            // The worker records the cancellation when the job stops
+            Some(JobStatus::Proving) => Ok(state.running.get(&id).inspect(|token| token.cancel()).is_some()),
+            _ => Ok(false),
+        }
+    }
+
+    /// The stored receipt of a succeeded job.
+    pub fn receipt(&self, id: u64) -> Option<Vec<u8>> {
+        fs::read(self.shared.receipt_path(id)).ok()
+    }
+
+    /// A read-only handle on the jobs that can outlive a borrow of the queue.
+    pub fn store(&self) -> JobStore {
+        JobStore { shared: Arc::clone(&self.shared) }
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shared.lock().shutting_down
+    }
+
+    /// Stop accepting and starting jobs, checkpoint, then give jobs already
+    /// proving up to `grace` to finish. Proofs still running after that are
+    /// abandoned and every worker is joined. Returns how many jobs remain
+    /// unfinished; they are requeued when the directory is reopened.
+    pub fn shutdown(self, grace: Duration) -> Result<usize, DaemonError> {
+        {
+            let mut state = self.shared.lock();
+            state.shutting_down = true;
+            self.shared.checkpoint(&state)?;
+        }
+        self.shared.available.notify_all();
+
+        let deadline = Instant::now() + grace;
+        while self.workers.iter().any(|worker| !worker.is_finished()) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        {
+            let mut state = self.shared.lock();
+            let State { running, interrupted, .. } = &mut *state;
+            for (&id, token) in running.iter() {
+                token.cancel();
+                interrupted.insert(id);
+            }
+        }
+        // A cancelled attempt returns within the job poll interval
+        for worker in self.workers {
+            if worker.join().is_err() {
+                eprintln!("⚠️  [Daemon] A worker thread panicked");
+            }
+        }
+        let state = self.shared.lock();
+        self.shared.checkpoint(&state)?;
+        Ok(state.jobs.values().filter(|status| matches!(status, JobStatus::Queued | JobStatus::Proving)).count())
+    }
+}
+
+/// Read access to a queue's jobs, their inputs and receipts.
+#[derive(Clone)]
+pub struct JobStore {
+    shared: Arc<Shared>,
+}
+
+impl JobStore {
+    pub fn jobs(&self) -> Vec<(u64, JobStatus)> {
+        self.shared.lock().jobs.iter().map(|(&id, status)| (id, status.clone())).collect()
+    }
+
+    pub fn status(&self, id: u64) -> Option<JobStatus> {
+        self.shared.lock().jobs.get(&id).cloned()
+    }
+
+    /// The input a job was submitted with.
+    pub fn input(&self, id: u64) -> Option<VoteTallyInput> {
+        bincode::deserialize(&fs::read(self.shared.input_path(id)).ok()?).ok()
+    }
+
+    pub fn receipt(&self, id: u64) -> Option<Vec<u8>> {
+        fs::read(self.shared.receipt_path(id)).ok()
+    }
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn input_path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("job-{}.input", id))
+    }
+
+    fn receipt_path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("job-{}.receipt", id))
+    }
+
+    // Write jobs.json atomically (write, then rename over the old checkpoint)
+    fn checkpoint(&self, state: &State) -> Result<(), DaemonError> {
+        let records: Vec<JobRecord> =
+            state.jobs.iter().map(|(&id, status)| JobRecord { id, status: status.clone() }).collect();
+        let json = serde_json::to_vec_pretty(&records).map_err(|e| DaemonError::Encoding { reason: e.to_string() })?;
+        let staging = self.dir.join(format!("{}.tmp", CHECKPOINT_FILE));
+        fs::write(&staging, json)?;
+        fs::rename(staging, self.dir.join(CHECKPOINT_FILE))?;
+        Ok(())
+    }
+
+    fn work(&self) {
+        loop {
+            let (id, token) = {
+                let mut state = self.lock();
+                loop {
+                    if state.shutting_down {
+                        return;
+                    }
+                    if let Some(id) = state.pending.pop_front() {
+                        state.jobs.insert(id, JobStatus::Proving);
+                        let token = CancellationToken::new();
+                        state.running.insert(id, token.clone());
+                        break (id, token);
+                    }
+                    state = self.available.wait(state).unwrap_or_else(|poisoned| poisoned.into_inner());
+                }
+            };
+
+            let result = self.run(id, &token);
+            let mut state = self.lock();
+            state.running.remove(&id);
+            let status = match result {
+                Ok(outcome) => JobStatus::Succeeded {
+                    option_counts: outcome.option_counts,
+                    total_votes: outcome.total_votes,
+                    journal_digest: outcome.journal_digest,
+                    proving: outcome.proving,
+                },
+                Err(JobError::Cancelled) if state.interrupted.remove(&id) => JobStatus::Queued,
+                Err(JobError::Cancelled) => JobStatus::Cancelled,
+                Err(e) => JobStatus::Failed { reason: e.to_string() },
+            };
+            state.jobs.insert(id, status);
+            if let Err(e) = self.checkpoint(&state) {
+                eprintln!("⚠️  [Daemon] Failed to checkpoint job {}: {}", id, e);
+            }
+        }
+    }
+
+    fn run(&self, id: u64, token: &CancellationToken) -> Result<JobOutcome, JobError> {
+        let failed = |reason: String| JobError::Storage { reason };
+        let bytes = fs::read(self.input_path(id)).map_err(|e| failed(format!("job input unreadable: {}", e)))?;
+        let input: Arc<VoteTallyInput> = Arc::new(bincode::deserialize(&bytes).map_err(|e| failed(format!("job input corrupt: {}", e)))?);
+        let runner = Arc::clone(&self.runner);
+        let outcome = run_job(&self.policy, token, move || runner(&input))?;
+        fs::write(self.receipt_path(id), &outcome.receipt).map_err(|e| failed(format!("failed to store receipt: {}", e)))?;
+        Ok(outcome)
+    }
+}
+
+// An election taking ballots: the tally input it was opened with, whose ballots,
+// ballot set root and audit log are filled in when it closes
+struct BallotBox {
+    template: Option<VoteTallyInput>, // Taken for the tally job when the election closes
+    gate: SubmissionGate,
+    audit_log: AuditLog,
+    ballots: Vec<EncryptedVote>,
+}
+
+/// The elections the daemon takes ballots for, by id.
+pub struct BallotBoxes {
+    config: IntakeConfig,
+    boxes: Mutex<HashMap<String, BallotBox>>,
+}
+
+impl BallotBoxes {
+    /// Every election opened gets a `SubmissionGate` with `config`.
+    pub fn new(config: IntakeConfig) -> Self {
+        BallotBoxes { config, boxes: Mutex::new(HashMap::new()) }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, BallotBox>> {
+        self.boxes.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// One HTTP request, as `route` sees it.
+pub struct Request<'a> {
+    pub method: &'a str,
+    pub path: &'a str,
+    pub api_key: Option<&'a str>, // The bearer token, if any
+    pub source: IpAddr, // Peer address, for the ballot rate limits
+    pub body: &'a [u8],
+}
+
+/// An HTTP response: status code, content type and body.
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    fn json(status: u16, value: serde_json::Value) -> Self {
+        Response { status, content_type: "application/json", body: value.to_string().into_bytes() }
+    }
+
+    fn error(status: u16, message: impl ToString) -> Self {
+        Response::json(status, serde_json::json!({ "error": message.to_string() }))
+    }
+}
+
+/// Handle one request.
+///
+/// - `GET /health`: liveness, no key needed (503 while shutting down)
+/// - `POST /jobs`: queue a JSON `VoteTallyInput` (admin)
+/// - `GET /jobs`, `GET /jobs/{id}`: job status (observer or admin)
+/// - `POST /jobs/{id}/cancel`: cancel a queued or proving job (admin)
+/// - `GET /jobs/{id}/receipt`: bincode receipt of a succeeded job (observer or admin)
+/// - `POST /elections/{id}/open`: open election `id` for ballots, with the JSON `VoteTallyInput` to tally them under (admin)
+/// - `POST /elections/{id}/ballots`: cast a JSON `EncryptedVote` in an open election (voter)
+/// - `POST /elections/{id}/close`: stop taking ballots and queue the tally job (admin)
+/// - `GET /results/{id}/snapshots`: election `id`'s interim tallies (observer or admin)
+/// - `GET /results/{id}/snapshots/{sequence}/receipt`: bincode receipt of one of them
+/// - `POST /graphql`: a read-only GraphQL query (observer or admin; `graphql` feature)
+pub fn route(queue: &JobQueue, keys: &ApiKeyStore, ballot_boxes: &BallotBoxes, results_dir: Option<&Path>, request: &Request) -> Response {
+    let Request { method, path, api_key, source, body } = *request;
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    if let (&"GET", ["health"]) = (&method, segments.as_slice()) {
+        return match queue.is_shutting_down() {
+            true => Response::error(503, "shutting down"),
+            false => Response::json(200, serde_json::json!({ "status": "ok" })),
+        };
+    }
+
+    let action = match (method, segments.as_slice()) {
+        ("POST", ["elections", _, "ballots"]) => Action::SubmitBallot,
+        ("POST", ["elections", _, "open"]) => Action::OpenElection,
+        ("POST", ["elections", _, "close"]) => Action::CloseElection,
+        // GraphQL queries are posted but only read
+        ("POST", ["graphql"]) => Action::FetchReceipt,
+        ("POST", _) => Action::TriggerTally,
+        _ => Action::FetchReceipt,
+    };
+    match keys.authorize(api_key.unwrap_or_default(), action) {
+        Ok(_) => {}
+        Err(e @ AccessError::Unauthenticated) => return Response::error(401, e),
+        Err(e @ AccessError::Forbidden { .. }) => return Response::error(403, e),
+    }
+
+    let job = |id: &str| id.parse::<u64>().ok().and_then(|id| Some((id, queue.status(id)?)));
+    match (method, segments.as_slice()) {
+        ("POST", ["jobs"]) => {
+            if body.len() > MAX_JOB_BODY_BYTES {
+                return Response::error(413, format!("job body exceeds {} bytes", MAX_JOB_BODY_BYTES));
+            }
+            let input: VoteTallyInput = match serde_json::from_slice(body) {
+                Ok(input) => input,
+                Err(e) => return Response::error(400, format!("invalid job input: {}", e)),
+            };
+            match queue.submit(&input) {
+                Ok(id) => Response::json(202, serde_json::json!({ "id": id })),
+                Err(e @ DaemonError::ShuttingDown) => Response::error(503, e),
+                Err(e) => Response::error(500, e),
+            }
+        }
+        ("POST", ["elections", id, "open"]) => {
+            if body.len() > MAX_JOB_BODY_BYTES {
+                return Response::error(413, format!("election body exceeds {} bytes", MAX_JOB_BODY_BYTES));
+            }
+            let template: VoteTallyInput = match serde_json::from_slice(body) {
+                Ok(template) => template,
+                Err(e) => return Response::error(400, format!("invalid election input: {}", e)),
+            };
+            if !template.encrypted_votes.is_empty() {
+                return Response::error(400, "an election opens without ballots");
+            }
+            let mut boxes = ballot_boxes.lock();
+            if boxes.contains_key(*id) {
+                return Response::error(409, "election already opened");
+            }
+            let gate = SubmissionGate::new(ballot_boxes.config);
+            boxes.insert(id.to_string(), BallotBox { template: Some(template), gate, audit_log: AuditLog::new(), ballots: Vec::new() });
+            Response::json(201, serde_json::json!({ "election": id, "open": true }))
+        }
+        ("POST", ["elections", id, "ballots"]) => {
+            let mut ballot: EncryptedVote = match serde_json::from_slice(body) {
+                Ok(ballot) => ballot,
+                Err(e) => return Response::error(400, format!("invalid ballot: {}", e)),
+            };
+            let mut boxes = ballot_boxes.lock();
+            let Some(ballot_box) = boxes.get_mut(*id) else {
+                return Response::error(404, "no such election");
+            };
+            let BallotBox { template: Some(template), gate, audit_log, ballots } = ballot_box else {
+                return Response::error(409, "election is closed");
+            };
+            if let Err(e) = gate.check_size(&ballot) {
+                return Response::error(413, e);
+            }
+            // Addresses are checksum-normalized before the rate limits key on them
+            let checked = normalize_ballot(&mut ballot)
+                .map_err(|e| e.to_string())
+                .and_then(|()| check_submission(&ballot, &template.election, &template.registrar_public_key, &template.identity_roster));
+            if let Err(reason) = checked {
+                audit_log.record_rejected(&ballot, reason.clone());
+                return Response::error(422, reason);
+            }
+            if let Err(e) = gate.admit(source, &ballot, Instant::now()) {
+                audit_log.record_rejected(&ballot, e.to_string());
+                return match e {
+                    IntakeError::PayloadTooLarge { .. } => Response::error(413, e),
+                    _ => Response::error(429, e),
+                };
+            }
+            let tracking_code = ballot_leaf_hash(&ballot);
+            audit_log.record_accepted(&ballot);
+            ballots.push(ballot);
+            Response::json(202, serde_json::json!({ "election": id, "tracking_code": hex::encode(tracking_code) }))
+        }
+        ("POST", ["elections", id, "close"]) => {
+            let mut boxes = ballot_boxes.lock();
+            let Some(ballot_box) = boxes.get_mut(*id) else {
+                return Response::error(404, "no such election");
+            };
+            let Some(template) = ballot_box.template.take() else {
+                return Response::error(409, "election is already closed");
+            };
+            let input = VoteTallyInput {
+                ballot_set_root: BallotSetCommitment::from_ballots(&ballot_box.ballots).root(),
+                encrypted_votes: ballot_box.ballots.clone(),
+                audit_log_head: ballot_box.audit_log.head(),
+                audit_log: ballot_box.audit_log.records().to_vec(),
+                ..template
+            };
+            let submitted = queue.submit(&input);
+            // Still open if the job could not be queued
+            let ballots = input.encrypted_votes.len();
+            match submitted {
+                Ok(job) => Response::json(202, serde_json::json!({ "election": id, "ballots": ballots, "job": job })),
+                Err(e) => {
+                    ballot_box.template = Some(VoteTallyInput { encrypted_votes: Vec::new(), ..input });
+                    match e {
+                        DaemonError::ShuttingDown => Response::error(503, e),
+                        _ => Response::error(500, e),
+                    }
+                }
+            }
+        }
+        ("POST", ["jobs", id, "cancel"]) => match job(id) {
+            Some((id, _)) => match queue.cancel(id) {
+                Ok(true) => Response::json(202, serde_json::json!({ "id": id, "cancelling": true })),
+                Ok(false) => Response::error(409, "job has already finished"),
+                Err(e) => Response::error(500, e),
+            },
+            None => Response::error(404, "no such job"),
+        },
+        ("GET", ["jobs"]) => {
+            let jobs: Vec<_> = queue.jobs().into_iter().map(|(id, status)| serde_json::json!({ "id": id, "status": status })).collect();
+            Response::json(200, serde_json::json!(jobs))
+        }
+        ("GET", ["jobs", id]) => match job(id) {
+            Some((id, status)) => Response::json(200, serde_json::json!({ "id": id, "status": status })),
+            None => Response::error(404, "no such job"),
+        },
+        ("GET", ["jobs", id, "receipt"]) => match job(id).and_then(|(id, _)| queue.receipt(id)) {
+            Some(receipt) => Response { status: 200, content_type: "application/octet-stream", body: receipt },
+            None => Response::error(404, "no receipt for this job"),
+        },
+        ("GET", ["results", id, "snapshots"]) => match interim_publications(results_dir, id).and_then(|publisher| publisher.map(|p| p.snapshots()).transpose()) {
+            Ok(Some(snapshots)) => Response::json(200, serde_json::json!({ "id": id, "snapshots": snapshots })),
+            Ok(None) => Response::error(404, "no such election"),
+            Err(e) => Response::error(500, e),
+        },
+        ("GET", ["results", id, "snapshots", sequence, "receipt"]) => {
+            let Ok(sequence) = sequence.parse() else {
+                return Response::error(404, "no such snapshot");
+            };
+            match interim_publications(results_dir, id).and_then(|publisher| Ok(publisher.map(|p| p.receipt(sequence)).transpose()?.flatten())) {
+                Ok(Some(receipt)) => Response { status: 200, content_type: "application/octet-stream", body: receipt },
+                Ok(None) => Response::error(404, "no such snapshot"),
+                Err(e) => Response::error(500, e),
+            }
+        }
+        #[cfg(feature = "graphql")]
+        ("POST", ["graphql"]) => {
+            if body.len() > MAX_GRAPHQL_BODY_BYTES {
+                return Response::error(413, format!("query body exceeds {} bytes", MAX_GRAPHQL_BODY_BYTES));
+            }
+            match crate::graphql::execute(queue.store(), body) {
+                Ok(response) => Response::json(200, response),
+                Err(e) => Response::error(400, e),
+            }
+        }
+        _ => Response::error(404, "not found"),
+    }
+}
+
+// Election ids name directories, so only plain names are looked up
+fn interim_publications(results_dir: Option<&Path>, id: &str) -> Result<Option<InterimPublisher>, InterimError> {
+    let plain = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    match results_dir.map(|dir| dir.join(id)) {
+        Some(dir) if plain && dir.is_dir() => Ok(Some(InterimPublisher::open(dir)?)),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access::Role;
+    use crate::address::to_checksum_address;
+    use crate::ballot_set::ballot_message;
+    use crate::fhe_client::FheClient;
+    use crate::intake::RateLimit;
+    use crate::registrar::{CredentialRequest, Registrar};
+    use crate::scheme::TallyScheme;
+    use crate::types::{BallotAuthorization, ElectionContext, InterimTallyOutput, JournalProfile, VoteOption, INTERIM_MARKER};
+
+    fn test_input(ballots: usize) -> VoteTallyInput {
+        VoteTallyInput {
+            encrypted_votes: Vec::new(),
+            ballot_set_root: [ballots as u8; 32],
+            election_public_key: crate::fhe_client::FheClient::new().get_public_key().clone(),
+            registrar_public_key: [0u8; 32],
+            identity_roster: Vec::new(),
+            audit_log: Vec::new(),
+            audit_log_head: [0u8; 32],
+            chain_anchor: None,
+            journal_profile: JournalProfile::Full,
+            interim: false,
+            prior_tally: None,
+            eip712_domain: None,
+            election: ElectionContext::default(),
+            candidates: Vec::new(),
+            scheme: TallyScheme::default(),
+            min_anonymity_set: 0,
+            dp_noise: None,
+            revocations: Vec::new(),
+            electorate: None,
+            decryption: None,
+            mix: None,
+        }
+    }
+
+    // Succeeds with the root's first byte as the only count, fails on root 0
+    fn test_runner() -> Box<JobRunner> {
+        Box::new(|input: &VoteTallyInput| match input.ballot_set_root[0] {
+            0 => Err("empty election".to_string()),
+            count => Ok(JobOutcome {
+                option_counts: vec![count as u32],
+                total_votes: count as u32,
+                journal_digest: input.ballot_set_root,
+                receipt: vec![count],
+                proving: Some(ProvingStats { total_cycles: 1 << 20, user_cycles: u64::from(count), segments: 1, proving_ms: 5, receipt_bytes: 1 }),
+            }),
+        })
+    }
+
+    fn request<'a>(method: &'a str, path: &'a str, api_key: Option<&'a str>, body: &'a [u8]) -> Request<'a> {
+        Request { method, path, api_key, source: IpAddr::V4(std::net::Ipv4Addr::LOCALHOST), body }
+    }
+
+    fn wait_until_finished(queue: &JobQueue) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while queue.jobs().iter().any(|(_, status)| matches!(status, JobStatus::Queued | JobStatus::Proving)) {
+            assert!(Instant::now() < deadline, "jobs did not finish");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_jobs_survive_restart_and_are_served_by_role() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir().join(format!("fhe-voting-daemon-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        // A job that was proving when the previous process died
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("job-0.input"), bincode::serialize(&test_input(3))?)?;
+        fs::write(dir.join(CHECKPOINT_FILE), r#"[{"id":0,"status":{"state":"proving"}}]"#)?;
+
+        let queue = JobQueue::open(&dir, 2, test_runner(), RetryPolicy::default())?;
+        let failing = queue.submit(&test_input(0))?;
+        wait_until_finished(&queue);
+
+        let mut keys = ApiKeyStore::new();
+        let admin = keys.issue(Role::Admin);
+        let observer = keys.issue(Role::Observer);
+        let voter = keys.issue(Role::Voter);
+        let boxes = BallotBoxes::new(IntakeConfig::default());
+        let get = |path: &str, key: &str| route(&queue, &keys, &boxes, Some(&dir), &request("GET", path, Some(key), b""));
+
+        let status: serde_json::Value = serde_json::from_slice(&get("/jobs/0", &observer).body)?;
+        assert_eq!(status["status"]["state"], "succeeded");
+        assert_eq!((status["status"]["proving"]["user_cycles"].as_u64(), status["status"]["proving"]["segments"].as_u64()), (Some(3), Some(1)));
+        assert_eq!(get("/jobs/0/receipt", &observer).body, vec![3]);
+        assert_eq!(queue.status(failing), Some(JobStatus::Failed { reason: "Proving failed after 1 attempt(s): empty election".to_string() }));
+        assert_eq!(get("/jobs/9", &admin).status, 404);
+        assert_eq!(route(&queue, &keys, &boxes, None, &request("POST", "/jobs/0/cancel", Some(&admin), b"")).status, 409);
+        assert_eq!(route(&queue, &keys, &boxes, None, &request("POST", "/jobs/0/cancel", Some(&observer), b"")).status, 403);
+        assert_eq!(get("/jobs", &voter).status, 403);
+        assert_eq!(route(&queue, &keys, &boxes, None, &request("GET", "/jobs", None, b"")).status, 401);
+        assert_eq!(route(&queue, &keys, &boxes, None, &request("POST", "/jobs", Some(&observer), b"{}")).status, 403);
+        assert_eq!(route(&queue, &keys, &boxes, None, &request("POST", "/jobs", Some(&admin), b"{}")).status, 400);
+
+        // Interim tallies of election "treasury", published under the results directory
+        let interim = InterimTallyOutput {
+            marker: INTERIM_MARKER,
+            ballots: 2,
+            superseded_ballots: 0,
+            counted_weight: 0,
+            election: ElectionContext::default(),
+            ballot_set_root: [1u8; 32],
+            identity_root: [0u8; 32],
+            election_key_fingerprint: [0u8; 32],
+            audit_log_head: [2u8; 32],
+            voter_keys_digest: [0u8; 32],
+            prior_tally_digest: [0u8; 32],
+            encrypted_tally_frames: 3,
+        };
+        InterimPublisher::open(dir.join("treasury"))?.publish(&interim, 2, [4u8; 32], b"interim receipt")?;
+        let snapshots: serde_json::Value = serde_json::from_slice(&get("/results/treasury/snapshots", &observer).body)?;
+        let first = &snapshots["snapshots"][0];
+        assert_eq!((first["sequence"].as_u64(), first["ballots"].as_u64()), (Some(0), Some(2)));
+        assert_eq!(first["journal_digest"], hex::encode([4u8; 32]));
+        assert!(first["published_at"].as_u64().is_some() && first["receipt_digest"].is_string());
+        assert_eq!(get("/results/treasury/snapshots/0/receipt", &observer).body, b"interim receipt");
+        assert_eq!(get("/results/treasury/snapshots/1/receipt", &observer).status, 404);
+        assert_eq!(get("/results/../snapshots", &observer).status, 404);
+        assert_eq!(get("/results/treasury/snapshots", &voter).status, 403);
+
+        // One GraphQL query for the fields a dashboard shows, under the observer role
+        #[cfg(feature = "graphql")]
+        {
+            let query = br#"{"query": "{ elections { id state failureReason ballots result { counts totalVotes } } election(id: 0) { receipt auditLog(limit: 1) { position accepted } } }"}"#;
+            let post = |key: &str, body: &[u8]| route(&queue, &keys, &boxes, None, &request("POST", "/graphql", Some(key), body));
+            let response: serde_json::Value = serde_json::from_slice(&post(&observer, query).body)?;
+            let elections = &response["data"]["elections"];
+            assert_eq!((elections[0]["state"].as_str(), elections[0]["ballots"].as_u64()), (Some("succeeded"), Some(0)));
+            assert_eq!(elections[0]["result"]["counts"], serde_json::json!([3]));
+            assert!(elections[1]["result"].is_null() && elections[1]["failureReason"].as_str().is_some_and(|r| r.contains("empty election")));
+            assert_eq!(response["data"]["election"]["receipt"], "03");
+            assert_eq!(response["data"]["election"]["auditLog"], serde_json::json!([]));
+            let invalid: serde_json::Value = serde_json::from_slice(&post(&observer, br#"{"query": "{ elections { nope } }"}"#).body)?;
+            assert!(invalid["errors"][0]["message"].is_string());
+            assert_eq!(post(&observer, b"not json").status, 400);
+            assert_eq!(post(&voter, query).status, 403);
+        }
+
+        let body = serde_json::to_vec(&test_input(5))?;
+        assert_eq!(route(&queue, &keys, &boxes, None, &request("POST", "/jobs", Some(&admin), &body)).status, 202);
+        wait_until_finished(&queue);
+        assert_eq!(queue.shutdown(Duration::from_secs(10))?, 0);
+        let records: Vec<JobRecord> = serde_json::from_slice(&fs::read(dir.join(CHECKPOINT_FILE))?)?;
+        assert_eq!(records.len(), 3);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_ballots_are_cast_by_voters_and_tallied_when_admins_close() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir().join(format!("fhe-voting-daemon-ballots-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let queue = JobQueue::open(&dir, 1, test_runner(), RetryPolicy::default())?;
+        let mut keys = ApiKeyStore::new();
+        let admin = keys.issue(Role::Admin);
+        let observer = keys.issue(Role::Observer);
+        let voter = keys.issue(Role::Voter);
+        let per_voter = RateLimit { max_requests: 1, window: Duration::from_secs(60) };
+        let boxes = BallotBoxes::new(IntakeConfig { per_voter, ..IntakeConfig::default() });
+        let post = |path: &str, key: Option<&str>, body: &[u8]| route(&queue, &keys, &boxes, None, &request("POST", path, key, body)).status;
+
+        // One registered voter, holding a registrar credential
+        let voter_address = to_checksum_address(&[7u8; 20]);
+        let mut registrar = Registrar::new([voter_address.clone()]);
+        let template = VoteTallyInput { registrar_public_key: registrar.public_key(), ..test_input(1) };
+        let (session, nonce_commitment) = registrar.open_session(&voter_address)?;
+        let (credential_request, blinded_challenge) = CredentialRequest::new(&registrar.public_key(), &nonce_commitment)?;
+        let credential_key = credential_request.unblind(registrar.sign_blinded(session, blinded_challenge)?)?;
+        let client = FheClient::with_public_key(template.election_public_key.clone());
+        let cast = |nonce: u8| -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+            let encrypted_vote_vector = client.encrypt_vote_vector(VoteOption::Option2)?;
+            let message = ballot_message(&template.election.election_id, &voter_address, &[nonce; 32], &encrypted_vote_vector, u64::from(nonce));
+            Ok(serde_json::to_vec(&EncryptedVote {
+                voter_address: voter_address.clone(),
+                election_id: template.election.election_id,
+                nonce: [nonce; 32],
+                encrypted_vote_vector,
+                signature: String::new(),
+                authorization: BallotAuthorization::Credential {
+                    credential: credential_key.credential().clone(),
+                    ballot_signature: credential_key.sign_ballot(&message),
+                },
+                sequence: u64::from(nonce),
+                actual_choice: VoteOption::Option2,
+            })?)
+        };
+        let election = serde_json::to_vec(&template)?;
+
+        // Only admins open an election, once
+        assert_eq!(post("/elections/board/open", Some(&observer), &election), 403);
+        assert_eq!(post("/elections/board/open", Some(&voter), &election), 403);
+        assert_eq!(post("/elections/board/open", Some(&admin), &election), 201);
+        assert_eq!(post("/elections/board/open", Some(&admin), &election), 409);
+
+        // Only voters cast ballots; a forged ballot is rejected before the voter's rate limit
+        let ballot = cast(0)?;
+        assert_eq!(post("/elections/board/ballots", Some(&admin), &ballot), 403);
+        assert_eq!(post("/elections/board/ballots", Some(&observer), &ballot), 403);
+        assert_eq!(post("/elections/board/ballots", None, &ballot), 401);
+        assert_eq!(post("/elections/other/ballots", Some(&voter), &ballot), 404);
+        let mut forged: EncryptedVote = serde_json::from_slice(&ballot)?;
+        forged.nonce = [9u8; 32];
+        assert_eq!(post("/elections/board/ballots", Some(&voter), &serde_json::to_vec(&forged)?), 422);
+        assert_eq!(post("/elections/board/ballots", Some(&voter), &ballot), 202);
+        assert_eq!(post("/elections/board/ballots", Some(&voter), &cast(1)?), 429);
+
+        // Only admins close it, which queues the tally of the accepted ballots
+        assert_eq!(post("/elections/board/close", Some(&voter), b""), 403);
+        assert_eq!(post("/elections/board/close", Some(&observer), b""), 403);
+        let closed: serde_json::Value = serde_json::from_slice(&route(&queue, &keys, &boxes, None, &request("POST", "/elections/board/close", Some(&admin), b"")).body)?;
+        assert_eq!(closed["ballots"].as_u64(), Some(1));
+        assert_eq!(post("/elections/board/close", Some(&admin), b""), 409);
+        assert_eq!(post("/elections/board/ballots", Some(&voter), &ballot), 409);
+
+        let job = closed["job"].as_u64().ok_or("no job queued")?;
+        let input = queue.store().input(job).ok_or("no job input")?;
+        assert_eq!(input.encrypted_votes.len(), 1);
+        assert_eq!(input.ballot_set_root, BallotSetCommitment::from_ballots(&input.encrypted_votes).root());
+        let accepted: Vec<bool> = input.audit_log.iter().map(|record| record.accepted).collect();
+        assert_eq!(accepted, [false, true, false]);
+        assert_eq!(input.audit_log_head, AuditLog::from_records(input.audit_log.clone()).head());
+        assert_eq!(input.registrar_public_key, template.registrar_public_key);
+
+        wait_until_finished(&queue);
+        queue.shutdown(Duration::from_secs(10))?;
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_shutdown_abandons_proofs_past_the_grace_period() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir().join(format!("fhe-voting-daemon-grace-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let stuck: Box<JobRunner> = Box::new(|_: &VoteTallyInput| {
+            std::thread::sleep(Duration::from_secs(60));
+            Err("unreachable".to_string())
+        });
+        let queue = JobQueue::open(&dir, 1, stuck, RetryPolicy::default())?;
+        let id = queue.submit(&test_input(3))?;
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while queue.status(id) != Some(JobStatus::Proving) {
+            assert!(Instant::now() < deadline, "job did not start");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let started = Instant::now();
+        assert_eq!(queue.shutdown(Duration::from_millis(100))?, 1);
+        assert!(started.elapsed() < Duration::from_secs(10));
+        let records: Vec<JobRecord> = serde_json::from_slice(&fs::read(dir.join(CHECKPOINT_FILE))?)?;
+        assert_eq!(records[0].status, JobStatus::Queued);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,494 @@
+// Verifiable trustee decryption shares.
+// Once the DKG completes, each trustee publishes a Pedersen commitment to every
+// coefficient of its key share (its `ShareVerificationKey`). A partial decryption
+// carries the trustee's key term for one ciphertext plus a Schnorr proof that the
+// term was computed from the committed key share, so the combiner - or anyone
+// holding the published verification keys - rejects a corrupted share before it
+// can touch the announced result.
+//
+// A key term is the trustee's share of (c1 * s)[0]: its key share weighted by
+// `key_term_mask`. Lagrange-combined, the terms give (c1 * s)[0] mod t, and the
+// plaintext is the descaled c0 minus that, so without `threshold` shares c0
+// decodes only to the masked value, and a wrong key term to a wrong count.
+// Real BFV: m = round(t/q * (c0 - sum_i lambda_i * <c1, s_i>)) mod q, with
+// smudging noise added to every share; here the terms combine mod t, unsmudged.
+//
+// A `DecryptionProof` bundles an announced plaintext with the shares behind it.
+// Checking it shows that every share's key term came from its trustee's
+// committed key share, for this ciphertext, and that the announced plaintext is
+// what the ciphertext decrypts to with the combined key term. Decryptions made
+// without the trustees (the challenge guest's) carry no `DecryptionProof`.
+//
+// The tally guest holds no key either: a final tally's input carries the
+// trustees' shares of its encrypted tallies (`trustee_decryption`), which the
+// guest checks the same way before decrypting, committing the `trustees_digest`
+// of the verification keys it checked them against. The encrypted tallies are
+// deterministic in the input, so the trustees decrypt them before the final
+// proof: the same input is first proven as an interim tally, and the trustees
+// share only the encrypted tallies of a receipt that verifies against the
+// tally guest (`ProvenInterimTallies`).
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use risc0_zkvm::sha::{Digest, Impl, Sha256};
+use risc0_zkvm::{Receipt, VerifierContext};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+use crate::dkg::{lagrange_coefficient, KeyShare};
+use crate::fhe_client::{decode_scaled_plaintext, key_term_mask, Cipher, FheClientError, Signed, PLAINTEXT_MODULUS};
+use crate::journal::{read_interim_journal, InterimJournal, JournalError};
+use crate::registrar::wide_hash;
+use crate::types::InterimTallyOutput;
+pub use crate::types::{PartialDecryption, ShareProof, ShareVerificationKey, TrusteeDecryption};
+
+// Domain separation tag (must match guest implementation)
+const TRUSTEES_TAG: u8 = 0x1d;
+
+#[derive(Error, Debug)]
+pub enum DecryptionShareError {
+    #[error("Invalid tally ciphertext: {0}")]
+    Ciphertext(#[from] FheClientError),
+    #[error("Malformed verification key for trustee {index}")]
+    MalformedVerificationKey { index: u32 },
+    #[error("No verification key published for trustee {index}")]
+    UnknownTrustee { index: u32 },
+    #[error("Share from trustee {index} was computed for a different ciphertext")]
+    CiphertextMismatch { index: u32 },
+    #[error("Invalid decryption proof from trustee {index}")]
+    InvalidProof { index: u32 },
+    #[error("Duplicate decryption share from trustee {index}")]
+    DuplicateShare { index: u32 },
+    #[error("Not enough valid decryption shares: have {have}, need {need}")]
+    NotEnoughShares { have: usize, need: usize },
+    #[error("Announced plaintext {announced} does not match the ciphertext's decoding {decrypted}")]
+    PlaintextMismatch { announced: u64, decrypted: u64 },
+    #[error("Decryption proof was issued for a different ciphertext")]
+    ProofCiphertextMismatch,
+    #[error("Interim tally receipt does not verify: {reason}")]
+    UnprovenTallies { reason: String },
+    #[error("Invalid interim tally journal: {0}")]
+    Journal(#[from] JournalError),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CombinedDecryption {
+    pub plaintext: u64,
+    pub key_term: u64, // sum_i lambda_i * k_i = (c1 * s)[0] mod PLAINTEXT_MODULUS
+    pub trustees: Vec<u32>,
+}
+
+/// Trustee-side state: the key share plus the Pedersen blinding factors.
+pub struct TrusteeDecryptor {
+    key_share: KeyShare,
+    blinding: Vec<Scalar>,
+}
+
+impl TrusteeDecryptor {
+    /// Commit to the key share; the returned verification key is published.
+    pub fn new(key_share: KeyShare) -> (Self, ShareVerificationKey) {
+        let mut rng = rand::thread_rng();
+        let blinding: Vec<Scalar> = (0..key_share.secret_data.len()).map(|_| Scalar::random(&mut rng)).collect();
+        let h = pedersen_h();
+
+        let coefficient_commitments = key_share
+            .secret_data
+            .iter()
+            .zip(&blinding)
+            .map(|(&s, r)| (Scalar::from(s) * RISTRETTO_BASEPOINT_POINT + r * h).compress().to_bytes())
+            .collect();
+
+        let verification_key = ShareVerificationKey { index: key_share.index, coefficient_commitments };
+        (TrusteeDecryptor { key_share, blinding }, verification_key)
+    }
+
+    pub fn index(&self) -> u32 {
+        self.key_share.index
+    }
+
+    pub fn partial_decrypt(&self, ciphertext: &[u8]) -> Result<PartialDecryption, DecryptionShareError> {
+        let cipher = Cipher::<Signed>::deserialize(ciphertext)?;
+        let mask = key_term_mask(&cipher);
+
+        // Integer inner product: every term is < 2^34, so the sum fits comfortably in u64
+        let key_term: u64 = mask.iter().zip(&self.key_share.secret_data).map(|(&c, &s)| c * s).sum();
+        let blinding_term: Scalar = mask.iter().zip(&self.blinding).map(|(&c, r)| Scalar::from(c) * r).sum();
+
+        // Schnorr proof of knowledge of `blinding_term` with C - key_term*G = blinding_term*H
+        let h = pedersen_h();
+        let nonce = Scalar::random(&mut rand::thread_rng());
+        let nonce_commitment = (nonce * h).compress();
+        let ciphertext_digest = ciphertext_digest(ciphertext);
+        let challenge = proof_challenge(self.index(), &ciphertext_digest, key_term, &nonce_commitment);
+        let response = nonce + challenge * blinding_term;
+
+        Ok(PartialDecryption {
+            trustee_index: self.index(),
+            ciphertext_digest,
+            key_term,
+            proof: ShareProof {
+                nonce_commitment: nonce_commitment.to_bytes(),
+                response: response.to_bytes(),
+            },
+        })
+    }
+}
+
+/// Check one trustee's share against its published verification key.
+pub fn verify_partial_decryption(
+    partial: &PartialDecryption,
+    verification_key: &ShareVerificationKey,
+    ciphertext: &[u8],
+) -> Result<(), DecryptionShareError> {
+    let index = partial.trustee_index;
+    if verification_key.index != index {
+        return Err(DecryptionShareError::UnknownTrustee { index });
+    }
+    if partial.ciphertext_digest != ciphertext_digest(ciphertext) {
+        return Err(DecryptionShareError::CiphertextMismatch { index });
+    }
+
+    let cipher = Cipher::<Signed>::deserialize(ciphertext)?;
+    let mask = key_term_mask(&cipher);
+    if verification_key.coefficient_commitments.len() != mask.len() {
+        return Err(DecryptionShareError::MalformedVerificationKey { index });
+    }
+
+    let mut combined = RistrettoPoint::default();
+    for (&c, bytes) in mask.iter().zip(&verification_key.coefficient_commitments) {
+        let point = CompressedRistretto(*bytes)
+            .decompress()
+            .ok_or(DecryptionShareError::MalformedVerificationKey { index })?;
+        combined += Scalar::from(c) * point;
+    }
+    let target = combined - Scalar::from(partial.key_term) * RISTRETTO_BASEPOINT_POINT;
+
+    let nonce_commitment = CompressedRistretto(partial.proof.nonce_commitment);
+    let nonce_point = nonce_commitment.decompress().ok_or(DecryptionShareError::InvalidProof { index })?;
+    let response = Option::<Scalar>::from(Scalar::from_canonical_bytes(partial.proof.response))
+        .ok_or(DecryptionShareError::InvalidProof { index })?;
+    let challenge = proof_challenge(index, &partial.ciphertext_digest, partial.key_term, &nonce_commitment);
+
+    if response * pedersen_h() != nonce_point + challenge * target {
+        return Err(DecryptionShareError::InvalidProof { index });
+    }
+    Ok(())
+}
+
+/// Verify every share, naming the first trustee whose share fails, then
+/// combine `threshold` of them.
+pub fn combine_partial_decryptions(
+    ciphertext: &[u8],
+    partials: &[PartialDecryption],
+    verification_keys: &[ShareVerificationKey],
+    threshold: u32,
+) -> Result<CombinedDecryption, DecryptionShareError> {
+    let mut accepted: Vec<&PartialDecryption> = Vec::new();
+    for partial in partials {
+        let index = partial.trustee_index;
+        if accepted.iter().any(|p| p.trustee_index == index) {
+            return Err(DecryptionShareError::DuplicateShare { index });
+        }
+        let verification_key = verification_keys
+            .iter()
+            .find(|vk| vk.index == index)
+            .ok_or(DecryptionShareError::UnknownTrustee { index })?;
+        verify_partial_decryption(partial, verification_key, ciphertext)?;
+        accepted.push(partial);
+    }
+
+    if accepted.len() < threshold as usize {
+        return Err(DecryptionShareError::NotEnoughShares { have: accepted.len(), need: threshold as usize });
+    }
+    accepted.truncate(threshold as usize);
+
+    let trustees: Vec<u32> = accepted.iter().map(|p| p.trustee_index).collect();
+    let key_term = accepted.iter().fold(0u64, |acc, p| {
+        let lambda = lagrange_coefficient(p.trustee_index, &trustees);
+        (acc + lambda * (p.key_term % PLAINTEXT_MODULUS)) % PLAINTEXT_MODULUS
+    });
+
+    // round(c0 / Δ) - (c1 * s)[0] (matches guest `decrypt_combined`)
+    let cipher = Cipher::<Signed>::deserialize(ciphertext)?;
+    Ok(CombinedDecryption {
+        plaintext: (decode_scaled_plaintext(cipher.ciphertext_data[0]) + PLAINTEXT_MODULUS - key_term) % PLAINTEXT_MODULUS,
+        key_term,
+        trustees,
+    })
+}
+
+/// A tally ciphertext's announced plaintext with the trustee shares it was
+/// decrypted with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecryptionProof {
+    pub ciphertext_digest: [u8; 32],
+    pub plaintext: u64,
+    pub partials: Vec<PartialDecryption>,
+}
+
+/// Combine the shares and package the result with the shares that produced it.
+pub fn prove_decryption(
+    ciphertext: &[u8],
+    partials: Vec<PartialDecryption>,
+    verification_keys: &[ShareVerificationKey],
+    threshold: u32,
+) -> Result<DecryptionProof, DecryptionShareError> {
+    let combined = combine_partial_decryptions(ciphertext, &partials, verification_keys, threshold)?;
+    Ok(DecryptionProof {
+        ciphertext_digest: ciphertext_digest(ciphertext),
+        plaintext: combined.plaintext,
+        partials,
+    })
+}
+
+/// Check the shares against the verification keys and the announced plaintext
+/// against the committed tally ciphertext's decryption with them.
+pub fn verify_decryption_proof(
+    proof: &DecryptionProof,
+    ciphertext: &[u8],
+    verification_keys: &[ShareVerificationKey],
+    threshold: u32,
+) -> Result<CombinedDecryption, DecryptionShareError> {
+    if proof.ciphertext_digest != ciphertext_digest(ciphertext) {
+        return Err(DecryptionShareError::ProofCiphertextMismatch);
+    }
+    let combined = combine_partial_decryptions(ciphertext, &proof.partials, verification_keys, threshold)?;
+    if combined.plaintext != proof.plaintext {
+        return Err(DecryptionShareError::PlaintextMismatch {
+            announced: proof.plaintext,
+            decrypted: combined.plaintext,
+        });
+    }
+    Ok(combined)
+}
+
+/// H(tag || threshold || count || per key: index || count || commitments), the
+/// digest a final tally commits (must match guest `trustees_digest`).
+pub fn trustees_digest(threshold: u32, verification_keys: &[ShareVerificationKey]) -> [u8; 32] {
+    let mut buf = vec![TRUSTEES_TAG];
+    buf.extend_from_slice(&threshold.to_le_bytes());
+    buf.extend_from_slice(&(verification_keys.len() as u32).to_le_bytes());
+    for key in verification_keys {
+        buf.extend_from_slice(&key.index.to_le_bytes());
+        buf.extend_from_slice(&(key.coefficient_commitments.len() as u32).to_le_bytes());
+        for commitment in &key.coefficient_commitments {
+            buf.extend_from_slice(commitment);
+        }
+    }
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// The encrypted tallies of an interim tally receipt that verified. Only
+/// `verify` builds one, so trustees share nothing the tally guest did not prove.
+#[derive(Debug, Clone)]
+pub struct ProvenInterimTallies {
+    output: InterimTallyOutput,
+    encrypted_tallies: Vec<Vec<u8>>,
+}
+
+impl ProvenInterimTallies {
+    /// Verify `receipt` against the tally guest's `image_id` and read its interim journal.
+    pub fn verify(ctx: &VerifierContext, receipt: &Receipt, image_id: impl Into<Digest>) -> Result<Self, DecryptionShareError> {
+        receipt
+            .verify_with_context(ctx, image_id)
+            .map_err(|e| DecryptionShareError::UnprovenTallies { reason: e.to_string() })?;
+        let InterimJournal { output, encrypted_tallies } = read_interim_journal(&receipt.journal.bytes)?;
+        Ok(ProvenInterimTallies { output, encrypted_tallies })
+    }
+
+    pub fn output(&self) -> &InterimTallyOutput {
+        &self.output
+    }
+
+    pub fn encrypted_tallies(&self) -> &[Vec<u8>] {
+        &self.encrypted_tallies
+    }
+}
+
+/// Shares of every proven encrypted tally from the `participating` trustees,
+/// for the guest to check and combine (`VoteTallyInput::decryption`).
+pub fn trustee_decryption(
+    participating: &[TrusteeDecryptor],
+    verification_keys: &[ShareVerificationKey],
+    threshold: u32,
+    tallies: &ProvenInterimTallies,
+) -> Result<TrusteeDecryption, DecryptionShareError> {
+    if participating.len() < threshold as usize {
+        return Err(DecryptionShareError::NotEnoughShares { have: participating.len(), need: threshold as usize });
+    }
+    let shares = tallies
+        .encrypted_tallies
+        .iter()
+        .map(|ciphertext| participating.iter().map(|trustee| trustee.partial_decrypt(ciphertext)).collect())
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(TrusteeDecryption { threshold, verification_keys: verification_keys.to_vec(), shares })
+}
+
+fn pedersen_h() -> RistrettoPoint {
+    RistrettoPoint::from_uniform_bytes(&wide_hash(b"fhe-voting/pedersen/h"))
+}
+
+fn ciphertext_digest(ciphertext: &[u8]) -> [u8; 32] {
+    (*Impl::hash_bytes(ciphertext)).into()
+}
+
+fn proof_challenge(index: u32, digest: &[u8; 32], key_term: u64, nonce_commitment: &CompressedRistretto) -> Scalar {
+    let mut buf = b"fhe-voting/decryption-share".to_vec();
+    buf.extend_from_slice(&index.to_le_bytes());
+    buf.extend_from_slice(digest);
+    buf.extend_from_slice(&key_term.to_le_bytes());
+    buf.extend_from_slice(nonce_commitment.as_bytes());
+    Scalar::from_bytes_mod_order_wide(&wide_hash(&buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dkg::{run_in_process, DkgConfig};
+    use crate::fhe_client::{FheClient, BOUND_HEADER_LEN};
+    use crate::journal::chain_frame;
+    use crate::types::{ElectionContext, JournalFrame, VoteOption, INTERIM_MARKER};
+    use risc0_zkvm::{FakeReceipt, InnerReceipt, ReceiptClaim};
+
+    const TALLY_IMAGE_ID: [u32; 8] = [3; 8];
+
+    // An interim tally receipt for `encrypted_tallies`; fake, so it verifies only in dev mode
+    fn interim_receipt(encrypted_tallies: &[Vec<u8>], image_id: [u32; 8]) -> Receipt {
+        let output = InterimTallyOutput {
+            marker: INTERIM_MARKER,
+            ballots: 1,
+            superseded_ballots: 0,
+            counted_weight: 1,
+            election: ElectionContext::default(),
+            ballot_set_root: [1u8; 32],
+            identity_root: [2u8; 32],
+            election_key_fingerprint: [4u8; 32],
+            audit_log_head: [3u8; 32],
+            voter_keys_digest: [5u8; 32],
+            prior_tally_digest: [0u8; 32],
+            encrypted_tally_frames: encrypted_tallies.len() as u32,
+        };
+        let mut words = risc0_zkvm::serde::to_vec(&output).unwrap();
+        let mut previous = [0u8; 32];
+        for (index, payload) in encrypted_tallies.iter().enumerate() {
+            let chunk_hash = chain_frame(&previous, index as u32, payload);
+            words.extend(risc0_zkvm::serde::to_vec(&JournalFrame { index: index as u32, payload: payload.clone(), chunk_hash }).unwrap());
+            previous = chunk_hash;
+        }
+        let journal: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+        Receipt::new(InnerReceipt::Fake(FakeReceipt::new(ReceiptClaim::ok(image_id, journal.clone()))), journal)
+    }
+
+    #[test]
+    fn test_threshold_decryption_with_corrupted_share() -> Result<(), Box<dyn std::error::Error>> {
+        let (public_key, outputs) = run_in_process(DkgConfig::threshold(2, 3)?)?;
+        let client = FheClient::with_public_key(public_key);
+        // Trustees decrypt tallies, which carry no key binding
+        let ciphertext = client.encrypt_vote_vector(VoteOption::Option2)?.remove(1).split_off(BOUND_HEADER_LEN);
+
+        let key_shares: Vec<KeyShare> = outputs.into_iter().map(|o| o.key_share).collect();
+        let (trustees, keys): (Vec<_>, Vec<_>) = key_shares.iter().cloned().map(TrusteeDecryptor::new).unzip();
+        let mut partials = vec![trustees[0].partial_decrypt(&ciphertext)?, trustees[2].partial_decrypt(&ciphertext)?];
+
+        let combined = combine_partial_decryptions(&ciphertext, &partials, &keys, 2)?;
+        assert_eq!(combined.plaintext, 1);
+        assert_eq!(combined.trustees, vec![1, 3]);
+        // c0 alone decodes to the masked value, not the plaintext
+        let cipher = Cipher::<Signed>::deserialize(&ciphertext)?;
+        assert_eq!(decode_scaled_plaintext(cipher.ciphertext_data[0]), (1 + combined.key_term) % PLAINTEXT_MODULUS);
+
+        // A share from an altered key share, verified against a key committing to
+        // it, carries a wrong key term and decrypts to a wrong count
+        let position = key_term_mask(&cipher).iter().position(|&m| m != 0).ok_or("mask is zero")?;
+        let mut altered = key_shares[2].clone();
+        altered.secret_data[position] = (altered.secret_data[position] + 1) % PLAINTEXT_MODULUS;
+        let (impostor, impostor_key) = TrusteeDecryptor::new(altered);
+        let impostor_keys = [keys[0].clone(), keys[1].clone(), impostor_key];
+        let wrong = combine_partial_decryptions(&ciphertext, &[partials[0].clone(), impostor.partial_decrypt(&ciphertext)?], &impostor_keys, 2)?;
+        assert_ne!(wrong.key_term, combined.key_term);
+        assert_ne!(wrong.plaintext, 1);
+
+        // Against the published keys, a tampered key term is rejected outright
+        partials[1].key_term += 1;
+        assert!(matches!(
+            combine_partial_decryptions(&ciphertext, &partials, &keys, 2),
+            Err(DecryptionShareError::InvalidProof { index: 3 })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decryption_proof_rejects_wrong_announcement() -> Result<(), Box<dyn std::error::Error>> {
+        let (public_key, outputs) = run_in_process(DkgConfig::threshold(2, 3)?)?;
+        let client = FheClient::with_public_key(public_key);
+        let mut vector: Vec<Vec<u8>> = client
+            .encrypt_vote_vector(VoteOption::Option1)?
+            .into_iter()
+            .map(|mut ciphertext| ciphertext.split_off(BOUND_HEADER_LEN))
+            .collect();
+
+        let (trustees, keys): (Vec<_>, Vec<_>) = outputs
+            .into_iter()
+            .map(|o| TrusteeDecryptor::new(o.key_share))
+            .unzip();
+        let partials = trustees[..2]
+            .iter()
+            .map(|t| t.partial_decrypt(&vector[0]))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut proof = prove_decryption(&vector[0], partials, &keys, 2)?;
+        assert_eq!(verify_decryption_proof(&proof, &vector[0], &keys, 2)?.plaintext, 1);
+
+        assert!(matches!(
+            verify_decryption_proof(&proof, &vector.remove(1), &keys, 2),
+            Err(DecryptionShareError::ProofCiphertextMismatch)
+        ));
+        proof.plaintext = 2;
+        assert!(matches!(
+            verify_decryption_proof(&proof, &vector[0], &keys, 2),
+            Err(DecryptionShareError::PlaintextMismatch { announced: 2, decrypted: 1 })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_trustee_decryption_for_the_guest() -> Result<(), Box<dyn std::error::Error>> {
+        let (public_key, outputs) = run_in_process(DkgConfig::threshold(2, 3)?)?;
+        let client = FheClient::with_public_key(public_key);
+        let tallies: Vec<Vec<u8>> = client
+            .encrypt_vote_vector(VoteOption::Option3)?
+            .into_iter()
+            .map(|mut ciphertext| ciphertext.split_off(BOUND_HEADER_LEN))
+            .collect();
+        let (trustees, keys): (Vec<_>, Vec<_>) = outputs
+            .into_iter()
+            .map(|o| TrusteeDecryptor::new(o.key_share))
+            .unzip();
+
+        // Trustees share only tallies a receipt of the tally guest proves
+        let receipt = interim_receipt(&tallies, TALLY_IMAGE_ID);
+        let dev_mode = VerifierContext::default().with_dev_mode(true);
+        assert!(matches!(
+            ProvenInterimTallies::verify(&VerifierContext::default().with_dev_mode(false), &receipt, TALLY_IMAGE_ID),
+            Err(DecryptionShareError::UnprovenTallies { .. })
+        ));
+        assert!(matches!(ProvenInterimTallies::verify(&dev_mode, &receipt, [4u32; 8]), Err(DecryptionShareError::UnprovenTallies { .. })));
+        let proven = ProvenInterimTallies::verify(&dev_mode, &receipt, TALLY_IMAGE_ID)?;
+        assert_eq!(proven.encrypted_tallies(), tallies.as_slice());
+
+        // One list of shares per tally, each enough to combine
+        let decryption = trustee_decryption(&trustees[1..], &keys, 2, &proven)?;
+        assert_eq!(decryption.shares.len(), tallies.len());
+        for ((ciphertext, shares), expected) in tallies.iter().zip(&decryption.shares).zip([0, 0, 1]) {
+            assert_eq!(combine_partial_decryptions(ciphertext, shares, &decryption.verification_keys, 2)?.plaintext, expected);
+        }
+        assert!(matches!(trustee_decryption(&trustees[..1], &keys, 2, &proven), Err(DecryptionShareError::NotEnoughShares { have: 1, need: 2 })));
+
+        // The committed digest names the threshold and every trustee's key
+        assert_eq!(trustees_digest(2, &keys), trustees_digest(decryption.threshold, &decryption.verification_keys));
+        assert_ne!(trustees_digest(1, &keys), trustees_digest(2, &keys));
+        assert_ne!(trustees_digest(2, &keys[..2]), trustees_digest(2, &keys));
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+// Differential testing of FHE backends.
+// Runs one seeded workload (the values to encrypt and the order they are added
+// in) through two `FheBackend`s and compares every decryption: each fresh
+// ballot after a serialization round trip, and the running sum after each
+// addition. Run against a reference BFV library (Sunscreen, on Microsoft SEAL,
+// with the `sunscreen` feature), this checks that the pure Rust scheme the
+// guest tallies with computes what BFV computes.
+//
+// Only the workload is shared. Ciphertexts are not comparable across schemes,
+// and library backends draw key and noise randomness from their own sources,
+// so agreement is checked on plaintexts. Values stay small and non-negative
+// so sums are exact in every backend's plaintext space.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use thiserror::Error;
+
+use crate::fhe_backend::{BackendError, FheBackend};
+
+/// Largest value a workload encrypts.
+pub const MAX_WORKLOAD_VALUE: i64 = 15;
+
+#[derive(Error, Debug)]
+pub enum DifferentialError {
+    #[error("Backend failed: {0}")]
+    Backend(#[from] BackendError),
+    #[error("Tally {tally} step {step}: backends decrypt to {left} and {right}")]
+    Mismatch { tally: usize, step: usize, left: i64, right: i64 },
+}
+
+/// Values for `tallies` independent sums of up to `max_terms` terms each.
+pub fn workload(seed: u64, tallies: usize, max_terms: usize) -> Vec<Vec<i64>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..tallies)
+        .map(|_| {
+            let terms = rng.gen_range(1..=max_terms);
+            (0..terms).map(|_| rng.gen_range(0..=MAX_WORKLOAD_VALUE)).collect()
+        })
+        .collect()
+}
+
+/// Decryptions a backend produces for `workload`: per tally, each ballot
+/// followed by the running sum after adding it.
+pub fn observe<B: FheBackend>(backend: &mut B, workload: &[Vec<i64>]) -> Result<Vec<Vec<i64>>, BackendError> {
+    let (public_key, private_key) = backend.generate_keys()?;
+    workload
+        .iter()
+        .map(|values| {
+            let mut observed = Vec::with_capacity(values.len() * 2);
+            let mut sum = backend.encrypt(0, &public_key)?;
+            for &value in values {
+                let ballot = backend.encrypt(value, &public_key)?;
+                let received = backend.deserialize(&backend.serialize(&ballot)?)?;
+                observed.push(backend.decrypt(&received, &private_key)?);
+                backend.add_assign(&mut sum, &received)?;
+                observed.push(backend.decrypt(&sum, &private_key)?);
+            }
+            Ok(observed)
+        })
+        .collect()
+}
+
+/// Run the workload for `seed` through both backends and report the first disagreement.
+pub fn compare<A: FheBackend, B: FheBackend>(
+    left: &mut A,
+    right: &mut B,
+    seed: u64,
+    tallies: usize,
+    max_terms: usize,
+) -> Result<(), DifferentialError> {
+    let workload = workload(seed, tallies, max_terms);
+    let left = observe(left, &workload)?;
+    let right = observe(right, &workload)?;
+    for (tally, (left, right)) in left.iter().zip(&right).enumerate() {
+        if let Some((step, (&left, &right))) = left.iter().zip(right).enumerate().find(|(_, (l, r))| l != r) {
+            return Err(DifferentialError::Mismatch { tally, step, left, right });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fhe_backend::PureRustBackend;
+
+    // Plaintext model: what any additive scheme must decrypt to
+    struct Plaintext;
+
+    impl FheBackend for Plaintext {
+        type PublicKey = ();
+        type PrivateKey = ();
+        type Ciphertext = i64;
+
+        fn generate_keys(&mut self) -> Result<((), ()), BackendError> {
+            Ok(((), ()))
+        }
+        fn encrypt(&self, value: i64, _: &()) -> Result<i64, BackendError> {
+            Ok(value)
+        }
+        fn add_assign(&self, sum: &mut i64, term: &i64) -> Result<(), BackendError> {
+            *sum += term;
+            Ok(())
+        }
+        fn serialize(&self, ciphertext: &i64) -> Result<Vec<u8>, BackendError> {
+            Ok(ciphertext.to_le_bytes().to_vec())
+        }
+        fn deserialize(&self, data: &[u8]) -> Result<i64, BackendError> {
+            let bytes = data.try_into().map_err(|_| BackendError::Serialization { reason: "not 8 bytes".to_string() })?;
+            Ok(i64::from_le_bytes(bytes))
+        }
+        fn decrypt(&self, ciphertext: &i64, _: &()) -> Result<i64, BackendError> {
+            Ok(*ciphertext)
+        }
+    }
+
+    #[test]
+    fn test_pure_rust_backend_matches_plaintext_model() -> Result<(), DifferentialError> {
+        for seed in 0..4 {
+            compare(&mut PureRustBackend::new(), &mut Plaintext, seed, 8, 64)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "sunscreen")]
+    #[test]
+    fn test_pure_rust_backend_matches_sunscreen_bfv() -> Result<(), DifferentialError> {
+        let mut reference = crate::sunscreen_backend::SunscreenBackend::new()?;
+        for seed in 0..2 {
+            compare(&mut PureRustBackend::new(), &mut reference, seed, 4, 16)?;
+        }
+        Ok(())
+    }
+}
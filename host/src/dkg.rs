@@ -0,0 +1,459 @@
+// Distributed key generation for the election key.
+// Trustees jointly produce the election public key so that no single party ever
+// holds the full FHE secret key. Each trustee drives a `DkgParticipant` through
+// three rounds:
+//   1. Commit   - broadcast hash commitments to its public key share and to the
+//                 Shamir shares it will deal to every trustee
+//   2. Reveal   - broadcast the public key share and privately send each
+//                 trustee its share of this trustee's secret contribution
+//   3. Finalize - check every reveal and share against the round-1 commitments,
+//                 then derive the combined public key and this trustee's key share
+// With `threshold == participants` this is n-of-n; a lower threshold gives t-of-n,
+// where any `threshold` key shares together carry the full decryption capability.
+//
+// Each trustee's secret contribution z_i is the constant term of its sharing
+// polynomials, and its public key share is b_i = a*z_i mod t with the common
+// public polynomial `a`, so the shares add up to b = a*z for the secret
+// z = sum_i z_i that the key shares Shamir-share over GF(PLAINTEXT_MODULUS).
+// Real BFV: pk_i = (-a*s_i + e_i, a) mod q, whose error hides s_i. Simplified:
+// b_i carries no error, and nothing but the hash commitment binds it to z_i.
+
+use std::collections::BTreeMap;
+
+use rand::Rng;
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+use crate::fhe_client::{multiply_polynomials, public_polynomial, PublicKey, PLAINTEXT_MODULUS, POLYNOMIAL_DEGREE};
+
+#[derive(Error, Debug)]
+pub enum DkgError {
+    #[error("Invalid DKG configuration: threshold {threshold} of {participants} participants")]
+    InvalidConfig { threshold: u32, participants: u32 },
+    #[error("Participant index {index} outside 1..={participants}")]
+    InvalidParticipant { index: u32, participants: u32 },
+    #[error("DKG step {step} not allowed in phase {phase:?}")]
+    WrongPhase { step: &'static str, phase: DkgPhase },
+    #[error("Duplicate {kind} from participant {from}")]
+    Duplicate { kind: &'static str, from: u32 },
+    #[error("Missing {kind} from participant {from}")]
+    Missing { kind: &'static str, from: u32 },
+    #[error("Share from participant {from} addressed to {to}, not to us")]
+    Misaddressed { from: u32, to: u32 },
+    #[error("{kind} from participant {from} does not match its commitment")]
+    CommitmentMismatch { kind: &'static str, from: u32 },
+    #[error("Malformed {kind} from participant {from}")]
+    Malformed { kind: &'static str, from: u32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DkgConfig {
+    pub threshold: u32,
+    pub participants: u32,
+}
+
+impl DkgConfig {
+    /// Every trustee is required to decrypt.
+    pub fn n_of_n(participants: u32) -> Result<Self, DkgError> {
+        Self::threshold(participants, participants)
+    }
+
+    /// Any `threshold` of `participants` trustees can decrypt.
+    pub fn threshold(threshold: u32, participants: u32) -> Result<Self, DkgError> {
+        // Shamir evaluation points must be distinct non-zero field elements
+        if threshold == 0 || threshold > participants || participants as u64 >= PLAINTEXT_MODULUS {
+            return Err(DkgError::InvalidConfig { threshold, participants });
+        }
+        Ok(DkgConfig { threshold, participants })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DkgPhase {
+    Commit,
+    Reveal,
+    Finalize,
+}
+
+/// Round 1 broadcast.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DkgCommitment {
+    pub from: u32,
+    pub public_key_commitment: [u8; 32],
+    pub share_commitments: Vec<[u8; 32]>, // indexed by recipient - 1
+}
+
+/// Round 2 broadcast.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DkgReveal {
+    pub from: u32,
+    pub public_key_share: PublicKey,
+}
+
+/// Round 2 point-to-point message; must travel over a private channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DkgShare {
+    pub from: u32,
+    pub to: u32,
+    pub share: Vec<u64>,
+}
+
+/// A trustee's share of the election secret key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyShare {
+    pub index: u32,
+    pub threshold: u32,
+    pub secret_data: Vec<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DkgOutput {
+    pub key_share: KeyShare,
+    pub public_key: PublicKey,
+    pub transcript_hash: [u8; 32], // Identical for every honest trustee
+}
+
+pub struct DkgParticipant {
+    index: u32,
+    config: DkgConfig,
+    phase: DkgPhase,
+    // polynomial[k][j]: degree-k coefficient of the sharing polynomial for secret coefficient j
+    polynomial: Vec<Vec<u64>>,
+    public_key_share: PublicKey,
+    commitments: BTreeMap<u32, DkgCommitment>,
+    reveals: BTreeMap<u32, PublicKey>,
+    received_shares: BTreeMap<u32, Vec<u64>>,
+}
+
+impl DkgParticipant {
+    pub fn new(index: u32, config: DkgConfig) -> Result<Self, DkgError> {
+        if index == 0 || index > config.participants {
+            return Err(DkgError::InvalidParticipant { index, participants: config.participants });
+        }
+
+        let mut rng = rand::thread_rng();
+        let polynomial: Vec<Vec<u64>> = (0..config.threshold)
+            .map(|_| (0..POLYNOMIAL_DEGREE).map(|_| rng.gen_range(0..PLAINTEXT_MODULUS)).collect())
+            .collect();
+        // b_i = a*z_i, z_i being the constant terms
+        let key_data = multiply_polynomials(&public_polynomial(), &polynomial[0]);
+
+        Ok(DkgParticipant {
+            index,
+            config,
+            phase: DkgPhase::Commit,
+            polynomial,
+            public_key_share: PublicKey { key_data },
+            commitments: BTreeMap::new(),
+            reveals: BTreeMap::new(),
+            received_shares: BTreeMap::new(),
+        })
+    }
+
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn phase(&self) -> DkgPhase {
+        self.phase
+    }
+
+    /// Round 1: produce this trustee's commitment broadcast.
+    pub fn commit(&mut self) -> Result<DkgCommitment, DkgError> {
+        self.expect_phase("commit", DkgPhase::Commit)?;
+
+        let commitment = DkgCommitment {
+            from: self.index,
+            public_key_commitment: public_key_commitment(self.index, &self.public_key_share),
+            share_commitments: (1..=self.config.participants)
+                .map(|to| share_commitment(self.index, to, &self.share_for(to)))
+                .collect(),
+        };
+        self.commitments.insert(self.index, commitment.clone());
+        Ok(commitment)
+    }
+
+    pub fn receive_commitment(&mut self, commitment: DkgCommitment) -> Result<(), DkgError> {
+        self.expect_phase("receive_commitment", DkgPhase::Commit)?;
+        self.check_sender(commitment.from)?;
+        if commitment.share_commitments.len() != self.config.participants as usize {
+            return Err(DkgError::Malformed { kind: "commitment", from: commitment.from });
+        }
+        if self.commitments.contains_key(&commitment.from) {
+            return Err(DkgError::Duplicate { kind: "commitment", from: commitment.from });
+        }
+        self.commitments.insert(commitment.from, commitment);
+        Ok(())
+    }
+
+    /// Round 2: once every commitment is in, reveal the public key share and deal shares.
+    pub fn reveal(&mut self) -> Result<(DkgReveal, Vec<DkgShare>), DkgError> {
+        self.expect_phase("reveal", DkgPhase::Commit)?;
+        self.require_all("commitment", |from| self.commitments.contains_key(&from))?;
+
+        let reveal = DkgReveal { from: self.index, public_key_share: self.public_key_share.clone() };
+        let shares = (1..=self.config.participants)
+            .filter(|&to| to != self.index)
+            .map(|to| DkgShare { from: self.index, to, share: self.share_for(to) })
+            .collect();
+
+        self.reveals.insert(self.index, self.public_key_share.clone());
+        self.received_shares.insert(self.index, self.share_for(self.index));
+        self.phase = DkgPhase::Reveal;
+        Ok((reveal, shares))
+    }
+
+    pub fn receive_reveal(&mut self, reveal: DkgReveal) -> Result<(), DkgError> {
+        self.expect_phase("receive_reveal", DkgPhase::Reveal)?;
+        self.check_sender(reveal.from)?;
+        if self.reveals.contains_key(&reveal.from) {
+            return Err(DkgError::Duplicate { kind: "reveal", from: reveal.from });
+        }
+        let key_data = &reveal.public_key_share.key_data;
+        if key_data.len() != POLYNOMIAL_DEGREE || key_data.iter().any(|&c| c >= PLAINTEXT_MODULUS) {
+            return Err(DkgError::Malformed { kind: "reveal", from: reveal.from });
+        }
+
+        let expected = &self.commitments[&reveal.from].public_key_commitment;
+        if &public_key_commitment(reveal.from, &reveal.public_key_share) != expected {
+            return Err(DkgError::CommitmentMismatch { kind: "Public key share", from: reveal.from });
+        }
+        self.reveals.insert(reveal.from, reveal.public_key_share);
+        Ok(())
+    }
+
+    pub fn receive_share(&mut self, share: DkgShare) -> Result<(), DkgError> {
+        self.expect_phase("receive_share", DkgPhase::Reveal)?;
+        self.check_sender(share.from)?;
+        if share.to != self.index {
+            return Err(DkgError::Misaddressed { from: share.from, to: share.to });
+        }
+        if self.received_shares.contains_key(&share.from) {
+            return Err(DkgError::Duplicate { kind: "share", from: share.from });
+        }
+        if share.share.len() != POLYNOMIAL_DEGREE || share.share.iter().any(|&c| c >= PLAINTEXT_MODULUS) {
+            return Err(DkgError::Malformed { kind: "share", from: share.from });
+        }
+
+        let expected = &self.commitments[&share.from].share_commitments[self.index as usize - 1];
+        if &share_commitment(share.from, self.index, &share.share) != expected {
+            return Err(DkgError::CommitmentMismatch { kind: "Secret share", from: share.from });
+        }
+        self.received_shares.insert(share.from, share.share);
+        Ok(())
+    }
+
+    /// Round 3: combine everything received into the election key material.
+    pub fn finalize(mut self) -> Result<DkgOutput, DkgError> {
+        self.expect_phase("finalize", DkgPhase::Reveal)?;
+        self.require_all("reveal", |from| self.reveals.contains_key(&from))?;
+        self.require_all("share", |from| self.received_shares.contains_key(&from))?;
+        self.phase = DkgPhase::Finalize;
+
+        let public_key = combine_public_key_shares(self.reveals.values());
+
+        let mut secret_data = vec![0u64; POLYNOMIAL_DEGREE];
+        for share in self.received_shares.values() {
+            for (acc, &coeff) in secret_data.iter_mut().zip(share) {
+                *acc = (*acc + coeff) % PLAINTEXT_MODULUS;
+            }
+        }
+
+        Ok(DkgOutput {
+            key_share: KeyShare { index: self.index, threshold: self.config.threshold, secret_data },
+            public_key,
+            transcript_hash: transcript_hash(self.config, self.commitments.values()),
+        })
+    }
+
+    // Evaluate this trustee's sharing polynomial at x = `to`
+    fn share_for(&self, to: u32) -> Vec<u64> {
+        let x = to as u64;
+        (0..POLYNOMIAL_DEGREE)
+            .map(|j| {
+                self.polynomial
+                    .iter()
+                    .rev()
+                    .fold(0u64, |acc, coeffs| (acc * x + coeffs[j]) % PLAINTEXT_MODULUS)
+            })
+            .collect()
+    }
+
+    fn expect_phase(&self, step: &'static str, phase: DkgPhase) -> Result<(), DkgError> {
+        if self.phase != phase {
+            return Err(DkgError::WrongPhase { step, phase: self.phase });
+        }
+        Ok(())
+    }
+
+    fn check_sender(&self, from: u32) -> Result<(), DkgError> {
+        if from == 0 || from > self.config.participants {
+            return Err(DkgError::InvalidParticipant { index: from, participants: self.config.participants });
+        }
+        Ok(())
+    }
+
+    fn require_all(&self, kind: &'static str, has: impl Fn(u32) -> bool) -> Result<(), DkgError> {
+        match (1..=self.config.participants).find(|&from| !has(from)) {
+            Some(from) => Err(DkgError::Missing { kind, from }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The election key: public key shares summed coefficient-wise mod t.
+pub(crate) fn combine_public_key_shares<'a>(shares: impl Iterator<Item = &'a PublicKey>) -> PublicKey {
+    let mut key_data = vec![0u64; POLYNOMIAL_DEGREE];
+    for share in shares {
+        for (acc, &coeff) in key_data.iter_mut().zip(&share.key_data) {
+            *acc = (*acc + coeff) % PLAINTEXT_MODULUS;
+        }
+    }
+    PublicKey { key_data }
+}
+
+pub(crate) fn public_key_commitment(from: u32, public_key: &PublicKey) -> [u8; 32] {
+    let mut buf = b"fhe-voting/dkg/public-key".to_vec();
+    buf.extend_from_slice(&from.to_le_bytes());
+    for coeff in &public_key.key_data {
+        buf.extend_from_slice(&coeff.to_le_bytes());
+    }
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+fn share_commitment(from: u32, to: u32, share: &[u64]) -> [u8; 32] {
+    let mut buf = b"fhe-voting/dkg/share".to_vec();
+    buf.extend_from_slice(&from.to_le_bytes());
+    buf.extend_from_slice(&to.to_le_bytes());
+    for coeff in share {
+        buf.extend_from_slice(&coeff.to_le_bytes());
+    }
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+pub(crate) fn transcript_hash<'a>(config: DkgConfig, commitments: impl Iterator<Item = &'a DkgCommitment>) -> [u8; 32] {
+    let mut buf = b"fhe-voting/dkg/transcript".to_vec();
+    buf.extend_from_slice(&config.threshold.to_le_bytes());
+    buf.extend_from_slice(&config.participants.to_le_bytes());
+    for commitment in commitments {
+        buf.extend_from_slice(&commitment.from.to_le_bytes());
+        buf.extend_from_slice(&commitment.public_key_commitment);
+        for share_commitment in &commitment.share_commitments {
+            buf.extend_from_slice(share_commitment);
+        }
+    }
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+fn pow_mod(mut base: u64, mut exp: u64, modulus: u64) -> u64 {
+    let mut result = 1u64;
+    base %= modulus;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exp >>= 1;
+    }
+    result
+}
+
+/// Lagrange coefficient at x = 0 for `index` within the set of share `indices`,
+/// over GF(PLAINTEXT_MODULUS).
+pub fn lagrange_coefficient(index: u32, indices: &[u32]) -> u64 {
+    let p = PLAINTEXT_MODULUS;
+    let (mut num, mut den) = (1u64, 1u64);
+    for &other in indices.iter().filter(|&&other| other != index) {
+        num = num * (other as u64) % p;
+        den = den * ((other as u64 + p - index as u64) % p) % p;
+    }
+    num * pow_mod(den, p - 2, p) % p
+}
+
+/// Run the full protocol with every trustee in this process.
+///
+/// Only suitable for demos and tests: whoever calls this sees every share.
+pub fn run_in_process(config: DkgConfig) -> Result<(PublicKey, Vec<DkgOutput>), DkgError> {
+    let mut trustees = (1..=config.participants)
+        .map(|index| DkgParticipant::new(index, config))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let commitments = trustees.iter_mut().map(|t| t.commit()).collect::<Result<Vec<_>, _>>()?;
+    for trustee in trustees.iter_mut() {
+        let me = trustee.index();
+        for commitment in commitments.iter().filter(|c| c.from != me) {
+            trustee.receive_commitment(commitment.clone())?;
+        }
+    }
+
+    let rounds = trustees.iter_mut().map(|t| t.reveal()).collect::<Result<Vec<_>, _>>()?;
+    for trustee in trustees.iter_mut() {
+        let me = trustee.index();
+        for (reveal, shares) in rounds.iter().filter(|(r, _)| r.from != me) {
+            trustee.receive_reveal(reveal.clone())?;
+            for share in shares.iter().filter(|s| s.to == me) {
+                trustee.receive_share(share.clone())?;
+            }
+        }
+    }
+
+    let outputs = trustees.into_iter().map(|t| t.finalize()).collect::<Result<Vec<_>, _>>()?;
+    Ok((outputs[0].public_key.clone(), outputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fhe_client::{decrypt_plaintext, Cipher, FheClient, PrivateKey, Signed};
+
+    fn reconstruct(shares: &[&KeyShare]) -> Vec<u64> {
+        let indices: Vec<u32> = shares.iter().map(|s| s.index).collect();
+        let mut secret = vec![0u64; POLYNOMIAL_DEGREE];
+        for share in shares {
+            let lambda = lagrange_coefficient(share.index, &indices);
+            for (acc, &coeff) in secret.iter_mut().zip(&share.secret_data) {
+                *acc = (*acc + lambda * coeff) % PLAINTEXT_MODULUS;
+            }
+        }
+        secret
+    }
+
+    #[test]
+    fn test_threshold_dkg_any_subset_reconstructs() -> Result<(), Box<dyn std::error::Error>> {
+        let (public_key, outputs) = run_in_process(DkgConfig::threshold(2, 3)?)?;
+        assert!(outputs.iter().all(|o| o.public_key.key_data == public_key.key_data));
+        assert!(outputs.iter().all(|o| o.transcript_hash == outputs[0].transcript_hash));
+
+        let shares: Vec<&KeyShare> = outputs.iter().map(|o| &o.key_share).collect();
+        let from_12 = reconstruct(&[shares[0], shares[1]]);
+        assert_eq!(from_12, reconstruct(&[shares[1], shares[2]]));
+        assert_eq!(from_12, reconstruct(&[shares[0], shares[2]]));
+
+        // The reconstructed secret is the one under the combined public key
+        assert_eq!(multiply_polynomials(&public_polynomial(), &from_12), public_key.key_data);
+        let client = FheClient::with_public_key(public_key);
+        let ciphertext = Cipher::<Signed>::deserialize_bound(&client.encrypt_values(&[9])?[0], &client.get_public_key().fingerprint())?;
+        assert_eq!(decrypt_plaintext(&ciphertext, &PrivateKey { secret_data: from_12 }).coefficients()[0], 9);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tampered_share_is_rejected() -> Result<(), DkgError> {
+        let config = DkgConfig::n_of_n(2)?;
+        let mut alice = DkgParticipant::new(1, config)?;
+        let mut bob = DkgParticipant::new(2, config)?;
+        let (a, b) = (alice.commit()?, bob.commit()?);
+        alice.receive_commitment(b)?;
+        bob.receive_commitment(a)?;
+
+        let (_, mut shares) = alice.reveal()?;
+        bob.reveal()?;
+        shares[0].share[0] = (shares[0].share[0] + 1) % PLAINTEXT_MODULUS;
+        assert!(matches!(
+            bob.receive_share(shares.remove(0)),
+            Err(DkgError::CommitmentMismatch { from: 1, .. })
+        ));
+        Ok(())
+    }
+}
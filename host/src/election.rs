@@ -0,0 +1,154 @@
+// Election identity.
+// Every tally commits an `ElectionContext`: the election id, the chain id the
+// result is consumed on (an operator-chosen domain tag for off-chain elections),
+// the time the ballot box closes, the digest of the candidate list (see
+// `candidates`) and the rule a tie for first place is settled by, so the prover
+// cannot pick one after the count. Verifiers check the committed context
+// against the election they expect, so a receipt proven for one election can
+// never be presented as the result of another, even one with the same ballots
+// and keys. Operators set:
+//
+//   FHE_VOTING_ELECTION_ID  64 hex digits; by default derived from the election
+//                           key and registrar key, so each key ceremony gets
+//                           its own id
+//   FHE_VOTING_CHAIN_ID     chain id or domain tag; defaults to the EIP-712
+//                           domain's chain, else 0
+//   FHE_VOTING_CLOSES_AT    ballot-close time in Unix seconds; defaults to now
+//   FHE_VOTING_TIE_BREAK    declare (the default), first or lot:<64 hex seed>
+//
+// Interim and batch tallies commit the same context, and the guest refuses to
+// continue or aggregate tallies of another election.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use risc0_zkvm::sha::{Impl, Sha256};
+use thiserror::Error;
+
+use crate::scheme::TieBreak;
+use crate::types::{Digest32, ElectionContext};
+
+// Domain separation tag for derived election ids
+const ELECTION_TAG: u8 = 0x09;
+
+#[derive(Error, Debug)]
+pub enum ElectionError {
+    #[error("FHE_VOTING_ELECTION_ID must be 64 hex digits, got {value}")]
+    InvalidId { value: String },
+    #[error("FHE_VOTING_TIE_BREAK must be declare, first or lot:<64 hex digits>, got {value}")]
+    InvalidTieBreak { value: String },
+    #[error("{name} must be a number, got {value}")]
+    InvalidValue { name: &'static str, value: String },
+    #[error("Tally is for {field} {committed}, expected {expected}")]
+    Mismatch { field: &'static str, expected: String, committed: String },
+}
+
+/// Default id: H(tag || election key fingerprint || registrar key).
+pub fn derive_election_id(election_key_fingerprint: &[u8; 32], registrar_public_key: &[u8; 32]) -> Digest32 {
+    let mut buf = vec![ELECTION_TAG];
+    buf.extend_from_slice(election_key_fingerprint);
+    buf.extend_from_slice(registrar_public_key);
+    Digest32((*Impl::hash_bytes(&buf)).into())
+}
+
+/// Context from the `FHE_VOTING_*` variables; unset ones keep `default`.
+pub fn election_from_env(default: ElectionContext) -> Result<ElectionContext, ElectionError> {
+    election_from_lookup(|name| std::env::var(name).ok(), default)
+}
+
+pub fn election_from_lookup(lookup: impl Fn(&str) -> Option<String>, default: ElectionContext) -> Result<ElectionContext, ElectionError> {
+    let number = |name: &'static str, default: u64| -> Result<u64, ElectionError> {
+        lookup(name).map_or(Ok(default), |value| value.trim().parse().map_err(|_| ElectionError::InvalidValue { name, value }))
+    };
+    let election_id = match lookup("FHE_VOTING_ELECTION_ID") {
+        Some(value) => Digest32::from_hex(value.trim()).ok_or(ElectionError::InvalidId { value })?,
+        None => default.election_id,
+    };
+    let tie_break = match lookup("FHE_VOTING_TIE_BREAK") {
+        Some(value) => match value.trim() {
+            "declare" => TieBreak::Declare,
+            "first" => TieBreak::FirstListed,
+            rule => match rule.strip_prefix("lot:").and_then(Digest32::from_hex) {
+                Some(seed) => TieBreak::Lot(seed.into()),
+                None => return Err(ElectionError::InvalidTieBreak { value }),
+            },
+        },
+        None => default.tie_break,
+    };
+    Ok(ElectionContext {
+        election_id,
+        chain_id: number("FHE_VOTING_CHAIN_ID", default.chain_id)?,
+        closes_at: number("FHE_VOTING_CLOSES_AT", default.closes_at)?,
+        candidates_digest: default.candidates_digest,
+        tie_break,
+        scheme_digest: default.scheme_digest,
+    })
+}
+
+/// Current Unix time, the default close time.
+pub fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Check that a tally commits the expected election, naming the first field that differs.
+pub fn check_election(expected: &ElectionContext, committed: &ElectionContext) -> Result<(), ElectionError> {
+    let mismatch = |field, expected: &dyn ToString, committed: &dyn ToString| ElectionError::Mismatch {
+        field,
+        expected: expected.to_string(),
+        committed: committed.to_string(),
+    };
+    if committed.election_id != expected.election_id {
+        return Err(mismatch("election", &expected.election_id, &committed.election_id));
+    }
+    if committed.chain_id != expected.chain_id {
+        return Err(mismatch("chain", &expected.chain_id, &committed.chain_id));
+    }
+    if committed.closes_at != expected.closes_at {
+        return Err(mismatch("ballot close time", &expected.closes_at, &committed.closes_at));
+    }
+    if committed.candidates_digest != expected.candidates_digest {
+        return Err(mismatch("candidate list", &expected.candidates_digest, &committed.candidates_digest));
+    }
+    if committed.tie_break != expected.tie_break {
+        return Err(mismatch("tie-break rule", &format!("{:?}", expected.tie_break), &format!("{:?}", committed.tie_break)));
+    }
+    if committed.scheme_digest != expected.scheme_digest {
+        return Err(mismatch("voting scheme", &expected.scheme_digest, &committed.scheme_digest));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn election(vars: &[(&str, &str)], default: ElectionContext) -> Result<ElectionContext, ElectionError> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        election_from_lookup(|name| vars.get(name).cloned(), default)
+    }
+
+    #[test]
+    fn test_election_context_parses_and_binds_the_tally() -> Result<(), ElectionError> {
+        let derived = derive_election_id(&[1u8; 32], &[2u8; 32]);
+        assert_ne!(derived, derive_election_id(&[1u8; 32], &[3u8; 32]));
+        let default = ElectionContext { election_id: derived, chain_id: 1, closes_at: 1_700_000_000, candidates_digest: Digest32([0xcd; 32]), tie_break: TieBreak::FirstListed, scheme_digest: Digest32([0xef; 32]) };
+        assert_eq!(election(&[], default)?, default);
+
+        let configured = election(&[("FHE_VOTING_ELECTION_ID", &format!("0x{}", "ab".repeat(32))), ("FHE_VOTING_CLOSES_AT", "1800000000")], default)?;
+        assert_eq!(configured, ElectionContext { election_id: Digest32([0xab; 32]), closes_at: 1_800_000_000, ..default });
+        assert!(matches!(election(&[("FHE_VOTING_ELECTION_ID", "abcd")], default), Err(ElectionError::InvalidId { .. })));
+        assert!(matches!(election(&[("FHE_VOTING_CHAIN_ID", "mainnet")], default), Err(ElectionError::InvalidValue { name: "FHE_VOTING_CHAIN_ID", .. })));
+        assert_eq!(election(&[("FHE_VOTING_TIE_BREAK", &format!("lot:{}", "01".repeat(32)))], default)?.tie_break, TieBreak::Lot([1u8; 32]));
+        assert!(matches!(election(&[("FHE_VOTING_TIE_BREAK", "coin")], default), Err(ElectionError::InvalidTieBreak { .. })));
+
+        // A receipt for another election, chain, close time, candidate list or tie-break rule is refused
+        check_election(&default, &default)?;
+        assert!(matches!(check_election(&default, &configured), Err(ElectionError::Mismatch { field: "election", .. })));
+        assert!(matches!(check_election(&default, &ElectionContext { chain_id: 10, ..default }), Err(ElectionError::Mismatch { field: "chain", .. })));
+        assert!(matches!(check_election(&default, &ElectionContext { closes_at: 0, ..default }), Err(ElectionError::Mismatch { field: "ballot close time", .. })));
+        assert!(matches!(check_election(&default, &ElectionContext { candidates_digest: Digest32::ZERO, ..default }), Err(ElectionError::Mismatch { field: "candidate list", .. })));
+        assert!(matches!(check_election(&default, &ElectionContext { tie_break: TieBreak::Declare, ..default }), Err(ElectionError::Mismatch { field: "tie-break rule", .. })));
+        assert!(matches!(check_election(&default, &ElectionContext { scheme_digest: Digest32::ZERO, ..default }), Err(ElectionError::Mismatch { field: "voting scheme", .. })));
+        Ok(())
+    }
+}
@@ -0,0 +1,303 @@
+// Security event log.
+// Separate from application output and from the submission `audit_log` the
+// guest checks: this records every security-relevant step the operator takes
+// (election created, voter key registered, ballot accepted or rejected, tally
+// started, receipt produced). Each record is hash-chained to the previous one
+// and signed with the operator's Ed25519 key, so the log is append-only in the
+// sense that dropping, reordering or editing a record breaks verification.
+//
+// Records can be mirrored to a JSON Lines file as they are appended and read
+// back for verification and queries. Reopening an existing file resumes its
+// sequence and chain once the records verify under the same operator key.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+use crate::attestation::Operator;
+
+const EVENT_DOMAIN: &[u8] = b"fhe-voting/event-log/v1";
+
+#[derive(Error, Debug)]
+pub enum EventLogError {
+    #[error("Event log I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Event log encoding failed: {reason}")]
+    Encoding { reason: String },
+    #[error("Event {sequence} is out of sequence")]
+    OutOfSequence { sequence: u64 },
+    #[error("Event {sequence} does not chain from the previous event")]
+    BrokenChain { sequence: u64 },
+    #[error("Event {sequence} signature does not verify")]
+    InvalidSignature { sequence: u64 },
+    #[error("Malformed operator public key")]
+    MalformedPublicKey,
+    #[error("Cannot resume an event log file into a log that already holds {records} records")]
+    ResumeConflict { records: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SecurityEvent {
+    ElectionCreated {
+        #[serde(with = "hex::serde")]
+        election_key_fingerprint: [u8; 32],
+        threshold: u32,
+        trustees: u32,
+    },
+    /// A voter credential token or identity commitment was registered.
+    KeyRegistered {
+        #[serde(with = "hex::serde")]
+        key: [u8; 32],
+    },
+    BallotAccepted {
+        #[serde(with = "hex::serde")]
+        ballot_hash: [u8; 32],
+    },
+    BallotRejected {
+        #[serde(with = "hex::serde")]
+        ballot_hash: [u8; 32],
+        reason: String,
+    },
+    TallyStarted {
+        ballots: u32,
+        #[serde(with = "hex::serde")]
+        ballot_set_root: [u8; 32],
+    },
+    ReceiptProduced {
+        #[serde(with = "hex::serde")]
+        image_id: [u8; 32],
+        #[serde(with = "hex::serde")]
+        journal_digest: [u8; 32],
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    ElectionCreated,
+    KeyRegistered,
+    BallotAccepted,
+    BallotRejected,
+    TallyStarted,
+    ReceiptProduced,
+}
+
+impl SecurityEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            SecurityEvent::ElectionCreated { .. } => EventKind::ElectionCreated,
+            SecurityEvent::KeyRegistered { .. } => EventKind::KeyRegistered,
+            SecurityEvent::BallotAccepted { .. } => EventKind::BallotAccepted,
+            SecurityEvent::BallotRejected { .. } => EventKind::BallotRejected,
+            SecurityEvent::TallyStarted { .. } => EventKind::TallyStarted,
+            SecurityEvent::ReceiptProduced { .. } => EventKind::ReceiptProduced,
+        }
+    }
+
+    fn ballot_hash(&self) -> Option<&[u8; 32]> {
+        match self {
+            SecurityEvent::BallotAccepted { ballot_hash } | SecurityEvent::BallotRejected { ballot_hash, .. } => Some(ballot_hash),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub sequence: u64,
+    pub timestamp: u64, // Seconds since the Unix epoch
+    pub event: SecurityEvent,
+    #[serde(with = "hex::serde")]
+    pub previous: [u8; 32], // Hash of the previous record, zero for the first
+    #[serde(with = "hex::serde")]
+    pub signature: [u8; 64],
+}
+
+impl EventRecord {
+    /// Canonical bytes the operator signs.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut buf = EVENT_DOMAIN.to_vec();
+        buf.extend_from_slice(&self.sequence.to_le_bytes());
+        buf.extend_from_slice(&self.timestamp.to_le_bytes());
+        buf.extend_from_slice(&self.previous);
+        buf.extend(bincode::serialize(&self.event).expect("event serialization is infallible"));
+        buf
+    }
+
+    /// Chain hash: covers the signed bytes and the signature.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut buf = self.signing_bytes();
+        buf.extend_from_slice(&self.signature);
+        (*Impl::hash_bytes(&buf)).into()
+    }
+}
+
+pub struct EventLog {
+    operator: Operator,
+    records: Vec<EventRecord>,
+    head: [u8; 32],
+    sink: Option<File>,
+}
+
+impl EventLog {
+    pub fn new(operator: Operator) -> Self {
+        EventLog { operator, records: Vec::new(), head: [0u8; 32], sink: None }
+    }
+
+    /// Also append every record to `path` as a JSON line. Records already in
+    /// the file must verify under this operator's key; new records continue
+    /// their sequence and chain.
+    pub fn with_sink(mut self, path: impl AsRef<Path>) -> Result<Self, EventLogError> {
+        let file = File::options().create(true).read(true).append(true).open(path)?;
+        let existing = read_jsonl(BufReader::new(&file))?;
+        if !existing.is_empty() {
+            if !self.records.is_empty() {
+                return Err(EventLogError::ResumeConflict { records: self.records.len() });
+            }
+            self.head = verify_event_log(&existing, &self.operator.public_key())?;
+            self.records = existing;
+        }
+        self.sink = Some(file);
+        Ok(self)
+    }
+
+    pub fn record(&mut self, event: SecurityEvent) -> Result<&EventRecord, EventLogError> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+        let mut record = EventRecord {
+            sequence: self.records.len() as u64,
+            timestamp,
+            event,
+            previous: self.head,
+            signature: [0u8; 64],
+        };
+        record.signature = self.operator.sign(&record.signing_bytes());
+
+        if let Some(sink) = &mut self.sink {
+            let line = serde_json::to_string(&record).map_err(|e| EventLogError::Encoding { reason: e.to_string() })?;
+            writeln!(sink, "{}", line)?;
+            sink.flush()?;
+        }
+        self.head = record.hash();
+        self.records.push(record);
+        Ok(self.records.last().expect("record was just appended"))
+    }
+
+    pub fn head(&self) -> [u8; 32] {
+        self.head
+    }
+
+    pub fn records(&self) -> &[EventRecord] {
+        &self.records
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.operator.public_key()
+    }
+}
+
+/// Read records written by `EventLog::with_sink`.
+pub fn read_jsonl(reader: impl BufRead) -> Result<Vec<EventRecord>, EventLogError> {
+    reader
+        .lines()
+        .filter(|line| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+        .map(|line| serde_json::from_str(&line?).map_err(|e| EventLogError::Encoding { reason: e.to_string() }))
+        .collect()
+}
+
+/// Check sequence numbers, the hash chain and every signature. Returns the head.
+pub fn verify_event_log(records: &[EventRecord], operator_public_key: &[u8; 32]) -> Result<[u8; 32], EventLogError> {
+    let key = VerifyingKey::from_bytes(operator_public_key).map_err(|_| EventLogError::MalformedPublicKey)?;
+    let mut head = [0u8; 32];
+    for (position, record) in records.iter().enumerate() {
+        if record.sequence != position as u64 {
+            return Err(EventLogError::OutOfSequence { sequence: record.sequence });
+        }
+        if record.previous != head {
+            return Err(EventLogError::BrokenChain { sequence: record.sequence });
+        }
+        key.verify(&record.signing_bytes(), &Signature::from_bytes(&record.signature))
+            .map_err(|_| EventLogError::InvalidSignature { sequence: record.sequence })?;
+        head = record.hash();
+    }
+    Ok(head)
+}
+
+// Queries over verified records
+
+pub fn events_of_kind(records: &[EventRecord], kind: EventKind) -> impl Iterator<Item = &EventRecord> {
+    records.iter().filter(move |record| record.event.kind() == kind)
+}
+
+/// Records with timestamps in `from..until` (seconds since the Unix epoch).
+pub fn events_between(records: &[EventRecord], from: u64, until: u64) -> impl Iterator<Item = &EventRecord> {
+    records.iter().filter(move |record| (from..until).contains(&record.timestamp))
+}
+
+/// Acceptance and rejection records for one ballot set leaf hash.
+pub fn events_for_ballot<'a>(records: &'a [EventRecord], ballot_hash: &'a [u8; 32]) -> impl Iterator<Item = &'a EventRecord> {
+    records.iter().filter(move |record| record.event.ballot_hash() == Some(ballot_hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_log_verifies_queries_and_detects_edits() -> Result<(), EventLogError> {
+        let path = std::env::temp_dir().join(format!("fhe-voting-events-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut log = EventLog::new(Operator::generate()).with_sink(&path)?;
+        log.record(SecurityEvent::ElectionCreated { election_key_fingerprint: [1u8; 32], threshold: 2, trustees: 3 })?;
+        log.record(SecurityEvent::BallotAccepted { ballot_hash: [2u8; 32] })?;
+        log.record(SecurityEvent::BallotRejected { ballot_hash: [3u8; 32], reason: "invalid credential".to_string() })?;
+        log.record(SecurityEvent::BallotAccepted { ballot_hash: [3u8; 32] })?;
+
+        let records = read_jsonl(BufReader::new(File::open(&path)?))?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(records, log.records());
+        assert_eq!(verify_event_log(&records, &log.public_key())?, log.head());
+
+        assert_eq!(events_of_kind(&records, EventKind::BallotAccepted).count(), 2);
+        assert_eq!(events_for_ballot(&records, &[3u8; 32]).map(|r| r.sequence).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(events_between(&records, 0, u64::MAX).count(), 4);
+
+        let mut edited = records.clone();
+        edited[2].event = SecurityEvent::BallotAccepted { ballot_hash: [3u8; 32] };
+        assert!(matches!(verify_event_log(&edited, &log.public_key()), Err(EventLogError::InvalidSignature { sequence: 2 })));
+        let mut dropped = records;
+        dropped.remove(1);
+        assert!(matches!(verify_event_log(&dropped, &log.public_key()), Err(EventLogError::OutOfSequence { sequence: 2 })));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reopened_sink_resumes_the_chain() -> Result<(), EventLogError> {
+        let path = std::env::temp_dir().join(format!("fhe-voting-events-resume-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let operator = Operator::generate();
+        let mut first = EventLog::new(operator.clone()).with_sink(&path)?;
+        first.record(SecurityEvent::ElectionCreated { election_key_fingerprint: [1u8; 32], threshold: 2, trustees: 3 })?;
+        first.record(SecurityEvent::BallotAccepted { ballot_hash: [2u8; 32] })?;
+        drop(first);
+
+        let mut resumed = EventLog::new(operator.clone()).with_sink(&path)?;
+        assert_eq!(resumed.record(SecurityEvent::BallotAccepted { ballot_hash: [3u8; 32] })?.sequence, 2);
+        let records = read_jsonl(BufReader::new(File::open(&path)?))?;
+        assert_eq!(records, resumed.records());
+        assert_eq!(verify_event_log(&records, &operator.public_key())?, resumed.head());
+
+        let other = EventLog::new(Operator::generate()).with_sink(&path);
+        let mut busy = EventLog::new(operator);
+        busy.record(SecurityEvent::BallotAccepted { ballot_hash: [4u8; 32] })?;
+        let conflict = busy.with_sink(&path);
+        std::fs::remove_file(&path)?;
+        assert!(matches!(other, Err(EventLogError::InvalidSignature { sequence: 0 })));
+        assert!(matches!(conflict, Err(EventLogError::ResumeConflict { records: 1 })));
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+// Executor and prover tuning.
+// The zkVM splits a guest run into segments of 2^po2 cycles and proves each one
+// separately. Prover memory grows roughly linearly with the segment size, so
+// hosts with less memory need smaller segments, and a very large election can
+// outgrow the default session limit. Operators set:
+//
+//   FHE_VOTING_SEGMENT_PO2       segment size as a power of two cycles
+//                                (13..=22; larger segments cannot be lifted
+//                                into succinct receipts)
+//   FHE_VOTING_SESSION_LIMIT     most cycles one proof may execute
+//   FHE_VOTING_PROVER_MEMORY_MB  prover memory budget; without an explicit
+//                                segment size, picks the largest that fits
+//   FHE_VOTING_PROVER            prover backend: cpu (r0vm at
+//                                RISC0_SERVER_PATH or on the PATH), gpu (a
+//                                CUDA or Metal r0vm at FHE_VOTING_GPU_R0VM),
+//                                bonsai (BONSAI_API_URL and BONSAI_API_KEY) or
+//                                dev (fake receipts; needs RISC0_DEV_MODE=1
+//                                for them to verify). Unset keeps the zkVM's
+//                                own choice from RISC0_PROVER and friends.
+//
+// Settings are checked when they are read, so a bad value fails before any
+// proving. Every proof goes through `prove`, whose errors come from `explain`:
+// it turns the executor's limit errors into messages that name the setting to
+// change. It returns the receipt with `ProvingStats` (cycles, segments, wall
+// time and receipt size), so operators can track what each election costs.
+
+use std::fmt::{self, Display};
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use risc0_zkvm::{default_prover, BonsaiProver, ExecutorEnv, ExecutorEnvBuilder, ExternalProver, ProveInfo, Prover, ProverOpts, Receipt};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Smallest segment the zkVM supports.
+pub const MIN_SEGMENT_PO2: u32 = 13;
+/// Largest segment the default recursion programs can lift.
+pub const MAX_SEGMENT_PO2: u32 = risc0_zkvm::DEFAULT_MAX_PO2 as u32;
+/// The zkVM's default segment size.
+pub const DEFAULT_SEGMENT_PO2: u32 = 20;
+/// Rough CPU prover footprint per segment cycle (about 8 GiB at po2 20).
+pub const PROVER_BYTES_PER_CYCLE: u64 = 8 * 1024;
+
+#[derive(Error, Debug)]
+pub enum ExecutorError {
+    #[error("{name} must be a number, got {value}")]
+    InvalidValue { name: &'static str, value: String },
+    #[error("Segment size 2^{po2} is outside 2^{MIN_SEGMENT_PO2}..=2^{MAX_SEGMENT_PO2} cycles")]
+    SegmentPo2OutOfRange { po2: u32 },
+    #[error("Segments of 2^{po2} cycles need about {needed_mb} MB, the prover memory budget is {budget_mb} MB")]
+    MemoryTooSmall { po2: u32, needed_mb: u64, budget_mb: u64 },
+    #[error("Proof exceeded the session limit ({reason}); raise FHE_VOTING_SESSION_LIMIT or split the tally into batches")]
+    SessionLimitExceeded { reason: String },
+    #[error("Proving failed: {reason}")]
+    Prover { reason: String },
+    #[error("Receipt encoding failed: {reason}")]
+    Encoding { reason: String },
+    #[error("Unknown prover backend {name} (expected cpu, gpu, bonsai or dev)")]
+    UnknownBackend { name: String },
+    #[error("The {backend} prover needs {missing}")]
+    BackendUnavailable { backend: ProverBackend, missing: &'static str },
+}
+
+/// Where proofs are generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProverBackend {
+    Cpu,
+    Gpu,
+    Bonsai,
+    Dev,
+}
+
+impl ProverBackend {
+    /// Fail unless the variables this backend reads are set.
+    pub fn check_env(&self) -> Result<(), ExecutorError> {
+        self.check(|name| std::env::var(name).ok())
+    }
+
+    fn check(&self, lookup: impl Fn(&str) -> Option<String>) -> Result<(), ExecutorError> {
+        let required: &[&'static str] = match self {
+            ProverBackend::Cpu => &[],
+            ProverBackend::Gpu => &["FHE_VOTING_GPU_R0VM"],
+            ProverBackend::Bonsai => &["BONSAI_API_URL", "BONSAI_API_KEY"],
+            ProverBackend::Dev => &["RISC0_DEV_MODE"],
+        };
+        match required.iter().find(|name| lookup(name).is_none_or(|value| value.is_empty())) {
+            Some(missing) => Err(ExecutorError::BackendUnavailable { backend: *self, missing }),
+            None => Ok(()),
+        }
+    }
+
+    pub fn prover(&self) -> Rc<dyn Prover> {
+        let r0vm = |name: &str| std::env::var(name).unwrap_or_else(|_| "r0vm".into());
+        match self {
+            ProverBackend::Cpu | ProverBackend::Dev => Rc::new(ExternalProver::new("ipc", r0vm("RISC0_SERVER_PATH"))),
+            ProverBackend::Gpu => Rc::new(ExternalProver::new("ipc", r0vm("FHE_VOTING_GPU_R0VM"))),
+            ProverBackend::Bonsai => Rc::new(BonsaiProver::new("bonsai")),
+        }
+    }
+
+    /// `opts` as this backend proves with; the dev backend only fakes receipts.
+    pub fn opts(&self, opts: &ProverOpts) -> ProverOpts {
+        opts.clone().with_dev_mode(*self == ProverBackend::Dev)
+    }
+}
+
+impl FromStr for ProverBackend {
+    type Err = ExecutorError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.trim() {
+            "cpu" | "local" | "ipc" => Ok(ProverBackend::Cpu),
+            "gpu" => Ok(ProverBackend::Gpu),
+            "bonsai" => Ok(ProverBackend::Bonsai),
+            "dev" => Ok(ProverBackend::Dev),
+            _ => Err(ExecutorError::UnknownBackend { name: name.trim().into() }),
+        }
+    }
+}
+
+impl Display for ProverBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ProverBackend::Cpu => "cpu",
+            ProverBackend::Gpu => "gpu",
+            ProverBackend::Bonsai => "bonsai",
+            ProverBackend::Dev => "dev",
+        })
+    }
+}
+
+/// What one proof cost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvingStats {
+    pub total_cycles: u64, // Including paging and padding to whole segments
+    pub user_cycles: u64,
+    pub segments: usize,
+    pub proving_ms: u64,
+    pub receipt_bytes: u64, // bincode, as receipts are stored
+}
+
+impl ProvingStats {
+    pub fn new(info: &ProveInfo, elapsed: Duration) -> Result<Self, ExecutorError> {
+        Ok(ProvingStats {
+            total_cycles: info.stats.total_cycles,
+            user_cycles: info.stats.user_cycles,
+            segments: info.stats.segments,
+            proving_ms: elapsed.as_millis().try_into().unwrap_or(u64::MAX),
+            receipt_bytes: bincode::serialized_size(&info.receipt).map_err(|e| ExecutorError::Encoding { reason: e.to_string() })?,
+        })
+    }
+}
+
+/// A receipt and what proving it cost.
+#[derive(Debug)]
+pub struct ProvedReceipt {
+    pub receipt: Receipt,
+    pub stats: ProvingStats,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecutorConfig {
+    pub segment_limit_po2: Option<u32>,
+    pub session_limit: Option<u64>, // Cycles
+    pub prover_memory_mb: Option<u64>,
+    pub prover_backend: Option<ProverBackend>, // None leaves the choice to `default_prover`
+}
+
+impl ExecutorConfig {
+    /// Settings from the `FHE_VOTING_*` variables; unset ones keep the zkVM defaults.
+    pub fn from_env() -> Result<Self, ExecutorError> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, ExecutorError> {
+        fn parse<T: std::str::FromStr>(name: &'static str, value: Option<String>) -> Result<Option<T>, ExecutorError> {
+            value.map(|value| value.trim().parse().map_err(|_| ExecutorError::InvalidValue { name, value })).transpose()
+        }
+        let config = ExecutorConfig {
+            segment_limit_po2: parse("FHE_VOTING_SEGMENT_PO2", lookup("FHE_VOTING_SEGMENT_PO2"))?,
+            session_limit: parse("FHE_VOTING_SESSION_LIMIT", lookup("FHE_VOTING_SESSION_LIMIT"))?,
+            prover_memory_mb: parse("FHE_VOTING_PROVER_MEMORY_MB", lookup("FHE_VOTING_PROVER_MEMORY_MB"))?,
+            prover_backend: lookup("FHE_VOTING_PROVER").map(|name| name.parse()).transpose()?,
+        };
+        config.segment_po2()?;
+        if let Some(backend) = config.prover_backend {
+            backend.check(&lookup)?;
+        }
+        Ok(config)
+    }
+
+    /// The segment size to prove with, or `None` for the zkVM default.
+    pub fn segment_po2(&self) -> Result<Option<u32>, ExecutorError> {
+        if let Some(po2) = self.segment_limit_po2 {
+            if !(MIN_SEGMENT_PO2..=MAX_SEGMENT_PO2).contains(&po2) {
+                return Err(ExecutorError::SegmentPo2OutOfRange { po2 });
+            }
+        }
+        let Some(budget_mb) = self.prover_memory_mb else {
+            return Ok(self.segment_limit_po2);
+        };
+        let fits = |po2: u32| segment_memory_mb(po2) <= budget_mb;
+        match self.segment_limit_po2 {
+            Some(po2) if fits(po2) => Ok(Some(po2)),
+            Some(po2) => Err(ExecutorError::MemoryTooSmall { po2, needed_mb: segment_memory_mb(po2), budget_mb }),
+            // Never larger than the default: bigger segments only pay off on big GPUs
+            None => (MIN_SEGMENT_PO2..=DEFAULT_SEGMENT_PO2)
+                .rev()
+                .find(|&po2| fits(po2))
+                .map(Some)
+                .ok_or(ExecutorError::MemoryTooSmall {
+                    po2: MIN_SEGMENT_PO2,
+                    needed_mb: segment_memory_mb(MIN_SEGMENT_PO2),
+                    budget_mb,
+                }),
+        }
+    }
+
+    /// Apply the limits to an executor environment.
+    pub fn apply<'a, 'b>(&self, builder: &'b mut ExecutorEnvBuilder<'a>) -> Result<&'b mut ExecutorEnvBuilder<'a>, ExecutorError> {
+        if let Some(po2) = self.segment_po2()? {
+            builder.segment_limit_po2(po2);
+        }
+        if self.session_limit.is_some() {
+            builder.session_limit(self.session_limit);
+        }
+        Ok(builder)
+    }
+
+    /// Prove `elf` on the configured backend.
+    pub fn prove(&self, env: ExecutorEnv<'_>, elf: &[u8], opts: &ProverOpts) -> Result<ProvedReceipt, ExecutorError> {
+        match self.prover_backend {
+            Some(backend) => self.prove_on(backend, env, elf, opts),
+            None => self.timed(default_prover(), env, elf, opts),
+        }
+    }
+
+    /// Prove `elf` on `backend`, whatever is configured.
+    pub fn prove_on(&self, backend: ProverBackend, env: ExecutorEnv<'_>, elf: &[u8], opts: &ProverOpts) -> Result<ProvedReceipt, ExecutorError> {
+        self.timed(backend.prover(), env, elf, &backend.opts(opts))
+    }
+
+    fn timed(&self, prover: Rc<dyn Prover>, env: ExecutorEnv<'_>, elf: &[u8], opts: &ProverOpts) -> Result<ProvedReceipt, ExecutorError> {
+        let started = Instant::now();
+        let info = prover.prove_with_opts(env, elf, opts).map_err(|e| self.explain(e))?;
+        let stats = ProvingStats::new(&info, started.elapsed())?;
+        Ok(ProvedReceipt { receipt: info.receipt, stats })
+    }
+
+    /// Name the setting behind an executor failure where there is one.
+    pub fn explain(&self, error: impl Display) -> ExecutorError {
+        let reason = error.to_string();
+        if reason.contains("Session limit exceeded") {
+            ExecutorError::SessionLimitExceeded { reason }
+        } else {
+            ExecutorError::Prover { reason }
+        }
+    }
+}
+
+/// Estimated prover memory for one segment of 2^po2 cycles, in MB.
+pub fn segment_memory_mb(po2: u32) -> u64 {
+    ((1u64 << po2) * PROVER_BYTES_PER_CYCLE).div_ceil(1024 * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config(vars: &[(&str, &str)]) -> Result<ExecutorConfig, ExecutorError> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        ExecutorConfig::from_lookup(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_limits_parse_and_fit_the_memory_budget() -> Result<(), ExecutorError> {
+        assert_eq!(config(&[])?, ExecutorConfig::default());
+        assert_eq!(config(&[])?.segment_po2()?, None);
+
+        let tuned = config(&[("FHE_VOTING_SEGMENT_PO2", "18"), ("FHE_VOTING_SESSION_LIMIT", "1000000")])?;
+        assert_eq!((tuned.segment_po2()?, tuned.session_limit), (Some(18), Some(1_000_000)));
+
+        // A memory budget alone picks the largest segment that fits, capped at the default
+        assert_eq!(segment_memory_mb(20), 8192);
+        assert_eq!(config(&[("FHE_VOTING_PROVER_MEMORY_MB", "5000")])?.segment_po2()?, Some(19));
+        assert_eq!(config(&[("FHE_VOTING_PROVER_MEMORY_MB", "65536")])?.segment_po2()?, Some(DEFAULT_SEGMENT_PO2));
+
+        assert!(matches!(config(&[("FHE_VOTING_SEGMENT_PO2", "24")]), Err(ExecutorError::SegmentPo2OutOfRange { po2: 24 })));
+        assert!(matches!(config(&[("FHE_VOTING_SESSION_LIMIT", "lots")]), Err(ExecutorError::InvalidValue { name: "FHE_VOTING_SESSION_LIMIT", .. })));
+        assert!(matches!(
+            config(&[("FHE_VOTING_SEGMENT_PO2", "21"), ("FHE_VOTING_PROVER_MEMORY_MB", "8192")]),
+            Err(ExecutorError::MemoryTooSmall { po2: 21, needed_mb: 16384, budget_mb: 8192 })
+        ));
+        assert!(matches!(config(&[("FHE_VOTING_PROVER_MEMORY_MB", "32")]), Err(ExecutorError::MemoryTooSmall { .. })));
+
+        assert!(matches!(tuned.explain("Session limit exceeded: 1048576 >= 1000000"), ExecutorError::SessionLimitExceeded { .. }));
+        assert!(matches!(tuned.explain("guest panicked"), ExecutorError::Prover { .. }));
+
+        let backend = |name: &str| config(&[("FHE_VOTING_PROVER", name), ("BONSAI_API_URL", "https://api.bonsai.xyz"), ("BONSAI_API_KEY", "key")]);
+        assert_eq!(backend("bonsai")?.prover_backend, Some(ProverBackend::Bonsai));
+        assert_eq!(backend(" cpu ")?.prover_backend, Some(ProverBackend::Cpu));
+        assert!(matches!(backend("tpu"), Err(ExecutorError::UnknownBackend { .. })));
+        assert!(matches!(config(&[("FHE_VOTING_PROVER", "gpu")]), Err(ExecutorError::BackendUnavailable { missing: "FHE_VOTING_GPU_R0VM", .. })));
+        assert!(matches!(config(&[("FHE_VOTING_PROVER", "bonsai")]), Err(ExecutorError::BackendUnavailable { backend: ProverBackend::Bonsai, .. })));
+        assert!(ProverBackend::Dev.opts(&ProverOpts::default()).dev_mode() && !ProverBackend::Cpu.opts(&ProverOpts::default()).dev_mode());
+        Ok(())
+    }
+}
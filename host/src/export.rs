@@ -0,0 +1,245 @@
+// Arrow and Parquet exports of election data.
+// An audit otherwise means scraping the host's output or decoding the audit log
+// by hand. `ElectionTables` lays the election out as three tables an analyst
+// can load straight into a dataframe (pandas, polars, DuckDB, Spark):
+//
+//   ballots     one row per accepted ballot: position in the ballot set, ballot
+//               hash (its tracking code), voter address, election, sequence,
+//               authorization kind and ciphertext size
+//   rejections  one row per rejected submission: position in the audit log,
+//               ballot hash and the reason it was refused
+//   results     one row per candidate: label, published count (null when the
+//               counts were withheld) and whether it is leading
+//
+// Each table is written as a Parquet file (Snappy-compressed) or an Arrow IPC
+// file. Digests are lowercase hex strings, as everywhere else outside the
+// journal. The tables describe what the host tallied; only the receipt proves
+// it, so an audit should still check `ballots` against the proven ballot set
+// root and `results` against the journal.
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, BooleanArray, RecordBatch, StringArray, UInt32Array, UInt64Array};
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::errors::ParquetError;
+use parquet::file::properties::WriterProperties;
+use thiserror::Error;
+
+use crate::ballot_set::ballot_leaf_hash;
+use crate::scheme::{TallyScheme, VoteScheme};
+use crate::types::{AuditRecord, BallotAuthorization, Candidate, EncryptedVote};
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] ArrowError),
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] ParquetError),
+    #[error("Export I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Unknown export format {0:?} (expected parquet or arrow)")]
+    UnknownFormat(String),
+}
+
+/// File format the tables are written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Parquet,
+    ArrowIpc,
+}
+
+impl ExportFormat {
+    pub fn parse(name: &str) -> Result<Self, ExportError> {
+        match name {
+            "parquet" => Ok(ExportFormat::Parquet),
+            "arrow" | "ipc" => Ok(ExportFormat::ArrowIpc),
+            other => Err(ExportError::UnknownFormat(other.to_string())),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::ArrowIpc => "arrow",
+        }
+    }
+}
+
+/// An election's ballots, rejections and results as Arrow record batches.
+#[derive(Debug, Clone)]
+pub struct ElectionTables {
+    pub ballots: RecordBatch,
+    pub rejections: RecordBatch,
+    pub results: RecordBatch,
+}
+
+impl ElectionTables {
+    /// Tables for the accepted `ballots`, the rejections in `audit_log` and the
+    /// published `counts` (`None` when they were withheld) under `scheme`.
+    pub fn new(
+        ballots: &[EncryptedVote],
+        audit_log: &[AuditRecord],
+        candidates: &[Candidate],
+        counts: Option<&[u32]>,
+        scheme: &TallyScheme,
+    ) -> Result<Self, ExportError> {
+        Ok(ElectionTables {
+            ballots: ballots_batch(ballots)?,
+            rejections: rejections_batch(audit_log)?,
+            results: results_batch(candidates, counts, scheme)?,
+        })
+    }
+
+    /// Write `ballots`, `rejections` and `results` files into `dir`.
+    pub fn write(&self, dir: impl AsRef<Path>, format: ExportFormat) -> Result<Vec<PathBuf>, ExportError> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        [("ballots", &self.ballots), ("rejections", &self.rejections), ("results", &self.results)]
+            .into_iter()
+            .map(|(name, batch)| {
+                let path = dir.join(format!("{}.{}", name, format.extension()));
+                write_batch(batch, &path, format)?;
+                Ok(path)
+            })
+            .collect()
+    }
+}
+
+/// One row per accepted ballot.
+pub fn ballots_batch(ballots: &[EncryptedVote]) -> Result<RecordBatch, ExportError> {
+    let schema = Schema::new(vec![
+        Field::new("position", DataType::UInt32, false),
+        Field::new("ballot_hash", DataType::Utf8, false),
+        Field::new("voter_address", DataType::Utf8, false),
+        Field::new("election_id", DataType::Utf8, false),
+        Field::new("sequence", DataType::UInt64, false),
+        Field::new("authorization", DataType::Utf8, false),
+        Field::new("ciphertext_bytes", DataType::UInt64, false),
+    ]);
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt32Array::from_iter_values(0..ballots.len() as u32)),
+        Arc::new(StringArray::from_iter_values(ballots.iter().map(|ballot| hex::encode(ballot_leaf_hash(ballot))))),
+        Arc::new(StringArray::from_iter_values(ballots.iter().map(|ballot| ballot.voter_address.as_str()))),
+        Arc::new(StringArray::from_iter_values(ballots.iter().map(|ballot| ballot.election_id.to_string()))),
+        Arc::new(UInt64Array::from_iter_values(ballots.iter().map(|ballot| ballot.sequence))),
+        Arc::new(StringArray::from_iter_values(ballots.iter().map(|ballot| match ballot.authorization {
+            BallotAuthorization::Credential { .. } => "credential",
+            BallotAuthorization::Membership(_) => "membership",
+        }))),
+        Arc::new(UInt64Array::from_iter_values(
+            ballots.iter().map(|ballot| ballot.encrypted_vote_vector.iter().map(|ciphertext| ciphertext.len() as u64).sum()),
+        )),
+    ];
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+/// One row per rejected submission in the audit log.
+pub fn rejections_batch(audit_log: &[AuditRecord]) -> Result<RecordBatch, ExportError> {
+    let rejected: Vec<(usize, &AuditRecord)> = audit_log.iter().enumerate().filter(|(_, record)| !record.accepted).collect();
+    let schema = Schema::new(vec![
+        Field::new("submission", DataType::UInt64, false),
+        Field::new("ballot_hash", DataType::Utf8, false),
+        Field::new("reason", DataType::Utf8, false),
+    ]);
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt64Array::from_iter_values(rejected.iter().map(|(index, _)| *index as u64))),
+        Arc::new(StringArray::from_iter_values(rejected.iter().map(|(_, record)| hex::encode(record.ballot_hash)))),
+        Arc::new(StringArray::from_iter_values(rejected.iter().map(|(_, record)| record.reason.as_str()))),
+    ];
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+/// One row per candidate; counts and leaders are null when the counts were withheld.
+pub fn results_batch(candidates: &[Candidate], counts: Option<&[u32]>, scheme: &TallyScheme) -> Result<RecordBatch, ExportError> {
+    let leading = counts.map(|counts| scheme.finalize(&counts.iter().map(|&count| u64::from(count)).collect::<Vec<_>>()));
+    let schema = Schema::new(vec![
+        Field::new("candidate", DataType::UInt32, false),
+        Field::new("label", DataType::Utf8, false),
+        Field::new("count", DataType::UInt32, true),
+        Field::new("leading", DataType::Boolean, true),
+    ]);
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(UInt32Array::from_iter_values(candidates.iter().map(|candidate| u32::from(candidate.id)))),
+        Arc::new(StringArray::from_iter_values(candidates.iter().map(|candidate| candidate.label.as_str()))),
+        Arc::new(UInt32Array::from_iter((0..candidates.len()).map(|index| counts.and_then(|counts| counts.get(index).copied())))),
+        Arc::new(BooleanArray::from_iter((0..candidates.len()).map(|index| leading.as_ref().map(|leading| leading.contains(&index))))),
+    ];
+    Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+}
+
+/// Write one table to `path`.
+pub fn write_batch(batch: &RecordBatch, path: &Path, format: ExportFormat) -> Result<(), ExportError> {
+    let file = File::create(path)?;
+    match format {
+        ExportFormat::Parquet => {
+            let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+            let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(properties))?;
+            writer.write(batch)?;
+            writer.close()?;
+        }
+        ExportFormat::ArrowIpc => {
+            let mut writer = arrow_ipc::writer::FileWriter::try_new(file, &batch.schema())?;
+            writer.write(batch)?;
+            writer.finish()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::UInt64Type;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use crate::types::{Digest32, MembershipProof, VoteOption};
+
+    fn ballot(voter: &str, sequence: u64) -> EncryptedVote {
+        EncryptedVote {
+            voter_address: voter.to_string(),
+            election_id: Digest32([7u8; 32]),
+            nonce: [sequence as u8; 32],
+            encrypted_vote_vector: vec![vec![1u8; 10], vec![2u8; 10], vec![3u8; 10]],
+            signature: String::new(),
+            authorization: BallotAuthorization::Membership(MembershipProof { nullifier: [0u8; 32], challenges: Vec::new(), responses: Vec::new() }),
+            sequence,
+            actual_choice: VoteOption::Option1,
+        }
+    }
+
+    #[test]
+    fn test_tables_round_trip_through_parquet_and_arrow() -> Result<(), ExportError> {
+        let ballots = vec![ballot("0xa", 0), ballot("0xb", 1)];
+        let audit_log = vec![
+            AuditRecord { ballot_hash: ballot_leaf_hash(&ballots[0]), accepted: true, reason: String::new() },
+            AuditRecord { ballot_hash: [9u8; 32], accepted: false, reason: "Duplicate ballot".to_string() },
+            AuditRecord { ballot_hash: ballot_leaf_hash(&ballots[1]), accepted: true, reason: String::new() },
+        ];
+        let candidates = vec![Candidate { id: 1, label: "Yes".to_string() }, Candidate { id: 2, label: "No".to_string() }];
+        let tables = ElectionTables::new(&ballots, &audit_log, &candidates, Some(&[5, 3]), &TallyScheme::Plurality)?;
+        assert_eq!((tables.ballots.num_rows(), tables.rejections.num_rows(), tables.results.num_rows()), (2, 1, 2));
+        assert_eq!(tables.rejections.column(0).as_primitive::<UInt64Type>().value(0), 1);
+
+        let dir = std::env::temp_dir().join(format!("fhe-voting-export-{}", std::process::id()));
+        let parquet = tables.write(&dir, ExportFormat::Parquet)?;
+        let read = ParquetRecordBatchReaderBuilder::try_new(File::open(&parquet[0])?)?.build()?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(read, vec![tables.ballots.clone()]);
+        assert_eq!(read[0].column(1).as_string::<i32>().value(1), hex::encode(ballot_leaf_hash(&ballots[1])));
+
+        let arrow = tables.write(&dir, ExportFormat::parse("arrow")?)?;
+        let read = arrow_ipc::reader::FileReader::try_new(File::open(&arrow[2])?, None)?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(read, vec![tables.results.clone()]);
+        assert!(read[0].column(3).as_boolean().value(0) && !read[0].column(3).as_boolean().value(1));
+        std::fs::remove_dir_all(&dir)?;
+
+        // Withheld counts export as nulls, not zeros
+        let withheld = results_batch(&candidates, None, &TallyScheme::Plurality)?;
+        assert_eq!(withheld.column(2).null_count(), 2);
+        assert!(matches!(ExportFormat::parse("csv"), Err(ExportError::UnknownFormat(_))));
+        Ok(())
+    }
+}
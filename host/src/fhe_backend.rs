@@ -0,0 +1,92 @@
+// Pluggable FHE backends (host side).
+// Counterpart of the guest's `FheBackend` for client-side and host-side use:
+// key generation, encryption, homomorphic addition, (de)serialization and
+// decryption. Library backends can fail at every step, so unlike the guest
+// trait every operation returns a `BackendError`. `PureRustBackend` is the
+// scheme the guest tallies with, so it can be compared against library backends.
+
+use thiserror::Error;
+
+use crate::fhe_client::{
+    decrypt_plaintext, Cipher, PrivateKey, PublicKey, PureRustFheRuntime, Signed, CIPHERTEXT_MODULUS,
+};
+
+#[derive(Error, Debug)]
+pub enum BackendError {
+    #[error("Key generation failed: {reason}")]
+    KeyGeneration { reason: String },
+    #[error("Encryption failed: {reason}")]
+    Encryption { reason: String },
+    #[error("Homomorphic evaluation failed: {reason}")]
+    Evaluation { reason: String },
+    #[error("Decryption failed: {reason}")]
+    Decryption { reason: String },
+    #[error("Ciphertext (de)serialization failed: {reason}")]
+    Serialization { reason: String },
+}
+
+pub trait FheBackend {
+    type PublicKey;
+    type PrivateKey;
+    type Ciphertext;
+
+    fn generate_keys(&mut self) -> Result<(Self::PublicKey, Self::PrivateKey), BackendError>;
+    fn encrypt(&self, value: i64, public_key: &Self::PublicKey) -> Result<Self::Ciphertext, BackendError>;
+    /// `sum += term`, homomorphically.
+    fn add_assign(&self, sum: &mut Self::Ciphertext, term: &Self::Ciphertext) -> Result<(), BackendError>;
+    fn serialize(&self, ciphertext: &Self::Ciphertext) -> Result<Vec<u8>, BackendError>;
+    fn deserialize(&self, data: &[u8]) -> Result<Self::Ciphertext, BackendError>;
+    fn decrypt(&self, ciphertext: &Self::Ciphertext, private_key: &Self::PrivateKey) -> Result<i64, BackendError>;
+}
+
+/// The pure Rust scheme: host client encryption, guest addition and decoding.
+pub struct PureRustBackend {
+    runtime: PureRustFheRuntime,
+}
+
+impl PureRustBackend {
+    pub fn new() -> Self {
+        PureRustBackend { runtime: PureRustFheRuntime::new() }
+    }
+}
+
+impl Default for PureRustBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FheBackend for PureRustBackend {
+    type PublicKey = PublicKey;
+    type PrivateKey = PrivateKey;
+    type Ciphertext = Cipher<Signed>;
+
+    fn generate_keys(&mut self) -> Result<(PublicKey, PrivateKey), BackendError> {
+        Ok(self.runtime.generate_keys())
+    }
+
+    fn encrypt(&self, value: i64, public_key: &PublicKey) -> Result<Cipher<Signed>, BackendError> {
+        self.runtime
+            .encrypt(Signed::from(value), public_key)
+            .map_err(|reason| BackendError::Encryption { reason })
+    }
+
+    fn add_assign(&self, sum: &mut Cipher<Signed>, term: &Cipher<Signed>) -> Result<(), BackendError> {
+        for (a, &b) in sum.ciphertext_data.iter_mut().zip(&term.ciphertext_data) {
+            *a = (*a + b) % CIPHERTEXT_MODULUS;
+        }
+        Ok(())
+    }
+
+    fn serialize(&self, ciphertext: &Cipher<Signed>) -> Result<Vec<u8>, BackendError> {
+        Ok(ciphertext.serialize())
+    }
+
+    fn deserialize(&self, data: &[u8]) -> Result<Cipher<Signed>, BackendError> {
+        Cipher::deserialize(data).map_err(|e| BackendError::Serialization { reason: e.to_string() })
+    }
+
+    fn decrypt(&self, ciphertext: &Cipher<Signed>, private_key: &PrivateKey) -> Result<i64, BackendError> {
+        Ok(decrypt_plaintext(ciphertext, private_key).coefficients()[0] as i64)
+    }
+}
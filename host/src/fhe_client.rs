@@ -2,19 +2,52 @@
 // This performs actual FHE encryption that the client would do
 
 use serde::{Serialize, Deserialize};
-use rand::Rng;
-use rand_distr::{Normal, Distribution};
+use rand::{Rng, RngCore};
+use rayon::prelude::*;
+use risc0_zkvm::sha::{Impl, Sha256};
 use thiserror::Error;
 
+pub use crate::noise_distribution::{NoiseDistribution, MAX_BINOMIAL_ETA};
+use crate::noise_distribution::NOISE_CDT;
+
 // Enhanced security parameters for BFV scheme (must match guest implementation)
 // Balanced for demonstration with improved security over original
-const PLAINTEXT_MODULUS: u64 = 65537; // Prime modulus for better security
-const CIPHERTEXT_MODULUS: u64 = 288230376151711744; // 2^58 for enhanced security
-const POLYNOMIAL_DEGREE: usize = 32; // Increased from 8, but manageable for serde
+pub(crate) const PLAINTEXT_MODULUS: u64 = 65537; // Prime modulus for better security
+pub(crate) const CIPHERTEXT_MODULUS: u64 = 288230376151711744; // 2^58 for enhanced security
+pub(crate) const POLYNOMIAL_DEGREE: usize = 32; // Increased from 8, but manageable for serde
 
 // Additional security parameters
-const NOISE_STANDARD_DEVIATION: f64 = 3.19; // Optimized for security/correctness balance
-const MAX_NOISE_BOUND: u64 = PLAINTEXT_MODULUS / 16; // Tighter noise bound
+
+/// Largest magnitude of an encoded signed value: plaintexts are centered mod t.
+pub const MAX_PLAINTEXT_MAGNITUDE: i64 = (PLAINTEXT_MODULUS / 2) as i64;
+
+
+/// Nonzero secret key coefficients by default: half of them, as in sparse
+/// ternary BFV secrets.
+pub const DEFAULT_SECRET_HAMMING_WEIGHT: u32 = POLYNOMIAL_DEGREE as u32 / 2;
+
+// Domain separation tag for key fingerprints (must match guest implementation)
+const KEY_FINGERPRINT_TAG: u8 = 0x05;
+
+// Domain separation tag for the public polynomial every key is built on (must match guest implementation)
+const PUBLIC_POLYNOMIAL_TAG: u8 = 0x1f;
+
+/// Ballot ciphertexts are prefixed with the fingerprint of the key they were
+/// encrypted under, so a tally can refuse ciphertexts under any other key.
+pub const KEY_FINGERPRINT_LEN: usize = 32;
+
+// Domain separation tags for the ballot ciphertext header (must match guest implementation)
+const PARAMETER_DIGEST_TAG: u8 = 0x0f;
+const CIPHERTEXT_CHECKSUM_TAG: u8 = 0x10;
+
+/// A ballot ciphertext is key fingerprint || parameter digest || coefficients,
+/// optionally followed by a `CHECKSUM_LEN`-byte checksum over everything before it.
+pub const BOUND_HEADER_LEN: usize = KEY_FINGERPRINT_LEN + 32;
+pub const CHECKSUM_LEN: usize = 8;
+const CIPHERTEXT_LEN: usize = POLYNOMIAL_DEGREE * 2 * 8;
+
+// Vote vectors are one-hot, so a ballot adds at most 1 to any candidate's tally
+pub const MAX_BALLOT_WEIGHT: u64 = 1;
 
 #[derive(Error, Debug)]
 pub enum FheClientError {
@@ -24,6 +57,109 @@ pub enum FheClientError {
     KeyGenerationFailed { reason: String },
     #[error("Invalid vote option: {option}")]
     InvalidVoteOption { option: u8 },
+    #[error("Invalid ciphertext length: expected {expected}, got {actual}")]
+    InvalidCiphertextLength { expected: usize, actual: usize },
+    #[error("Tally overflow: {ballots} ballots could exceed the plaintext modulus (at most {max} can be tallied)")]
+    TallyOverflow { ballots: usize, max: u64 },
+    #[error("Ciphertext is bound to a different public key")]
+    KeyMismatch,
+    #[error("Invalid FHE parameters: {reason}")]
+    InvalidParameters { reason: String },
+    #[error("Ciphertext coefficient {index} is not below the ciphertext modulus")]
+    NonCanonicalCoefficient { index: usize },
+    #[error("Ciphertext was encrypted under different scheme parameters")]
+    ParameterMismatch,
+    #[error("Ciphertext checksum does not match its contents")]
+    ChecksumMismatch,
+    #[error("{values} values do not fit in the {max} plaintext coefficients")]
+    PackingOverflow { values: usize, max: usize },
+    #[error("Plaintext value {value} is outside the centered range of the plaintext modulus")]
+    PlaintextOutOfRange { value: i64 },
+    #[error("Client holds no private key: its election key was generated elsewhere")]
+    MissingPrivateKey,
+}
+
+/// H(tag || t || q || N): the scheme parameters a ballot ciphertext declares
+/// in its header (matches guest `parameter_digest`).
+pub fn parameter_digest() -> [u8; 32] {
+    let mut buf = vec![PARAMETER_DIGEST_TAG];
+    buf.extend_from_slice(&PLAINTEXT_MODULUS.to_le_bytes());
+    buf.extend_from_slice(&CIPHERTEXT_MODULUS.to_le_bytes());
+    buf.extend_from_slice(&(POLYNOMIAL_DEGREE as u32).to_le_bytes());
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+// First bytes of H(tag || header || coefficients)
+fn ciphertext_checksum(bound: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut buf = vec![CIPHERTEXT_CHECKSUM_TAG];
+    buf.extend_from_slice(bound);
+    let digest: [u8; 32] = (*Impl::hash_bytes(&buf)).into();
+    digest[..CHECKSUM_LEN].try_into().expect("checksum is shorter than the digest")
+}
+
+// The noise model's bound on a fresh encryption
+impl NoiseDistribution {
+    /// Largest noise magnitude a sample can have.
+    pub fn max_magnitude(self) -> u64 {
+        match self {
+            NoiseDistribution::DiscreteGaussian => NOISE_CDT.len() as u64,
+            NoiseDistribution::CenteredBinomial { eta } => u64::from(eta),
+        }
+    }
+}
+
+/// Scheme choices made within this build's fixed moduli and degree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FheParameters {
+    pub noise: NoiseDistribution,
+    /// Secret key coefficients are ternary, {-1, 0, 1}, with exactly this many nonzero.
+    pub secret_hamming_weight: u32,
+}
+
+impl Default for FheParameters {
+    fn default() -> Self {
+        FheParameters {
+            noise: NoiseDistribution::default(),
+            secret_hamming_weight: DEFAULT_SECRET_HAMMING_WEIGHT,
+        }
+    }
+}
+
+impl FheParameters {
+    pub fn validate(&self) -> Result<(), FheClientError> {
+        if let NoiseDistribution::CenteredBinomial { eta } = self.noise {
+            if !(1..=MAX_BINOMIAL_ETA).contains(&eta) {
+                return Err(FheClientError::InvalidParameters {
+                    reason: format!("centered binomial eta {} is not in 1..={}", eta, MAX_BINOMIAL_ETA),
+                });
+            }
+        }
+        if !(1..=POLYNOMIAL_DEGREE as u32).contains(&self.secret_hamming_weight) {
+            return Err(FheClientError::InvalidParameters {
+                reason: format!("secret Hamming weight {} is not in 1..={}", self.secret_hamming_weight, POLYNOMIAL_DEGREE),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A plaintext polynomial: N coefficients mod t, built with `encode_slice`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plaintext {
+    coefficients: Vec<u64>,
+}
+
+impl Plaintext {
+    pub fn coefficients(&self) -> &[u64] {
+        &self.coefficients
+    }
+    
+    // Values already reduced mod t, zero-padded to N coefficients
+    fn from_residues(residues: &[u64]) -> Self {
+        let mut coefficients = residues.to_vec();
+        coefficients.resize(POLYNOMIAL_DEGREE, 0);
+        Plaintext { coefficients }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -42,6 +178,17 @@ pub struct PublicKey {
     pub key_data: Vec<u64>,
 }
 
+impl PublicKey {
+    /// H(tag || coefficients), the key binding carried by every ballot ciphertext.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let mut buf = vec![KEY_FINGERPRINT_TAG];
+        for coeff in &self.key_data {
+            buf.extend_from_slice(&coeff.to_le_bytes());
+        }
+        (*Impl::hash_bytes(&buf)).into()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PrivateKey {
     pub secret_data: Vec<u64>,
@@ -61,118 +208,599 @@ impl<T> Cipher<T> {
         }
         result
     }
+    
+    /// Serialize behind the fingerprint of the key this was encrypted under
+    /// and the digest of the scheme parameters.
+    pub fn serialize_bound(&self, key_fingerprint: &[u8; 32]) -> Vec<u8> {
+        [key_fingerprint.as_slice(), &parameter_digest(), &self.serialize()].concat()
+    }
+    
+    /// `serialize_bound` followed by a checksum, so corruption in transit or
+    /// storage is caught on deserialization.
+    pub fn serialize_bound_with_checksum(&self, key_fingerprint: &[u8; 32]) -> Vec<u8> {
+        let mut result = self.serialize_bound(key_fingerprint);
+        result.extend(ciphertext_checksum(&result));
+        result
+    }
+}
+
+impl Cipher<Signed> {
+    /// Every coefficient must already be reduced mod q (as in the guest), so
+    /// mod-q arithmetic cannot overflow.
+    pub fn deserialize(data: &[u8]) -> Result<Self, FheClientError> {
+        if data.len() != CIPHERTEXT_LEN {
+            return Err(FheClientError::InvalidCiphertextLength {
+                expected: CIPHERTEXT_LEN,
+                actual: data.len(),
+            });
+        }
+        
+        let (words, _) = data.as_chunks::<8>();
+        let ciphertext_data: Vec<u64> = words.iter().map(|&word| u64::from_le_bytes(word)).collect();
+        if let Some(index) = ciphertext_data.iter().position(|&c| c >= CIPHERTEXT_MODULUS) {
+            return Err(FheClientError::NonCanonicalCoefficient { index });
+        }
+        
+        Ok(Cipher {
+            ciphertext_data,
+            _phantom: std::marker::PhantomData,
+        })
+    }
+    
+    /// Coefficient-wise sum mod q (matches the guest's `AddAssign`).
+    pub fn add(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a.wrapping_add(b))
+    }
+    
+    /// Coefficient-wise difference mod q (matches the guest's `SubAssign`).
+    pub fn sub(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a.wrapping_sub(b))
+    }
+    
+    /// Multiply by a public scalar mod q (matches the guest's `mul_plain`).
+    pub fn mul_plain(&self, scalar: u64) -> Self {
+        Cipher {
+            ciphertext_data: self.ciphertext_data.iter().map(|&c| c.wrapping_mul(scalar) & (CIPHERTEXT_MODULUS - 1)).collect(),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+    
+    /// Multiply both polynomials by X^steps mod X^N + 1 (matches the guest's `rotate`).
+    pub fn rotate(&self, steps: u32) -> Self {
+        let steps = steps as usize % (2 * POLYNOMIAL_DEGREE);
+        let mut ciphertext_data = vec![0u64; POLYNOMIAL_DEGREE * 2];
+        for (input, output) in self.ciphertext_data.chunks(POLYNOMIAL_DEGREE).zip(ciphertext_data.chunks_mut(POLYNOMIAL_DEGREE)) {
+            for (i, &c) in input.iter().enumerate() {
+                let shifted = i + steps;
+                let negate = (shifted / POLYNOMIAL_DEGREE) % 2 == 1;
+                output[shifted % POLYNOMIAL_DEGREE] = if negate { c.wrapping_neg() & (CIPHERTEXT_MODULUS - 1) } else { c };
+            }
+        }
+        Cipher { ciphertext_data, _phantom: std::marker::PhantomData }
+    }
+    
+    fn zip_with(&self, other: &Self, op: impl Fn(u64, u64) -> u64) -> Self {
+        Cipher {
+            ciphertext_data: self.ciphertext_data.iter().zip(&other.ciphertext_data).map(|(&a, &b)| op(a, b) & (CIPHERTEXT_MODULUS - 1)).collect(),
+            _phantom: std::marker::PhantomData,
+        }
+    }
+    
+    /// Deserialize a key-bound ballot ciphertext, refusing one bound to another
+    /// key, declaring other scheme parameters or failing its embedded checksum.
+    pub fn deserialize_bound(data: &[u8], key_fingerprint: &[u8; 32]) -> Result<Self, FheClientError> {
+        let expected_len = BOUND_HEADER_LEN + CIPHERTEXT_LEN;
+        if data.len() != expected_len && data.len() != expected_len + CHECKSUM_LEN {
+            return Err(FheClientError::InvalidCiphertextLength { expected: expected_len, actual: data.len() });
+        }
+        if data[..KEY_FINGERPRINT_LEN] != key_fingerprint[..] {
+            return Err(FheClientError::KeyMismatch);
+        }
+        if data[KEY_FINGERPRINT_LEN..BOUND_HEADER_LEN] != parameter_digest() {
+            return Err(FheClientError::ParameterMismatch);
+        }
+        let (bound, checksum) = data.split_at(expected_len);
+        if !checksum.is_empty() && checksum != ciphertext_checksum(bound) {
+            return Err(FheClientError::ChecksumMismatch);
+        }
+        Self::deserialize(&bound[BOUND_HEADER_LEN..])
+    }
+}
+
+/// Round a scaled coefficient of c0 to the nearest multiple of Δ, mod t,
+/// tolerating noise of either sign. The key term still has to come off.
+pub(crate) fn decode_scaled_plaintext(noisy_scaled_plaintext: u64) -> u64 {
+    let scaling_factor = CIPHERTEXT_MODULUS / PLAINTEXT_MODULUS;
+    ((noisy_scaled_plaintext + scaling_factor / 2) / scaling_factor) % PLAINTEXT_MODULUS
+}
+
+/// c mod q read as the integer in -q/2..q/2 it stands for, reduced mod t:
+/// a ternary key coefficient, or a c1 coefficient after additions and negations.
+pub(crate) fn residue_mod_t(c: u64) -> u64 {
+    let signed = if c >= CIPHERTEXT_MODULUS / 2 { c as i64 - CIPHERTEXT_MODULUS as i64 } else { c as i64 };
+    signed.rem_euclid(PLAINTEXT_MODULUS as i64) as u64
+}
+
+/// Negacyclic product mod (X^N + 1, t) (matches guest
+/// `multiply_plaintext_polynomials`, which uses the NTT).
+pub(crate) fn multiply_polynomials(a: &[u64], b: &[u64]) -> Vec<u64> {
+    let mut product = vec![0u64; POLYNOMIAL_DEGREE];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            let term = x % PLAINTEXT_MODULUS * (y % PLAINTEXT_MODULUS) % PLAINTEXT_MODULUS;
+            let k = (i + j) % POLYNOMIAL_DEGREE;
+            // X^N = -1
+            product[k] = if i + j < POLYNOMIAL_DEGREE { (product[k] + term) % PLAINTEXT_MODULUS } else { (product[k] + PLAINTEXT_MODULUS - term) % PLAINTEXT_MODULUS };
+        }
+    }
+    product
+}
+
+/// The public polynomial a: N coefficients mod t hashed from a fixed tag
+/// (must match guest `public_polynomial`). A public key is b = a*s mod t.
+pub(crate) fn public_polynomial() -> Vec<u64> {
+    (0..POLYNOMIAL_DEGREE as u32)
+        .map(|i| {
+            let mut buf = vec![PUBLIC_POLYNOMIAL_TAG];
+            buf.extend_from_slice(&i.to_le_bytes());
+            let digest: [u8; 32] = (*Impl::hash_bytes(&buf)).into();
+            u64::from_le_bytes(digest[..8].try_into().unwrap()) % PLAINTEXT_MODULUS
+        })
+        .collect()
+}
+
+/// The weights m with sum_j m_j * s_j = (c1 * s)[0] mod t for any secret s
+/// (must match guest `key_term_mask`): a trustee weights its key share by
+/// them to get its key term for coefficient 0.
+pub(crate) fn key_term_mask(cipher: &Cipher<Signed>) -> Vec<u64> {
+    let residues: Vec<u64> = cipher.ciphertext_data[POLYNOMIAL_DEGREE..].iter().map(|&c| residue_mod_t(c)).collect();
+    // X^N = -1: c1_j meets s_(N-j) in coefficient 0 with a minus sign
+    (0..POLYNOMIAL_DEGREE)
+        .map(|j| if j == 0 { residues[0] } else { (PLAINTEXT_MODULUS - residues[POLYNOMIAL_DEGREE - j]) % PLAINTEXT_MODULUS })
+        .collect()
+}
+
+/// Every plaintext coefficient of a ciphertext, round(c0 / Δ) - c1*s mod t
+/// (matches guest `decrypt_plaintext`), e.g. every candidate's count from a
+/// sum of `encrypt_packed_vote` ballots.
+pub fn decrypt_plaintext(ciphertext: &Cipher<Signed>, private_key: &PrivateKey) -> Plaintext {
+    let secret: Vec<u64> = private_key.secret_data.iter().map(|&s| residue_mod_t(s)).collect();
+    let c1: Vec<u64> = ciphertext.ciphertext_data[POLYNOMIAL_DEGREE..].iter().map(|&c| residue_mod_t(c)).collect();
+    let key_terms = multiply_polynomials(&c1, &secret);
+    let coefficients = ciphertext.ciphertext_data[..POLYNOMIAL_DEGREE]
+        .iter()
+        .zip(&key_terms)
+        .map(|(&c, &key_term)| (decode_scaled_plaintext(c) + PLAINTEXT_MODULUS - key_term) % PLAINTEXT_MODULUS)
+        .collect();
+    Plaintext { coefficients }
+}
+
+/// Coefficient encoding (matches the guest runtime's `encode_slice`): value i
+/// becomes coefficient i. Values are signed, at most N of them, each within
+/// ±`MAX_PLAINTEXT_MAGNITUDE`.
+pub fn encode_slice(values: &[i64]) -> Result<Plaintext, FheClientError> {
+    if values.len() > POLYNOMIAL_DEGREE {
+        return Err(FheClientError::PackingOverflow { values: values.len(), max: POLYNOMIAL_DEGREE });
+    }
+    if let Some(&value) = values.iter().find(|value| value.unsigned_abs() > MAX_PLAINTEXT_MAGNITUDE as u64) {
+        return Err(FheClientError::PlaintextOutOfRange { value });
+    }
+    let residues: Vec<u64> = values.iter().map(|&value| value.rem_euclid(PLAINTEXT_MODULUS as i64) as u64).collect();
+    Ok(Plaintext::from_residues(&residues))
+}
+
+/// Inverse of `encode_slice`: every coefficient as its centered value mod t.
+pub fn decode(plaintext: &Plaintext) -> Vec<i64> {
+    plaintext
+        .coefficients
+        .iter()
+        .map(|&c| if c > PLAINTEXT_MODULUS / 2 { c as i64 - PLAINTEXT_MODULUS as i64 } else { c as i64 })
+        .collect()
+}
+
+/// Most ballots that can be tallied before a candidate's count could wrap
+/// modulo PLAINTEXT_MODULUS (must match the guest's `max_tally_ballots`).
+pub const fn max_tally_ballots() -> u64 {
+    (PLAINTEXT_MODULUS - 1) / MAX_BALLOT_WEIGHT
+}
+
+/// Most contributions of values up to `max_value` whose slot sums cannot wrap
+/// modulo PLAINTEXT_MODULUS (must match the guest's `max_sum_contributions`).
+pub const fn max_sum_contributions(max_value: u64) -> u64 {
+    (PLAINTEXT_MODULUS - 1) / max_value
+}
+
+/// Refuse elections whose tallies could silently wrap.
+pub fn check_tally_capacity(ballots: usize) -> Result<(), FheClientError> {
+    if ballots as u64 > max_tally_ballots() {
+        return Err(FheClientError::TallyOverflow { ballots, max: max_tally_ballots() });
+    }
+    Ok(())
 }
 
 pub struct FheClient {
     runtime: PureRustFheRuntime,
     public_key: PublicKey,
+    private_key: Option<PrivateKey>, // Only for a key this client generated
+    key_fingerprint: [u8; 32],
 }
 
 impl FheClient {
     pub fn new() -> Self {
-        let mut runtime = PureRustFheRuntime::new();
-        let (public_key, _private_key) = runtime.generate_keys();
+        Self::with_generated_key(PureRustFheRuntime::new())
+    }
+    
+    /// Client with a fresh key drawn under `parameters`, also encrypting with them.
+    pub fn generate(parameters: FheParameters) -> Result<Self, FheClientError> {
+        parameters.validate()?;
+        Ok(Self::with_generated_key(PureRustFheRuntime { parameters }))
+    }
+    
+    fn with_generated_key(mut runtime: PureRustFheRuntime) -> Self {
+        let (public_key, private_key) = runtime.generate_keys();
         
         FheClient {
             runtime,
+            key_fingerprint: public_key.fingerprint(),
             public_key,
+            private_key: Some(private_key),
+        }
+    }
+    
+    /// Client encrypting under an externally generated election key
+    /// (e.g. the combined public key from the trustees' DKG).
+    pub fn with_public_key(public_key: PublicKey) -> Self {
+        FheClient {
+            runtime: PureRustFheRuntime::new(),
+            key_fingerprint: public_key.fingerprint(),
+            public_key,
+            private_key: None,
         }
     }
     
     // REAL FHE ENCRYPTION - no simulation!
     pub fn encrypt_vote_vector(&self, vote_choice: crate::types::VoteOption) -> Result<Vec<Vec<u8>>, FheClientError> {
         println!("🔐 [FHE Client] Performing REAL FHE encryption of vote vector");
-        
-        let mut encrypted_vector = Vec::new();
-        
-        // Create vote vector: [1,0,0], [0,1,0], or [0,0,1]
-        for candidate_idx in 0..3 {
-            let vote_value = if candidate_idx == (vote_choice as usize - 1) { 1 } else { 0 };
-            
-            println!("  🔐 Encrypting {} for candidate {}", vote_value, candidate_idx + 1);
-            
-            // REAL FHE ENCRYPTION
-            let plaintext = Signed::from(vote_value);
-            let ciphertext = self.runtime.encrypt(plaintext, &self.public_key)
-                .map_err(|e| FheClientError::EncryptionFailed { reason: e })?;
-            let serialized = ciphertext.serialize();
-            
-            encrypted_vector.push(serialized);
-        }
-        
+        let encrypted_vector = self.encrypt_one_hot(vote_choice)?;
         println!("✅ [FHE Client] Vote vector encrypted with real FHE");
         Ok(encrypted_vector)
     }
     
+    /// Encrypt many vote vectors across all cores, in the order of `vote_choices`.
+    /// Intended for generating large test elections, so it does not log per ballot.
+    pub fn encrypt_vote_vectors(&self, vote_choices: &[crate::types::VoteOption]) -> Result<Vec<Vec<Vec<u8>>>, FheClientError> {
+        vote_choices.par_iter().map(|&vote_choice| self.encrypt_one_hot(vote_choice)).collect()
+    }
+    
+    /// Encrypt an integer vector slot by slot, each ciphertext bound to the key.
+    /// Values are reduced modulo the plaintext modulus.
+    pub fn encrypt_values(&self, values: &[u64]) -> Result<Vec<Vec<u8>>, FheClientError> {
+        values
+            .iter()
+            .map(|&value| {
+                let ciphertext = self.runtime.encrypt(Signed::from(value as i64), &self.public_key)
+                    .map_err(|e| FheClientError::EncryptionFailed { reason: e })?;
+                Ok(ciphertext.serialize_bound(&self.key_fingerprint))
+            })
+            .collect()
+    }
+    
+    /// Encrypt a whole vote vector as one key-bound ciphertext: candidate i is
+    /// plaintext coefficient i, so summing packed ballots gives every count
+    /// at once (read back with `decrypt`).
+    pub fn encrypt_packed_vote(&self, vote_choice: crate::types::VoteOption) -> Result<Vec<u8>, FheClientError> {
+        let mut vote = [0; 3];
+        vote[vote_choice as usize - 1] = 1;
+        self.encrypt_plaintext(&encode_slice(&vote)?)
+    }
+    
+    /// Encrypt a whole plaintext polynomial into one key-bound ciphertext.
+    pub fn encrypt_plaintext(&self, plaintext: &Plaintext) -> Result<Vec<u8>, FheClientError> {
+        let ciphertext = self.runtime.encrypt_plaintext(plaintext, &self.public_key)
+            .map_err(|e| FheClientError::EncryptionFailed { reason: e })?;
+        Ok(ciphertext.serialize_bound(&self.key_fingerprint))
+    }
+    
+    /// Encrypt a vote vector with every noise sample drawn from `rng`, in
+    /// candidate order; a seeded `rng` makes the ciphertexts reproducible
+    /// (see `honest_encryption`).
+    pub fn encrypt_vote_vector_with_rng(&self, vote_choice: crate::types::VoteOption, rng: &mut impl RngCore) -> Result<Vec<Vec<u8>>, FheClientError> {
+        self.encrypt_one_hot_with(vote_choice, rng)
+    }
+    
+    fn encrypt_one_hot(&self, vote_choice: crate::types::VoteOption) -> Result<Vec<Vec<u8>>, FheClientError> {
+        self.encrypt_one_hot_with(vote_choice, &mut rand::thread_rng())
+    }
+    
+    fn encrypt_one_hot_with(&self, vote_choice: crate::types::VoteOption, rng: &mut impl RngCore) -> Result<Vec<Vec<u8>>, FheClientError> {
+        // Create vote vector: [1,0,0], [0,1,0], or [0,0,1]
+        (0..3)
+            .map(|candidate_idx| {
+                let vote_value = if candidate_idx == (vote_choice as usize - 1) { 1 } else { 0 };
+                
+                // REAL FHE ENCRYPTION
+                let plaintext = Plaintext::from_residues(&[vote_value]);
+                let ciphertext = self.runtime.encrypt_plaintext_with(&plaintext, &self.public_key, rng)
+                    .map_err(|e| FheClientError::EncryptionFailed { reason: e })?;
+                Ok(ciphertext.serialize_bound(&self.key_fingerprint))
+            })
+            .collect()
+    }
+    
+    /// Encrypt with `parameters` instead of the defaults. The key is already
+    /// fixed, so only the noise distribution applies; see `generate`.
+    pub fn with_parameters(mut self, parameters: FheParameters) -> Result<Self, FheClientError> {
+        parameters.validate()?;
+        self.runtime.parameters = parameters;
+        Ok(self)
+    }
+    
     pub fn get_public_key(&self) -> &PublicKey {
         &self.public_key
     }
+    
+    /// Decrypt with the key this client generated. A client built
+    /// `with_public_key` cannot: only the trustees' shares decrypt under it.
+    pub fn decrypt(&self, ciphertext: &Cipher<Signed>) -> Result<Plaintext, FheClientError> {
+        let private_key = self.private_key.as_ref().ok_or(FheClientError::MissingPrivateKey)?;
+        Ok(decrypt_plaintext(ciphertext, private_key))
+    }
+}
+
+impl Default for FheClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sparse ternary secret (must match guest implementation): `hamming_weight`
+/// distinct coefficients of ±1, stored mod q so -1 is q - 1, and the rest 0.
+fn sample_ternary_secret(rng: &mut impl Rng, hamming_weight: u32) -> Vec<u64> {
+    let mut secret = vec![0u64; POLYNOMIAL_DEGREE];
+    for position in rand::seq::index::sample(rng, POLYNOMIAL_DEGREE, hamming_weight as usize) {
+        secret[position] = if rng.gen() { 1 } else { CIPHERTEXT_MODULUS - 1 };
+    }
+    secret
 }
 
-struct PureRustFheRuntime {
-    noise_seed: u64,
+pub(crate) struct PureRustFheRuntime {
+    parameters: FheParameters,
 }
 
 impl PureRustFheRuntime {
     pub fn new() -> Self {
         PureRustFheRuntime {
-            noise_seed: 12345,
+            parameters: FheParameters::default(),
         }
     }
     
     pub fn generate_keys(&mut self) -> (PublicKey, PrivateKey) {
         // SECURITY FIX: Use cryptographically secure key generation
-        let mut secret_data = vec![0u64; POLYNOMIAL_DEGREE];
-        let mut key_data = vec![0u64; POLYNOMIAL_DEGREE];
-        
         // CRITICAL FIX: Use cryptographically secure random number generator
         // This replaces the predictable PRNG that was a major security vulnerability
         let mut rng = rand::thread_rng();
-        for i in 0..POLYNOMIAL_DEGREE {
-            secret_data[i] = rng.gen_range(0..PLAINTEXT_MODULUS);
-            key_data[i] = rng.gen_range(0..CIPHERTEXT_MODULUS);
-        }
+        // b = a*s mod t, no error (must match guest `generate_keys`)
+        let secret_data = sample_ternary_secret(&mut rng, self.parameters.secret_hamming_weight);
+        let secret: Vec<u64> = secret_data.iter().map(|&s| residue_mod_t(s)).collect();
+        let key_data = multiply_polynomials(&public_polynomial(), &secret);
         
         (PublicKey { key_data }, PrivateKey { secret_data })
     }
     
-    pub fn encrypt(&self, plaintext: Signed, _public_key: &PublicKey) -> Result<Cipher<Signed>, String> {
-        let plaintext_val = (plaintext.val as u64) % PLAINTEXT_MODULUS;
-        let mut ciphertext_data = vec![0u64; POLYNOMIAL_DEGREE * 2];
+    pub fn encrypt(&self, plaintext: Signed, public_key: &PublicKey) -> Result<Cipher<Signed>, String> {
+        self.encrypt_plaintext(&Plaintext::from_residues(&[plaintext.val as u64 % PLAINTEXT_MODULUS]), public_key)
+    }
+    
+    /// Must match guest `encrypt_plaintext`.
+    pub fn encrypt_plaintext(&self, plaintext: &Plaintext, public_key: &PublicKey) -> Result<Cipher<Signed>, String> {
+        self.encrypt_plaintext_with(plaintext, public_key, &mut rand::thread_rng())
+    }
+    
+    /// `encrypt_plaintext` with the mask and noise drawn from `rng`.
+    pub fn encrypt_plaintext_with(&self, plaintext: &Plaintext, public_key: &PublicKey, rng: &mut impl RngCore) -> Result<Cipher<Signed>, String> {
+        if public_key.key_data.len() != POLYNOMIAL_DEGREE || public_key.key_data.iter().any(|&b| b >= PLAINTEXT_MODULUS) {
+            return Err(format!("public key must be {} coefficients mod {}", POLYNOMIAL_DEGREE, PLAINTEXT_MODULUS));
+        }
         
-        // CRYPTOGRAPHICALLY SECURE FHE ENCRYPTION: Gaussian noise distribution
-        // Real BFV schemes use Gaussian noise for provable semantic security
-        let mut rng = rand::thread_rng();
+        // Integer noise from the configured distribution (must match guest implementation):
+        // no floating point, and no timing that depends on the noise
+        let noise = self.parameters.noise;
         
-        // Production-level Gaussian noise parameters (must match guest implementation)
-        // This standard deviation provides 128-bit security with our modulus
-        let noise_std_dev = NOISE_STANDARD_DEVIATION;
-        let gaussian = Normal::new(0.0, noise_std_dev)
-            .map_err(|_| "Failed to create Gaussian distribution".to_string())?;
+        // c = (Δ(m + b*u) + e, a*u) with u uniform mod t, so only the secret key
+        // takes the mask b*u = a*u*s back off
+        let u: Vec<u64> = (0..POLYNOMIAL_DEGREE).map(|_| rng.gen_range(0..PLAINTEXT_MODULUS)).collect();
+        let mask = multiply_polynomials(&public_key.key_data, &u);
         
-        // Scale plaintext up to higher-order bits for noise tolerance
-        // This is essential for BFV schemes to separate signal from noise
+        // Scale the masked plaintext up to higher-order bits for noise tolerance,
+        // adding the noise with its sign, i.e. negative noise wraps to just below q
         let scaling_factor = CIPHERTEXT_MODULUS / PLAINTEXT_MODULUS;
-        let scaled_plaintext = plaintext_val * scaling_factor;
-        
-        // Sample Gaussian noise and add to scaled plaintext
-        // This provides provable semantic security against chosen plaintext attacks
-        let noise_sample: f64 = gaussian.sample(&mut rng);
-        let noise_magnitude = (noise_sample.abs() as u64) % MAX_NOISE_BOUND; // Tighter security bound
-        ciphertext_data[0] = (scaled_plaintext + noise_magnitude) % CIPHERTEXT_MODULUS;
-        
-        // Fill remaining polynomial coefficients with cryptographically secure randomness
-        // These represent the polynomial structure essential for FHE security
-        for i in 1..POLYNOMIAL_DEGREE * 2 {
-            // Each coefficient gets independent Gaussian noise
-            let coeff_noise: f64 = gaussian.sample(&mut rng);
-            let coeff_magnitude = (coeff_noise.abs() as u64) % CIPHERTEXT_MODULUS;
-            ciphertext_data[i] = coeff_magnitude;
-        }
+        let mut ciphertext_data: Vec<u64> = plaintext
+            .coefficients
+            .iter()
+            .zip(&mask)
+            .map(|(&value, &mask)| ((value + mask) % PLAINTEXT_MODULUS * scaling_factor).wrapping_add(noise.sample(rng) as u64) & (CIPHERTEXT_MODULUS - 1))
+            .collect();
+        ciphertext_data.extend(multiply_polynomials(&public_polynomial(), &u));
         
         Ok(Cipher {
             ciphertext_data,
             _phantom: std::marker::PhantomData,
         })
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tally_capacity_stops_below_plaintext_modulus() {
+        assert!(check_tally_capacity(0).is_ok());
+        assert!(check_tally_capacity(65536).is_ok());
+        assert!(matches!(
+            check_tally_capacity(65537),
+            Err(FheClientError::TallyOverflow { ballots: 65537, max: 65536 })
+        ));
+    }
+
+    #[test]
+    fn test_ballot_ciphertexts_are_bound_to_the_encryption_key() -> Result<(), FheClientError> {
+        let client = FheClient::new();
+        let other_key = FheClient::new().get_public_key().fingerprint();
+        let ciphertext = client.encrypt_vote_vector(crate::types::VoteOption::Option1)?.remove(0);
+
+        let decoded = Cipher::<Signed>::deserialize_bound(&ciphertext, &client.get_public_key().fingerprint())?;
+        assert_eq!(decoded.serialize(), ciphertext[BOUND_HEADER_LEN..]);
+        assert!(matches!(Cipher::<Signed>::deserialize_bound(&ciphertext, &other_key), Err(FheClientError::KeyMismatch)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_packed_ballots_tally_every_candidate_at_once() -> Result<(), FheClientError> {
+        use crate::types::VoteOption;
+        let client = FheClient::new();
+        let fingerprint = client.get_public_key().fingerprint();
+        let mut tally = Cipher::<Signed>::deserialize(&[0; POLYNOMIAL_DEGREE * 2 * 8])?;
+        for choice in [VoteOption::Option3, VoteOption::Option1, VoteOption::Option3, VoteOption::Option2, VoteOption::Option3] {
+            tally = tally.add(&Cipher::<Signed>::deserialize_bound(&client.encrypt_packed_vote(choice)?, &fingerprint)?);
+        }
+        assert_eq!(client.decrypt(&tally)?.coefficients()[..3], [1, 1, 3]);
+        
+        let signed = [-MAX_PLAINTEXT_MAGNITUDE, -1, 0, 7, MAX_PLAINTEXT_MAGNITUDE];
+        let ciphertext = Cipher::<Signed>::deserialize_bound(&client.encrypt_plaintext(&encode_slice(&signed)?)?, &fingerprint)?;
+        assert_eq!(decode(&client.decrypt(&ciphertext)?)[..5], signed);
+        assert!(matches!(encode_slice(&[1; POLYNOMIAL_DEGREE + 1]), Err(FheClientError::PackingOverflow { values: 33, max: 32 })));
+        assert!(matches!(encode_slice(&[-MAX_PLAINTEXT_MAGNITUDE - 1]), Err(FheClientError::PlaintextOutOfRange { .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn test_discrete_gaussian_noise_matches_its_table() {
+        use crate::noise_distribution::sample_discrete_gaussian;
+        use rand::SeedableRng;
+        
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let samples: Vec<i64> = (0..200_000).map(|_| sample_discrete_gaussian(&mut rng)).collect();
+        let mean = samples.iter().sum::<i64>() as f64 / samples.len() as f64;
+        let variance = samples.iter().map(|&x| (x * x) as f64).sum::<f64>() / samples.len() as f64 - mean * mean;
+        assert!(mean.abs() < 0.05, "mean {}", mean);
+        assert!((variance.sqrt() - 3.19).abs() < 0.05, "standard deviation {}", variance.sqrt());
+        assert!(samples.iter().all(|x| x.unsigned_abs() <= NOISE_CDT.len() as u64));
+        
+        // Table edges: the lowest word gives 0, the highest the tail of either sign
+        let sample = |word| sample_discrete_gaussian(&mut rand::rngs::mock::StepRng::new(word, 0));
+        let tail = NOISE_CDT.len() as i64;
+        assert_eq!([0, 1, u64::MAX - 1, u64::MAX].map(sample), [0, 0, tail, -tail]);
+    }
+    
+    #[test]
+    fn test_centered_binomial_noise_option() -> Result<(), FheClientError> {
+        use crate::noise_distribution::sample_centered_binomial;
+        use rand::SeedableRng;
+        
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        let samples: Vec<i64> = (0..200_000).map(|_| sample_centered_binomial(&mut rng, 20)).collect();
+        let variance = samples.iter().map(|&x| (x * x) as f64).sum::<f64>() / samples.len() as f64;
+        assert!((variance - 10.0).abs() < 0.2, "variance {}", variance);
+        assert!(samples.iter().all(|x| x.unsigned_abs() <= 20));
+        // Only the low eta bits of each half count
+        let sample = |word, eta| sample_centered_binomial(&mut rand::rngs::mock::StepRng::new(word, 0), eta);
+        assert_eq!((sample(u64::from(u32::MAX), 3), sample(u64::MAX << 32, 32), sample(u64::MAX, 32)), (3, -32, 0));
+        
+        let binomial = |eta| FheParameters { noise: NoiseDistribution::CenteredBinomial { eta }, ..FheParameters::default() };
+        let client = FheClient::new().with_parameters(binomial(MAX_BINOMIAL_ETA))?;
+        let ciphertext = client.encrypt_values(&[7])?.remove(0);
+        let decoded = Cipher::<Signed>::deserialize_bound(&ciphertext, &client.get_public_key().fingerprint())?;
+        assert_eq!(client.decrypt(&decoded)?.coefficients()[0], 7);
+        for eta in [0, MAX_BINOMIAL_ETA + 1] {
+            assert!(matches!(FheClient::new().with_parameters(binomial(eta)), Err(FheClientError::InvalidParameters { .. })));
+        }
+        Ok(())
+    }
+    
+    #[test]
+    fn test_ternary_secret_has_its_hamming_weight() {
+        use rand::SeedableRng;
+        
+        let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+        for hamming_weight in [1, DEFAULT_SECRET_HAMMING_WEIGHT, POLYNOMIAL_DEGREE as u32] {
+            let secret = sample_ternary_secret(&mut rng, hamming_weight);
+            assert_eq!(secret.iter().filter(|&&s| s != 0).count(), hamming_weight as usize);
+            assert!(secret.iter().all(|&s| [0, 1, CIPHERTEXT_MODULUS - 1].contains(&s)));
+        }
+        // Both signs occur
+        let full = sample_ternary_secret(&mut rng, POLYNOMIAL_DEGREE as u32);
+        assert!(full.contains(&1) && full.contains(&(CIPHERTEXT_MODULUS - 1)));
+        
+        for secret_hamming_weight in [0, POLYNOMIAL_DEGREE as u32 + 1] {
+            let parameters = FheParameters { secret_hamming_weight, ..FheParameters::default() };
+            assert!(matches!(FheClient::generate(parameters), Err(FheClientError::InvalidParameters { .. })));
+        }
+    }
+    
+    // The host decodes and sums independently of the guest code that wrote the vectors
+    #[test]
+    fn test_known_answer_vectors() -> Result<(), Box<dyn std::error::Error>> {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../test_vectors/fhe_kat.json");
+        let vectors: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        let hex_field = |value: &serde_json::Value| hex::decode(value.as_str().unwrap_or_default());
+        assert_eq!(parameter_digest().to_vec(), hex_field(&vectors["parameters"]["parameter_digest"])?);
+        
+        for case in vectors["cases"].as_array().ok_or("no cases")? {
+            let key_data = hex_field(&case["public_key"])?.chunks(8).map(|c| u64::from_le_bytes(c.try_into().unwrap())).collect();
+            let fingerprint = PublicKey { key_data }.fingerprint();
+            assert_eq!(fingerprint.to_vec(), hex_field(&case["key_fingerprint"])?);
+            let secret_data = hex_field(&case["private_key"])?.chunks(8).map(|c| u64::from_le_bytes(c.try_into().unwrap())).collect();
+            let private_key = PrivateKey { secret_data };
+            let decrypt = |cipher: &Cipher<Signed>| decrypt_plaintext(cipher, &private_key).coefficients()[0];
+            
+            let mut sums = vec![vec![0u64; POLYNOMIAL_DEGREE * 2]; 3];
+            for ballot in case["ballots"].as_array().ok_or("no ballots")? {
+                let choice = ballot["choice"].as_u64().ok_or("no choice")? as usize;
+                for (candidate, ciphertext) in ballot["ciphertexts"].as_array().ok_or("no ciphertexts")?.iter().enumerate() {
+                    let cipher = Cipher::<Signed>::deserialize_bound(&hex_field(ciphertext)?, &fingerprint)?;
+                    assert_eq!(decrypt(&cipher), (candidate + 1 == choice) as u64);
+                    for (sum, c) in sums[candidate].iter_mut().zip(&cipher.ciphertext_data) {
+                        *sum = (*sum + c) % CIPHERTEXT_MODULUS;
+                    }
+                }
+            }
+            
+            let tally = case["tally"].as_array().ok_or("no tally")?;
+            let counts = case["counts"].as_array().ok_or("no counts")?;
+            for ((sum, expected), count) in sums.iter().zip(tally).zip(counts) {
+                let expected = Cipher::<Signed>::deserialize(&hex_field(expected)?)?;
+                assert_eq!(sum, &expected.ciphertext_data);
+                assert_eq!(Some(decrypt(&expected)), count.as_u64());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed_ballot_ciphertexts_are_rejected() -> Result<(), FheClientError> {
+        assert!(matches!(
+            Cipher::<Signed>::deserialize(&[0xff; POLYNOMIAL_DEGREE * 2 * 8]),
+            Err(FheClientError::NonCanonicalCoefficient { index: 0 })
+        ));
+        
+        let client = FheClient::new();
+        let fingerprint = client.get_public_key().fingerprint();
+        let cipher = Cipher::<Signed>::deserialize_bound(&client.encrypt_vote_vector(crate::types::VoteOption::Option1)?[0], &fingerprint)?;
+        let checked = cipher.serialize_bound_with_checksum(&fingerprint);
+        assert_eq!(Cipher::<Signed>::deserialize_bound(&checked, &fingerprint)?.ciphertext_data, cipher.ciphertext_data);
+        
+        let mut corrupt = checked.clone();
+        corrupt[BOUND_HEADER_LEN + 5] ^= 1;
+        assert!(matches!(Cipher::<Signed>::deserialize_bound(&corrupt, &fingerprint), Err(FheClientError::ChecksumMismatch)));
+        let mut foreign = cipher.serialize_bound(&fingerprint);
+        foreign[KEY_FINGERPRINT_LEN] ^= 1;
+        assert!(matches!(Cipher::<Signed>::deserialize_bound(&foreign, &fingerprint), Err(FheClientError::ParameterMismatch)));
+        let mut oversized = cipher.serialize_bound(&fingerprint);
+        oversized[BOUND_HEADER_LEN + 8 * 2 + 7] |= 0x80;
+        assert!(matches!(
+            Cipher::<Signed>::deserialize_bound(&oversized, &fingerprint),
+            Err(FheClientError::NonCanonicalCoefficient { index: 2 })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,172 @@
+// GraphQL queries over the daemon's elections.
+// The REST endpoints in `daemon` return fixed documents: a dashboard showing
+// ten elections with their counts and recent rejections makes a request per
+// election and throws most of each response away. `POST /graphql` answers one
+// query for exactly the fields asked for:
+//
+//   elections / election(id)   every tally job, or one
+//     state, failureReason     queued, proving, succeeded, failed or cancelled
+//     electionId, chainId, closesAt, candidates, ballots
+//     result                   counts, total votes and journal digest, once proven
+//     receipt                  the bincode receipt, hex-encoded, once proven
+//     auditLog(offset, limit)  submission log entries with rejection reasons
+//
+// The schema is read-only: jobs are still submitted and cancelled over REST,
+// under the admin role. Each election's input is read from the state directory
+// at most once per query, and only when a field needs it. Nothing here is
+// proven by itself; a client that relies on a result verifies the receipt.
+
+use std::sync::OnceLock;
+
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Request, Schema, SimpleObject};
+
+use crate::daemon::{JobStatus, JobStore};
+use crate::types::VoteTallyInput;
+
+/// Deepest query accepted; the schema itself is four levels deep.
+const MAX_QUERY_DEPTH: usize = 8;
+
+pub type ElectionSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+pub fn schema(store: JobStore) -> ElectionSchema {
+    Schema::build(Query, EmptyMutation, EmptySubscription).data(store).limit_depth(MAX_QUERY_DEPTH).finish()
+}
+
+/// Run a JSON GraphQL request (`{"query": ..., "variables": ...}`) against `store`.
+/// Errors in the query itself are reported in the response, as GraphQL does;
+/// only a body that is not a GraphQL request is an error here.
+pub fn execute(store: JobStore, body: &[u8]) -> Result<serde_json::Value, serde_json::Error> {
+    let request: Request = serde_json::from_slice(body)?;
+    serde_json::to_value(pollster::block_on(schema(store).execute(request)))
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// Every election the daemon has been asked to tally, in submission order.
+    async fn elections(&self, ctx: &Context<'_>) -> Vec<Election> {
+        ctx.data_unchecked::<JobStore>().jobs().into_iter().map(|(id, status)| Election::new(id, status)).collect()
+    }
+
+    async fn election(&self, ctx: &Context<'_>, id: u64) -> Option<Election> {
+        ctx.data_unchecked::<JobStore>().status(id).map(|status| Election::new(id, status))
+    }
+}
+
+/// One tally job and the election it tallies.
+pub struct Election {
+    id: u64,
+    status: JobStatus,
+    input: OnceLock<Option<VoteTallyInput>>,
+}
+
+impl Election {
+    fn new(id: u64, status: JobStatus) -> Self {
+        Election { id, status, input: OnceLock::new() }
+    }
+
+    fn input(&self, ctx: &Context<'_>) -> async_graphql::Result<&VoteTallyInput> {
+        self.input
+            .get_or_init(|| ctx.data_unchecked::<JobStore>().input(self.id))
+            .as_ref()
+            .ok_or_else(|| format!("input of job {} is unavailable", self.id).into())
+    }
+}
+
+/// A proven tally's published numbers.
+#[derive(SimpleObject)]
+pub struct TallyResult {
+    counts: Vec<u32>,
+    total_votes: u32,
+    journal_digest: String,
+}
+
+/// One submission in the election's audit log.
+#[derive(SimpleObject)]
+pub struct AuditEntry {
+    position: usize,
+    ballot_hash: String,
+    accepted: bool,
+    reason: String,
+}
+
+#[Object]
+impl Election {
+    /// Job id, as in `/jobs/{id}`.
+    async fn id(&self) -> u64 {
+        self.id
+    }
+
+    async fn state(&self) -> &'static str {
+        match self.status {
+            JobStatus::Queued => "queued",
+            JobStatus::Proving => "proving",
+            JobStatus::Succeeded { .. } => "succeeded",
+            JobStatus::Failed { .. } => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    async fn failure_reason(&self) -> Option<&str> {
+        match &self.status {
+            JobStatus::Failed { reason } => Some(reason),
+            _ => None,
+        }
+    }
+
+    async fn election_id(&self, ctx: &Context<'_>) -> async_graphql::Result<String> {
+        Ok(self.input(ctx)?.election.election_id.to_string())
+    }
+
+    async fn chain_id(&self, ctx: &Context<'_>) -> async_graphql::Result<u64> {
+        Ok(self.input(ctx)?.election.chain_id)
+    }
+
+    /// Unix seconds the ballot box closed.
+    async fn closes_at(&self, ctx: &Context<'_>) -> async_graphql::Result<u64> {
+        Ok(self.input(ctx)?.election.closes_at)
+    }
+
+    async fn candidates(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<String>> {
+        Ok(self.input(ctx)?.candidates.iter().map(|candidate| candidate.label.clone()).collect())
+    }
+
+    /// Accepted ballots submitted for tallying.
+    async fn ballots(&self, ctx: &Context<'_>) -> async_graphql::Result<usize> {
+        Ok(self.input(ctx)?.encrypted_votes.len())
+    }
+
+    async fn result(&self) -> Option<TallyResult> {
+        match &self.status {
+            JobStatus::Succeeded { option_counts, total_votes, journal_digest, .. } => Some(TallyResult {
+                counts: option_counts.clone(),
+                total_votes: *total_votes,
+                journal_digest: hex::encode(journal_digest),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Hex of the bincode receipt, as served by `/jobs/{id}/receipt`.
+    async fn receipt(&self, ctx: &Context<'_>) -> Option<String> {
+        ctx.data_unchecked::<JobStore>().receipt(self.id).map(hex::encode)
+    }
+
+    /// Audit log entries from `offset` (default 0), at most `limit` of them (default all).
+    async fn audit_log(&self, ctx: &Context<'_>, offset: Option<usize>, limit: Option<usize>) -> async_graphql::Result<Vec<AuditEntry>> {
+        let records = &self.input(ctx)?.audit_log;
+        Ok(records
+            .iter()
+            .enumerate()
+            .skip(offset.unwrap_or(0))
+            .take(limit.unwrap_or(records.len()))
+            .map(|(position, record)| AuditEntry {
+                position,
+                ballot_hash: hex::encode(record.ballot_hash),
+                accepted: record.accepted,
+                reason: record.reason.clone(),
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,131 @@
+// Guest images loaded at runtime.
+// The host embeds the guests it was built with (`methods::FHE_VOTING_ELF` and
+// friends), so trying a modified guest means rebuilding the host. A
+// `GuestImage` is either one of those or a program binary read from a file:
+// the `.bin` the guest build writes for each guest, user ELF and kernel
+// combined. A loaded image's id is computed from its contents, so receipts are
+// always checked against the program that actually ran; pinning an expected
+// id refuses any other program.
+//
+// `from_lookup` reads FHE_VOTING_GUEST_ELF (the path) and, optionally,
+// FHE_VOTING_GUEST_IMAGE_ID (the hex id to pin), falling back to the embedded
+// image when no path is set.
+
+use std::borrow::Cow;
+use std::path::Path;
+
+use risc0_zkvm::compute_image_id;
+use risc0_zkvm::sha::Digest;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum GuestImageError {
+    #[error("Guest image I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Not a guest program binary: {reason}")]
+    InvalidProgram { reason: String },
+    #[error("Image id {value} is not 32 hex-encoded bytes")]
+    InvalidImageId { value: String },
+    #[error("Guest image id is {actual}, expected {expected}")]
+    ImageIdMismatch { expected: Digest, actual: Digest },
+}
+
+/// A guest program and its image id.
+#[derive(Debug, Clone)]
+pub struct GuestImage {
+    elf: Cow<'static, [u8]>,
+    image_id: Digest,
+    embedded: bool,
+}
+
+impl GuestImage {
+    /// A guest compiled into the host.
+    pub fn embedded(elf: &'static [u8], image_id: [u32; 8]) -> Self {
+        GuestImage { elf: Cow::Borrowed(elf), image_id: image_id.into(), embedded: true }
+    }
+
+    /// Read a program binary and compute its image id.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, GuestImageError> {
+        let elf = std::fs::read(path)?;
+        let image_id = compute_image_id(&elf).map_err(|e| GuestImageError::InvalidProgram { reason: e.to_string() })?;
+        Ok(GuestImage { elf: Cow::Owned(elf), image_id, embedded: false })
+    }
+
+    /// The image at FHE_VOTING_GUEST_ELF (pinned to FHE_VOTING_GUEST_IMAGE_ID
+    /// if set), or `embedded`.
+    pub fn from_env(embedded: GuestImage) -> Result<Self, GuestImageError> {
+        Self::from_lookup(embedded, |name| std::env::var(name).ok())
+    }
+
+    pub fn from_lookup(embedded: GuestImage, lookup: impl Fn(&str) -> Option<String>) -> Result<Self, GuestImageError> {
+        let image = match lookup("FHE_VOTING_GUEST_ELF") {
+            Some(path) => GuestImage::load(path)?,
+            None => embedded,
+        };
+        match lookup("FHE_VOTING_GUEST_IMAGE_ID") {
+            Some(value) => {
+                let expected = hex::decode(value.trim())
+                    .ok()
+                    .and_then(|bytes| Digest::try_from(bytes).ok())
+                    .ok_or(GuestImageError::InvalidImageId { value })?;
+                image.pinned(expected)
+            }
+            None => Ok(image),
+        }
+    }
+
+    /// Refuse the image unless its id is `expected`.
+    pub fn pinned(self, expected: impl Into<Digest>) -> Result<Self, GuestImageError> {
+        let expected = expected.into();
+        if self.image_id != expected {
+            return Err(GuestImageError::ImageIdMismatch { expected, actual: self.image_id });
+        }
+        Ok(self)
+    }
+
+    pub fn elf(&self) -> &[u8] {
+        &self.elf
+    }
+
+    pub fn image_id(&self) -> [u32; 8] {
+        self.image_id.into()
+    }
+
+    /// Whether this is the guest the host was built with.
+    pub fn is_embedded(&self) -> bool {
+        self.embedded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    const EMBEDDED_ID: [u32; 8] = [3; 8];
+
+    fn from_vars(vars: &[(&str, &str)]) -> Result<GuestImage, GuestImageError> {
+        let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        GuestImage::from_lookup(GuestImage::embedded(b"embedded", EMBEDDED_ID), |name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn test_images_load_from_files_and_pin_their_ids() -> Result<(), GuestImageError> {
+        let image = from_vars(&[])?;
+        assert!(image.is_embedded());
+        assert_eq!((image.elf(), image.image_id()), (&b"embedded"[..], EMBEDDED_ID));
+
+        let pinned = hex::encode(Digest::from(EMBEDDED_ID));
+        assert!(from_vars(&[("FHE_VOTING_GUEST_IMAGE_ID", &pinned)]).is_ok());
+        let other = hex::encode([7u8; 32]);
+        assert!(matches!(from_vars(&[("FHE_VOTING_GUEST_IMAGE_ID", &other)]), Err(GuestImageError::ImageIdMismatch { .. })));
+        assert!(matches!(from_vars(&[("FHE_VOTING_GUEST_IMAGE_ID", "abcd")]), Err(GuestImageError::InvalidImageId { .. })));
+
+        let path = std::env::temp_dir().join(format!("fhe-voting-guest-{}.bin", std::process::id()));
+        std::fs::write(&path, b"not a program")?;
+        let loaded = from_vars(&[("FHE_VOTING_GUEST_ELF", path.to_str().unwrap_or_default())]);
+        std::fs::remove_file(&path)?;
+        assert!(matches!(loaded, Err(GuestImageError::InvalidProgram { .. })));
+        Ok(())
+    }
+}
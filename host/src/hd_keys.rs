@@ -0,0 +1,129 @@
+// HD key derivation for voter identities.
+// A voter keeps one BIP39 mnemonic (24 words) instead of a secret per
+// election; everything they sign or prove with derives from its BIP32 seed:
+//
+//   m/44'/60'/0'/0/<account>  Ethereum account key for submission signatures;
+//                             the standard wallet path, so a hardware wallet
+//                             restored from the same words signs as the same
+//                             account
+//   m/7718'/0'                identity root; the identity for one election is
+//                             y = wide_hash(tag || root key || election id)
+//   m/7718'/1'                encryption secret, from which derandomized
+//                             ballots derive their nonce and noise
+//                             (`honest_encryption`)
+//
+// The identity (and so the roster commitment and nullifier) is deterministic
+// per election: a voter who loses their device re-derives it from the words
+// and re-votes under the same nullifier, superseding their earlier ballot. The
+// identities of two elections are unrelated scalars, so commitments and
+// nullifiers cannot be linked across elections. Set
+// FHE_VOTING_MNEMONIC_PASSPHRASE for a BIP39 passphrase.
+
+use bip32::{DerivationPath, Language, Mnemonic, XPrv};
+use curve25519_dalek::scalar::Scalar;
+use k256::ecdsa::SigningKey;
+use thiserror::Error;
+
+use crate::membership::Identity;
+use crate::onchain::address_from_public_key;
+use crate::registrar::wide_hash;
+use crate::types::Digest32;
+
+// Domain separation tag for per-election identities
+const IDENTITY_DERIVATION_TAG: u8 = 0x0a;
+const IDENTITY_ROOT_PATH: &str = "m/7718'/0'";
+const ENCRYPTION_SECRET_PATH: &str = "m/7718'/1'";
+
+#[derive(Error, Debug)]
+pub enum HdError {
+    #[error("Invalid mnemonic: {reason}")]
+    InvalidMnemonic { reason: String },
+    #[error("Key derivation failed: {reason}")]
+    Derivation { reason: String },
+}
+
+/// A voter's BIP32 seed, from their mnemonic.
+pub struct VoterSeed {
+    seed: [u8; 64],
+}
+
+impl VoterSeed {
+    /// A fresh 24-word mnemonic and its seed.
+    pub fn generate(passphrase: &str) -> (String, Self) {
+        let mnemonic = Mnemonic::random(rand::rngs::OsRng, Language::English);
+        let seed = VoterSeed { seed: *mnemonic.to_seed(passphrase).as_bytes() };
+        (mnemonic.phrase().to_string(), seed)
+    }
+
+    pub fn from_mnemonic(phrase: &str, passphrase: &str) -> Result<Self, HdError> {
+        let words = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mnemonic = Mnemonic::new(words, Language::English).map_err(|e| HdError::InvalidMnemonic { reason: e.to_string() })?;
+        Ok(VoterSeed { seed: *mnemonic.to_seed(passphrase).as_bytes() })
+    }
+
+    /// The seed with the `FHE_VOTING_MNEMONIC_PASSPHRASE` passphrase, if set.
+    pub fn from_mnemonic_env(phrase: &str) -> Result<Self, HdError> {
+        Self::from_mnemonic(phrase, &std::env::var("FHE_VOTING_MNEMONIC_PASSPHRASE").unwrap_or_default())
+    }
+
+    /// Identity for one election; the same words always give the same identity.
+    pub fn identity(&self, election_id: &Digest32) -> Result<Identity, HdError> {
+        let root = self.derive(IDENTITY_ROOT_PATH)?;
+        let mut buf = vec![IDENTITY_DERIVATION_TAG];
+        buf.extend_from_slice(&root.to_bytes());
+        buf.extend_from_slice(election_id.as_bytes());
+        let secret = Scalar::from_bytes_mod_order_wide(&wide_hash(&buf));
+        Identity::from_bytes(secret.to_bytes()).ok_or(HdError::Derivation { reason: "non-canonical identity".to_string() })
+    }
+
+    /// Secret behind the voter's derandomized ballot encryptions.
+    pub fn encryption_secret(&self) -> Result<[u8; 32], HdError> {
+        Ok(self.derive(ENCRYPTION_SECRET_PATH)?.to_bytes())
+    }
+
+    /// Account key at m/44'/60'/0'/0/<account>.
+    pub fn signing_key(&self, account: u32) -> Result<SigningKey, HdError> {
+        Ok(self.derive(&format!("m/44'/60'/0'/0/{}", account))?.private_key().clone())
+    }
+
+    /// Ethereum address of the account key.
+    pub fn address(&self, account: u32) -> Result<[u8; 20], HdError> {
+        let point = self.signing_key(account)?.verifying_key().to_encoded_point(false);
+        let public_key: &[u8; 64] = point.as_bytes()[1..].try_into().map_err(|_| HdError::Derivation { reason: "bad public key".to_string() })?;
+        Ok(address_from_public_key(public_key))
+    }
+
+    fn derive(&self, path: &str) -> Result<XPrv, HdError> {
+        let derivation = |e: bip32::Error| HdError::Derivation { reason: e.to_string() };
+        XPrv::derive_from_path(self.seed, &path.parse::<DerivationPath>().map_err(derivation)?).map_err(derivation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // BIP39 all-zero-entropy vector ("abandon" x23 + "art")
+    const TEST_MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+                                 abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art";
+
+    #[test]
+    fn test_mnemonic_derives_accounts_and_per_election_identities() -> Result<(), HdError> {
+        let seed = VoterSeed::from_mnemonic(TEST_MNEMONIC, "")?;
+        assert_eq!(hex::encode(seed.address(0)?), "f278cf59f82edcf871d630f28ecc8056f25c1cdb");
+
+        // Recovery: the same words give the same identity, so the same nullifier
+        let election = Digest32([1u8; 32]);
+        let recovered = VoterSeed::from_mnemonic(&format!("  {}\n", TEST_MNEMONIC), "")?;
+        assert_eq!(seed.identity(&election)?.commitment(), recovered.identity(&election)?.commitment());
+        // Other elections and passphrases give unrelated identities
+        assert_ne!(seed.identity(&election)?.commitment(), seed.identity(&Digest32([2u8; 32]))?.commitment());
+        assert_ne!(seed.identity(&election)?.commitment(), VoterSeed::from_mnemonic(TEST_MNEMONIC, "pass")?.identity(&election)?.commitment());
+
+        let (phrase, generated) = VoterSeed::generate("");
+        assert_eq!(phrase.split(' ').count(), 24);
+        assert_eq!(VoterSeed::from_mnemonic(&phrase, "")?.address(0)?, generated.address(0)?);
+        assert!(matches!(VoterSeed::from_mnemonic("abandon about", ""), Err(HdError::InvalidMnemonic { .. })));
+        Ok(())
+    }
+}
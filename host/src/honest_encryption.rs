@@ -0,0 +1,154 @@
+// Derandomized, provably honest ballot encryption.
+// A ballot's nonce and the noise in each of its ciphertexts are free choices
+// of the client, so a compromised client could pick them to leak the vote (or
+// anything else) through the ballot itself, a subliminal channel no check on
+// the ciphertexts would notice. In this mode the client has no choices left:
+//
+//   ballot secret = H(tag || voter secret || election id || sequence)
+//   nonce         = H(nonce tag || ballot secret)
+//   noise         = ChaCha20 seeded with H(noise tag || ballot secret), drawn
+//                   in candidate and coefficient order
+//
+// The voter secret comes from the voter's mnemonic (`VoterSeed::
+// encryption_secret`), so the same words, election and sequence always give
+// the same ballot. An `EncryptionOpening` (the ballot secret and the choice)
+// lets anyone recompute the ballot and compare it byte for byte, without
+// learning the voter secret or anything about the voter's other ballots. The
+// opening reveals the choice: an opened ballot is spoiled (cast-or-audit), or
+// the opening goes to an auditor only after the tally.
+//
+// Only the nonce and ciphertexts are derived; the authorization (membership
+// proof or signature) keeps its own randomness. The derivation is host-only:
+// the guest tallies these ballots like any other.
+
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::fhe_client::{FheClient, FheClientError};
+use crate::types::{Digest32, EncryptedVote, VoteOption};
+
+// Domain separation tags for the ballot derivation
+const BALLOT_SECRET_TAG: u8 = 0x12;
+const NONCE_TAG: u8 = 0x13;
+const NOISE_SEED_TAG: u8 = 0x14;
+
+#[derive(Error, Debug)]
+pub enum HonestEncryptionError {
+    #[error("Encryption failed: {0}")]
+    Fhe(#[from] FheClientError),
+    #[error("Ballot nonce was not derived from the opened ballot secret")]
+    NonceMismatch,
+    #[error("Ciphertext {index} differs from the honest encryption of the opened choice")]
+    CiphertextMismatch { index: usize },
+}
+
+/// What a voter reveals to show one ballot was encrypted honestly.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptionOpening {
+    #[serde(with = "hex::serde")]
+    pub ballot_secret: [u8; 32],
+    pub choice: VoteOption,
+}
+
+/// A derandomized ballot's nonce and ciphertexts, with the opening that proves them.
+#[derive(Debug, Clone)]
+pub struct HonestBallot {
+    pub nonce: [u8; 32],
+    pub encrypted_vote_vector: Vec<Vec<u8>>,
+    pub opening: EncryptionOpening,
+}
+
+/// The per-ballot secret everything else in the ballot derives from.
+pub fn ballot_secret(voter_secret: &[u8; 32], election_id: &Digest32, sequence: u64) -> [u8; 32] {
+    let mut buf = vec![BALLOT_SECRET_TAG];
+    buf.extend_from_slice(voter_secret);
+    buf.extend_from_slice(election_id.as_bytes());
+    buf.extend_from_slice(&sequence.to_le_bytes());
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// The nonce a derandomized ballot carries.
+pub fn derived_nonce(ballot_secret: &[u8; 32]) -> [u8; 32] {
+    tagged_hash(NONCE_TAG, ballot_secret)
+}
+
+/// Encrypt `choice` with the nonce and noise derived from `ballot_secret`.
+pub fn encrypt_opened(client: &FheClient, opening: &EncryptionOpening) -> Result<([u8; 32], Vec<Vec<u8>>), FheClientError> {
+    let mut noise = ChaCha20Rng::from_seed(tagged_hash(NOISE_SEED_TAG, &opening.ballot_secret));
+    let encrypted_vote_vector = client.encrypt_vote_vector_with_rng(opening.choice, &mut noise)?;
+    Ok((derived_nonce(&opening.ballot_secret), encrypted_vote_vector))
+}
+
+/// Encrypt a voter's ballot for `sequence` in `election_id` with no free randomness.
+pub fn encrypt_honest(client: &FheClient, voter_secret: &[u8; 32], election_id: &Digest32, sequence: u64, choice: VoteOption) -> Result<HonestBallot, FheClientError> {
+    let opening = EncryptionOpening { ballot_secret: ballot_secret(voter_secret, election_id, sequence), choice };
+    let (nonce, encrypted_vote_vector) = encrypt_opened(client, &opening)?;
+    Ok(HonestBallot { nonce, encrypted_vote_vector, opening })
+}
+
+/// Check `ballot` is exactly the honest encryption `opening` describes under
+/// the client's election key.
+pub fn verify_opening(client: &FheClient, ballot: &EncryptedVote, opening: &EncryptionOpening) -> Result<(), HonestEncryptionError> {
+    let (nonce, expected) = encrypt_opened(client, opening)?;
+    if ballot.nonce != nonce {
+        return Err(HonestEncryptionError::NonceMismatch);
+    }
+    if ballot.encrypted_vote_vector.len() != expected.len() {
+        return Err(HonestEncryptionError::CiphertextMismatch { index: expected.len().min(ballot.encrypted_vote_vector.len()) });
+    }
+    match ballot.encrypted_vote_vector.iter().zip(&expected).position(|(actual, expected)| actual != expected) {
+        Some(index) => Err(HonestEncryptionError::CiphertextMismatch { index }),
+        None => Ok(()),
+    }
+}
+
+fn tagged_hash(tag: u8, secret: &[u8; 32]) -> [u8; 32] {
+    let mut buf = vec![tag];
+    buf.extend_from_slice(secret);
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BallotAuthorization, MembershipProof};
+
+    fn ballot(honest: &HonestBallot) -> EncryptedVote {
+        EncryptedVote {
+            voter_address: String::new(),
+            election_id: Digest32::ZERO,
+            nonce: honest.nonce,
+            encrypted_vote_vector: honest.encrypted_vote_vector.clone(),
+            signature: String::new(),
+            authorization: BallotAuthorization::Membership(MembershipProof { nullifier: [0u8; 32], challenges: Vec::new(), responses: Vec::new() }),
+            sequence: 0,
+            actual_choice: VoteOption::Option1,
+        }
+    }
+
+    #[test]
+    fn test_derandomized_ballots_open_to_their_choice() -> Result<(), HonestEncryptionError> {
+        let client = FheClient::new();
+        let honest = encrypt_honest(&client, &[7u8; 32], &Digest32::ZERO, 0, VoteOption::Option2)?;
+        // Same secret, election and sequence: same ballot
+        let again = encrypt_honest(&client, &[7u8; 32], &Digest32::ZERO, 0, VoteOption::Option2)?;
+        assert_eq!((again.nonce, &again.encrypted_vote_vector), (honest.nonce, &honest.encrypted_vote_vector));
+        assert_ne!(encrypt_honest(&client, &[7u8; 32], &Digest32::ZERO, 1, VoteOption::Option2)?.nonce, honest.nonce);
+
+        let cast = ballot(&honest);
+        verify_opening(&client, &cast, &honest.opening)?;
+        let wrong_choice = EncryptionOpening { choice: VoteOption::Option1, ..honest.opening.clone() };
+        assert!(matches!(verify_opening(&client, &cast, &wrong_choice), Err(HonestEncryptionError::CiphertextMismatch { index: 0 })));
+
+        // Fresh noise in place of the derived noise is caught
+        let mut grinded = cast.clone();
+        grinded.encrypted_vote_vector[2] = client.encrypt_vote_vector(VoteOption::Option2)?.swap_remove(2);
+        assert!(matches!(verify_opening(&client, &grinded, &honest.opening), Err(HonestEncryptionError::CiphertextMismatch { index: 2 })));
+        grinded.nonce[0] ^= 1;
+        assert!(matches!(verify_opening(&client, &grinded, &honest.opening), Err(HonestEncryptionError::NonceMismatch)));
+        Ok(())
+    }
+}
@@ -0,0 +1,312 @@
+// Result finalization hooks.
+// Once a tally receipt verifies and the host has checked the proven result
+// against its inputs, every configured hook is handed a `FinalizedResult`, so
+// a governance system can enact the winning option as soon as the proof checks
+// out instead of polling for it. Hooks never see unverified results.
+//
+// Built-in hooks:
+// - `FileDropHook` writes the result as JSON into a directory a watcher picks
+//   up from; the file is renamed into place, so it never appears half-written.
+// - `WebhookHook` POSTs the same JSON to a URL (`webhooks` feature).
+// - `ContractCallHook` sends `enactResult(uint8,bytes32)` with the winning
+//   option and journal digest to a contract (`ethereum` feature).
+//
+// Hooks run in the order they were added. A failing hook does not stop the
+// ones after it; the failures are returned to the caller to report.
+
+use std::fs;
+use std::path::PathBuf;
+
+use risc0_zkvm::sha::{Digest, Impl, Sha256};
+use serde::{Serialize, Deserialize};
+use sha3::{Digest as _, Keccak256};
+use thiserror::Error;
+
+use crate::executor::ProvingStats;
+use crate::onchain::OnchainError;
+use crate::types::VoteTallyOutput;
+
+/// Signature of the call `ContractCallHook` makes.
+pub const ENACT_SIGNATURE: &str = "enactResult(uint8,bytes32)";
+
+#[derive(Error, Debug)]
+pub enum HookError {
+    #[error("Hook I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Hook payload encoding failed: {reason}")]
+    Encoding { reason: String },
+    #[error("Webhook request failed: {reason}")]
+    Http { reason: String },
+    #[error("Webhook answered with status {status}")]
+    Rejected { status: u16 },
+    #[error("No winning option to enact: no votes, or a declared tie")]
+    Tied,
+    #[error("Contract call failed: {0}")]
+    Onchain(#[from] OnchainError),
+}
+
+/// A verified election result, as handed to hooks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FinalizedResult {
+    #[serde(with = "hex::serde")]
+    pub image_id: [u8; 32],
+    #[serde(with = "hex::serde")]
+    pub journal_digest: [u8; 32],
+    pub option_counts: Vec<u32>,
+    pub total_votes: u32,
+    /// 1-based winning option the guest committed; `None` for no votes or a declared tie.
+    pub winner: Option<u8>,
+    #[serde(with = "hex::serde")]
+    pub ballot_set_root: [u8; 32],
+    /// What proving the receipt cost, when the host that proved it reports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proving: Option<ProvingStats>,
+}
+
+impl FinalizedResult {
+    /// Describe a verified receipt's image ID, journal and decoded results.
+    pub fn new(image_id: impl Into<Digest>, journal: &[u8], output: &VoteTallyOutput) -> Self {
+        let image_id: Digest = image_id.into();
+        FinalizedResult {
+            image_id: image_id.into(),
+            journal_digest: (*Impl::hash_bytes(journal)).into(),
+            option_counts: vec![output.option1_count, output.option2_count, output.option3_count],
+            winner: output.outcome.winner(),
+            total_votes: output.total_votes,
+            ballot_set_root: output.ballot_set_root,
+            proving: None,
+        }
+    }
+
+    pub fn with_proving(mut self, stats: ProvingStats) -> Self {
+        self.proving = Some(stats);
+        self
+    }
+
+    pub fn to_json(&self) -> Result<Vec<u8>, HookError> {
+        serde_json::to_vec_pretty(self).map_err(|e| HookError::Encoding { reason: e.to_string() })
+    }
+}
+
+/// Something to do with a result once it is proven and verified.
+pub trait ResultHook: Send + Sync {
+    /// Short description for logs, e.g. the target URL or directory.
+    fn name(&self) -> String;
+    fn on_finalized(&self, result: &FinalizedResult) -> Result<(), HookError>;
+}
+
+/// The hooks to run after a successful verification.
+#[derive(Default)]
+pub struct FinalizationHooks {
+    hooks: Vec<Box<dyn ResultHook>>,
+}
+
+impl FinalizationHooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_hook(mut self, hook: impl ResultHook + 'static) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.hooks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Run every hook; returns the name and error of each one that failed.
+    pub fn run(&self, result: &FinalizedResult) -> Vec<(String, HookError)> {
+        self.hooks
+            .iter()
+            .filter_map(|hook| hook.on_finalized(result).err().map(|e| (hook.name(), e)))
+            .collect()
+    }
+}
+
+/// Writes `<journal digest>.json` into a directory.
+pub struct FileDropHook {
+    dir: PathBuf,
+}
+
+impl FileDropHook {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FileDropHook { dir: dir.into() }
+    }
+}
+
+impl ResultHook for FileDropHook {
+    fn name(&self) -> String {
+        format!("file drop {}", self.dir.display())
+    }
+
+    fn on_finalized(&self, result: &FinalizedResult) -> Result<(), HookError> {
+        fs::create_dir_all(&self.dir)?;
+        let name = hex::encode(result.journal_digest);
+        let partial = self.dir.join(format!(".{}.json.partial", name));
+        fs::write(&partial, result.to_json()?)?;
+        fs::rename(&partial, self.dir.join(format!("{}.json", name)))?;
+        Ok(())
+    }
+}
+
+/// Calldata for `enactResult(winner, journalDigest)`.
+pub fn enact_calldata(winner: u8, journal_digest: [u8; 32]) -> Vec<u8> {
+    let mut calldata = Keccak256::digest(ENACT_SIGNATURE.as_bytes())[..4].to_vec();
+    calldata.extend(crate::onchain::abi_word(winner as usize));
+    calldata.extend(journal_digest);
+    calldata
+}
+
+#[cfg(feature = "webhooks")]
+pub use webhook::WebhookHook;
+
+#[cfg(feature = "webhooks")]
+mod webhook {
+    use std::time::Duration;
+
+    use super::{FinalizedResult, HookError, ResultHook};
+
+    /// POSTs the result as JSON; any non-2xx answer is a failure.
+    pub struct WebhookHook {
+        http: reqwest::blocking::Client,
+        url: String,
+    }
+
+    impl WebhookHook {
+        pub fn new(url: &str) -> Result<Self, HookError> {
+            let http = reqwest::blocking::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .map_err(|e| HookError::Http { reason: e.to_string() })?;
+            Ok(WebhookHook { http, url: url.to_string() })
+        }
+    }
+
+    impl ResultHook for WebhookHook {
+        fn name(&self) -> String {
+            format!("webhook {}", self.url)
+        }
+
+        fn on_finalized(&self, result: &FinalizedResult) -> Result<(), HookError> {
+            let response = self
+                .http
+                .post(&self.url)
+                .header("Content-Type", "application/json")
+                .body(result.to_json()?)
+                .send()
+                .map_err(|e| HookError::Http { reason: e.to_string() })?;
+            if !response.status().is_success() {
+                return Err(HookError::Rejected { status: response.status().as_u16() });
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "ethereum")]
+pub use contract::ContractCallHook;
+
+#[cfg(feature = "ethereum")]
+mod contract {
+    use std::time::Duration;
+
+    use super::{enact_calldata, FinalizedResult, HookError, ResultHook};
+    use crate::onchain::EthereumClient;
+
+    /// Calls `enactResult` on a contract and waits for the transaction to be mined.
+    pub struct ContractCallHook {
+        client: EthereumClient,
+        contract: String,
+    }
+
+    impl ContractCallHook {
+        pub fn new(rpc_url: &str, from: &str, contract: &str) -> Result<Self, HookError> {
+            Ok(ContractCallHook { client: EthereumClient::new(rpc_url, from, contract)?, contract: contract.to_string() })
+        }
+    }
+
+    impl ResultHook for ContractCallHook {
+        fn name(&self) -> String {
+            format!("contract call {}", self.contract)
+        }
+
+        fn on_finalized(&self, result: &FinalizedResult) -> Result<(), HookError> {
+            let winner = result.winner.ok_or(HookError::Tied)?;
+            let tx_hash = self.client.send_transaction(&enact_calldata(winner, result.journal_digest))?;
+            self.client.wait_for_receipt(&tx_hash, Duration::from_secs(120))?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use crate::scheme::{TallyScheme, TieBreak};
+    use crate::types::Outcome;
+
+    // Records the winners it was handed
+    struct Recorder(Arc<Mutex<Vec<Option<u8>>>>);
+
+    impl ResultHook for Recorder {
+        fn name(&self) -> String {
+            "recorder".to_string()
+        }
+        fn on_finalized(&self, result: &FinalizedResult) -> Result<(), HookError> {
+            self.0.lock().unwrap().push(result.winner);
+            Ok(())
+        }
+    }
+
+    fn result(option_counts: [u32; 3]) -> FinalizedResult {
+        FinalizedResult {
+            image_id: [1u8; 32],
+            journal_digest: [2u8; 32],
+            option_counts: option_counts.to_vec(),
+            total_votes: option_counts.iter().sum(),
+            winner: Outcome::decide(&TallyScheme::Plurality, &option_counts, TieBreak::Declare).winner(),
+            ballot_set_root: [3u8; 32],
+            proving: None,
+        }
+    }
+
+    #[test]
+    fn test_hooks_run_in_order_and_report_failures() -> Result<(), HookError> {
+        assert_eq!(result([3, 2, 2]).winner, Some(1));
+        assert_eq!(result([1, 4, 4]).winner, None);
+        assert_eq!(result([0, 0, 0]).winner, None);
+        assert_eq!(Outcome::decide(&TallyScheme::Plurality, &[1, 4, 4], TieBreak::FirstListed).winner(), Some(2));
+
+        let dir = std::env::temp_dir().join(format!("fhe-voting-hooks-{}", std::process::id()));
+        let blocked = dir.join("blocked");
+        fs::create_dir_all(&dir)?;
+        fs::write(&blocked, b"not a directory")?;
+        let winners = Arc::new(Mutex::new(Vec::new()));
+        let hooks = FinalizationHooks::new()
+            .with_hook(FileDropHook::new(blocked.join("drop")))
+            .with_hook(FileDropHook::new(dir.join("drop")))
+            .with_hook(Recorder(Arc::clone(&winners)));
+
+        // The broken drop directory fails without stopping the hooks after it
+        let finalized = result([1, 5, 2]);
+        let failures = hooks.run(&finalized);
+        assert_eq!(failures.len(), 1);
+        assert!(failures[0].0.contains("blocked"));
+        assert_eq!(*winners.lock().unwrap(), [Some(2)]);
+        let dropped = fs::read(dir.join("drop").join(format!("{}.json", hex::encode(finalized.journal_digest))))?;
+        assert_eq!(serde_json::from_slice::<FinalizedResult>(&dropped).unwrap(), finalized);
+
+        let calldata = enact_calldata(2, finalized.journal_digest);
+        assert_eq!(calldata.len(), 4 + 32 * 2);
+        assert_eq!(calldata[35], 2);
+        assert_eq!(calldata[36..], finalized.journal_digest);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+// Receipt and journal inspection.
+// "Why doesn't this receipt verify?" usually comes down to a handful of facts:
+// which image the claim is for, whether the guest exited cleanly, what kind of
+// seal it carries (a fake seal only verifies in dev mode) and whether the
+// journal decodes as the guest's output. `inspect_receipt` gathers all of them
+// without stopping at the first failure, for the `inspect` binary to print as
+// text or JSON.
+//
+// The receipt is verified against the image id its own claim names; whether
+// that is the expected guest is read off `guest`, matched against the image
+// ids the caller knows. Journals of known guests are decoded with the readers
+// in `journal`, the tally guest's by its layout: interim, compact or full.
+
+use risc0_zkvm::sha::{Digest, Digestible, Impl, Sha256};
+use risc0_zkvm::{InnerReceipt, Receipt, VerifierContext};
+use serde::Serialize;
+
+use crate::journal::{read_aggregate_journal, read_auction_journal, read_challenge_journal, read_circuit_journal, read_compact_journal, read_interim_journal, read_journal, read_vector_sum_journal, COMPACT_JOURNAL_LEN};
+use crate::types::{Digest32, INTERIM_MARKER};
+
+/// A guest image an inspected receipt may be for.
+#[derive(Debug, Clone, Copy)]
+pub struct KnownGuest {
+    pub name: &'static str,
+    pub image_id: [u32; 8],
+}
+
+/// Everything `inspect_receipt` could learn about a receipt.
+#[derive(Debug, Serialize)]
+pub struct ReceiptInspection {
+    pub seal_type: &'static str,
+    pub seal_bytes: usize,
+    pub journal_bytes: usize,
+    pub journal_digest: Digest32,
+    pub image_id: Option<Digest32>, // None when the claim is pruned
+    pub exit_code: Option<String>,
+    pub guest: Option<&'static str>, // The known guest with `image_id`
+    pub verified: bool,
+    pub verification_error: Option<String>,
+    pub journal: Option<serde_json::Value>, // Decoded output, for known guests
+    pub journal_error: Option<String>,
+}
+
+/// Inspect `receipt`; `detached_frames` is the tally guest's stdout for a
+/// compact journal and may be empty otherwise.
+pub fn inspect_receipt(ctx: &VerifierContext, receipt: &Receipt, guests: &[KnownGuest], detached_frames: &[u8]) -> ReceiptInspection {
+    let claim = receipt.claim().ok().and_then(|claim| claim.as_value().ok().cloned());
+    let image_id = claim.as_ref().map(|claim| claim.pre.digest());
+    let guest = image_id.and_then(|id| guests.iter().find(|guest| Digest::from(guest.image_id) == id));
+    let verification = match image_id {
+        Some(id) => receipt.verify_with_context(ctx, id),
+        None => receipt.verify_integrity_with_context(ctx),
+    };
+    let (journal, journal_error) = match guest.map(|guest| decode_journal(guest.name, &receipt.journal.bytes, detached_frames)) {
+        Some(Ok(journal)) => (Some(journal), None),
+        Some(Err(e)) => (None, Some(e)),
+        None => (None, None),
+    };
+    ReceiptInspection {
+        seal_type: seal_type(&receipt.inner),
+        seal_bytes: receipt.seal_size(),
+        journal_bytes: receipt.journal.bytes.len(),
+        journal_digest: <[u8; 32]>::from(*Impl::hash_bytes(&receipt.journal.bytes)).into(),
+        image_id: image_id.map(|id| <[u8; 32]>::from(id).into()),
+        exit_code: claim.map(|claim| format!("{:?}", claim.exit_code)),
+        guest: guest.map(|guest| guest.name),
+        verified: verification.is_ok(),
+        verification_error: verification.err().map(|e| e.to_string()),
+        journal,
+        journal_error,
+    }
+}
+
+fn seal_type(inner: &InnerReceipt) -> &'static str {
+    match inner {
+        InnerReceipt::Composite(_) => "composite",
+        InnerReceipt::Succinct(_) => "succinct",
+        InnerReceipt::Groth16(_) => "groth16",
+        InnerReceipt::Fake(_) => "fake",
+        _ => "unknown",
+    }
+}
+
+fn decode_journal(guest: &str, journal: &[u8], detached_frames: &[u8]) -> Result<serde_json::Value, String> {
+    let (output, frames) = match guest {
+        "tally" if journal.get(..4) == Some(&INTERIM_MARKER.to_le_bytes()[..]) => {
+            let journal = read_interim_journal(journal).map_err(|e| e.to_string())?;
+            (serde_json::to_value(journal.output), journal.encrypted_tallies.len())
+        }
+        "tally" => {
+            let journal = if journal.len() == COMPACT_JOURNAL_LEN { read_compact_journal(journal, detached_frames) } else { read_journal(journal) };
+            let journal = journal.map_err(|e| e.to_string())?;
+            (serde_json::to_value(journal.output), journal.encrypted_tallies.len())
+        }
+        "aggregate" => {
+            let journal = read_aggregate_journal(journal).map_err(|e| e.to_string())?;
+            (serde_json::to_value(journal.output), journal.encrypted_tallies.len())
+        }
+        "vector-sum" => {
+            let journal = read_vector_sum_journal(journal).map_err(|e| e.to_string())?;
+            (serde_json::to_value(journal.output), journal.encrypted_sums.len())
+        }
+        "auction" => {
+            let journal = read_auction_journal(journal).map_err(|e| e.to_string())?;
+            (serde_json::to_value(journal.output), journal.encrypted_demand.len())
+        }
+        "circuit" => {
+            let journal = read_circuit_journal(journal).map_err(|e| e.to_string())?;
+            (serde_json::to_value(journal.output), journal.encrypted_outputs.len())
+        }
+        // Results are fields of the output, not frames
+        "challenge" => (serde_json::to_value(read_challenge_journal(journal).map_err(|e| e.to_string())?), 0),
+        _ => return Err(format!("No journal reader for guest {}", guest)),
+    };
+    let mut output = output.map_err(|e| e.to_string())?;
+    if let Some(fields) = output.as_object_mut() {
+        fields.insert("encrypted_frames_read".into(), frames.into());
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use risc0_zkvm::{FakeReceipt, ReceiptClaim};
+
+    use crate::journal::chain_frame;
+    use crate::types::{CircuitOutput, JournalFrame};
+
+    const CIRCUIT_ID: [u32; 8] = [9; 8];
+
+    #[test]
+    fn test_inspection_reports_why_a_receipt_fails() -> Result<(), Box<dyn std::error::Error>> {
+        let output = CircuitOutput { key_fingerprint: [1; 32], circuit_digest: [2; 32], inputs_digest: [3; 32], ops: 4, encrypted_output_frames: 1 };
+        let payload = vec![7u8; 16];
+        let mut words = risc0_zkvm::serde::to_vec(&output)?;
+        words.extend(risc0_zkvm::serde::to_vec(&JournalFrame { index: 0, payload: payload.clone(), chunk_hash: chain_frame(&[0; 32], 0, &payload) })?);
+        let journal: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+        let receipt = Receipt::new(InnerReceipt::Fake(FakeReceipt::new(ReceiptClaim::ok(CIRCUIT_ID, journal.clone()))), journal.clone());
+        let guests = [KnownGuest { name: "tally", image_id: [8; 8] }, KnownGuest { name: "circuit", image_id: CIRCUIT_ID }];
+
+        let inspection = inspect_receipt(&VerifierContext::default().with_dev_mode(true), &receipt, &guests, &[]);
+        assert!(inspection.verified && inspection.journal_error.is_none());
+        assert_eq!((inspection.seal_type, inspection.guest, inspection.journal_bytes), ("fake", Some("circuit"), journal.len()));
+        let fields = inspection.journal.ok_or("journal was not decoded")?;
+        assert_eq!((fields["ops"].as_u64(), fields["encrypted_frames_read"].as_u64()), (Some(4), Some(1)));
+
+        // Outside dev mode the fake seal is the reason, and the journal still decodes
+        let inspection = inspect_receipt(&VerifierContext::default().with_dev_mode(false), &receipt, &guests, &[]);
+        assert!(!inspection.verified && inspection.verification_error.is_some() && inspection.journal.is_some());
+        // An unknown image is reported as such, with nothing decoded
+        let inspection = inspect_receipt(&VerifierContext::default().with_dev_mode(true), &receipt, &guests[..1], &[]);
+        assert!(inspection.guest.is_none() && inspection.journal.is_none() && inspection.image_id.is_some());
+        Ok(())
+    }
+}
@@ -0,0 +1,268 @@
+// Abuse protection for ballot intake.
+// `SubmissionGate` rejects submissions that are oversized, arrive too fast from
+// one source address or for one voter address, or exceed the election's ballot
+// quota. The size cap is checked before anything else; the rate limits and the
+// quota apply only to submissions that passed `check_submission`, so forged
+// ballots naming someone else's voter address cannot lock that voter out, and
+// only accepted ballots count against the windows and the quota. Rejections are
+// recorded in the audit log like any other rejected submission.
+//
+// The gate is transport-agnostic: `daemon::route` passes the peer address of
+// each ballot posted to an open election, the demo run passes the local host.
+// Limits are fixed windows; windows that have run out are evicted.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+use crate::ballot_set::ballot_message;
+use crate::membership::verify_membership;
+use crate::registrar::{verify_ballot_signature, verify_credential};
+use crate::types::{BallotAuthorization, ElectionContext, EncryptedVote};
+
+/// Default cap on one encoded submission (vote vector plus authorization).
+/// Membership proofs grow by 64 bytes per roster entry, so this allows
+/// rosters of a few thousand identities.
+pub const DEFAULT_MAX_PAYLOAD_BYTES: usize = 256 * 1024;
+
+/// Default election-wide quota, well below the guest's 10k ballot cap.
+pub const DEFAULT_BALLOT_QUOTA: usize = 5000;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum IntakeError {
+    #[error("Submission is {size} bytes, maximum allowed: {max}")]
+    PayloadTooLarge { size: usize, max: usize },
+    #[error("Too many submissions from {address}")]
+    SourceRateLimited { address: IpAddr },
+    #[error("Too many submissions for {voter}")]
+    VoterRateLimited { voter: String },
+    #[error("Ballot quota of {quota} submissions exhausted")]
+    QuotaExhausted { quota: usize },
+}
+
+/// At most `max_requests` submissions per `window`.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub max_requests: u32,
+    pub window: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct IntakeConfig {
+    pub max_payload_bytes: usize,
+    pub per_source: RateLimit,
+    pub per_voter: RateLimit,
+    pub ballot_quota: usize,
+}
+
+impl Default for IntakeConfig {
+    fn default() -> Self {
+        IntakeConfig {
+            max_payload_bytes: DEFAULT_MAX_PAYLOAD_BYTES,
+            per_source: RateLimit { max_requests: 120, window: Duration::from_secs(60) },
+            per_voter: RateLimit { max_requests: 5, window: Duration::from_secs(60) },
+            ballot_quota: DEFAULT_BALLOT_QUOTA,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Window {
+    start: Instant,
+    count: u32,
+}
+
+impl Window {
+    fn expired(&self, limit: &RateLimit, now: Instant) -> bool {
+        now.duration_since(self.start) >= limit.window
+    }
+
+    // Requests already counted in the window current at `now`
+    fn count(&self, limit: &RateLimit, now: Instant) -> u32 {
+        if self.expired(limit, now) { 0 } else { self.count }
+    }
+
+    fn take(&mut self, limit: &RateLimit, now: Instant) {
+        if self.expired(limit, now) {
+            *self = Window { start: now, count: 0 };
+        }
+        self.count += 1;
+    }
+}
+
+pub struct SubmissionGate {
+    config: IntakeConfig,
+    sources: HashMap<IpAddr, Window>,
+    voters: HashMap<String, Window>,
+    admitted: usize,
+    last_eviction: Option<Instant>,
+}
+
+impl SubmissionGate {
+    pub fn new(config: IntakeConfig) -> Self {
+        SubmissionGate { config, sources: HashMap::new(), voters: HashMap::new(), admitted: 0, last_eviction: None }
+    }
+
+    /// Refuse a submission over the payload cap, before it is checked.
+    pub fn check_size(&self, ballot: &EncryptedVote) -> Result<(), IntakeError> {
+        let size = bincode::serialized_size(ballot).map_or(usize::MAX, |size| size as usize);
+        if size > self.config.max_payload_bytes {
+            return Err(IntakeError::PayloadTooLarge { size, max: self.config.max_payload_bytes });
+        }
+        Ok(())
+    }
+
+    /// Admit a submission from `source` received at `now` that passed
+    /// `check_submission`, or say why not. Only admitted submissions count
+    /// against the rate limit windows and the quota.
+    pub fn admit(&mut self, source: IpAddr, ballot: &EncryptedVote, now: Instant) -> Result<(), IntakeError> {
+        self.check_size(ballot)?;
+        if self.admitted >= self.config.ballot_quota {
+            return Err(IntakeError::QuotaExhausted { quota: self.config.ballot_quota });
+        }
+        self.evict_expired(now);
+
+        let IntakeConfig { per_source, per_voter, .. } = self.config;
+        if self.sources.get(&source).is_some_and(|window| window.count(&per_source, now) >= per_source.max_requests) {
+            return Err(IntakeError::SourceRateLimited { address: source });
+        }
+        if self.voters.get(&ballot.voter_address).is_some_and(|window| window.count(&per_voter, now) >= per_voter.max_requests) {
+            return Err(IntakeError::VoterRateLimited { voter: ballot.voter_address.clone() });
+        }
+
+        let fresh = Window { start: now, count: 0 };
+        self.sources.entry(source).or_insert(fresh).take(&per_source, now);
+        self.voters.entry(ballot.voter_address.clone()).or_insert(fresh).take(&per_voter, now);
+        self.admitted += 1;
+        Ok(())
+    }
+
+    pub fn admitted(&self) -> usize {
+        self.admitted
+    }
+
+    /// Sources and voters with a window still running.
+    pub fn tracked(&self) -> (usize, usize) {
+        (self.sources.len(), self.voters.len())
+    }
+
+    // Drop run-out windows, at most once per the shorter window so a busy gate does not sweep on every ballot
+    fn evict_expired(&mut self, now: Instant) {
+        let IntakeConfig { per_source, per_voter, .. } = self.config;
+        if self.last_eviction.is_some_and(|last| now.duration_since(last) < per_source.window.min(per_voter.window)) {
+            return;
+        }
+        self.sources.retain(|_, window| !window.expired(&per_source, now));
+        self.voters.retain(|_, window| !window.expired(&per_voter, now));
+        self.last_eviction = Some(now);
+    }
+}
+
+/// Submission-time checks, mirroring the guest's eligibility rules: the
+/// election, and the registrar credential and ballot signature or the
+/// membership proof.
+pub fn check_submission(ballot: &EncryptedVote, election: &ElectionContext, registrar_public_key: &[u8; 32], identity_roster: &[[u8; 32]]) -> Result<(), String> {
+    if ballot.election_id != election.election_id {
+        return Err(format!("cast in election {}", ballot.election_id));
+    }
+    let message = ballot_message(&ballot.election_id, &ballot.voter_address, &ballot.nonce, &ballot.encrypted_vote_vector, ballot.sequence);
+    match &ballot.authorization {
+        BallotAuthorization::Credential { credential, ballot_signature } => {
+            if !verify_credential(credential, registrar_public_key) {
+                return Err("invalid registrar credential".to_string());
+            }
+            if !verify_ballot_signature(credential, ballot_signature, &message) {
+                return Err("ballot not signed by the credential holder".to_string());
+            }
+        }
+        BallotAuthorization::Membership(proof) => {
+            if !verify_membership(proof, identity_roster, &ballot.election_id, &message) {
+                return Err("invalid membership proof".to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use crate::types::{BallotAuthorization, Digest32, MembershipProof, VoteOption};
+
+    fn test_ballot(voter: &str, ciphertext_len: usize) -> EncryptedVote {
+        EncryptedVote {
+            voter_address: voter.to_string(),
+            election_id: Digest32::ZERO,
+            nonce: [0u8; 32],
+            encrypted_vote_vector: vec![vec![0u8; ciphertext_len]; 3],
+            signature: String::new(),
+            authorization: BallotAuthorization::Membership(MembershipProof {
+                nullifier: [0u8; 32],
+                challenges: Vec::new(),
+                responses: Vec::new(),
+            }),
+            sequence: 0,
+            actual_choice: VoteOption::Option1,
+        }
+    }
+
+    #[test]
+    fn test_gate_enforces_size_rate_and_quota_limits() {
+        let config = IntakeConfig {
+            max_payload_bytes: 4096,
+            per_source: RateLimit { max_requests: 3, window: Duration::from_secs(60) },
+            per_voter: RateLimit { max_requests: 1, window: Duration::from_secs(60) },
+            ballot_quota: 4,
+        };
+        let mut gate = SubmissionGate::new(config);
+        let local = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let remote = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let start = Instant::now();
+
+        assert!(matches!(gate.check_size(&test_ballot("a", 4096)), Err(IntakeError::PayloadTooLarge { .. })));
+        assert!(matches!(gate.admit(local, &test_ballot("a", 4096), start), Err(IntakeError::PayloadTooLarge { .. })));
+        assert_eq!(gate.admit(local, &test_ballot("a", 544), start), Ok(()));
+        assert_eq!(
+            gate.admit(local, &test_ballot("a", 544), start),
+            Err(IntakeError::VoterRateLimited { voter: "a".to_string() })
+        );
+        // Refused submissions take nothing from the source's window
+        assert_eq!(gate.admit(local, &test_ballot("b", 544), start), Ok(()));
+        assert_eq!(gate.admit(local, &test_ballot("c", 544), start), Ok(()));
+        assert_eq!(gate.admit(local, &test_ballot("d", 544), start), Err(IntakeError::SourceRateLimited { address: local }));
+        assert_eq!(gate.admitted(), 3);
+        assert_eq!(gate.tracked(), (1, 3));
+
+        // Windows reset and run-out ones are evicted, but the quota does not reset
+        let later = start + Duration::from_secs(60);
+        assert_eq!(gate.admit(remote, &test_ballot("e", 544), later), Ok(()));
+        assert_eq!(gate.tracked(), (1, 1));
+        assert_eq!(gate.admit(local, &test_ballot("a", 544), later), Err(IntakeError::QuotaExhausted { quota: 4 }));
+        assert_eq!(gate.admitted(), 4);
+    }
+
+    #[test]
+    fn test_replayed_ballot_with_bumped_sequence_is_refused() -> Result<(), crate::registrar::RegistrarError> {
+        use crate::registrar::{CredentialRequest, Registrar};
+        let mut registrar = Registrar::new(["alice"]);
+        let registrar_key = registrar.public_key();
+        let (session, nonce_commitment) = registrar.open_session("alice")?;
+        let (request, blinded) = CredentialRequest::new(&registrar_key, &nonce_commitment)?;
+        let key = request.unblind(registrar.sign_blinded(session, blinded)?)?;
+
+        let election = ElectionContext::default();
+        let mut ballot = test_ballot("alice", 544);
+        let message = ballot_message(&ballot.election_id, &ballot.voter_address, &ballot.nonce, &ballot.encrypted_vote_vector, ballot.sequence);
+        ballot.authorization = BallotAuthorization::Credential { credential: key.credential().clone(), ballot_signature: key.sign_ballot(&message) };
+        assert_eq!(check_submission(&ballot, &election, &registrar_key, &[]), Ok(()));
+
+        // The authorization covers the sequence, so a replay cannot claim to be a later re-vote
+        let mut replay = ballot.clone();
+        replay.sequence += 10;
+        assert_eq!(check_submission(&replay, &election, &registrar_key, &[]), Err("ballot not signed by the credential holder".to_string()));
+        Ok(())
+    }
+}
@@ -0,0 +1,292 @@
+// Interim tally proofs.
+// While voting is open the host can prove the encrypted tally of the ballots
+// accepted so far at a fixed interval and publish each receipt, so observers
+// can check that ballots are processed honestly throughout the voting period
+// and not only at the end. An interim input is the submission log up to that
+// moment and the ballots it accepted; the guest checks it like a final one but
+// commits `InterimTallyOutput`, which keeps the counts sealed.
+//
+// The audit log is hash-chained, so every published interim head must be the
+// head of a prefix of the final log. `verify_interim_heads` checks that, which
+// means a ballot an interim proof covered cannot later be dropped or rewritten
+// without the final log failing against the published heads.
+//
+// Interim tallies can also be proven incrementally: each one names the tally
+// before it as a `PriorTally`, whose receipt the guest verifies as an assumption
+// and whose submission log it checks is a prefix of its own. The journal then
+// commits `prior_tally_digest`, so the published records form a chain that
+// `verify_interim_chain` checks from the first tally to the final result.
+//
+// Receipts are written as `interim-<sequence>.receipt` (bincode) next to an
+// `interim.json` index of `InterimRecord`s, rewritten by rename on every
+// publication so readers never see a partial index. `snapshots` lists them
+// with a digest of each receipt file, as the daemon serves them to observers.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use risc0_zkvm::sha::{Digest, Impl, Sha256};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+use crate::audit_log::{chain_record, EMPTY_AUDIT_LOG_HEAD};
+use crate::types::{AuditRecord, InterimTallyOutput};
+
+const INDEX_FILE: &str = "interim.json";
+
+// Domain separation tag (must match guest implementation)
+const PRIOR_TAG: u8 = 0x08;
+
+#[derive(Error, Debug)]
+pub enum InterimError {
+    #[error("Interim I/O failed: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Interim index encoding failed: {reason}")]
+    Encoding { reason: String },
+    #[error("Interim tally {sequence} covers {submissions} submissions, the log has {len}")]
+    BeyondLog { sequence: u32, submissions: u64, len: usize },
+    #[error("Interim tally {sequence} head is not a prefix of the audit log")]
+    HeadMismatch { sequence: u32 },
+    #[error("Interim tally {sequence} does not continue the tally before it")]
+    ChainBroken { sequence: u32 },
+}
+
+/// When the next interim tally is due.
+pub struct InterimSchedule {
+    interval: Duration,
+    next: Instant,
+}
+
+impl InterimSchedule {
+    /// First due one `interval` after `start`.
+    pub fn every(interval: Duration, start: Instant) -> Self {
+        InterimSchedule { interval, next: start + interval }
+    }
+
+    /// Whether a tally is due at `now`; intervals missed while proving are skipped.
+    pub fn due(&mut self, now: Instant) -> bool {
+        if now < self.next {
+            return false;
+        }
+        while self.next <= now && !self.interval.is_zero() {
+            self.next += self.interval;
+        }
+        true
+    }
+}
+
+/// One published interim tally.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterimRecord {
+    pub sequence: u32,
+    pub published_at: u64, // Unix seconds
+    pub submissions: u64, // Audit log records the interim input held
+    pub ballots: u32,
+    #[serde(with = "hex::serde")]
+    pub ballot_set_root: [u8; 32],
+    #[serde(with = "hex::serde")]
+    pub audit_log_head: [u8; 32],
+    #[serde(with = "hex::serde")]
+    pub journal_digest: [u8; 32],
+    #[serde(with = "hex::serde", default)]
+    pub prior_tally_digest: [u8; 32], // Zero for a tally that continues none
+}
+
+/// A published interim tally with the digest of its receipt file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterimSnapshot {
+    #[serde(flatten)]
+    pub record: InterimRecord,
+    #[serde(with = "hex::serde")]
+    pub receipt_digest: [u8; 32], // SHA-256 of the bincode receipt
+}
+
+/// Publishes interim receipts into a directory.
+pub struct InterimPublisher {
+    dir: PathBuf,
+    records: Vec<InterimRecord>,
+}
+
+impl InterimPublisher {
+    /// Open the directory, continuing an existing index.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, InterimError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let records = match fs::read(dir.join(INDEX_FILE)) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| InterimError::Encoding { reason: e.to_string() })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(InterimPublisher { dir, records })
+    }
+
+    pub fn records(&self) -> &[InterimRecord] {
+        &self.records
+    }
+
+    /// The most recently published receipt, to continue from.
+    pub fn latest_receipt(&self) -> Result<Option<Vec<u8>>, InterimError> {
+        match self.records.last() {
+            Some(record) => self.receipt(record.sequence),
+            None => Ok(None),
+        }
+    }
+
+    /// The receipt published as interim tally `sequence`, if there is one.
+    pub fn receipt(&self, sequence: u32) -> Result<Option<Vec<u8>>, InterimError> {
+        if sequence as usize >= self.records.len() {
+            return Ok(None);
+        }
+        Ok(Some(fs::read(self.receipt_path(sequence))?))
+    }
+
+    fn receipt_path(&self, sequence: u32) -> PathBuf {
+        self.dir.join(format!("interim-{:04}.receipt", sequence))
+    }
+
+    /// Every published tally in order, with its receipt digest.
+    pub fn snapshots(&self) -> Result<Vec<InterimSnapshot>, InterimError> {
+        self.records
+            .iter()
+            .map(|record| {
+                let receipt = fs::read(self.receipt_path(record.sequence))?;
+                Ok(InterimSnapshot { record: record.clone(), receipt_digest: (*Impl::hash_bytes(&receipt)).into() })
+            })
+            .collect()
+    }
+
+    /// Write the receipt and add it to the index.
+    pub fn publish(
+        &mut self,
+        output: &InterimTallyOutput,
+        submissions: u64,
+        journal_digest: [u8; 32],
+        receipt: &[u8],
+    ) -> Result<&InterimRecord, InterimError> {
+        let sequence = self.records.len() as u32;
+        fs::write(self.receipt_path(sequence), receipt)?;
+        self.records.push(InterimRecord {
+            sequence,
+            published_at: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs()),
+            submissions,
+            ballots: output.ballots,
+            ballot_set_root: output.ballot_set_root,
+            audit_log_head: output.audit_log_head,
+            journal_digest,
+            prior_tally_digest: output.prior_tally_digest,
+        });
+
+        let index = serde_json::to_vec_pretty(&self.records).map_err(|e| InterimError::Encoding { reason: e.to_string() })?;
+        let partial = self.dir.join(format!("{}.partial", INDEX_FILE));
+        fs::write(&partial, index)?;
+        fs::rename(&partial, self.dir.join(INDEX_FILE))?;
+        Ok(&self.records[sequence as usize])
+    }
+}
+
+/// Check every published interim head against the prefixes of the final audit log.
+pub fn verify_interim_heads(audit_log: &[AuditRecord], records: &[InterimRecord]) -> Result<(), InterimError> {
+    let mut heads = Vec::with_capacity(audit_log.len() + 1);
+    heads.push(EMPTY_AUDIT_LOG_HEAD);
+    for (index, record) in audit_log.iter().enumerate() {
+        heads.push(chain_record(&heads[index], index as u64, record));
+    }
+    for record in records {
+        let head = heads
+            .get(record.submissions as usize)
+            .ok_or(InterimError::BeyondLog { sequence: record.sequence, submissions: record.submissions, len: audit_log.len() })?;
+        if *head != record.audit_log_head {
+            return Err(InterimError::HeadMismatch { sequence: record.sequence });
+        }
+    }
+    Ok(())
+}
+
+/// H(tag || image_id || journal digest): what a tally continuing this one commits.
+pub fn prior_tally_digest(image_id: impl Into<Digest>, journal_digest: &[u8; 32]) -> [u8; 32] {
+    let image_id: Digest = image_id.into();
+    let mut buf = vec![PRIOR_TAG];
+    buf.extend_from_slice(image_id.as_bytes());
+    buf.extend_from_slice(journal_digest);
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// Check that every published tally after the first continues the one before it.
+pub fn verify_interim_chain(image_id: impl Into<Digest>, records: &[InterimRecord]) -> Result<(), InterimError> {
+    let image_id: Digest = image_id.into();
+    for pair in records.windows(2) {
+        if pair[1].prior_tally_digest != prior_tally_digest(image_id, &pair[0].journal_digest) {
+            return Err(InterimError::ChainBroken { sequence: pair[1].sequence });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit_log::AuditLog;
+
+    fn interim_output(ballots: u32, audit_log_head: [u8; 32], prior_tally_digest: [u8; 32]) -> InterimTallyOutput {
+        InterimTallyOutput {
+            marker: crate::types::INTERIM_MARKER,
+            ballots,
+            superseded_ballots: 0,
+            counted_weight: 0,
+            election: crate::types::ElectionContext::default(),
+            ballot_set_root: [ballots as u8; 32],
+            identity_root: [0u8; 32],
+            election_key_fingerprint: [0u8; 32],
+            audit_log_head,
+            voter_keys_digest: [0u8; 32],
+            prior_tally_digest,
+            encrypted_tally_frames: 3,
+        }
+    }
+
+    #[test]
+    fn test_interim_publications_chain_into_the_final_log() -> Result<(), InterimError> {
+        let start = Instant::now();
+        let mut schedule = InterimSchedule::every(Duration::from_secs(3600), start);
+        assert!(!schedule.due(start + Duration::from_secs(60)));
+        assert!(schedule.due(start + Duration::from_secs(3 * 3600 + 1)));
+        assert!(!schedule.due(start + Duration::from_secs(3 * 3600 + 2)));
+        assert!(schedule.due(start + Duration::from_secs(4 * 3600)));
+
+        let records: Vec<_> = (0..4u8)
+            .map(|i| AuditRecord { ballot_hash: [i; 32], accepted: i != 2, reason: String::new() })
+            .collect();
+        let dir = std::env::temp_dir().join(format!("fhe-voting-interim-{}", std::process::id()));
+        let mut publisher = InterimPublisher::open(&dir)?;
+        let image_id = Digest::from([5u32; 8]);
+        let mut prior = [0u8; 32];
+        for submissions in [1, 3] {
+            let head = AuditLog::from_records(records[..submissions].to_vec()).head();
+            let journal_digest = [submissions as u8; 32];
+            publisher.publish(&interim_output(submissions as u32, head, prior), submissions as u64, journal_digest, b"receipt")?;
+            prior = prior_tally_digest(image_id, &journal_digest);
+        }
+
+        // The index survives a restart and matches every prefix of the final log
+        let reopened = InterimPublisher::open(&dir)?;
+        assert_eq!(reopened.records(), publisher.records());
+        assert!(dir.join("interim-0001.receipt").exists());
+        verify_interim_heads(&records, reopened.records())?;
+        verify_interim_chain(image_id, reopened.records())?;
+        assert_eq!(reopened.latest_receipt()?, Some(b"receipt".to_vec()));
+        let snapshots = reopened.snapshots()?;
+        assert_eq!((snapshots.len(), &snapshots[1].record), (2, &reopened.records()[1]));
+        assert_eq!(snapshots[0].receipt_digest, <[u8; 32]>::from(*Impl::hash_bytes(b"receipt")));
+        assert_eq!(reopened.receipt(2)?, None);
+        assert!(matches!(verify_interim_chain(Digest::ZERO, reopened.records()), Err(InterimError::ChainBroken { sequence: 1 })));
+
+        // A final log that rewrote an early submission no longer matches the published heads
+        let mut rewritten = records.clone();
+        rewritten[0].accepted = false;
+        assert!(matches!(verify_interim_heads(&rewritten, reopened.records()), Err(InterimError::HeadMismatch { sequence: 0 })));
+        assert!(matches!(verify_interim_heads(&records[..2], reopened.records()), Err(InterimError::BeyondLog { sequence: 1, .. })));
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
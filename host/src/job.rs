@@ -0,0 +1,240 @@
+// Proving jobs with timeouts, cancellation and retries.
+// A proof runs for minutes to hours and the zkVM offers no way to interrupt
+// one, so a prover that wedges (a hung r0vm, a Bonsai session that never
+// completes) would hold a daemon worker forever. `run_job` proves each attempt
+// on its own thread and waits for it against a deadline while watching a
+// `CancellationToken`. A timed-out or cancelled attempt is abandoned: its
+// thread runs on until the prover returns, and the result is dropped.
+//
+// Failures that look transient (connection errors, rate limits, 5xx responses)
+// are retried with exponential backoff; any other failure, and a timeout, ends
+// the job, so stuck provers never pile up. Operators set:
+//
+//   FHE_VOTING_JOB_TIMEOUT_SECS  longest one proving attempt may take
+//                                (default: no limit)
+//   FHE_VOTING_JOB_RETRIES       retries after a transient failure (default 2)
+//   FHE_VOTING_JOB_BACKOFF_MS    wait before the first retry, doubled for each
+//                                one after it up to five minutes (default 1000)
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+/// Longest wait between retries.
+pub const MAX_BACKOFF: Duration = Duration::from_secs(300);
+// How often a waiting job looks at its cancellation token
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Failure messages that are worth another attempt
+const TRANSIENT_MARKERS: [&str; 10] = [
+    "connection",
+    "broken pipe",
+    "temporarily",
+    "unavailable",
+    "too many requests",
+    "timed out",
+    "429",
+    "502",
+    "503",
+    "504",
+];
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum JobError {
+    #[error("{name} must be a number, got {value}")]
+    InvalidValue { name: &'static str, value: String },
+    #[error("Job was cancelled")]
+    Cancelled,
+    #[error("Proving attempt {attempt} timed out after {secs}s")]
+    TimedOut { attempt: u32, secs: u64 },
+    #[error("Proving failed after {attempts} attempt(s): {reason}")]
+    Failed { attempts: u32, reason: String },
+    #[error("Job state I/O failed: {reason}")]
+    Storage { reason: String },
+}
+
+/// Asks a running job to stop. Clones share one flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    // Sleep for `duration`, waking early to report a cancellation
+    fn sleep(&self, duration: Duration) -> Result<(), JobError> {
+        let deadline = Instant::now() + duration;
+        loop {
+            if self.is_cancelled() {
+                return Err(JobError::Cancelled);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(());
+            }
+            std::thread::sleep(POLL_INTERVAL.min(deadline - now));
+        }
+    }
+}
+
+/// How long an attempt may run and how failed attempts are retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub timeout: Option<Duration>, // Per attempt
+    pub retries: u32,
+    pub backoff: Duration, // Before the first retry
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy { timeout: None, retries: 2, backoff: Duration::from_secs(1) }
+    }
+}
+
+impl RetryPolicy {
+    /// Settings from the `FHE_VOTING_JOB_*` variables; unset ones keep the defaults.
+    pub fn from_env() -> Result<Self, JobError> {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, JobError> {
+        let parse = |name: &'static str| -> Result<Option<u64>, JobError> {
+            lookup(name).map(|value| value.trim().parse().map_err(|_| JobError::InvalidValue { name, value })).transpose()
+        };
+        let defaults = RetryPolicy::default();
+        Ok(RetryPolicy {
+            timeout: parse("FHE_VOTING_JOB_TIMEOUT_SECS")?.map(Duration::from_secs),
+            retries: match parse("FHE_VOTING_JOB_RETRIES")? {
+                Some(retries) => retries.try_into().unwrap_or(u32::MAX),
+                None => defaults.retries,
+            },
+            backoff: parse("FHE_VOTING_JOB_BACKOFF_MS")?.map(Duration::from_millis).unwrap_or(defaults.backoff),
+        })
+    }
+}
+
+/// Whether a failure reads like one another attempt could get past.
+pub fn is_transient(reason: &str) -> bool {
+    let reason = reason.to_lowercase();
+    TRANSIENT_MARKERS.iter().any(|marker| reason.contains(marker))
+}
+
+/// Run `prove` under `policy` until it succeeds, fails for good, times out or
+/// `token` is cancelled.
+pub fn run_job<T: Send + 'static>(
+    policy: &RetryPolicy,
+    token: &CancellationToken,
+    prove: impl Fn() -> Result<T, String> + Send + Sync + 'static,
+) -> Result<T, JobError> {
+    let prove = Arc::new(prove);
+    let mut backoff = policy.backoff;
+    let mut attempt = 1;
+    loop {
+        match run_attempt(policy, token, attempt, Arc::clone(&prove))? {
+            Ok(value) => return Ok(value),
+            Err(reason) if attempt <= policy.retries && is_transient(&reason) => {
+                eprintln!("⚠️  [Job] Attempt {} failed ({}); retrying in {}ms", attempt, reason, backoff.as_millis());
+                token.sleep(backoff)?;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+                attempt += 1;
+            }
+            Err(reason) => return Err(JobError::Failed { attempts: attempt, reason }),
+        }
+    }
+}
+
+fn run_attempt<T: Send + 'static>(
+    policy: &RetryPolicy,
+    token: &CancellationToken,
+    attempt: u32,
+    prove: Arc<impl Fn() -> Result<T, String> + Send + Sync + 'static>,
+) -> Result<Result<T, String>, JobError> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        // The receiver is gone once the attempt is abandoned
+        let _ = sender.send(prove());
+    });
+    let deadline = policy.timeout.map(|timeout| Instant::now() + timeout);
+    loop {
+        if token.is_cancelled() {
+            return Err(JobError::Cancelled);
+        }
+        let wait = match deadline {
+            Some(deadline) => POLL_INTERVAL.min(deadline.saturating_duration_since(Instant::now())),
+            None => POLL_INTERVAL,
+        };
+        match receiver.recv_timeout(wait) {
+            Ok(result) => return Ok(result),
+            Err(RecvTimeoutError::Timeout) if deadline.is_some_and(|deadline| Instant::now() >= deadline) => {
+                let secs = policy.timeout.unwrap_or_default().as_secs();
+                return Err(JobError::TimedOut { attempt, secs });
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Ok(Err("proving thread panicked".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+
+    fn policy(timeout_ms: Option<u64>) -> RetryPolicy {
+        RetryPolicy { timeout: timeout_ms.map(Duration::from_millis), retries: 2, backoff: Duration::from_millis(1) }
+    }
+
+    #[test]
+    fn test_transient_failures_are_retried_and_others_are_not() -> Result<(), JobError> {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&attempts);
+        let flaky = move || match counter.fetch_add(1, Ordering::SeqCst) {
+            0 | 1 => Err("Bonsai returned 503 Service Unavailable".to_string()),
+            _ => Ok(7),
+        };
+        assert_eq!(run_job(&policy(None), &CancellationToken::new(), flaky)?, 7);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        let failing = || Err::<u32, _>("guest panicked".to_string());
+        assert_eq!(run_job(&policy(None), &CancellationToken::new(), failing), Err(JobError::Failed { attempts: 1, reason: "guest panicked".into() }));
+        let down = || Err::<u32, _>("connection refused".to_string());
+        assert!(matches!(run_job(&policy(None), &CancellationToken::new(), down), Err(JobError::Failed { attempts: 3, .. })));
+
+        let vars = [("FHE_VOTING_JOB_TIMEOUT_SECS", "3600"), ("FHE_VOTING_JOB_RETRIES", "5")];
+        let configured = RetryPolicy::from_lookup(|name| vars.iter().find(|(key, _)| *key == name).map(|(_, value)| value.to_string()))?;
+        assert_eq!(configured, RetryPolicy { timeout: Some(Duration::from_secs(3600)), retries: 5, backoff: Duration::from_secs(1) });
+        assert!(matches!(RetryPolicy::from_lookup(|_| Some("soon".into())), Err(JobError::InvalidValue { .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stuck_attempts_time_out_and_jobs_can_be_cancelled() {
+        let stuck = || {
+            std::thread::sleep(Duration::from_secs(5));
+            Ok(())
+        };
+        let started = Instant::now();
+        assert_eq!(run_job(&policy(Some(100)), &CancellationToken::new(), stuck), Err(JobError::TimedOut { attempt: 1, secs: 0 }));
+
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            canceller.cancel();
+        });
+        assert_eq!(run_job(&policy(None), &token, stuck), Err(JobError::Cancelled));
+        assert!(started.elapsed() < Duration::from_secs(4));
+    }
+}
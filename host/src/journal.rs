@@ -0,0 +1,543 @@
+// Reader for the guest's framed journal.
+// The guest commits `VoteTallyOutput` followed by `encrypted_tally_frames`
+// frames, each carrying one chunk of streamed output and a hash chained over
+// the frames before it. Reading checks the order, the chain and the count, so
+// a consumer handed the frames separately can still detect a missing, reordered
+// or altered chunk. A tally journal must also be exactly the encoding of what
+// it decodes to, so it can carry nothing beyond the result and the encrypted
+// tallies: no unread seed words, no second encoding of the same values.
+//
+// The compact profile (`JournalProfile::Compact`) trades that for on-chain
+// cost: the journal is a fixed 548-byte layout of counts, turnout, outcome,
+// exclusions and 32-byte commitments, and the frames reach the host on the
+// guest's stdout, bound by their chain head in the journal.
+// `read_tally_journal` reads either profile.
+//
+// Interim tallies commit `InterimTallyOutput` instead, starting with
+// `INTERIM_MARKER` where a final journal has its first count. `read_journal`
+// refuses them, so an interim receipt is never mistaken for a result; they are
+// read with `read_interim_journal`.
+//
+// The aggregation guest commits `AggregateTallyOutput` and the summed encrypted
+// tallies as frames; `read_aggregate_journal` reads it. The vector sum guest
+// likewise commits `VectorSumOutput` and the encrypted slot sums, read with
+// `read_vector_sum_journal`, the auction guest `AuctionOutput` and the
+// encrypted demand, read with `read_auction_journal`, and the circuit guest
+// `CircuitOutput` and the output registers, read with `read_circuit_journal`.
+// The challenge guest commits a single `ChallengeOutput`, read with
+// `read_challenge_journal`.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
+
+use risc0_zkvm::serde::Deserializer;
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::scheme::TieBreak;
+use crate::types::{AggregateTallyOutput, AuctionOutput, ChallengeOutput, CircuitOutput, ChainAnchor, Digest32, ElectionContext, Electorate, InterimTallyOutput, JournalFrame, JournalProfile, Outcome, Turnout, VectorSumOutput, VoteTallyOutput, INTERIM_MARKER};
+
+// Domain separation tag (must match guest implementation)
+const FRAME_TAG: u8 = 0x04;
+
+/// Size of a compact journal.
+pub const COMPACT_JOURNAL_LEN: usize = 548;
+
+#[derive(Error, Debug)]
+pub enum JournalError {
+    #[error("Journal is not a whole number of words ({len} bytes)")]
+    Misaligned { len: usize },
+    #[error("Failed to decode journal: {reason}")]
+    Decode { reason: String },
+    #[error("Journal frame {position} has index {index}")]
+    OutOfOrder { position: u32, index: u32 },
+    #[error("Journal frame {index} hash does not chain from the previous frame")]
+    ChunkHashMismatch { index: u32 },
+    #[error("Journal has {extra} bytes after the last frame")]
+    TrailingData { extra: usize },
+    #[error("Journal is not the encoding of the result it decodes to")]
+    NonCanonical,
+    #[error("Compact journal is {len} bytes, expected {COMPACT_JOURNAL_LEN}")]
+    CompactLength { len: usize },
+    #[error("Detached tally frames do not match the head committed in the journal")]
+    DetachedFramesMismatch,
+    #[error("Journal is an interim tally, not a final result")]
+    Interim,
+    #[error("Journal is not an interim tally")]
+    NotInterim,
+}
+
+/// Chain step: hash' = H(tag || hash || index || len || payload).
+pub fn chain_frame(previous: &[u8; 32], index: u32, payload: &[u8]) -> [u8; 32] {
+    let mut buf = vec![FRAME_TAG];
+    buf.extend_from_slice(previous);
+    buf.extend_from_slice(&index.to_le_bytes());
+    buf.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    buf.extend_from_slice(payload);
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// SHA-256 of the little-endian counts (mirrors the guest's `computation_hash`).
+pub fn computation_hash(option1_count: u32, option2_count: u32, option3_count: u32) -> Digest32 {
+    let mut buf = Vec::with_capacity(12);
+    for count in [option1_count, option2_count, option3_count] {
+        buf.extend_from_slice(&count.to_le_bytes());
+    }
+    Digest32((*Impl::hash_bytes(&buf)).into())
+}
+
+/// A decoded tally journal: the summary and the streamed encrypted tallies.
+pub struct TallyJournal {
+    pub output: VoteTallyOutput,
+    pub encrypted_tallies: Vec<Vec<u8>>,
+}
+
+pub fn read_journal(bytes: &[u8]) -> Result<TallyJournal, JournalError> {
+    let words = to_words(bytes)?;
+    if words.first() == Some(&INTERIM_MARKER) {
+        return Err(JournalError::Interim);
+    }
+    let mut remaining: &[u32] = &words;
+    let output = VoteTallyOutput::deserialize(&mut Deserializer::new(&mut remaining)).map_err(decode)?;
+    let (encrypted_tallies, _) = read_frames(&mut remaining, output.encrypted_tally_frames)?;
+    if !remaining.is_empty() {
+        return Err(JournalError::TrailingData { extra: remaining.len() * 4 });
+    }
+    if encode_journal(&output, &encrypted_tallies) != bytes {
+        return Err(JournalError::NonCanonical);
+    }
+
+    Ok(TallyJournal { output, encrypted_tallies })
+}
+
+/// Journal bytes for `output` and its encrypted tallies (mirrors the guest's commits).
+pub fn encode_journal(output: &VoteTallyOutput, encrypted_tallies: &[Vec<u8>]) -> Vec<u8> {
+    let mut words = risc0_zkvm::serde::to_vec(output).expect("tally output serialization is infallible");
+    let mut head = [0u8; 32];
+    for (index, payload) in encrypted_tallies.iter().enumerate() {
+        head = chain_frame(&head, index as u32, payload);
+        let frame = JournalFrame { index: index as u32, payload: payload.clone(), chunk_hash: head };
+        words.extend(risc0_zkvm::serde::to_vec(&frame).expect("frame serialization is infallible"));
+    }
+    words.iter().flat_map(|word| word.to_le_bytes()).collect()
+}
+
+/// A decoded interim tally journal.
+pub struct InterimJournal {
+    pub output: InterimTallyOutput,
+    pub encrypted_tallies: Vec<Vec<u8>>,
+}
+
+pub fn read_interim_journal(bytes: &[u8]) -> Result<InterimJournal, JournalError> {
+    let words = to_words(bytes)?;
+    if words.first() != Some(&INTERIM_MARKER) {
+        return Err(JournalError::NotInterim);
+    }
+    let mut remaining: &[u32] = &words;
+    let output = InterimTallyOutput::deserialize(&mut Deserializer::new(&mut remaining)).map_err(decode)?;
+    let (encrypted_tallies, _) = read_frames(&mut remaining, output.encrypted_tally_frames)?;
+    if !remaining.is_empty() {
+        return Err(JournalError::TrailingData { extra: remaining.len() * 4 });
+    }
+
+    Ok(InterimJournal { output, encrypted_tallies })
+}
+
+/// A decoded aggregation journal.
+pub struct AggregateJournal {
+    pub output: AggregateTallyOutput,
+    pub encrypted_tallies: Vec<Vec<u8>>,
+}
+
+pub fn read_aggregate_journal(bytes: &[u8]) -> Result<AggregateJournal, JournalError> {
+    let words = to_words(bytes)?;
+    let mut remaining: &[u32] = &words;
+    let output = AggregateTallyOutput::deserialize(&mut Deserializer::new(&mut remaining)).map_err(decode)?;
+    let (encrypted_tallies, _) = read_frames(&mut remaining, output.encrypted_tally_frames)?;
+    if !remaining.is_empty() {
+        return Err(JournalError::TrailingData { extra: remaining.len() * 4 });
+    }
+
+    Ok(AggregateJournal { output, encrypted_tallies })
+}
+
+/// A decoded vector sum journal.
+pub struct VectorSumJournal {
+    pub output: VectorSumOutput,
+    pub encrypted_sums: Vec<Vec<u8>>,
+}
+
+pub fn read_vector_sum_journal(bytes: &[u8]) -> Result<VectorSumJournal, JournalError> {
+    let words = to_words(bytes)?;
+    let mut remaining: &[u32] = &words;
+    let output = VectorSumOutput::deserialize(&mut Deserializer::new(&mut remaining)).map_err(decode)?;
+    let (encrypted_sums, _) = read_frames(&mut remaining, output.encrypted_sum_frames)?;
+    if !remaining.is_empty() {
+        return Err(JournalError::TrailingData { extra: remaining.len() * 4 });
+    }
+
+    Ok(VectorSumJournal { output, encrypted_sums })
+}
+
+/// A decoded auction journal.
+pub struct AuctionJournal {
+    pub output: AuctionOutput,
+    pub encrypted_demand: Vec<Vec<u8>>,
+}
+
+pub fn read_auction_journal(bytes: &[u8]) -> Result<AuctionJournal, JournalError> {
+    let words = to_words(bytes)?;
+    let mut remaining: &[u32] = &words;
+    let output = AuctionOutput::deserialize(&mut Deserializer::new(&mut remaining)).map_err(decode)?;
+    let (encrypted_demand, _) = read_frames(&mut remaining, output.encrypted_demand_frames)?;
+    if !remaining.is_empty() {
+        return Err(JournalError::TrailingData { extra: remaining.len() * 4 });
+    }
+
+    Ok(AuctionJournal { output, encrypted_demand })
+}
+
+/// A decoded circuit journal.
+pub struct CircuitJournal {
+    pub output: CircuitOutput,
+    pub encrypted_outputs: Vec<Vec<u8>>,
+}
+
+pub fn read_circuit_journal(bytes: &[u8]) -> Result<CircuitJournal, JournalError> {
+    let words = to_words(bytes)?;
+    let mut remaining: &[u32] = &words;
+    let output = CircuitOutput::deserialize(&mut Deserializer::new(&mut remaining)).map_err(decode)?;
+    let (encrypted_outputs, _) = read_frames(&mut remaining, output.encrypted_output_frames)?;
+    if !remaining.is_empty() {
+        return Err(JournalError::TrailingData { extra: remaining.len() * 4 });
+    }
+
+    Ok(CircuitJournal { output, encrypted_outputs })
+}
+
+pub fn read_challenge_journal(bytes: &[u8]) -> Result<ChallengeOutput, JournalError> {
+    let words = to_words(bytes)?;
+    let mut remaining: &[u32] = &words;
+    let output = ChallengeOutput::deserialize(&mut Deserializer::new(&mut remaining)).map_err(decode)?;
+    if !remaining.is_empty() {
+        return Err(JournalError::TrailingData { extra: remaining.len() * 4 });
+    }
+
+    Ok(output)
+}
+
+/// Read a journal of the given profile; `detached_frames` is the guest's stdout.
+pub fn read_tally_journal(profile: JournalProfile, journal: &[u8], detached_frames: &[u8]) -> Result<TallyJournal, JournalError> {
+    match profile {
+        JournalProfile::Full => read_journal(journal),
+        JournalProfile::Compact => read_compact_journal(journal, detached_frames),
+    }
+}
+
+/// Compact journals carry no computation hash; the decoded output leaves it zero.
+pub fn read_compact_journal(journal: &[u8], detached_frames: &[u8]) -> Result<TallyJournal, JournalError> {
+    let journal: &[u8; COMPACT_JOURNAL_LEN] = journal.try_into().map_err(|_| JournalError::CompactLength { len: journal.len() })?;
+    let word = |at: usize| u32::from_le_bytes(journal[at..at + 4].try_into().unwrap());
+    let commitment = |at: usize| -> [u8; 32] { journal[at..at + 32].try_into().unwrap() };
+    let long = |at: usize| u64::from_le_bytes(journal[at..at + 8].try_into().unwrap());
+    let close_block = long(24);
+    let close_block_hash = commitment(128);
+    let tie_break = match word(400) {
+        0 => TieBreak::Declare,
+        1 => TieBreak::FirstListed,
+        2 => TieBreak::Lot(commitment(404)),
+        rule => return Err(JournalError::Decode { reason: format!("unknown tie-break rule {}", rule) }),
+    };
+    let output = VoteTallyOutput {
+        option1_count: word(0),
+        option2_count: word(4),
+        option3_count: word(8),
+        total_votes: word(12),
+        election: ElectionContext { election_id: Digest32(commitment(256)), chain_id: long(288), closes_at: long(296), candidates_digest: Digest32(commitment(304)), tie_break, scheme_digest: Digest32(commitment(484)) },
+        computation_hash: Digest32::ZERO,
+        ballot_set_root: commitment(32),
+        identity_root: commitment(64),
+        superseded_ballots: word(16),
+        turnout: Turnout {
+            electorate: Electorate { registered_voters: word(336), registry_root: commitment(360) },
+            submissions: word(340),
+            rejected_submissions: word(344),
+            revoked_ballots: word(348),
+            ballots_tallied: word(352),
+            turnout_basis_points: word(356),
+        },
+        outcome: Outcome {
+            leading: word(392),
+            winner: word(396),
+            tie_break,
+        },
+        rejected_count: word(436),
+        duplicate_count: word(440),
+        invalid_signature_count: word(444),
+        rejections_by_reason: core::array::from_fn(|code| word(448 + 4 * code)),
+        audit_log_head: commitment(96),
+        chain_anchor: (close_block != 0 || close_block_hash != [0u8; 32]).then_some(ChainAnchor { close_block, block_hash: close_block_hash }),
+        prior_tally_digest: commitment(192),
+        result_typed_digest: commitment(224),
+        trustees_digest: commitment(516),
+        encrypted_tally_frames: word(20),
+    };
+
+    let words = to_words(detached_frames)?;
+    let mut remaining: &[u32] = &words;
+    let (encrypted_tallies, head) = read_frames(&mut remaining, output.encrypted_tally_frames)?;
+    if !remaining.is_empty() {
+        return Err(JournalError::TrailingData { extra: remaining.len() * 4 });
+    }
+    if head != commitment(160) {
+        return Err(JournalError::DetachedFramesMismatch);
+    }
+    if encode_compact_journal(&output, &head) != journal {
+        return Err(JournalError::NonCanonical);
+    }
+
+    Ok(TallyJournal { output, encrypted_tallies })
+}
+
+/// Compact journal bytes for `output` (mirrors the guest's encoder).
+pub fn encode_compact_journal(output: &VoteTallyOutput, frames_head: &[u8; 32]) -> Vec<u8> {
+    let (close_block, close_block_hash) = output.chain_anchor.map_or((0, [0u8; 32]), |anchor| (anchor.close_block, anchor.block_hash));
+    let mut bytes = Vec::with_capacity(COMPACT_JOURNAL_LEN);
+    for word in [output.option1_count, output.option2_count, output.option3_count, output.total_votes, output.superseded_ballots, output.encrypted_tally_frames] {
+        bytes.extend_from_slice(&word.to_le_bytes());
+    }
+    bytes.extend_from_slice(&close_block.to_le_bytes());
+    for commitment in [&output.ballot_set_root, &output.identity_root, &output.audit_log_head, &close_block_hash, frames_head, &output.prior_tally_digest, &output.result_typed_digest] {
+        bytes.extend_from_slice(commitment);
+    }
+    bytes.extend_from_slice(output.election.election_id.as_bytes());
+    bytes.extend_from_slice(&output.election.chain_id.to_le_bytes());
+    bytes.extend_from_slice(&output.election.closes_at.to_le_bytes());
+    bytes.extend_from_slice(output.election.candidates_digest.as_bytes());
+    let turnout = &output.turnout;
+    for word in [turnout.electorate.registered_voters, turnout.submissions, turnout.rejected_submissions, turnout.revoked_ballots, turnout.ballots_tallied, turnout.turnout_basis_points] {
+        bytes.extend_from_slice(&word.to_le_bytes());
+    }
+    bytes.extend_from_slice(&turnout.electorate.registry_root);
+    let (rule, seed) = match output.outcome.tie_break {
+        TieBreak::Declare => (0u32, [0u8; 32]),
+        TieBreak::FirstListed => (1, [0u8; 32]),
+        TieBreak::Lot(seed) => (2, seed),
+    };
+    for word in [output.outcome.leading, output.outcome.winner, rule] {
+        bytes.extend_from_slice(&word.to_le_bytes());
+    }
+    bytes.extend_from_slice(&seed);
+    for word in [output.rejected_count, output.duplicate_count, output.invalid_signature_count].into_iter().chain(output.rejections_by_reason) {
+        bytes.extend_from_slice(&word.to_le_bytes());
+    }
+    bytes.extend_from_slice(output.election.scheme_digest.as_bytes());
+    bytes.extend_from_slice(&output.trustees_digest);
+    bytes
+}
+
+fn decode(e: risc0_zkvm::serde::Error) -> JournalError {
+    JournalError::Decode { reason: e.to_string() }
+}
+
+fn to_words(bytes: &[u8]) -> Result<Vec<u32>, JournalError> {
+    let (words, rest) = bytes.as_chunks::<4>();
+    if !rest.is_empty() {
+        return Err(JournalError::Misaligned { len: bytes.len() });
+    }
+    Ok(words.iter().map(|&word| u32::from_le_bytes(word)).collect())
+}
+
+// Read `count` chained frames; returns their payloads and the chain head
+fn read_frames(remaining: &mut &[u32], count: u32) -> Result<(Vec<Vec<u8>>, [u8; 32]), JournalError> {
+    let mut head = [0u8; 32];
+    // The frame count is untrusted: every frame takes at least one word
+    let mut payloads = Vec::with_capacity((count as usize).min(remaining.len()));
+    for position in 0..count {
+        let frame = JournalFrame::deserialize(&mut Deserializer::new(&mut *remaining)).map_err(decode)?;
+        if frame.index != position {
+            return Err(JournalError::OutOfOrder { position, index: frame.index });
+        }
+        head = chain_frame(&head, frame.index, &frame.payload);
+        if head != frame.chunk_hash {
+            return Err(JournalError::ChunkHashMismatch { index: frame.index });
+        }
+        payloads.push(frame.payload);
+    }
+    Ok((payloads, head))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RejectionReason;
+
+    fn test_output() -> VoteTallyOutput {
+        VoteTallyOutput {
+            option1_count: 1,
+            option2_count: 0,
+            option3_count: 0,
+            total_votes: 1,
+            election: ElectionContext { election_id: Digest32([8u8; 32]), chain_id: 1, closes_at: 1_700_000_000, candidates_digest: Digest32([9u8; 32]), tie_break: TieBreak::Lot([4u8; 32]), scheme_digest: Digest32([6u8; 32]) },
+            computation_hash: computation_hash(1, 0, 0),
+            ballot_set_root: [1u8; 32],
+            identity_root: [2u8; 32],
+            superseded_ballots: 0,
+            turnout: Turnout {
+                electorate: Electorate { registered_voters: 3, registry_root: [7u8; 32] },
+                submissions: 2,
+                rejected_submissions: 1,
+                revoked_ballots: 0,
+                ballots_tallied: 1,
+                turnout_basis_points: 3333,
+            },
+            outcome: Outcome { leading: 0b001, winner: 1, tie_break: TieBreak::Lot([4u8; 32]) },
+            rejected_count: 3,
+            duplicate_count: 1,
+            invalid_signature_count: 2,
+            rejections_by_reason: [0, 0, 0, 0, 2, 0, 1, 0, 0],
+            audit_log_head: [3u8; 32],
+            chain_anchor: None,
+            prior_tally_digest: [5u8; 32],
+            result_typed_digest: [6u8; 32],
+            trustees_digest: [8u8; 32],
+            encrypted_tally_frames: 2,
+        }
+    }
+
+    fn test_journal(frames: &[JournalFrame]) -> Vec<u8> {
+        let mut words = risc0_zkvm::serde::to_vec(&test_output()).unwrap();
+        words.extend(frame_words(frames));
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    fn frame_words(frames: &[JournalFrame]) -> Vec<u32> {
+        frames.iter().flat_map(|frame| risc0_zkvm::serde::to_vec(frame).unwrap()).collect()
+    }
+
+    fn frame(previous: &[u8; 32], index: u32, payload: Vec<u8>) -> JournalFrame {
+        let chunk_hash = chain_frame(previous, index, &payload);
+        JournalFrame { index, payload, chunk_hash }
+    }
+
+    #[test]
+    fn test_framed_journal_round_trip_and_tampering() -> Result<(), JournalError> {
+        let first = frame(&[0u8; 32], 0, vec![7u8; 512]);
+        let second = frame(&first.chunk_hash, 1, vec![9u8; 512]);
+
+        let journal = read_journal(&test_journal(&[first.clone(), second.clone()]))?;
+        assert_eq!(journal.output.total_votes, 1);
+        assert_eq!(journal.output.computation_hash, computation_hash(1, 0, 0));
+        assert_eq!(journal.encrypted_tallies, vec![vec![7u8; 512], vec![9u8; 512]]);
+
+        // Digests are packed bytes in the journal (a length word and eight data words) and hex in JSON
+        let digest = computation_hash(1, 0, 0);
+        assert_eq!(risc0_zkvm::serde::to_vec(&digest).unwrap().len(), 9);
+        let json = serde_json::to_string(&digest).unwrap();
+        assert_eq!(json, format!("\"{}\"", digest));
+        assert_eq!(serde_json::from_str::<Digest32>(&json).unwrap(), digest);
+        assert_eq!(Digest32::from_hex(&format!("0x{}", digest)), Some(digest));
+
+        let mut altered = second.clone();
+        altered.payload[0] ^= 1;
+        assert!(matches!(read_journal(&test_journal(&[first.clone(), altered])), Err(JournalError::ChunkHashMismatch { index: 1 })));
+        assert!(matches!(read_journal(&test_journal(&[second, first.clone()])), Err(JournalError::OutOfOrder { .. })));
+        assert!(matches!(read_journal(&test_journal(&[first])), Err(JournalError::Decode { .. })));
+        Ok(())
+    }
+    #[test]
+    fn test_compact_journal_round_trip_and_detached_frames() -> Result<(), JournalError> {
+        let first = frame(&[0u8; 32], 0, vec![7u8; 512]);
+        let second = frame(&first.chunk_hash, 1, vec![9u8; 512]);
+        let detached: Vec<u8> = frame_words(&[first.clone(), second.clone()]).iter().flat_map(|word| word.to_le_bytes()).collect();
+        let mut output = test_output();
+        output.chain_anchor = Some(ChainAnchor { close_block: 42, block_hash: [4u8; 32] });
+
+        let compact = encode_compact_journal(&output, &second.chunk_hash);
+        assert_eq!(compact.len(), COMPACT_JOURNAL_LEN);
+        // The counts sit where the governance contract reads them in either profile
+        assert_eq!(compact[..16], test_journal(&[])[..16]);
+        let journal = read_tally_journal(JournalProfile::Compact, &compact, &detached)?;
+        assert_eq!(journal.output.chain_anchor, output.chain_anchor);
+        assert_eq!(journal.output.election, output.election);
+        assert_eq!(journal.output.turnout, output.turnout);
+        assert_eq!((journal.output.outcome, journal.output.outcome.winner()), (output.outcome, Some(1)));
+        assert_eq!(output.turnout.electorate.turnout_basis_points(1), output.turnout.turnout_basis_points);
+        assert_eq!((journal.output.rejected_count, journal.output.duplicate_count, journal.output.invalid_signature_count), (3, 1, 2));
+        assert_eq!(journal.output.rejections_by_reason[RejectionReason::InvalidSignature as usize], 2);
+        assert_eq!((journal.output.option1_count, journal.output.total_votes), (1, 1));
+        assert_eq!((journal.output.audit_log_head, journal.output.prior_tally_digest), ([3u8; 32], [5u8; 32]));
+        assert_eq!(journal.encrypted_tallies, vec![vec![7u8; 512], vec![9u8; 512]]);
+
+        // Frames that chain correctly but are not the ones committed are refused
+        let other = frame(&first.chunk_hash, 1, vec![8u8; 512]);
+        let swapped: Vec<u8> = frame_words(&[first, other]).iter().flat_map(|word| word.to_le_bytes()).collect();
+        assert!(matches!(read_compact_journal(&compact, &swapped), Err(JournalError::DetachedFramesMismatch)));
+        assert!(matches!(read_compact_journal(&compact[..100], &detached), Err(JournalError::CompactLength { len: 100 })));
+
+        // Below the anonymity set only the turnout is committed, with no frames to detach
+        let withheld = VoteTallyOutput { option1_count: 0, total_votes: 2, computation_hash: computation_hash(0, 0, 0), encrypted_tally_frames: 0, ..test_output() };
+        let journal = read_tally_journal(JournalProfile::Compact, &encode_compact_journal(&withheld, &[0u8; 32]), &[])?;
+        assert!(journal.output.counts_withheld() && journal.encrypted_tallies.is_empty());
+        assert!(!test_output().counts_withheld());
+        Ok(())
+    }
+
+    #[test]
+    fn test_journals_hold_only_the_result() -> Result<(), JournalError> {
+        let first = frame(&[0u8; 32], 0, vec![7u8; 16]);
+        let second = frame(&first.chunk_hash, 1, vec![9u8; 16]);
+        let journal = test_journal(&[first.clone(), second.clone()]);
+        assert_eq!(encode_journal(&test_output(), &[first.payload.clone(), second.payload.clone()]), journal);
+
+        // A payload byte is a whole word; its spare bits must not carry anything
+        let payload_word = 4 * (risc0_zkvm::serde::to_vec(&test_output()).unwrap().len() + 2);
+        let mut smuggled = journal.clone();
+        smuggled[payload_word + 1] = 0xff;
+        assert!(read_journal(&smuggled).is_err());
+
+        // Neither may the lot seed words of a journal whose rule draws no lot
+        let declared = VoteTallyOutput {
+            election: ElectionContext { tie_break: TieBreak::Declare, ..test_output().election },
+            outcome: Outcome { tie_break: TieBreak::Declare, ..test_output().outcome },
+            ..test_output()
+        };
+        let detached: Vec<u8> = frame_words(&[first, second.clone()]).iter().flat_map(|word| word.to_le_bytes()).collect();
+        let mut compact = encode_compact_journal(&declared, &second.chunk_hash);
+        read_compact_journal(&compact, &detached)?;
+        compact[404] = 1;
+        assert!(matches!(read_compact_journal(&compact, &detached), Err(JournalError::NonCanonical)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_interim_journals_are_not_results() -> Result<(), JournalError> {
+        let first = frame(&[0u8; 32], 0, vec![7u8; 64]);
+        let output = InterimTallyOutput {
+            marker: INTERIM_MARKER,
+            ballots: 4,
+            superseded_ballots: 1,
+            counted_weight: 4,
+            election: ElectionContext::default(),
+            ballot_set_root: [1u8; 32],
+            identity_root: [2u8; 32],
+            election_key_fingerprint: [4u8; 32],
+            audit_log_head: [3u8; 32],
+            voter_keys_digest: [5u8; 32],
+            prior_tally_digest: [0u8; 32],
+            encrypted_tally_frames: 1,
+        };
+        let mut words = risc0_zkvm::serde::to_vec(&output).unwrap();
+        words.extend(frame_words(&[first]));
+        let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+
+        assert!(matches!(read_journal(&bytes), Err(JournalError::Interim)));
+        let interim = read_interim_journal(&bytes)?;
+        assert_eq!((interim.output.ballots, interim.output.audit_log_head), (4, [3u8; 32]));
+        assert_eq!(interim.encrypted_tallies, vec![vec![7u8; 64]]);
+        assert!(matches!(read_interim_journal(&test_journal(&[])), Err(JournalError::NotInterim)));
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+// Lattigo BFV parameter interop.
+// Go services in an election stack describe BFV parameters with Lattigo's
+// `bfv.ParametersLiteral`, serialized as JSON. This module exports the demo's
+// parameters in that form and checks whether Lattigo can instantiate them.
+//
+// Lattigo's rings require every modulus in Q to be an NTT-friendly prime
+// (q = 1 mod 2N); the demo's q = 2^58 is not, so `check_compatibility` rejects
+// it and Lattigo cannot evaluate on the guest's ciphertexts until the scheme
+// moves to an RNS prime chain. `ParametersLiteral::for_chain` describes a
+// `ModulusChain` of such primes.
+//
+// Ciphertexts still convert: `marshal_ciphertext` writes a guest ciphertext in
+// the byte layout of Lattigo's `rlwe.Ciphertext.MarshalBinary`, which Go reads
+// back with `UnmarshalBinary` without instantiating a ring, and
+// `unmarshal_ciphertext` takes such bytes back to the guest's form. The layout,
+// all integers little-endian u64 after the first byte:
+//
+//   metadata flag (u8, 0 = none) | degree + 1 = 2 | per polynomial (c0, c1):
+//   levels = 1 | coefficient count = N | N coefficients mod q
+//
+// A Go service must clear `MetaData` and keep its polynomials out of NTT form,
+// which is the guest's representation.
+
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+use crate::fhe_client::{Cipher, Signed, CIPHERTEXT_MODULUS, PLAINTEXT_MODULUS, POLYNOMIAL_DEGREE};
+use crate::modulus_chain::ModulusChain;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum LattigoError {
+    #[error("Ring degree 2^{log_n} is out of range")]
+    InvalidRingDegree { log_n: u32 },
+    #[error("Modulus {modulus} is not an NTT-friendly prime for ring degree {degree} (needs a prime = 1 mod {})", 2 * degree)]
+    NotNttFriendly { modulus: u64, degree: usize },
+    #[error("Plaintext modulus {modulus} is not prime")]
+    PlaintextModulusNotPrime { modulus: u64 },
+    #[error("Lattigo ciphertext is truncated or has trailing bytes")]
+    MalformedCiphertext,
+    #[error("Lattigo ciphertext carries metadata; clear MetaData before marshalling")]
+    UnexpectedMetaData,
+    #[error("Lattigo ciphertext has {polys} polynomials, expected 2")]
+    UnexpectedDegree { polys: u64 },
+    #[error("Lattigo polynomial has {levels} levels of {coefficients} coefficients, expected 1 of {}", POLYNOMIAL_DEGREE)]
+    ShapeMismatch { levels: u64, coefficients: u64 },
+    #[error("Lattigo ciphertext coefficient {index} is not below the ciphertext modulus")]
+    NonCanonicalCoefficient { index: usize },
+}
+
+/// Lattigo's `bfv.ParametersLiteral` (field names match its JSON encoding).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ParametersLiteral {
+    pub log_n: u32,
+    pub q: Vec<u64>,
+    pub p: Vec<u64>,
+    pub plaintext_modulus: u64,
+}
+
+impl ParametersLiteral {
+    /// The demo scheme's parameters (client and guest).
+    pub fn demo() -> Self {
+        ParametersLiteral {
+            log_n: POLYNOMIAL_DEGREE.trailing_zeros(),
+            q: vec![CIPHERTEXT_MODULUS],
+            p: Vec::new(),
+            plaintext_modulus: PLAINTEXT_MODULUS,
+        }
+    }
+
+    /// Parameters with `chain` as Q, in place of the demo's single modulus.
+    pub fn for_chain(chain: &ModulusChain) -> Self {
+        ParametersLiteral { q: chain.primes(), ..Self::demo() }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("parameter literal serialization is infallible")
+    }
+}
+
+/// A guest ciphertext in Lattigo's `rlwe.Ciphertext` binary layout (see the header).
+pub fn marshal_ciphertext(ciphertext: &Cipher<Signed>) -> Vec<u8> {
+    let mut bytes = vec![0u8];
+    bytes.extend_from_slice(&2u64.to_le_bytes());
+    for poly in ciphertext.ciphertext_data.chunks(POLYNOMIAL_DEGREE) {
+        bytes.extend_from_slice(&1u64.to_le_bytes());
+        bytes.extend_from_slice(&(POLYNOMIAL_DEGREE as u64).to_le_bytes());
+        for &coefficient in poly {
+            bytes.extend_from_slice(&coefficient.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+/// Read a degree-1, single-level, coefficient-domain Lattigo ciphertext as a
+/// guest ciphertext. Bind it with `serialize_bound` before tallying.
+pub fn unmarshal_ciphertext(bytes: &[u8]) -> Result<Cipher<Signed>, LattigoError> {
+    let (&flag, rest) = bytes.split_first().ok_or(LattigoError::MalformedCiphertext)?;
+    if flag != 0 {
+        return Err(LattigoError::UnexpectedMetaData);
+    }
+    let (words, trailing) = rest.as_chunks::<8>();
+    if !trailing.is_empty() {
+        return Err(LattigoError::MalformedCiphertext);
+    }
+    let mut words = words.iter().map(|&word| u64::from_le_bytes(word));
+    let mut next = || words.next().ok_or(LattigoError::MalformedCiphertext);
+
+    let polys = next()?;
+    if polys != 2 {
+        return Err(LattigoError::UnexpectedDegree { polys });
+    }
+    let mut ciphertext_data = Vec::with_capacity(2 * POLYNOMIAL_DEGREE);
+    for _ in 0..2 {
+        let (levels, coefficients) = (next()?, next()?);
+        if levels != 1 || coefficients != POLYNOMIAL_DEGREE as u64 {
+            return Err(LattigoError::ShapeMismatch { levels, coefficients });
+        }
+        for _ in 0..POLYNOMIAL_DEGREE {
+            ciphertext_data.push(next()?);
+        }
+    }
+    if next().is_ok() {
+        return Err(LattigoError::MalformedCiphertext);
+    }
+    if let Some(index) = ciphertext_data.iter().position(|&c| c >= CIPHERTEXT_MODULUS) {
+        return Err(LattigoError::NonCanonicalCoefficient { index });
+    }
+    Ok(Cipher { ciphertext_data, _phantom: std::marker::PhantomData })
+}
+
+/// Check that Lattigo can build rings for `params`.
+pub fn check_compatibility(params: &ParametersLiteral) -> Result<(), LattigoError> {
+    if params.log_n == 0 || params.log_n >= 32 {
+        return Err(LattigoError::InvalidRingDegree { log_n: params.log_n });
+    }
+    let degree = 1usize << params.log_n;
+    let two_n = 2 * degree as u64;
+    if let Some(&modulus) = params.q.iter().chain(&params.p).find(|&&m| !is_prime(m) || m % two_n != 1) {
+        return Err(LattigoError::NotNttFriendly { modulus, degree });
+    }
+    if !is_prime(params.plaintext_modulus) {
+        return Err(LattigoError::PlaintextModulusNotPrime { modulus: params.plaintext_modulus });
+    }
+    Ok(())
+}
+
+// Deterministic Miller-Rabin; these bases are exact for all u64
+pub(crate) fn is_prime(n: u64) -> bool {
+    const BASES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    if let Some(&base) = BASES.iter().find(|&&b| n.is_multiple_of(b)) {
+        return n == base;
+    }
+    let mul = |a: u64, b: u64| ((a as u128 * b as u128) % n as u128) as u64;
+    let pow = |mut base: u64, mut exp: u64| {
+        let mut result = 1;
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = mul(result, base);
+            }
+            base = mul(base, base);
+            exp >>= 1;
+        }
+        result
+    };
+    let rounds = (n - 1).trailing_zeros();
+    let odd = (n - 1) >> rounds;
+    BASES.iter().all(|&base| {
+        let mut x = pow(base, odd);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        (1..rounds).any(|_| {
+            x = mul(x, x);
+            x == n - 1
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fhe_client::FheClient;
+    use crate::types::VoteOption;
+
+    #[test]
+    fn test_demo_parameters_are_not_lattigo_compatible() {
+        let demo = ParametersLiteral::demo();
+        assert_eq!(demo.to_json(), format!(r#"{{"LogN":5,"Q":[{}],"P":[],"PlaintextModulus":65537}}"#, CIPHERTEXT_MODULUS));
+        assert_eq!(
+            check_compatibility(&demo),
+            Err(LattigoError::NotNttFriendly { modulus: CIPHERTEXT_MODULUS, degree: 32 })
+        );
+
+        // 0x3fffffff000001 is a 54-bit prime = 1 mod 2^24
+        let prime_chain = ParametersLiteral { q: vec![0x3fffffff000001], ..demo };
+        assert_eq!(check_compatibility(&prime_chain), Ok(()));
+        assert_eq!(check_compatibility(&ParametersLiteral::for_chain(&ModulusChain::default())), Ok(()));
+    }
+
+    #[test]
+    fn test_ciphertexts_round_trip_through_the_lattigo_layout() -> Result<(), Box<dyn std::error::Error>> {
+        let client = FheClient::new();
+        let fingerprint = client.get_public_key().fingerprint();
+        let ballot = client.encrypt_vote_vector(VoteOption::Option2)?.remove(0);
+        let ciphertext = Cipher::<Signed>::deserialize_bound(&ballot, &fingerprint)?;
+
+        let marshalled = marshal_ciphertext(&ciphertext);
+        assert_eq!(marshalled.len(), 1 + 8 + 2 * (16 + POLYNOMIAL_DEGREE * 8));
+        assert_eq!(marshalled[..25], [[0u8].as_slice(), &2u64.to_le_bytes(), &1u64.to_le_bytes(), &32u64.to_le_bytes()].concat());
+        assert_eq!(unmarshal_ciphertext(&marshalled)?.serialize_bound(&fingerprint), ballot);
+
+        let mut with_metadata = marshalled.clone();
+        with_metadata[0] = 1;
+        assert_eq!(unmarshal_ciphertext(&with_metadata).err(), Some(LattigoError::UnexpectedMetaData));
+        let mut two_levels = marshalled.clone();
+        two_levels[9] = 2;
+        assert_eq!(
+            unmarshal_ciphertext(&two_levels).err(),
+            Some(LattigoError::ShapeMismatch { levels: 2, coefficients: 32 })
+        );
+        let mut three_polys = marshalled.clone();
+        three_polys[1] = 3;
+        assert_eq!(unmarshal_ciphertext(&three_polys).err(), Some(LattigoError::UnexpectedDegree { polys: 3 }));
+        let mut non_canonical = marshalled.clone();
+        non_canonical[25..33].copy_from_slice(&CIPHERTEXT_MODULUS.to_le_bytes());
+        assert_eq!(unmarshal_ciphertext(&non_canonical).err(), Some(LattigoError::NonCanonicalCoefficient { index: 0 }));
+        assert_eq!(unmarshal_ciphertext(&marshalled[..marshalled.len() - 8]).err(), Some(LattigoError::MalformedCiphertext));
+        assert_eq!(unmarshal_ciphertext(&[marshalled, vec![0; 8]].concat()).err(), Some(LattigoError::MalformedCiphertext));
+        Ok(())
+    }
+}
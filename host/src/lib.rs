@@ -0,0 +1,76 @@
+// Host-side library shared by the prover binary and external auditors: client
+// encryption, the input/output types mirrored by the guest, and the election
+// protocol pieces. Each module describes itself in its own header.
+
+// `types`, `journal` and `scheme` name their allocations through `alloc`, so
+// the verifier can compile them without std
+extern crate alloc;
+
+pub mod types;
+pub mod fhe_client;
+pub mod honest_encryption;
+pub mod ballot_set;
+pub mod dkg;
+pub mod ceremony;
+pub mod decryption_shares;
+pub mod mixnet;
+pub mod registrar;
+pub mod registration;
+pub mod election;
+pub mod candidates;
+pub mod address;
+#[path = "../../methods/guest/src/scheme.rs"]
+pub mod scheme;
+#[path = "../../methods/guest/src/dp_noise.rs"]
+pub mod dp_noise;
+#[path = "../../methods/guest/src/noise_distribution.rs"]
+pub mod noise_distribution;
+pub mod setup;
+pub mod census;
+pub mod membership;
+pub mod revocation;
+pub mod hd_keys;
+pub mod qr;
+pub mod wallet;
+pub mod audit_log;
+pub mod intake;
+pub mod access;
+pub mod daemon;
+pub mod job;
+pub mod interim;
+pub mod aggregate;
+pub mod vector_sum;
+pub mod auction;
+pub mod statistics;
+pub mod circuit;
+pub mod noise;
+pub mod modulus_chain;
+pub mod executor;
+pub mod guest_image;
+pub mod cross_check;
+pub mod bundle;
+pub mod snapshot;
+pub mod archive;
+pub mod journal;
+pub mod inspect;
+pub mod onchain;
+pub mod chain_ingest;
+pub mod hooks;
+pub mod progress;
+pub mod timelock;
+pub mod fhe_backend;
+pub mod differential;
+pub mod tally_backend;
+pub mod lattigo;
+pub mod attestation;
+pub mod event_log;
+#[cfg(feature = "sunscreen")]
+pub mod sunscreen_backend;
+#[cfg(feature = "tls")]
+pub mod tls;
+#[cfg(feature = "wide-modulus")]
+pub mod wide;
+#[cfg(feature = "export")]
+pub mod export;
+#[cfg(feature = "graphql")]
+pub mod graphql;
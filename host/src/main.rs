@@ -1,12 +1,78 @@
-use methods::{FHE_VOTING_ELF, FHE_VOTING_ID};
-use risc0_zkvm::{default_prover, ExecutorEnv};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use rand::RngCore;
+use methods::{AGGREGATE_ELF, AGGREGATE_ID, FHE_VOTING_ELF, FHE_VOTING_ID, AUCTION_ELF, AUCTION_ID, CIRCUIT_ELF, CIRCUIT_ID, VECTOR_SUM_ELF, VECTOR_SUM_ID};
+use rayon::prelude::*;
+use risc0_zkvm::sha::{Impl, Sha256};
+use risc0_zkvm::{ExecutorEnv, ProverOpts, Receipt, VerifierContext};
 use sha3::{Digest, Keccak256};
 
-mod types;
-mod fhe_client;
+use host::types::{VoteTallyInput, VoteTallyOutput, EncryptedVote, VoteOption, BallotAuthorization, BallotMix, DpNoise, Eip712Domain, ElectionContext, Electorate, JournalProfile, Outcome, PriorTally, RejectionReason, Turnout};
+use host::fhe_client::{check_tally_capacity, FheClient, FheParameters, PublicKey};
+use host::ballot_set::{ballot_leaf_hash, ballot_message, voter_key, voter_keys, BallotSetCommitment};
+use host::dkg::{DkgConfig, DkgOutput};
+use host::mixnet::{counted_vote_vectors, shuffle, SHUFFLE_PROOF_ROUNDS};
+use host::decryption_shares::{prove_decryption, trustee_decryption, trustees_digest, verify_decryption_proof, ProvenInterimTallies, ShareVerificationKey, TrusteeDecryptor};
+use host::registrar::{CredentialKey, CredentialRequest, Registrar, RegistrarError};
+use host::membership::{prove_membership, Identity};
+use host::audit_log::{verify_accepted_ballots, verify_audit_log, AuditLog};
+use host::journal::{computation_hash, read_aggregate_journal, read_interim_journal, read_auction_journal, read_circuit_journal, read_tally_journal, read_vector_sum_journal, AggregateJournal, AuctionJournal, CircuitJournal, InterimJournal, TallyJournal, VectorSumJournal};
+use host::attestation::{verify_attestation, AttestationBody, Operator};
+use host::ceremony::{check_election_key, run_recorded, verify_transcript, CeremonyTranscript};
+use host::intake::{check_submission, IntakeConfig, SubmissionGate};
+use host::event_log::{EventLog, SecurityEvent};
+use host::access::{ApiKeyStore, Role};
+use host::daemon::{route, BallotBoxes, JobOutcome, JobQueue, Request, MAX_JOB_BODY_BYTES};
+use host::job::RetryPolicy;
+use host::snapshot::{ElectionMetadata, ElectionSnapshot};
+use host::registration::{Registrant, RegistrationService};
+use host::census::Census;
+use host::election::{self, check_election, derive_election_id, election_from_env};
+use host::candidates::{candidates_digest, candidates_from_env, verify_candidates};
+use host::scheme::{TallyScheme, TieBreak};
+use host::dp_noise::{noised, noised_counts, sensitivity};
+use host::address::{normalize_ballot, pseudonymous_address, to_checksum_address};
+use host::interim::{prior_tally_digest, verify_interim_chain, verify_interim_heads, InterimPublisher, InterimSchedule};
+use host::aggregate::{aggregate_input, check_aggregate, split_batches};
+use host::vector_sum::{check_vector_sum, vector_sum_input};
+use host::statistics::{encrypt_response, statistics_input, Statistics};
+use host::auction::{auction_input, check_auction, clear_auction, level_ciphertexts, seal_bid};
+use host::circuit::{check_circuit, evaluate_circuit, CircuitProgram};
+use host::noise::NoiseModel;
+use host::onchain::{parse_address, result_typed_digest};
+use host::chain_ingest::ChainBallotSet;
+use host::hooks::{FileDropHook, FinalizationHooks, FinalizedResult};
+use host::progress::{TallyEvent, TallyEvents};
+use host::revocation::{check_revocation, revoke_with_credential, revoke_with_identity};
+use host::timelock::{squaring_rate, squarings_until, SealedTally, TimeLock};
+use host::executor::{ExecutorConfig, ProvedReceipt, ProvingStats};
+use host::guest_image::GuestImage;
+use host::cross_check::{backends_from_env, cross_check};
+#[cfg(feature = "webhooks")]
+use host::hooks::WebhookHook;
+#[cfg(feature = "export")]
+use host::export::{ElectionTables, ExportFormat};
+use host::archive::{DecryptionTranscript, ElectionArchive, SchemeParameters};
+#[cfg(feature = "ethereum")]
+use host::onchain::{encode_seal, EthereumClient};
+#[cfg(feature = "ethereum")]
+use host::chain_ingest::{BallotIngestor, IngestError, RpcChainSource, DEFAULT_CONFIRMATIONS};
+#[cfg(feature = "ethereum")]
+use host::hooks::ContractCallHook;
+use signal_hook::consts::{SIGINT, SIGTERM};
+
+// The tally guest: the embedded image unless FHE_VOTING_GUEST_ELF names another (see host/src/guest_image.rs)
+static TALLY_GUEST: OnceLock<GuestImage> = OnceLock::new();
 
-use types::{VoteTallyInput, VoteTallyOutput, EncryptedVote, VoteOption};
-use fhe_client::FheClient;
+fn tally_guest() -> &'static GuestImage {
+    TALLY_GUEST.get_or_init(|| GuestImage::embedded(FHE_VOTING_ELF, FHE_VOTING_ID))
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("🚀 RISC Zero + FHE Voting Proof of Concept");
@@ -17,9 +83,73 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with_env_filter(tracing_subscriber::filter::EnvFilter::from_default_env())
         .init();
 
+    // Security events are signed by the operator key
+    let operator = load_operator()?;
+    // Hooks are configured up front so a bad setting fails before any proving
+    let hooks = load_hooks()?;
+    let progress = load_progress()?;
+    // So are the executor limits and prover backend (FHE_VOTING_SEGMENT_PO2, FHE_VOTING_SESSION_LIMIT,
+    // FHE_VOTING_PROVER_MEMORY_MB, FHE_VOTING_PROVER; see host/src/executor.rs)
+    let executor = ExecutorConfig::from_env()?;
+    if let Some(po2) = executor.segment_po2()? {
+        println!("⚙️  [Host] Proving in segments of 2^{} cycles", po2);
+    }
+    if let Some(backend) = executor.prover_backend {
+        println!("⚙️  [Host] Proving on the {} backend", backend);
+    }
+    // And the tally guest, when FHE_VOTING_GUEST_ELF names a program other than the embedded one
+    let guest = GuestImage::from_env(GuestImage::embedded(FHE_VOTING_ELF, FHE_VOTING_ID))?;
+    if !guest.is_embedded() {
+        println!("🧪 [Host] Tally guest loaded from FHE_VOTING_GUEST_ELF, image id {}", risc0_zkvm::sha::Digest::from(guest.image_id()));
+    }
+    let _ = TALLY_GUEST.set(guest);
+    
+    // FHE_VOTING_DAEMON=<address> runs the long-lived tally service instead of the demo
+    if let Ok(address) = std::env::var("FHE_VOTING_DAEMON") {
+        return run_daemon(&address, open_event_log(operator)?, hooks, progress, executor);
+    }
+    
+    let mut events = open_event_log(operator.clone())?;
+    if let Ok(path) = std::env::var("FHE_VOTING_RESUME") {
+        return resume_election(&path, &mut events, &progress, &executor);
+    }
+    
     // Create test voting data
     println!("📝 [Host] Creating test voting data...");
-    let vote_input = create_test_votes();
+    // Trustees jointly generate the election key; nobody holds the full secret key
+    println!("🔑 [Host] Running 2-of-3 trustee DKG for the election key...");
+    let dkg_config = DkgConfig::threshold(2, 3)?;
+    let (election_public_key, trustee_outputs, ceremony) = run_recorded(dkg_config, &operator)?;
+    println!("✅ [Host] Election key established by {} trustees", trustee_outputs.len());
+    export_ceremony(&ceremony)?;
+    // Every trustee publishes a verification key for its key share; the guest checks the
+    // shares of the final tallies against them, and only `threshold` trustees take part
+    let (trustees, verification_keys): (Vec<_>, Vec<_>) = trustee_outputs
+        .iter()
+        .map(|output| TrusteeDecryptor::new(output.key_share.clone()))
+        .unzip();
+    let participating = &trustees[..dkg_config.threshold as usize];
+    events.record(SecurityEvent::ElectionCreated {
+        election_key_fingerprint: election_public_key.fingerprint(),
+        threshold: dkg_config.threshold,
+        trustees: trustee_outputs.len() as u32,
+    })?;
+    // Set FHE_VOTING_PUBLISH_KEY=<path> to publish the election key for voters encrypting with `vote`
+    if let Ok(path) = std::env::var("FHE_VOTING_PUBLISH_KEY") {
+        std::fs::write(&path, serde_json::to_vec(&election_public_key)?)?;
+        println!("🔑 [Host] Election key {} published to {}", hex::encode(election_public_key.fingerprint()), path);
+    }
+    
+    let mut vote_input = create_test_votes(election_public_key, &mut events, &progress, &executor)?;
+    
+    // Every submitted ballot is an upper bound on the ballots the guest will count
+    check_tally_capacity(vote_input.encrypted_votes.len())?;
+    
+    // FHE_VOTING_MIXNET=1 shuffles and re-encrypts the vote vectors before tallying, so the order
+    // the guest adds them in no longer follows submission; the guest checks the shuffle proof
+    if std::env::var("FHE_VOTING_MIXNET").is_ok_and(|v| v == "1") {
+        mix_ballots(&mut vote_input)?;
+    }
     
     println!("📊 [Host] Processing {} encrypted vote vectors:", vote_input.encrypted_votes.len());
     for vote in &vote_input.encrypted_votes {
@@ -27,36 +157,111 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("    [Verification only - actual choice: {}]", vote.actual_choice.description());
     }
     
+    // The state so far, so another machine can take over the proof
+    export_snapshot(&vote_input, dkg_config, Vec::new())?;
+    
     // Create executor environment with vote data
     println!("\n🔮 [Host] Starting RISC Zero proof generation...");
-    let env = ExecutorEnv::builder()
-        .write(&vote_input)?
-        .build()?;
+    events.record(SecurityEvent::TallyStarted {
+        ballots: vote_input.encrypted_votes.len() as u32,
+        ballot_set_root: vote_input.ballot_set_root,
+    })?;
+    // Compact journals send the encrypted tally frames on the guest's stdout
+    let mut detached_frames = Vec::new();
+    // The final tally continues the last published interim tally, verified inside the guest
+    let prior_interim = latest_interim()?;
+    if let Some(receipt) = &prior_interim {
+        vote_input.prior_tally = Some(PriorTally { image_id: tally_guest().image_id(), journal: receipt.journal.bytes.clone() });
+    }
+    share_final_tallies(&mut vote_input, prior_interim.as_slice(), participating, &verification_keys, dkg_config.threshold, &executor)?;
+    cross_check_backends(&vote_input, prior_interim.as_slice(), &executor)?;
+    let env = {
+        let mut builder = ExecutorEnv::builder();
+        executor.apply(&mut builder)?;
+        if let Some(receipt) = prior_interim {
+            builder.add_assumption(receipt);
+        }
+        builder
+            .write(&vote_input)?
+            .stdout(&mut detached_frames)
+            .build()?
+    };
 
     // Get the prover and generate proof
-    let prover = default_prover();
-    let prove_info = prover.prove(env, FHE_VOTING_ELF)?;
-    let receipt = prove_info.receipt;
+    // On-chain verification needs a Groth16 receipt (a fake one in dev mode)
+    let opts = if std::env::var("FHE_VOTING_ETH_RPC").is_ok() { ProverOpts::groth16() } else { ProverOpts::default() };
+    let ProvedReceipt { receipt, stats } = executor.prove(env, tally_guest().elf(), &opts)?;
     
     println!("✅ [Host] Cryptographic proof generated!");
+    print_proving_stats(&stats);
     
     // Verify the proof
-    receipt.verify(FHE_VOTING_ID)?;
+    receipt.verify(tally_guest().image_id())?;
     println!("🎯 [Host] Proof verified successfully!");
+    progress.emit(TallyEvent::receipt_verified(tally_guest().image_id(), &receipt.journal.bytes));
+    events.record(SecurityEvent::ReceiptProduced {
+        image_id: risc0_zkvm::sha::Digest::from(tally_guest().image_id()).into(),
+        journal_digest: (*Impl::hash_bytes(&receipt.journal.bytes)).into(),
+    })?;
     
     // Extract the proven results
-    let result: VoteTallyOutput = receipt.journal.decode()?;
+    let TallyJournal { output: result, encrypted_tallies } = read_tally_journal(vote_input.journal_profile, &receipt.journal.bytes, &detached_frames)?;
+    if seal_results(&receipt, &result)? {
+        return Ok(());
+    }
     
     println!("\n🏆 PROVEN ELECTION RESULTS");
     println!("=========================");
-    println!("📊 {}: {} votes", VoteOption::Option1.description(), result.option1_count);
-    println!("📊 {}: {} votes", VoteOption::Option2.description(), result.option2_count);
-    println!("📊 {}: {} votes", VoteOption::Option3.description(), result.option3_count);
+    // Labelled with the candidate list the proof commits to
+    verify_candidates(&vote_input.candidates, &result.election.candidates_digest)?;
+    if result.counts_withheld() {
+        println!("🙈 Counts withheld: fewer ballots than the anonymity set of {}", vote_input.min_anonymity_set);
+    } else {
+        for (candidate, count) in vote_input.candidates.iter().zip([result.option1_count, result.option2_count, result.option3_count]) {
+            println!("📊 {}: {} votes", candidate.label, count);
+        }
+        // The proven outcome, not a reading of the counts
+        let leading: Vec<&str> = (0..vote_input.candidates.len())
+            .filter(|index| result.outcome.leading & (1 << index) != 0)
+            .map(|index| vote_input.candidates[index].label.as_str())
+            .collect();
+        match result.outcome.winner() {
+            Some(winner) if leading.len() > 1 => println!("🏆 Winner: {} (tie between {} settled by {:?})",
+                                                           vote_input.candidates[winner as usize - 1].label, leading.join(", "), result.outcome.tie_break),
+            Some(winner) => println!("🏆 Winner: {}", vote_input.candidates[winner as usize - 1].label),
+            None if leading.len() > 1 => println!("⚖️  Declared tie between {}", leading.join(", ")),
+            None => println!("🏆 No winner"),
+        }
+    }
     println!("📈 Total votes: {}", result.total_votes);
+    println!("🔁 Superseded ballots: {}", result.superseded_ballots);
+    println!("📜 Audit log head: {}", hex::encode(result.audit_log_head));
     println!("🔍 Computation hash: {}", result.computation_hash);
+    println!("🌳 Ballot set root: {}", hex::encode(result.ballot_set_root));
+    println!("🪪 Identity roster root: {}", hex::encode(result.identity_root));
+    
+    // The ballots are under the key the recorded ceremony produced
+    check_election_key(&verify_transcript(&ceremony)?, &vote_input.election_public_key.fingerprint())?;
+    println!("🕯️  [Host] Key ceremony transcript verified against the election key");
     
     // Verify the results are correct
     verify_results(&vote_input, &result)?;
+    verify_ballot_inclusion(&vote_input, &result)?;
+    verify_audit_trail(&vote_input, &result)?;
+    verify_interim_tallies(&vote_input, &result)?;
+    prove_aggregate(&vote_input, &result, participating, &verification_keys, dkg_config.threshold, &progress, &executor)?;
+    prove_vector_sum(&vote_input.election_public_key, &trustee_outputs, dkg_config.threshold, &executor)?;
+    prove_auction(&vote_input.election_public_key, &trustee_outputs, dkg_config.threshold, &executor)?;
+    prove_statistics(&vote_input.election_public_key, &trustee_outputs, dkg_config.threshold, &executor)?;
+    prove_circuit(&vote_input.election_public_key, &trustee_outputs, dkg_config.threshold, &executor)?;
+    let decryption = decrypt_with_trustees(&vote_input, participating, &verification_keys, dkg_config.threshold, &result, &encrypted_tallies)?;
+    export_attestation(&operator, &receipt.journal.bytes, &result)?;
+    export_snapshot(&vote_input, dkg_config, vec![bincode::serialize(&receipt)?])?;
+    export_archive(&vote_input, &receipt, decryption)?;
+    export_tables(&vote_input, &result)?;
+    post_results(&receipt, &result)?;
+    run_hooks(&hooks, &receipt.journal.bytes, &result, stats);
+    println!("🗒️  [Host] {} security events recorded, head {}", events.records().len(), hex::encode(events.head()));
     
     println!("\n🎉 SUCCESS: TRUSTLESS FHE VOTING ACHIEVED!");
     println!("===========================================");
@@ -76,8 +281,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn create_test_votes() -> VoteTallyInput {
-    let voter_data = vec![
+fn create_test_votes(election_public_key: PublicKey, events: &mut EventLog, progress: &TallyEvents, executor: &ExecutorConfig) -> Result<VoteTallyInput, Box<dyn std::error::Error>> {
+    let voter_data = [
         ("alice", VoteOption::Option1),
         ("bob", VoteOption::Option2),
         ("charlie", VoteOption::Option1),
@@ -87,10 +292,38 @@ fn create_test_votes() -> VoteTallyInput {
         ("grace", VoteOption::Option2),
     ];
     
-    // Initialize FHE client for real encryption
-    let fhe_client = FheClient::new();
+    // Initialize FHE client for real encryption under the combined election key
+    let fhe_client = FheClient::with_public_key(election_public_key.clone());
     
-    let encrypted_votes = voter_data.into_iter().map(|(name, option)| {
+    // The registrar knows the electoral roll, but never sees the credentials it signs
+    let mut registrar = Registrar::new(voter_data.iter().map(|(name, _)| generate_eth_address(name)));
+    let registrar_public_key = registrar.public_key();
+    
+    // Set FHE_VOTING_EIP712_DOMAIN=<chain id>:<contract address> to commit the result's EIP-712 digest
+    let eip712_domain = match std::env::var("FHE_VOTING_EIP712_DOMAIN") {
+        Ok(domain) => {
+            let (chain_id, contract) = domain.split_once(':').ok_or("FHE_VOTING_EIP712_DOMAIN must be <chain id>:<contract address>")?;
+            Some(Eip712Domain { chain_id: chain_id.trim().parse()?, verifying_contract: parse_address(contract.trim())? })
+        }
+        Err(_) => None,
+    };
+    // Set FHE_VOTING_CANDIDATES=<label>|<label>|<label> to name the options; the proof binds their digest
+    let candidates = candidates_from_env()?;
+    // Set FHE_VOTING_ELECTION_ID, FHE_VOTING_CHAIN_ID, FHE_VOTING_CLOSES_AT or FHE_VOTING_TIE_BREAK to name the election the tally is for
+    let election = election_from_env(ElectionContext {
+        election_id: derive_election_id(&election_public_key.fingerprint(), &registrar_public_key),
+        chain_id: eip712_domain.map_or(0, |domain| domain.chain_id),
+        closes_at: election::now(),
+        candidates_digest: candidates_digest(&candidates),
+        tie_break: TieBreak::default(),
+        scheme_digest: TallyScheme::default().digest().into(),
+    })?;
+    println!("🗳️  [Host] Election {} on chain {}, ballot box closes at {}", election.election_id, election.chain_id, election.closes_at);
+    
+    // Alternative to registrar credentials: set FHE_VOTING_AUTH=membership to have
+    // every voter register an identity commitment and prove roster membership
+    let anonymous_membership = std::env::var("FHE_VOTING_AUTH").is_ok_and(|v| v == "membership");
+    let voters: Vec<VoterKey> = voter_data.iter().map(|(name, _)| {
         // Input validation
         if name.is_empty() {
             panic!("Invalid voter name: cannot be empty");
@@ -99,36 +332,291 @@ fn create_test_votes() -> VoteTallyInput {
             panic!("Invalid voter name: too long ({}), max 50 characters", name.len());
         }
         
-        let voter_address = generate_eth_address(name);
-        let signature = create_signature(&voter_address, &option);
-        
-        // REAL FHE ENCRYPTION: No simulation!
-        // Each client encrypts their vote vector with real FHE
-        println!("🗳️ [Host] {} is encrypting their vote with real FHE...", name);
-        let encrypted_vote_vector = match fhe_client.encrypt_vote_vector(option) {
-            Ok(encrypted) => encrypted,
+        if anonymous_membership {
+            return VoterKey::Identity(Identity::generate());
+        }
+        match register_voter(&mut registrar, &generate_eth_address(name)) {
+            Ok(credential_key) => VoterKey::Credential(credential_key),
             Err(e) => {
-                eprintln!("❌ [Host] Failed to encrypt vote vector for {}: {:?}", name, e);
-                panic!("Critical FHE encryption error: Cannot encrypt vote");
+                eprintln!("❌ [Host] Registration failed for {}: {}", name, e);
+                panic!("Critical registration error: Cannot obtain voter credential");
             }
+        }
+    }).collect();
+    
+    // Voters enter the registry until registration closes; the frozen root is published
+    let mut registration = RegistrationService::new();
+    for ((name, _), voter) in voter_data.iter().zip(&voters) {
+        registration.register(match voter {
+            VoterKey::Credential(_) => Registrant::Address(parse_address(&generate_eth_address(name))?),
+            VoterKey::Identity(identity) => Registrant::Identity(identity.commitment()),
+        })?;
+    }
+    let registry_root = registration.close()?;
+    println!("📇 [Host] Registration closed: {} voters, registry root {}", registration.len(), hex::encode(registry_root));
+    // Each voter checks their inclusion proof against the published root before casting a ballot
+    for registrant in registration.registrants() {
+        if !registration.inclusion_proof(registrant)?.verify(&registry_root) {
+            return Err("Registry inclusion proof failed to verify".into());
+        }
+    }
+    let identity_roster = registration.identity_roster();
+    export_census(&registration)?;
+    for voter in &voters {
+        let key = match voter {
+            VoterKey::Credential(credential_key) => credential_key.credential().token,
+            VoterKey::Identity(identity) => identity.commitment(),
         };
+        events.record(SecurityEvent::KeyRegistered { key })?;
+    }
+    
+    // Re-voting: these voters change their mind before close; only their latest ballot counts
+    let revotes = [("eve", VoteOption::Option3)];
+    
+    let ballots = voter_data.iter().enumerate().map(|(voter_index, &(name, option))| (voter_index, name, option, 0))
+        .chain(revotes.into_iter().map(|(name, option)| {
+            let voter_index = voter_data.iter().position(|&(voter, _)| voter == name)
+                .unwrap_or_else(|| panic!("Re-vote from unknown voter {}", name));
+            (voter_index, name, option, 1)
+        }))
+        .collect::<Vec<_>>();
+    
+    // REAL FHE ENCRYPTION: No simulation!
+    // Each client encrypts their vote vector with real FHE; ballots are prepared across all cores
+    println!("🗳️ [Host] Encrypting {} vote vectors with real FHE on {} threads...",
+             ballots.len(), rayon::current_num_threads());
+    let vote_choices: Vec<VoteOption> = ballots.iter().map(|&(_, _, option, _)| option).collect();
+    let encrypted_vote_vectors = match fhe_client.encrypt_vote_vectors(&vote_choices) {
+        Ok(encrypted) => encrypted,
+        Err(e) => {
+            eprintln!("❌ [Host] Failed to encrypt vote vectors: {:?}", e);
+            panic!("Critical FHE encryption error: Cannot encrypt vote");
+        }
+    };
+    
+    let mut submissions = ballots.into_par_iter().zip(encrypted_vote_vectors).map(|((voter_index, name, option, sequence), encrypted_vote_vector)| {
+        // The ballot is identified only by its anonymous voter key: the credential's token or
+        // the identity's nullifier in this election
+        let voter_address = pseudonymous_address(&match &voters[voter_index] {
+            VoterKey::Credential(credential_key) => credential_key.credential().token,
+            VoterKey::Identity(identity) => identity.nullifier(&identity_roster, &election.election_id),
+        });
+        
+        // Authorizations are bound to the election, the voter address, a fresh nonce, the ciphertexts
+        // and the sequence, so they cannot be replayed here, under another voter or in another election
+        let nonce: [u8; 32] = rand::random();
+        let message = ballot_message(&election.election_id, &voter_address, &nonce, &encrypted_vote_vector, sequence);
+        let authorization = match &voters[voter_index] {
+            VoterKey::Credential(credential_key) => BallotAuthorization::Credential {
+                credential: credential_key.credential().clone(),
+                ballot_signature: credential_key.sign_ballot(&message),
+            },
+            VoterKey::Identity(identity) => match prove_membership(identity, &identity_roster, &election.election_id, &message) {
+                Ok(proof) => BallotAuthorization::Membership(proof),
+                Err(e) => {
+                    eprintln!("❌ [Host] Membership proof failed for {}: {}", name, e);
+                    panic!("Critical registration error: Cannot prove roster membership");
+                }
+            },
+        };
+        
+        let signature = create_signature(&voter_address, &option);
         
         EncryptedVote {
             voter_address,
+            election_id: election.election_id,
+            nonce,
             encrypted_vote_vector,
             signature,
+            authorization,
+            sequence,
             actual_choice: option, // Only for demo verification - removed in production
         }
-    }).collect();
+    }).collect::<Vec<_>>();
+    
+    // On-chain elections tally the ballots cast in the ballot box up to the close block
+    let chain_anchor = match ingest_chain_ballots()? {
+        Some((chain_ballots, anchor)) => {
+            println!("⛓️  [Host] {} ballots read on-chain as of close block {} ({})",
+                     chain_ballots.len(), anchor.close_block, hex::encode(anchor.block_hash));
+            submissions = chain_ballots;
+            Some(anchor)
+        }
+        None => None,
+    };
+    
+    // Abuse protection: size caps up front, then per-source and per-voter rate limits and the
+    // ballot quota for authenticated ballots (set FHE_VOTING_BALLOT_QUOTA=<n> to override the default)
+    let mut intake_config = IntakeConfig::default();
+    if let Some(quota) = std::env::var("FHE_VOTING_BALLOT_QUOTA").ok().and_then(|v| v.parse().ok()) {
+        intake_config.ballot_quota = quota;
+    }
+    let mut gate = SubmissionGate::new(intake_config);
+    let mut interim = open_interim()?;
+    let source = IpAddr::V4(Ipv4Addr::LOCALHOST); // Demo submissions all arrive locally
+    // Addresses are checksum-normalized before rate limits and duplicate checks key on them
+    let normalized: Vec<_> = submissions.iter_mut().map(|submission| normalize_ballot(submission).map_err(|e| e.to_string())).collect();
+    
+    // Every submission is logged; only those passing the checks enter the ballot set
+    let mut audit_log = AuditLog::new();
+    let mut encrypted_votes = Vec::new();
+    let checks: Vec<_> = submissions.par_iter().zip(normalized)
+        .map(|(submission, normalized)| {
+            normalized?;
+            gate.check_size(submission).map_err(|e| e.to_string())?;
+            check_submission(submission, &election, &registrar_public_key, &identity_roster)
+        })
+        .collect();
+    for (submission, check) in submissions.into_iter().zip(checks) {
+        let check = check.and_then(|()| gate.admit(source, &submission, Instant::now()).map_err(|e| e.to_string()));
+        match check {
+            Ok(()) => {
+                events.record(SecurityEvent::BallotAccepted { ballot_hash: ballot_leaf_hash(&submission) })?;
+                progress.emit(TallyEvent::BallotValidated { voter_address: submission.voter_address.clone(), ballot_hash: ballot_leaf_hash(&submission) });
+                audit_log.record_accepted(&submission);
+                encrypted_votes.push(submission);
+            }
+            Err(reason) => {
+                println!("🚫 [Host] Rejected submission {}: {}", submission.voter_address, reason);
+                events.record(SecurityEvent::BallotRejected { ballot_hash: ballot_leaf_hash(&submission), reason: reason.clone() })?;
+                progress.emit(TallyEvent::BallotRejected { voter_address: submission.voter_address.clone(), ballot_hash: ballot_leaf_hash(&submission), reason: reason.clone() });
+                audit_log.record_rejected(&submission, reason);
+            }
+        }
+        if let Some((schedule, publisher)) = &mut interim {
+            if schedule.due(Instant::now()) {
+                prove_interim(publisher, progress, executor, VoteTallyInput {
+                    ballot_set_root: BallotSetCommitment::from_ballots(&encrypted_votes).root(),
+                    encrypted_votes: encrypted_votes.clone(),
+                    election_public_key: election_public_key.clone(),
+                    registrar_public_key,
+                    identity_roster: identity_roster.clone(),
+                    audit_log_head: audit_log.head(),
+                    audit_log: audit_log.records().to_vec(),
+                    chain_anchor: None,
+                    journal_profile: JournalProfile::Full,
+                    interim: true,
+                    prior_tally: None,
+                    eip712_domain: None,
+                    election,
+                    candidates: candidates.clone(),
+                    scheme: TallyScheme::default(),
+                    min_anonymity_set: 0, // Interim tallies reveal no counts
+                    dp_noise: None,
+                    revocations: Vec::new(), // Revocations are applied to the final tally only
+                    electorate: None,
+                    decryption: None,
+                    mix: None,
+                })?;
+            }
+        }
+    }
+    
+    // FHE_VOTING_REVOKE=<voter>[,<voter>...]: those voters take back their counted ballot before close
+    let mut revocations = Vec::new();
+    if let Ok(names) = std::env::var("FHE_VOTING_REVOKE") {
+        let mut revoked = Vec::new();
+        for name in names.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            let voter_index = voter_data.iter().position(|&(voter, _)| voter == name)
+                .ok_or_else(|| format!("FHE_VOTING_REVOKE names unknown voter {}", name))?;
+            // The voter finds their counted ballot by their own key: credential token or nullifier
+            let key = match &voters[voter_index] {
+                VoterKey::Credential(credential_key) => credential_key.credential().token,
+                VoterKey::Identity(identity) => identity.nullifier(&identity_roster, &election.election_id),
+            };
+            let ballot = encrypted_votes.iter().filter(|ballot| voter_key(&ballot.authorization) == key).max_by_key(|ballot| ballot.sequence)
+                .ok_or_else(|| format!("{} has no counted ballot to revoke", name))?;
+            let tracking_code = ballot_leaf_hash(ballot);
+            let revocation = match &voters[voter_index] {
+                VoterKey::Credential(credential_key) => revoke_with_credential(credential_key, &election.election_id, tracking_code),
+                VoterKey::Identity(identity) => revoke_with_identity(identity, &identity_roster, &election.election_id, tracking_code)?,
+            };
+            check_revocation(&revocation, &encrypted_votes, &revoked, &registrar_public_key, &identity_roster, &election.election_id)?;
+            println!("↩️  [Host] {} revoked ballot {}", name, hex::encode(tracking_code));
+            audit_log.record_revoked(tracking_code);
+            revoked.push(tracking_code);
+            revocations.push(revocation);
+        }
+    }
+    println!("📜 [Host] Audit log: {} submissions, {} accepted, {} revoked", audit_log.len(), encrypted_votes.len(), revocations.len());
+    
+    // Commit to the ballot set so the guest can bind its proof to it
+    let ballot_set_root = BallotSetCommitment::from_ballots(&encrypted_votes).root();
+    
+    // FHE_VOTING_JOURNAL=compact commits the fixed-width journal the contract path prefers
+    let journal_profile = match std::env::var("FHE_VOTING_JOURNAL").as_deref() {
+        Ok("compact") => JournalProfile::Compact,
+        Ok("full") | Err(_) => JournalProfile::Full,
+        Ok(other) => return Err(format!("FHE_VOTING_JOURNAL must be full or compact, got {}", other).into()),
+    };
+    // import-and-verify reads the encrypted tallies from the journal itself
+    if journal_profile == JournalProfile::Compact && std::env::var("FHE_VOTING_ARCHIVE").is_ok() {
+        return Err("FHE_VOTING_ARCHIVE needs the full journal profile".into());
+    }
+    // The contract reads the election id and chain id at fixed offsets of the compact journal
+    if journal_profile == JournalProfile::Full && std::env::var("FHE_VOTING_ETH_CONTRACT").is_ok() {
+        return Err("FHE_VOTING_ETH_CONTRACT needs FHE_VOTING_JOURNAL=compact".into());
+    }
+    // FHE_VOTING_MIN_ANONYMITY_SET=<ballots>: the guest reveals only the turnout below that
+    let min_anonymity_set = std::env::var("FHE_VOTING_MIN_ANONYMITY_SET").ok().map(|v| v.parse()).transpose()?.unwrap_or(0);
+    // FHE_VOTING_DP_EPSILON=<epsilon> publishes noisy counts; the seed stays with the operator
+    let dp_noise = match std::env::var("FHE_VOTING_DP_EPSILON") {
+        Ok(epsilon) => {
+            let epsilon: f64 = epsilon.parse()?;
+            if !epsilon.is_finite() || epsilon <= 0.0 {
+                return Err(format!("FHE_VOTING_DP_EPSILON must be positive, got {}", epsilon).into());
+            }
+            let mut seed = [0u8; 32];
+            rand::rngs::OsRng.fill_bytes(&mut seed);
+            Some(DpNoise { epsilon_milli: (epsilon * 1000.0).round().max(1.0) as u32, seed })
+        }
+        Err(_) => None,
+    };
     
-    VoteTallyInput { encrypted_votes }
+    Ok(VoteTallyInput {
+        encrypted_votes,
+        ballot_set_root,
+        election_public_key,
+        registrar_public_key,
+        identity_roster,
+        audit_log_head: audit_log.head(),
+        audit_log: audit_log.records().to_vec(),
+        chain_anchor,
+        journal_profile,
+        interim: false,
+        prior_tally: None,
+        eip712_domain,
+        election,
+        candidates,
+        scheme: TallyScheme::default(),
+        min_anonymity_set,
+        dp_noise,
+        revocations,
+        electorate: Some(Electorate { registered_voters: registration.len() as u32, registry_root }),
+        decryption: None,
+        mix: None,
+    })
+}
+
+// What a voter holds to authorize ballots
+enum VoterKey {
+    Credential(CredentialKey),
+    Identity(Identity),
+}
+
+// Blind issuance: the registrar checks eligibility, the voter unblinds the signature
+fn register_voter(registrar: &mut Registrar, voter_address: &str) -> Result<CredentialKey, RegistrarError> {
+    let (session, nonce_commitment) = registrar.open_session(voter_address)?;
+    let (request, blinded_challenge) = CredentialRequest::new(&registrar.public_key(), &nonce_commitment)?;
+    let blinded_response = registrar.sign_blinded(session, blinded_challenge)?;
+    request.unblind(blinded_response)
 }
 
 fn generate_eth_address(seed: &str) -> String {
     let mut hasher = Keccak256::new();
     hasher.update(seed.as_bytes());
     let result = hasher.finalize();
-    format!("0x{}", hex::encode(&result[..20]))
+    to_checksum_address(&result[..20].try_into().unwrap())
 }
 
 fn create_signature(voter_address: &str, vote_option: &VoteOption) -> String {
@@ -151,7 +639,75 @@ fn verify_results(input: &VoteTallyInput, output: &VoteTallyOutput) -> Result<()
     let mut option2_count = 0;
     let mut option3_count = 0;
     
+    // Only each voter's latest ballot counts
+    let mut latest: HashMap<[u8; 32], &EncryptedVote> = HashMap::new();
     for vote in &input.encrypted_votes {
+        let entry = latest.entry(voter_key(&vote.authorization)).or_insert(vote);
+        if vote.sequence > entry.sequence {
+            *entry = vote;
+        }
+    }
+    let superseded_ballots = (input.encrypted_votes.len() - latest.len()) as u32;
+    if output.superseded_ballots != superseded_ballots {
+        return Err(format!("Superseded ballot mismatch: expected {}, got {}", superseded_ballots, output.superseded_ballots));
+    }
+    // The exclusion totals must add up to the per-reason counts, and the host only submits
+    // ballots it has checked, so the guest should have excluded none of them
+    let excluded = |matches: fn(RejectionReason) -> bool| -> u32 {
+        RejectionReason::ALL.into_iter().filter(|&reason| matches(reason)).map(|reason| output.rejections_by_reason[reason as usize]).sum()
+    };
+    if output.rejected_count != output.rejections_by_reason.iter().sum::<u32>()
+        || output.duplicate_count != excluded(RejectionReason::is_duplicate)
+        || output.invalid_signature_count != excluded(RejectionReason::is_invalid_signature)
+    {
+        return Err(format!("Exclusion totals do not match the reason codes {:?}", output.rejections_by_reason));
+    }
+    if output.rejected_count != 0 {
+        let reasons: Vec<String> = RejectionReason::ALL
+            .iter()
+            .filter(|&&reason| output.rejections_by_reason[reason as usize] != 0)
+            .map(|&reason| format!("{:?} x{}", reason, output.rejections_by_reason[reason as usize]))
+            .collect();
+        return Err(format!("Guest excluded {} checked ballots: {}", output.rejected_count, reasons.join(", ")));
+    }
+    
+    // A revoked ballot was subtracted again before decryption
+    let revoked: Vec<[u8; 32]> = input.revocations.iter().map(|revocation| revocation.tracking_code).collect();
+    latest.retain(|_, vote| !revoked.contains(&ballot_leaf_hash(vote)));
+    
+    // Below the anonymity set the guest commits the turnout alone, and must not commit counts above it
+    let tallied = latest.len() as u32;
+    
+    // Turnout is reported against the registered voters, with every submission accounted for
+    let electorate = input.electorate.unwrap_or_default();
+    let revoked_ballots = input.revocations.len() as u32;
+    let turnout = Turnout {
+        electorate,
+        submissions: input.audit_log.len() as u32 - revoked_ballots,
+        rejected_submissions: input.audit_log.iter().filter(|record| !record.accepted).count() as u32 - revoked_ballots,
+        revoked_ballots,
+        ballots_tallied: tallied,
+        turnout_basis_points: electorate.turnout_basis_points(tallied),
+    };
+    if output.turnout != turnout {
+        return Err(format!("Turnout mismatch: expected {:?}, got {:?}", turnout, output.turnout));
+    }
+    if input.electorate.is_some() {
+        println!("🗳️  [Host] Turnout of {} registered voters: {}.{:02}%", electorate.registered_voters, turnout.turnout_basis_points / 100, turnout.turnout_basis_points % 100);
+    }
+    if output.counts_withheld() != (tallied < input.min_anonymity_set) {
+        return Err(format!("Anonymity set of {} applied wrongly to {} ballots", input.min_anonymity_set, tallied));
+    }
+    if output.counts_withheld() {
+        if output.total_votes != tallied || output.option1_count + output.option2_count + output.option3_count != 0 || output.outcome.winner != 0 {
+            return Err(format!("Withheld result must give the turnout {} and no counts or winner", tallied));
+        }
+        check_election(&input.election, &output.election).map_err(|e| e.to_string())?;
+        println!("✅ [Host] Turnout of {} verified; counts withheld below the anonymity set of {}", tallied, input.min_anonymity_set);
+        return Ok(());
+    }
+    
+    for vote in latest.values() {
         // Use actual_choice for verification (in production this wouldn't exist)
         match vote.actual_choice {
             VoteOption::Option1 => option1_count += 1,
@@ -160,6 +716,12 @@ fn verify_results(input: &VoteTallyInput, output: &VoteTallyOutput) -> Result<()
         }
     }
     
+    // The guest publishes the counts with the noise the input's seed gives, and their sum as the total
+    if let Some(config) = &input.dp_noise {
+        [option1_count, option2_count, option3_count] = noised_counts(config, sensitivity(&input.scheme), &output.ballot_set_root, [option1_count, option2_count, option3_count]);
+    }
+    let total_votes = option1_count + option2_count + option3_count;
+    
     // Verify counts match
     if output.option1_count != option1_count {
         return Err(format!("Option1 count mismatch: expected {}, got {}", option1_count, output.option1_count));
@@ -170,9 +732,30 @@ fn verify_results(input: &VoteTallyInput, output: &VoteTallyOutput) -> Result<()
     if output.option3_count != option3_count {
         return Err(format!("Option3 count mismatch: expected {}, got {}", option3_count, output.option3_count));
     }
-    if output.total_votes != (option1_count + option2_count + option3_count) {
-        return Err(format!("Total count mismatch: expected {}, got {}", 
-                          option1_count + option2_count + option3_count, output.total_votes));
+    if output.total_votes != total_votes {
+        return Err(format!("Total count mismatch: expected {}, got {}", total_votes, output.total_votes));
+    }
+    // The committed winner follows from the published counts under the input's tie-break
+    let outcome = Outcome::decide(&input.scheme, &[option1_count, option2_count, option3_count], input.election.tie_break);
+    if output.outcome != outcome {
+        return Err(format!("Outcome mismatch: expected {:?}, got {:?}", outcome, output.outcome));
+    }
+    
+    // The receipt must be for this election and no other
+    check_election(&input.election, &output.election).map_err(|e| e.to_string())?;
+    
+    // Compact journals leave the computation hash out
+    if input.journal_profile == JournalProfile::Full && output.computation_hash != computation_hash(option1_count, option2_count, option3_count) {
+        return Err(format!("Computation hash mismatch: got {}", output.computation_hash));
+    }
+    
+    // The committed typed digest must be the one a contract recomputes from these counts
+    let expected_digest = input.eip712_domain.map_or([0u8; 32], |domain| result_typed_digest(&domain, output));
+    if output.result_typed_digest != expected_digest {
+        return Err("EIP-712 result digest does not match the proven counts and domain".to_string());
+    }
+    if input.eip712_domain.is_some() {
+        println!("✍️  [Host] EIP-712 result digest: 0x{}", hex::encode(output.result_typed_digest));
     }
     
     println!("✅ [Host] All vote counts verified correctly!");
@@ -180,3 +763,910 @@ fn verify_results(input: &VoteTallyInput, output: &VoteTallyOutput) -> Result<()
     
     Ok(())
 }
+
+fn verify_ballot_inclusion(input: &VoteTallyInput, output: &VoteTallyOutput) -> Result<(), String> {
+    println!("\n🌳 [Host] Checking ballot inclusion against the proven ballot set...");
+    
+    let commitment = BallotSetCommitment::from_ballots(&input.encrypted_votes);
+    if commitment.root() != output.ballot_set_root {
+        return Err("Proven ballot set root does not match the submitted ballots".to_string());
+    }
+    
+    // Every voter can check their own ballot against the root in the journal
+    for (index, vote) in input.encrypted_votes.iter().enumerate() {
+        let proof = commitment.inclusion_proof(index).map_err(|e| e.to_string())?;
+        if !proof.verify(vote, &output.ballot_set_root) {
+            return Err(format!("Inclusion proof failed for ballot {} ({})", index, vote.voter_address));
+        }
+    }
+    
+    println!("✅ [Host] All {} ballots included in the proven set", commitment.len());
+    Ok(())
+}
+
+fn verify_audit_trail(input: &VoteTallyInput, output: &VoteTallyOutput) -> Result<(), Box<dyn std::error::Error>> {
+    println!("\n📜 [Host] Auditing the submission log against the proven head...");
+    
+    // Anyone holding the published records can replay the chain and the accepted set
+    verify_audit_log(&input.audit_log, &output.audit_log_head)?;
+    verify_accepted_ballots(&input.audit_log, &input.encrypted_votes)?;
+    
+    let rejected = input.audit_log.iter().filter(|record| !record.accepted).count();
+    println!("✅ [Host] Audit log verified: {} submissions, {} rejected", input.audit_log.len(), rejected);
+    Ok(())
+}
+
+// The mix hides which ballot is which, so the guest needs every counted ballot at unit
+// weight. Only each voter's latest ballot is mixed, as that is the one the guest counts
+fn mix_ballots(input: &mut VoteTallyInput) -> Result<(), Box<dyn std::error::Error>> {
+    if matches!(input.scheme, TallyScheme::Weighted(_)) {
+        return Err("FHE_VOTING_MIXNET needs an unweighted voting scheme".into());
+    }
+    let counted = counted_vote_vectors(&input.encrypted_votes);
+    let mixed = shuffle(&counted, &input.election_public_key, SHUFFLE_PROOF_ROUNDS)?;
+    println!("🔀 [Host] {} ballots shuffled and re-encrypted with a {}-round shuffle proof", mixed.ballots.len(), mixed.proof.rounds.len());
+    input.mix = Some(BallotMix { ballots: mixed.ballots, proof: mixed.proof });
+    Ok(())
+}
+
+// The trustees decrypt the final tallies before the proof, as the guest holds no key:
+// proving the same input as an interim tally gives exactly the encrypted tallies the
+// final proof will decrypt, and the trustees share only those of a receipt that
+// verifies. Below the anonymity set they are not asked, as the counts stay withheld
+// (the ballots less the superseded and revoked ones bound the tally).
+fn share_final_tallies(
+    input: &mut VoteTallyInput,
+    assumptions: &[Receipt],
+    participating: &[TrusteeDecryptor],
+    verification_keys: &[ShareVerificationKey],
+    threshold: u32,
+    executor: &ExecutorConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    input.interim = true;
+    let env = {
+        let mut builder = ExecutorEnv::builder();
+        executor.apply(&mut builder)?;
+        for receipt in assumptions {
+            builder.add_assumption(receipt.clone());
+        }
+        builder.write(&*input)?.build()
+    };
+    input.interim = false;
+    let receipt = executor.prove(env?, tally_guest().elf(), &ProverOpts::succinct())?.receipt;
+    let tallies = ProvenInterimTallies::verify(&VerifierContext::default(), &receipt, tally_guest().image_id())?;
+    
+    let output = tallies.output();
+    let tallied = output.ballots.saturating_sub(output.superseded_ballots).saturating_sub(input.revocations.len() as u32);
+    if tallied < input.min_anonymity_set {
+        input.decryption = None;
+        return Ok(());
+    }
+    input.decryption = Some(trustee_decryption(participating, verification_keys, threshold, &tallies)?);
+    println!("🔐 [Host] {} trustees shared the decryption of {} proven encrypted tallies for the guest", participating.len(), tallies.encrypted_tallies().len());
+    Ok(())
+}
+
+fn decrypt_with_trustees(
+    input: &VoteTallyInput,
+    participating: &[TrusteeDecryptor],
+    verification_keys: &[ShareVerificationKey],
+    threshold: u32,
+    output: &VoteTallyOutput,
+    encrypted_tallies: &[Vec<u8>],
+) -> Result<DecryptionTranscript, Box<dyn std::error::Error>> {
+    println!("\n🔐 [Host] Trustees decrypting the proven encrypted tallies...");
+    
+    // The guest decrypted with shares checked against these keys
+    if !output.counts_withheld() && output.trustees_digest != trustees_digest(threshold, verification_keys) {
+        return Err("Proven counts were decrypted with another set of trustees".into());
+    }
+    
+    let counts = [output.option1_count, output.option2_count, output.option3_count];
+    let mut proofs = Vec::with_capacity(counts.len());
+    for (candidate, (ciphertext, &count)) in encrypted_tallies.iter().zip(&counts).enumerate() {
+        let partials = participating
+            .iter()
+            .map(|trustee| trustee.partial_decrypt(ciphertext))
+            .collect::<Result<Vec<_>, _>>()?;
+        let proof = prove_decryption(ciphertext, partials, verification_keys, threshold)?;
+        
+        // Anyone with the journal and the published verification keys can repeat this check;
+        // the shares are checked against the keys and the count against the ciphertext's decoding
+        let combined = verify_decryption_proof(&proof, ciphertext, verification_keys, threshold)?;
+        // Noisy journals publish the exact decryption plus the seed's noise
+        let published = match &input.dp_noise {
+            Some(config) => noised(config, sensitivity(&input.scheme), &output.ballot_set_root, candidate, proof.plaintext as u32) as u64,
+            None => proof.plaintext,
+        };
+        if published != count as u64 {
+            return Err(format!(
+                "Proven decryption of option {} gave {} (published as {}), journal says {}",
+                candidate + 1, proof.plaintext, published, count
+            ).into());
+        }
+        println!("  ✅ Option{}: {} (shares from trustees {:?} verified)", candidate + 1, proof.plaintext, combined.trustees);
+        proofs.push(proof);
+    }
+    
+    Ok(DecryptionTranscript { threshold, verification_keys: verification_keys.to_vec(), proofs })
+}
+
+// Set FHE_VOTING_EVENT_LOG=<path> to also append security events to a JSON Lines
+// file; an existing file is verified and resumed, so keep FHE_VOTING_OPERATOR_KEY fixed
+fn open_event_log(operator: Operator) -> Result<EventLog, Box<dyn std::error::Error>> {
+    let mut events = EventLog::new(operator);
+    if let Ok(path) = std::env::var("FHE_VOTING_EVENT_LOG") {
+        events = events.with_sink(&path)?;
+        println!("🗒️  [Host] Appending signed security events to {}", path);
+    }
+    Ok(events)
+}
+
+// Tally service: jobs are proven on FHE_VOTING_WORKERS threads (default 1) and
+// checkpointed in FHE_VOTING_STATE_DIR. SIGTERM or SIGINT stops intake and waits
+// up to FHE_VOTING_SHUTDOWN_GRACE_SECS (default 30) for running proofs; any still
+// running are abandoned and requeued for the next start. Proofs time out and retry per FHE_VOTING_JOB_* (see host/src/job.rs). Observers read
+// interim tallies from FHE_VOTING_RESULTS_DIR/<election>, each directory written
+// by a host run with FHE_VOTING_INTERIM_DIR pointing at it.
+fn run_daemon(address: &str, events: EventLog, hooks: FinalizationHooks, progress: TallyEvents, executor: ExecutorConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let state_dir = std::env::var("FHE_VOTING_STATE_DIR").unwrap_or_else(|_| "fhe-voting-state".to_string());
+    let workers = std::env::var("FHE_VOTING_WORKERS").ok().map(|v| v.parse()).transpose()?.unwrap_or(1);
+    let grace = std::env::var("FHE_VOTING_SHUTDOWN_GRACE_SECS").ok().map(|v| v.parse()).transpose()?.unwrap_or(30);
+    let grace = Duration::from_secs(grace);
+    let policy = RetryPolicy::from_env()?;
+    let results_dir = std::env::var("FHE_VOTING_RESULTS_DIR").ok().map(PathBuf::from);
+    
+    // One-off keys for this run; admins open and close elections and submit jobs, voters cast
+    // ballots, observers read status and receipts
+    let mut keys = ApiKeyStore::new();
+    println!("🔑 [Daemon] Admin API key: {}", keys.issue(Role::Admin));
+    println!("🔑 [Daemon] Voter API key: {}", keys.issue(Role::Voter));
+    println!("🔑 [Daemon] Observer API key: {}", keys.issue(Role::Observer));
+    // Ballots posted to open elections pass the same intake limits as the demo run's
+    let mut intake_config = IntakeConfig::default();
+    if let Some(quota) = std::env::var("FHE_VOTING_BALLOT_QUOTA").ok().and_then(|v| v.parse().ok()) {
+        intake_config.ballot_quota = quota;
+    }
+    let ballot_boxes = BallotBoxes::new(intake_config);
+    
+    let events = Mutex::new(events);
+    let runner = move |input: &VoteTallyInput| prove_job(input, &events, &hooks, &progress, &executor).map_err(|e| e.to_string());
+    let queue = JobQueue::open(&state_dir, workers, Box::new(runner), policy)?;
+    let server = tiny_http::Server::http(address).map_err(|e| e.to_string())?;
+    
+    let terminate = Arc::new(AtomicBool::new(false));
+    for signal in [SIGTERM, SIGINT] {
+        signal_hook::flag::register(signal, Arc::clone(&terminate))?;
+    }
+    println!("🛰️  [Daemon] Serving tally jobs on http://{} ({} workers, state in {})", address, workers, state_dir);
+    
+    while !terminate.load(Ordering::Relaxed) {
+        let Some(request) = server.recv_timeout(Duration::from_millis(250))? else {
+            continue;
+        };
+        if let Err(e) = serve_request(&queue, &keys, &ballot_boxes, results_dir.as_deref(), request) {
+            eprintln!("⚠️  [Daemon] Failed to answer request: {}", e);
+        }
+    }
+    
+    println!("🛑 [Daemon] Shutting down: intake closed, waiting up to {}s for running proofs", grace.as_secs());
+    let unfinished = queue.shutdown(grace)?;
+    println!("💾 [Daemon] State checkpointed; {} unfinished jobs resume on restart", unfinished);
+    Ok(())
+}
+
+fn serve_request(queue: &JobQueue, keys: &ApiKeyStore, ballot_boxes: &BallotBoxes, results_dir: Option<&Path>, mut request: tiny_http::Request) -> std::io::Result<()> {
+    let api_key = request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .and_then(|header| header.value.as_str().strip_prefix("Bearer ").map(str::to_string));
+    // One byte over the limit is enough for `route` to refuse the body
+    let mut body = Vec::new();
+    request.as_reader().take(MAX_JOB_BODY_BYTES as u64 + 1).read_to_end(&mut body)?;
+    let path = request.url().split('?').next().unwrap_or_default().to_string();
+    
+    let source = request.remote_addr().map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |address| address.ip());
+    let response = route(queue, keys, ballot_boxes, results_dir, &Request {
+        method: request.method().as_str(),
+        path: &path,
+        api_key: api_key.as_deref(),
+        source,
+        body: &body,
+    });
+    let content_type = tiny_http::Header::from_bytes("Content-Type", response.content_type).expect("content type is a valid header");
+    request.respond(tiny_http::Response::from_data(response.body).with_status_code(response.status).with_header(content_type))
+}
+
+fn prove_job(input: &VoteTallyInput, events: &Mutex<EventLog>, hooks: &FinalizationHooks, progress: &TallyEvents, executor: &ExecutorConfig) -> Result<JobOutcome, Box<dyn std::error::Error>> {
+    check_tally_capacity(input.encrypted_votes.len())?;
+    let record = |event| -> Result<(), Box<dyn std::error::Error>> {
+        events.lock().map_err(|_| "event log lock poisoned")?.record(event)?;
+        Ok(())
+    };
+    record(SecurityEvent::TallyStarted {
+        ballots: input.encrypted_votes.len() as u32,
+        ballot_set_root: input.ballot_set_root,
+    })?;
+    
+    let mut detached_frames = Vec::new();
+    let env = executor.apply(&mut ExecutorEnv::builder())?.write(input)?.stdout(&mut detached_frames).build()?;
+    let ProvedReceipt { receipt, stats } = executor.prove(env, tally_guest().elf(), &ProverOpts::default())?;
+    receipt.verify(tally_guest().image_id())?;
+    progress.emit(TallyEvent::receipt_verified(tally_guest().image_id(), &receipt.journal.bytes));
+    let journal_digest: [u8; 32] = (*Impl::hash_bytes(&receipt.journal.bytes)).into();
+    record(SecurityEvent::ReceiptProduced {
+        image_id: risc0_zkvm::sha::Digest::from(tally_guest().image_id()).into(),
+        journal_digest,
+    })?;
+    
+    let TallyJournal { output, .. } = read_tally_journal(input.journal_profile, &receipt.journal.bytes, &detached_frames)?;
+    run_hooks(hooks, &receipt.journal.bytes, &output, stats);
+    Ok(JobOutcome {
+        option_counts: vec![output.option1_count, output.option2_count, output.option3_count],
+        total_votes: output.total_votes,
+        journal_digest,
+        receipt: bincode::serialize(&receipt)?,
+        proving: Some(stats),
+    })
+}
+
+// Set FHE_VOTING_CENSUS=<path> to publish the registered-voter set; check it with census-verify
+fn export_census(registration: &RegistrationService) -> Result<(), Box<dyn std::error::Error>> {
+    let Ok(path) = std::env::var("FHE_VOTING_CENSUS") else {
+        return Ok(());
+    };
+    
+    Census::export(registration)?.write(&path)?;
+    println!("📇 [Host] Census of {} registered voters written to {}", registration.len(), path);
+    Ok(())
+}
+
+// Set FHE_VOTING_INTERIM_DIR=<directory, one per election> to prove and publish the
+// encrypted tally every FHE_VOTING_INTERIM_SECS seconds (default 3600) while voting is open
+fn open_interim() -> Result<Option<(InterimSchedule, InterimPublisher)>, Box<dyn std::error::Error>> {
+    let Ok(dir) = std::env::var("FHE_VOTING_INTERIM_DIR") else {
+        return Ok(None);
+    };
+    let interval = std::env::var("FHE_VOTING_INTERIM_SECS").ok().map(|v| v.parse()).transpose()?.unwrap_or(3600);
+    println!("⏱️  [Host] Publishing interim tally proofs every {}s to {}", interval, dir);
+    Ok(Some((InterimSchedule::every(Duration::from_secs(interval), Instant::now()), InterimPublisher::open(&dir)?)))
+}
+
+fn prove_interim(publisher: &mut InterimPublisher, progress: &TallyEvents, executor: &ExecutorConfig, mut input: VoteTallyInput) -> Result<(), Box<dyn std::error::Error>> {
+    // Each interim tally continues the one published before it
+    let mut builder = ExecutorEnv::builder();
+    executor.apply(&mut builder)?;
+    if let Some(receipt) = latest_interim_receipt(publisher)? {
+        input.prior_tally = Some(PriorTally { image_id: tally_guest().image_id(), journal: receipt.journal.bytes.clone() });
+        builder.add_assumption(receipt);
+    }
+    let env = builder.write(&input)?.build()?;
+    // Succinct, so the next tally can verify it as an assumption
+    let receipt = executor.prove(env, tally_guest().elf(), &ProverOpts::succinct())?.receipt;
+    receipt.verify(tally_guest().image_id())?;
+    progress.emit(TallyEvent::receipt_verified(tally_guest().image_id(), &receipt.journal.bytes));
+    let InterimJournal { output, .. } = read_interim_journal(&receipt.journal.bytes)?;
+    let journal_digest = (*Impl::hash_bytes(&receipt.journal.bytes)).into();
+    let record = publisher.publish(&output, input.audit_log.len() as u64, journal_digest, &bincode::serialize(&receipt)?)?;
+    println!("⏱️  [Host] Interim tally {} proven over {} ballots ({} submissions)", record.sequence, record.ballots, record.submissions);
+    Ok(())
+}
+
+fn latest_interim_receipt(publisher: &InterimPublisher) -> Result<Option<Receipt>, Box<dyn std::error::Error>> {
+    Ok(publisher.latest_receipt()?.map(|bytes| bincode::deserialize(&bytes)).transpose()?)
+}
+
+// Set FHE_VOTING_CROSS_CHECK=cpu,bonsai to prove the same input on each backend and compare the journals
+fn cross_check_backends(input: &VoteTallyInput, assumptions: &[Receipt], executor: &ExecutorConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let Ok(backends) = std::env::var("FHE_VOTING_CROSS_CHECK") else {
+        return Ok(());
+    };
+    let backends = backends_from_env(&backends)?;
+    println!("🔀 [Host] Cross-checking the tally on {} prover backends...", backends.len());
+    let report = cross_check(&backends, input, assumptions, tally_guest().elf(), tally_guest().image_id(), executor)?;
+    let names: Vec<String> = report.backends.iter().map(|backend| backend.to_string()).collect();
+    println!("✅ [Host] {} agree on journal {}", names.join(", "), report.journal_digest);
+    Ok(())
+}
+
+fn latest_interim() -> Result<Option<Receipt>, Box<dyn std::error::Error>> {
+    let Ok(dir) = std::env::var("FHE_VOTING_INTERIM_DIR") else {
+        return Ok(None);
+    };
+    latest_interim_receipt(&InterimPublisher::open(&dir)?)
+}
+
+// Observers holding the published interim heads check them against the final log,
+// and that every tally, the final one included, continues the one before it
+fn verify_interim_tallies(input: &VoteTallyInput, output: &VoteTallyOutput) -> Result<(), Box<dyn std::error::Error>> {
+    let Ok(dir) = std::env::var("FHE_VOTING_INTERIM_DIR") else {
+        return Ok(());
+    };
+    
+    let publisher = InterimPublisher::open(&dir)?;
+    verify_interim_heads(&input.audit_log, publisher.records())?;
+    verify_interim_chain(tally_guest().image_id(), publisher.records())?;
+    if let Some(last) = publisher.records().last() {
+        if output.prior_tally_digest != prior_tally_digest(tally_guest().image_id(), &last.journal_digest) {
+            return Err("Final tally does not continue the last interim tally".into());
+        }
+    }
+    println!("✅ [Host] {} interim tallies chain into the final audit log", publisher.records().len());
+    Ok(())
+}
+
+// Set FHE_VOTING_BATCH_SIZE=<ballots> to also prove the tally in batches of that many
+// ballots and aggregate the batch receipts into one succinct receipt
+fn prove_aggregate(
+    input: &VoteTallyInput,
+    result: &VoteTallyOutput,
+    participating: &[TrusteeDecryptor],
+    verification_keys: &[ShareVerificationKey],
+    threshold: u32,
+    progress: &TallyEvents,
+    executor: &ExecutorConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Ok(batch_size) = std::env::var("FHE_VOTING_BATCH_SIZE") else {
+        return Ok(());
+    };
+    // The batch tallies would decrypt to the counts the guest withheld
+    if result.counts_withheld() {
+        return Err("FHE_VOTING_BATCH_SIZE needs an election above its anonymity set".into());
+    }
+    // A revoked ballot may sit in another batch than the one that counted it
+    if !input.revocations.is_empty() {
+        return Err("FHE_VOTING_BATCH_SIZE needs an election without revocations".into());
+    }
+    
+    let batches = split_batches(input, batch_size.parse()?)?;
+    println!("\n🧮 [Host] Proving {} batch tallies of up to {} ballots...", batches.len(), batch_size);
+    let mut receipts = Vec::with_capacity(batches.len());
+    for (index, batch) in batches.iter().enumerate() {
+        let env = executor.apply(&mut ExecutorEnv::builder())?.write(batch)?.build()?;
+        // Succinct receipts can be verified inside the aggregation guest
+        let receipt = executor.prove(env, tally_guest().elf(), &ProverOpts::succinct())?.receipt;
+        progress.emit(TallyEvent::BatchProven { batch: index, batches: batches.len(), ballots: batch.encrypted_votes.len() });
+        receipt.verify(tally_guest().image_id())?;
+        progress.emit(TallyEvent::receipt_verified(tally_guest().image_id(), &receipt.journal.bytes));
+        receipts.push(receipt);
+    }
+    let batch_journals: Vec<_> = receipts.iter().map(|receipt| receipt.journal.bytes.clone()).collect();
+    let batch_voter_keys = batches.iter().map(|batch| voter_keys(&batch.encrypted_votes)).collect();
+    let aggregate_input = aggregate_input(tally_guest().image_id(), batch_journals.clone(), batch_voter_keys)?;
+    
+    // Every batch receipt is an assumption the aggregation proof resolves
+    let mut builder = ExecutorEnv::builder();
+    executor.apply(&mut builder)?;
+    for receipt in receipts {
+        builder.add_assumption(receipt);
+    }
+    let env = builder.write(&aggregate_input)?.build()?;
+    let receipt = executor.prove(env, AGGREGATE_ELF, &ProverOpts::succinct())?.receipt;
+    receipt.verify(AGGREGATE_ID)?;
+    progress.emit(TallyEvent::receipt_verified(AGGREGATE_ID, &receipt.journal.bytes));
+    let AggregateJournal { output, encrypted_tallies } = read_aggregate_journal(&receipt.journal.bytes)?;
+    check_aggregate(&output, &batch_journals)?;
+    check_election(&input.election, &output.election)?;
+    if output.identity_root != result.identity_root {
+        return Err("Aggregate was tallied against another identity roster".into());
+    }
+    println!("✅ [Host] Aggregate receipt verified: {} ballots in {} batches", output.ballots, output.batches);
+    
+    // The combined encrypted tallies decrypt to the single-proof counts
+    decrypt_with_trustees(input, participating, verification_keys, threshold, result, &encrypted_tallies)?;
+    Ok(())
+}
+
+// Set FHE_VOTING_VECTOR_LEN=<slots> to also sum sample encrypted vectors of that
+// length in the vector sum guest and decrypt the sums with the trustees
+fn prove_vector_sum(
+    election_public_key: &PublicKey,
+    trustee_outputs: &[DkgOutput],
+    threshold: u32,
+    executor: &ExecutorConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Ok(vector_len) = std::env::var("FHE_VOTING_VECTOR_LEN") else {
+        return Ok(());
+    };
+    let vector_len: u32 = vector_len.parse()?;
+    const MAX_VALUE: u64 = 100;
+    
+    // Sample survey answers from five contributors, encrypted under the election key
+    let client = FheClient::with_public_key(election_public_key.clone());
+    let vectors: Vec<Vec<u64>> = (0..5u64)
+        .map(|contributor| (0..vector_len as u64).map(|slot| (contributor * 37 + slot * 11) % (MAX_VALUE + 1)).collect())
+        .collect();
+    let contributions = vectors.iter().map(|values| client.encrypt_values(values)).collect::<Result<Vec<_>, _>>()?;
+    let input = vector_sum_input(election_public_key.clone(), vector_len, MAX_VALUE, contributions)?;
+    println!("\n🧮 [Host] Summing {} encrypted vectors of {} slots...", vectors.len(), vector_len);
+    
+    let env = executor.apply(&mut ExecutorEnv::builder())?.write(&input)?.build()?;
+    let receipt = executor.prove(env, VECTOR_SUM_ELF, &ProverOpts::default())?.receipt;
+    receipt.verify(VECTOR_SUM_ID)?;
+    let VectorSumJournal { output, encrypted_sums } = read_vector_sum_journal(&receipt.journal.bytes)?;
+    check_vector_sum(&output, &input)?;
+    println!("✅ [Host] Vector sum receipt verified: {} contributions", output.contributions);
+    
+    let sums = decrypt_values_with_trustees(trustee_outputs, threshold, &encrypted_sums)?;
+    for (slot, &sum) in sums.iter().enumerate() {
+        let expected: u64 = vectors.iter().map(|values| values[slot]).sum();
+        if sum != expected {
+            return Err(format!("Proven decryption of slot {} gave {}, expected {}", slot, sum, expected).into());
+        }
+        println!("  ✅ Slot {}: {}", slot, sum);
+    }
+    Ok(())
+}
+
+// Set FHE_VOTING_AUCTION_LEVELS=<ascending prices, comma-separated> to also run a
+// sample sealed-bid second-price auction over that price ladder
+fn prove_auction(
+    election_public_key: &PublicKey,
+    trustee_outputs: &[DkgOutput],
+    threshold: u32,
+    executor: &ExecutorConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Ok(levels) = std::env::var("FHE_VOTING_AUCTION_LEVELS") else {
+        return Ok(());
+    };
+    let price_levels = levels.split(',').map(|price| price.trim().parse()).collect::<Result<Vec<u64>, _>>()?;
+    let top = price_levels.last().copied().unwrap_or_default();
+    
+    // Sample bids spread over the ladder, sealed under the election key
+    let client = FheClient::with_public_key(election_public_key.clone());
+    let bidders = [("alice", top * 3 / 4), ("bob", top / 2), ("charlie", top), ("david", top / 4)];
+    let bids = bidders.iter().map(|&(bidder, bid)| seal_bid(&client, bidder, bid, &price_levels)).collect::<Result<Vec<_>, _>>()?;
+    let input = auction_input(election_public_key.clone(), price_levels, bids)?;
+    println!("\n🔨 [Host] Proving a sealed-bid auction of {} bids over {} price levels...", input.bids.len(), input.price_levels.len());
+    
+    let env = executor.apply(&mut ExecutorEnv::builder())?.write(&input)?.build()?;
+    let receipt = executor.prove(env, AUCTION_ELF, &ProverOpts::default())?.receipt;
+    receipt.verify(AUCTION_ID)?;
+    let AuctionJournal { output, encrypted_demand } = read_auction_journal(&receipt.journal.bytes)?;
+    check_auction(&output, &input)?;
+    println!("✅ [Host] Auction receipt verified: {} sealed bids", output.bids);
+    
+    // Trustees reveal only the demand at each price, then who reached the winning price
+    let demand = decrypt_values_with_trustees(trustee_outputs, threshold, &encrypted_demand)?;
+    let Some(clearing) = clear_auction(&output.price_levels, &demand)? else {
+        println!("  ⚠️  No bid reached the lowest price level");
+        return Ok(());
+    };
+    let (names, ciphertexts): (Vec<_>, Vec<_>) = level_ciphertexts(&input, clearing.winning_level)
+        .into_iter()
+        .map(|(bidder, ciphertext)| (bidder, ciphertext.to_vec()))
+        .unzip();
+    let reached = decrypt_values_with_trustees(trustee_outputs, threshold, &ciphertexts)?;
+    let winner = names.iter().zip(&reached).find(|(_, &bit)| bit == 1).map(|(name, _)| *name).ok_or("No bid decrypts to the winning level")?;
+    println!("  🏆 {} wins at price level {}, paying {}", winner, clearing.winning_price, clearing.clearing_price);
+    Ok(())
+}
+
+// Set FHE_VOTING_POLL_MAX=<highest answer> to also run a sample numeric poll through
+// the vector sum guest and publish the mean and variance of the answers
+fn prove_statistics(
+    election_public_key: &PublicKey,
+    trustee_outputs: &[DkgOutput],
+    threshold: u32,
+    executor: &ExecutorConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Ok(max_answer) = std::env::var("FHE_VOTING_POLL_MAX") else {
+        return Ok(());
+    };
+    let max_answer: u64 = max_answer.parse()?;
+    
+    // Sample answers, each encrypted with its square under the election key
+    let client = FheClient::with_public_key(election_public_key.clone());
+    let answers: Vec<u64> = (0..6u64).map(|respondent| (respondent * 7 + 3) % (max_answer + 1)).collect();
+    let responses = answers.iter().map(|&answer| encrypt_response(&client, answer, max_answer)).collect::<Result<Vec<_>, _>>()?;
+    let input = statistics_input(election_public_key.clone(), max_answer, responses)?;
+    println!("\n📈 [Host] Proving encrypted statistics over {} poll answers...", answers.len());
+    
+    let env = executor.apply(&mut ExecutorEnv::builder())?.write(&input)?.build()?;
+    let receipt = executor.prove(env, VECTOR_SUM_ELF, &ProverOpts::default())?.receipt;
+    receipt.verify(VECTOR_SUM_ID)?;
+    let VectorSumJournal { output, encrypted_sums } = read_vector_sum_journal(&receipt.journal.bytes)?;
+    check_vector_sum(&output, &input)?;
+    
+    let sums = decrypt_values_with_trustees(trustee_outputs, threshold, &encrypted_sums)?;
+    let stats = Statistics::from_sums(output.contributions, sums[0], sums[1])?;
+    if stats.sum != answers.iter().sum::<u64>() {
+        return Err(format!("Proven sum of answers is {}, expected {}", stats.sum, answers.iter().sum::<u64>()).into());
+    }
+    println!("✅ [Host] Poll of {} answers: mean {:.2}, variance {:.2}", stats.responses, stats.mean, stats.variance);
+    Ok(())
+}
+
+// Set FHE_VOTING_CIRCUIT=<path to a JSON CircuitProgram> to also run that FHE circuit
+// in the circuit interpreter guest and decrypt its outputs with the trustees
+fn prove_circuit(
+    election_public_key: &PublicKey,
+    trustee_outputs: &[DkgOutput],
+    threshold: u32,
+    executor: &ExecutorConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Ok(path) = std::env::var("FHE_VOTING_CIRCUIT") else {
+        return Ok(());
+    };
+    let program: CircuitProgram = serde_json::from_slice(&std::fs::read(&path)?)?;
+    // Refuse a circuit whose outputs might not decrypt before proving it
+    NoiseModel::new(&FheParameters::default())?.circuit(&program)?;
+    let input = program.encrypt(&FheClient::with_public_key(election_public_key.clone()))?;
+    println!("\n🧩 [Host] Proving circuit {} ({} ops over {} inputs)...", path, input.ops.len(), input.inputs.len());
+    
+    let env = executor.apply(&mut ExecutorEnv::builder())?.write(&input)?.build()?;
+    let receipt = executor.prove(env, CIRCUIT_ELF, &ProverOpts::default())?.receipt;
+    receipt.verify(CIRCUIT_ID)?;
+    let CircuitJournal { output, encrypted_outputs } = read_circuit_journal(&receipt.journal.bytes)?;
+    check_circuit(&output, &input)?;
+    // The guest must have computed exactly what the same ops give natively
+    let expected: Vec<_> = evaluate_circuit(&input)?.iter().map(|cipher| cipher.serialize()).collect();
+    if encrypted_outputs != expected {
+        return Err("Circuit outputs differ from the native evaluation".into());
+    }
+    println!("✅ [Host] Circuit receipt verified: {} ops", output.ops);
+    
+    let values = decrypt_values_with_trustees(trustee_outputs, threshold, &encrypted_outputs)?;
+    for (name, value) in input.outputs.iter().zip(values) {
+        println!("  ✅ {} = {}", name, value);
+    }
+    Ok(())
+}
+
+// Threshold-decrypt each ciphertext, verifying the trustees' shares
+fn decrypt_values_with_trustees(
+    trustee_outputs: &[DkgOutput],
+    threshold: u32,
+    ciphertexts: &[Vec<u8>],
+) -> Result<Vec<u64>, Box<dyn std::error::Error>> {
+    let (trustees, verification_keys): (Vec<_>, Vec<_>) = trustee_outputs
+        .iter()
+        .map(|output| TrusteeDecryptor::new(output.key_share.clone()))
+        .unzip();
+    ciphertexts
+        .iter()
+        .map(|ciphertext| {
+            let partials = trustees[..threshold as usize]
+                .iter()
+                .map(|trustee| trustee.partial_decrypt(ciphertext))
+                .collect::<Result<Vec<_>, _>>()?;
+            let proof = prove_decryption(ciphertext, partials, &verification_keys, threshold)?;
+            verify_decryption_proof(&proof, ciphertext, &verification_keys, threshold)?;
+            Ok(proof.plaintext)
+        })
+        .collect()
+}
+
+// Set FHE_VOTING_SNAPSHOT=<directory or .tar.gz path> to snapshot the election state:
+// once intake closes, before proving, and again with the receipt
+fn export_snapshot(input: &VoteTallyInput, dkg_config: DkgConfig, receipts: Vec<Vec<u8>>) -> Result<(), Box<dyn std::error::Error>> {
+    let Ok(path) = std::env::var("FHE_VOTING_SNAPSHOT") else {
+        return Ok(());
+    };
+    
+    let metadata = ElectionMetadata {
+        election_public_key: input.election_public_key.clone(),
+        threshold: dkg_config.threshold,
+        trustees: dkg_config.participants,
+        registrar_public_key: input.registrar_public_key,
+        identity_roster: input.identity_roster.clone(),
+        election: input.election,
+        candidates: input.candidates.clone(),
+        scheme: input.scheme.clone(),
+    };
+    let audit_log = AuditLog::from_records(input.audit_log.clone());
+    let snapshot = ElectionSnapshot::new(metadata, audit_log, input.encrypted_votes.clone(), input.revocations.clone(), receipts)?;
+    write_snapshot(&snapshot, &path)?;
+    println!("💾 [Host] Election state snapshot written to {}", path);
+    Ok(())
+}
+
+fn write_snapshot(snapshot: &ElectionSnapshot, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if path.ends_with(".tar.gz") {
+        snapshot.write_tarball(path)?;
+    } else {
+        snapshot.write_dir(path)?;
+    }
+    Ok(())
+}
+
+// FHE_VOTING_RESUME=<directory or .tar.gz path> carries on an election from a
+// snapshot taken on another machine: the restored ballots are proven here and,
+// with FHE_VOTING_SNAPSHOT set, the snapshot is written out again with the new
+// receipt. The guest holds no key, so the restored ballots are proven as an
+// interim tally whose encrypted tallies the trustees decrypt from the journal.
+fn resume_election(path: &str, events: &mut EventLog, progress: &TallyEvents, executor: &ExecutorConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let mut snapshot = if path.ends_with(".tar.gz") { ElectionSnapshot::read_tarball(path)? } else { ElectionSnapshot::read_dir(path)? };
+    println!("📂 [Host] Resuming election {} from {}: {} ballots, {} receipts so far",
+             snapshot.metadata.election.election_id, path, snapshot.ballots.len(), snapshot.receipts.len());
+    let mut input = snapshot.tally_input();
+    input.interim = true;
+    check_tally_capacity(input.encrypted_votes.len())?;
+    events.record(SecurityEvent::TallyStarted { ballots: input.encrypted_votes.len() as u32, ballot_set_root: input.ballot_set_root })?;
+    
+    let env = {
+        let mut builder = ExecutorEnv::builder();
+        executor.apply(&mut builder)?;
+        builder.write(&input)?.build()?
+    };
+    let ProvedReceipt { receipt, stats } = executor.prove(env, tally_guest().elf(), &ProverOpts::default())?;
+    receipt.verify(tally_guest().image_id())?;
+    print_proving_stats(&stats);
+    progress.emit(TallyEvent::receipt_verified(tally_guest().image_id(), &receipt.journal.bytes));
+    events.record(SecurityEvent::ReceiptProduced {
+        image_id: risc0_zkvm::sha::Digest::from(tally_guest().image_id()).into(),
+        journal_digest: (*Impl::hash_bytes(&receipt.journal.bytes)).into(),
+    })?;
+    
+    // The proof must cover exactly the restored ballots and audit log
+    let InterimJournal { output, encrypted_tallies } = read_interim_journal(&receipt.journal.bytes)?;
+    if output.ballot_set_root != input.ballot_set_root || output.audit_log_head != input.audit_log_head {
+        return Err("Resumed tally does not commit to the restored ballots".into());
+    }
+    println!("✅ [Host] Restored election proven: {} ballots tallied into {} encrypted tallies for the trustees",
+             output.ballots - output.superseded_ballots, encrypted_tallies.len());
+    
+    snapshot.receipts.push(bincode::serialize(&receipt)?);
+    if let Ok(out) = std::env::var("FHE_VOTING_SNAPSHOT") {
+        write_snapshot(&snapshot, &out)?;
+        println!("💾 [Host] Election state snapshot written to {}", out);
+    }
+    println!("🗒️  [Host] {} security events recorded, head {}", events.records().len(), hex::encode(events.head()));
+    Ok(())
+}
+
+// Set FHE_VOTING_ARCHIVE=<directory or .tar.gz path> to export an archive of the
+// finished election; check it later with the import-and-verify binary
+fn export_archive(input: &VoteTallyInput, receipt: &Receipt, decryption: DecryptionTranscript) -> Result<(), Box<dyn std::error::Error>> {
+    let Ok(path) = std::env::var("FHE_VOTING_ARCHIVE") else {
+        return Ok(());
+    };
+    
+    let archive = ElectionArchive {
+        parameters: SchemeParameters::current(risc0_zkvm::sha::Digest::from(tally_guest().image_id()).into()),
+        election_public_key: input.election_public_key.clone(),
+        ballot_commitments: input.encrypted_votes.iter().map(ballot_leaf_hash).collect(),
+        receipt: receipt.clone(),
+        decryption,
+    };
+    let manifest_hash = archive.write(&path)?;
+    println!("🗄️  [Host] Election archive written to {}", path);
+    println!("   Manifest hash (publish with the result): {}", hex::encode(manifest_hash));
+    println!("   Election (publish with the result): {}", serde_json::to_string(&input.election)?);
+    Ok(())
+}
+
+// Set FHE_VOTING_EXPORT_DIR=<directory> to write the ballots, rejections and results tables as
+// Parquet, or as Arrow IPC files with FHE_VOTING_EXPORT_FORMAT=arrow (export feature)
+fn export_tables(input: &VoteTallyInput, output: &VoteTallyOutput) -> Result<(), Box<dyn std::error::Error>> {
+    let Ok(dir) = std::env::var("FHE_VOTING_EXPORT_DIR") else {
+        return Ok(());
+    };
+    #[cfg(feature = "export")]
+    {
+        let format = ExportFormat::parse(&std::env::var("FHE_VOTING_EXPORT_FORMAT").unwrap_or_else(|_| "parquet".to_string()))?;
+        let counts = [output.option1_count, output.option2_count, output.option3_count];
+        let published = (!output.counts_withheld()).then_some(&counts[..]);
+        let tables = ElectionTables::new(&input.encrypted_votes, &input.audit_log, &input.candidates, published, &input.scheme)?;
+        for path in tables.write(&dir, format)? {
+            println!("📦 [Host] Exported {}", path.display());
+        }
+        Ok(())
+    }
+    #[cfg(not(feature = "export"))]
+    {
+        let _ = (input, output);
+        Err(format!("FHE_VOTING_EXPORT_DIR={} is set but the host was built without the export feature", dir).into())
+    }
+}
+
+// Set FHE_VOTING_ETH_RPC, FHE_VOTING_ETH_BALLOT_BOX and FHE_VOTING_ETH_CLOSE_BLOCK to read
+// ballots from the chain; FHE_VOTING_ETH_START_BLOCK (default 0) is where the election
+// opened and FHE_VOTING_ETH_CONFIRMATIONS (default 12) sets finality
+#[cfg(feature = "ethereum")]
+fn ingest_chain_ballots() -> Result<Option<ChainBallotSet>, Box<dyn std::error::Error>> {
+    let (Ok(rpc_url), Ok(ballot_box)) = (std::env::var("FHE_VOTING_ETH_RPC"), std::env::var("FHE_VOTING_ETH_BALLOT_BOX")) else {
+        return Ok(None);
+    };
+    let close_block: u64 = std::env::var("FHE_VOTING_ETH_CLOSE_BLOCK")?.parse()?;
+    let start_block = std::env::var("FHE_VOTING_ETH_START_BLOCK").ok().map(|v| v.parse()).transpose()?.unwrap_or(0);
+    let confirmations = std::env::var("FHE_VOTING_ETH_CONFIRMATIONS").ok().map(|v| v.parse()).transpose()?.unwrap_or(DEFAULT_CONFIRMATIONS);
+    
+    let source = RpcChainSource::new(&rpc_url, &ballot_box)?;
+    let mut ingestor = BallotIngestor::new(start_block, confirmations);
+    println!("⛓️  [Host] Ingesting ballots from block {} until block {} has {} confirmations...", start_block, close_block, confirmations);
+    loop {
+        let rolled_back = ingestor.sync(&source)?;
+        if rolled_back > 0 {
+            println!("🔀 [Host] Reorg: rolled back {} blocks", rolled_back);
+        }
+        match ingestor.ballot_set(close_block) {
+            Ok(ballot_set) => return Ok(Some(ballot_set)),
+            Err(IngestError::NotFinal { .. }) => std::thread::sleep(Duration::from_secs(5)),
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+#[cfg(not(feature = "ethereum"))]
+fn ingest_chain_ballots() -> Result<Option<ChainBallotSet>, Box<dyn std::error::Error>> {
+    if std::env::var("FHE_VOTING_ETH_BALLOT_BOX").is_ok() {
+        return Err("FHE_VOTING_ETH_BALLOT_BOX is set but the host was built without the ethereum feature".into());
+    }
+    Ok(None)
+}
+
+// Set FHE_VOTING_ETH_RPC, FHE_VOTING_ETH_FROM (an account unlocked at the node) and
+// FHE_VOTING_ETH_CONTRACT to finalize the result in the governance contract
+#[cfg(feature = "ethereum")]
+fn post_results(receipt: &Receipt, result: &VoteTallyOutput) -> Result<(), Box<dyn std::error::Error>> {
+    let Ok(rpc_url) = std::env::var("FHE_VOTING_ETH_RPC") else {
+        return Ok(());
+    };
+    // The contract refuses withheld counts rather than record them as zeros
+    if result.counts_withheld() {
+        return Err("Counts are withheld below the anonymity set and cannot be finalized on-chain".into());
+    }
+    
+    let client = EthereumClient::new(&rpc_url, &std::env::var("FHE_VOTING_ETH_FROM")?, &std::env::var("FHE_VOTING_ETH_CONTRACT")?)?;
+    let seal = encode_seal(receipt)?;
+    println!("⛓️  [Host] Posting results on-chain ({} byte seal, selector {})", seal.len(), hex::encode(&seal[..4]));
+    let tx_hash = client.finalize_results(&receipt.journal.bytes, &seal)?;
+    client.wait_for_receipt(&tx_hash, Duration::from_secs(120))?;
+    println!("✅ [Host] Results finalized on-chain in transaction {}", tx_hash);
+    Ok(())
+}
+
+#[cfg(not(feature = "ethereum"))]
+fn post_results(_receipt: &Receipt, _result: &VoteTallyOutput) -> Result<(), Box<dyn std::error::Error>> {
+    if std::env::var("FHE_VOTING_ETH_RPC").is_ok() {
+        return Err("FHE_VOTING_ETH_RPC is set but the host was built without the ethereum feature".into());
+    }
+    Ok(())
+}
+
+// Post-tally hooks, run once the result is verified: FHE_VOTING_HOOK_DIR=<directory>
+// drops the result as JSON, FHE_VOTING_HOOK_WEBHOOK=<url> POSTs it (webhooks feature)
+// and FHE_VOTING_HOOK_CONTRACT=<address> calls enactResult on it from FHE_VOTING_ETH_FROM
+// through FHE_VOTING_ETH_RPC (ethereum feature)
+fn load_hooks() -> Result<FinalizationHooks, Box<dyn std::error::Error>> {
+    let mut hooks = FinalizationHooks::new();
+    if let Ok(dir) = std::env::var("FHE_VOTING_HOOK_DIR") {
+        hooks = hooks.with_hook(FileDropHook::new(dir));
+    }
+    if let Ok(url) = std::env::var("FHE_VOTING_HOOK_WEBHOOK") {
+        #[cfg(feature = "webhooks")]
+        {
+            hooks = hooks.with_hook(WebhookHook::new(&url)?);
+        }
+        #[cfg(not(feature = "webhooks"))]
+        return Err(format!("FHE_VOTING_HOOK_WEBHOOK={} is set but the host was built without the webhooks feature", url).into());
+    }
+    if let Ok(contract) = std::env::var("FHE_VOTING_HOOK_CONTRACT") {
+        #[cfg(feature = "ethereum")]
+        {
+            let rpc_url = std::env::var("FHE_VOTING_ETH_RPC")?;
+            hooks = hooks.with_hook(ContractCallHook::new(&rpc_url, &std::env::var("FHE_VOTING_ETH_FROM")?, &contract)?);
+        }
+        #[cfg(not(feature = "ethereum"))]
+        return Err(format!("FHE_VOTING_HOOK_CONTRACT={} is set but the host was built without the ethereum feature", contract).into());
+    }
+    Ok(hooks)
+}
+
+// Set FHE_VOTING_REVEAL_AT=<unix seconds> to keep the result sealed until then: the verified
+// result and its receipt are time-locked into FHE_VOTING_TIMELOCK (default sealed-result.json)
+// and nothing is printed, posted or handed to hooks. The lock takes as many squarings as this
+// machine does by the reveal time, or FHE_VOTING_SQUARINGS_PER_SEC for a faster adversary;
+// anyone can open it with timelock-open
+fn seal_results(receipt: &Receipt, output: &VoteTallyOutput) -> Result<bool, Box<dyn std::error::Error>> {
+    let Ok(reveal_at) = std::env::var("FHE_VOTING_REVEAL_AT") else {
+        return Ok(false);
+    };
+    let reveal_at: u64 = reveal_at.parse()?;
+    let now = election::now();
+    if reveal_at <= now {
+        println!("🔓 [Host] Reveal time {} has passed; publishing the result", reveal_at);
+        return Ok(false);
+    }
+    
+    let rate = match std::env::var("FHE_VOTING_SQUARINGS_PER_SEC") {
+        Ok(rate) => rate.parse()?,
+        Err(_) => squaring_rate(&mut rand::rngs::OsRng),
+    };
+    let sealed = SealedTally {
+        result: FinalizedResult::new(tally_guest().image_id(), &receipt.journal.bytes, output),
+        receipt: bincode::serialize(receipt)?,
+    };
+    let squarings = squarings_until(reveal_at, now, rate);
+    let lock = TimeLock::seal(&sealed.to_bytes()?, squarings, reveal_at, &mut rand::rngs::OsRng);
+    let path = std::env::var("FHE_VOTING_TIMELOCK").unwrap_or_else(|_| "sealed-result.json".to_string());
+    std::fs::write(&path, lock.to_json()?)?;
+    println!("🔒 [Host] Result and receipt sealed in {} until {} ({} squarings at {}/s); nothing else published", path, reveal_at, squarings, rate);
+    Ok(true)
+}
+
+// Set FHE_VOTING_PROGRESS=<path> to stream tally progress events there as JSON lines
+// (see host/src/progress.rs); embedders register their own observers instead
+fn load_progress() -> Result<TallyEvents, Box<dyn std::error::Error>> {
+    let Ok(path) = std::env::var("FHE_VOTING_PROGRESS") else {
+        return Ok(TallyEvents::new());
+    };
+    let file = Mutex::new(std::fs::File::create(&path)?);
+    Ok(TallyEvents::new().with_observer(move |event: &TallyEvent| {
+        let written = serde_json::to_string(event).map_err(std::io::Error::from).and_then(|line| {
+            let mut file = file.lock().map_err(|_| std::io::Error::other("progress file lock poisoned"))?;
+            writeln!(file, "{}", line)
+        });
+        if let Err(e) = written {
+            eprintln!("⚠️  [Host] Failed to write progress event: {}", e);
+        }
+    }))
+}
+
+// A failed hook is reported but does not undo the verified result
+fn run_hooks(hooks: &FinalizationHooks, journal: &[u8], output: &VoteTallyOutput, stats: ProvingStats) {
+    if hooks.is_empty() {
+        return;
+    }
+    let result = FinalizedResult::new(tally_guest().image_id(), journal, output).with_proving(stats);
+    let failures = hooks.run(&result);
+    for (hook, error) in &failures {
+        eprintln!("⚠️  [Host] Finalization hook {} failed: {}", hook, error);
+    }
+    println!("🪝 [Host] {} of {} finalization hooks ran", hooks.len() - failures.len(), hooks.len());
+}
+
+fn print_proving_stats(stats: &ProvingStats) {
+    println!(
+        "📊 [Host] {} cycles ({} in the guest) in {} segments, proved in {:.1}s, receipt {} bytes",
+        stats.total_cycles,
+        stats.user_cycles,
+        stats.segments,
+        stats.proving_ms as f64 / 1000.0,
+        stats.receipt_bytes
+    );
+}
+
+// The operator signs attestations and security events with the hex Ed25519 secret
+// in FHE_VOTING_OPERATOR_KEY (or a one-off key)
+fn load_operator() -> Result<Operator, Box<dyn std::error::Error>> {
+    match std::env::var("FHE_VOTING_OPERATOR_KEY") {
+        Ok(secret) => {
+            let secret: [u8; 32] = hex::decode(secret.trim())?
+                .try_into()
+                .map_err(|_| "FHE_VOTING_OPERATOR_KEY must be 32 bytes of hex")?;
+            Ok(Operator::from_secret(secret))
+        }
+        Err(_) => {
+            println!("⚠️  [Host] FHE_VOTING_OPERATOR_KEY not set; signing with a one-off operator key");
+            Ok(Operator::generate())
+        }
+    }
+}
+
+// Set FHE_VOTING_CEREMONY=<path> to publish the key ceremony transcript (see host/src/ceremony.rs)
+fn export_ceremony(transcript: &CeremonyTranscript) -> Result<(), Box<dyn std::error::Error>> {
+    let Ok(path) = std::env::var("FHE_VOTING_CEREMONY") else {
+        return Ok(());
+    };
+    std::fs::write(&path, transcript.to_json()?)?;
+    println!("🕯️  [Host] Key ceremony transcript ({} entries) written to {}", transcript.entries.len(), path);
+    Ok(())
+}
+
+// Set FHE_VOTING_ATTESTATION=<path prefix> to write <prefix>.json and <prefix>.cbor
+fn export_attestation(operator: &Operator, journal: &[u8], output: &VoteTallyOutput) -> Result<(), Box<dyn std::error::Error>> {
+    let Ok(prefix) = std::env::var("FHE_VOTING_ATTESTATION") else {
+        return Ok(());
+    };
+    
+    let attestation = operator.attest(AttestationBody::new(tally_guest().image_id(), journal, output));
+    verify_attestation(&attestation)?;
+    std::fs::write(format!("{}.json", prefix), attestation.to_json()?)?;
+    std::fs::write(format!("{}.cbor", prefix), attestation.to_cbor()?)?;
+    println!("🖋️  [Host] Signed attestation written to {}.json and {}.cbor (operator key {})",
+             prefix, prefix, hex::encode(attestation.operator_public_key));
+    Ok(())
+}
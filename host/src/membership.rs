@@ -0,0 +1,237 @@
+// Semaphore-style anonymous ballot authorization.
+// Voters register an identity commitment Y = y*G; the registered commitments form
+// the identity roster, whose Merkle root the guest commits to the journal. A
+// ballot then carries a one-out-of-many proof that its author knows the secret
+// behind *some* roster entry, plus a nullifier N = y*H_e, where H_e is derived
+// from the election's domain and the roster root. The nullifier is the same
+// for every ballot from one identity in this election, so the guest can
+// enforce one vote per identity without learning which identity voted, and
+// unrelated to the same identity's nullifier in any other election, even one
+// over the same roster.
+//
+// The proof is a Cramer-Damgard-Schoenmakers OR-composition of Chaum-Pedersen
+// proofs (log_G Y_i == log_H N), bound to the ballot's `ballot_message`. Its
+// size and verification cost are linear in the roster; Semaphore's Merkle-path
+// SNARK is logarithmic, but needs a proof system the guest does not have.
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use risc0_zkvm::sha::{Impl, Sha256};
+use thiserror::Error;
+
+use crate::ballot_set::{election_domain, merkle_root};
+use crate::registrar::wide_hash;
+use crate::types::{Digest32, MembershipProof};
+
+// Domain separation tag for roster leaves (ballot set uses 0x00/0x01)
+const IDENTITY_TAG: u8 = 0x02;
+
+#[derive(Error, Debug)]
+pub enum MembershipError {
+    #[error("Identity is not on the roster")]
+    NotRegistered,
+    #[error("Malformed identity commitment at roster position {position}")]
+    MalformedCommitment { position: usize },
+}
+
+/// A voter's long-term identity secret.
+pub struct Identity {
+    secret: Scalar,
+}
+
+impl Identity {
+    pub fn generate() -> Self {
+        Identity { secret: Scalar::random(&mut rand::thread_rng()) }
+    }
+
+    /// Public commitment the voter registers.
+    pub fn commitment(&self) -> [u8; 32] {
+        (self.secret * RISTRETTO_BASEPOINT_POINT).compress().to_bytes()
+    }
+
+    /// The secret, for the voter to keep between registering and voting.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.secret.to_bytes()
+    }
+
+    /// The nullifier this identity's ballots carry in `election_id` over `roster`,
+    /// known before any ballot is proven.
+    pub fn nullifier(&self, roster: &[[u8; 32]], election_id: &Digest32) -> [u8; 32] {
+        (self.secret * nullifier_base(election_id, &identity_root(roster))).compress().to_bytes()
+    }
+
+    /// Restore a saved identity; `None` unless the bytes are a canonical scalar.
+    pub fn from_bytes(bytes: [u8; 32]) -> Option<Self> {
+        Option::from(Scalar::from_canonical_bytes(bytes)).map(|secret| Identity { secret })
+    }
+}
+
+/// Merkle root naming the roster (matches guest `identity_root`).
+pub fn identity_root(roster: &[[u8; 32]]) -> [u8; 32] {
+    merkle_root(roster.iter().map(identity_leaf).collect())
+}
+
+/// Roster tree leaf for one identity commitment.
+pub fn identity_leaf(commitment: &[u8; 32]) -> [u8; 32] {
+    let mut buf = vec![IDENTITY_TAG];
+    buf.extend_from_slice(commitment);
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+pub fn prove_membership(
+    identity: &Identity,
+    roster: &[[u8; 32]],
+    election_id: &Digest32,
+    message: &[u8; 32],
+) -> Result<MembershipProof, MembershipError> {
+    let members = decompress_roster(roster)?;
+    let own_commitment = identity.commitment();
+    let position = roster.iter().position(|c| c == &own_commitment).ok_or(MembershipError::NotRegistered)?;
+
+    let root = identity_root(roster);
+    let h = nullifier_base(election_id, &root);
+    let nullifier = identity.secret * h;
+
+    // Simulate every other branch, then answer the real one with the leftover challenge
+    let mut rng = rand::thread_rng();
+    let nonce = Scalar::random(&mut rng);
+    let mut challenges: Vec<Scalar> = (0..members.len()).map(|_| Scalar::random(&mut rng)).collect();
+    let mut responses: Vec<Scalar> = (0..members.len()).map(|_| Scalar::random(&mut rng)).collect();
+    let announcements: Vec<(RistrettoPoint, RistrettoPoint)> = members
+        .iter()
+        .enumerate()
+        .map(|(i, y)| {
+            if i == position {
+                (nonce * RISTRETTO_BASEPOINT_POINT, nonce * h)
+            } else {
+                (
+                    responses[i] * RISTRETTO_BASEPOINT_POINT - challenges[i] * y,
+                    responses[i] * h - challenges[i] * nullifier,
+                )
+            }
+        })
+        .collect();
+
+    let total = proof_challenge(&root, &nullifier.compress(), message, &announcements);
+    let simulated: Scalar = challenges.iter().enumerate().filter(|&(i, _)| i != position).map(|(_, c)| c).sum();
+    challenges[position] = total - simulated;
+    responses[position] = nonce + challenges[position] * identity.secret;
+
+    Ok(MembershipProof {
+        nullifier: nullifier.compress().to_bytes(),
+        challenges: challenges.iter().map(Scalar::to_bytes).collect(),
+        responses: responses.iter().map(Scalar::to_bytes).collect(),
+    })
+}
+
+/// Check a membership proof (matches guest `verify_membership`).
+pub fn verify_membership(proof: &MembershipProof, roster: &[[u8; 32]], election_id: &Digest32, message: &[u8; 32]) -> bool {
+    let Ok(members) = decompress_roster(roster) else {
+        return false;
+    };
+    if proof.challenges.len() != members.len() || proof.responses.len() != members.len() {
+        return false;
+    }
+    let Some(nullifier) = CompressedRistretto(proof.nullifier).decompress() else {
+        return false;
+    };
+    let scalars = |bytes: &[[u8; 32]]| -> Option<Vec<Scalar>> {
+        bytes.iter().map(|b| Option::<Scalar>::from(Scalar::from_canonical_bytes(*b))).collect()
+    };
+    let (Some(challenges), Some(responses)) = (scalars(&proof.challenges), scalars(&proof.responses)) else {
+        return false;
+    };
+
+    let root = identity_root(roster);
+    let h = nullifier_base(election_id, &root);
+    let announcements: Vec<(RistrettoPoint, RistrettoPoint)> = members
+        .iter()
+        .zip(challenges.iter().zip(&responses))
+        .map(|(y, (c, s))| (s * RISTRETTO_BASEPOINT_POINT - c * y, s * h - c * nullifier))
+        .collect();
+
+    let total = proof_challenge(&root, &CompressedRistretto(proof.nullifier), message, &announcements);
+    challenges.iter().sum::<Scalar>() == total
+}
+
+fn decompress_roster(roster: &[[u8; 32]]) -> Result<Vec<RistrettoPoint>, MembershipError> {
+    roster
+        .iter()
+        .enumerate()
+        .map(|(position, bytes)| {
+            CompressedRistretto(*bytes).decompress().ok_or(MembershipError::MalformedCommitment { position })
+        })
+        .collect()
+}
+
+// H_e: per-election base point, so nullifiers do not link across elections
+fn nullifier_base(election_id: &Digest32, identity_root: &[u8; 32]) -> RistrettoPoint {
+    let mut buf = b"fhe-voting/nullifier".to_vec();
+    buf.extend_from_slice(&election_domain(election_id));
+    buf.extend_from_slice(identity_root);
+    RistrettoPoint::from_uniform_bytes(&wide_hash(&buf))
+}
+
+fn proof_challenge(
+    identity_root: &[u8; 32],
+    nullifier: &CompressedRistretto,
+    message: &[u8; 32],
+    announcements: &[(RistrettoPoint, RistrettoPoint)],
+) -> Scalar {
+    let mut buf = b"fhe-voting/membership".to_vec();
+    buf.extend_from_slice(identity_root);
+    buf.extend_from_slice(nullifier.as_bytes());
+    buf.extend_from_slice(message);
+    for (a, b) in announcements {
+        buf.extend_from_slice(a.compress().as_bytes());
+        buf.extend_from_slice(b.compress().as_bytes());
+    }
+    Scalar::from_bytes_mod_order_wide(&wide_hash(&buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ballot_set::ballot_message;
+
+    #[test]
+    fn test_membership_proof_and_nullifier() -> Result<(), MembershipError> {
+        let identities: Vec<Identity> = (0..4).map(|_| Identity::generate()).collect();
+        let roster: Vec<[u8; 32]> = identities.iter().map(Identity::commitment).collect();
+        let election_id = Digest32([7u8; 32]);
+        let ciphertexts = [vec![1u8; 8], vec![2u8; 8]];
+        let message = ballot_message(&election_id, "", &[0u8; 32], &ciphertexts, 0);
+
+        let proof = prove_membership(&identities[2], &roster, &election_id, &message)?;
+        assert!(verify_membership(&proof, &roster, &election_id, &message));
+        assert!(!verify_membership(&proof, &roster, &election_id, &ballot_message(&election_id, "", &[0u8; 32], &ciphertexts, 1)));
+        // The same ballot replayed in another election, under another nonce or another voter address, is not authorized
+        assert!(!verify_membership(&proof, &roster, &election_id, &ballot_message(&Digest32([8u8; 32]), "", &[0u8; 32], &ciphertexts, 0)));
+        assert!(!verify_membership(&proof, &roster, &election_id, &ballot_message(&election_id, "", &[1u8; 32], &ciphertexts, 0)));
+        assert!(!verify_membership(&proof, &roster, &election_id, &ballot_message(&election_id, "0x01", &[0u8; 32], &ciphertexts, 0)));
+
+        // A second ballot from the same identity carries the same nullifier
+        let again = prove_membership(&identities[2], &roster, &election_id, &ballot_message(&election_id, "", &[1u8; 32], &[vec![4u8; 8]], 0))?;
+        assert_eq!(proof.nullifier, again.nullifier);
+        assert_eq!(identities[2].nullifier(&roster, &election_id), proof.nullifier);
+
+        // Another election over the same roster gets an unrelated nullifier, and a proof
+        // from this one does not verify there even against the other election's message
+        let other = Digest32([8u8; 32]);
+        let other_message = ballot_message(&other, "", &[0u8; 32], &ciphertexts, 0);
+        let elsewhere = prove_membership(&identities[2], &roster, &other, &other_message)?;
+        assert_ne!(elsewhere.nullifier, proof.nullifier);
+        assert!(verify_membership(&elsewhere, &roster, &other, &other_message));
+        assert!(!verify_membership(&elsewhere, &roster, &election_id, &other_message));
+
+        // A saved identity still proves membership and keeps its nullifier
+        let restored = Identity::from_bytes(identities[2].to_bytes()).ok_or(MembershipError::NotRegistered)?;
+        assert_eq!(prove_membership(&restored, &roster, &election_id, &message)?.nullifier, proof.nullifier);
+        assert!(Identity::from_bytes([0xff; 32]).is_none());
+
+        let outsider = Identity::generate();
+        assert!(matches!(prove_membership(&outsider, &roster, &election_id, &message), Err(MembershipError::NotRegistered)));
+        Ok(())
+    }
+}
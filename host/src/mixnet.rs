@@ -0,0 +1,394 @@
+// Optional re-encryption mixnet stage for ballots.
+// Before tallying, a mixer permutes the encrypted vote vectors and re-randomizes
+// every ciphertext, so the order in which the guest processes ciphertexts no
+// longer follows the order in which voters submitted them. The ballots keep
+// their authorizations: the guest authenticates the submitted ballots, resolves
+// re-votes, checks the shuffle proof from the vote vectors it counts to the mix
+// (guest `mixnet`), and tallies the mixed ciphertexts in their place.
+//
+// The shuffle proof is a cut-and-choose (Sako-Kilian) argument made
+// non-interactive with Fiat-Shamir: the mixer publishes `rounds` shadow shuffles
+// of the input, and for each one a challenge bit decides whether it opens the
+// link input -> shadow or shadow -> output. Never both, so the permutation stays
+// hidden, while a mixer that altered any ballot is caught with probability
+// 1 - 2^-rounds.
+//
+// Re-randomization adds a fresh encryption of zero under the election key,
+// (Δ·(b*u mod t) + e, a*u mod t) with u uniform mod t and every e below
+// `REENCRYPTION_NOISE_BOUND`, so the plaintext and its key term move together.
+// An opening reveals u, and the verifier checks that target - source is that
+// encryption of zero. Masks compose linearly, which is what lets a shadow ->
+// output opening reveal only a difference of masks (its noise then stays
+// within `REENCRYPTION_NOISE_BOUND` + r, r = q mod t, for the wrap). Every
+// ciphertext must be bound to the election key the mix runs under, and
+// re-randomized ciphertexts stay bound to it.
+
+use std::collections::HashMap;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use risc0_zkvm::sha::{Impl, Sha256};
+use thiserror::Error;
+
+use crate::ballot_set::voter_key;
+use crate::fhe_client::{
+    multiply_polynomials, public_polynomial, Cipher, FheClientError, PublicKey, Signed, CIPHERTEXT_MODULUS, PLAINTEXT_MODULUS,
+    POLYNOMIAL_DEGREE,
+};
+use crate::types::EncryptedVote;
+pub use crate::types::{ShadowOpening, ShadowRound, ShuffleProof};
+
+/// Default number of shadow shuffles (soundness error 2^-40).
+pub const SHUFFLE_PROOF_ROUNDS: usize = 40;
+
+/// Noise added to the plaintext-carrying coefficient on each re-encryption.
+pub(crate) const REENCRYPTION_NOISE_BOUND: u64 = PLAINTEXT_MODULUS / 16;
+
+/// One ballot's encrypted vote vector (one serialized ciphertext per candidate).
+pub type VoteVector = Vec<Vec<u8>>;
+
+// Coefficients of every ciphertext in a vote vector
+type Randomizer = Vec<Vec<u64>>;
+
+// The mask u of every ciphertext's re-encryption in a vote vector
+type Masks = Vec<Vec<u64>>;
+
+#[derive(Error, Debug)]
+pub enum MixError {
+    #[error("Invalid ballot ciphertext: {0}")]
+    Ciphertext(#[from] FheClientError),
+    #[error("Vote vectors have inconsistent shapes")]
+    ShapeMismatch,
+    #[error("Shuffle proof has {actual} rounds, expected at least {expected}")]
+    TooFewRounds { expected: usize, actual: usize },
+    #[error("Shuffle proof round {round} failed: {reason}")]
+    RoundFailed { round: usize, reason: String },
+}
+
+/// The vote vectors the guest tallies, and so the ones to mix: each voter's
+/// latest ballot, in ballot set order.
+pub fn counted_vote_vectors(ballots: &[EncryptedVote]) -> Vec<VoteVector> {
+    let mut latest: HashMap<[u8; 32], u64> = HashMap::new();
+    for ballot in ballots {
+        let sequence = latest.entry(voter_key(&ballot.authorization)).or_insert(ballot.sequence);
+        *sequence = (*sequence).max(ballot.sequence);
+    }
+    ballots
+        .iter()
+        .filter(|ballot| latest[&voter_key(&ballot.authorization)] == ballot.sequence)
+        .map(|ballot| ballot.encrypted_vote_vector.clone())
+        .collect()
+}
+
+pub struct ShuffleOutput {
+    pub ballots: Vec<VoteVector>,
+    pub proof: ShuffleProof,
+    /// output[j] came from input[permutation[j]]. Mixer-private: never publish.
+    pub permutation: Vec<u32>,
+}
+
+/// Shuffle and re-randomize `input` under the election key `public_key`,
+/// proving it with `rounds` shadow shuffles.
+pub fn shuffle(input: &[VoteVector], public_key: &PublicKey, rounds: usize) -> Result<ShuffleOutput, MixError> {
+    let key_fingerprint = &public_key.fingerprint();
+    let decoded = decode_all(input, key_fingerprint)?;
+    let mut rng = rand::thread_rng();
+
+    let (permutation, masks, randomizers) = random_permutation_and_randomizers(&decoded, public_key, &mut rng);
+    let output_coeffs = apply(&decoded, &permutation, &randomizers);
+
+    let mut shadows = Vec::with_capacity(rounds);
+    for _ in 0..rounds {
+        let (shadow_permutation, shadow_masks, shadow_randomizers) = random_permutation_and_randomizers(&decoded, public_key, &mut rng);
+        let shadow_coeffs = apply(&decoded, &shadow_permutation, &shadow_randomizers);
+        shadows.push((shadow_permutation, shadow_masks, shadow_coeffs));
+    }
+
+    let ballots = encode_all(&output_coeffs, key_fingerprint);
+    let shadow_ballots: Vec<Vec<VoteVector>> = shadows.iter().map(|(_, _, coeffs)| encode_all(coeffs, key_fingerprint)).collect();
+    let challenges = challenge_bits(input, &ballots, &shadow_ballots);
+
+    let proof_rounds = shadows
+        .into_iter()
+        .zip(shadow_ballots)
+        .zip(challenges)
+        .map(|(((shadow_permutation, shadow_masks, _), shadow), open_to_output)| {
+            let opening = if open_to_output {
+                // output[j] = input[pi[j]] + Enc(0; u[j]) and shadow[k] = input[sigma[k]] + Enc(0; v[k]),
+                // so output[j] = shadow[rho[j]] + Enc(0; u[j] - v[rho[j]]) where sigma[rho[j]] = pi[j]
+                let mut inverse_sigma = vec![0u32; shadow_permutation.len()];
+                for (k, &source) in shadow_permutation.iter().enumerate() {
+                    inverse_sigma[source as usize] = k as u32;
+                }
+                let rho: Vec<u32> = permutation.iter().map(|&source| inverse_sigma[source as usize]).collect();
+                let deltas = rho.iter().zip(&masks).map(|(&k, u)| subtract(u, &shadow_masks[k as usize])).collect();
+                ShadowOpening::ToOutput { permutation: rho, randomizers: deltas }
+            } else {
+                ShadowOpening::FromInput { permutation: shadow_permutation, randomizers: shadow_masks }
+            };
+            ShadowRound { shadow, opening }
+        })
+        .collect();
+
+    Ok(ShuffleOutput { ballots, proof: ShuffleProof { rounds: proof_rounds }, permutation })
+}
+
+/// Publicly check that `output` is a re-randomized permutation of `input`
+/// under the election key `public_key`.
+pub fn verify_shuffle(
+    input: &[VoteVector],
+    output: &[VoteVector],
+    proof: &ShuffleProof,
+    public_key: &PublicKey,
+    min_rounds: usize,
+) -> Result<(), MixError> {
+    if proof.rounds.len() < min_rounds {
+        return Err(MixError::TooFewRounds { expected: min_rounds, actual: proof.rounds.len() });
+    }
+    let key_fingerprint = &public_key.fingerprint();
+    let a = public_polynomial();
+    let input_coeffs = decode_all(input, key_fingerprint)?;
+    let output_coeffs = decode_all(output, key_fingerprint)?;
+    if input_coeffs.len() != output_coeffs.len() {
+        return Err(MixError::ShapeMismatch);
+    }
+
+    let shadow_ballots: Vec<Vec<VoteVector>> = proof.rounds.iter().map(|r| r.shadow.clone()).collect();
+    let challenges = challenge_bits(input, output, &shadow_ballots);
+
+    for (round, (proof_round, open_to_output)) in proof.rounds.iter().zip(challenges).enumerate() {
+        let fail = |reason: &str| MixError::RoundFailed { round, reason: reason.to_string() };
+        let shadow_coeffs = decode_all(&proof_round.shadow, key_fingerprint)?;
+
+        let (from, to, permutation, masks) = match (&proof_round.opening, open_to_output) {
+            (ShadowOpening::FromInput { permutation, randomizers }, false) => {
+                (&input_coeffs, &shadow_coeffs, permutation, randomizers)
+            }
+            (ShadowOpening::ToOutput { permutation, randomizers }, true) => {
+                (&shadow_coeffs, &output_coeffs, permutation, randomizers)
+            }
+            _ => return Err(fail("opening does not match the challenge")),
+        };
+
+        if !is_permutation(permutation, from.len()) || masks.len() != from.len() {
+            return Err(fail("not a permutation"));
+        }
+        for ((&source, target), masks) in permutation.iter().zip(to).zip(masks) {
+            let source = &from[source as usize];
+            if source.len() != target.len() || masks.len() != target.len() {
+                return Err(fail("re-encryption does not match"));
+            }
+            for ((source, target), mask) in source.iter().zip(target).zip(masks) {
+                check_reencryption(source, target, mask, public_key, &a, open_to_output).map_err(fail)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn decode_all(ballots: &[VoteVector], key_fingerprint: &[u8; 32]) -> Result<Vec<Randomizer>, MixError> {
+    let decoded = ballots
+        .iter()
+        .map(|ballot| {
+            ballot
+                .iter()
+                .map(|bytes| Cipher::<Signed>::deserialize_bound(bytes, key_fingerprint).map(|c| c.ciphertext_data))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if decoded.windows(2).any(|pair| pair[0].len() != pair[1].len()) {
+        return Err(MixError::ShapeMismatch);
+    }
+    Ok(decoded)
+}
+
+fn encode_all(ballots: &[Randomizer], key_fingerprint: &[u8; 32]) -> Vec<VoteVector> {
+    ballots
+        .iter()
+        .map(|ballot| {
+            ballot
+                .iter()
+                .map(|coeffs| Cipher::<Signed> { ciphertext_data: coeffs.clone(), _phantom: std::marker::PhantomData }.serialize_bound(key_fingerprint))
+                .collect()
+        })
+        .collect()
+}
+
+fn random_permutation_and_randomizers(ballots: &[Randomizer], public_key: &PublicKey, rng: &mut impl Rng) -> (Vec<u32>, Vec<Masks>, Vec<Randomizer>) {
+    let mut permutation: Vec<u32> = (0..ballots.len() as u32).collect();
+    permutation.shuffle(rng);
+
+    let masks: Vec<Masks> = ballots
+        .iter()
+        .map(|ballot| ballot.iter().map(|_| (0..POLYNOMIAL_DEGREE).map(|_| rng.gen_range(0..PLAINTEXT_MODULUS)).collect()).collect())
+        .collect();
+    let randomizers = masks
+        .iter()
+        .map(|ballot| ballot.iter().map(|u| zero_encryption(public_key, u, rng)).collect())
+        .collect();
+    (permutation, masks, randomizers)
+}
+
+// (Δ·(b*u mod t) + e, a*u mod t): an encryption of zero with mask u and every e
+// below `REENCRYPTION_NOISE_BOUND`
+fn zero_encryption(public_key: &PublicKey, u: &[u64], rng: &mut impl Rng) -> Vec<u64> {
+    let scaling_factor = CIPHERTEXT_MODULUS / PLAINTEXT_MODULUS;
+    let mut coeffs: Vec<u64> = multiply_polynomials(&public_key.key_data, u)
+        .iter()
+        .map(|&mask| mask * scaling_factor + rng.gen_range(0..REENCRYPTION_NOISE_BOUND))
+        .collect();
+    coeffs.extend(multiply_polynomials(&public_polynomial(), u));
+    coeffs
+}
+
+// c mod q read as the integer in -q/2..q/2 it stands for
+fn centered_mod_q(c: u64) -> i64 {
+    let c = c & (CIPHERTEXT_MODULUS - 1);
+    if c >= CIPHERTEXT_MODULUS / 2 { c as i64 - CIPHERTEXT_MODULUS as i64 } else { c as i64 }
+}
+
+// target - source must be an encryption of zero with mask u (`zero_encryption`),
+// or with `difference` the difference of two, whose c1 stays within ±t and
+// whose noise may carry r = q mod t from the wrap of (b*u1 mod t) - (b*u2 mod t)
+fn check_reencryption(
+    source: &[u64],
+    target: &[u64],
+    u: &[u64],
+    public_key: &PublicKey,
+    a: &[u64],
+    difference: bool,
+) -> Result<(), &'static str> {
+    if source.len() != 2 * POLYNOMIAL_DEGREE
+        || target.len() != source.len()
+        || u.len() != POLYNOMIAL_DEGREE
+        || u.iter().any(|&u| u >= PLAINTEXT_MODULUS)
+    {
+        return Err("re-encryption does not match");
+    }
+    let scaling_factor = CIPHERTEXT_MODULUS / PLAINTEXT_MODULUS;
+    let noise_bound = if difference { REENCRYPTION_NOISE_BOUND + CIPHERTEXT_MODULUS % PLAINTEXT_MODULUS } else { REENCRYPTION_NOISE_BOUND };
+    let masks = multiply_polynomials(&public_key.key_data, u);
+    let c1_masks = multiply_polynomials(a, u);
+    for i in 0..POLYNOMIAL_DEGREE {
+        let noise = centered_mod_q(target[i].wrapping_sub(source[i]).wrapping_sub(masks[i] * scaling_factor));
+        if noise.unsigned_abs() >= noise_bound || (!difference && noise < 0) {
+            return Err("re-encryption noise out of bounds");
+        }
+        let c1 = centered_mod_q(target[POLYNOMIAL_DEGREE + i].wrapping_sub(source[POLYNOMIAL_DEGREE + i]));
+        let c1_ok = if difference {
+            c1.unsigned_abs() < PLAINTEXT_MODULUS && c1.rem_euclid(PLAINTEXT_MODULUS as i64) as u64 == c1_masks[i]
+        } else {
+            c1 == c1_masks[i] as i64
+        };
+        if !c1_ok {
+            return Err("re-encryption does not match");
+        }
+    }
+    Ok(())
+}
+
+// result[j] = source[permutation[j]] + randomizers[j] (mod q)
+fn apply(source: &[Randomizer], permutation: &[u32], randomizers: &[Randomizer]) -> Vec<Randomizer> {
+    permutation
+        .iter()
+        .zip(randomizers)
+        .map(|(&from, randomizer)| {
+            source[from as usize]
+                .iter()
+                .zip(randomizer)
+                .map(|(coeffs, noise)| {
+                    coeffs.iter().zip(noise).map(|(&c, &n)| (c + n) % CIPHERTEXT_MODULUS).collect()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn subtract(a: &Masks, b: &Masks) -> Masks {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| {
+            x.iter().zip(y).map(|(&x, &y)| (x + PLAINTEXT_MODULUS - y) % PLAINTEXT_MODULUS).collect()
+        })
+        .collect()
+}
+
+fn is_permutation(permutation: &[u32], len: usize) -> bool {
+    let mut seen = vec![false; len];
+    permutation.len() == len
+        && permutation.iter().all(|&i| (i as usize) < len && !std::mem::replace(&mut seen[i as usize], true))
+}
+
+fn hash_ballots(buf: &mut Vec<u8>, ballots: &[VoteVector]) {
+    buf.extend_from_slice(&(ballots.len() as u32).to_le_bytes());
+    for ballot in ballots {
+        buf.extend_from_slice(&(ballot.len() as u32).to_le_bytes());
+        for ciphertext in ballot {
+            let digest: [u8; 32] = (*Impl::hash_bytes(ciphertext)).into();
+            buf.extend_from_slice(&digest);
+        }
+    }
+}
+
+// Fiat-Shamir: one bit per round from the hash of the whole statement
+fn challenge_bits(input: &[VoteVector], output: &[VoteVector], shadows: &[Vec<VoteVector>]) -> Vec<bool> {
+    let mut buf = b"fhe-voting/mixnet/shuffle".to_vec();
+    hash_ballots(&mut buf, input);
+    hash_ballots(&mut buf, output);
+    for shadow in shadows {
+        hash_ballots(&mut buf, shadow);
+    }
+    let seed: [u8; 32] = (*Impl::hash_bytes(&buf)).into();
+
+    (0..shadows.len())
+        .map(|round| {
+            let mut block = seed.to_vec();
+            block.extend_from_slice(&((round / 256) as u32).to_le_bytes());
+            let digest: [u8; 32] = (*Impl::hash_bytes(&block)).into();
+            let bit = round % 256;
+            digest[bit / 8] >> (bit % 8) & 1 == 1
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fhe_client::FheClient;
+    use crate::types::VoteOption;
+
+    #[test]
+    fn test_shuffle_preserves_votes_and_detects_tampering() -> Result<(), Box<dyn std::error::Error>> {
+        let client = FheClient::new();
+        let public_key = client.get_public_key();
+        let key_fingerprint = public_key.fingerprint();
+        let choices = [VoteOption::Option1, VoteOption::Option2, VoteOption::Option3, VoteOption::Option2];
+        let input = choices
+            .iter()
+            .map(|&choice| client.encrypt_vote_vector(choice))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mixed = shuffle(&input, public_key, 16)?;
+        verify_shuffle(&input, &mixed.ballots, &mixed.proof, public_key, 16)?;
+
+        for (j, ballot) in mixed.ballots.iter().enumerate() {
+            let choice = choices[mixed.permutation[j] as usize];
+            let plaintexts: Vec<u64> = ballot
+                .iter()
+                .map(|bytes| Ok(client.decrypt(&Cipher::<Signed>::deserialize_bound(bytes, &key_fingerprint)?)?.coefficients()[0]))
+                .collect::<Result<_, FheClientError>>()?;
+            assert_eq!(plaintexts[choice as usize - 1], 1);
+            assert_eq!(plaintexts.iter().sum::<u64>(), 1);
+        }
+
+        // Swapping in a fresh vote for a different candidate must break the proof
+        let mut tampered = mixed.ballots.clone();
+        let original = choices[mixed.permutation[0] as usize];
+        let other = if original == VoteOption::Option1 { VoteOption::Option2 } else { VoteOption::Option1 };
+        tampered[0] = client.encrypt_vote_vector(other)?;
+        assert!(verify_shuffle(&input, &tampered, &mixed.proof, public_key, 16).is_err());
+        Ok(())
+    }
+}
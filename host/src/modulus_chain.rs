@@ -0,0 +1,343 @@
+// RNS ciphertext modulus chain.
+// The voting scheme works mod a single q = 2^58, which rules out the NTT on
+// ciphertexts and leaves nothing to switch down to. Realistic BFV/BGV/CKKS
+// parameters use a chain of NTT-friendly primes q_0, ..., q_L (every q_i = 1
+// mod 2N) and keep a ciphertext as its residues mod each prime (RNS form).
+//
+// An `RnsCipher` is at level l when it has residues mod q_0..=q_l, that is mod
+// Q_l = q_0·…·q_l. `drop_level` discards the top residue, which leaves the same
+// value mod Q_(l-1): modulus switching and rescaling build on it by first
+// dividing by q_l. Operands of an addition or product must be at the same
+// level, and every prime multiplies polynomials with its own NTT tables.
+
+use thiserror::Error;
+
+use crate::fhe_client::POLYNOMIAL_DEGREE;
+use crate::lattigo::is_prime;
+
+/// Bits of the primes in the default chain; sums of two residues stay below 2^64.
+pub const CHAIN_PRIME_BITS: u32 = 50;
+
+/// Primes in the default chain (levels 0 to 2).
+pub const DEFAULT_CHAIN_LENGTH: usize = 3;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ModulusChainError {
+    #[error("The modulus chain needs at least one prime")]
+    EmptyChain,
+    #[error("Modulus {modulus} is not an NTT-friendly prime below 2^62 (needs a prime = 1 mod {})", 2 * POLYNOMIAL_DEGREE)]
+    NotNttFriendly { modulus: u64 },
+    #[error("Modulus {modulus} appears twice in the chain")]
+    DuplicatePrime { modulus: u64 },
+    #[error("Only {found} NTT-friendly primes of {bits} bits exist, {wanted} wanted")]
+    NotEnoughPrimes { bits: u32, wanted: usize, found: usize },
+    #[error("Ciphertexts at levels {lhs} and {rhs} cannot be combined")]
+    LevelMismatch { lhs: usize, rhs: usize },
+    #[error("A ciphertext at level 0 has no level to drop")]
+    NoLevelToDrop,
+    #[error("Expected {expected} coefficients, got {actual}")]
+    InvalidLength { expected: usize, actual: usize },
+    #[error("Coefficient {index} does not fit in an i128 at this level")]
+    LiftOverflow { index: usize },
+}
+
+/// Negacyclic NTT over Z_q[X]/(X^N + 1) for one prime of the chain.
+#[derive(Debug, Clone)]
+struct PrimeNtt {
+    modulus: u64,
+    psi_powers: Vec<u64>,     // psi^bitrev(i), psi a primitive 2N-th root of unity
+    psi_inv_powers: Vec<u64>, // psi^-bitrev(i)
+    degree_inv: u64,          // N^-1 mod q
+}
+
+impl PrimeNtt {
+    fn new(modulus: u64) -> Self {
+        let two_n = 2 * POLYNOMIAL_DEGREE as u64;
+        // psi is primitive iff psi^N = -1
+        let psi = (2..)
+            .map(|g| pow_mod(g, (modulus - 1) / two_n, modulus))
+            .find(|&psi| pow_mod(psi, POLYNOMIAL_DEGREE as u64, modulus) == modulus - 1)
+            .expect("an NTT-friendly prime has a primitive 2N-th root of unity");
+        let psi_inv = pow_mod(psi, modulus - 2, modulus);
+        let bits = POLYNOMIAL_DEGREE.trailing_zeros();
+        let bitrev = |i: usize| (i.reverse_bits() >> (usize::BITS - bits)) as u64;
+
+        PrimeNtt {
+            modulus,
+            psi_powers: (0..POLYNOMIAL_DEGREE).map(|i| pow_mod(psi, bitrev(i), modulus)).collect(),
+            psi_inv_powers: (0..POLYNOMIAL_DEGREE).map(|i| pow_mod(psi_inv, bitrev(i), modulus)).collect(),
+            degree_inv: pow_mod(POLYNOMIAL_DEGREE as u64, modulus - 2, modulus),
+        }
+    }
+
+    // Cooley-Tukey, bit-reversed output (as the guest's plaintext NTT)
+    fn forward(&self, a: &mut [u64]) {
+        let q = self.modulus;
+        let n = a.len();
+        let mut t = n;
+        let mut m = 1;
+        while m < n {
+            t /= 2;
+            for i in 0..m {
+                let s = self.psi_powers[m + i];
+                for j in 2 * i * t..2 * i * t + t {
+                    let u = a[j];
+                    let v = mul_mod(a[j + t], s, q);
+                    a[j] = (u + v) % q;
+                    a[j + t] = (u + q - v) % q;
+                }
+            }
+            m *= 2;
+        }
+    }
+
+    // Gentleman-Sande, bit-reversed input
+    fn inverse(&self, a: &mut [u64]) {
+        let q = self.modulus;
+        let n = a.len();
+        let mut t = 1;
+        let mut m = n;
+        while m > 1 {
+            let h = m / 2;
+            for i in 0..h {
+                let s = self.psi_inv_powers[h + i];
+                for j in 2 * i * t..2 * i * t + t {
+                    let u = a[j];
+                    let v = a[j + t];
+                    a[j] = (u + v) % q;
+                    a[j + t] = mul_mod((u + q - v) % q, s, q);
+                }
+            }
+            t *= 2;
+            m = h;
+        }
+        for x in a.iter_mut() {
+            *x = mul_mod(*x, self.degree_inv, q);
+        }
+    }
+
+    fn multiply(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let (mut fa, mut fb) = (a.to_vec(), b.to_vec());
+        self.forward(&mut fa);
+        self.forward(&mut fb);
+        let mut product: Vec<u64> = fa.iter().zip(&fb).map(|(&x, &y)| mul_mod(x, y, self.modulus)).collect();
+        self.inverse(&mut product);
+        product
+    }
+}
+
+/// A chain of NTT-friendly primes q_0, ..., q_L; level l works mod q_0·…·q_l.
+#[derive(Debug, Clone)]
+pub struct ModulusChain {
+    primes: Vec<PrimeNtt>,
+}
+
+impl ModulusChain {
+    pub fn new(primes: &[u64]) -> Result<Self, ModulusChainError> {
+        if primes.is_empty() {
+            return Err(ModulusChainError::EmptyChain);
+        }
+        let two_n = 2 * POLYNOMIAL_DEGREE as u64;
+        if let Some(&modulus) = primes.iter().find(|&&q| q >= 1 << 62 || q % two_n != 1 || !is_prime(q)) {
+            return Err(ModulusChainError::NotNttFriendly { modulus });
+        }
+        if let Some(modulus) = primes.iter().enumerate().find(|&(index, q)| primes[..index].contains(q)).map(|(_, &q)| q) {
+            return Err(ModulusChainError::DuplicatePrime { modulus });
+        }
+        Ok(ModulusChain { primes: primes.iter().map(|&q| PrimeNtt::new(q)).collect() })
+    }
+
+    /// The `length` largest NTT-friendly primes below 2^bits, largest first.
+    pub fn generate(length: usize, bits: u32) -> Result<Self, ModulusChainError> {
+        let two_n = 2 * POLYNOMIAL_DEGREE as u64;
+        let bits = bits.min(62);
+        let primes: Vec<u64> = (1..(1u64 << bits) / two_n)
+            .rev()
+            .map(|k| k * two_n + 1)
+            .filter(|&q| q > 1 << (bits - 1) && is_prime(q))
+            .take(length)
+            .collect();
+        if primes.len() < length {
+            return Err(ModulusChainError::NotEnoughPrimes { bits, wanted: length, found: primes.len() });
+        }
+        Self::new(&primes)
+    }
+
+    pub fn primes(&self) -> Vec<u64> {
+        self.primes.iter().map(|ntt| ntt.modulus).collect()
+    }
+
+    /// Level of a fresh ciphertext, L.
+    pub fn top_level(&self) -> usize {
+        self.primes.len() - 1
+    }
+
+    /// log2 of Q_level, the modulus a ciphertext at `level` works under.
+    pub fn modulus_bits(&self, level: usize) -> f64 {
+        self.primes[..=level].iter().map(|ntt| (ntt.modulus as f64).log2()).sum()
+    }
+
+    /// Encode signed coefficients (c0 then c1, as in `Cipher`) at the top level.
+    pub fn encode(&self, coefficients: &[i64]) -> Result<RnsCipher, ModulusChainError> {
+        let expected = 2 * POLYNOMIAL_DEGREE;
+        if coefficients.len() != expected {
+            return Err(ModulusChainError::InvalidLength { expected, actual: coefficients.len() });
+        }
+        let residues = self
+            .primes
+            .iter()
+            .map(|ntt| coefficients.iter().map(|&c| c.rem_euclid(ntt.modulus as i64) as u64).collect())
+            .collect();
+        Ok(RnsCipher { level: self.top_level(), residues })
+    }
+}
+
+impl Default for ModulusChain {
+    fn default() -> Self {
+        Self::generate(DEFAULT_CHAIN_LENGTH, CHAIN_PRIME_BITS).expect("the default chain has enough primes")
+    }
+}
+
+/// A ciphertext in RNS form: its residues mod q_0..=q_level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RnsCipher {
+    level: usize,
+    residues: Vec<Vec<u64>>, // residues[i]: the 2N coefficients mod q_i
+}
+
+impl RnsCipher {
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    pub fn add_assign(&mut self, other: &RnsCipher, chain: &ModulusChain) -> Result<(), ModulusChainError> {
+        self.check_level(other)?;
+        for ((lhs, rhs), ntt) in self.residues.iter_mut().zip(&other.residues).zip(&chain.primes) {
+            for (a, &b) in lhs.iter_mut().zip(rhs) {
+                *a = (*a + b) % ntt.modulus;
+            }
+        }
+        Ok(())
+    }
+
+    /// Multiply both components by the plaintext polynomial `plain` mod X^N + 1.
+    pub fn mul_plain_poly(&self, plain: &[i64], chain: &ModulusChain) -> Result<RnsCipher, ModulusChainError> {
+        if plain.len() != POLYNOMIAL_DEGREE {
+            return Err(ModulusChainError::InvalidLength { expected: POLYNOMIAL_DEGREE, actual: plain.len() });
+        }
+        let residues = self
+            .residues
+            .iter()
+            .zip(&chain.primes)
+            .map(|(residue, ntt)| {
+                let plain: Vec<u64> = plain.iter().map(|&c| c.rem_euclid(ntt.modulus as i64) as u64).collect();
+                residue.chunks(POLYNOMIAL_DEGREE).flat_map(|component| ntt.multiply(component, &plain)).collect()
+            })
+            .collect();
+        Ok(RnsCipher { level: self.level, residues })
+    }
+
+    /// Discard the residue mod q_level. The value is unchanged mod Q_(level-1).
+    pub fn drop_level(&mut self) -> Result<(), ModulusChainError> {
+        if self.level == 0 {
+            return Err(ModulusChainError::NoLevelToDrop);
+        }
+        self.residues.pop();
+        self.level -= 1;
+        Ok(())
+    }
+
+    /// The centered representative of every coefficient mod Q_level (CRT by
+    /// Garner's mixed radix), failing if one does not fit in an i128.
+    pub fn lift(&self, chain: &ModulusChain) -> Result<Vec<i128>, ModulusChainError> {
+        let primes = &chain.primes[..=self.level];
+        (0..2 * POLYNOMIAL_DEGREE)
+            .map(|index| {
+                let mut value: i128 = 0;
+                let mut radix: Option<i128> = Some(1); // Q_(i-1), while it fits
+                for (i, ntt) in primes.iter().enumerate() {
+                    let q = ntt.modulus;
+                    let radix_mod_q = primes[..i].iter().fold(1, |acc, p| mul_mod(acc, p.modulus % q, q));
+                    let gap = (self.residues[i][index] + q - value.rem_euclid(q as i128) as u64) % q;
+                    let digit = mul_mod(gap, pow_mod(radix_mod_q, q - 2, q), q);
+                    let digit = if digit > q / 2 { digit as i128 - q as i128 } else { digit as i128 };
+                    if digit != 0 {
+                        let term = radix.and_then(|radix| radix.checked_mul(digit)).ok_or(ModulusChainError::LiftOverflow { index })?;
+                        value = value.checked_add(term).ok_or(ModulusChainError::LiftOverflow { index })?;
+                    }
+                    radix = radix.and_then(|radix| radix.checked_mul(q as i128));
+                }
+                Ok(value)
+            })
+            .collect()
+    }
+
+    fn check_level(&self, other: &RnsCipher) -> Result<(), ModulusChainError> {
+        if self.level != other.level {
+            return Err(ModulusChainError::LevelMismatch { lhs: self.level, rhs: other.level });
+        }
+        Ok(())
+    }
+}
+
+fn mul_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    (a as u128 * b as u128 % modulus as u128) as u64
+}
+
+fn pow_mod(mut base: u64, mut exp: u64, modulus: u64) -> u64 {
+    let mut result = 1;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, modulus);
+        }
+        base = mul_mod(base, base, modulus);
+        exp >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_chain_is_ntt_friendly() -> Result<(), ModulusChainError> {
+        let chain = ModulusChain::default();
+        let primes = chain.primes();
+        assert_eq!(primes.len(), DEFAULT_CHAIN_LENGTH);
+        assert!(primes.windows(2).all(|pair| pair[0] > pair[1]));
+        assert!(primes.iter().all(|&q| q % (2 * POLYNOMIAL_DEGREE as u64) == 1 && q >> (CHAIN_PRIME_BITS - 1) == 1));
+        assert!(chain.modulus_bits(chain.top_level()) > 149.0);
+
+        assert_eq!(ModulusChain::new(&[]).err(), Some(ModulusChainError::EmptyChain));
+        assert_eq!(ModulusChain::new(&[1 << 58]).err(), Some(ModulusChainError::NotNttFriendly { modulus: 1 << 58 }));
+        assert_eq!(ModulusChain::new(&[primes[0], primes[0]]).err(), Some(ModulusChainError::DuplicatePrime { modulus: primes[0] }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_levels_track_the_residues_kept() -> Result<(), ModulusChainError> {
+        let chain = ModulusChain::default();
+        let coefficients: Vec<i64> = (0..2 * POLYNOMIAL_DEGREE as i64).map(|i| (i - 20) * 1_000_003).collect();
+        let mut sum = chain.encode(&coefficients)?;
+        sum.add_assign(&chain.encode(&coefficients)?, &chain)?;
+        assert_eq!(sum.level(), 2);
+        let doubled: Vec<i128> = coefficients.iter().map(|&c| 2 * i128::from(c)).collect();
+        assert_eq!(sum.lift(&chain)?, doubled);
+
+        // X · (c0 + c1·Y) rotates each component negacyclically
+        let mut x = vec![0; POLYNOMIAL_DEGREE];
+        x[1] = 1;
+        let shifted = sum.mul_plain_poly(&x, &chain)?.lift(&chain)?;
+        assert_eq!((shifted[0], shifted[1]), (-doubled[POLYNOMIAL_DEGREE - 1], doubled[0]));
+
+        // Small values survive dropping levels; mixed levels cannot be added
+        sum.drop_level()?;
+        sum.drop_level()?;
+        assert_eq!((sum.level(), sum.lift(&chain)?), (0, doubled));
+        assert_eq!(sum.drop_level(), Err(ModulusChainError::NoLevelToDrop));
+        let fresh = chain.encode(&coefficients)?;
+        assert_eq!(sum.add_assign(&fresh, &chain), Err(ModulusChainError::LevelMismatch { lhs: 0, rhs: 2 }));
+        Ok(())
+    }
+}
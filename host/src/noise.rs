@@ -0,0 +1,179 @@
+// Offline noise-growth estimates.
+// Proving a large tally takes hours, and parameters whose noise outgrows what
+// decryption tolerates only show up as a wrong count at the very end.
+// `NoiseModel` carries a worst-case bound on the error in the plaintext-carrying
+// coefficient through the steps the guests take, so an operator can size an
+// election or a circuit before proving anything.
+//
+// Decryption rounds c[0] / Δ, so it is correct while the error stays below
+// Δ / 2. A fresh encryption carries at most the largest sample of its noise
+// distribution and every mixnet re-encryption adds below
+// `REENCRYPTION_NOISE_BOUND`. Additions and subtractions add the bounds, plus
+// r = q mod t when the scaled value wraps mod t (q = t·Δ + r, so a wrap leaves
+// r behind). The public key's mask makes that value uniform mod t, so any
+// addition, re-encryption or negation may wrap, however small the count. A
+// multiplication by k scales the bound by k and adds up to (k - 1)·r for the
+// wraps; rotations permute coefficients and negate some. Every bound is a
+// worst case: the noise actually drawn is far smaller.
+
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::circuit::CircuitProgram;
+use crate::fhe_client::{max_tally_ballots, FheClientError, FheParameters, CIPHERTEXT_MODULUS, PLAINTEXT_MODULUS};
+use crate::mixnet::REENCRYPTION_NOISE_BOUND;
+use crate::types::CircuitOp;
+
+#[derive(Error, Debug)]
+pub enum NoiseError {
+    #[error("Noise model failed: {0}")]
+    Parameters(#[from] FheClientError),
+    #[error("{ballots} ballots could exceed the plaintext modulus (at most {max} can be tallied)")]
+    Overflow { ballots: u64, max: u64 },
+    #[error("Op {op} reads register {name} before it is written")]
+    UnwrittenRegister { op: usize, name: String },
+    #[error("Output register {name} is never written")]
+    MissingOutput { name: String },
+    #[error("Worst-case noise {worst_case} in {what} reaches the decryption bound {limit}")]
+    ExceedsBound { what: String, worst_case: u128, limit: u64 },
+}
+
+/// Worst-case error of a ciphertext against the error decryption tolerates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoiseBound {
+    pub worst_case: u128,
+    pub limit: u64,
+}
+
+impl NoiseBound {
+    pub fn decrypts(&self) -> bool {
+        self.worst_case < u128::from(self.limit)
+    }
+
+    /// Bits of headroom left; zero or less once decryption can fail.
+    pub fn budget_bits(&self) -> f64 {
+        (self.limit as f64).log2() - (self.worst_case.max(1) as f64).log2()
+    }
+
+    fn check(self, what: impl Into<String>) -> Result<Self, NoiseError> {
+        if !self.decrypts() {
+            return Err(NoiseError::ExceedsBound { what: what.into(), worst_case: self.worst_case, limit: self.limit });
+        }
+        Ok(self)
+    }
+}
+
+/// Worst-case noise growth under a set of FHE parameters.
+#[derive(Debug, Clone, Copy)]
+pub struct NoiseModel {
+    fresh: u128,
+    wrap: u128, // r = q mod t, left behind whenever a plaintext wraps mod t
+    limit: u64,
+}
+
+impl NoiseModel {
+    pub fn new(parameters: &FheParameters) -> Result<Self, NoiseError> {
+        parameters.validate()?;
+        Ok(NoiseModel {
+            fresh: parameters.noise.max_magnitude().into(),
+            wrap: (CIPHERTEXT_MODULUS % PLAINTEXT_MODULUS).into(),
+            limit: CIPHERTEXT_MODULUS / PLAINTEXT_MODULUS / 2,
+        })
+    }
+
+    /// Each candidate's tally over `ballots` ballots, every one re-encrypted
+    /// `mix_rounds` times by the mixnet. Fails if the tally might not decrypt.
+    pub fn tally(&self, ballots: u64, mix_rounds: u32) -> Result<NoiseBound, NoiseError> {
+        if ballots > max_tally_ballots() {
+            return Err(NoiseError::Overflow { ballots, max: max_tally_ballots() });
+        }
+        self.bound(u128::from(ballots).saturating_mul(self.ballot_noise(mix_rounds) + self.wrap)).check("the tally")
+    }
+
+    /// Most ballots a tally can count after `mix_rounds` re-encryptions, by
+    /// both noise and plaintext capacity.
+    pub fn max_ballots(&self, mix_rounds: u32) -> u64 {
+        let by_noise = (u128::from(self.limit) - 1) / (self.ballot_noise(mix_rounds) + self.wrap);
+        u64::try_from(by_noise).unwrap_or(u64::MAX).min(max_tally_ballots())
+    }
+
+    /// Every output register of `program`, whose inputs are fresh encryptions.
+    /// Fails on the first output that might not decrypt.
+    pub fn circuit(&self, program: &CircuitProgram) -> Result<Vec<(String, NoiseBound)>, NoiseError> {
+        let mut registers: HashMap<&str, u128> = program.inputs.iter().map(|(name, _)| (name.as_str(), self.fresh)).collect();
+        for (index, op) in program.ops.iter().enumerate() {
+            let read = |name: &String| {
+                registers.get(name.as_str()).copied().ok_or_else(|| NoiseError::UnwrittenRegister { op: index, name: name.clone() })
+            };
+            let (out, noise) = match op {
+                CircuitOp::Add { out, lhs, rhs } | CircuitOp::Sub { out, lhs, rhs } => {
+                    (out, read(lhs)?.saturating_add(read(rhs)?).saturating_add(self.wrap))
+                }
+                CircuitOp::MulPlain { out, input, scalar } => {
+                    let scalar = u128::from(*scalar);
+                    (out, read(input)?.saturating_mul(scalar).saturating_add(scalar.saturating_sub(1).saturating_mul(self.wrap)))
+                }
+                CircuitOp::Rotate { out, input, .. } => (out, read(input)?.saturating_add(self.wrap)),
+            };
+            registers.insert(out, noise);
+        }
+        program
+            .outputs
+            .iter()
+            .map(|name| {
+                let noise = registers.get(name.as_str()).copied().ok_or_else(|| NoiseError::MissingOutput { name: name.clone() })?;
+                Ok((name.clone(), self.bound(noise).check(format!("output register {}", name))?))
+            })
+            .collect()
+    }
+
+    // A ballot's noise after `mix_rounds` re-encryptions, each of which may wrap
+    fn ballot_noise(&self, mix_rounds: u32) -> u128 {
+        self.fresh + u128::from(mix_rounds) * (u128::from(REENCRYPTION_NOISE_BOUND - 1) + self.wrap)
+    }
+
+    fn bound(&self, worst_case: u128) -> NoiseBound {
+        NoiseBound { worst_case, limit: self.limit }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fhe_client::NoiseDistribution;
+
+    #[test]
+    fn test_tallies_within_capacity_decrypt() -> Result<(), NoiseError> {
+        let model = NoiseModel::new(&FheParameters::default())?;
+        let tally = model.tally(max_tally_ballots(), 10)?;
+        assert!(tally.decrypts() && tally.budget_bits() > 5.0);
+        assert_eq!(model.max_ballots(10), max_tally_ballots());
+        assert!(matches!(model.tally(max_tally_ballots() + 1, 0), Err(NoiseError::Overflow { .. })));
+
+        // The binomial bound is eta itself, and each addition may wrap
+        let binomial = FheParameters { noise: NoiseDistribution::CenteredBinomial { eta: 4 }, ..FheParameters::default() };
+        assert_eq!(NoiseModel::new(&binomial)?.tally(3, 0)?.worst_case, 3 * (4 + 64513));
+        Ok(())
+    }
+
+    #[test]
+    fn test_repeated_scalar_multiplication_exhausts_the_budget() -> Result<(), NoiseError> {
+        let model = NoiseModel::new(&FheParameters::default())?;
+        let scale = |out: &str, input: &str| CircuitOp::MulPlain { out: out.into(), input: input.into(), scalar: 1000 };
+        let mut program = CircuitProgram {
+            inputs: vec![("x".into(), 1)],
+            ops: vec![scale("y", "x"), scale("z", "y"), CircuitOp::Rotate { out: "w".into(), input: "z".into(), steps: 3 }],
+            outputs: vec!["w".into()],
+        };
+        let outputs = model.circuit(&program)?;
+        assert!(outputs[0].1.decrypts());
+
+        // A third multiplication by 1000 can no longer be rounded off
+        program.ops.push(scale("w", "w"));
+        assert!(matches!(model.circuit(&program), Err(NoiseError::ExceedsBound { .. })));
+        program.outputs = vec!["v".into()];
+        assert!(matches!(model.circuit(&program), Err(NoiseError::MissingOutput { .. })));
+        Ok(())
+    }
+}
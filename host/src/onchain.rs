@@ -0,0 +1,336 @@
+// Posting proven results on-chain.
+// The governance contract (contracts/FheVotingResults.sol) takes the compact
+// journal and a seal, checks the journal's election id and chain id (at
+// `ELECTION_ID_OFFSET` and `CHAIN_ID_OFFSET`) against the election it was
+// deployed for, and asks the RISC Zero verifier router to check the seal
+// against its pinned guest image id and the journal's SHA-256 before reading
+// the counts out of the journal and finalizing the election.
+//
+// Seals use the router's encoding: a 4-byte selector naming the verifier,
+// followed by the proof. Groth16 receipts are selected by the first bytes of
+// their verifier parameters digest; dev-mode fake receipts get the mock
+// verifier's 0xffffffff selector followed by the claim digest, which only a
+// test deployment accepts. Other receipt kinds are too large to verify
+// on-chain and must be compressed to Groth16 first.
+//
+// A tally proven with an `Eip712Domain` also commits `result_typed_digest`,
+// the EIP-712 digest of its counts and commitments typed as
+//
+//   TallyResult(uint32 option1,uint32 option2,uint32 option3,uint32 totalVotes,
+//               bytes32 ballotSetRoot,bytes32 auditLogHead)
+//
+// under the domain { name: "FHE Voting", version: "1", chainId,
+// verifyingContract }, which the guest computes with accelerated Keccak-256.
+// `result_typed_digest` here mirrors it, so the host can check the committed
+// value and a contract can recompute it with `keccak256` alone.
+//
+// With the `ethereum` feature, `EthereumClient` submits the call through a
+// node's JSON-RPC `eth_sendTransaction`, so the sending account must be
+// unlocked at the node (e.g. anvil or a signer proxy).
+
+use risc0_zkvm::sha::Digestible;
+use risc0_zkvm::{InnerReceipt, Receipt};
+use sha3::{Digest, Keccak256};
+use thiserror::Error;
+
+use crate::types::{Eip712Domain, VoteTallyOutput};
+
+/// Signature of the contract's finalization entry point.
+pub const FINALIZE_SIGNATURE: &str = "finalizeResults(bytes,bytes)";
+
+/// Router selector for the mock verifier that accepts fake receipts.
+pub const MOCK_SELECTOR: [u8; 4] = [0xff; 4];
+
+/// Where the contract reads the election id and chain id in a compact journal.
+pub const ELECTION_ID_OFFSET: usize = 256;
+pub const CHAIN_ID_OFFSET: usize = 288;
+/// Where it reads the encrypted tally frame count; zero (withheld counts) never finalizes.
+pub const TALLY_FRAMES_OFFSET: usize = 20;
+
+#[derive(Error, Debug)]
+pub enum OnchainError {
+    #[error("{kind} receipts cannot be verified on-chain; prove with Groth16 compression")]
+    UnsupportedReceipt { kind: &'static str },
+    #[error("Malformed Ethereum address {address}")]
+    InvalidAddress { address: String },
+    #[error("JSON-RPC request failed: {reason}")]
+    Rpc { reason: String },
+    #[error("Transaction {tx_hash} reverted")]
+    Reverted { tx_hash: String },
+    #[error("Transaction {tx_hash} was not mined in time")]
+    Timeout { tx_hash: String },
+}
+
+/// Encode a receipt's seal for the verifier router.
+pub fn encode_seal(receipt: &Receipt) -> Result<Vec<u8>, OnchainError> {
+    let (selector, proof) = match &receipt.inner {
+        InnerReceipt::Groth16(inner) => (inner.verifier_parameters.as_bytes()[..4].to_vec(), inner.seal.clone()),
+        InnerReceipt::Fake(inner) => (MOCK_SELECTOR.to_vec(), inner.claim.digest().as_bytes().to_vec()),
+        InnerReceipt::Composite(_) => return Err(OnchainError::UnsupportedReceipt { kind: "Composite" }),
+        InnerReceipt::Succinct(_) => return Err(OnchainError::UnsupportedReceipt { kind: "Succinct" }),
+        _ => return Err(OnchainError::UnsupportedReceipt { kind: "Unknown" }),
+    };
+    Ok([selector, proof].concat())
+}
+
+/// Calldata for `finalizeResults(journal, seal)`.
+pub fn finalize_calldata(journal: &[u8], seal: &[u8]) -> Vec<u8> {
+    let mut calldata = Keccak256::digest(FINALIZE_SIGNATURE.as_bytes())[..4].to_vec();
+    calldata.extend(abi_encode_bytes(&[journal, seal]));
+    calldata
+}
+
+/// ABI encoding of a tuple of dynamic `bytes`: offsets, then length-prefixed padded data.
+pub fn abi_encode_bytes(args: &[&[u8]]) -> Vec<u8> {
+    let mut head = Vec::new();
+    let mut tail = Vec::new();
+    for arg in args {
+        head.extend(abi_word(args.len() * 32 + tail.len()));
+        tail.extend(abi_word(arg.len()));
+        tail.extend_from_slice(arg);
+        tail.resize(tail.len().next_multiple_of(32), 0);
+    }
+    head.extend(tail);
+    head
+}
+
+/// Decode ABI-encoded data holding a single `bytes` value, such as an event's data.
+pub fn abi_decode_bytes(data: &[u8]) -> Option<Vec<u8>> {
+    let word = |at: usize| -> Option<usize> {
+        let word = data.get(at..at.checked_add(32)?)?;
+        word[..24].iter().all(|&b| b == 0).then(|| u64::from_be_bytes(word[24..].try_into().unwrap()) as usize)
+    };
+    let offset = word(0)?;
+    let len = word(offset)?;
+    let start = offset.checked_add(32)?;
+    data.get(start..start.checked_add(len)?).map(<[u8]>::to_vec)
+}
+
+pub(crate) fn abi_word(value: usize) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&(value as u64).to_be_bytes());
+    word
+}
+
+/// Parse a 0x-prefixed hex address, checking its EIP-55 checksum if it has one.
+pub fn parse_address(address: &str) -> Result<[u8; 20], OnchainError> {
+    crate::address::parse_address(address).map_err(|_| OnchainError::InvalidAddress { address: address.to_string() })
+}
+
+/// Ethereum address of an uncompressed secp256k1 key (x || y, without the 0x04 prefix).
+pub fn address_from_public_key(public_key: &[u8; 64]) -> [u8; 20] {
+    Keccak256::digest(public_key)[12..].try_into().unwrap()
+}
+
+// Typed data for the result (must match guest implementation)
+const DOMAIN_TYPE: &str = "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+const RESULT_TYPE: &str = "TallyResult(uint32 option1,uint32 option2,uint32 option3,uint32 totalVotes,bytes32 ballotSetRoot,bytes32 auditLogHead)";
+const DOMAIN_NAME: &str = "FHE Voting";
+const DOMAIN_VERSION: &str = "1";
+
+pub fn domain_separator(domain: &Eip712Domain) -> [u8; 32] {
+    let mut address = [0u8; 32];
+    address[12..].copy_from_slice(&domain.verifying_contract);
+    let mut hasher = Keccak256::new();
+    hasher.update(Keccak256::digest(DOMAIN_TYPE));
+    hasher.update(Keccak256::digest(DOMAIN_NAME));
+    hasher.update(Keccak256::digest(DOMAIN_VERSION));
+    hasher.update(abi_word(domain.chain_id as usize));
+    hasher.update(address);
+    hasher.finalize().into()
+}
+
+/// keccak256(0x19 || 0x01 || domain separator || struct hash).
+pub fn typed_data_digest(domain: &Eip712Domain, struct_hash: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update([0x19, 0x01]);
+    hasher.update(domain_separator(domain));
+    hasher.update(struct_hash);
+    hasher.finalize().into()
+}
+
+/// EIP-712 digest the guest commits for `output` under `domain`.
+pub fn result_typed_digest(domain: &Eip712Domain, output: &VoteTallyOutput) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(Keccak256::digest(RESULT_TYPE));
+    for count in [output.option1_count, output.option2_count, output.option3_count, output.total_votes] {
+        hasher.update(abi_word(count as usize));
+    }
+    hasher.update(output.ballot_set_root);
+    hasher.update(output.audit_log_head);
+    typed_data_digest(domain, &hasher.finalize().into())
+}
+
+#[cfg(feature = "ethereum")]
+pub use client::{EthereumClient, JsonRpc};
+
+#[cfg(feature = "ethereum")]
+mod client {
+    use std::time::{Duration, Instant};
+
+    use serde_json::{json, Value};
+
+    use super::{finalize_calldata, parse_address, OnchainError};
+
+    fn rpc_error(e: impl ToString) -> OnchainError {
+        OnchainError::Rpc { reason: e.to_string() }
+    }
+
+    /// Minimal JSON-RPC connection to a node.
+    pub struct JsonRpc {
+        http: reqwest::blocking::Client,
+        url: String,
+    }
+
+    impl JsonRpc {
+        pub fn new(url: &str) -> Self {
+            JsonRpc { http: reqwest::blocking::Client::new(), url: url.to_string() }
+        }
+
+        pub fn call(&self, method: &str, params: Value) -> Result<Value, OnchainError> {
+            let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+            let mut response: Value = self.http.post(&self.url).json(&request).send().map_err(rpc_error)?.json().map_err(rpc_error)?;
+            if let Some(error) = response.get("error") {
+                return Err(rpc_error(error));
+            }
+            Ok(response["result"].take())
+        }
+    }
+
+    /// Submits finalization transactions through a node's JSON-RPC endpoint.
+    pub struct EthereumClient {
+        rpc: JsonRpc,
+        from: [u8; 20],
+        contract: [u8; 20],
+    }
+
+    impl EthereumClient {
+        pub fn new(rpc_url: &str, from: &str, contract: &str) -> Result<Self, OnchainError> {
+            Ok(EthereumClient { rpc: JsonRpc::new(rpc_url), from: parse_address(from)?, contract: parse_address(contract)? })
+        }
+
+        /// Send `finalizeResults(journal, seal)`; returns the transaction hash.
+        pub fn finalize_results(&self, journal: &[u8], seal: &[u8]) -> Result<String, OnchainError> {
+            self.send_transaction(&finalize_calldata(journal, seal))
+        }
+
+        /// Send arbitrary calldata to the contract; returns the transaction hash.
+        pub fn send_transaction(&self, calldata: &[u8]) -> Result<String, OnchainError> {
+            let transaction = json!({
+                "from": format!("0x{}", hex::encode(self.from)),
+                "to": format!("0x{}", hex::encode(self.contract)),
+                "data": format!("0x{}", hex::encode(calldata)),
+            });
+            match self.rpc.call("eth_sendTransaction", json!([transaction]))? {
+                Value::String(tx_hash) => Ok(tx_hash),
+                other => Err(rpc_error(format!("unexpected transaction hash {}", other))),
+            }
+        }
+
+        /// Poll until the transaction is mined, failing if it reverted.
+        pub fn wait_for_receipt(&self, tx_hash: &str, timeout: Duration) -> Result<Value, OnchainError> {
+            let deadline = Instant::now() + timeout;
+            loop {
+                let receipt = self.rpc.call("eth_getTransactionReceipt", json!([tx_hash]))?;
+                if !receipt.is_null() {
+                    if receipt["status"] != "0x1" {
+                        return Err(OnchainError::Reverted { tx_hash: tx_hash.to_string() });
+                    }
+                    return Ok(receipt);
+                }
+                if Instant::now() >= deadline {
+                    return Err(OnchainError::Timeout { tx_hash: tx_hash.to_string() });
+                }
+                std::thread::sleep(Duration::from_secs(1));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use risc0_zkvm::{FakeReceipt, ReceiptClaim};
+    use crate::journal::encode_compact_journal;
+    use crate::types::{Digest32, ElectionContext, Outcome, Turnout, REJECTION_REASONS};
+
+    #[test]
+    fn test_fake_receipt_seal_and_calldata() -> Result<(), OnchainError> {
+        let journal = vec![1u8; 40];
+        let claim = ReceiptClaim::ok([5u8; 32], journal.clone());
+        let claim_digest = claim.digest();
+        let receipt = Receipt::new(InnerReceipt::Fake(FakeReceipt::new(claim)), journal.clone());
+
+        let seal = encode_seal(&receipt)?;
+        assert_eq!(seal[..4], MOCK_SELECTOR);
+        assert_eq!(seal[4..], *claim_digest.as_bytes());
+
+        // selector | offset 0x40 | offset 0x40 + 32 + 64 | len 40 | 64 padded bytes | len 36 | 64 padded bytes
+        let calldata = finalize_calldata(&journal, &seal);
+        assert_eq!(calldata.len(), 4 + 32 * 2 + (32 + 64) * 2);
+        assert_eq!(calldata[4..36], abi_word(0x40));
+        assert_eq!(calldata[36..68], abi_word(0x40 + 32 + 64));
+        assert_eq!(calldata[68..100], abi_word(40));
+        assert_eq!(calldata[100..140], journal[..]);
+        assert_eq!(calldata[164..196], abi_word(36));
+        assert_eq!(abi_decode_bytes(&abi_encode_bytes(&[&seal])), Some(seal));
+        assert!(parse_address("0x1234").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_addresses_and_typed_result_digest() -> Result<(), OnchainError> {
+        // The secp256k1 generator is the public key of private key 1
+        let generator: [u8; 64] = hex::decode(
+            "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798\
+             483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
+        )
+        .unwrap()
+        .try_into()
+        .unwrap();
+        assert_eq!(address_from_public_key(&generator), parse_address("0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf")?);
+        assert_eq!(
+            hex::encode(Keccak256::digest(DOMAIN_TYPE)),
+            "8b73c3c69bb8fe3d512ecc4cf759cc79239f7b179b0ffacaa9a75d522b39400f"
+        );
+
+        let domain = Eip712Domain { chain_id: 1, verifying_contract: [7u8; 20] };
+        let mut output = VoteTallyOutput {
+            option1_count: 2,
+            option2_count: 1,
+            option3_count: 0,
+            total_votes: 3,
+            election: ElectionContext::default(),
+            computation_hash: Digest32::ZERO,
+            ballot_set_root: [1u8; 32],
+            identity_root: [2u8; 32],
+            superseded_ballots: 0,
+            turnout: Turnout::default(),
+            outcome: Outcome::default(),
+            rejected_count: 0,
+            duplicate_count: 0,
+            invalid_signature_count: 0,
+            rejections_by_reason: [0; REJECTION_REASONS],
+            audit_log_head: [3u8; 32],
+            chain_anchor: None,
+            prior_tally_digest: [0u8; 32],
+            result_typed_digest: [0u8; 32],
+            trustees_digest: [0u8; 32],
+            encrypted_tally_frames: 3,
+        };
+        let digest = result_typed_digest(&domain, &output);
+        // Bound to the chain, the contract and every typed field
+        assert_ne!(digest, result_typed_digest(&Eip712Domain { chain_id: 10, ..domain }, &output));
+        assert_ne!(digest, result_typed_digest(&Eip712Domain { verifying_contract: [8u8; 20], ..domain }, &output));
+        output.option3_count += 1;
+        assert_ne!(digest, result_typed_digest(&domain, &output));
+
+        // The contract finds the election where the compact journal puts it
+        output.election = ElectionContext { election_id: Digest32([9u8; 32]), chain_id: 10, ..ElectionContext::default() };
+        let compact = encode_compact_journal(&output, &[0u8; 32]);
+        assert_eq!(compact[ELECTION_ID_OFFSET..ELECTION_ID_OFFSET + 32], [9u8; 32]);
+        assert_eq!(compact[CHAIN_ID_OFFSET..CHAIN_ID_OFFSET + 8], 10u64.to_le_bytes());
+        assert_eq!(compact[TALLY_FRAMES_OFFSET..TALLY_FRAMES_OFFSET + 4], 3u32.to_le_bytes());
+        Ok(())
+    }
+}
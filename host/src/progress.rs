@@ -0,0 +1,138 @@
+// Tally progress events.
+// While the host tallies it emits a `TallyEvent` at each step an embedder may
+// want to show or alert on:
+//
+//   ballot_validated  a submission passed intake and its checks
+//   ballot_rejected   a submission failed them, with the reason
+//   batch_proven      one batch receipt of a batched tally was proven
+//   receipt_verified  a tally (or interim, batch or aggregate) receipt verified
+//
+// Observers are registered on a `TallyEvents` and called in order, on the
+// tallying thread, so a progress bar or dashboard needs no stdout parsing. Any
+// `Fn(&TallyEvent)` closure is an observer. Observers cannot fail the tally: an
+// observer that can fail (a socket, a file) reports its own errors.
+//
+// Unlike `hooks`, which only ever see a verified final result, these events
+// describe work in progress; nothing here is proven until `receipt_verified`.
+
+use risc0_zkvm::sha::{Digest, Impl, Sha256};
+use serde::{Deserialize, Serialize};
+
+/// One step of a tally, as handed to observers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TallyEvent {
+    BallotValidated {
+        voter_address: String,
+        #[serde(with = "hex::serde")]
+        ballot_hash: [u8; 32],
+    },
+    BallotRejected {
+        voter_address: String,
+        #[serde(with = "hex::serde")]
+        ballot_hash: [u8; 32],
+        reason: String,
+    },
+    BatchProven {
+        batch: usize, // 0-based
+        batches: usize,
+        ballots: usize,
+    },
+    ReceiptVerified {
+        #[serde(with = "hex::serde")]
+        image_id: [u8; 32],
+        #[serde(with = "hex::serde")]
+        journal_digest: [u8; 32],
+    },
+}
+
+impl TallyEvent {
+    /// The event for a receipt of `image_id` with `journal` that verified.
+    pub fn receipt_verified(image_id: impl Into<Digest>, journal: &[u8]) -> Self {
+        TallyEvent::ReceiptVerified {
+            image_id: image_id.into().into(),
+            journal_digest: (*Impl::hash_bytes(journal)).into(),
+        }
+    }
+}
+
+/// Something that follows a tally as it runs.
+pub trait TallyObserver: Send + Sync {
+    fn on_event(&self, event: &TallyEvent);
+}
+
+impl<F: Fn(&TallyEvent) + Send + Sync> TallyObserver for F {
+    fn on_event(&self, event: &TallyEvent) {
+        self(event)
+    }
+}
+
+/// The observers to notify while tallying.
+#[derive(Default)]
+pub struct TallyEvents {
+    observers: Vec<Box<dyn TallyObserver>>,
+}
+
+impl TallyEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_observer(mut self, observer: impl TallyObserver + 'static) -> Self {
+        self.observers.push(Box::new(observer));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.observers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    /// Hand `event` to every observer, in the order they were added.
+    pub fn emit(&self, event: TallyEvent) {
+        for observer in &self.observers {
+            observer.on_event(&event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_observers_see_every_event_in_order() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let rejected = Arc::new(Mutex::new(0));
+        let events = {
+            let (seen, rejected) = (Arc::clone(&seen), Arc::clone(&rejected));
+            TallyEvents::new()
+                .with_observer(move |event: &TallyEvent| seen.lock().unwrap().push(event.clone()))
+                .with_observer(move |event: &TallyEvent| {
+                    if matches!(event, TallyEvent::BallotRejected { .. }) {
+                        *rejected.lock().unwrap() += 1;
+                    }
+                })
+        };
+        assert_eq!(events.len(), 2);
+
+        let rejection = TallyEvent::BallotRejected { voter_address: "bob".to_string(), ballot_hash: [2u8; 32], reason: "Duplicate ballot".to_string() };
+        events.emit(TallyEvent::BallotValidated { voter_address: "alice".to_string(), ballot_hash: [1u8; 32] });
+        events.emit(rejection.clone());
+        events.emit(TallyEvent::BatchProven { batch: 0, batches: 1, ballots: 1 });
+        events.emit(TallyEvent::receipt_verified([3u32; 8], b"journal"));
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 4);
+        assert_eq!(seen[1], rejection);
+        assert_eq!(*rejected.lock().unwrap(), 1);
+
+        // One JSON object per event, tagged for dashboards
+        let json = serde_json::to_value(&seen[3]).unwrap();
+        assert_eq!(json["event"], "receipt_verified");
+        assert_eq!(json["journal_digest"], hex::encode(Impl::hash_bytes(b"journal").as_bytes()));
+    }
+}
@@ -0,0 +1,177 @@
+// QR-code ballot transport.
+// An air-gapped or mobile voter hands a ballot to a collection point as a
+// series of QR codes instead of over a network. The ballot is bincode-encoded
+// and deflated, then split into frames of at most `frame_bytes` bytes, each
+// rendered as one QR code:
+//
+//   FHEV/<index>/<total>/<ballot tag>/<payload>
+//
+// in upper-case hex, so every character fits the QR alphanumeric mode. The
+// ballot tag is the first 8 bytes of SHA-256 over the whole deflated ballot:
+// it keeps frames of different ballots apart and checks the reassembled bytes.
+// The collection point scans the frames in any order, repeats included, and
+// `decode_ballot` names any frames still missing. A frame carries no more than
+// the ballot JSON would, so QR transport reveals nothing extra about the vote.
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use qrcode::render::svg;
+use qrcode::{EcLevel, QrCode};
+use risc0_zkvm::sha::{Impl, Sha256};
+use thiserror::Error;
+
+use crate::types::EncryptedVote;
+
+const FRAME_PREFIX: &str = "FHEV";
+/// Frame size most phone cameras still scan reliably at error-correction level M.
+pub const DEFAULT_FRAME_BYTES: usize = 1024;
+
+#[derive(Error, Debug)]
+pub enum QrError {
+    #[error("Frame size must be at least one byte")]
+    FrameSize,
+    #[error("Ballot encoding failed: {reason}")]
+    Encoding { reason: String },
+    #[error("Frame {frame} is not a ballot frame")]
+    MalformedFrame { frame: usize },
+    #[error("Frames belong to more than one ballot")]
+    MixedBallots,
+    #[error("No frames scanned")]
+    NoFrames,
+    #[error("Frames {missing:?} have not been scanned")]
+    MissingFrames { missing: Vec<u32> },
+    #[error("Reassembled ballot does not match its tag")]
+    TagMismatch,
+}
+
+struct Frame {
+    index: u32,
+    total: u32,
+    tag: String,
+    payload: Vec<u8>,
+}
+
+/// Split a ballot into QR frame texts, in order.
+pub fn ballot_frames(ballot: &EncryptedVote, frame_bytes: usize) -> Result<Vec<String>, QrError> {
+    if frame_bytes == 0 {
+        return Err(QrError::FrameSize);
+    }
+    let encoding = |e: std::io::Error| QrError::Encoding { reason: e.to_string() };
+    let encoded = bincode::serialize(ballot).map_err(|e| QrError::Encoding { reason: e.to_string() })?;
+    let mut deflater = DeflateEncoder::new(Vec::new(), Compression::best());
+    deflater.write_all(&encoded).map_err(encoding)?;
+    let deflated = deflater.finish().map_err(encoding)?;
+
+    let tag = ballot_tag(&deflated);
+    let total = deflated.len().div_ceil(frame_bytes);
+    Ok(deflated
+        .chunks(frame_bytes)
+        .enumerate()
+        .map(|(index, payload)| format!("{}/{}/{}/{}/{}", FRAME_PREFIX, index, total, tag, hex::encode_upper(payload)))
+        .collect())
+}
+
+/// Render one frame as an SVG QR code.
+pub fn frame_svg(frame: &str) -> Result<String, QrError> {
+    let code = QrCode::with_error_correction_level(frame.as_bytes(), EcLevel::M)
+        .map_err(|e| QrError::Encoding { reason: e.to_string() })?;
+    Ok(code.render::<svg::Color>().min_dimensions(400, 400).build())
+}
+
+/// Reassemble a ballot from scanned frames, in any order and with repeats.
+pub fn decode_ballot<S: AsRef<str>>(scanned: &[S]) -> Result<EncryptedVote, QrError> {
+    let mut frames = BTreeMap::new();
+    let mut ballot: Option<(String, u32)> = None;
+    for (position, text) in scanned.iter().enumerate() {
+        let frame = parse_frame(text.as_ref().trim()).ok_or(QrError::MalformedFrame { frame: position })?;
+        match &ballot {
+            Some((tag, total)) if *tag != frame.tag || *total != frame.total => return Err(QrError::MixedBallots),
+            Some(_) => {}
+            None => ballot = Some((frame.tag.clone(), frame.total)),
+        }
+        frames.insert(frame.index, frame.payload);
+    }
+    let (tag, total) = ballot.ok_or(QrError::NoFrames)?;
+    let missing: Vec<u32> = (0..total).filter(|index| !frames.contains_key(index)).collect();
+    if !missing.is_empty() {
+        return Err(QrError::MissingFrames { missing });
+    }
+
+    let deflated: Vec<u8> = frames.into_values().flatten().collect();
+    if ballot_tag(&deflated) != tag {
+        return Err(QrError::TagMismatch);
+    }
+    let mut encoded = Vec::new();
+    DeflateDecoder::new(deflated.as_slice())
+        .read_to_end(&mut encoded)
+        .map_err(|e| QrError::Encoding { reason: e.to_string() })?;
+    bincode::deserialize(&encoded).map_err(|e| QrError::Encoding { reason: e.to_string() })
+}
+
+fn ballot_tag(deflated: &[u8]) -> String {
+    hex::encode_upper(&Impl::hash_bytes(deflated).as_bytes()[..8])
+}
+
+fn parse_frame(text: &str) -> Option<Frame> {
+    let mut fields = text.split('/');
+    if fields.next()? != FRAME_PREFIX {
+        return None;
+    }
+    let index: u32 = fields.next()?.parse().ok()?;
+    let total: u32 = fields.next()?.parse().ok()?;
+    let tag = fields.next()?.to_string();
+    let payload = hex::decode(fields.next()?).ok()?;
+    if fields.next().is_some() || index >= total || tag.len() != 16 || payload.is_empty() {
+        return None;
+    }
+    Some(Frame { index, total, tag, payload })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BallotAuthorization, Digest32, MembershipProof, VoteOption};
+
+    fn ballot() -> EncryptedVote {
+        EncryptedVote {
+            voter_address: "voter-0a0b".to_string(),
+            election_id: Digest32([1u8; 32]),
+            nonce: [2u8; 32],
+            encrypted_vote_vector: (0..3).map(|i| (0..3000u32).map(|b| (b * 7 + i) as u8).collect()).collect(),
+            signature: String::new(),
+            authorization: BallotAuthorization::Membership(MembershipProof {
+                nullifier: [3u8; 32],
+                challenges: vec![[4u8; 32]; 2],
+                responses: vec![[5u8; 32]; 2],
+            }),
+            sequence: 1,
+            actual_choice: VoteOption::Option1,
+        }
+    }
+
+    #[test]
+    fn test_ballot_survives_qr_transport() -> Result<(), QrError> {
+        let ballot = ballot();
+        let frames = ballot_frames(&ballot, 64)?;
+        assert!(frames.len() > 1);
+        assert!(frames.iter().all(|frame| frame.chars().all(|c| c.is_ascii_digit() || c.is_ascii_uppercase() || c == '/')));
+        assert!(frame_svg(&frames[0])?.starts_with("<?xml"));
+
+        // Scanned out of order and with repeats
+        let mut scanned: Vec<&String> = frames.iter().rev().collect();
+        scanned.push(&frames[0]);
+        let decoded = decode_ballot(&scanned)?;
+        assert_eq!(crate::ballot_set::ballot_leaf_hash(&decoded), crate::ballot_set::ballot_leaf_hash(&ballot));
+
+        assert!(matches!(decode_ballot(&frames[1..]), Err(QrError::MissingFrames { missing }) if missing == vec![0]));
+        let other = ballot_frames(&EncryptedVote { sequence: 2, ..ballot }, 64)?;
+        assert!(matches!(decode_ballot(&[&frames[0], &other[1]]), Err(QrError::MixedBallots)));
+        assert!(matches!(decode_ballot(&["FHEV/0/1/ZZ/00"]), Err(QrError::MalformedFrame { frame: 0 })));
+        assert!(matches!(decode_ballot::<&str>(&[]), Err(QrError::NoFrames)));
+        Ok(())
+    }
+}
@@ -0,0 +1,282 @@
+// Blind-signature voter registration.
+// The registrar checks a voter against the electoral roll and signs a fresh,
+// voter-chosen credential token without ever seeing it (blind Schnorr over
+// Ristretto). Ballots carry only the unblinded credential, which the guest
+// verifies against the registrar's public key, so eligibility is enforced
+// without linking a voter's identity to their ballot.
+//
+// The token is the public half of a ballot key that only the voter holds, and
+// every ballot is signed with it, so a credential seen on the bulletin board
+// cannot be replayed to override the voter's latest ballot.
+//
+// Issuance, per voter:
+//   1. registrar.open_session(voter)       -> nonce commitment R = k*G
+//   2. CredentialRequest::new(pk, R)       -> blinded challenge c = c' + beta
+//      where R' = R + alpha*G + beta*X and c' = H(R', X, token)
+//   3. registrar.sign_blinded(session, c)  -> s = k + c*x
+//   4. request.unblind(s)                  -> signature (R', s' = s + alpha)
+// Verification: s'*G == R' + c'*X.
+//
+// Each session is consumed by `sign_blinded`, so a nonce is never reused. Real
+// deployments must also bound concurrent sessions (ROS attack on blind Schnorr).
+//
+// Roll entries that are Ethereum addresses are held in their EIP-55 form, so a
+// voter is found, and issued at most one credential, however the address is
+// cased.
+
+use std::collections::HashMap;
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use risc0_zkvm::sha::{Impl, Sha256};
+use thiserror::Error;
+
+use crate::address::normalize_address;
+use crate::types::{SchnorrSignature, VoterCredential};
+
+#[derive(Error, Debug)]
+pub enum RegistrarError {
+    #[error("Voter {voter} is not on the electoral roll")]
+    NotEligible { voter: String },
+    #[error("Voter {voter} has already been issued a credential")]
+    AlreadyIssued { voter: String },
+    #[error("Malformed registrar message")]
+    Malformed,
+    #[error("Registrar signature does not verify")]
+    InvalidSignature,
+}
+
+/// One signing session; consumed when the blinded challenge is signed.
+pub struct IssuanceSession {
+    voter: String,
+    nonce: Scalar,
+}
+
+pub struct Registrar {
+    secret: Scalar,
+    public_key: RistrettoPoint,
+    issued: HashMap<String, bool>,
+}
+
+impl Registrar {
+    pub fn new<I, S>(electoral_roll: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let secret = Scalar::random(&mut rand::thread_rng());
+        Registrar {
+            secret,
+            public_key: secret * RISTRETTO_BASEPOINT_POINT,
+            issued: electoral_roll.into_iter().map(|voter| (roll_key(&voter.into()), false)).collect(),
+        }
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.public_key.compress().to_bytes()
+    }
+
+    /// Check eligibility and start a session; returns the nonce commitment R.
+    pub fn open_session(&mut self, voter: &str) -> Result<(IssuanceSession, [u8; 32]), RegistrarError> {
+        let voter = roll_key(voter);
+        match self.issued.get(&voter) {
+            None => return Err(RegistrarError::NotEligible { voter }),
+            Some(true) => return Err(RegistrarError::AlreadyIssued { voter }),
+            Some(false) => {}
+        }
+        let nonce = Scalar::random(&mut rand::thread_rng());
+        let commitment = (nonce * RISTRETTO_BASEPOINT_POINT).compress().to_bytes();
+        Ok((IssuanceSession { voter, nonce }, commitment))
+    }
+
+    /// Sign the voter's blinded challenge; the voter is then marked as issued.
+    pub fn sign_blinded(&mut self, session: IssuanceSession, blinded_challenge: [u8; 32]) -> Result<[u8; 32], RegistrarError> {
+        let challenge = Option::<Scalar>::from(Scalar::from_canonical_bytes(blinded_challenge))
+            .ok_or(RegistrarError::Malformed)?;
+        match self.issued.get_mut(&session.voter) {
+            Some(issued @ false) => *issued = true,
+            Some(true) => return Err(RegistrarError::AlreadyIssued { voter: session.voter }),
+            None => return Err(RegistrarError::NotEligible { voter: session.voter }),
+        }
+        Ok((session.nonce + challenge * self.secret).to_bytes())
+    }
+}
+
+// Addresses by their checksummed spelling; other roll entries (names) as given
+fn roll_key(voter: &str) -> String {
+    normalize_address(voter).unwrap_or_else(|_| voter.to_string())
+}
+
+/// Voter-side state for one blind issuance.
+pub struct CredentialRequest {
+    registrar_public_key: RistrettoPoint,
+    ballot_key: Scalar,
+    token: [u8; 32],
+    alpha: Scalar,
+    commitment: CompressedRistretto, // R'
+    challenge: Scalar,               // c'
+}
+
+impl CredentialRequest {
+    /// Pick a fresh token and blind it; returns the challenge to send to the registrar.
+    pub fn new(registrar_public_key: &[u8; 32], nonce_commitment: &[u8; 32]) -> Result<(Self, [u8; 32]), RegistrarError> {
+        let x = decompress(registrar_public_key)?;
+        let r = decompress(nonce_commitment)?;
+
+        let mut rng = rand::thread_rng();
+        let ballot_key = Scalar::random(&mut rng);
+        let token = (ballot_key * RISTRETTO_BASEPOINT_POINT).compress().to_bytes();
+        let alpha = Scalar::random(&mut rng);
+        let beta = Scalar::random(&mut rng);
+
+        let commitment = (r + alpha * RISTRETTO_BASEPOINT_POINT + beta * x).compress();
+        let challenge = credential_challenge(&commitment, registrar_public_key, &token);
+        let blinded = (challenge + beta).to_bytes();
+
+        let request = CredentialRequest { registrar_public_key: x, ballot_key, token, alpha, commitment, challenge };
+        Ok((request, blinded))
+    }
+
+    /// Unblind the registrar's response and check the resulting credential.
+    pub fn unblind(self, blinded_response: [u8; 32]) -> Result<CredentialKey, RegistrarError> {
+        let s = Option::<Scalar>::from(Scalar::from_canonical_bytes(blinded_response))
+            .ok_or(RegistrarError::Malformed)?;
+        let response = s + self.alpha;
+
+        let commitment = self.commitment.decompress().ok_or(RegistrarError::Malformed)?;
+        if response * RISTRETTO_BASEPOINT_POINT != commitment + self.challenge * self.registrar_public_key {
+            return Err(RegistrarError::InvalidSignature);
+        }
+
+        let credential = VoterCredential {
+            token: self.token,
+            signature: SchnorrSignature {
+                commitment: self.commitment.to_bytes(),
+                response: response.to_bytes(),
+            },
+        };
+        Ok(CredentialKey { credential, ballot_key: self.ballot_key })
+    }
+}
+
+/// A registrar credential together with the ballot key its token commits to.
+pub struct CredentialKey {
+    credential: VoterCredential,
+    ballot_key: Scalar,
+}
+
+impl CredentialKey {
+    pub fn credential(&self) -> &VoterCredential {
+        &self.credential
+    }
+
+    /// Sign a `ballot_message` so the guest accepts the ballot under this credential.
+    pub fn sign_ballot(&self, message: &[u8; 32]) -> SchnorrSignature {
+        let nonce = Scalar::random(&mut rand::thread_rng());
+        let commitment = (nonce * RISTRETTO_BASEPOINT_POINT).compress();
+        let challenge = ballot_challenge(&commitment, &self.credential.token, message);
+        SchnorrSignature {
+            commitment: commitment.to_bytes(),
+            response: (nonce + challenge * self.ballot_key).to_bytes(),
+        }
+    }
+}
+
+/// Check an unblinded credential against the registrar key (matches guest `verify_credential`).
+pub fn verify_credential(credential: &VoterCredential, registrar_public_key: &[u8; 32]) -> bool {
+    let (Ok(x), Ok(r)) = (decompress(registrar_public_key), decompress(&credential.signature.commitment)) else {
+        return false;
+    };
+    let Some(response) = Option::<Scalar>::from(Scalar::from_canonical_bytes(credential.signature.response)) else {
+        return false;
+    };
+    let challenge = credential_challenge(
+        &CompressedRistretto(credential.signature.commitment),
+        registrar_public_key,
+        &credential.token,
+    );
+    response * RISTRETTO_BASEPOINT_POINT == r + challenge * x
+}
+
+/// Check a ballot signature against the credential's token (matches guest `verify_ballot_signature`).
+pub fn verify_ballot_signature(credential: &VoterCredential, signature: &SchnorrSignature, message: &[u8; 32]) -> bool {
+    let (Ok(p), Ok(r)) = (decompress(&credential.token), decompress(&signature.commitment)) else {
+        return false;
+    };
+    let Some(response) = Option::<Scalar>::from(Scalar::from_canonical_bytes(signature.response)) else {
+        return false;
+    };
+    let challenge = ballot_challenge(&CompressedRistretto(signature.commitment), &credential.token, message);
+    response * RISTRETTO_BASEPOINT_POINT == r + challenge * p
+}
+
+fn decompress(bytes: &[u8; 32]) -> Result<RistrettoPoint, RegistrarError> {
+    CompressedRistretto(*bytes).decompress().ok_or(RegistrarError::Malformed)
+}
+
+fn credential_challenge(commitment: &CompressedRistretto, registrar_public_key: &[u8; 32], token: &[u8; 32]) -> Scalar {
+    let mut buf = b"fhe-voting/registrar".to_vec();
+    buf.extend_from_slice(commitment.as_bytes());
+    buf.extend_from_slice(registrar_public_key);
+    buf.extend_from_slice(token);
+    Scalar::from_bytes_mod_order_wide(&wide_hash(&buf))
+}
+
+fn ballot_challenge(commitment: &CompressedRistretto, token: &[u8; 32], message: &[u8; 32]) -> Scalar {
+    let mut buf = b"fhe-voting/ballot-signature".to_vec();
+    buf.extend_from_slice(commitment.as_bytes());
+    buf.extend_from_slice(token);
+    buf.extend_from_slice(message);
+    Scalar::from_bytes_mod_order_wide(&wide_hash(&buf))
+}
+
+/// H(0x00 || data) || H(0x01 || data): 64 uniform bytes from SHA-256, which the
+/// guest runs on the zkVM accelerator (must match guest `wide_hash`).
+pub(crate) fn wide_hash(data: &[u8]) -> [u8; 64] {
+    let mut wide = [0u8; 64];
+    let mut buf = Vec::with_capacity(data.len() + 1);
+    for (counter, half) in wide.as_chunks_mut::<32>().0.iter_mut().enumerate() {
+        buf.clear();
+        buf.push(counter as u8);
+        buf.extend_from_slice(data);
+        half.copy_from_slice(Impl::hash_bytes(&buf).as_bytes());
+    }
+    wide
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blind_issuance_and_eligibility() -> Result<(), RegistrarError> {
+        let mut registrar = Registrar::new(["alice", "bob"]);
+        let registrar_key = registrar.public_key();
+
+        let (session, nonce_commitment) = registrar.open_session("alice")?;
+        let (request, blinded) = CredentialRequest::new(&registrar_key, &nonce_commitment)?;
+        let key = request.unblind(registrar.sign_blinded(session, blinded)?)?;
+        let mut credential = key.credential().clone();
+        assert!(verify_credential(&credential, &registrar_key));
+
+        let message = [7u8; 32];
+        let ballot_signature = key.sign_ballot(&message);
+        assert!(verify_ballot_signature(&credential, &ballot_signature, &message));
+        assert!(!verify_ballot_signature(&credential, &ballot_signature, &[8u8; 32]));
+
+        assert!(matches!(registrar.open_session("alice"), Err(RegistrarError::AlreadyIssued { .. })));
+        assert!(matches!(registrar.open_session("mallory"), Err(RegistrarError::NotEligible { .. })));
+
+        // An address is one voter however it is cased
+        let mut registrar = Registrar::new(["0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"]);
+        let (session, nonce_commitment) = registrar.open_session("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed")?;
+        let (request, blinded) = CredentialRequest::new(&registrar.public_key(), &nonce_commitment)?;
+        request.unblind(registrar.sign_blinded(session, blinded)?)?;
+        assert!(matches!(registrar.open_session("0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED"), Err(RegistrarError::AlreadyIssued { .. })));
+
+        credential.token[0] ^= 1;
+        assert!(!verify_credential(&credential, &registrar_key));
+        Ok(())
+    }
+}
@@ -0,0 +1,211 @@
+// Voter registration service.
+// While registration is open the service collects voters into a Merkle tree
+// built like the ballot set: Ethereum addresses for credential elections, or
+// identity commitments for anonymous ones. Closing registration freezes the
+// tree, so nothing more is accepted and its root can be published. After that
+// every registered voter can get an inclusion proof to check against the
+// published root before submitting a ballot.
+//
+// Identity leaves are the roster leaves of `membership`, so a frozen identity
+// registry's root is exactly the `identity_root` the guest commits for that
+// roster. Address leaves carry their own domain tag. A registry holds one kind
+// of registrant.
+
+use std::collections::HashMap;
+
+use curve25519_dalek::ristretto::CompressedRistretto;
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+use crate::ballot_set::{BallotSetCommitment, InclusionProof};
+use crate::membership::identity_leaf;
+
+// Domain separation tag for address leaves (identity leaves use 0x02)
+const ADDRESS_TAG: u8 = 0x06;
+
+#[derive(Error, Debug)]
+pub enum RegistrationError {
+    #[error("Registration is closed")]
+    Closed,
+    #[error("Registration is still open; inclusion proofs exist only for the frozen registry")]
+    StillOpen,
+    #[error("{registrant} is already registered")]
+    Duplicate { registrant: String },
+    #[error("{registrant} is not registered")]
+    NotRegistered { registrant: String },
+    #[error("Malformed identity commitment {registrant}")]
+    MalformedCommitment { registrant: String },
+    #[error("A registry holds either addresses or identity commitments, not both")]
+    MixedRegistrants,
+}
+
+/// One registered voter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Registrant {
+    Address([u8; 20]),
+    Identity([u8; 32]), // Compressed Ristretto identity commitment
+}
+
+impl Registrant {
+    pub fn leaf_hash(&self) -> [u8; 32] {
+        match self {
+            Registrant::Address(address) => {
+                let mut buf = vec![ADDRESS_TAG];
+                buf.extend_from_slice(address);
+                (*Impl::hash_bytes(&buf)).into()
+            }
+            Registrant::Identity(commitment) => identity_leaf(commitment),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Registrant::Address(address) => format!("address 0x{}", hex::encode(address)),
+            Registrant::Identity(commitment) => format!("identity {}", hex::encode(commitment)),
+        }
+    }
+}
+
+/// Proof that a voter is in the frozen registry with the given root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistrationProof {
+    pub registrant: Registrant,
+    pub root: [u8; 32],
+    pub proof: InclusionProof,
+}
+
+impl RegistrationProof {
+    /// Check the proof against the published registry root.
+    pub fn verify(&self, root: &[u8; 32]) -> bool {
+        &self.root == root && self.proof.verify_leaf(self.registrant.leaf_hash(), root)
+    }
+}
+
+#[derive(Default)]
+pub struct RegistrationService {
+    registrants: Vec<Registrant>,
+    positions: HashMap<Registrant, usize>,
+    frozen: Option<BallotSetCommitment>,
+}
+
+impl RegistrationService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a voter while registration is open; returns their position in the tree.
+    pub fn register(&mut self, registrant: Registrant) -> Result<usize, RegistrationError> {
+        if self.frozen.is_some() {
+            return Err(RegistrationError::Closed);
+        }
+        if self.positions.contains_key(&registrant) {
+            return Err(RegistrationError::Duplicate { registrant: registrant.describe() });
+        }
+        if let Some(first) = self.registrants.first() {
+            if std::mem::discriminant(first) != std::mem::discriminant(&registrant) {
+                return Err(RegistrationError::MixedRegistrants);
+            }
+        }
+        // The guest refuses a roster with an undecodable commitment, so refuse it here first
+        if let Registrant::Identity(commitment) = registrant {
+            if CompressedRistretto(commitment).decompress().is_none() {
+                return Err(RegistrationError::MalformedCommitment { registrant: registrant.describe() });
+            }
+        }
+        let position = self.registrants.len();
+        self.registrants.push(registrant);
+        self.positions.insert(registrant, position);
+        Ok(position)
+    }
+
+    /// Close registration and freeze the tree; returns the root to publish.
+    pub fn close(&mut self) -> Result<[u8; 32], RegistrationError> {
+        if self.frozen.is_some() {
+            return Err(RegistrationError::Closed);
+        }
+        let tree = BallotSetCommitment::from_leaves(self.registrants.iter().map(Registrant::leaf_hash).collect());
+        let root = tree.root();
+        self.frozen = Some(tree);
+        Ok(root)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.frozen.is_some()
+    }
+
+    /// Root of the frozen registry, once registration has closed.
+    pub fn root(&self) -> Option<[u8; 32]> {
+        self.frozen.as_ref().map(BallotSetCommitment::root)
+    }
+
+    pub fn len(&self) -> usize {
+        self.registrants.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.registrants.is_empty()
+    }
+
+    pub fn registrants(&self) -> &[Registrant] {
+        &self.registrants
+    }
+
+    /// Registered identity commitments in tree order, as the guest's identity roster.
+    pub fn identity_roster(&self) -> Vec<[u8; 32]> {
+        self.registrants
+            .iter()
+            .filter_map(|registrant| match registrant {
+                Registrant::Identity(commitment) => Some(*commitment),
+                Registrant::Address(_) => None,
+            })
+            .collect()
+    }
+
+    pub fn inclusion_proof(&self, registrant: &Registrant) -> Result<RegistrationProof, RegistrationError> {
+        let tree = self.frozen.as_ref().ok_or(RegistrationError::StillOpen)?;
+        let position = *self
+            .positions
+            .get(registrant)
+            .ok_or_else(|| RegistrationError::NotRegistered { registrant: registrant.describe() })?;
+        let proof = tree.inclusion_proof(position).expect("registered positions are in the tree");
+        Ok(RegistrationProof { registrant: *registrant, root: tree.root(), proof })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::membership::{identity_root, Identity};
+
+    #[test]
+    fn test_registry_freezes_and_proves_inclusion() -> Result<(), RegistrationError> {
+        let identities: Vec<_> = (0..5).map(|_| Registrant::Identity(Identity::generate().commitment())).collect();
+        let mut registration = RegistrationService::new();
+        for identity in &identities {
+            registration.register(*identity)?;
+        }
+        assert!(matches!(registration.register(identities[0]), Err(RegistrationError::Duplicate { .. })));
+        assert!(matches!(registration.register(Registrant::Address([1u8; 20])), Err(RegistrationError::MixedRegistrants)));
+        assert!(matches!(registration.register(Registrant::Identity([0xffu8; 32])), Err(RegistrationError::MalformedCommitment { .. })));
+        assert!(matches!(registration.inclusion_proof(&identities[0]), Err(RegistrationError::StillOpen)));
+
+        let root = registration.close()?;
+        assert_eq!(root, identity_root(&registration.identity_roster()));
+        assert!(matches!(registration.register(Registrant::Identity(Identity::generate().commitment())), Err(RegistrationError::Closed)));
+        for identity in &identities {
+            assert!(registration.inclusion_proof(identity)?.verify(&root));
+        }
+
+        // A proof does not carry over to another registrant or another root
+        let mut forged = registration.inclusion_proof(&identities[1])?;
+        forged.registrant = identities[2];
+        assert!(!forged.verify(&root));
+        assert!(!registration.inclusion_proof(&identities[1])?.verify(&[0u8; 32]));
+        assert!(matches!(
+            registration.inclusion_proof(&Registrant::Identity(Identity::generate().commitment())),
+            Err(RegistrationError::NotRegistered { .. })
+        ));
+        Ok(())
+    }
+}
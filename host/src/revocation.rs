@@ -0,0 +1,162 @@
+// Voter revocation of counted ballots.
+// A voter who spoiled a ballot (the wrong choice, cast under duress, a device
+// they no longer trust) takes it back by naming its tracking code in a
+// `BallotRevocation`, authorized by the same key the ballot was: a signature
+// with their credential key, or a membership proof carrying the same
+// nullifier. The guest adds every counted ballot to the encrypted tallies as
+// usual, then subtracts each revoked ballot's ciphertexts (times its weight)
+// before anything is decrypted, so the correction is covered by the same proof
+// and the revoked choice is never seen.
+//
+//   message = H(0x19 || election domain || tracking code)
+//
+// The tag keeps a revocation from being mistaken for a ballot, and the domain
+// from being replayed in another election. A revocation only applies to a
+// ballot that was counted, at most once; to change a vote rather than withdraw
+// it the voter casts a later ballot as before. Each applied revocation is
+// logged as a rejected audit record with `audit_log::REVOKED_REASON`, and the
+// guest checks the log's revocations against those it applied, so the
+// committed audit log head names every revoked ballot.
+
+use thiserror::Error;
+
+use crate::ballot_set::{ballot_leaf_hash, revocation_message};
+use crate::membership::{prove_membership, verify_membership, Identity, MembershipError};
+use crate::registrar::{verify_ballot_signature, verify_credential, CredentialKey};
+use crate::types::{BallotAuthorization, BallotRevocation, Digest32, EncryptedVote};
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum RevocationError {
+    #[error("No ballot has tracking code {tracking_code}")]
+    UnknownBallot { tracking_code: String },
+    #[error("Ballot {tracking_code} is not counted: a later ballot from the same voter replaced it")]
+    Superseded { tracking_code: String },
+    #[error("Ballot {tracking_code} is already revoked")]
+    AlreadyRevoked { tracking_code: String },
+    #[error("Revocation of ballot {tracking_code} is not authorized by the ballot's voter")]
+    Unauthorized { tracking_code: String },
+}
+
+/// Revoke the ballot with `tracking_code`, cast with a registrar credential.
+pub fn revoke_with_credential(key: &CredentialKey, election_id: &Digest32, tracking_code: [u8; 32]) -> BallotRevocation {
+    BallotRevocation {
+        tracking_code,
+        authorization: BallotAuthorization::Credential {
+            credential: key.credential().clone(),
+            ballot_signature: key.sign_ballot(&revocation_message(election_id, &tracking_code)),
+        },
+    }
+}
+
+/// Revoke the ballot with `tracking_code`, cast with an anonymous roster identity.
+pub fn revoke_with_identity(
+    identity: &Identity,
+    roster: &[[u8; 32]],
+    election_id: &Digest32,
+    tracking_code: [u8; 32],
+) -> Result<BallotRevocation, MembershipError> {
+    let proof = prove_membership(identity, roster, election_id, &revocation_message(election_id, &tracking_code))?;
+    Ok(BallotRevocation { tracking_code, authorization: BallotAuthorization::Membership(proof) })
+}
+
+/// Check `revocation` as the guest will, given the tracking codes already
+/// `revoked`, and return the position of the ballot it takes back.
+pub fn check_revocation(
+    revocation: &BallotRevocation,
+    ballots: &[EncryptedVote],
+    revoked: &[[u8; 32]],
+    registrar_public_key: &[u8; 32],
+    roster: &[[u8; 32]],
+    election_id: &Digest32,
+) -> Result<usize, RevocationError> {
+    let tracking_code = hex::encode(revocation.tracking_code);
+    let index = ballots
+        .iter()
+        .position(|ballot| ballot_leaf_hash(ballot) == revocation.tracking_code)
+        .ok_or_else(|| RevocationError::UnknownBallot { tracking_code: tracking_code.clone() })?;
+    let ballot = &ballots[index];
+    let ballot_key = voter_key(&ballot.authorization);
+    if ballots.iter().any(|other| voter_key(&other.authorization) == ballot_key && other.sequence > ballot.sequence) {
+        return Err(RevocationError::Superseded { tracking_code });
+    }
+    if revoked.contains(&revocation.tracking_code) {
+        return Err(RevocationError::AlreadyRevoked { tracking_code });
+    }
+
+    let message = revocation_message(election_id, &revocation.tracking_code);
+    let authorized = match &revocation.authorization {
+        BallotAuthorization::Credential { credential, ballot_signature } => {
+            credential.token == ballot_key
+                && verify_credential(credential, registrar_public_key)
+                && verify_ballot_signature(credential, ballot_signature, &message)
+        }
+        BallotAuthorization::Membership(proof) => {
+            proof.nullifier == ballot_key && verify_membership(proof, roster, election_id, &message)
+        }
+    };
+    if !authorized {
+        return Err(RevocationError::Unauthorized { tracking_code });
+    }
+    Ok(index)
+}
+
+fn voter_key(authorization: &BallotAuthorization) -> [u8; 32] {
+    match authorization {
+        BallotAuthorization::Credential { credential, .. } => credential.token,
+        BallotAuthorization::Membership(proof) => proof.nullifier,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ballot_set::ballot_message;
+    use crate::types::VoteOption;
+
+    fn ballot(identity: &Identity, roster: &[[u8; 32]], election_id: &Digest32, sequence: u64) -> Result<EncryptedVote, MembershipError> {
+        let nonce = [sequence as u8; 32];
+        let encrypted_vote_vector = vec![vec![sequence as u8; 8]; 3];
+        let proof = prove_membership(identity, roster, election_id, &ballot_message(election_id, "", &nonce, &encrypted_vote_vector, sequence))?;
+        Ok(EncryptedVote {
+            voter_address: String::new(),
+            election_id: *election_id,
+            nonce,
+            encrypted_vote_vector,
+            signature: String::new(),
+            authorization: BallotAuthorization::Membership(proof),
+            sequence,
+            actual_choice: VoteOption::Option1,
+        })
+    }
+
+    #[test]
+    fn test_only_the_voter_revokes_their_counted_ballot() -> Result<(), MembershipError> {
+        let identities: Vec<Identity> = (0..3).map(|_| Identity::generate()).collect();
+        let roster: Vec<[u8; 32]> = identities.iter().map(Identity::commitment).collect();
+        let election_id = Digest32([7u8; 32]);
+        let ballots = vec![
+            ballot(&identities[0], &roster, &election_id, 0)?,
+            ballot(&identities[1], &roster, &election_id, 0)?,
+            ballot(&identities[1], &roster, &election_id, 1)?,
+        ];
+        let codes: Vec<[u8; 32]> = ballots.iter().map(ballot_leaf_hash).collect();
+        let check = |revocation: &BallotRevocation, revoked: &[[u8; 32]]| check_revocation(revocation, &ballots, revoked, &[0u8; 32], &roster, &election_id);
+
+        let revocation = revoke_with_identity(&identities[0], &roster, &election_id, codes[0])?;
+        assert_eq!(check(&revocation, &[]), Ok(0));
+        assert!(matches!(check(&revocation, &[codes[0]]), Err(RevocationError::AlreadyRevoked { .. })));
+
+        // Another voter cannot take the ballot back, nor can the revocation be replayed in another election
+        let forged = revoke_with_identity(&identities[2], &roster, &election_id, codes[0])?;
+        assert!(matches!(check(&forged, &[]), Err(RevocationError::Unauthorized { .. })));
+        let elsewhere = revoke_with_identity(&identities[0], &roster, &Digest32([8u8; 32]), codes[0])?;
+        assert!(matches!(check(&elsewhere, &[]), Err(RevocationError::Unauthorized { .. })));
+
+        // Only the voter's latest ballot is counted, so only it can be revoked
+        let replaced = revoke_with_identity(&identities[1], &roster, &election_id, codes[1])?;
+        assert!(matches!(check(&replaced, &[]), Err(RevocationError::Superseded { .. })));
+        assert_eq!(check(&revoke_with_identity(&identities[1], &roster, &election_id, codes[2])?, &[]), Ok(2));
+        assert!(matches!(check(&revoke_with_identity(&identities[0], &roster, &election_id, [9u8; 32])?, &[]), Err(RevocationError::UnknownBallot { .. })));
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+// Programmatic election setup.
+// `ElectionBuilder` gathers what an election needs before the first ballot
+// arrives and builds it in one go:
+//
+//   let election = ElectionBuilder::new("treasury-vote")
+//       .candidates(["Fund", "Defer", "Reject"])
+//       .scheme(TallyScheme::Plurality)
+//       .eligibility(roster_root)
+//       .close_at(closes_at)
+//       .build()?;
+//
+// `build` checks the metadata (a name, one labelled candidate per option, a
+// well-formed voting scheme, an eligibility root, a close time still in the
+// future), runs the trustee DKG for the election key and derives the election
+// context the way the demo does (see `election`). The result carries the
+// trustees' key shares and an empty ballot store: an `ElectionSnapshot` with
+// no ballots, an empty audit log and zero partial tallies, ready to be written
+// out and carried on.
+//
+// The trustees default to the demo's 2-of-3 and the registrar key to zero, for
+// elections without a credential registrar.
+
+use thiserror::Error;
+
+use crate::audit_log::AuditLog;
+use crate::candidates::{candidates_digest, check_candidates, CandidatesError};
+use crate::dkg::{run_in_process, DkgConfig, DkgError, DkgOutput};
+use crate::election::{derive_election_id, now};
+use crate::scheme::{SchemeError, TallyScheme, TieBreak};
+use crate::snapshot::{ElectionMetadata, ElectionSnapshot, SnapshotError};
+use crate::types::{Candidate, ElectionContext};
+
+#[derive(Error, Debug)]
+pub enum SetupError {
+    #[error("Election has no name")]
+    EmptyName,
+    #[error("Election {name} is missing its {field}")]
+    Missing { name: String, field: &'static str },
+    #[error("Election would close at {closes_at}, which is not after now ({now})")]
+    AlreadyClosed { closes_at: u64, now: u64 },
+    #[error("Invalid candidate list: {0}")]
+    Candidates(#[from] CandidatesError),
+    #[error("Invalid voting scheme: {0}")]
+    Scheme(#[from] SchemeError),
+    #[error("Trustee key generation failed: {0}")]
+    Dkg(#[from] DkgError),
+    #[error("Ballot store setup failed: {0}")]
+    Snapshot(#[from] SnapshotError),
+}
+
+/// A configured election: validated metadata, keys and its ballot store.
+pub struct Election {
+    pub name: String,
+    pub scheme: TallyScheme,
+    pub candidates: Vec<Candidate>,
+    pub eligibility_root: [u8; 32],
+    pub context: ElectionContext,
+    pub trustee_outputs: Vec<DkgOutput>, // One per trustee, to be handed out
+    pub store: ElectionSnapshot, // Empty until ballots are accepted
+}
+
+/// Fluent setup for an `Election`.
+pub struct ElectionBuilder {
+    name: String,
+    labels: Vec<String>,
+    scheme: TallyScheme,
+    eligibility_root: Option<[u8; 32]>,
+    closes_at: Option<u64>,
+    chain_id: u64,
+    tie_break: TieBreak,
+    dkg: (u32, u32),
+    registrar_public_key: [u8; 32],
+}
+
+impl ElectionBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        ElectionBuilder {
+            name: name.into(),
+            labels: Vec::new(),
+            scheme: TallyScheme::default(),
+            eligibility_root: None,
+            closes_at: None,
+            chain_id: 0,
+            tie_break: TieBreak::default(),
+            dkg: (2, 3),
+            registrar_public_key: [0u8; 32],
+        }
+    }
+
+    /// Ballot labels for options 1, 2 and 3, in order.
+    pub fn candidates<I, S>(mut self, labels: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.labels = labels.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn scheme(mut self, scheme: TallyScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Root of the registry of eligible voters (identity roster or registration root).
+    pub fn eligibility(mut self, root: [u8; 32]) -> Self {
+        self.eligibility_root = Some(root);
+        self
+    }
+
+    /// Ballot-close time in Unix seconds.
+    pub fn close_at(mut self, closes_at: u64) -> Self {
+        self.closes_at = Some(closes_at);
+        self
+    }
+
+    pub fn chain_id(mut self, chain_id: u64) -> Self {
+        self.chain_id = chain_id;
+        self
+    }
+
+    /// How a tie for first place is settled; part of the election context.
+    pub fn tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.tie_break = tie_break;
+        self
+    }
+
+    /// `threshold` of `participants` trustees must cooperate to decrypt.
+    pub fn trustees(mut self, threshold: u32, participants: u32) -> Self {
+        self.dkg = (threshold, participants);
+        self
+    }
+
+    pub fn registrar(mut self, registrar_public_key: [u8; 32]) -> Self {
+        self.registrar_public_key = registrar_public_key;
+        self
+    }
+
+    pub fn build(self) -> Result<Election, SetupError> {
+        self.build_at(now())
+    }
+
+    // Validation against a given current time, so tests need no clock
+    fn build_at(self, current_time: u64) -> Result<Election, SetupError> {
+        if self.name.trim().is_empty() {
+            return Err(SetupError::EmptyName);
+        }
+        let missing = |field| SetupError::Missing { name: self.name.clone(), field };
+        let eligibility_root = self.eligibility_root.ok_or_else(|| missing("eligibility root"))?;
+        let closes_at = self.closes_at.ok_or_else(|| missing("close time"))?;
+        if closes_at <= current_time {
+            return Err(SetupError::AlreadyClosed { closes_at, now: current_time });
+        }
+        let candidates: Vec<Candidate> = self.labels.iter().zip(1..).map(|(label, id)| Candidate { id, label: label.trim().to_string() }).collect();
+        check_candidates(&candidates)?;
+        self.scheme.check()?;
+
+        let (threshold, participants) = self.dkg;
+        let (election_public_key, trustee_outputs) = run_in_process(DkgConfig::threshold(threshold, participants)?)?;
+        let context = ElectionContext {
+            election_id: derive_election_id(&election_public_key.fingerprint(), &self.registrar_public_key),
+            chain_id: self.chain_id,
+            closes_at,
+            candidates_digest: candidates_digest(&candidates),
+            tie_break: self.tie_break,
+            scheme_digest: self.scheme.digest().into(),
+        };
+        let metadata = ElectionMetadata {
+            election_public_key,
+            threshold,
+            trustees: participants,
+            registrar_public_key: self.registrar_public_key,
+            identity_roster: Vec::new(),
+            election: context,
+            candidates: candidates.clone(),
+            scheme: self.scheme.clone(),
+        };
+        let store = ElectionSnapshot::new(metadata, AuditLog::new(), Vec::new(), Vec::new(), Vec::new())?;
+        Ok(Election { name: self.name, scheme: self.scheme, candidates, eligibility_root, context, trustee_outputs, store })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheme::VoterWeight;
+    use crate::types::Digest32;
+
+    #[test]
+    fn test_builder_validates_and_sets_up_an_empty_election() -> Result<(), SetupError> {
+        let builder = || {
+            ElectionBuilder::new("treasury-vote")
+                .candidates(["Fund", "Defer", "Reject"])
+                .scheme(TallyScheme::Plurality)
+                .eligibility([5u8; 32])
+                .close_at(1_800_000_000)
+        };
+        let election = builder().build_at(1_700_000_000)?;
+        assert_eq!(election.candidates[2], Candidate { id: 3, label: "Reject".to_string() });
+        assert_eq!(election.context.candidates_digest, candidates_digest(&election.candidates));
+        assert_eq!(election.context.scheme_digest, Digest32::ZERO);
+        let key = &election.store.metadata.election_public_key;
+        assert_eq!(election.context.election_id, derive_election_id(&key.fingerprint(), &[0u8; 32]));
+        assert_eq!(election.trustee_outputs.len(), 3);
+        assert!(election.store.ballots.is_empty() && election.store.audit_log.records().is_empty());
+        assert_eq!(election.store.partial_tallies.len(), 3);
+
+        assert!(matches!(builder().build_at(1_800_000_000), Err(SetupError::AlreadyClosed { .. })));
+        assert!(matches!(builder().candidates(["Fund", "Defer"]).build_at(0), Err(SetupError::Candidates(CandidatesError::WrongCount { count: 2 }))));
+        assert!(matches!(ElectionBuilder::new("treasury-vote").close_at(1).build_at(0), Err(SetupError::Missing { field: "eligibility root", .. })));
+        assert!(matches!(ElectionBuilder::new(" ").build_at(0), Err(SetupError::EmptyName)));
+        let unsorted = vec![VoterWeight { voter_address: "b".to_string(), weight: 1 }, VoterWeight { voter_address: "a".to_string(), weight: 1 }];
+        assert!(matches!(builder().scheme(TallyScheme::Weighted(unsorted)).build_at(0), Err(SetupError::Scheme(SchemeError::UnsortedWeights { .. }))));
+        assert_eq!(builder().scheme(TallyScheme::Approval).build_at(0)?.context.scheme_digest, TallyScheme::Approval.digest());
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+// Election state snapshots.
+// Captures everything an operator needs to carry on an election on another
+// machine: election metadata, the submission audit log, the accepted ballots,
+// revocations, encrypted partial tallies and the receipts produced so far.
+// Trustee key shares are not part of the state: trustees hold them.
+//
+// A snapshot is written as a manifested bundle (see `bundle`), so an
+// interrupted snapshot has no manifest and is refused. Restoring checks the
+// digests, the audit log chain, that the accepted records are the ballots,
+// and that the partial tallies are the sums of those ballots. `tally_input`
+// turns a restored snapshot back into the input the tally guest proves.
+
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+use thiserror::Error;
+
+use crate::audit_log::{verify_accepted_ballots, verify_audit_log, AuditLog, AuditLogError};
+use crate::ballot_set::BallotSetCommitment;
+use crate::bundle::{Bundle, BundleError};
+use crate::fhe_client::{Cipher, FheClientError, PublicKey, Signed, CIPHERTEXT_MODULUS, POLYNOMIAL_DEGREE};
+use crate::scheme::TallyScheme;
+use crate::types::{BallotRevocation, Candidate, ElectionContext, EncryptedVote, JournalProfile, VoteTallyInput};
+
+/// Layout version written to the manifest.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+const METADATA_FILE: &str = "metadata.json";
+const AUDIT_LOG_FILE: &str = "audit_log.bin";
+const BALLOTS_FILE: &str = "ballots.bin";
+const REVOCATIONS_FILE: &str = "revocations.bin";
+const PARTIAL_TALLIES_FILE: &str = "partial_tallies.bin";
+const RECEIPTS_DIR: &str = "receipts";
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("Snapshot bundle is invalid: {0}")]
+    Bundle(#[from] BundleError),
+    #[error("Snapshot encoding failed: {reason}")]
+    Encoding { reason: String },
+    #[error("Snapshot audit log is inconsistent: {0}")]
+    AuditLog(#[from] AuditLogError),
+    #[error("Snapshot ballot is malformed: {0}")]
+    Ballot(#[from] FheClientError),
+    #[error("Snapshot partial tallies are not the sums of its ballots")]
+    TallyMismatch,
+}
+
+fn encoding(e: impl ToString) -> SnapshotError {
+    SnapshotError::Encoding { reason: e.to_string() }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElectionMetadata {
+    pub election_public_key: PublicKey,
+    pub threshold: u32,
+    pub trustees: u32,
+    #[serde(with = "hex::serde")]
+    pub registrar_public_key: [u8; 32],
+    pub identity_roster: Vec<[u8; 32]>,
+    pub election: ElectionContext,
+    pub candidates: Vec<Candidate>,
+    pub scheme: TallyScheme,
+}
+
+pub struct ElectionSnapshot {
+    pub metadata: ElectionMetadata,
+    pub audit_log: AuditLog,
+    pub ballots: Vec<EncryptedVote>, // Accepted ballots, in arrival order
+    pub revocations: Vec<BallotRevocation>, // Matching the revoked records of the audit log
+    pub partial_tallies: Vec<Vec<u8>>, // Encrypted per-candidate sums of `ballots`
+    pub receipts: Vec<Vec<u8>>, // Serialized receipts, oldest first
+}
+
+/// Homomorphic per-candidate sums of `ballots`, each bound to `key_fingerprint`.
+/// Superseded ballots are included; the guest resolves re-voting at tally time.
+pub fn partial_tallies(ballots: &[EncryptedVote], key_fingerprint: &[u8; 32]) -> Result<Vec<Vec<u8>>, FheClientError> {
+    let mut sums = vec![vec![0u64; POLYNOMIAL_DEGREE * 2]; 3];
+    for ballot in ballots {
+        for (sum, ciphertext) in sums.iter_mut().zip(&ballot.encrypted_vote_vector) {
+            let cipher = Cipher::<Signed>::deserialize_bound(ciphertext, key_fingerprint)?;
+            for (s, c) in sum.iter_mut().zip(&cipher.ciphertext_data) {
+                *s = (*s + c) % CIPHERTEXT_MODULUS;
+            }
+        }
+    }
+    Ok(sums
+        .into_iter()
+        .map(|ciphertext_data| Cipher::<Signed> { ciphertext_data, _phantom: std::marker::PhantomData }.serialize_bound(key_fingerprint))
+        .collect())
+}
+
+impl ElectionSnapshot {
+    /// Snapshot of an election in progress; partial tallies are computed from `ballots`.
+    pub fn new(
+        metadata: ElectionMetadata,
+        audit_log: AuditLog,
+        ballots: Vec<EncryptedVote>,
+        revocations: Vec<BallotRevocation>,
+        receipts: Vec<Vec<u8>>,
+    ) -> Result<Self, SnapshotError> {
+        let partial_tallies = partial_tallies(&ballots, &metadata.election_public_key.fingerprint())?;
+        Ok(ElectionSnapshot { metadata, audit_log, ballots, revocations, partial_tallies, receipts })
+    }
+
+    /// Input for a tally of the restored ballots. Publication options
+    /// (journal profile, anonymity set, DP noise, EIP-712 domain, electorate)
+    /// and the trustees' decryption shares are not election state and start
+    /// from their defaults.
+    pub fn tally_input(&self) -> VoteTallyInput {
+        VoteTallyInput {
+            encrypted_votes: self.ballots.clone(),
+            ballot_set_root: BallotSetCommitment::from_ballots(&self.ballots).root(),
+            election_public_key: self.metadata.election_public_key.clone(),
+            registrar_public_key: self.metadata.registrar_public_key,
+            identity_roster: self.metadata.identity_roster.clone(),
+            audit_log: self.audit_log.records().to_vec(),
+            audit_log_head: self.audit_log.head(),
+            chain_anchor: None,
+            journal_profile: JournalProfile::Full,
+            interim: false,
+            prior_tally: None,
+            eip712_domain: None,
+            election: self.metadata.election,
+            candidates: self.metadata.candidates.clone(),
+            scheme: self.metadata.scheme.clone(),
+            min_anonymity_set: 0,
+            dp_noise: None,
+            revocations: self.revocations.clone(),
+            electorate: None,
+            decryption: None,
+            mix: None,
+        }
+    }
+
+    fn bundle(&self) -> Result<Bundle, SnapshotError> {
+        let mut bundle = Bundle::new(SNAPSHOT_VERSION);
+        bundle.insert(METADATA_FILE, serde_json::to_vec_pretty(&self.metadata).map_err(encoding)?);
+        bundle.insert(AUDIT_LOG_FILE, bincode::serialize(&self.audit_log).map_err(encoding)?);
+        bundle.insert(BALLOTS_FILE, bincode::serialize(&self.ballots).map_err(encoding)?);
+        bundle.insert(REVOCATIONS_FILE, bincode::serialize(&self.revocations).map_err(encoding)?);
+        bundle.insert(PARTIAL_TALLIES_FILE, bincode::serialize(&self.partial_tallies).map_err(encoding)?);
+        for (index, receipt) in self.receipts.iter().enumerate() {
+            bundle.insert(format!("{}/{}.bin", RECEIPTS_DIR, index), receipt.clone());
+        }
+        Ok(bundle)
+    }
+
+    // Decode a verified bundle and cross-check the state
+    fn from_bundle(mut bundle: Bundle) -> Result<Self, SnapshotError> {
+        let metadata: ElectionMetadata = serde_json::from_slice(&bundle.take(METADATA_FILE)?).map_err(encoding)?;
+        let audit_log: AuditLog = bincode::deserialize(&bundle.take(AUDIT_LOG_FILE)?).map_err(encoding)?;
+        let ballots: Vec<EncryptedVote> = bincode::deserialize(&bundle.take(BALLOTS_FILE)?).map_err(encoding)?;
+        let revocations: Vec<BallotRevocation> = bincode::deserialize(&bundle.take(REVOCATIONS_FILE)?).map_err(encoding)?;
+        let stored_tallies: Vec<Vec<u8>> = bincode::deserialize(&bundle.take(PARTIAL_TALLIES_FILE)?).map_err(encoding)?;
+        let receipts = (0..).map_while(|index| bundle.take(&format!("{}/{}.bin", RECEIPTS_DIR, index)).ok()).collect();
+
+        verify_audit_log(audit_log.records(), &audit_log.head())?;
+        verify_accepted_ballots(audit_log.records(), &ballots)?;
+        let snapshot = ElectionSnapshot::new(metadata, audit_log, ballots, revocations, receipts)?;
+        if snapshot.partial_tallies != stored_tallies {
+            return Err(SnapshotError::TallyMismatch);
+        }
+        Ok(snapshot)
+    }
+
+    /// Write into `dir`, replacing a snapshot already there.
+    pub fn write_dir(&self, dir: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        Ok(self.bundle()?.write_dir(dir)?)
+    }
+
+    pub fn read_dir(dir: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        Self::from_bundle(Bundle::read_dir(dir, SNAPSHOT_VERSION)?)
+    }
+
+    /// Write a gzipped tarball (manifest last).
+    pub fn write_tarball(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        Ok(self.bundle()?.write_tarball(path)?)
+    }
+
+    pub fn read_tarball(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        Self::from_bundle(Bundle::read_tarball(path, SNAPSHOT_VERSION)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::bundle::MANIFEST_FILE;
+    use crate::fhe_client::FheClient;
+    use crate::types::{BallotAuthorization, Digest32, MembershipProof, VoteOption};
+
+    #[test]
+    fn test_snapshot_restores_from_dir_and_tarball() -> Result<(), Box<dyn std::error::Error>> {
+        let client = FheClient::new();
+        let mut audit_log = AuditLog::new();
+        let mut ballots = Vec::new();
+        for (i, choice) in [VoteOption::Option1, VoteOption::Option3].into_iter().enumerate() {
+            let ballot = EncryptedVote {
+                voter_address: format!("voter-{}", i),
+                election_id: Digest32::ZERO,
+                nonce: [i as u8; 32],
+                encrypted_vote_vector: client.encrypt_vote_vectors(&[choice])?.remove(0),
+                signature: String::new(),
+                authorization: BallotAuthorization::Membership(MembershipProof {
+                    nullifier: [i as u8; 32],
+                    challenges: Vec::new(),
+                    responses: Vec::new(),
+                }),
+                sequence: 0,
+                actual_choice: choice,
+            };
+            audit_log.record_accepted(&ballot);
+            ballots.push(ballot);
+        }
+        let metadata = ElectionMetadata {
+            election_public_key: client.get_public_key().clone(),
+            threshold: 2,
+            trustees: 3,
+            registrar_public_key: [7u8; 32],
+            identity_roster: vec![[8u8; 32]],
+            election: ElectionContext { closes_at: 1_700_000_000, ..ElectionContext::default() },
+            candidates: Vec::new(),
+            scheme: TallyScheme::Approval,
+        };
+        let snapshot = ElectionSnapshot::new(metadata, audit_log, ballots, Vec::new(), vec![vec![1, 2, 3]])?;
+
+        let base = std::env::temp_dir().join(format!("fhe-voting-snapshot-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        snapshot.write_dir(&base)?;
+        let restored = ElectionSnapshot::read_dir(&base)?;
+        assert_eq!(restored.audit_log.head(), snapshot.audit_log.head());
+        assert_eq!(restored.partial_tallies, snapshot.partial_tallies);
+        assert_eq!(restored.receipts, snapshot.receipts);
+
+        // The restored state is what the tally guest is given to prove
+        let input = restored.tally_input();
+        assert_eq!(input.ballot_set_root, BallotSetCommitment::from_ballots(&snapshot.ballots).root());
+        assert_eq!((input.audit_log_head, input.election.closes_at, input.scheme), (snapshot.audit_log.head(), 1_700_000_000, TallyScheme::Approval));
+
+        // Rewriting in place replaces the snapshot, receipts included
+        let mut continued = restored;
+        continued.receipts.push(vec![4, 5]);
+        continued.write_dir(&base)?;
+        assert_eq!(ElectionSnapshot::read_dir(&base)?.receipts, vec![vec![1, 2, 3], vec![4, 5]]);
+
+        let tarball = base.with_extension("tar.gz");
+        snapshot.write_tarball(&tarball)?;
+        assert_eq!(ElectionSnapshot::read_tarball(&tarball)?.ballots.len(), 2);
+        fs::remove_file(&tarball)?;
+
+        // A corrupted file or an interrupted snapshot is refused
+        fs::write(base.join(BALLOTS_FILE), b"corrupt")?;
+        assert!(matches!(ElectionSnapshot::read_dir(&base), Err(SnapshotError::Bundle(BundleError::DigestMismatch { .. }))));
+        fs::remove_file(base.join(MANIFEST_FILE))?;
+        assert!(matches!(ElectionSnapshot::read_dir(&base), Err(SnapshotError::Bundle(BundleError::MissingFile { .. }))));
+        fs::remove_dir_all(&base)?;
+        Ok(())
+    }
+}
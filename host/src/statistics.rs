@@ -0,0 +1,109 @@
+// Encrypted statistics over numeric poll answers.
+// The scheme only adds ciphertexts, so a respondent encrypts both its answer
+// x and x² (`encrypt_response`); the vector sum guest adds the responses slot
+// by slot, giving the encrypted sum and sum of squares. Once the trustees
+// decrypt those two values `Statistics::from_sums` gives the mean and the
+// variance, and no single answer is ever decrypted.
+//
+// The square slot is bounded by `max_answer²`, which caps a poll at
+// `max_sum_contributions(max_answer²)` responses. The guest cannot check that
+// the second slot really is the square of the first; `from_sums` refuses sums
+// that no honest responses could produce.
+
+use thiserror::Error;
+
+use crate::fhe_client::{FheClient, FheClientError, PublicKey, PLAINTEXT_MODULUS};
+use crate::types::VectorSumInput;
+use crate::vector_sum::{vector_sum_input, VectorSumError};
+
+/// Slots of a response: the answer and its square.
+pub const RESPONSE_SLOTS: u32 = 2;
+
+#[derive(Error, Debug)]
+pub enum StatisticsError {
+    #[error("Answer {answer} is above the poll maximum {max_answer}")]
+    AnswerOutOfRange { answer: u64, max_answer: u64 },
+    #[error("Poll maximum {max_answer} squared exceeds the plaintext modulus")]
+    MaxAnswerTooLarge { max_answer: u64 },
+    #[error("Response encryption failed: {0}")]
+    Encryption(#[from] FheClientError),
+    #[error("Responses cannot be summed: {0}")]
+    VectorSum(#[from] VectorSumError),
+    #[error("Statistics need at least one response")]
+    NoResponses,
+    #[error("Decrypted sums are inconsistent: the squares do not match the answers")]
+    MalformedSums,
+}
+
+/// Mean and population variance of the poll answers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Statistics {
+    pub responses: u32,
+    pub sum: u64,
+    pub sum_of_squares: u64,
+    pub mean: f64,
+    pub variance: f64,
+}
+
+impl Statistics {
+    /// Statistics from the decrypted sum and sum of squares of `responses` answers.
+    pub fn from_sums(responses: u32, sum: u64, sum_of_squares: u64) -> Result<Self, StatisticsError> {
+        if responses == 0 {
+            return Err(StatisticsError::NoResponses);
+        }
+        let n = u128::from(responses);
+        // n·Σx² ≥ (Σx)² holds for any real answers (Cauchy–Schwarz)
+        let spread = (n * u128::from(sum_of_squares)).checked_sub(u128::from(sum) * u128::from(sum)).ok_or(StatisticsError::MalformedSums)?;
+        Ok(Statistics {
+            responses,
+            sum,
+            sum_of_squares,
+            mean: sum as f64 / responses as f64,
+            variance: spread as f64 / (n * n) as f64,
+        })
+    }
+}
+
+/// Encrypt one answer as the response vector [answer, answer²].
+pub fn encrypt_response(client: &FheClient, answer: u64, max_answer: u64) -> Result<Vec<Vec<u8>>, StatisticsError> {
+    if answer > max_answer {
+        return Err(StatisticsError::AnswerOutOfRange { answer, max_answer });
+    }
+    Ok(client.encrypt_values(&[answer, answer * answer])?)
+}
+
+/// Vector sum input over the responses; every slot is bounded by `max_answer²`.
+pub fn statistics_input(public_key: PublicKey, max_answer: u64, responses: Vec<Vec<Vec<u8>>>) -> Result<VectorSumInput, StatisticsError> {
+    let max_square = max_answer.checked_mul(max_answer).filter(|&square| square < PLAINTEXT_MODULUS).ok_or(StatisticsError::MaxAnswerTooLarge { max_answer })?;
+    Ok(vector_sum_input(public_key, RESPONSE_SLOTS, max_square.max(1), responses)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mean_and_variance_from_sums() -> Result<(), StatisticsError> {
+        // Answers 2, 4, 4, 4, 5, 5, 7, 9: mean 5, variance 4
+        let stats = Statistics::from_sums(8, 40, 232)?;
+        assert_eq!((stats.mean, stats.variance), (5.0, 4.0));
+        assert!(matches!(Statistics::from_sums(2, 10, 20), Err(StatisticsError::MalformedSums)));
+        assert!(matches!(Statistics::from_sums(0, 0, 0), Err(StatisticsError::NoResponses)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_statistics_input_bounds_the_squares() -> Result<(), StatisticsError> {
+        let client = FheClient::new();
+        let public_key = client.get_public_key().clone();
+        let responses = vec![encrypt_response(&client, 3, 10)?, encrypt_response(&client, 10, 10)?];
+        let input = statistics_input(public_key.clone(), 10, responses)?;
+        assert_eq!((input.vector_len, input.max_value), (RESPONSE_SLOTS, 100));
+        assert!(matches!(encrypt_response(&client, 11, 10), Err(StatisticsError::AnswerOutOfRange { answer: 11, max_answer: 10 })));
+        // Squares up to 200² leave room for a single response
+        let responses = vec![encrypt_response(&client, 1, 200)?, encrypt_response(&client, 2, 200)?];
+        assert!(matches!(statistics_input(public_key.clone(), 200, responses), Err(StatisticsError::VectorSum(VectorSumError::Overflow { .. }))));
+        assert!(matches!(statistics_input(public_key, 300, Vec::new()), Err(StatisticsError::MaxAnswerTooLarge { max_answer: 300 })));
+        Ok(())
+    }
+}
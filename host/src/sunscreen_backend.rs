@@ -0,0 +1,217 @@
+// Sunscreen BFV backend (enabled with the `sunscreen` cargo feature).
+// Ciphertext addition runs a compiled one-line FHE program, which is how
+// Sunscreen evaluates anything on ciphertexts. Sunscreen builds on Microsoft
+// SEAL, so this is host/client-side only until it compiles for the zkVM target.
+// Its ciphertexts are not interchangeable with the pure Rust scheme's.
+//
+// SEAL interop: ciphertexts and public keys can be exported as the raw bytes of
+// SEAL's own `save` (header, parms_id and coefficients) and imported from
+// bytes produced by SEAL, or any tooling that writes SEAL's format, under the
+// parameters `params()` reports. This lets mainstream FHE tooling produce
+// ballots the Sunscreen backend can tally and lets auditors load its encrypted
+// results in SEAL.
+//
+// This interop covers the Sunscreen backend only. The zkVM guest's pure Rust
+// scheme has no SEAL encoding: SEAL's coefficient moduli must be NTT-friendly
+// primes and its ring degree at least 1024, while the guest uses q = 2^58 and
+// N = 32. SEAL builds no keys or ciphertexts under those parameters
+// (`check_guest_parameters` reports why), so ballots from SEAL tooling are
+// tallied by the Sunscreen backend, not by the guest.
+
+use seal_fhe::{BfvEncryptionParametersBuilder, Context, FromBytes, KeyGenerator, Modulus, SecurityLevel, ToBytes};
+use sunscreen::types::{bfv::Signed, Cipher, Type, TypeName};
+use sunscreen::{
+    fhe_program, Ciphertext, CompiledFheProgram, FheProgramFnExt, FheRuntime, InnerCiphertext, Params, PrivateKey,
+    PublicKey, WithContext,
+};
+
+use crate::fhe_backend::{BackendError, FheBackend};
+use crate::fhe_client::{CIPHERTEXT_MODULUS, PLAINTEXT_MODULUS, POLYNOMIAL_DEGREE};
+
+#[fhe_program(scheme = "bfv")]
+fn tally_add(sum: Cipher<Signed>, term: Cipher<Signed>) -> Cipher<Signed> {
+    sum + term
+}
+
+pub struct SunscreenBackend {
+    program: CompiledFheProgram,
+    runtime: FheRuntime,
+    public_key: Option<PublicKey>, // Evaluation needs the public key; set by `generate_keys`
+}
+
+impl SunscreenBackend {
+    pub fn new() -> Result<Self, BackendError> {
+        let program = tally_add
+            .compile()
+            .map_err(|e| BackendError::KeyGeneration { reason: e.to_string() })?;
+        let runtime = FheRuntime::new(&program.metadata.params)
+            .map_err(|e| BackendError::KeyGeneration { reason: e.to_string() })?;
+        Ok(SunscreenBackend { program, runtime, public_key: None })
+    }
+
+    /// Encryption parameters a SEAL peer must use for exported ciphertexts and keys.
+    pub fn params(&self) -> &Params {
+        self.runtime.params()
+    }
+
+    /// SEAL's serialization of a ciphertext produced by this backend.
+    pub fn export_seal_ciphertext(&self, ciphertext: &Ciphertext) -> Result<Vec<u8>, BackendError> {
+        let InnerCiphertext::Seal(parts) = &ciphertext.inner;
+        match parts.as_slice() {
+            [part] => part.data.as_bytes().map_err(serialization),
+            _ => Err(BackendError::Serialization {
+                reason: format!("expected one SEAL ciphertext, found {}", parts.len()),
+            }),
+        }
+    }
+
+    /// Load a SEAL-serialized ciphertext of a signed integer under `params()`.
+    pub fn import_seal_ciphertext(&self, bytes: &[u8]) -> Result<Ciphertext, BackendError> {
+        let data = seal_fhe::Ciphertext::from_bytes(&self.seal_context()?, bytes).map_err(serialization)?;
+        Ok(Ciphertext {
+            data_type: Type { is_encrypted: true, ..Signed::type_name() },
+            inner: InnerCiphertext::Seal(vec![WithContext { params: self.params().clone(), data }]),
+        })
+    }
+
+    /// SEAL's serialization of the encryption key (Galois and relinearization keys are not needed to tally).
+    pub fn export_seal_public_key(&self, public_key: &PublicKey) -> Result<Vec<u8>, BackendError> {
+        public_key.public_key.data.as_bytes().map_err(serialization)
+    }
+
+    /// Load a SEAL-serialized public key, e.g. to encrypt ballots for a SEAL-held secret key.
+    pub fn import_seal_public_key(&self, bytes: &[u8]) -> Result<PublicKey, BackendError> {
+        let data = seal_fhe::PublicKey::from_bytes(&self.seal_context()?, bytes).map_err(serialization)?;
+        Ok(PublicKey {
+            public_key: WithContext { params: self.params().clone(), data },
+            galois_key: None,
+            relin_key: None,
+        })
+    }
+
+    fn seal_context(&self) -> Result<Context, BackendError> {
+        let params = self.params();
+        seal_context(&params.coeff_modulus, params.plain_modulus, params.lattice_dimension, params.security_level)
+    }
+}
+
+/// Whether SEAL can use the zkVM guest's parameters, and so exchange ciphertexts
+/// with it. SEAL takes the moduli as values but refuses to generate keys under
+/// them; the error carries its reason.
+pub fn check_guest_parameters() -> Result<(), BackendError> {
+    let context = seal_context(&[CIPHERTEXT_MODULUS], PLAINTEXT_MODULUS, POLYNOMIAL_DEGREE as u64, SecurityLevel::TC128)?;
+    KeyGenerator::new(&context).map(drop).map_err(serialization)
+}
+
+fn seal_context(
+    coefficient_modulus: &[u64],
+    plain_modulus: u64,
+    degree: u64,
+    security_level: SecurityLevel,
+) -> Result<Context, BackendError> {
+    let coefficient_modulus = coefficient_modulus
+        .iter()
+        .map(|&modulus| Modulus::new(modulus))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(serialization)?;
+    let encryption_params = BfvEncryptionParametersBuilder::new()
+        .set_coefficient_modulus(coefficient_modulus)
+        .set_plain_modulus_u64(plain_modulus)
+        .set_poly_modulus_degree(degree)
+        .build()
+        .map_err(serialization)?;
+    Context::new(&encryption_params, false, security_level).map_err(serialization)
+}
+
+fn serialization(e: seal_fhe::Error) -> BackendError {
+    BackendError::Serialization { reason: e.to_string() }
+}
+
+impl FheBackend for SunscreenBackend {
+    type PublicKey = PublicKey;
+    type PrivateKey = PrivateKey;
+    type Ciphertext = Ciphertext;
+
+    fn generate_keys(&mut self) -> Result<(PublicKey, PrivateKey), BackendError> {
+        let (public_key, private_key) = self
+            .runtime
+            .generate_keys()
+            .map_err(|e| BackendError::KeyGeneration { reason: e.to_string() })?;
+        self.public_key = Some(public_key.clone());
+        Ok((public_key, private_key))
+    }
+
+    fn encrypt(&self, value: i64, public_key: &PublicKey) -> Result<Ciphertext, BackendError> {
+        self.runtime
+            .encrypt(Signed::from(value), public_key)
+            .map_err(|e| BackendError::Encryption { reason: e.to_string() })
+    }
+
+    fn add_assign(&self, sum: &mut Ciphertext, term: &Ciphertext) -> Result<(), BackendError> {
+        let evaluation = |reason: String| BackendError::Evaluation { reason };
+        let public_key = self.public_key.as_ref().ok_or_else(|| evaluation("no keys generated".to_string()))?;
+        let mut outputs = self
+            .runtime
+            .run(&self.program, vec![sum.clone(), term.clone()], public_key)
+            .map_err(|e| evaluation(e.to_string()))?;
+        *sum = outputs.pop().ok_or_else(|| evaluation("tally_add returned no output".to_string()))?;
+        Ok(())
+    }
+
+    fn serialize(&self, ciphertext: &Ciphertext) -> Result<Vec<u8>, BackendError> {
+        bincode::serialize(ciphertext).map_err(|e| BackendError::Serialization { reason: e.to_string() })
+    }
+
+    fn deserialize(&self, data: &[u8]) -> Result<Ciphertext, BackendError> {
+        bincode::deserialize(data).map_err(|e| BackendError::Serialization { reason: e.to_string() })
+    }
+
+    fn decrypt(&self, ciphertext: &Ciphertext, private_key: &PrivateKey) -> Result<i64, BackendError> {
+        let plaintext: Signed = self
+            .runtime
+            .decrypt(ciphertext, private_key)
+            .map_err(|e| BackendError::Decryption { reason: e.to_string() })?;
+        Ok(plaintext.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sunscreen_backend_tallies_through_serialization() -> Result<(), BackendError> {
+        let mut backend = SunscreenBackend::new()?;
+        let (public_key, private_key) = backend.generate_keys()?;
+
+        let mut sum = backend.encrypt(0, &public_key)?;
+        for vote in [1, 0, 1, 1] {
+            let ballot = backend.encrypt(vote, &public_key)?;
+            let received = backend.deserialize(&backend.serialize(&ballot)?)?;
+            backend.add_assign(&mut sum, &received)?;
+        }
+        assert_eq!(backend.decrypt(&sum, &private_key)?, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_seal_ciphertext_and_key_round_trip() -> Result<(), BackendError> {
+        let mut backend = SunscreenBackend::new()?;
+        let (public_key, private_key) = backend.generate_keys()?;
+
+        // A ballot encrypted under a key that went through SEAL's format
+        let imported_key = backend.import_seal_public_key(&backend.export_seal_public_key(&public_key)?)?;
+        let ballot = backend.encrypt(1, &imported_key)?;
+        let mut sum = backend.import_seal_ciphertext(&backend.export_seal_ciphertext(&ballot)?)?;
+        backend.add_assign(&mut sum, &ballot)?;
+        assert_eq!(backend.decrypt(&sum, &private_key)?, 2);
+
+        assert!(backend.import_seal_ciphertext(&[0u8; 16]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_guest_parameters_are_rejected_by_seal() {
+        assert!(matches!(check_guest_parameters(), Err(BackendError::Serialization { .. })));
+    }
+}
@@ -0,0 +1,206 @@
+// Election tally backends with a plaintext reference.
+// A `TallyBackend` runs a whole tally over a list of ballots: validate each
+// ballot under the election's `VoteScheme`, accumulate the valid ones and
+// finalize. `FheTally` encrypts every ballot under a fresh key and tallies the
+// way the guest does (key-bound ciphertexts, shape-only validation, lazy
+// weighted addition, decryption of the sums); `PlaintextTally` runs the same
+// scheme over the ballots in the clear, where the answer is obvious.
+//
+// `diff_tallies` runs both on the same ballots and names the first count,
+// rejection total or winner they disagree on, so a correctness bug in the FHE
+// path shows up as a mismatch against the reference. Unlike `differential`,
+// which compares FHE libraries operation by operation, this compares whole
+// election results, voting rules included. Ballots the reference rejects for
+// their contents (which encryption hides) are left out of the FHE run, as an
+// honest client would never produce them; wrong lengths and voters without a
+// weight are rejected by both.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use thiserror::Error;
+
+use crate::fhe_client::{Cipher, FheClient, FheClientError, Signed};
+use crate::scheme::{Accumulator, BallotEntry, SchemeError, TallyScheme, VoteScheme};
+
+#[derive(Error, Debug)]
+pub enum TallyBackendError {
+    #[error("Encrypted tally failed: {0}")]
+    Fhe(#[from] FheClientError),
+    #[error("Backends disagree on {what}: {reference} in the plaintext reference, {fhe} under FHE")]
+    Mismatch { what: String, reference: String, fhe: String },
+}
+
+/// A ballot as a client would fill it in: one entry per candidate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReferenceBallot {
+    pub voter_address: String,
+    pub entries: Vec<u64>,
+}
+
+/// What a backend tallied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TallyResult {
+    pub counts: Vec<u64>,
+    pub winners: Vec<usize>,
+    pub rejected: usize,
+}
+
+pub trait TallyBackend {
+    fn name(&self) -> &'static str;
+    fn tally(&mut self, scheme: &TallyScheme, candidates: usize, ballots: &[ReferenceBallot]) -> Result<TallyResult, TallyBackendError>;
+}
+
+/// Tally over plaintext entries.
+pub struct PlaintextTally;
+
+impl TallyBackend for PlaintextTally {
+    fn name(&self) -> &'static str {
+        "plaintext"
+    }
+
+    fn tally(&mut self, scheme: &TallyScheme, candidates: usize, ballots: &[ReferenceBallot]) -> Result<TallyResult, TallyBackendError> {
+        let mut counts = vec![0u64; candidates];
+        let mut rejected = 0;
+        for ballot in ballots {
+            match scheme.validate_ballot(&ballot.voter_address, &ballot.entries, candidates) {
+                Ok(weight) => scheme.accumulate(&mut counts, &ballot.entries, weight),
+                Err(_) => rejected += 1,
+            }
+        }
+        Ok(TallyResult { winners: scheme.finalize(&counts), counts, rejected })
+    }
+}
+
+// Serialized ciphertexts: the scheme sees their number, not their contents
+impl BallotEntry for Vec<u8> {}
+
+// Wrapping sums reduced once at the end, as in the guest's `TallyAccumulator`
+impl Accumulator<Cipher<Signed>> for Cipher<Signed> {
+    fn add_weighted(&mut self, entry: &Cipher<Signed>, weight: u64) {
+        *self = if weight == 1 { self.add(entry) } else { self.add(&entry.mul_plain(weight)) };
+    }
+}
+
+/// Tally over ciphertexts of the pure Rust scheme the guest runs.
+pub struct FheTally {
+    client: FheClient,
+}
+
+impl FheTally {
+    pub fn new() -> Self {
+        FheTally { client: FheClient::new() }
+    }
+}
+
+impl Default for FheTally {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TallyBackend for FheTally {
+    fn name(&self) -> &'static str {
+        "fhe"
+    }
+
+    fn tally(&mut self, scheme: &TallyScheme, candidates: usize, ballots: &[ReferenceBallot]) -> Result<TallyResult, TallyBackendError> {
+        let fingerprint = self.client.get_public_key().fingerprint();
+        let zero = Cipher::<Signed>::deserialize_bound(&self.client.encrypt_values(&[0])?[0], &fingerprint)?;
+        let mut sums = vec![zero; candidates];
+        let mut rejected = 0;
+        for ballot in ballots {
+            let encrypted = self.client.encrypt_values(&ballot.entries)?;
+            let weight = match scheme.validate_ballot(&ballot.voter_address, &encrypted, candidates) {
+                Ok(weight) => weight,
+                Err(_) => {
+                    rejected += 1;
+                    continue;
+                }
+            };
+            let ciphers = encrypted.iter().map(|bytes| Cipher::<Signed>::deserialize_bound(bytes, &fingerprint)).collect::<Result<Vec<_>, _>>()?;
+            scheme.accumulate(&mut sums, &ciphers, weight);
+        }
+        let counts = sums.iter().map(|sum| Ok(self.client.decrypt(sum)?.coefficients()[0])).collect::<Result<Vec<u64>, FheClientError>>()?;
+        Ok(TallyResult { winners: scheme.finalize(&counts), counts, rejected })
+    }
+}
+
+/// Tally `ballots` with both backends and require the same result.
+pub fn diff_tallies(scheme: &TallyScheme, candidates: usize, ballots: &[ReferenceBallot]) -> Result<TallyResult, TallyBackendError> {
+    let reference = PlaintextTally.tally(scheme, candidates, ballots)?;
+    // Content errors are invisible under encryption, so those ballots are not encrypted
+    let well_formed: Vec<ReferenceBallot> = ballots
+        .iter()
+        .filter(|ballot| {
+            !matches!(
+                scheme.validate_ballot(&ballot.voter_address, &ballot.entries, candidates),
+                Err(SchemeError::NotOneHot | SchemeError::NotApproval)
+            )
+        })
+        .cloned()
+        .collect();
+    let mut fhe = FheTally::new().tally(scheme, candidates, &well_formed)?;
+    fhe.rejected += ballots.len() - well_formed.len();
+
+    let mismatch = |what: String, reference: &dyn std::fmt::Debug, fhe: &dyn std::fmt::Debug| TallyBackendError::Mismatch {
+        what,
+        reference: format!("{:?}", reference),
+        fhe: format!("{:?}", fhe),
+    };
+    if let Some((candidate, (left, right))) = reference.counts.iter().zip(&fhe.counts).enumerate().find(|(_, (l, r))| l != r) {
+        return Err(mismatch(format!("the count for candidate {}", candidate + 1), left, right));
+    }
+    if reference.rejected != fhe.rejected {
+        return Err(mismatch("rejected ballots".to_string(), &reference.rejected, &fhe.rejected));
+    }
+    if reference.winners != fhe.winners {
+        return Err(mismatch("the winners".to_string(), &reference.winners, &fhe.winners));
+    }
+    Ok(reference)
+}
+
+/// `count` seeded ballots from `voters` voters, mostly well formed for `scheme`.
+pub fn random_ballots(seed: u64, count: usize, voters: usize, scheme: &TallyScheme, candidates: usize) -> Vec<ReferenceBallot> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..count)
+        .map(|_| {
+            let mut entries = match scheme {
+                TallyScheme::Approval => (0..candidates).map(|_| rng.gen_range(0..=1)).collect(),
+                TallyScheme::Plurality | TallyScheme::Weighted(_) => {
+                    let mut entries = vec![0; candidates];
+                    entries[rng.gen_range(0..candidates)] = 1;
+                    entries
+                }
+            };
+            // One ballot in ten is malformed
+            if rng.gen_ratio(1, 10) {
+                entries[0] = 2;
+            }
+            ReferenceBallot { voter_address: format!("voter-{}", rng.gen_range(0..voters)), entries }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheme::VoterWeight;
+
+    #[test]
+    fn test_fhe_tally_matches_the_plaintext_reference() -> Result<(), TallyBackendError> {
+        let weights = (0..8).map(|i| VoterWeight { voter_address: format!("voter-{}", i), weight: i + 1 }).collect();
+        for scheme in [TallyScheme::Plurality, TallyScheme::Approval, TallyScheme::weighted(weights)] {
+            for seed in 0..3 {
+                // Ten voters against eight weights, so weighted runs also reject unknown voters
+                let ballots = random_ballots(seed, 40, 10, &scheme, 3);
+                let result = diff_tallies(&scheme, 3, &ballots)?;
+                assert!(result.rejected > 0 && result.counts.iter().sum::<u64>() > 0);
+            }
+        }
+
+        // Ballots of the wrong length are rejected by both
+        let short = vec![ReferenceBallot { voter_address: "voter-0".to_string(), entries: vec![1, 0] }];
+        assert_eq!(diff_tallies(&TallyScheme::Plurality, 3, &short)?.rejected, 1);
+        Ok(())
+    }
+}
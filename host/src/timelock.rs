@@ -0,0 +1,240 @@
+// Time-locked election results.
+// A tally proven before the polls close would, once published, tell the
+// voters still to vote how the election stands. A `TimeLock` seals the result
+// (the verified `FinalizedResult` together with the receipt, whose journal
+// carries the counts in the clear) so that nobody, the operator included once
+// the sealing secrets are dropped, can read it before the reveal time.
+//
+// The lock is a Rivest-Shamir-Wagner puzzle: the key is H(2^(2^t) mod N) for
+// an RSA modulus N whose factors are discarded after sealing. Knowing them,
+// the sealer reduces the exponent mod phi(N) and computes the key at once;
+// everyone else must do t sequential squarings, which no amount of parallel
+// hardware speeds up. t is calibrated from the squaring rate measured on the
+// sealing machine and the time left until the reveal; a faster solver opens
+// the lock early by the ratio of the speeds, so the rate should be the fastest
+// hardware an adversary is assumed to have, not the operator's.
+//
+//   key        = H(tag || 2^(2^t) mod N), big-endian, padded to N's length
+//   ciphertext = plaintext XOR ChaCha20(key)
+//   check      = H(check tag || key || plaintext)
+//
+// The check binds the key, so a guessable plaintext (a handful of small
+// counts) cannot be confirmed without solving the puzzle.
+
+use std::time::Instant;
+
+use num_bigint::BigUint;
+use rand::{CryptoRng, RngCore};
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::hooks::FinalizedResult;
+
+// Domain separation tags for the puzzle key and the plaintext check
+const KEY_TAG: u8 = 0x15;
+const CHECK_TAG: u8 = 0x16;
+
+/// Bits in each prime factor of the puzzle modulus.
+pub const PRIME_BITS: u64 = 1024;
+
+#[derive(Error, Debug)]
+pub enum TimeLockError {
+    #[error("Solved key does not open the sealed result (corrupt or tampered lock)")]
+    CheckFailed,
+    #[error("Sealed result encoding failed: {reason}")]
+    Encoding { reason: String },
+}
+
+/// A result only readable after `squarings` sequential squarings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeLock {
+    #[serde(with = "hex::serde")]
+    pub modulus: Vec<u8>,
+    pub squarings: u64,
+    /// Unix seconds the lock was calibrated to open at.
+    pub reveal_at: u64,
+    #[serde(with = "hex::serde")]
+    pub ciphertext: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub check: [u8; 32],
+}
+
+/// What the host seals: the verified result and the receipt proving it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealedTally {
+    pub result: FinalizedResult,
+    pub receipt: Vec<u8>, // bincode, as in archives
+}
+
+impl SealedTally {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, TimeLockError> {
+        bincode::serialize(self).map_err(|e| TimeLockError::Encoding { reason: e.to_string() })
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TimeLockError> {
+        bincode::deserialize(bytes).map_err(|e| TimeLockError::Encoding { reason: e.to_string() })
+    }
+}
+
+impl TimeLock {
+    /// Seal `plaintext` behind `squarings` squarings.
+    pub fn seal<R: RngCore + CryptoRng>(plaintext: &[u8], squarings: u64, reveal_at: u64, rng: &mut R) -> Self {
+        let p = random_prime(PRIME_BITS, rng);
+        let q = random_prime(PRIME_BITS, rng);
+        let modulus = &p * &q;
+        let phi = (p - 1u32) * (q - 1u32);
+        // The shortcut: 2^(2^t) = 2^(2^t mod phi) mod N
+        let exponent = BigUint::from(2u32).modpow(&BigUint::from(squarings), &phi);
+        let key = puzzle_key(&BigUint::from(2u32).modpow(&exponent, &modulus), &modulus);
+        TimeLock {
+            modulus: modulus.to_bytes_be(),
+            squarings,
+            reveal_at,
+            ciphertext: keystream_xor(&key, plaintext),
+            check: check(&key, plaintext),
+        }
+    }
+
+    /// Solve the puzzle the slow way and return the plaintext.
+    pub fn open(&self) -> Result<Vec<u8>, TimeLockError> {
+        let modulus = BigUint::from_bytes_be(&self.modulus);
+        let mut value = BigUint::from(2u32);
+        for _ in 0..self.squarings {
+            value = &value * &value % &modulus;
+        }
+        let key = puzzle_key(&value, &modulus);
+        let plaintext = keystream_xor(&key, &self.ciphertext);
+        if check(&key, &plaintext) != self.check {
+            return Err(TimeLockError::CheckFailed);
+        }
+        Ok(plaintext)
+    }
+
+    pub fn to_json(&self) -> Result<Vec<u8>, TimeLockError> {
+        serde_json::to_vec_pretty(self).map_err(|e| TimeLockError::Encoding { reason: e.to_string() })
+    }
+
+    pub fn from_json(bytes: &[u8]) -> Result<Self, TimeLockError> {
+        serde_json::from_slice(bytes).map_err(|e| TimeLockError::Encoding { reason: e.to_string() })
+    }
+}
+
+/// Squarings per second modulo a puzzle-sized modulus on this machine.
+pub fn squaring_rate<R: RngCore>(rng: &mut R) -> u64 {
+    // Any odd modulus of the right size squares at the same speed as a puzzle's
+    let mut bytes = vec![0u8; (2 * PRIME_BITS / 8) as usize];
+    rng.fill_bytes(&mut bytes);
+    bytes[0] |= 0x80;
+    let modulus = BigUint::from_bytes_be(&bytes) | BigUint::from(1u32);
+    let mut value = BigUint::from(2u32);
+    let sample = 20_000u64;
+    let started = Instant::now();
+    for _ in 0..sample {
+        value = &value * &value % &modulus;
+    }
+    (sample as f64 / started.elapsed().as_secs_f64().max(1e-9)) as u64
+}
+
+/// Squarings that take until `reveal_at` at `rate` squarings per second.
+pub fn squarings_until(reveal_at: u64, now: u64, rate: u64) -> u64 {
+    reveal_at.saturating_sub(now).saturating_mul(rate)
+}
+
+fn puzzle_key(value: &BigUint, modulus: &BigUint) -> [u8; 32] {
+    let width = modulus.to_bytes_be().len();
+    let bytes = value.to_bytes_be();
+    let mut buf = vec![KEY_TAG];
+    buf.resize(1 + width - bytes.len(), 0);
+    buf.extend_from_slice(&bytes);
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+fn keystream_xor(key: &[u8; 32], data: &[u8]) -> Vec<u8> {
+    let mut stream = vec![0u8; data.len()];
+    ChaCha20Rng::from_seed(*key).fill_bytes(&mut stream);
+    data.iter().zip(stream).map(|(byte, pad)| byte ^ pad).collect()
+}
+
+fn check(key: &[u8; 32], plaintext: &[u8]) -> [u8; 32] {
+    let mut buf = vec![CHECK_TAG];
+    buf.extend_from_slice(key);
+    buf.extend_from_slice(plaintext);
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+// Miller-Rabin with 40 random bases: a composite slips through with probability below 2^-80
+fn random_prime<R: RngCore>(bits: u64, rng: &mut R) -> BigUint {
+    let mut bytes = vec![0u8; (bits / 8) as usize];
+    loop {
+        rng.fill_bytes(&mut bytes);
+        // Top two bits set so the product has exactly twice the bits; odd
+        bytes[0] |= 0xc0;
+        let last = bytes.len() - 1;
+        bytes[last] |= 1;
+        let candidate = BigUint::from_bytes_be(&bytes);
+        if is_probable_prime(&candidate, rng) {
+            return candidate;
+        }
+    }
+}
+
+fn is_probable_prime<R: RngCore>(n: &BigUint, rng: &mut R) -> bool {
+    let one = BigUint::from(1u32);
+    for small in [3u32, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47] {
+        if (n % small) == BigUint::from(0u32) {
+            return *n == BigUint::from(small);
+        }
+    }
+    let n_minus_one = n - &one;
+    let shift = n_minus_one.trailing_zeros().unwrap_or(0);
+    let odd = &n_minus_one >> shift;
+    let mut base_bytes = vec![0u8; n.to_bytes_be().len()];
+    'bases: for _ in 0..40 {
+        rng.fill_bytes(&mut base_bytes);
+        let base = BigUint::from_bytes_be(&base_bytes) % (n - 3u32) + 2u32;
+        let mut x = base.modpow(&odd, n);
+        if x == one || x == n_minus_one {
+            continue;
+        }
+        for _ in 1..shift {
+            x = &x * &x % n;
+            if x == n_minus_one {
+                continue 'bases;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_sealed_result_opens_only_after_the_squarings() -> Result<(), TimeLockError> {
+        let mut rng = StdRng::seed_from_u64(7);
+        let plaintext = b"option counts 3, 3, 1".to_vec();
+        let lock = TimeLock::seal(&plaintext, 5_000, 1_800_000_000, &mut rng);
+        assert_eq!(lock.modulus.len() as u64, 2 * PRIME_BITS / 8);
+        assert_ne!(lock.ciphertext, plaintext);
+        assert_eq!(TimeLock::from_json(&lock.to_json()?)?.open()?, plaintext);
+
+        // Stopping short of the squarings gives the wrong key
+        let short = TimeLock { squarings: lock.squarings - 1, ..lock.clone() };
+        assert!(matches!(short.open(), Err(TimeLockError::CheckFailed)));
+        let mut tampered = lock.clone();
+        tampered.ciphertext[0] ^= 1;
+        assert!(matches!(tampered.open(), Err(TimeLockError::CheckFailed)));
+
+        assert_eq!(squarings_until(1_000, 400, 50), 30_000);
+        assert_eq!(squarings_until(400, 1_000, 50), 0);
+        assert!(!is_probable_prime(&(BigUint::from(1_000_003u32) * BigUint::from(1_000_033u32)), &mut rng));
+        assert!(is_probable_prime(&BigUint::from(1_000_003u32), &mut rng));
+        Ok(())
+    }
+}
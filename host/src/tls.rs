@@ -0,0 +1,87 @@
+// TLS termination for the ballot submission surface.
+// Builds a rustls server configuration (ring provider) from a PEM certificate
+// chain and private key, so encrypted ballots, signatures and API keys are not
+// sent in the clear. TLS 1.3 early data stays disabled: 0-RTT data can be
+// replayed by the network, and a replayed submission must go through the
+// normal intake path like any other.
+//
+// There is no network server in this tree yet; a front-end wraps its listener
+// with `server_config()`. Certificates come from `FHE_VOTING_TLS_CERT` and
+// `FHE_VOTING_TLS_KEY` (or `TlsConfig` directly). ACME issuance is not
+// built in: point these at certificates an ACME client keeps renewed.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::ServerConfig;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TlsError {
+    #[error("Failed to read PEM from {path}: {reason}")]
+    Pem { path: PathBuf, reason: String },
+    #[error("No certificates found in {path}")]
+    NoCertificates { path: PathBuf },
+    #[error("Invalid TLS configuration: {0}")]
+    Config(#[from] rustls::Error),
+}
+
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+impl TlsConfig {
+    /// Paths from `FHE_VOTING_TLS_CERT` and `FHE_VOTING_TLS_KEY`, if both are set.
+    pub fn from_env() -> Option<Self> {
+        Some(TlsConfig {
+            cert_path: std::env::var_os("FHE_VOTING_TLS_CERT")?.into(),
+            key_path: std::env::var_os("FHE_VOTING_TLS_KEY")?.into(),
+        })
+    }
+
+    pub fn server_config(&self) -> Result<Arc<ServerConfig>, TlsError> {
+        let pem_error = |path: &PathBuf| {
+            let path = path.clone();
+            move |e: rustls::pki_types::pem::Error| TlsError::Pem { path, reason: e.to_string() }
+        };
+        let certs = CertificateDer::pem_file_iter(&self.cert_path)
+            .map_err(pem_error(&self.cert_path))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(pem_error(&self.cert_path))?;
+        if certs.is_empty() {
+            return Err(TlsError::NoCertificates { path: self.cert_path.clone() });
+        }
+        let key = PrivateKeyDer::from_pem_file(&self.key_path).map_err(pem_error(&self.key_path))?;
+
+        let mut config = ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)?;
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Ok(Arc::new(config))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+    }
+
+    #[test]
+    fn test_server_config_from_pem_files() -> Result<(), TlsError> {
+        let config = TlsConfig { cert_path: fixture("tls_cert.pem"), key_path: fixture("tls_key.pem") }.server_config()?;
+        assert_eq!(config.max_early_data_size, 0);
+
+        // Swapped files: the key file holds no certificate
+        let swapped = TlsConfig { cert_path: fixture("tls_key.pem"), key_path: fixture("tls_cert.pem") };
+        assert!(matches!(swapped.server_config(), Err(TlsError::NoCertificates { .. })));
+        Ok(())
+    }
+}
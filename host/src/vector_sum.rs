@@ -0,0 +1,124 @@
+// Encrypted vector sums.
+// The vector sum guest adds integer vectors slot by slot under encryption, the
+// same FHE-in-zkVM pattern as the tally without the ballot rules: contributors
+// encrypt vectors of one configured length with `FheClient::encrypt_values`,
+// the guest proves the encrypted sums cover exactly those contributions, and
+// the key holders decrypt the sums.
+//
+// `vector_sum_input` runs the guest's checks on the host so a bad contribution
+// fails before any proving, and `check_vector_sum` checks a journal against
+// the contributions it claims to cover through `contributions_digest`.
+
+use risc0_zkvm::sha::{Impl, Sha256};
+use thiserror::Error;
+
+use crate::fhe_client::{max_sum_contributions, PublicKey};
+use crate::types::{VectorSumInput, VectorSumOutput};
+
+// Domain separation tag (must match guest implementation)
+const CONTRIBUTION_TAG: u8 = 0x0b;
+
+#[derive(Error, Debug)]
+pub enum VectorSumError {
+    #[error("Vector length must be at least one slot")]
+    EmptyVector,
+    #[error("Maximum slot value must be at least one")]
+    ZeroMaxValue,
+    #[error("Summing needs at least one contribution")]
+    NoContributions,
+    #[error("Contribution {contribution} has {slots} slots, expected {expected}")]
+    LengthMismatch { contribution: usize, slots: usize, expected: u32 },
+    #[error("{contributions} contributions of values up to {max_value} could exceed the plaintext modulus (at most {max} can be summed)")]
+    Overflow { contributions: usize, max_value: u64, max: u64 },
+    #[error("Vector sum journal does not cover the given contributions")]
+    ContributionsMismatch,
+}
+
+/// Input for the vector sum guest, after the checks the guest will repeat.
+pub fn vector_sum_input(
+    public_key: PublicKey,
+    vector_len: u32,
+    max_value: u64,
+    contributions: Vec<Vec<Vec<u8>>>,
+) -> Result<VectorSumInput, VectorSumError> {
+    if vector_len == 0 {
+        return Err(VectorSumError::EmptyVector);
+    }
+    if max_value == 0 {
+        return Err(VectorSumError::ZeroMaxValue);
+    }
+    if contributions.is_empty() {
+        return Err(VectorSumError::NoContributions);
+    }
+    if contributions.len() as u64 > max_sum_contributions(max_value) {
+        return Err(VectorSumError::Overflow { contributions: contributions.len(), max_value, max: max_sum_contributions(max_value) });
+    }
+    if let Some((contribution, slots)) = contributions.iter().map(Vec::len).enumerate().find(|&(_, slots)| slots != vector_len as usize) {
+        return Err(VectorSumError::LengthMismatch { contribution, slots, expected: vector_len });
+    }
+    Ok(VectorSumInput { public_key, vector_len, max_value, contributions })
+}
+
+/// Digest: H(tag || (len || ciphertext) for every slot of every contribution, in order).
+pub fn contributions_digest(contributions: &[Vec<Vec<u8>>]) -> [u8; 32] {
+    let mut buf = vec![CONTRIBUTION_TAG];
+    for ciphertext in contributions.iter().flatten() {
+        buf.extend_from_slice(&(ciphertext.len() as u32).to_le_bytes());
+        buf.extend_from_slice(ciphertext);
+    }
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// Check that a vector sum journal covers exactly the contributions of `input`.
+pub fn check_vector_sum(output: &VectorSumOutput, input: &VectorSumInput) -> Result<(), VectorSumError> {
+    if output.vector_len != input.vector_len
+        || output.contributions as usize != input.contributions.len()
+        || output.max_value != input.max_value
+        || output.key_fingerprint != input.public_key.fingerprint()
+        || output.contributions_digest != contributions_digest(&input.contributions)
+        || output.encrypted_sum_frames != input.vector_len
+    {
+        return Err(VectorSumError::ContributionsMismatch);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fhe_client::FheClient;
+
+    #[test]
+    fn test_vector_sum_input_checks_and_journal_binding() -> Result<(), Box<dyn std::error::Error>> {
+        let client = FheClient::new();
+        let public_key = client.get_public_key().clone();
+        let contributions = vec![client.encrypt_values(&[3, 0, 7])?, client.encrypt_values(&[1, 2, 0])?];
+        let input = vector_sum_input(public_key.clone(), 3, 10, contributions.clone())?;
+
+        let mut output = VectorSumOutput {
+            vector_len: 3,
+            contributions: 2,
+            max_value: 10,
+            key_fingerprint: public_key.fingerprint(),
+            contributions_digest: contributions_digest(&contributions),
+            encrypted_sum_frames: 3,
+        };
+        check_vector_sum(&output, &input)?;
+        // Reordering the contributions changes the digest
+        output.contributions_digest = contributions_digest(&[contributions[1].clone(), contributions[0].clone()]);
+        assert!(matches!(check_vector_sum(&output, &input), Err(VectorSumError::ContributionsMismatch)));
+
+        // Every contribution has the configured length, and the sums cannot wrap
+        assert!(matches!(
+            vector_sum_input(public_key.clone(), 2, 10, contributions.clone()),
+            Err(VectorSumError::LengthMismatch { contribution: 0, slots: 3, expected: 2 })
+        ));
+        assert!(matches!(
+            vector_sum_input(public_key.clone(), 3, max_sum_contributions(1), contributions.clone()),
+            Err(VectorSumError::Overflow { contributions: 2, .. })
+        ));
+        assert!(matches!(vector_sum_input(public_key.clone(), 0, 10, contributions), Err(VectorSumError::EmptyVector)));
+        assert!(matches!(vector_sum_input(public_key, 3, 10, Vec::new()), Err(VectorSumError::NoContributions)));
+        Ok(())
+    }
+}
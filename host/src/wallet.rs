@@ -0,0 +1,190 @@
+// Hardware wallet signing for ballot submissions.
+// The machine that encrypts a ballot never needs the voter's account key: the
+// ballot's submission signature is made by an external signer, a Ledger or
+// Trezor behind `cast wallet sign --ledger` / `--trezor`, or a WalletConnect
+// bridge, given only the 32-byte ballot message. Any command that takes the
+// message as its last argument (0x-prefixed hex) and prints a 65-byte
+// signature works:
+//
+//   FHE_VOTING_SIGNER="cast wallet sign --ledger"
+//
+// The signature is EIP-191 (`personal_sign`) over `ballot_message`, so it
+// commits to the same election, nonce, ciphertexts and sequence as the ballot's
+// authorization, and is stored in the ballot's `signature` field as 0x-hex.
+// Collection points recover the signing account with `recover_signer`. Voters
+// without a hardware wallet sign with the account key their mnemonic derives
+// (`hd_keys`), which implements the same `SubmissionSigner`.
+//
+// A wallet signature names the account that submitted the ballot, though not
+// its choice; elections that keep submitters unlinkable leave `signature`
+// empty and rely on the anonymous authorization alone.
+
+use std::process::Command;
+
+use k256::ecdsa::{RecoveryId, Signature, SigningKey, VerifyingKey};
+use sha3::{Digest, Keccak256};
+use thiserror::Error;
+
+use crate::ballot_set::ballot_message;
+use crate::onchain::address_from_public_key;
+use crate::types::EncryptedVote;
+
+#[derive(Error, Debug)]
+pub enum WalletError {
+    #[error("Signer command is empty")]
+    NoCommand,
+    #[error("Signer command failed: {reason}")]
+    Signer { reason: String },
+    #[error("Malformed signature: {reason}")]
+    MalformedSignature { reason: String },
+    #[error("Ballot carries no wallet signature")]
+    Unsigned,
+}
+
+/// Makes EIP-191 signatures over ballot messages.
+pub trait SubmissionSigner {
+    fn sign(&self, message: &[u8; 32]) -> Result<[u8; 65], WalletError>;
+}
+
+/// Signs ballot messages by running an external wallet command.
+#[derive(Debug, Clone)]
+pub struct ExternalSigner {
+    program: String,
+    args: Vec<String>,
+}
+
+impl ExternalSigner {
+    /// Split a command line such as `cast wallet sign --ledger` on whitespace.
+    pub fn from_command(command: &str) -> Result<Self, WalletError> {
+        let mut words = command.split_whitespace().map(str::to_string);
+        let program = words.next().ok_or(WalletError::NoCommand)?;
+        Ok(ExternalSigner { program, args: words.collect() })
+    }
+
+    /// The `FHE_VOTING_SIGNER` command, if one is set.
+    pub fn from_env() -> Result<Option<Self>, WalletError> {
+        std::env::var("FHE_VOTING_SIGNER").ok().map(|command| Self::from_command(&command)).transpose()
+    }
+}
+
+impl SubmissionSigner for ExternalSigner {
+    /// Have the wallet sign `message`; the user confirms on the device.
+    fn sign(&self, message: &[u8; 32]) -> Result<[u8; 65], WalletError> {
+        let output = Command::new(&self.program)
+            .args(&self.args)
+            .arg(format!("0x{}", hex::encode(message)))
+            .output()
+            .map_err(|e| WalletError::Signer { reason: format!("{}: {}", self.program, e) })?;
+        if !output.status.success() {
+            return Err(WalletError::Signer { reason: String::from_utf8_lossy(&output.stderr).trim().to_string() });
+        }
+        parse_signature(String::from_utf8_lossy(&output.stdout).trim())
+    }
+}
+
+impl SubmissionSigner for SigningKey {
+    fn sign(&self, message: &[u8; 32]) -> Result<[u8; 65], WalletError> {
+        let (rs, recovery_id) = self
+            .sign_prehash_recoverable(&personal_message_hash(message))
+            .map_err(|e| WalletError::Signer { reason: e.to_string() })?;
+        let mut signature = [0u8; 65];
+        signature[..64].copy_from_slice(&rs.to_bytes());
+        signature[64] = 27 + recovery_id.to_byte();
+        Ok(signature)
+    }
+}
+
+/// Sign a ballot's message and store the signature in its `signature` field.
+pub fn sign_ballot(signer: &impl SubmissionSigner, ballot: &mut EncryptedVote) -> Result<[u8; 20], WalletError> {
+    let message = ballot_message(&ballot.election_id, &ballot.voter_address, &ballot.nonce, &ballot.encrypted_vote_vector, ballot.sequence);
+    let signature = signer.sign(&message)?;
+    ballot.signature = format!("0x{}", hex::encode(signature));
+    recover_signer(&message, &signature)
+}
+
+/// The account that signed a ballot's submission.
+pub fn ballot_signer(ballot: &EncryptedVote) -> Result<[u8; 20], WalletError> {
+    if ballot.signature.is_empty() {
+        return Err(WalletError::Unsigned);
+    }
+    let message = ballot_message(&ballot.election_id, &ballot.voter_address, &ballot.nonce, &ballot.encrypted_vote_vector, ballot.sequence);
+    recover_signer(&message, &parse_signature(&ballot.signature)?)
+}
+
+/// keccak256("\x19Ethereum Signed Message:\n32" || message)
+pub fn personal_message_hash(message: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(b"\x19Ethereum Signed Message:\n32");
+    hasher.update(message);
+    hasher.finalize().into()
+}
+
+/// Recover the address behind an EIP-191 signature (r || s || v) over `message`.
+pub fn recover_signer(message: &[u8; 32], signature: &[u8; 65]) -> Result<[u8; 20], WalletError> {
+    let malformed = |reason: &str| WalletError::MalformedSignature { reason: reason.to_string() };
+    let recovery_id = match signature[64] {
+        v @ (0 | 1) => v,
+        v @ (27 | 28) => v - 27,
+        _ => return Err(malformed("recovery id must be 0, 1, 27 or 28")),
+    };
+    let rs = Signature::from_slice(&signature[..64]).map_err(|e| malformed(&e.to_string()))?;
+    let recovery_id = RecoveryId::from_byte(recovery_id).ok_or_else(|| malformed("bad recovery id"))?;
+    let key = VerifyingKey::recover_from_prehash(&personal_message_hash(message), &rs, recovery_id)
+        .map_err(|e| malformed(&e.to_string()))?;
+    let point = key.to_encoded_point(false);
+    let public_key: [u8; 64] = point.as_bytes()[1..].try_into().map_err(|_| malformed("bad public key"))?;
+    Ok(address_from_public_key(&public_key))
+}
+
+fn parse_signature(text: &str) -> Result<[u8; 65], WalletError> {
+    let bytes = hex::decode(text.strip_prefix("0x").unwrap_or(text))
+        .map_err(|e| WalletError::MalformedSignature { reason: e.to_string() })?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| WalletError::MalformedSignature { reason: format!("{} bytes, expected 65", bytes.len()) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wallet_signature_recovers_the_account() -> Result<(), WalletError> {
+        let key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let point = key.verifying_key().to_encoded_point(false);
+        let address = address_from_public_key(point.as_bytes()[1..].try_into().unwrap());
+        let mut ballot = EncryptedVote {
+            voter_address: "voter-0a0b".to_string(),
+            election_id: crate::types::Digest32([1u8; 32]),
+            nonce: [2u8; 32],
+            encrypted_vote_vector: vec![vec![3u8; 16]; 3],
+            signature: String::new(),
+            authorization: crate::types::BallotAuthorization::Membership(crate::types::MembershipProof {
+                nullifier: [4u8; 32],
+                challenges: Vec::new(),
+                responses: Vec::new(),
+            }),
+            sequence: 0,
+            actual_choice: crate::types::VoteOption::Option1,
+        };
+        assert!(matches!(ballot_signer(&ballot), Err(WalletError::Unsigned)));
+
+        // A script standing in for the device prints the signature it would return
+        let message = ballot_message(&ballot.election_id, &ballot.voter_address, &ballot.nonce, &ballot.encrypted_vote_vector, ballot.sequence);
+        let script = std::env::temp_dir().join(format!("fhe-voting-wallet-{}.sh", std::process::id()));
+        std::fs::write(&script, format!("#!/bin/sh\necho 0x{}\n", hex::encode(key.sign(&message)?))).unwrap();
+        std::fs::set_permissions(&script, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+        let signer = ExternalSigner::from_command(&script.to_string_lossy())?;
+        assert_eq!(sign_ballot(&signer, &mut ballot)?, address);
+        assert_eq!(ballot_signer(&ballot)?, address);
+        std::fs::remove_file(&script).unwrap();
+        // A key held locally signs as the same account
+        assert_eq!(sign_ballot(&key, &mut ballot.clone())?, address);
+
+        // The signature covers the ballot message: a changed sequence recovers another account
+        ballot.sequence = 1;
+        assert_ne!(ballot_signer(&ballot).ok(), Some(address));
+        assert!(matches!(ExternalSigner::from_command("  "), Err(WalletError::NoCommand)));
+        assert!(matches!(ExternalSigner::from_command("false")?.sign(&message), Err(WalletError::Signer { .. })));
+        assert!(matches!(parse_signature("0x1234"), Err(WalletError::MalformedSignature { .. })));
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+// u128 coefficient arithmetic for ciphertext moduli beyond 64 bits.
+// The client and guest keep coefficients in u64 under q = 2^58. Until the
+// scheme moves to an RNS prime chain, this module lets a client-side
+// experiment pick a power-of-two q of up to 2^126 and run the same encryption,
+// additions, scalar multiplications and rotations on u128 coefficients, under
+// the same keys (b = a*s mod t, so c1 = a*u stays below t and only c0 grows), e.g.
+// to see how much more noise headroom a larger q buys. It is behind the
+// `wide-modulus` feature and never compiled into the guest.
+//
+// A power-of-two q keeps reduction a mask, as in the u64 path, and makes
+// wrapping u128 arithmetic exact mod q; q <= 2^126 keeps c[0] + Δ / 2 below
+// 2^128 when decoding. Wide ciphertexts serialize as
+// 16-byte little-endian coefficients and are not accepted by the guest.
+
+use thiserror::Error;
+
+use rand::Rng;
+
+use crate::fhe_client::{
+    multiply_polynomials, public_polynomial, residue_mod_t, FheClientError, FheParameters, PrivateKey, PublicKey, PLAINTEXT_MODULUS, POLYNOMIAL_DEGREE,
+};
+
+/// Smallest and largest ciphertext modulus sizes, in bits.
+pub const MIN_WIDE_MODULUS_BITS: u32 = 32;
+pub const MAX_WIDE_MODULUS_BITS: u32 = 126;
+
+#[derive(Error, Debug)]
+pub enum WideError {
+    #[error("Ciphertext modulus 2^{bits} is outside 2^{MIN_WIDE_MODULUS_BITS}..=2^{MAX_WIDE_MODULUS_BITS}")]
+    UnsupportedModulus { bits: u32 },
+    #[error("Wide ciphertext parameters failed: {0}")]
+    Parameters(#[from] FheClientError),
+    #[error("Invalid wide ciphertext length: expected {expected}, got {actual}")]
+    InvalidLength { expected: usize, actual: usize },
+    #[error("Ciphertext coefficient {index} is not below the ciphertext modulus")]
+    NonCanonicalCoefficient { index: usize },
+    #[error("Ciphertexts under moduli 2^{lhs} and 2^{rhs} cannot be combined")]
+    ModulusMismatch { lhs: u32, rhs: u32 },
+}
+
+/// A power-of-two ciphertext modulus q = 2^bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WideModulus {
+    bits: u32,
+}
+
+impl WideModulus {
+    pub fn new(bits: u32) -> Result<Self, WideError> {
+        if !(MIN_WIDE_MODULUS_BITS..=MAX_WIDE_MODULUS_BITS).contains(&bits) {
+            return Err(WideError::UnsupportedModulus { bits });
+        }
+        Ok(WideModulus { bits })
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.bits
+    }
+
+    fn mask(&self) -> u128 {
+        (1 << self.bits) - 1
+    }
+
+    /// Δ = floor(q / t), the scale of the plaintext in c[0].
+    pub fn scaling_factor(&self) -> u128 {
+        (1u128 << self.bits) / u128::from(PLAINTEXT_MODULUS)
+    }
+
+    /// Encrypt `value` mod t under `public_key` with noise from `parameters`
+    /// (as the client does under q = 2^58).
+    pub fn encrypt(&self, value: u64, public_key: &PublicKey, parameters: &FheParameters) -> Result<WideCipher, WideError> {
+        parameters.validate()?;
+        if public_key.key_data.len() != POLYNOMIAL_DEGREE || public_key.key_data.iter().any(|&b| b >= PLAINTEXT_MODULUS) {
+            return Err(FheClientError::EncryptionFailed {
+                reason: format!("public key must be {} coefficients mod {}", POLYNOMIAL_DEGREE, PLAINTEXT_MODULUS),
+            }
+            .into());
+        }
+        let mut rng = rand::thread_rng();
+        // c = (Δ(m + b*u) + e, a*u) with u uniform mod t and signed noise, so
+        // negative samples wrap to just below q
+        let u: Vec<u64> = (0..POLYNOMIAL_DEGREE).map(|_| rng.gen_range(0..PLAINTEXT_MODULUS)).collect();
+        let mask = multiply_polynomials(&public_key.key_data, &u);
+        let mut ciphertext_data: Vec<u128> = mask
+            .iter()
+            .enumerate()
+            .map(|(i, &mask)| {
+                let value = if i == 0 { value % PLAINTEXT_MODULUS } else { 0 };
+                let scaled = u128::from((value + mask) % PLAINTEXT_MODULUS) * self.scaling_factor();
+                scaled.wrapping_add(parameters.noise.sample(&mut rng) as u128) & self.mask()
+            })
+            .collect();
+        ciphertext_data.extend(multiply_polynomials(&public_polynomial(), &u).into_iter().map(u128::from));
+        Ok(WideCipher { modulus: *self, ciphertext_data })
+    }
+
+    // c mod q read as the integer in -q/2..q/2 it stands for, reduced mod t
+    fn residue_mod_t(&self, c: u128) -> u64 {
+        let signed = if c >> (self.bits - 1) == 1 { c as i128 - (1i128 << self.bits) } else { c as i128 };
+        signed.rem_euclid(i128::from(PLAINTEXT_MODULUS)) as u64
+    }
+
+    /// Deserialize 16-byte little-endian coefficients, refusing any not below q.
+    pub fn deserialize(&self, data: &[u8]) -> Result<WideCipher, WideError> {
+        let expected = POLYNOMIAL_DEGREE * 2 * 16;
+        if data.len() != expected {
+            return Err(WideError::InvalidLength { expected, actual: data.len() });
+        }
+        let (words, _) = data.as_chunks::<16>();
+        let ciphertext_data: Vec<u128> = words.iter().map(|&word| u128::from_le_bytes(word)).collect();
+        if let Some(index) = ciphertext_data.iter().position(|&c| c > self.mask()) {
+            return Err(WideError::NonCanonicalCoefficient { index });
+        }
+        Ok(WideCipher { modulus: *self, ciphertext_data })
+    }
+}
+
+/// A ciphertext with u128 coefficients mod a `WideModulus`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WideCipher {
+    modulus: WideModulus,
+    pub ciphertext_data: Vec<u128>,
+}
+
+impl WideCipher {
+    pub fn modulus(&self) -> WideModulus {
+        self.modulus
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        self.ciphertext_data.iter().flat_map(|c| c.to_le_bytes()).collect()
+    }
+
+    /// Coefficient-wise sum mod q.
+    pub fn add(&self, other: &Self) -> Result<Self, WideError> {
+        self.zip_with(other, u128::wrapping_add)
+    }
+
+    /// Coefficient-wise difference mod q.
+    pub fn sub(&self, other: &Self) -> Result<Self, WideError> {
+        self.zip_with(other, u128::wrapping_sub)
+    }
+
+    /// Multiply by a public scalar mod q; wrapping mod 2^128 is exact mod q.
+    pub fn mul_plain(&self, scalar: u64) -> Self {
+        self.map(|c| c.wrapping_mul(u128::from(scalar)))
+    }
+
+    /// Multiply both polynomials by X^steps mod X^N + 1.
+    pub fn rotate(&self, steps: u32) -> Self {
+        let steps = steps as usize % (2 * POLYNOMIAL_DEGREE);
+        let mask = self.modulus.mask();
+        let mut ciphertext_data = vec![0u128; POLYNOMIAL_DEGREE * 2];
+        for (input, output) in self.ciphertext_data.chunks(POLYNOMIAL_DEGREE).zip(ciphertext_data.chunks_mut(POLYNOMIAL_DEGREE)) {
+            for (i, &c) in input.iter().enumerate() {
+                let shifted = i + steps;
+                let negate = (shifted / POLYNOMIAL_DEGREE) % 2 == 1;
+                output[shifted % POLYNOMIAL_DEGREE] = if negate { c.wrapping_neg() & mask } else { c };
+            }
+        }
+        WideCipher { modulus: self.modulus, ciphertext_data }
+    }
+
+    /// Round c[0] / Δ and take off (c1*s)[0], as `decrypt_plaintext` does under q = 2^58.
+    pub fn decrypt(&self, private_key: &PrivateKey) -> u64 {
+        let scaling_factor = self.modulus.scaling_factor();
+        let descaled = (((self.ciphertext_data[0] + scaling_factor / 2) / scaling_factor) % u128::from(PLAINTEXT_MODULUS)) as u64;
+        // Secret coefficients are stored mod 2^58, c1 mod this q
+        let secret: Vec<u64> = private_key.secret_data.iter().map(|&s| residue_mod_t(s)).collect();
+        let c1: Vec<u64> = self.ciphertext_data[POLYNOMIAL_DEGREE..].iter().map(|&c| self.modulus.residue_mod_t(c)).collect();
+        let key_term = multiply_polynomials(&c1, &secret)[0];
+        (descaled + PLAINTEXT_MODULUS - key_term) % PLAINTEXT_MODULUS
+    }
+
+    fn map(&self, op: impl Fn(u128) -> u128) -> Self {
+        let mask = self.modulus.mask();
+        WideCipher { modulus: self.modulus, ciphertext_data: self.ciphertext_data.iter().map(|&c| op(c) & mask).collect() }
+    }
+
+    fn zip_with(&self, other: &Self, op: impl Fn(u128, u128) -> u128) -> Result<Self, WideError> {
+        if self.modulus != other.modulus {
+            return Err(WideError::ModulusMismatch { lhs: self.modulus.bits, rhs: other.modulus.bits });
+        }
+        let mask = self.modulus.mask();
+        Ok(WideCipher {
+            modulus: self.modulus,
+            ciphertext_data: self.ciphertext_data.iter().zip(&other.ciphertext_data).map(|(&a, &b)| op(a, b) & mask).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fhe_client::PureRustFheRuntime;
+
+    #[test]
+    fn test_wide_modulus_tallies_and_round_trips() -> Result<(), Box<dyn std::error::Error>> {
+        let modulus = WideModulus::new(100)?;
+        let parameters = FheParameters::default();
+        let (public_key, private_key) = PureRustFheRuntime::new().generate_keys();
+        let mut tally = modulus.encrypt(0, &public_key, &parameters)?;
+        for value in [1, 1, 0, 1] {
+            tally = tally.add(&modulus.encrypt(value, &public_key, &parameters)?)?;
+        }
+        assert_eq!(tally.decrypt(&private_key), 3);
+        // A scalar far beyond what the noise budget under 2^58 allows
+        assert_eq!(tally.mul_plain(1 << 20).decrypt(&private_key), (3 << 20) % PLAINTEXT_MODULUS);
+        assert_eq!(tally.rotate(64), tally);
+        assert_eq!(modulus.deserialize(&tally.serialize())?, tally);
+
+        assert!(matches!(WideModulus::new(127), Err(WideError::UnsupportedModulus { bits: 127 })));
+        assert!(matches!(tally.add(&WideModulus::new(80)?.encrypt(1, &public_key, &parameters)?), Err(WideError::ModulusMismatch { lhs: 100, rhs: 80 })));
+        let mut oversized = tally.serialize();
+        oversized[16 * 5 + 15] = 0xff;
+        assert!(matches!(modulus.deserialize(&oversized), Err(WideError::NonCanonicalCoefficient { index: 5 })));
+        Ok(())
+    }
+}
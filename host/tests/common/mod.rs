@@ -0,0 +1,150 @@
+// Election fixtures shared by the integration tests.
+
+use host::address::to_checksum_address;
+use host::audit_log::AuditLog;
+use host::ballot_set::{ballot_message, BallotSetCommitment};
+use host::candidates::{candidates_digest, default_candidates};
+use host::decryption_shares::{trustee_decryption, ProvenInterimTallies, TrusteeDecryptor};
+use host::dkg::{self, DkgConfig, DkgOutput};
+use host::fhe_client::FheClient;
+use host::registrar::{CredentialRequest, Registrar};
+use host::scheme::{TallyScheme, TieBreak};
+use host::types::{BallotAuthorization, Digest32, ElectionContext, Electorate, EncryptedVote, JournalProfile, ShareVerificationKey, VoteOption, VoteTallyInput};
+use methods::{FHE_VOTING_ELF, FHE_VOTING_ID};
+use risc0_zkvm::{default_prover, ExecutorEnv, VerifierContext};
+
+/// A valid election with one credential-authorized ballot per entry of
+/// `choices`, encrypted under a 2-of-3 trustee key. Returns the guest input and
+/// the trustees' DKG outputs.
+pub fn election(choices: &[VoteOption]) -> Result<(VoteTallyInput, Vec<DkgOutput>), Box<dyn std::error::Error>> {
+    election_with_revotes(choices, &[])
+}
+
+/// A second ballot: the voter, their new choice, and the ciphertexts to cast in
+/// place of its encryption, if any
+pub type Revote = (usize, VoteOption, Option<Vec<Vec<u8>>>);
+
+/// Like [`election`], then each of `revotes` is cast by the voter's credential
+/// with sequence 1.
+pub fn election_with_revotes(
+    choices: &[VoteOption],
+    revotes: &[Revote],
+) -> Result<(VoteTallyInput, Vec<DkgOutput>), Box<dyn std::error::Error>> {
+    let (election_public_key, trustees) = dkg::run_in_process(DkgConfig::threshold(2, 3)?)?;
+    let fhe_client = FheClient::with_public_key(election_public_key.clone());
+
+    let voters: Vec<String> = (0..choices.len()).map(|i| to_checksum_address(&[i as u8; 20])).collect();
+    let mut registrar = Registrar::new(voters.iter().cloned());
+    let registrar_public_key = registrar.public_key();
+    let vectors = fhe_client.encrypt_vote_vectors(choices)?;
+    let candidates = default_candidates();
+    let election = ElectionContext { election_id: Digest32([1u8; 32]), chain_id: 0, closes_at: 0, candidates_digest: candidates_digest(&candidates), tie_break: TieBreak::default(), scheme_digest: Digest32::ZERO };
+
+    let mut audit_log = AuditLog::new();
+    let mut encrypted_votes = Vec::with_capacity(choices.len() + revotes.len());
+    let mut credential_keys = Vec::with_capacity(choices.len());
+    for (i, ((voter, &option), encrypted_vote_vector)) in voters.iter().zip(choices).zip(vectors).enumerate() {
+        let (session, nonce_commitment) = registrar.open_session(voter)?;
+        let (request, blinded_challenge) = CredentialRequest::new(&registrar_public_key, &nonce_commitment)?;
+        let credential_key = request.unblind(registrar.sign_blinded(session, blinded_challenge)?)?;
+
+        let nonce = [i as u8; 32];
+        let message = ballot_message(&election.election_id, voter, &nonce, &encrypted_vote_vector, 0);
+        let ballot = EncryptedVote {
+            voter_address: voter.clone(),
+            election_id: election.election_id,
+            nonce,
+            encrypted_vote_vector,
+            signature: String::new(),
+            authorization: BallotAuthorization::Credential {
+                credential: credential_key.credential().clone(),
+                ballot_signature: credential_key.sign_ballot(&message),
+            },
+            sequence: 0,
+            actual_choice: option,
+        };
+        audit_log.record_accepted(&ballot);
+        encrypted_votes.push(ballot);
+        credential_keys.push(credential_key);
+    }
+    for (r, (voter, actual_choice, ciphertexts)) in revotes.iter().enumerate() {
+        let encrypted_vote_vector = match ciphertexts {
+            Some(ciphertexts) => ciphertexts.clone(),
+            None => fhe_client.encrypt_vote_vector(*actual_choice)?,
+        };
+        let nonce = [(choices.len() + r) as u8; 32];
+        let message = ballot_message(&election.election_id, &voters[*voter], &nonce, &encrypted_vote_vector, 1);
+        let ballot = EncryptedVote {
+            voter_address: voters[*voter].clone(),
+            election_id: election.election_id,
+            nonce,
+            encrypted_vote_vector,
+            signature: String::new(),
+            authorization: BallotAuthorization::Credential {
+                credential: credential_keys[*voter].credential().clone(),
+                ballot_signature: credential_keys[*voter].sign_ballot(&message),
+            },
+            sequence: 1,
+            actual_choice: *actual_choice,
+        };
+        audit_log.record_accepted(&ballot);
+        encrypted_votes.push(ballot);
+    }
+
+    let input = VoteTallyInput {
+        ballot_set_root: BallotSetCommitment::from_ballots(&encrypted_votes).root(),
+        encrypted_votes,
+        election_public_key,
+        registrar_public_key,
+        identity_roster: Vec::new(),
+        audit_log_head: audit_log.head(),
+        audit_log: audit_log.records().to_vec(),
+        chain_anchor: None,
+        journal_profile: JournalProfile::Full,
+        interim: false,
+        prior_tally: None,
+        eip712_domain: None,
+        election,
+        candidates,
+        scheme: TallyScheme::default(),
+        min_anonymity_set: 0,
+        dp_noise: None,
+        revocations: Vec::new(),
+        electorate: Some(Electorate { registered_voters: voters.len() as u32, registry_root: [0u8; 32] }),
+        decryption: None,
+        mix: None,
+    };
+    Ok((input, trustees))
+}
+
+/// Attach the decryption shares of the first `threshold` of `trustees` to
+/// `input`, for the encrypted tallies an interim proof of the guest proves.
+pub fn share_final_tallies(
+    input: &mut VoteTallyInput,
+    trustees: &[TrusteeDecryptor],
+    verification_keys: &[ShareVerificationKey],
+    threshold: u32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    input.interim = true;
+    let env = ExecutorEnv::builder().write(&*input)?.build();
+    input.interim = false;
+    let receipt = default_prover().prove(env?, FHE_VOTING_ELF)?.receipt;
+    let tallies = ProvenInterimTallies::verify(&VerifierContext::default(), &receipt, FHE_VOTING_ID)?;
+    input.decryption = Some(trustee_decryption(&trustees[..threshold as usize], verification_keys, threshold, &tallies)?);
+    Ok(())
+}
+
+/// The trustees' decryptors and their published verification keys
+pub type Trustees = (Vec<TrusteeDecryptor>, Vec<ShareVerificationKey>);
+
+/// Like [`election`], with the 2-of-3 trustees' decryption shares attached so
+/// the guest can publish final counts.
+pub fn shared_election(choices: &[VoteOption]) -> Result<(VoteTallyInput, Trustees), Box<dyn std::error::Error>> {
+    let (mut input, trustee_outputs) = election(choices)?;
+    let (trustees, verification_keys): (Vec<_>, Vec<_>) = trustee_outputs
+        .into_iter()
+        .map(|output| TrusteeDecryptor::new(output.key_share))
+        .unzip();
+    share_final_tallies(&mut input, &trustees, &verification_keys, 2)?;
+    Ok((input, (trustees, verification_keys)))
+}
@@ -0,0 +1,124 @@
+// End-to-end pipeline test.
+// Client encryption under a trustee key -> host input assembly -> guest proving
+// -> trustee decryption shares for the guest -> guest proving -> receipt
+// verification -> journal decoding -> threshold decryption of the proven
+// encrypted tallies with decryption proofs, asserting the final counts at each
+// stage that reports them. The journal must be rebuilt byte for byte from the
+// decoded result and encrypted tallies alone, so nothing from the trustees'
+// shares or plaintexts reaches it beyond the counts and the trustees digest.
+//
+// Proving is expensive, so the test only exists with the `e2e` feature. Run it
+// with dev-mode receipts (fake proofs, real guest execution):
+//   RISC0_DEV_MODE=1 cargo test --release -p host --features e2e --test end_to_end
+#![cfg(feature = "e2e")]
+
+mod common;
+
+use methods::{FHE_VOTING_ELF, FHE_VOTING_ID};
+use risc0_zkvm::{default_prover, ExecutorEnv};
+
+use host::decryption_shares::{prove_decryption, trustees_digest, verify_decryption_proof, TrusteeDecryptor};
+use host::journal::{encode_journal, read_journal, TallyJournal};
+use host::mixnet::{counted_vote_vectors, shuffle, SHUFFLE_PROOF_ROUNDS};
+use host::types::{BallotMix, RejectionReason, ShadowOpening, VoteOption};
+
+#[test]
+fn election_pipeline_end_to_end() -> Result<(), Box<dyn std::error::Error>> {
+    let choices = [
+        VoteOption::Option1,
+        VoteOption::Option2,
+        VoteOption::Option1,
+        VoteOption::Option3,
+        VoteOption::Option2,
+        VoteOption::Option1,
+        VoteOption::Option2,
+    ];
+    let expected = [3u32, 3, 1];
+    let threshold = 2;
+    let (input, (trustees, verification_keys)) = common::shared_election(&choices)?;
+
+    let env = ExecutorEnv::builder().write(&input)?.build()?;
+    let receipt = default_prover().prove(env, FHE_VOTING_ELF)?.receipt;
+    receipt.verify(FHE_VOTING_ID)?;
+
+    let TallyJournal { output, encrypted_tallies } = read_journal(&receipt.journal.bytes)?;
+    assert_eq!([output.option1_count, output.option2_count, output.option3_count], expected);
+    assert_eq!(output.total_votes as usize, choices.len());
+    // Every registered voter cast a ballot
+    assert_eq!((output.turnout.ballots_tallied as usize, output.turnout.turnout_basis_points), (choices.len(), 10_000));
+    assert_eq!(output.ballot_set_root, input.ballot_set_root);
+    assert_eq!(output.audit_log_head, input.audit_log_head);
+    assert_eq!(encrypted_tallies.len(), expected.len());
+    assert_eq!(output.trustees_digest, trustees_digest(threshold, &verification_keys));
+
+    // The journal holds the intended outputs and nothing else
+    assert_eq!(encode_journal(&output, &encrypted_tallies), receipt.journal.bytes);
+
+    // Trustees decrypt what the guest proved, not what the host claims
+    for (ciphertext, &count) in encrypted_tallies.iter().zip(&expected) {
+        let partials = trustees[1..=threshold as usize]
+            .iter()
+            .map(|trustee| trustee.partial_decrypt(ciphertext))
+            .collect::<Result<Vec<_>, _>>()?;
+        let proof = prove_decryption(ciphertext, partials, &verification_keys, threshold)?;
+        let combined = verify_decryption_proof(&proof, ciphertext, &verification_keys, threshold)?;
+        assert_eq!(combined.plaintext, count as u64);
+    }
+    Ok(())
+}
+
+#[test]
+fn malformed_revote_keeps_the_earlier_ballot() -> Result<(), Box<dyn std::error::Error>> {
+    let choices = [VoteOption::Option1, VoteOption::Option2, VoteOption::Option3];
+    // Voter 0 re-votes with ciphertexts that pass every check but deserialization
+    let (mut input, trustee_outputs) = common::election_with_revotes(&choices, &[(0, VoteOption::Option1, Some(vec![vec![0xff; 16]; 3]))])?;
+    let (trustees, verification_keys): (Vec<_>, Vec<_>) = trustee_outputs
+        .into_iter()
+        .map(|output| TrusteeDecryptor::new(output.key_share))
+        .unzip();
+    common::share_final_tallies(&mut input, &trustees, &verification_keys, 2)?;
+
+    let env = ExecutorEnv::builder().write(&input)?.build()?;
+    let receipt = default_prover().prove(env, FHE_VOTING_ELF)?.receipt;
+    receipt.verify(FHE_VOTING_ID)?;
+
+    let TallyJournal { output, .. } = read_journal(&receipt.journal.bytes)?;
+    assert_eq!([output.option1_count, output.option2_count, output.option3_count], [1, 1, 1]);
+    // The bad ballot is excluded once, as malformed, and supersedes nothing
+    assert_eq!(output.superseded_ballots, 0);
+    assert_eq!(output.rejected_count, 1);
+    assert_eq!(output.rejections_by_reason[RejectionReason::Malformed as usize], 1);
+    Ok(())
+}
+
+#[test]
+fn mixed_election_tallies_the_latest_ballots() -> Result<(), Box<dyn std::error::Error>> {
+    let choices = [VoteOption::Option1, VoteOption::Option2, VoteOption::Option3];
+    // Voter 0 changes their vote to option 3; only that ballot of theirs is mixed
+    let (mut input, trustee_outputs) = common::election_with_revotes(&choices, &[(0, VoteOption::Option3, None)])?;
+    let (trustees, verification_keys): (Vec<_>, Vec<_>) = trustee_outputs
+        .into_iter()
+        .map(|output| TrusteeDecryptor::new(output.key_share))
+        .unzip();
+    let counted = counted_vote_vectors(&input.encrypted_votes);
+    assert_eq!(counted.len(), choices.len());
+    let mixed = shuffle(&counted, &input.election_public_key, SHUFFLE_PROOF_ROUNDS)?;
+    input.mix = Some(BallotMix { ballots: mixed.ballots, proof: mixed.proof });
+    common::share_final_tallies(&mut input, &trustees, &verification_keys, 2)?;
+
+    let env = ExecutorEnv::builder().write(&input)?.build()?;
+    let receipt = default_prover().prove(env, FHE_VOTING_ELF)?.receipt;
+    receipt.verify(FHE_VOTING_ID)?;
+    let TallyJournal { output, .. } = read_journal(&receipt.journal.bytes)?;
+    assert_eq!([output.option1_count, output.option2_count, output.option3_count], [0, 1, 2]);
+    assert_eq!(output.superseded_ballots, 1);
+
+    // A shuffle proof with one round's opening altered is refused
+    let Some(mix) = input.mix.as_mut() else { unreachable!() };
+    match &mut mix.proof.rounds[0].opening {
+        ShadowOpening::FromInput { permutation, .. } | ShadowOpening::ToOutput { permutation, .. } => permutation.swap(0, 1),
+    }
+    let env = ExecutorEnv::builder().write(&input)?.build()?;
+    assert!(default_prover().prove(env, FHE_VOTING_ELF).is_err());
+    Ok(())
+}
@@ -0,0 +1,95 @@
+// Guest cycle regression harness.
+// Runs the tally guest in the executor (no proving) for two fixed ballot counts
+// and derives cycles per ballot from the difference, so fixed startup cost does
+// not mask per-ballot growth. The fixed cost left over is tracked too: it is
+// mostly encrypting the zero tallies, which measures the guest's noise
+// sampler, and checking the trustees' decryption shares. Both are compared against the baseline committed in
+// guest_cycles_baseline.txt and the test fails if either regresses by more than
+// MAX_REGRESSION_PERCENT, or if the baseline is missing or unrecorded.
+//
+// Needs the guest ELF, so it is ignored by default:
+//   cargo test --release -p host --test guest_cycles -- --ignored --nocapture
+// Set FHE_VOTING_UPDATE_CYCLE_BASELINE=1 to (re)record the baseline after an
+// intended change, and commit the file.
+
+mod common;
+
+use std::path::PathBuf;
+
+use methods::FHE_VOTING_ELF;
+use risc0_zkvm::{default_executor, ExecutorEnv};
+
+use host::types::{VoteOption, VoteTallyInput, VoteTallyOutput};
+
+const BALLOT_COUNTS: (usize, usize) = (8, 32);
+const MAX_REGRESSION_PERCENT: u64 = 10;
+const BASELINE_HEADER: &str = "\
+# Tally guest executor cycles, checked by tests/guest_cycles.rs: `name cycles`
+# lines. Re-record after an intended change with FHE_VOTING_UPDATE_CYCLE_BASELINE=1.
+";
+
+fn baseline_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/guest_cycles_baseline.txt")
+}
+
+// A valid election of `ballots` credential-authorized ballots, one per voter,
+// with the trustees' decryption shares attached
+fn election_input(ballots: usize) -> Result<VoteTallyInput, Box<dyn std::error::Error>> {
+    let options = [VoteOption::Option1, VoteOption::Option2, VoteOption::Option3];
+    let choices: Vec<VoteOption> = (0..ballots).map(|i| options[i % options.len()]).collect();
+    Ok(common::shared_election(&choices)?.0)
+}
+
+fn execute_cycles(ballots: usize) -> Result<u64, Box<dyn std::error::Error>> {
+    let input = election_input(ballots)?;
+    let env = ExecutorEnv::builder().write(&input)?.build()?;
+    let session = default_executor().execute(env, FHE_VOTING_ELF)?;
+
+    let output: VoteTallyOutput = session.journal.decode()?;
+    assert_eq!(output.total_votes as usize, ballots, "guest rejected ballots from a valid election");
+    Ok(session.cycles())
+}
+
+#[test]
+#[ignore = "runs the guest ELF in the executor; build the guest and run with --ignored"]
+fn guest_cycles_do_not_regress() -> Result<(), Box<dyn std::error::Error>> {
+    let (small, large) = BALLOT_COUNTS;
+    let small_cycles = execute_cycles(small)?;
+    let large_cycles = execute_cycles(large)?;
+    let per_ballot = large_cycles.saturating_sub(small_cycles) / (large - small) as u64;
+    let fixed = small_cycles.saturating_sub(per_ballot * small as u64);
+    println!("{} ballots: {} cycles, {} ballots: {} cycles, {} cycles per ballot, {} fixed (noise sampling and share checks)",
+             small, small_cycles, large, large_cycles, per_ballot, fixed);
+
+    let path = baseline_path();
+    if std::env::var("FHE_VOTING_UPDATE_CYCLE_BASELINE").is_ok_and(|v| v == "1") {
+        std::fs::write(&path, format!("{}per_ballot {}\nfixed {}\n", BASELINE_HEADER, per_ballot, fixed))?;
+        println!("Recorded cycle baseline in {}", path.display());
+        return Ok(());
+    }
+
+    let recorded = std::fs::read_to_string(&path)
+        .map_err(|e| format!("no cycle baseline at {} ({}); record one with FHE_VOTING_UPDATE_CYCLE_BASELINE=1", path.display(), e))?;
+    for (name, measured) in [("per_ballot", per_ballot), ("fixed", fixed)] {
+        let baseline = baseline_entry(&recorded, name)?;
+        let limit = baseline + baseline * MAX_REGRESSION_PERCENT / 100;
+        assert!(
+            measured <= limit,
+            "guest {} cycles regressed: {} against a baseline of {} (limit {}, +{}%)",
+            name, measured, baseline, limit, MAX_REGRESSION_PERCENT
+        );
+    }
+    Ok(())
+}
+
+// The cycle count recorded as `name` in the baseline file
+fn baseline_entry(recorded: &str, name: &str) -> Result<u64, String> {
+    let value = recorded
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+        .ok_or_else(|| format!("cycle baseline has no {} entry", name))?;
+    value.trim().parse().map_err(|_| {
+        format!("cycle baseline {} is {:?}, not a cycle count; record it with FHE_VOTING_UPDATE_CYCLE_BASELINE=1", name, value.trim())
+    })
+}
@@ -0,0 +1,54 @@
+// Published guest image ids.
+// verifier/src/published_image_ids.rs pins the image ids the verifier crate
+// checks receipts against when built without its `guest-build` feature. This
+// compares them with the ids of the current guest build, so a guest change
+// cannot ship with stale constants.
+//
+// It runs with the rest of the host tests. A build with RISC0_SKIP_BUILD has
+// no guest ELFs and all-zero ids to compare against, so it is skipped there;
+// wherever the guests are built, stale or placeholder ids fail. Set
+// FHE_VOTING_UPDATE_IMAGE_IDS=1 to rewrite the file after an intended guest
+// change:
+//   FHE_VOTING_UPDATE_IMAGE_IDS=1 cargo test --release -p host --test image_ids
+
+use std::path::PathBuf;
+
+use methods::{AGGREGATE_ID, AUCTION_ID, CHALLENGE_ID, CIRCUIT_ID, FHE_VOTING_ELF, FHE_VOTING_ID, VECTOR_SUM_ID};
+
+fn image_ids_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../verifier/src/published_image_ids.rs")
+}
+
+fn render() -> String {
+    let mut file = String::from(
+        "// Guest image ids, generated from the guest build by host/tests/image_ids.rs.\n\
+         // Do not edit; rerun that test with FHE_VOTING_UPDATE_IMAGE_IDS=1 instead.\n\n",
+    );
+    for (name, id) in [
+        ("FHE_VOTING_ID", FHE_VOTING_ID),
+        ("AGGREGATE_ID", AGGREGATE_ID),
+        ("VECTOR_SUM_ID", VECTOR_SUM_ID),
+        ("AUCTION_ID", AUCTION_ID),
+        ("CIRCUIT_ID", CIRCUIT_ID),
+        ("CHALLENGE_ID", CHALLENGE_ID),
+    ] {
+        let words: Vec<String> = id.iter().map(|word| format!("0x{:08x}", word)).collect();
+        file += &format!("pub const {}: [u32; 8] = [{}];\n", name, words.join(", "));
+    }
+    file
+}
+
+#[test]
+fn test_published_image_ids_match_the_guest_build() -> Result<(), Box<dyn std::error::Error>> {
+    if FHE_VOTING_ELF.is_empty() {
+        eprintln!("Guests not built (RISC0_SKIP_BUILD); published image ids not checked");
+        return Ok(());
+    }
+    let expected = render();
+    if std::env::var_os("FHE_VOTING_UPDATE_IMAGE_IDS").is_some() {
+        std::fs::write(image_ids_path(), &expected)?;
+        return Ok(());
+    }
+    assert_eq!(std::fs::read_to_string(image_ids_path())?, expected, "stale image ids; rerun with FHE_VOTING_UPDATE_IMAGE_IDS=1");
+    Ok(())
+}
@@ -0,0 +1,32 @@
+// Canonical voter addresses (guest side).
+// The host rewrites every voter address in its EIP-55 form at ingestion, so
+// one account has one spelling wherever ballots are compared; the guest counts
+// only ballots whose address is already in that form (must match host
+// implementation).
+
+use crate::keccak::keccak256;
+
+/// The EIP-55 spelling of `address`.
+fn to_checksum_address(address: &[u8]) -> String {
+    let mut checksummed = String::with_capacity(42);
+    checksummed.push_str("0x");
+    let lower: String = address.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let hash = keccak256(lower.as_bytes());
+    for (i, c) in lower.chars().enumerate() {
+        let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+        checksummed.push(if nibble >= 8 { c.to_ascii_uppercase() } else { c });
+    }
+    checksummed
+}
+
+/// True if `value` is a 0x-prefixed 20-byte address spelled with its checksum.
+pub fn is_canonical_address(value: &str) -> bool {
+    let Some(digits) = value.strip_prefix("0x") else {
+        return false;
+    };
+    if digits.len() != 40 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return false;
+    }
+    let address: Vec<u8> = (0..20).filter_map(|i| u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).ok()).collect();
+    address.len() == 20 && to_checksum_address(&address) == value
+}
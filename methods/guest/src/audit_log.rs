@@ -0,0 +1,65 @@
+// Hash-chained submission audit log (guest side).
+// Recomputes the head the host published and checks that the accepted records
+// are exactly the ballots being tallied, in order.
+
+use risc0_zkvm::sha::{Impl, Sha256};
+
+use crate::ballot_set::ballot_leaf_hash;
+use crate::types::{AuditRecord, EncryptedVote};
+
+// Domain separation tag (must match host implementation)
+const AUDIT_TAG: u8 = 0x03;
+
+pub const EMPTY_AUDIT_LOG_HEAD: [u8; 32] = [0u8; 32];
+
+/// Reason of the record a revocation is logged with (must match host `REVOKED_REASON`).
+const REVOKED_REASON: &str = "revoked by the voter";
+
+fn chain_record(head: &[u8; 32], index: u64, record: &AuditRecord) -> [u8; 32] {
+    let mut buf = vec![AUDIT_TAG];
+    buf.extend_from_slice(head);
+    buf.extend_from_slice(&index.to_le_bytes());
+    buf.extend_from_slice(&record.ballot_hash);
+    buf.push(record.accepted as u8);
+    buf.extend_from_slice(&(record.reason.len() as u32).to_le_bytes());
+    buf.extend_from_slice(record.reason.as_bytes());
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+pub fn compute_audit_log_head(records: &[AuditRecord]) -> [u8; 32] {
+    records
+        .iter()
+        .enumerate()
+        .fold(EMPTY_AUDIT_LOG_HEAD, |head, (index, record)| chain_record(&head, index as u64, record))
+}
+
+/// True if `head` is the head of some prefix of `records` (the empty prefix included).
+pub fn has_prefix_head(records: &[AuditRecord], head: &[u8; 32]) -> bool {
+    let mut prefix_head = EMPTY_AUDIT_LOG_HEAD;
+    if prefix_head == *head {
+        return true;
+    }
+    for (index, record) in records.iter().enumerate() {
+        prefix_head = chain_record(&prefix_head, index as u64, record);
+        if prefix_head == *head {
+            return true;
+        }
+    }
+    false
+}
+
+/// True if the accepted records, in order, are exactly `ballots`.
+pub fn accepted_records_match(records: &[AuditRecord], ballots: &[EncryptedVote]) -> bool {
+    let accepted: Vec<&AuditRecord> = records.iter().filter(|record| record.accepted).collect();
+    accepted.len() == ballots.len()
+        && accepted.iter().zip(ballots).all(|(record, ballot)| record.ballot_hash == ballot_leaf_hash(ballot))
+}
+
+/// Tracking codes of the revoked ballots, in log order.
+pub fn revoked_records(records: &[AuditRecord]) -> Vec<[u8; 32]> {
+    records
+        .iter()
+        .filter(|record| !record.accepted && record.reason == REVOKED_REASON)
+        .map(|record| record.ballot_hash)
+        .collect()
+}
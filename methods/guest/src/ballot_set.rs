@@ -0,0 +1,146 @@
+// Merkle commitment over the submitted encrypted ballots (guest side).
+// Recomputes the root the host built so the journal commits to exactly the set
+// of ballots that was tallied.
+
+use risc0_zkvm::sha::{Impl, Sha256};
+
+use crate::types::{BallotAuthorization, Digest32, EncryptedVote};
+
+// Domain separation tags (must match host implementation)
+const LEAF_TAG: u8 = 0x00;
+const NODE_TAG: u8 = 0x01;
+const ELECTION_DOMAIN_TAG: u8 = 0x18;
+const REVOCATION_TAG: u8 = 0x19;
+const VOTER_KEYS_TAG: u8 = 0x1b;
+
+pub const EMPTY_BALLOT_SET_ROOT: [u8; 32] = [0u8; 32];
+
+pub fn ballot_leaf_hash(ballot: &EncryptedVote) -> [u8; 32] {
+    let mut buf = vec![LEAF_TAG];
+    push_field(&mut buf, ballot.voter_address.as_bytes());
+    buf.extend_from_slice(&election_domain(&ballot.election_id));
+    buf.extend_from_slice(&ballot.nonce);
+    buf.extend_from_slice(&(ballot.encrypted_vote_vector.len() as u32).to_le_bytes());
+    for ciphertext in &ballot.encrypted_vote_vector {
+        push_field(&mut buf, ciphertext);
+    }
+    push_field(&mut buf, ballot.signature.as_bytes());
+    match &ballot.authorization {
+        BallotAuthorization::Credential { credential, ballot_signature } => {
+            buf.push(0);
+            buf.extend_from_slice(&credential.token);
+            buf.extend_from_slice(&credential.signature.commitment);
+            buf.extend_from_slice(&credential.signature.response);
+            buf.extend_from_slice(&ballot_signature.commitment);
+            buf.extend_from_slice(&ballot_signature.response);
+        }
+        BallotAuthorization::Membership(proof) => {
+            buf.push(1);
+            buf.extend_from_slice(&proof.nullifier);
+            buf.extend_from_slice(&(proof.challenges.len() as u32).to_le_bytes());
+            for (challenge, response) in proof.challenges.iter().zip(&proof.responses) {
+                buf.extend_from_slice(challenge);
+                buf.extend_from_slice(response);
+            }
+        }
+    }
+    buf.extend_from_slice(&ballot.sequence.to_le_bytes());
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// Per-election domain every ballot artifact is derived under: the ballot
+/// message (and so every ballot signature and membership proof), the nullifier
+/// base and the ballot's leaf hash, its tracking code. None of them means
+/// anything in another election.
+pub fn election_domain(election_id: &Digest32) -> [u8; 32] {
+    let mut buf = vec![ELECTION_DOMAIN_TAG];
+    buf.extend_from_slice(election_id.as_bytes());
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// Digest of a ballot's content that its authorization is bound to: the
+/// election's domain and the voter's nonce, so the ballot cannot be replayed elsewhere,
+/// and the voter address, so it cannot be replayed under another voter's weight.
+pub fn ballot_message(election_id: &Digest32, voter_address: &str, nonce: &[u8; 32], encrypted_vote_vector: &[Vec<u8>], sequence: u64) -> [u8; 32] {
+    let mut buf = election_domain(election_id).to_vec();
+    push_field(&mut buf, voter_address.as_bytes());
+    buf.extend_from_slice(nonce);
+    for ciphertext in encrypted_vote_vector {
+        push_field(&mut buf, ciphertext);
+    }
+    buf.extend_from_slice(&sequence.to_le_bytes());
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// What a revocation of the ballot with `tracking_code` is authorized over: a
+/// message no ballot can have, in this election's domain.
+pub fn revocation_message(election_id: &Digest32, tracking_code: &[u8; 32]) -> [u8; 32] {
+    let mut buf = vec![REVOCATION_TAG];
+    buf.extend_from_slice(&election_domain(election_id));
+    buf.extend_from_slice(tracking_code);
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// Key every ballot of one voter shares: the credential's token or the
+/// membership proof's nullifier.
+pub fn voter_key(authorization: &BallotAuthorization) -> [u8; 32] {
+    match authorization {
+        BallotAuthorization::Credential { credential, .. } => credential.token,
+        BallotAuthorization::Membership(proof) => proof.nullifier,
+    }
+}
+
+/// Distinct voter keys of a ballot set, sorted.
+pub fn voter_keys(ballots: &[EncryptedVote]) -> Vec<[u8; 32]> {
+    let mut keys: Vec<_> = ballots.iter().map(|ballot| voter_key(&ballot.authorization)).collect();
+    keys.sort_unstable();
+    keys.dedup();
+    keys
+}
+
+/// Digest of a sorted voter key set: H(tag || count || keys).
+pub fn voter_keys_digest(keys: &[[u8; 32]]) -> [u8; 32] {
+    let mut buf = vec![VOTER_KEYS_TAG];
+    buf.extend_from_slice(&(keys.len() as u32).to_le_bytes());
+    for key in keys {
+        buf.extend_from_slice(key);
+    }
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+fn push_field(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    buf.extend_from_slice(bytes);
+}
+
+fn hash_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut buf = Vec::with_capacity(65);
+    buf.push(NODE_TAG);
+    buf.extend_from_slice(left);
+    buf.extend_from_slice(right);
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// Root of the ballot set; odd nodes are carried up unchanged.
+pub fn compute_ballot_set_root(ballots: &[EncryptedVote]) -> [u8; 32] {
+    merkle_root(ballots.iter().map(ballot_leaf_hash).collect())
+}
+
+/// Root of a tree over arbitrary pre-hashed leaves, built like the ballot set.
+pub fn merkle_root(mut level: Vec<[u8; 32]>) -> [u8; 32] {
+    if level.is_empty() {
+        return EMPTY_BALLOT_SET_ROOT;
+    }
+
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => hash_nodes(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    level[0]
+}
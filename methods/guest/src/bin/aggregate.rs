@@ -0,0 +1,177 @@
+// Proof aggregation guest.
+// Verifies the receipts of any number of batch tallies and adds their encrypted
+// tallies together, so one succinct receipt covers the whole election however
+// many batches were proven. Each batch is an interim tally (counts sealed) of
+// its own ballot set; the batch receipts are assumptions of this proof, which
+// only verifies once they are resolved.
+//
+// Batches must share the election, voting scheme, identity roster and election
+// key and may not repeat a ballot set. Re-voting is resolved within a batch only, so no voter
+// may have ballots in two batches: each batch commits the digest of its sorted
+// voter keys, the host supplies the keys, and a key seen in an earlier batch
+// fails the proof before any tally is added.
+
+extern crate alloc;
+
+use std::collections::HashSet;
+
+use risc0_zkvm::guest::env;
+use risc0_zkvm::serde::Deserializer;
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde::Deserialize;
+
+#[path = "../types.rs"]
+mod types;
+#[path = "../pure_rust_fhe.rs"]
+mod pure_rust_fhe;
+#[path = "../noise_distribution.rs"]
+mod noise_distribution;
+#[path = "../scheme.rs"]
+mod scheme;
+#[path = "../journal.rs"]
+mod journal;
+#[path = "../ballot_set.rs"]
+mod ballot_set;
+
+use types::{AggregateInput, AggregateTallyOutput, InterimTallyOutput, JournalFrame, INTERIM_MARKER};
+use pure_rust_fhe::{max_tally_ballots, PureRustFheRuntime, TallyAccumulator};
+
+// Domain separation tag (must match host implementation)
+const BATCH_TAG: u8 = 0x07;
+
+fn main() {
+    let input: AggregateInput = env::read();
+    if input.batch_journals.is_empty() {
+        panic!("Aggregation needs at least one batch tally");
+    }
+    if input.batch_voter_keys.len() != input.batch_journals.len() {
+        panic!("Aggregation needs the voter keys of every batch");
+    }
+    eprintln!("🧮 [zkVM Aggregator] Combining {} batch tallies", input.batch_journals.len());
+
+    let fhe_runtime = PureRustFheRuntime::new();
+    let mut first: Option<InterimTallyOutput> = None;
+    let mut ballot_sets = HashSet::new();
+    let mut voter_keys = HashSet::new();
+    let mut batch_chain_head = [0u8; 32];
+    let mut ballots = 0u64;
+    let mut counted_weight = 0u64;
+    let mut superseded_ballots = 0u32;
+    let mut tallies: Vec<TallyAccumulator> = Vec::new();
+
+    for (index, journal) in input.batch_journals.iter().enumerate() {
+        // Resolved against the batch receipt the host added as an assumption
+        env::verify(input.tally_image_id, journal).unwrap();
+        let (batch, encrypted_tallies) = read_batch(index, journal);
+
+        if let Some(first) = &first {
+            // Weights from another table would be added to this election's tallies
+            if batch.election.scheme_digest != first.election.scheme_digest {
+                panic!("Batch {} was tallied under another voting scheme", index);
+            }
+            if batch.election != first.election {
+                panic!("Batch {} belongs to another election", index);
+            }
+            if batch.identity_root != first.identity_root {
+                panic!("Batch {} was tallied against another identity roster", index);
+            }
+            if batch.election_key_fingerprint != first.election_key_fingerprint {
+                panic!("Batch {} is encrypted under another election key", index);
+            }
+            if batch.encrypted_tally_frames != first.encrypted_tally_frames {
+                panic!("Batch {} has {} encrypted tallies, expected {}", index, batch.encrypted_tally_frames, first.encrypted_tally_frames);
+            }
+        }
+        // The same batch twice would count its ballots twice
+        if !ballot_sets.insert(batch.ballot_set_root) {
+            panic!("Batch {} repeats an earlier batch's ballot set", index);
+        }
+        // A voter in two batches would be counted in both
+        let keys = &input.batch_voter_keys[index];
+        if !keys.windows(2).all(|pair| pair[0] < pair[1]) || ballot_set::voter_keys_digest(keys) != batch.voter_keys_digest {
+            panic!("Batch {} voter keys do not match its journal", index);
+        }
+        if keys.iter().any(|key| !voter_keys.insert(*key)) {
+            panic!("Batch {} has a voter with ballots in an earlier batch", index);
+        }
+
+        for (position, payload) in encrypted_tallies.iter().enumerate() {
+            let cipher = fhe_runtime
+                .deserialize_ciphertext(payload)
+                .unwrap_or_else(|e| panic!("Batch {} tally {} is not a ciphertext: {:?}", index, position, e));
+            match tallies.get_mut(position) {
+                Some(tally) => tally.add(&cipher),
+                None => tallies.push(TallyAccumulator::new(cipher)),
+            }
+        }
+        batch_chain_head = chain_batch(&batch_chain_head, index as u32, &batch);
+        ballots += batch.ballots as u64;
+        counted_weight = counted_weight.saturating_add(batch.counted_weight);
+        superseded_ballots += batch.superseded_ballots;
+        first.get_or_insert(batch);
+    }
+
+    // Same bound as a single tally: the weighted sums must not wrap modulo the plaintext modulus
+    if counted_weight > max_tally_ballots() {
+        panic!("Tally overflow: {} ballots of total weight {} could exceed the plaintext modulus (at most {} can be tallied)",
+               ballots, counted_weight, max_tally_ballots());
+    }
+
+    let first = first.expect("at least one batch");
+    env::commit(&AggregateTallyOutput {
+        tally_image_id: input.tally_image_id,
+        batches: input.batch_journals.len() as u32,
+        ballots: ballots as u32,
+        superseded_ballots,
+        election: first.election,
+        identity_root: first.identity_root,
+        election_key_fingerprint: first.election_key_fingerprint,
+        batch_chain_head,
+        encrypted_tally_frames: tallies.len() as u32,
+    });
+    let mut frames = journal::FrameWriter::new();
+    for tally in tallies {
+        frames.commit(tally.finish().serialize());
+    }
+    eprintln!("🎯 [zkVM Aggregator] {} ballots across {} batches committed to proof!", ballots, input.batch_journals.len());
+}
+
+// Decode an interim tally journal and check its frame chain
+fn read_batch(index: usize, journal: &[u8]) -> (InterimTallyOutput, Vec<Vec<u8>>) {
+    if journal.len() % 4 != 0 {
+        panic!("Batch {} journal is not a whole number of words", index);
+    }
+    let words: Vec<u32> = journal.chunks_exact(4).map(|word| u32::from_le_bytes(word.try_into().unwrap())).collect();
+    let mut remaining: &[u32] = &words;
+    if remaining.first() != Some(&INTERIM_MARKER) {
+        panic!("Batch {} journal is not an interim tally", index);
+    }
+    let batch = InterimTallyOutput::deserialize(&mut Deserializer::new(&mut remaining))
+        .unwrap_or_else(|e| panic!("Batch {} journal does not decode: {}", index, e));
+
+    let mut head = [0u8; 32];
+    let mut payloads = Vec::with_capacity(batch.encrypted_tally_frames as usize);
+    for position in 0..batch.encrypted_tally_frames {
+        let frame = JournalFrame::deserialize(&mut Deserializer::new(&mut remaining))
+            .unwrap_or_else(|e| panic!("Batch {} frame {} does not decode: {}", index, position, e));
+        head = journal::chain_frame(&head, position, &frame.payload);
+        if frame.index != position || frame.chunk_hash != head {
+            panic!("Batch {} frame {} does not chain from the previous frame", index, position);
+        }
+        payloads.push(frame.payload);
+    }
+    if !remaining.is_empty() {
+        panic!("Batch {} journal has data after the last frame", index);
+    }
+    (batch, payloads)
+}
+
+// Chain step: head' = H(tag || head || index || ballot_set_root || audit_log_head)
+fn chain_batch(previous: &[u8; 32], index: u32, batch: &InterimTallyOutput) -> [u8; 32] {
+    let mut buf = vec![BATCH_TAG];
+    buf.extend_from_slice(previous);
+    buf.extend_from_slice(&index.to_le_bytes());
+    buf.extend_from_slice(&batch.ballot_set_root);
+    buf.extend_from_slice(&batch.audit_log_head);
+    (*Impl::hash_bytes(&buf)).into()
+}
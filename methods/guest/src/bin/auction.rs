@@ -0,0 +1,94 @@
+// Sealed-bid auction guest.
+// Bids are sealed as unary vectors over a public price ladder: a bidder's
+// ciphertext at level j encrypts 1 if the bid reaches `price_levels[j]` and 0
+// otherwise. Adding those vectors under encryption gives the demand at every
+// level, the number of bidders willing to pay that price, which is all the
+// comparison an auction needs: once the trustees decrypt the demand, the
+// winning level is the highest one with any demand and a second-price auction
+// clears at the highest level with demand from two bidders.
+//
+// This guest proves the encrypted demand covers exactly the committed bids.
+// It cannot see the plaintexts, so a bid is trusted to be a well-formed unary
+// vector of zeros and ones, as a ballot is trusted to be one-hot.
+
+extern crate alloc;
+
+use std::collections::HashSet;
+
+use risc0_zkvm::guest::env;
+use risc0_zkvm::sha::{Impl, Sha256};
+
+#[path = "../types.rs"]
+mod types;
+#[path = "../pure_rust_fhe.rs"]
+mod pure_rust_fhe;
+#[path = "../noise_distribution.rs"]
+mod noise_distribution;
+#[path = "../scheme.rs"]
+mod scheme;
+#[path = "../journal.rs"]
+mod journal;
+
+use types::{AuctionInput, AuctionOutput};
+use pure_rust_fhe::{max_sum_contributions, Cipher, PureRustFheRuntime, Signed, TallyAccumulator};
+
+// Domain separation tag (must match host implementation)
+const BID_TAG: u8 = 0x0c;
+
+fn main() {
+    let input: AuctionInput = env::read();
+    let levels = input.price_levels.len();
+    if levels == 0 {
+        panic!("The auction needs at least one price level");
+    }
+    if input.price_levels.windows(2).any(|pair| pair[0] >= pair[1]) {
+        panic!("Price levels must be strictly ascending");
+    }
+    if input.bids.is_empty() {
+        panic!("The auction needs at least one bid");
+    }
+    // Each level's demand counts one per bidder
+    if input.bids.len() as u64 > max_sum_contributions(1) {
+        panic!("Demand overflow: {} bids could exceed the plaintext modulus (at most {} can be counted)",
+               input.bids.len(), max_sum_contributions(1));
+    }
+    eprintln!("🔨 [zkVM Auction] Counting {} sealed bids over {} price levels", input.bids.len(), levels);
+
+    let fhe_runtime = PureRustFheRuntime::new();
+    let key_fingerprint = input.public_key.fingerprint();
+    let mut bidders = HashSet::new();
+    let mut bid_levels: Vec<Cipher<Signed>> = (0..levels).map(|_| Cipher::zeroed()).collect();
+    let mut demand: Option<Vec<TallyAccumulator>> = None;
+    let mut digest_input = vec![BID_TAG];
+    for (index, bid) in input.bids.iter().enumerate() {
+        if !bidders.insert(bid.bidder.as_str()) {
+            panic!("Bidder {} bid twice", bid.bidder);
+        }
+        fhe_runtime
+            .deserialize_vote_vector(&bid.encrypted_levels, &key_fingerprint, &mut bid_levels)
+            .unwrap_or_else(|e| panic!("Bid {} is malformed: {:?}", index, e));
+        digest_input.extend_from_slice(&(bid.bidder.len() as u32).to_le_bytes());
+        digest_input.extend_from_slice(bid.bidder.as_bytes());
+        for ciphertext in &bid.encrypted_levels {
+            digest_input.extend_from_slice(&(ciphertext.len() as u32).to_le_bytes());
+            digest_input.extend_from_slice(ciphertext);
+        }
+        match &mut demand {
+            Some(demand) => demand.iter_mut().zip(&bid_levels).for_each(|(sum, level)| sum.add(level)),
+            None => demand = Some(bid_levels.iter().map(|level| TallyAccumulator::new(level.clone())).collect()),
+        }
+    }
+
+    env::commit(&AuctionOutput {
+        price_levels: input.price_levels.clone(),
+        bids: input.bids.len() as u32,
+        key_fingerprint,
+        bids_digest: (*Impl::hash_bytes(&digest_input)).into(),
+        encrypted_demand_frames: levels as u32,
+    });
+    let mut frames = journal::FrameWriter::new();
+    for sum in demand.expect("at least one bid") {
+        frames.commit(sum.finish().serialize());
+    }
+    eprintln!("🎯 [zkVM Auction] Encrypted demand at {} price levels committed to proof!", levels);
+}
@@ -0,0 +1,178 @@
+// Challenge guest for the external challenger (challenger.rs at the repo root).
+// Evaluates the challenger's circuit over ciphertexts encrypted to the
+// challenger's key, so the prover never holds a secret key; the challenger
+// decrypts the committed outputs and checks them against its plaintexts.
+// Invalid challenge ciphertexts are reported in the journal rather than
+// aborting the proof, so a negative challenge checks the guest's validation
+// under proof.
+
+extern crate alloc;
+
+use std::collections::{HashMap, HashSet};
+
+use risc0_zkvm::guest::env;
+
+#[path = "../types.rs"]
+mod types;
+#[path = "../pure_rust_fhe.rs"]
+mod pure_rust_fhe;
+#[path = "../noise_distribution.rs"]
+mod noise_distribution;
+#[path = "../scheme.rs"]
+mod scheme;
+#[path = "../circuit_digest.rs"]
+mod circuit_digest;
+
+use types::{ChallengeInput, ChallengeOutput, ChallengeRejection, ChallengeRejectionReason as RejectionReason, CircuitOp};
+use pure_rust_fhe::{FheError, PureRustFheRuntime, Signed, Cipher, PLAINTEXT_MODULUS};
+use circuit_digest::circuit_digest;
+
+fn main() {
+    eprintln!("🎯 [zkVM Guest] O3 CHALLENGE PROTOCOL - FHE Mathematical Proof");
+    eprintln!("==============================================================");
+    eprintln!("🔒 [zkVM Guest] PROVING: Real FHE computation inside zkVM");
+    eprintln!("🔑 [zkVM Guest] External challenger controls secret key");
+    eprintln!("⚡ [zkVM Guest] Prover CANNOT forge results without genuine FHE");
+    
+    // Read challenge input from external challenger
+    let challenge_input: ChallengeInput = env::read();
+    
+    eprintln!("📥 [zkVM Guest] Received challenge from external auditor:");
+    eprintln!("  Test ID: {}", challenge_input.test_metadata.test_id);
+    eprintln!("  Challenge ciphertexts: {}", challenge_input.challenge_ciphertexts.len());
+    eprintln!("  Expected operations: {:?}", challenge_input.test_metadata.expected_operations);
+    eprintln!("  🚨 CRITICAL: Guest has NO access to secret key!");
+    
+    // Perform the mathematical proof
+    let result = execute_fhe_challenge_protocol(challenge_input);
+    
+    eprintln!("✅ [zkVM Guest] Challenge protocol completed");
+    eprintln!("🔍 [zkVM Guest] Results journaled for external verification");
+    
+    // Commit the result - this creates the cryptographic proof
+    env::commit(&result);
+    
+    eprintln!("🎯 [zkVM Guest] MATHEMATICAL PROOF COMMITTED TO BLOCKCHAIN!");
+    eprintln!("📋 [zkVM Guest] External challenger can now verify with their secret key");
+}
+
+/// Execute O3's Challenge Protocol Inside zkVM
+/// 
+/// This function demonstrates that REAL FHE computation occurs inside the zkVM:
+/// 1. Accepts external public key (no SK access)
+/// 2. Performs genuine FHE operations on challenge ciphertexts
+/// 3. Returns encrypted results that only external challenger can decrypt
+/// 4. zkVM proof guarantees this exact computation occurred
+fn execute_fhe_challenge_protocol(challenge: ChallengeInput) -> ChallengeOutput {
+    eprintln!("⚙️  [zkVM Guest] Initializing FHE runtime with EXTERNAL keys...");
+    
+    // CRITICAL: Use external public key - guest NEVER generates secret key
+    let public_key = challenge.public_key;
+    let fhe_runtime = PureRustFheRuntime::new();
+    
+    eprintln!("🔑 [zkVM Guest] Using challenger's public key - NO SECRET KEY ACCESS");
+    
+    let mut operations_performed = Vec::new();
+    let mut result_ciphertexts = Vec::new();
+    let mut intermediate_results = Vec::new();
+    let mut fhe_operation_log = Vec::new();
+    
+    // Step 1: Deserialize challenge ciphertexts, bound to the challenger's key.
+    // Invalid ones are reported rather than aborting the proof, so a negative
+    // challenge can check the rejections under proof.
+    eprintln!("📄 [zkVM Guest] Deserializing challenge ciphertexts...");
+    let key_fingerprint = public_key.fingerprint();
+    let mut registers: HashMap<String, Cipher<Signed>> = HashMap::new();
+    let mut seen = HashSet::new();
+    let mut rejection_report = Vec::new();
+    
+    for (i, ciphertext_bytes) in challenge.challenge_ciphertexts.iter().enumerate() {
+        let mut cipher = Cipher::zeroed();
+        let reason = match fhe_runtime.deserialize_vote_vector(std::slice::from_ref(ciphertext_bytes), &key_fingerprint, std::slice::from_mut(&mut cipher)) {
+            Err(FheError::KeyMismatch) => Some(RejectionReason::WrongKey),
+            Err(_) => Some(RejectionReason::Malformed),
+            Ok(()) if !seen.insert(cipher.serialize()) => Some(RejectionReason::Duplicate),
+            Ok(()) => None,
+        };
+        match reason {
+            None => {
+                registers.insert(format!("c{}", i), cipher);
+                eprintln!("  ✅ Challenge ciphertext {} deserialized", i + 1);
+                fhe_operation_log.push(format!("Deserialized challenge ciphertext {}", i + 1));
+            },
+            Some(reason) => {
+                eprintln!("  ❌ Challenge ciphertext {} rejected: {:?}", i + 1, reason);
+                fhe_operation_log.push(format!("Rejected challenge ciphertext {}: {:?}", i + 1, reason));
+                rejection_report.push(ChallengeRejection { index: i as u32, reason });
+            }
+        }
+    }
+    
+    operations_performed.push("DeserializeChallenges".to_string());
+    
+    // Step 2: Evaluate the challenger's circuit with REAL homomorphic operations
+    eprintln!("🧮 [zkVM Guest] Evaluating {} circuit ops...", challenge.circuit.ops.len());
+    let accepted = registers.len();
+    let read = |registers: &HashMap<String, Cipher<Signed>>, name: &str| -> Cipher<Signed> {
+        registers.get(name).cloned().unwrap_or_else(|| panic!("Register {} is read before it is written", name))
+    };
+    for op in &challenge.circuit.ops {
+        let (out, value, operation) = match op {
+            CircuitOp::Add { out, lhs, rhs } => {
+                let mut value = read(&registers, lhs);
+                value += &read(&registers, rhs);
+                (out, value, "HomomorphicAddition")
+            }
+            CircuitOp::Sub { out, lhs, rhs } => {
+                let mut value = read(&registers, lhs);
+                value -= &read(&registers, rhs);
+                (out, value, "HomomorphicSubtraction")
+            }
+            CircuitOp::MulPlain { out, input, scalar } => {
+                if *scalar >= PLAINTEXT_MODULUS {
+                    panic!("Scalar {} is not below the plaintext modulus", scalar);
+                }
+                (out, read(&registers, input).mul_plain(*scalar), "HomomorphicScalarMultiplication")
+            }
+            CircuitOp::Rotate { out, input, steps } => (out, read(&registers, input).rotate(*steps), "HomomorphicRotation"),
+        };
+        fhe_operation_log.push(format!("{:?}", op));
+        operations_performed.push(operation.to_string());
+        intermediate_results.push(value.serialize());
+        registers.insert(out.clone(), value);
+    }
+    
+    // Step 3: Serialize the output registers
+    eprintln!("📦 [zkVM Guest] Serializing {} output registers...", challenge.circuit.outputs.len());
+    for name in &challenge.circuit.outputs {
+        result_ciphertexts.push(read(&registers, name).serialize());
+    }
+    
+    eprintln!("✅ [zkVM Guest] All FHE operations completed inside zkVM");
+    fhe_operation_log.push("Output registers serialized".to_string());
+    
+    // Step 4: Create comprehensive proof output
+    let challenge_output = ChallengeOutput {
+        test_id: challenge.test_metadata.test_id.clone(),
+        operations_performed,
+        circuit_digest: circuit_digest(&challenge.circuit.ops, &challenge.circuit.outputs),
+        result_ciphertexts,
+        intermediate_results,
+        fhe_operation_log,
+        rejection_report,
+        challenge_proof_complete: true,
+    };
+    
+    eprintln!("🎯 [zkVM Guest] MATHEMATICAL PROOF SUMMARY:");
+    eprintln!("  🔐 Processed {} challenge ciphertexts", challenge.challenge_ciphertexts.len());
+    eprintln!("  🚫 Rejected {} challenge ciphertexts", challenge_output.rejection_report.len());
+    eprintln!("  🧮 Evaluated {} circuit ops over {} challenges", challenge.circuit.ops.len(), accepted);
+    eprintln!("  📊 Generated {} result ciphertexts", challenge_output.result_ciphertexts.len());
+    eprintln!("  📝 Logged {} FHE operations", challenge_output.fhe_operation_log.len());
+    eprintln!("  🔑 NO SECRET KEY used (external challenger verification required)");
+    
+    eprintln!("\n🏆 PROOF COMPLETE: Real FHE computation performed inside zkVM!");
+    eprintln!("🔍 External challenger can decrypt results to verify correctness");
+    
+    challenge_output
+}
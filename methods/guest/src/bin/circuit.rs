@@ -0,0 +1,106 @@
+// FHE circuit interpreter guest.
+// Runs a small FHE program given in the input (add, sub, multiply by a public
+// scalar, rotate) over named ciphertext registers and proves the outputs, so a
+// new encrypted computation can be proven with this one published image
+// instead of a guest of its own.
+//
+// Registers start as the bound input ciphertexts; every op writes its `out`
+// register, replacing an earlier value of the same name. The journal commits
+// the circuit and input digests, so a verifier knows exactly which program ran
+// on which ciphertexts, and the output registers follow as frames.
+
+extern crate alloc;
+
+use std::collections::{HashMap, HashSet};
+
+use risc0_zkvm::guest::env;
+use risc0_zkvm::sha::{Impl, Sha256};
+
+#[path = "../types.rs"]
+mod types;
+#[path = "../pure_rust_fhe.rs"]
+mod pure_rust_fhe;
+#[path = "../noise_distribution.rs"]
+mod noise_distribution;
+#[path = "../scheme.rs"]
+mod scheme;
+#[path = "../journal.rs"]
+mod journal;
+#[path = "../circuit_digest.rs"]
+mod circuit_digest;
+
+use types::{CircuitInput, CircuitOp, CircuitOutput};
+use pure_rust_fhe::{Cipher, PureRustFheRuntime, Signed, PLAINTEXT_MODULUS};
+use circuit_digest::{circuit_digest, extend_with_name};
+
+// Domain separation tag (must match host implementation)
+const CIRCUIT_INPUT_TAG: u8 = 0x0e;
+
+fn main() {
+    let input: CircuitInput = env::read();
+    if input.inputs.is_empty() {
+        panic!("The circuit needs at least one input register");
+    }
+    if input.outputs.is_empty() {
+        panic!("The circuit needs at least one output register");
+    }
+    eprintln!("🧩 [zkVM Circuit] Running {} ops over {} input registers", input.ops.len(), input.inputs.len());
+
+    let fhe_runtime = PureRustFheRuntime::new();
+    let key_fingerprint = input.public_key.fingerprint();
+    let mut registers: HashMap<&str, Cipher<Signed>> = HashMap::new();
+    let mut names = HashSet::new();
+    let mut inputs_digest = vec![CIRCUIT_INPUT_TAG];
+    for register in &input.inputs {
+        if !names.insert(register.name.as_str()) {
+            panic!("Input register {} is given twice", register.name);
+        }
+        let mut cipher = Cipher::zeroed();
+        fhe_runtime
+            .deserialize_vote_vector(std::slice::from_ref(&register.ciphertext), &key_fingerprint, std::slice::from_mut(&mut cipher))
+            .unwrap_or_else(|e| panic!("Input register {} is malformed: {:?}", register.name, e));
+        registers.insert(&register.name, cipher);
+        extend_with_name(&mut inputs_digest, &register.name);
+        inputs_digest.extend_from_slice(&(register.ciphertext.len() as u32).to_le_bytes());
+        inputs_digest.extend_from_slice(&register.ciphertext);
+    }
+
+    let read = |registers: &HashMap<&str, Cipher<Signed>>, name: &str| -> Cipher<Signed> {
+        registers.get(name).cloned().unwrap_or_else(|| panic!("Register {} is read before it is written", name))
+    };
+    for op in &input.ops {
+        let (out, value) = match op {
+            CircuitOp::Add { out, lhs, rhs } => {
+                let mut value = read(&registers, lhs);
+                value += &read(&registers, rhs);
+                (out, value)
+            }
+            CircuitOp::Sub { out, lhs, rhs } => {
+                let mut value = read(&registers, lhs);
+                value -= &read(&registers, rhs);
+                (out, value)
+            }
+            CircuitOp::MulPlain { out, input, scalar } => {
+                if *scalar >= PLAINTEXT_MODULUS {
+                    panic!("Scalar {} is not below the plaintext modulus", scalar);
+                }
+                (out, read(&registers, input).mul_plain(*scalar))
+            }
+            CircuitOp::Rotate { out, input, steps } => (out, read(&registers, input).rotate(*steps)),
+        };
+        registers.insert(out, value);
+    }
+
+    env::commit(&CircuitOutput {
+        key_fingerprint,
+        circuit_digest: circuit_digest(&input.ops, &input.outputs),
+        inputs_digest: (*Impl::hash_bytes(&inputs_digest)).into(),
+        ops: input.ops.len() as u32,
+        encrypted_output_frames: input.outputs.len() as u32,
+    });
+    let mut frames = journal::FrameWriter::new();
+    for name in &input.outputs {
+        frames.commit(read(&registers, name).serialize());
+    }
+    eprintln!("🎯 [zkVM Circuit] {} output registers committed to proof!", input.outputs.len());
+}
@@ -0,0 +1,85 @@
+// Encrypted vector sum guest.
+// The FHE-in-zkVM pattern beyond voting: contributors encrypt integer vectors
+// of one configured length (salary bands, sensor readings, survey answers) and
+// this guest adds them slot by slot under encryption, proving the sums cover
+// exactly the committed contributions. The sums are decrypted afterwards by
+// whoever holds the key, as with a tally; nothing here sees a plaintext.
+//
+// Every contribution must have `vector_len` ciphertexts bound to the input key.
+// `max_value` is the declared bound on each slot: the guest cannot see the
+// plaintexts, so it only guarantees the sums cannot wrap modulo the plaintext
+// modulus for contributions that respect it.
+
+extern crate alloc;
+
+use risc0_zkvm::guest::env;
+use risc0_zkvm::sha::{Impl, Sha256};
+
+#[path = "../types.rs"]
+mod types;
+#[path = "../pure_rust_fhe.rs"]
+mod pure_rust_fhe;
+#[path = "../noise_distribution.rs"]
+mod noise_distribution;
+#[path = "../scheme.rs"]
+mod scheme;
+#[path = "../journal.rs"]
+mod journal;
+
+use types::{VectorSumInput, VectorSumOutput};
+use pure_rust_fhe::{max_sum_contributions, Cipher, PureRustFheRuntime, Signed, TallyAccumulator};
+
+// Domain separation tag (must match host implementation)
+const CONTRIBUTION_TAG: u8 = 0x0b;
+
+fn main() {
+    let input: VectorSumInput = env::read();
+    let vector_len = input.vector_len as usize;
+    if vector_len == 0 {
+        panic!("Vector length must be at least one slot");
+    }
+    if input.max_value == 0 {
+        panic!("Maximum slot value must be at least one");
+    }
+    if input.contributions.is_empty() {
+        panic!("Summing needs at least one contribution");
+    }
+    if input.contributions.len() as u64 > max_sum_contributions(input.max_value) {
+        panic!("Sum overflow: {} contributions of values up to {} could exceed the plaintext modulus (at most {} can be summed)",
+               input.contributions.len(), input.max_value, max_sum_contributions(input.max_value));
+    }
+    eprintln!("🧮 [zkVM Vector Sum] Adding {} encrypted vectors of {} slots", input.contributions.len(), vector_len);
+
+    let fhe_runtime = PureRustFheRuntime::new();
+    let key_fingerprint = input.public_key.fingerprint();
+    let mut slots: Vec<Cipher<Signed>> = (0..vector_len).map(|_| Cipher::zeroed()).collect();
+    let mut sums: Option<Vec<TallyAccumulator>> = None;
+    let mut digest_input = vec![CONTRIBUTION_TAG];
+    for (index, contribution) in input.contributions.iter().enumerate() {
+        fhe_runtime
+            .deserialize_vote_vector(contribution, &key_fingerprint, &mut slots)
+            .unwrap_or_else(|e| panic!("Contribution {} is malformed: {:?}", index, e));
+        for ciphertext in contribution {
+            digest_input.extend_from_slice(&(ciphertext.len() as u32).to_le_bytes());
+            digest_input.extend_from_slice(ciphertext);
+        }
+        match &mut sums {
+            Some(sums) => sums.iter_mut().zip(&slots).for_each(|(sum, slot)| sum.add(slot)),
+            None => sums = Some(slots.iter().map(|slot| TallyAccumulator::new(slot.clone())).collect()),
+        }
+    }
+
+    env::commit(&VectorSumOutput {
+        vector_len: input.vector_len,
+        contributions: input.contributions.len() as u32,
+        max_value: input.max_value,
+        key_fingerprint,
+        contributions_digest: (*Impl::hash_bytes(&digest_input)).into(),
+        encrypted_sum_frames: input.vector_len,
+    });
+    let mut frames = journal::FrameWriter::new();
+    for sum in sums.expect("at least one contribution") {
+        frames.commit(sum.finish().serialize());
+    }
+    eprintln!("🎯 [zkVM Vector Sum] {} slot sums committed to proof!", vector_len);
+}
@@ -0,0 +1,29 @@
+// Candidate list binding (guest side).
+// Recomputes the digest of the ballot text the host supplied, so the committed
+// election context ties the counts to exactly these labels.
+
+use risc0_zkvm::sha::{Impl, Sha256};
+
+use crate::types::{Candidate, Digest32};
+
+// Domain separation tag (must match host implementation)
+const CANDIDATES_TAG: u8 = 0x11;
+
+/// Digest of the candidate list; zero for an empty list.
+pub fn candidates_digest(candidates: &[Candidate]) -> Digest32 {
+    if candidates.is_empty() {
+        return Digest32::ZERO;
+    }
+    let mut buf = vec![CANDIDATES_TAG];
+    for candidate in candidates {
+        buf.push(candidate.id);
+        buf.extend_from_slice(&(candidate.label.len() as u32).to_le_bytes());
+        buf.extend_from_slice(candidate.label.as_bytes());
+    }
+    Digest32((*Impl::hash_bytes(&buf)).into())
+}
+
+/// True if there is one candidate per counted option, in option order.
+pub fn covers_options(candidates: &[Candidate]) -> bool {
+    candidates.len() == 3 && candidates.iter().zip(1u8..).all(|(candidate, id)| candidate.id == id)
+}
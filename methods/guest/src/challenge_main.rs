@@ -1,228 +0,0 @@
-use risc0_zkvm::guest::env;
-use serde::{Serialize, Deserialize};
-
-mod pure_rust_fhe;
-use pure_rust_fhe::{PureRustFheRuntime, PublicKey, PrivateKey, Signed, Cipher};
-
-/// O3 Challenge Protocol Input
-/// 
-/// This input is generated by the external challenger who:
-/// 1. Generated FHE keys externally (prover never sees SK)
-/// 2. Created random test vectors and encrypted them
-/// 3. Sent only public inputs to the zkVM prover
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ChallengeInput {
-    pub public_key: PublicKey,
-    pub challenge_ciphertexts: Vec<Vec<u8>>, // Serialized challenge ciphertexts
-    pub test_metadata: ChallengeMetadata,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ChallengeMetadata {
-    pub test_id: String,
-    pub num_challenges: usize,
-    pub expected_operations: Vec<String>,
-    pub timestamp: u64,
-}
-
-/// O3 Challenge Protocol Output
-/// 
-/// This output contains all FHE computation results that will be
-/// journaled in the zkVM proof for external verification
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ChallengeOutput {
-    pub test_id: String,
-    pub operations_performed: Vec<String>,
-    pub result_ciphertexts: Vec<Vec<u8>>, // Serialized result ciphertexts
-    pub intermediate_results: Vec<Vec<u8>>, // All intermediate computations
-    pub fhe_operation_log: Vec<String>,
-    pub challenge_proof_complete: bool,
-}
-
-fn main() {
-    eprintln!("🎯 [zkVM Guest] O3 CHALLENGE PROTOCOL - FHE Mathematical Proof");
-    eprintln!("==============================================================");
-    eprintln!("🔒 [zkVM Guest] PROVING: Real FHE computation inside zkVM");
-    eprintln!("🔑 [zkVM Guest] External challenger controls secret key");
-    eprintln!("⚡ [zkVM Guest] Prover CANNOT forge results without genuine FHE");
-    
-    // Read challenge input from external challenger
-    let challenge_input: ChallengeInput = env::read();
-    
-    eprintln!("📥 [zkVM Guest] Received challenge from external auditor:");
-    eprintln!("  Test ID: {}", challenge_input.test_metadata.test_id);
-    eprintln!("  Challenge ciphertexts: {}", challenge_input.challenge_ciphertexts.len());
-    eprintln!("  Expected operations: {:?}", challenge_input.test_metadata.expected_operations);
-    eprintln!("  🚨 CRITICAL: Guest has NO access to secret key!");
-    
-    // Perform the mathematical proof
-    let result = execute_fhe_challenge_protocol(challenge_input);
-    
-    eprintln!("✅ [zkVM Guest] Challenge protocol completed");
-    eprintln!("🔍 [zkVM Guest] Results journaled for external verification");
-    
-    // Commit the result - this creates the cryptographic proof
-    env::commit(&result);
-    
-    eprintln!("🎯 [zkVM Guest] MATHEMATICAL PROOF COMMITTED TO BLOCKCHAIN!");
-    eprintln!("📋 [zkVM Guest] External challenger can now verify with their secret key");
-}
-
-/// Execute O3's Challenge Protocol Inside zkVM
-/// 
-/// This function demonstrates that REAL FHE computation occurs inside the zkVM:
-/// 1. Accepts external public key (no SK access)
-/// 2. Performs genuine FHE operations on challenge ciphertexts
-/// 3. Returns encrypted results that only external challenger can decrypt
-/// 4. zkVM proof guarantees this exact computation occurred
-fn execute_fhe_challenge_protocol(challenge: ChallengeInput) -> ChallengeOutput {
-    eprintln!("⚙️  [zkVM Guest] Initializing FHE runtime with EXTERNAL keys...");
-    
-    // CRITICAL: Use external public key - guest NEVER generates secret key
-    let public_key = challenge.public_key;
-    let fhe_runtime = PureRustFheRuntime::new();
-    
-    eprintln!("🔑 [zkVM Guest] Using challenger's public key - NO SECRET KEY ACCESS");
-    
-    let mut operations_performed = Vec::new();
-    let mut result_ciphertexts = Vec::new();
-    let mut intermediate_results = Vec::new();
-    let mut fhe_operation_log = Vec::new();
-    
-    // Step 1: Deserialize challenge ciphertexts
-    eprintln!("📄 [zkVM Guest] Deserializing challenge ciphertexts...");
-    let mut challenge_ciphers = Vec::new();
-    
-    for (i, ciphertext_bytes) in challenge.challenge_ciphertexts.iter().enumerate() {
-        match fhe_runtime.deserialize_ciphertext(ciphertext_bytes) {
-            Ok(cipher) => {
-                challenge_ciphers.push(cipher);
-                eprintln!("  ✅ Challenge ciphertext {} deserialized", i + 1);
-                fhe_operation_log.push(format!("Deserialized challenge ciphertext {}", i + 1));
-            },
-            Err(e) => {
-                eprintln!("  ❌ Failed to deserialize challenge {}: {:?}", i + 1, e);
-                panic!("Invalid challenge ciphertext - cannot continue proof");
-            }
-        }
-    }
-    
-    operations_performed.push("DeserializeChallenges".to_string());
-    
-    // Step 2: Initialize encrypted accumulator (sum = 0)
-    eprintln!("🔢 [zkVM Guest] Initializing encrypted accumulator...");
-    let zero_plaintext = Signed::from(0);
-    let mut encrypted_sum = fhe_runtime.encrypt(zero_plaintext, &public_key)
-        .expect("Failed to encrypt zero for accumulator");
-    
-    eprintln!("✅ [zkVM Guest] Encrypted zero accumulator created");
-    fhe_operation_log.push("Created encrypted zero accumulator".to_string());
-    operations_performed.push("InitializeAccumulator".to_string());
-    
-    // Serialize intermediate result
-    let sum_bytes = encrypted_sum.serialize();
-    intermediate_results.push(sum_bytes);
-    
-    // Step 3: Perform homomorphic addition on all challenge ciphertexts
-    eprintln!("➕ [zkVM Guest] Performing REAL homomorphic addition...");
-    
-    for (i, challenge_cipher) in challenge_ciphers.iter().enumerate() {
-        eprintln!("  🔄 Adding challenge ciphertext {} to encrypted sum", i + 1);
-        
-        // REAL FHE HOMOMORPHIC ADDITION - This is the core proof!
-        encrypted_sum = encrypted_sum + challenge_cipher.clone();
-        
-        eprintln!("  ✅ Homomorphic addition {} completed", i + 1);
-        fhe_operation_log.push(format!("Homomorphic addition of challenge {}", i + 1));
-        
-        // Store intermediate result for verification
-        let intermediate_bytes = encrypted_sum.serialize();
-        intermediate_results.push(intermediate_bytes);
-    }
-    
-    operations_performed.push("HomomorphicAddition".to_string());
-    
-    // Step 4: Additional FHE operations for comprehensive proof
-    eprintln!("🔬 [zkVM Guest] Performing additional FHE operations...");
-    
-    // Homomorphic multiplication by constant (if we have at least one challenge)
-    if !challenge_ciphers.is_empty() {
-        eprintln!("  ✖️  Performing homomorphic multiplication by 2...");
-        
-        // Encrypt the constant 2
-        let two_plaintext = Signed::from(2);
-        let encrypted_two = fhe_runtime.encrypt(two_plaintext, &public_key)
-            .expect("Failed to encrypt constant 2");
-        
-        // Multiply first challenge by 2 (homomorphic operation)
-        let multiplied_result = homomorphic_multiply(&challenge_ciphers[0], &encrypted_two);
-        
-        eprintln!("  ✅ Homomorphic multiplication completed");
-        fhe_operation_log.push("Homomorphic multiplication by constant 2".to_string());
-        operations_performed.push("HomomorphicMultiplication".to_string());
-        
-        // Store multiplication result
-        let mult_bytes = multiplied_result.serialize();
-        result_ciphertexts.push(mult_bytes);
-    }
-    
-    // Step 5: Final result serialization
-    eprintln!("📦 [zkVM Guest] Serializing final encrypted sum...");
-    let final_sum_bytes = encrypted_sum.serialize();
-    result_ciphertexts.push(final_sum_bytes);
-    
-    eprintln!("✅ [zkVM Guest] All FHE operations completed inside zkVM");
-    fhe_operation_log.push("Final encrypted sum serialized".to_string());
-    
-    // Step 6: Create comprehensive proof output
-    let challenge_output = ChallengeOutput {
-        test_id: challenge.test_metadata.test_id.clone(),
-        operations_performed,
-        result_ciphertexts,
-        intermediate_results,
-        fhe_operation_log,
-        challenge_proof_complete: true,
-    };
-    
-    eprintln!("🎯 [zkVM Guest] MATHEMATICAL PROOF SUMMARY:");
-    eprintln!("  🔐 Processed {} challenge ciphertexts", challenge.challenge_ciphertexts.len());
-    eprintln!("  ➕ Performed {} homomorphic additions", challenge.challenge_ciphertexts.len());
-    eprintln!("  📊 Generated {} result ciphertexts", challenge_output.result_ciphertexts.len());
-    eprintln!("  📝 Logged {} FHE operations", challenge_output.fhe_operation_log.len());
-    eprintln!("  🔑 NO SECRET KEY used (external challenger verification required)");
-    
-    eprintln!("\n🏆 PROOF COMPLETE: Real FHE computation performed inside zkVM!");
-    eprintln!("🔍 External challenger can decrypt results to verify correctness");
-    
-    challenge_output
-}
-
-/// Simplified homomorphic multiplication for demonstration
-/// In production, this would use proper relinearization
-fn homomorphic_multiply(cipher1: &Cipher<Signed>, cipher2: &Cipher<Signed>) -> Cipher<Signed> {
-    // Simplified multiplication (coefficient-wise for demo)
-    // Real BFV would require proper polynomial multiplication and relinearization
-    let mut result_data = vec![0u64; cipher1.ciphertext_data.len()];
-    
-    const CIPHERTEXT_MODULUS: u64 = 288230376151711744; // Must match main parameters
-    
-    for i in 0..result_data.len() {
-        let a = cipher1.get_coefficient(i);
-        let b = cipher2.get_coefficient(i);
-        
-        // Simplified multiplication mod q
-        let product = (a as u128 * b as u128) % CIPHERTEXT_MODULUS as u128;
-        result_data[i] = product as u64;
-    }
-    
-    Cipher {
-        ciphertext_data: result_data,
-        _phantom: std::marker::PhantomData,
-    }
-}
-
-impl Cipher<Signed> {
-    pub fn get_coefficient(&self, index: usize) -> u64 {
-        self.ciphertext_data.get(index).copied().unwrap_or(0)
-    }
-}
\ No newline at end of file
@@ -0,0 +1,53 @@
+// Digest of an FHE circuit, shared by the circuit interpreter guest and the
+// challenge guest so both commit the same value for the same program.
+
+use risc0_zkvm::sha::{Impl, Sha256};
+
+use crate::types::CircuitOp;
+
+// Domain separation tag (must match host implementation)
+const CIRCUIT_TAG: u8 = 0x0d;
+
+// H(tag || op count || ops || output count || output names); names are length-prefixed
+pub fn circuit_digest(ops: &[CircuitOp], outputs: &[String]) -> [u8; 32] {
+    let mut buf = vec![CIRCUIT_TAG];
+    buf.extend_from_slice(&(ops.len() as u32).to_le_bytes());
+    for op in ops {
+        match op {
+            CircuitOp::Add { out, lhs, rhs } => {
+                buf.push(0);
+                for name in [out, lhs, rhs] {
+                    extend_with_name(&mut buf, name);
+                }
+            }
+            CircuitOp::Sub { out, lhs, rhs } => {
+                buf.push(1);
+                for name in [out, lhs, rhs] {
+                    extend_with_name(&mut buf, name);
+                }
+            }
+            CircuitOp::MulPlain { out, input, scalar } => {
+                buf.push(2);
+                extend_with_name(&mut buf, out);
+                extend_with_name(&mut buf, input);
+                buf.extend_from_slice(&scalar.to_le_bytes());
+            }
+            CircuitOp::Rotate { out, input, steps } => {
+                buf.push(3);
+                extend_with_name(&mut buf, out);
+                extend_with_name(&mut buf, input);
+                buf.extend_from_slice(&steps.to_le_bytes());
+            }
+        }
+    }
+    buf.extend_from_slice(&(outputs.len() as u32).to_le_bytes());
+    for name in outputs {
+        extend_with_name(&mut buf, name);
+    }
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+pub fn extend_with_name(buf: &mut Vec<u8>, name: &str) {
+    buf.extend_from_slice(&(name.len() as u32).to_le_bytes());
+    buf.extend_from_slice(name.as_bytes());
+}
@@ -0,0 +1,59 @@
+// Continuity with a prior interim tally (guest side).
+// The prior tally's receipt is an assumption of this proof: `env::verify` only
+// holds once the host has supplied that receipt, so the journal checked here is
+// one the tally image really proved. This proof then attests that it continues
+// that tally: the same election, identity roster and election key, and a
+// submission log whose prefix is the log the prior tally covered.
+
+use risc0_zkvm::guest::env;
+use risc0_zkvm::serde::Deserializer;
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde::Deserialize;
+
+use crate::audit_log::has_prefix_head;
+use crate::credentials::identity_root;
+use crate::types::{InterimTallyOutput, PriorTally, VoteTallyInput, INTERIM_MARKER};
+
+// Domain separation tag (must match host implementation)
+const PRIOR_TAG: u8 = 0x08;
+
+/// H(tag || image_id || journal digest), committed as `prior_tally_digest`.
+pub fn prior_tally_digest(image_id: &[u32; 8], journal_digest: &[u8; 32]) -> [u8; 32] {
+    let mut buf = vec![PRIOR_TAG];
+    for word in image_id {
+        buf.extend_from_slice(&word.to_le_bytes());
+    }
+    buf.extend_from_slice(journal_digest);
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// Verify the prior tally and check that `input` continues it; returns its digest.
+pub fn verify_prior_tally(prior: &PriorTally, input: &VoteTallyInput) -> [u8; 32] {
+    env::verify(prior.image_id, &prior.journal).unwrap();
+
+    if prior.journal.len() % 4 != 0 {
+        panic!("Prior tally journal is not a whole number of words");
+    }
+    let words: Vec<u32> = prior.journal.chunks_exact(4).map(|word| u32::from_le_bytes(word.try_into().unwrap())).collect();
+    if words.first() != Some(&INTERIM_MARKER) {
+        panic!("Prior tally is not an interim tally");
+    }
+    let mut remaining: &[u32] = &words;
+    let previous = InterimTallyOutput::deserialize(&mut Deserializer::new(&mut remaining))
+        .unwrap_or_else(|e| panic!("Prior tally journal does not decode: {}", e));
+
+    if previous.election != input.election {
+        panic!("Prior tally belongs to another election");
+    }
+    if previous.identity_root != identity_root(&input.identity_roster) {
+        panic!("Prior tally was tallied against another identity roster");
+    }
+    if previous.election_key_fingerprint != input.election_public_key.fingerprint() {
+        panic!("Prior tally is encrypted under another election key");
+    }
+    // Nothing the prior tally covered may have been dropped or rewritten since
+    if !has_prefix_head(&input.audit_log, &previous.audit_log_head) {
+        panic!("Prior tally's submission log is not a prefix of this one");
+    }
+    prior_tally_digest(&prior.image_id, &(*Impl::hash_bytes(&prior.journal)).into())
+}
@@ -0,0 +1,146 @@
+// Ballot authorization checks (guest side).
+// A ballot is only tallied if it carries either a blind Schnorr signature from the
+// registrar on its credential token, or a membership proof against the identity
+// roster with a fresh nullifier. Each token or nullifier authorizes one ballot.
+//
+// Every hash here runs on the zkVM's SHA-256 accelerator: challenges and the
+// nullifier base are reduced from 64 bytes made of two SHA-256 digests
+// (`wide_hash`) instead of one SHA-512, which the guest would run in software.
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use risc0_zkvm::sha::{Impl, Sha256};
+
+use crate::ballot_set::merkle_root;
+use crate::types::{BallotAuthorization, MembershipProof, SchnorrSignature, VoterCredential};
+
+// Domain separation tag for roster leaves (must match host implementation)
+const IDENTITY_TAG: u8 = 0x02;
+
+/// s'*G == R' + H(R', X, token)*X (must match host `verify_credential`)
+pub fn verify_credential(credential: &VoterCredential, registrar_public_key: &[u8; 32]) -> bool {
+    let commitment = CompressedRistretto(credential.signature.commitment);
+    let (Some(x), Some(r)) = (CompressedRistretto(*registrar_public_key).decompress(), commitment.decompress()) else {
+        return false;
+    };
+    let Some(response) = Option::<Scalar>::from(Scalar::from_canonical_bytes(credential.signature.response)) else {
+        return false;
+    };
+
+    let mut buf = b"fhe-voting/registrar".to_vec();
+    buf.extend_from_slice(commitment.as_bytes());
+    buf.extend_from_slice(registrar_public_key);
+    buf.extend_from_slice(&credential.token);
+    let challenge = Scalar::from_bytes_mod_order_wide(&wide_hash(&buf));
+
+    response * RISTRETTO_BASEPOINT_POINT == r + challenge * x
+}
+
+/// Schnorr signature by the credential's ballot key (must match host `verify_ballot_signature`)
+pub fn verify_ballot_signature(credential: &VoterCredential, signature: &SchnorrSignature, message: &[u8; 32]) -> bool {
+    let commitment = CompressedRistretto(signature.commitment);
+    let (Some(p), Some(r)) = (CompressedRistretto(credential.token).decompress(), commitment.decompress()) else {
+        return false;
+    };
+    let Some(response) = Option::<Scalar>::from(Scalar::from_canonical_bytes(signature.response)) else {
+        return false;
+    };
+
+    let mut buf = b"fhe-voting/ballot-signature".to_vec();
+    buf.extend_from_slice(commitment.as_bytes());
+    buf.extend_from_slice(&credential.token);
+    buf.extend_from_slice(message);
+    let challenge = Scalar::from_bytes_mod_order_wide(&wide_hash(&buf));
+
+    response * RISTRETTO_BASEPOINT_POINT == r + challenge * p
+}
+
+/// H(0x00 || data) || H(0x01 || data) (must match host `wide_hash`)
+pub fn wide_hash(data: &[u8]) -> [u8; 64] {
+    let mut wide = [0u8; 64];
+    let mut buf = Vec::with_capacity(data.len() + 1);
+    for (counter, half) in wide.chunks_exact_mut(32).enumerate() {
+        buf.clear();
+        buf.push(counter as u8);
+        buf.extend_from_slice(data);
+        half.copy_from_slice(Impl::hash_bytes(&buf).as_bytes());
+    }
+    wide
+}
+
+pub fn identity_root(roster: &[[u8; 32]]) -> [u8; 32] {
+    merkle_root(
+        roster
+            .iter()
+            .map(|commitment| {
+                let mut buf = vec![IDENTITY_TAG];
+                buf.extend_from_slice(commitment);
+                (*Impl::hash_bytes(&buf)).into()
+            })
+            .collect(),
+    )
+}
+
+/// One-out-of-many Chaum-Pedersen check (must match host `verify_membership`).
+/// `members` is the decompressed roster and `election_domain` the election's
+/// `ballot_set::election_domain`, both computed once per tally.
+pub fn verify_membership(
+    proof: &MembershipProof,
+    members: &[RistrettoPoint],
+    identity_root: &[u8; 32],
+    election_domain: &[u8; 32],
+    message: &[u8; 32],
+) -> bool {
+    if proof.challenges.len() != members.len() || proof.responses.len() != members.len() {
+        return false;
+    }
+    let Some(nullifier) = CompressedRistretto(proof.nullifier).decompress() else {
+        return false;
+    };
+    let scalars = |bytes: &[[u8; 32]]| -> Option<Vec<Scalar>> {
+        bytes.iter().map(|b| Option::<Scalar>::from(Scalar::from_canonical_bytes(*b))).collect()
+    };
+    let (Some(challenges), Some(responses)) = (scalars(&proof.challenges), scalars(&proof.responses)) else {
+        return false;
+    };
+
+    // H_e: per-election base point, so nullifiers do not link across elections
+    let mut h_buf = b"fhe-voting/nullifier".to_vec();
+    h_buf.extend_from_slice(election_domain);
+    h_buf.extend_from_slice(identity_root);
+    let h = RistrettoPoint::from_uniform_bytes(&wide_hash(&h_buf));
+
+    let mut buf = b"fhe-voting/membership".to_vec();
+    buf.extend_from_slice(identity_root);
+    buf.extend_from_slice(&proof.nullifier);
+    buf.extend_from_slice(message);
+    for (y, (c, s)) in members.iter().zip(challenges.iter().zip(&responses)) {
+        buf.extend_from_slice((s * RISTRETTO_BASEPOINT_POINT - c * y).compress().as_bytes());
+        buf.extend_from_slice((s * h - c * nullifier).compress().as_bytes());
+    }
+    let total = Scalar::from_bytes_mod_order_wide(&wide_hash(&buf));
+
+    challenges.iter().sum::<Scalar>() == total
+}
+
+/// The voter key (credential token or nullifier) behind an authorization over
+/// `message`, or `None` if it does not check out.
+pub fn authorized_key(
+    authorization: &BallotAuthorization,
+    registrar_public_key: &[u8; 32],
+    members: &[RistrettoPoint],
+    identity_root: &[u8; 32],
+    election_domain: &[u8; 32],
+    message: &[u8; 32],
+) -> Option<[u8; 32]> {
+    match authorization {
+        BallotAuthorization::Credential { credential, ballot_signature } => {
+            (verify_credential(credential, registrar_public_key) && verify_ballot_signature(credential, ballot_signature, message))
+                .then_some(credential.token)
+        }
+        BallotAuthorization::Membership(proof) => {
+            verify_membership(proof, members, identity_root, election_domain, message).then_some(proof.nullifier)
+        }
+    }
+}
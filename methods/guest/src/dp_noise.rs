@@ -0,0 +1,144 @@
+// Differentially private noise on published counts, shared by the guest and
+// the host (which includes this file by path, to reproduce the guest's noise).
+// With `dp_noise` in the tally input the guest publishes each count plus
+// discrete Laplace noise, the difference of two geometric draws with
+// parameter exp(-epsilon / sensitivity), clamped at zero. The sensitivity is
+// the most one ballot can add to a count: 1, or the largest weight under a
+// weighted scheme. The published total is the sum of the noised counts, never
+// the exact sum. The encrypted tallies committed for the trustees stay exact,
+// so the binding result is still the trustees' decryption.
+//
+//   uniform(i, d) = (top 53 bits of H(tag || seed || release || i || d) + 1) / 2^53
+//   geometric     = floor(-ln(uniform) * sensitivity / epsilon)
+//   noise(i)      = geometric(i, 0) - geometric(i, 1)
+//
+// The seed is a private input: it is never committed, and whoever holds it can
+// remove the noise. It should be fixed before the tally (with the election,
+// say), or an operator could re-prove until the noise suits them. `release` is
+// the ballot set root the journal commits, so two releases over different
+// ballots draw independent noise and subtracting them does not cancel it,
+// while re-proving the same ballots gives the same counts again. `ln` uses
+// basic IEEE operations only, so the host's noise matches the guest's bit for
+// bit.
+
+use risc0_zkvm::sha::{Impl, Sha256};
+
+use crate::scheme::TallyScheme;
+use crate::types::DpNoise;
+
+// Domain separation tag for the noise draws
+const NOISE_TAG: u8 = 0x17;
+
+/// The most one ballot can add to any count under `scheme`.
+pub fn sensitivity(scheme: &TallyScheme) -> u64 {
+    match scheme {
+        TallyScheme::Weighted(weights) => weights.iter().map(|entry| entry.weight).max().unwrap_or(1),
+        TallyScheme::Plurality | TallyScheme::Approval => 1,
+    }
+}
+
+/// Noise for candidate `index` in the release committing ballot set root `release`.
+pub fn noise(config: &DpNoise, sensitivity: u64, release: &[u8; 32], index: usize) -> i64 {
+    let scale = sensitivity as f64 * 1000.0 / config.epsilon_milli.max(1) as f64;
+    geometric(config, release, index, 0, scale) - geometric(config, release, index, 1, scale)
+}
+
+/// Candidate `index`'s count as published.
+pub fn noised(config: &DpNoise, sensitivity: u64, release: &[u8; 32], index: usize, count: u32) -> u32 {
+    (count as i64 + noise(config, sensitivity, release, index)).clamp(0, u32::MAX as i64) as u32
+}
+
+/// Every count as published.
+pub fn noised_counts<const N: usize>(config: &DpNoise, sensitivity: u64, release: &[u8; 32], counts: [u32; N]) -> [u32; N] {
+    std::array::from_fn(|index| noised(config, sensitivity, release, index, counts[index]))
+}
+
+fn geometric(config: &DpNoise, release: &[u8; 32], index: usize, draw: u8, scale: f64) -> i64 {
+    let mut buf = vec![NOISE_TAG];
+    buf.extend_from_slice(&config.seed);
+    buf.extend_from_slice(release);
+    buf.extend_from_slice(&(index as u32).to_le_bytes());
+    buf.push(draw);
+    let digest = Impl::hash_bytes(&buf);
+    let mut word = [0u8; 8];
+    word.copy_from_slice(&digest.as_bytes()[..8]);
+    // In (0, 1], so the logarithm is finite
+    let uniform = ((u64::from_le_bytes(word) >> 11) + 1) as f64 / (1u64 << 53) as f64;
+    (-ln(uniform) * scale).max(0.0).floor() as i64
+}
+
+// ln(m * 2^e) = 2 atanh((m - 1) / (m + 1)) + e ln 2, for positive normal x
+fn ln(x: f64) -> f64 {
+    let bits = x.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i64 - 1022;
+    let mantissa = f64::from_bits((bits & 0x000f_ffff_ffff_ffff) | 0x3fe0_0000_0000_0000); // In [0.5, 1)
+    let t = (mantissa - 1.0) / (mantissa + 1.0);
+    let t2 = t * t;
+    let mut term = t;
+    let mut sum = 0.0;
+    for k in 0..30 {
+        sum += term / (2 * k + 1) as f64;
+        term *= t2;
+    }
+    2.0 * sum + exponent as f64 * std::f64::consts::LN_2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheme::VoterWeight;
+
+    #[test]
+    fn test_noise_is_reproducible_and_calibrated() {
+        for x in [1e-16, 0.001, 0.3, 0.5, 0.75, 1.0] {
+            assert!((ln(x) - x.ln()).abs() < 1e-12);
+        }
+
+        let config = DpNoise { epsilon_milli: 500, seed: [7u8; 32] };
+        let release = [3u8; 32];
+        assert_eq!(noised_counts(&config, 1, &release, [40, 30, 20]), noised_counts(&config, 1, &release, [40, 30, 20]));
+        assert_ne!(noise(&config, 1, &release, 0), noise(&DpNoise { seed: [8u8; 32], ..config }, 1, &release, 0));
+        assert_eq!(noised(&config, 1, &release, 0, 0), noise(&config, 1, &release, 0).max(0) as u32);
+
+        // Discrete Laplace with epsilon 0.5: mean 0, mean absolute value 2q / (1 - q^2) ~ 1.92
+        let draws: Vec<i64> = (0..4000u32)
+            .map(|i| {
+                let mut seed = [0u8; 32];
+                seed[..4].copy_from_slice(&i.to_le_bytes());
+                noise(&DpNoise { epsilon_milli: 500, seed }, 1, &[0u8; 32], 0)
+            })
+            .collect();
+        let mean = draws.iter().sum::<i64>() as f64 / draws.len() as f64;
+        let mean_abs = draws.iter().map(|d| d.abs()).sum::<i64>() as f64 / draws.len() as f64;
+        assert!(mean.abs() < 0.2, "mean {}", mean);
+        assert!((mean_abs - 1.92).abs() < 0.2, "mean absolute noise {}", mean_abs);
+
+        let weighted = TallyScheme::weighted(vec![VoterWeight { voter_address: "a".to_string(), weight: 5 }, VoterWeight { voter_address: "b".to_string(), weight: 9 }]);
+        assert_eq!((sensitivity(&weighted), sensitivity(&TallyScheme::Approval)), (9, 1));
+    }
+
+    #[test]
+    fn test_releases_draw_independent_noise() {
+        // Under one seed, the noise of releases over different ballot sets is
+        // uncorrelated, so the difference of two releases keeps noise of its own
+        let config = DpNoise { epsilon_milli: 500, seed: [7u8; 32] };
+        let pairs: Vec<(i64, i64)> = (0..4000u32)
+            .map(|i| {
+                let mut first = [0u8; 32];
+                first[..4].copy_from_slice(&i.to_le_bytes());
+                let mut second = first;
+                second[31] = 1;
+                (noise(&config, 1, &first, 0), noise(&config, 1, &second, 0))
+            })
+            .collect();
+        let n = pairs.len() as f64;
+        let (mean_a, mean_b) = (pairs.iter().map(|p| p.0).sum::<i64>() as f64 / n, pairs.iter().map(|p| p.1).sum::<i64>() as f64 / n);
+        let covariance = pairs.iter().map(|&(a, b)| (a as f64 - mean_a) * (b as f64 - mean_b)).sum::<f64>() / n;
+        let variance = pairs.iter().map(|&(a, _)| (a as f64 - mean_a).powi(2)).sum::<f64>() / n;
+        assert!((covariance / variance).abs() < 0.1, "correlation {}", covariance / variance);
+        assert!(pairs.iter().filter(|(a, b)| a != b).count() > pairs.len() / 2);
+
+        // The same release gives the same noise, so re-proving reveals nothing new
+        assert_eq!(noise(&config, 1, &[3u8; 32], 2), noise(&config, 1, &[3u8; 32], 2));
+    }
+}
@@ -0,0 +1,142 @@
+// Pluggable FHE backends.
+// `FheBackend` is the small surface an additive tally needs: key generation,
+// encryption, homomorphic addition and (de)serialization, plus decryption for
+// the demo's decrypt-inside-guest mode. `PureRustFheRuntime` implements it
+// everywhere; with the `tfhe` cargo feature, TFHE-rs does too, on targets it
+// supports (natively; its key sizes and bootstrapping are far beyond a zkVM
+// guest today). `DefaultBackend` is the backend the feature selects.
+//
+// The tally program still decodes host ciphertexts of the pure Rust scheme
+// directly, so nothing in the guest binary is generic over the backend yet.
+#![allow(dead_code)]
+
+use crate::pure_rust_fhe::{Cipher, FheError, PrivateKey, PublicKey, PureRustFheRuntime, Signed};
+
+pub trait FheBackend {
+    type PublicKey;
+    type PrivateKey;
+    type Ciphertext;
+
+    fn generate_keys(&mut self) -> (Self::PublicKey, Self::PrivateKey);
+    fn encrypt(&self, value: i64, public_key: &Self::PublicKey) -> Result<Self::Ciphertext, FheError>;
+    /// `sum += term`, homomorphically.
+    fn add_assign(&self, sum: &mut Self::Ciphertext, term: &Self::Ciphertext);
+    fn serialize(&self, ciphertext: &Self::Ciphertext) -> Vec<u8>;
+    fn deserialize(&self, data: &[u8]) -> Result<Self::Ciphertext, FheError>;
+    fn decrypt(&self, ciphertext: &Self::Ciphertext, private_key: &Self::PrivateKey) -> Result<i64, FheError>;
+}
+
+#[cfg(not(feature = "tfhe"))]
+pub type DefaultBackend = PureRustFheRuntime;
+#[cfg(feature = "tfhe")]
+pub type DefaultBackend = tfhe_backend::TfheBackend;
+
+impl FheBackend for PureRustFheRuntime {
+    type PublicKey = PublicKey;
+    type PrivateKey = PrivateKey;
+    type Ciphertext = Cipher<Signed>;
+
+    fn generate_keys(&mut self) -> (PublicKey, PrivateKey) {
+        PureRustFheRuntime::generate_keys(self)
+    }
+
+    fn encrypt(&self, value: i64, public_key: &PublicKey) -> Result<Cipher<Signed>, FheError> {
+        PureRustFheRuntime::encrypt(self, Signed::from(value), public_key)
+    }
+
+    fn add_assign(&self, sum: &mut Cipher<Signed>, term: &Cipher<Signed>) {
+        *sum += term;
+    }
+
+    fn serialize(&self, ciphertext: &Cipher<Signed>) -> Vec<u8> {
+        ciphertext.serialize()
+    }
+
+    fn deserialize(&self, data: &[u8]) -> Result<Cipher<Signed>, FheError> {
+        self.deserialize_ciphertext(data)
+    }
+
+    fn decrypt(&self, ciphertext: &Cipher<Signed>, private_key: &PrivateKey) -> Result<i64, FheError> {
+        PureRustFheRuntime::decrypt(self, ciphertext, private_key).map(|plaintext| plaintext.val)
+    }
+}
+
+#[cfg(feature = "tfhe")]
+pub mod tfhe_backend {
+    use tfhe::prelude::*;
+    use tfhe::{ClientKey, ConfigBuilder, FheUint32};
+
+    use super::FheBackend;
+    use crate::pure_rust_fhe::FheError;
+
+    /// TFHE-rs over 32-bit unsigned integers. Key generation installs the
+    /// server key for the calling thread, which homomorphic addition needs.
+    #[derive(Default)]
+    pub struct TfheBackend;
+
+    impl TfheBackend {
+        pub fn new() -> Self {
+            TfheBackend
+        }
+    }
+
+    impl FheBackend for TfheBackend {
+        type PublicKey = tfhe::PublicKey;
+        type PrivateKey = ClientKey;
+        type Ciphertext = FheUint32;
+
+        fn generate_keys(&mut self) -> (tfhe::PublicKey, ClientKey) {
+            let (client_key, server_key) = tfhe::generate_keys(ConfigBuilder::default().build());
+            tfhe::set_server_key(server_key);
+            (tfhe::PublicKey::new(&client_key), client_key)
+        }
+
+        fn encrypt(&self, value: i64, public_key: &tfhe::PublicKey) -> Result<FheUint32, FheError> {
+            let value = u32::try_from(value).map_err(|_| FheError::EncryptionFailed {
+                reason: format!("{} does not fit in an FheUint32", value),
+            })?;
+            FheUint32::try_encrypt(value, public_key)
+                .map_err(|e| FheError::EncryptionFailed { reason: e.to_string() })
+        }
+
+        fn add_assign(&self, sum: &mut FheUint32, term: &FheUint32) {
+            *sum += term;
+        }
+
+        fn serialize(&self, ciphertext: &FheUint32) -> Vec<u8> {
+            bincode::serialize(ciphertext).expect("FheUint32 serialization is infallible")
+        }
+
+        fn deserialize(&self, data: &[u8]) -> Result<FheUint32, FheError> {
+            bincode::deserialize(data).map_err(|_| FheError::InvalidByteSlice)
+        }
+
+        fn decrypt(&self, ciphertext: &FheUint32, private_key: &ClientKey) -> Result<i64, FheError> {
+            let value: u32 = ciphertext.decrypt(private_key);
+            Ok(value as i64)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Generic, so the trait methods are exercised rather than inherent ones
+    fn tally_through_serialization<B: FheBackend>(backend: &mut B, votes: &[i64]) -> Result<i64, FheError> {
+        let (public_key, private_key) = backend.generate_keys();
+        let mut sum = backend.encrypt(0, &public_key)?;
+        for &vote in votes {
+            let ballot = backend.encrypt(vote, &public_key)?;
+            let received = backend.deserialize(&backend.serialize(&ballot))?;
+            backend.add_assign(&mut sum, &received);
+        }
+        backend.decrypt(&sum, &private_key)
+    }
+
+    #[test]
+    fn test_default_backend_tallies_through_serialization() -> Result<(), FheError> {
+        assert_eq!(tally_through_serialization(&mut DefaultBackend::new(), &[1, 0, 1, 1])?, 3);
+        Ok(())
+    }
+}
@@ -0,0 +1,110 @@
+// Streamed journal output (guest side).
+// Large results are committed after `VoteTallyOutput` as a sequence of frames,
+// one `env::commit` each, so they are never held in one serialized struct.
+// Every frame carries a hash chained over the frames before it.
+//
+// The compact profile commits a fixed-width summary instead (layout in
+// `compact_journal`, mirrored by the host's reader). Its frames are written to
+// the host's stdout rather than the journal; only their chain head is
+// committed, so the host can still check them.
+
+use risc0_zkvm::guest::env;
+use risc0_zkvm::sha::{Impl, Sha256};
+
+use crate::scheme::TieBreak;
+use crate::types::{JournalFrame, VoteTallyOutput};
+
+// Domain separation tag (must match host implementation)
+const FRAME_TAG: u8 = 0x04;
+
+pub fn chain_frame(previous: &[u8; 32], index: u32, payload: &[u8]) -> [u8; 32] {
+    let mut buf = vec![FRAME_TAG];
+    buf.extend_from_slice(previous);
+    buf.extend_from_slice(&index.to_le_bytes());
+    buf.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    buf.extend_from_slice(payload);
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+pub struct FrameWriter {
+    head: [u8; 32],
+    next_index: u32,
+    detached: bool,
+}
+
+impl FrameWriter {
+    pub fn new() -> Self {
+        FrameWriter { head: [0u8; 32], next_index: 0, detached: false }
+    }
+
+    /// Frames go to the host's stdout; commit `head()` to bind them.
+    pub fn detached() -> Self {
+        FrameWriter { detached: true, ..Self::new() }
+    }
+
+    pub fn head(&self) -> [u8; 32] {
+        self.head
+    }
+
+    pub fn commit(&mut self, payload: Vec<u8>) {
+        self.head = chain_frame(&self.head, self.next_index, &payload);
+        let frame = JournalFrame { index: self.next_index, payload, chunk_hash: self.head };
+        if self.detached {
+            env::write(&frame);
+        } else {
+            env::commit(&frame);
+        }
+        self.next_index += 1;
+    }
+}
+
+// Compact journal, all little-endian:
+//   0 option1_count u32 | 4 option2_count u32 | 8 option3_count u32 | 12 total_votes u32
+//  16 superseded_ballots u32 | 20 encrypted_tally_frames u32 | 24 close_block u64
+//  32 ballot_set_root | 64 identity_root | 96 audit_log_head | 128 close block hash
+// 160 tally frames head | 192 prior tally digest | 224 EIP-712 result digest
+// 256 election id | 288 chain id u64 | 296 closes at u64 | 304 candidates digest
+// 336 registered voters u32 | 340 submissions u32 | 344 rejected submissions u32
+// 348 revoked ballots u32 | 352 ballots tallied u32 | 356 turnout basis points u32
+// 360 registry root | 392 leading mask u32 | 396 winner u32
+// 400 tie-break rule u32 (0 declare, 1 first listed, 2 lot) | 404 lot seed
+// 436 rejected u32 | 440 duplicates u32 | 444 invalid signatures u32
+// 448 rejections by reason, nine u32 in RejectionReason code order
+// 484 scheme digest | 516 trustees digest
+// An absent chain anchor is a zero close block and hash. The counts sit where
+// they do in the full journal, so the governance contract reads either.
+pub fn compact_journal(output: &VoteTallyOutput, frames_head: &[u8; 32]) -> Vec<u8> {
+    let (close_block, close_block_hash) = output.chain_anchor.map_or((0, [0u8; 32]), |anchor| (anchor.close_block, anchor.block_hash));
+    let mut bytes = Vec::with_capacity(548);
+    for word in [output.option1_count, output.option2_count, output.option3_count, output.total_votes, output.superseded_ballots, output.encrypted_tally_frames] {
+        bytes.extend_from_slice(&word.to_le_bytes());
+    }
+    bytes.extend_from_slice(&close_block.to_le_bytes());
+    for commitment in [&output.ballot_set_root, &output.identity_root, &output.audit_log_head, &close_block_hash, frames_head, &output.prior_tally_digest, &output.result_typed_digest] {
+        bytes.extend_from_slice(commitment);
+    }
+    bytes.extend_from_slice(output.election.election_id.as_bytes());
+    bytes.extend_from_slice(&output.election.chain_id.to_le_bytes());
+    bytes.extend_from_slice(&output.election.closes_at.to_le_bytes());
+    bytes.extend_from_slice(output.election.candidates_digest.as_bytes());
+    let turnout = &output.turnout;
+    for word in [turnout.electorate.registered_voters, turnout.submissions, turnout.rejected_submissions, turnout.revoked_ballots, turnout.ballots_tallied, turnout.turnout_basis_points] {
+        bytes.extend_from_slice(&word.to_le_bytes());
+    }
+    bytes.extend_from_slice(&turnout.electorate.registry_root);
+    let (rule, seed) = match output.outcome.tie_break {
+        TieBreak::Declare => (0u32, [0u8; 32]),
+        TieBreak::FirstListed => (1, [0u8; 32]),
+        TieBreak::Lot(seed) => (2, seed),
+    };
+    for word in [output.outcome.leading, output.outcome.winner, rule] {
+        bytes.extend_from_slice(&word.to_le_bytes());
+    }
+    bytes.extend_from_slice(&seed);
+    for word in [output.rejected_count, output.duplicate_count, output.invalid_signature_count].into_iter().chain(output.rejections_by_reason) {
+        bytes.extend_from_slice(&word.to_le_bytes());
+    }
+    bytes.extend_from_slice(output.election.scheme_digest.as_bytes());
+    bytes.extend_from_slice(&output.trustees_digest);
+    bytes
+}
@@ -0,0 +1,73 @@
+// Ethereum-compatible hashing inside the zkVM.
+// Keccak-256 comes from tiny-keccak, patched in Cargo.toml to RISC Zero's fork,
+// which runs the permutation on the zkVM's Keccak accelerator. That lets the
+// proof compute what Ethereum computes: voter addresses from public keys, and
+// EIP-712 digests a contract or wallet can check without trusting the host.
+//
+// The result is typed as
+//
+//   TallyResult(uint32 option1,uint32 option2,uint32 option3,uint32 totalVotes,
+//               bytes32 ballotSetRoot,bytes32 auditLogHead)
+//
+// under the domain { name: "FHE Voting", version: "1", chainId,
+// verifyingContract } (must match host implementation).
+
+use tiny_keccak::{Hasher, Keccak};
+
+use crate::types::{Eip712Domain, VoteTallyOutput};
+
+const DOMAIN_TYPE: &[u8] = b"EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+const RESULT_TYPE: &[u8] = b"TallyResult(uint32 option1,uint32 option2,uint32 option3,uint32 totalVotes,bytes32 ballotSetRoot,bytes32 auditLogHead)";
+const DOMAIN_NAME: &[u8] = b"FHE Voting";
+const DOMAIN_VERSION: &[u8] = b"1";
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    hasher.update(data);
+    let mut digest = [0u8; 32];
+    hasher.finalize(&mut digest);
+    digest
+}
+
+/// Ethereum address of an uncompressed secp256k1 key (x || y, without the 0x04 prefix).
+#[allow(dead_code)] // For ballots authorized by Ethereum accounts
+pub fn address_from_public_key(public_key: &[u8; 64]) -> [u8; 20] {
+    keccak256(public_key)[12..].try_into().unwrap()
+}
+
+/// ABI encoding of an unsigned integer: 32 bytes, big-endian.
+fn abi_uint(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+pub fn domain_separator(domain: &Eip712Domain) -> [u8; 32] {
+    let mut address = [0u8; 32];
+    address[12..].copy_from_slice(&domain.verifying_contract);
+    let mut buf = Vec::with_capacity(5 * 32);
+    for word in [keccak256(DOMAIN_TYPE), keccak256(DOMAIN_NAME), keccak256(DOMAIN_VERSION), abi_uint(domain.chain_id), address] {
+        buf.extend_from_slice(&word);
+    }
+    keccak256(&buf)
+}
+
+/// keccak256(0x19 || 0x01 || domain separator || struct hash)
+pub fn typed_data_digest(domain: &Eip712Domain, struct_hash: &[u8; 32]) -> [u8; 32] {
+    let mut buf = vec![0x19, 0x01];
+    buf.extend_from_slice(&domain_separator(domain));
+    buf.extend_from_slice(struct_hash);
+    keccak256(&buf)
+}
+
+/// EIP-712 digest of the result's counts and commitments.
+pub fn result_typed_digest(domain: &Eip712Domain, output: &VoteTallyOutput) -> [u8; 32] {
+    let mut buf = Vec::with_capacity(7 * 32);
+    buf.extend_from_slice(&keccak256(RESULT_TYPE));
+    for count in [output.option1_count, output.option2_count, output.option3_count, output.total_votes] {
+        buf.extend_from_slice(&abi_uint(count as u64));
+    }
+    buf.extend_from_slice(&output.ballot_set_root);
+    buf.extend_from_slice(&output.audit_log_head);
+    typed_data_digest(domain, &keccak256(&buf))
+}
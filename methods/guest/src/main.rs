@@ -1,10 +1,32 @@
+extern crate alloc; // scheme.rs is shared with the alloc-only verifier
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use curve25519_dalek::ristretto::CompressedRistretto;
 use risc0_zkvm::guest::env;
+use risc0_zkvm::sha::{Impl, Sha256};
 
 mod types;
 mod pure_rust_fhe;
+mod noise_distribution;
+mod ballot_set;
+mod credentials;
+mod audit_log;
+mod journal;
+mod fhe_backend;
+mod continuity;
+mod keccak;
+mod candidates;
+mod address;
+mod scheme;
+mod dp_noise;
+mod trustee_shares;
+mod mixnet;
 
-use types::{VoteTallyInput, VoteTallyOutput, VoteOption};
-use pure_rust_fhe::{PureRustFheRuntime, Signed};
+use types::{BallotAuthorization, Digest32, InterimTallyOutput, JournalProfile, Outcome, RejectionReason, Turnout, VoteTallyInput, VoteTallyOutput, VoteOption, INTERIM_MARKER, REJECTION_REASONS};
+use pure_rust_fhe::{max_tally_ballots, Cipher, PureRustFheRuntime, Signed, TallyAccumulator};
+use scheme::{Accumulator, BallotEntry, VoteScheme};
 
 fn main() {
     eprintln!("🔒 [zkVM Guest] Starting REAL FHE voting computation...");
@@ -23,45 +45,164 @@ fn main() {
     
     eprintln!("📊 [zkVM Guest] Processing {} encrypted vote vectors", input.encrypted_votes.len());
     
+    // Bind the proof to the exact ballot set the host committed to
+    let ballot_set_root = ballot_set::compute_ballot_set_root(&input.encrypted_votes);
+    if ballot_set_root != input.ballot_set_root {
+        panic!("Ballot set commitment mismatch: input root does not match submitted ballots");
+    }
+    eprintln!("🌳 [zkVM Guest] Ballot set root verified");
+    
+    // The tallied ballots must be exactly those accepted in the published submission log
+    if audit_log::compute_audit_log_head(&input.audit_log) != input.audit_log_head {
+        panic!("Audit log head mismatch: records do not hash to the published head");
+    }
+    if !audit_log::accepted_records_match(&input.audit_log, &input.encrypted_votes) {
+        panic!("Audit log mismatch: accepted submissions differ from the tallied ballots");
+    }
+    eprintln!("📜 [zkVM Guest] Audit log verified ({} submissions)", input.audit_log.len());
+    if let Some(anchor) = &input.chain_anchor {
+        eprintln!("⛓️  [zkVM Guest] Ballot set read from chain at close block {}", anchor.close_block);
+    }
+    // A result typed for one chain must not claim another
+    if let Some(domain) = &input.eip712_domain {
+        if domain.chain_id != input.election.chain_id {
+            panic!("EIP-712 domain chain {} differs from the election's chain {}", domain.chain_id, input.election.chain_id);
+        }
+    }
+    eprintln!("🗳️  [zkVM Guest] Election {} on chain {}, closed at {}", input.election.election_id, input.election.chain_id, input.election.closes_at);
+    // The committed context names the ballot text the counts belong to
+    if candidates::candidates_digest(&input.candidates) != input.election.candidates_digest {
+        panic!("Candidate list does not hash to the election's candidates digest");
+    }
+    if !input.candidates.is_empty() && !candidates::covers_options(&input.candidates) {
+        panic!("Candidate list must name options 1, 2 and 3 in order");
+    }
+    // Weights are found by binary search, so the table must be strictly ascending
+    if let Err(e) = input.scheme.check() {
+        panic!("Invalid voting scheme: {}", e);
+    }
+    // The committed context names the voting rule and weights the ballots were counted under
+    if input.election.scheme_digest != input.scheme.digest() {
+        panic!("Voting scheme does not hash to the election's scheme digest");
+    }
+    
+    // Incremental proving: this proof attests that it continues the prior interim tally
+    let prior_tally_digest = match &input.prior_tally {
+        Some(prior) => {
+            let digest = continuity::verify_prior_tally(prior, &input);
+            eprintln!("🔗 [zkVM Guest] Continues the prior interim tally");
+            digest
+        }
+        None => [0u8; 32],
+    };
+    
+    let journal_profile = input.journal_profile;
+    let interim = input.interim;
+    let election_key_fingerprint = input.election_public_key.fingerprint();
+    let ballots = input.encrypted_votes.len() as u32;
+    // An aggregator checks no voter has ballots in two batches against this
+    let voter_keys_digest = if interim { ballot_set::voter_keys_digest(&ballot_set::voter_keys(&input.encrypted_votes)) } else { [0u8; 32] };
+    
     // Perform REAL FHE vote tallying
-    let result = tally_encrypted_votes_with_fhe(input);
+    let (result, encrypted_tallies, counted_weight) = tally_encrypted_votes_with_fhe(input, prior_tally_digest);
     
     eprintln!("✅ [zkVM Guest] REAL FHE computation completed");
     eprintln!("📈 [zkVM Guest] Results: {} total votes", result.total_votes);
     
+    // Interim tallies publish only the encrypted tallies; the counts stay sealed until the final tally
+    if interim {
+        env::commit(&InterimTallyOutput {
+            marker: INTERIM_MARKER,
+            ballots,
+            superseded_ballots: result.superseded_ballots,
+            counted_weight,
+            election: result.election,
+            ballot_set_root: result.ballot_set_root,
+            identity_root: result.identity_root,
+            election_key_fingerprint,
+            audit_log_head: result.audit_log_head,
+            voter_keys_digest,
+            prior_tally_digest: result.prior_tally_digest,
+            encrypted_tally_frames: result.encrypted_tally_frames,
+        });
+        let mut frames = journal::FrameWriter::new();
+        for tally in &encrypted_tallies {
+            frames.commit(tally.serialize());
+        }
+        eprintln!("🎯 [zkVM Guest] Interim tally of {} ballots committed to proof!", ballots);
+        return;
+    }
+    
     // Commit the result - this is what gets proven
-    env::commit(&result);
+    match journal_profile {
+        JournalProfile::Full => {
+            env::commit(&result);
+            
+            // Stream the encrypted tallies as framed chunks so trustees can decrypt them with verifiable shares
+            let mut frames = journal::FrameWriter::new();
+            for tally in &encrypted_tallies {
+                frames.commit(tally.serialize());
+            }
+        }
+        JournalProfile::Compact => {
+            // Smallest calldata for on-chain verification: the frames leave out of band, bound by their head
+            let mut frames = journal::FrameWriter::detached();
+            for tally in &encrypted_tallies {
+                frames.commit(tally.serialize());
+            }
+            env::commit_slice(&journal::compact_journal(&result, &frames.head()));
+        }
+    }
     
     eprintln!("🎯 [zkVM Guest] Result committed to proof!");
 }
 
+// Ballots reach the voting scheme as serialized ciphertexts: only their shape can be checked
+impl BallotEntry for Vec<u8> {}
+
+impl Accumulator<Cipher<Signed>> for TallyAccumulator {
+    fn add_weighted(&mut self, entry: &Cipher<Signed>, weight: u64) {
+        if weight == 1 {
+            self.add(entry);
+        } else {
+            self.add(&entry.mul_plain(weight));
+        }
+    }
+}
+
 // REAL FHE tallying function that runs inside the zkVM
 // This performs actual homomorphic encryption operations
-fn tally_encrypted_votes_with_fhe(input: VoteTallyInput) -> VoteTallyOutput {
+fn tally_encrypted_votes_with_fhe(input: VoteTallyInput, prior_tally_digest: [u8; 32]) -> (VoteTallyOutput, Vec<Cipher<Signed>>, u64) {
     eprintln!("⚙️  [zkVM Guest] Initializing FHE runtime inside zkVM...");
     
-    let mut fhe_runtime = PureRustFheRuntime::new();
-    let (public_key, private_key) = fhe_runtime.generate_keys();
+    // The initial tallies' noise is bound to this election and the tally it continues
+    let noise_seed = *Impl::hash_bytes(&[input.election.election_id.as_bytes().as_slice(), &prior_tally_digest].concat());
+    let fhe_runtime = PureRustFheRuntime::with_noise_seed(noise_seed.into());
+    
+    // Tallies accumulate under the trustees' combined election key (from the DKG)
+    let public_key = &input.election_public_key;
+    // Ballots must be bound to this key; ciphertexts under any other key are rejected
+    let election_key_fingerprint = public_key.fingerprint();
     
-    eprintln!("🔑 [zkVM Guest] FHE keys generated inside secure enclave");
+    eprintln!("🔑 [zkVM Guest] Using combined election public key from trustee DKG");
     
     // Initialize encrypted tallies as actual FHE ciphertexts of zero
     let zero_plaintext = Signed::from(0);
-    let mut tally_option1 = match fhe_runtime.encrypt(zero_plaintext, &public_key) {
+    let tally_option1 = match fhe_runtime.encrypt(zero_plaintext, public_key) {
         Ok(cipher) => cipher,
         Err(e) => {
             eprintln!("❌ [zkVM Guest] Failed to encrypt initial tally for option1: {:?}", e);
             panic!("Critical FHE error: Cannot initialize tally ciphertexts");
         }
     };
-    let mut tally_option2 = match fhe_runtime.encrypt(zero_plaintext, &public_key) {
+    let tally_option2 = match fhe_runtime.encrypt(zero_plaintext, public_key) {
         Ok(cipher) => cipher,
         Err(e) => {
             eprintln!("❌ [zkVM Guest] Failed to encrypt initial tally for option2: {:?}", e);
             panic!("Critical FHE error: Cannot initialize tally ciphertexts");
         }
     };
-    let mut tally_option3 = match fhe_runtime.encrypt(zero_plaintext, &public_key) {
+    let tally_option3 = match fhe_runtime.encrypt(zero_plaintext, public_key) {
         Ok(cipher) => cipher,
         Err(e) => {
             eprintln!("❌ [zkVM Guest] Failed to encrypt initial tally for option3: {:?}", e);
@@ -71,125 +212,393 @@ fn tally_encrypted_votes_with_fhe(input: VoteTallyInput) -> VoteTallyOutput {
     
     eprintln!("📊 [zkVM Guest] Performing REAL homomorphic addition on encrypted votes...");
     
+    // Reduction mod q is deferred to the end of the addition chain
+    let mut tallies = [tally_option1, tally_option2, tally_option3].map(TallyAccumulator::new);
+    
+    // Every ballot is deserialized into these buffers, so the loop does not allocate
+    let mut ballot_ciphers = [Cipher::zeroed(), Cipher::zeroed(), Cipher::zeroed()];
+    
+    // Re-voting: each voter's valid ballots, latest first; the latest whose ciphertexts
+    // deserialize is counted, so a malformed later ballot cannot cancel an earlier vote
+    let (voters, identity_root, mut rejections) = select_latest_ballots(&input);
+    
+    // Refuse to tally if any candidate's count could wrap modulo the plaintext modulus; a voter's
+    // ballots may carry different weights, so the heaviest bounds what the voter adds
+    let counted_weight: u64 = voters.iter().filter_map(|ballots| ballots.iter().map(|ballot| ballot.weight).max()).sum();
+    if counted_weight > max_tally_ballots() {
+        panic!("Tally overflow: {} voters of total weight up to {} could exceed the plaintext modulus (at most {} can be tallied)",
+               voters.len(), counted_weight, max_tally_ballots());
+    }
+    
     // PRIVACY FIX: Rick Weber @ Sunscreen.tech feedback
     // Process encrypted vote vectors - server cannot see individual choices
-    for (i, encrypted_vote) in input.encrypted_votes.iter().enumerate() {
-        eprintln!("  Processing encrypted vote vector {}: {} -> PRIVATE", 
-                  i + 1, encrypted_vote.voter_address);
-        eprintln!("    [zkVM cannot see vote choice - only encrypted vector]");
-        
-        // Process the encrypted vote vector: [encrypt(1|0), encrypt(1|0), encrypt(1|0)]
-        // In real system, these would already be FHE ciphertexts
-        // For now, we'll simulate by converting the "encrypted" data to FHE ciphertexts
-        
-        // Validate vote vector structure
-        const EXPECTED_CANDIDATES: usize = 3;
-        const MAX_CIPHERTEXT_SIZE: usize = 1024; // Reasonable limit for each ciphertext
-        
-        if encrypted_vote.encrypted_vote_vector.len() != EXPECTED_CANDIDATES {
-            eprintln!("    ❌ Invalid vote vector length: expected {}, got {}", 
-                     EXPECTED_CANDIDATES, encrypted_vote.encrypted_vote_vector.len());
-            continue;
-        }
-        
-        // Validate each ciphertext size to prevent memory exhaustion
-        let mut valid_vote = true;
-        for (idx, ciphertext_bytes) in encrypted_vote.encrypted_vote_vector.iter().enumerate() {
-            if ciphertext_bytes.len() > MAX_CIPHERTEXT_SIZE {
-                eprintln!("    ❌ Ciphertext {} too large: {} bytes (max: {})", 
-                         idx, ciphertext_bytes.len(), MAX_CIPHERTEXT_SIZE);
-                valid_vote = false;
-                break;
+    let mut tallied_ballots = 0u32;
+    let mut superseded_ballots = 0u32;
+    // Weight of each tallied ballot, by position in the ballot set
+    let mut weights: Vec<Option<u64>> = vec![None; input.encrypted_votes.len()];
+    for ballots in &voters {
+        let mut ballots = ballots.iter();
+        for ballot in ballots.by_ref() {
+            let encrypted_vote = &input.encrypted_votes[ballot.index];
+            eprintln!("  Processing encrypted vote vector {}: {} -> PRIVATE", 
+                      ballot.index + 1, encrypted_vote.voter_address);
+            eprintln!("    [zkVM cannot see vote choice - only encrypted vector]");
+            
+            // REAL FHE DESERIALIZATION: the whole vote vector is validated and decoded in one pass
+            if let Err(e) = fhe_runtime.deserialize_vote_vector(&encrypted_vote.encrypted_vote_vector, &election_key_fingerprint, &mut ballot_ciphers) {
+                eprintln!("    ❌ Failed to deserialize encrypted vote vector: {:?}; trying the voter's previous ballot", e);
+                rejections[RejectionReason::Malformed as usize] += 1;
+                continue;
+            }
+            
+            // A mixed election tallies the re-encrypted vote vectors below instead
+            if input.mix.is_none() {
+                input.scheme.accumulate(&mut tallies, &ballot_ciphers, ballot.weight);
             }
+            tallied_ballots += 1;
+            weights[ballot.index] = Some(ballot.weight);
+            eprintln!("    ✅ Homomorphic addition completed for every option with weight {} (real FHE)", ballot.weight);
+            break;
         }
-        
-        if !valid_vote {
-            continue;
+        // The voter's remaining ballots are older than the one counted
+        superseded_ballots += ballots.count() as u32;
+    }
+    eprintln!("🔁 [zkVM Guest] {} ballots counted, {} superseded by a later ballot",
+              tallied_ballots, superseded_ballots);
+    
+    // Mixnet: the host shuffled and re-encrypted the vote vectors, so the order they are added
+    // in says nothing about who cast them. The shuffle is proven against the authenticated
+    // ballots counted above (each voter's latest, in ballot set order), which must all count at
+    // unit weight since the mix hides which one is which
+    if let Some(mix) = &input.mix {
+        if weights.iter().flatten().any(|&weight| weight != 1) {
+            panic!("A ballot mix needs every ballot counted at unit weight");
         }
-        
-        // Convert each element of the vote vector to FHE ciphertext and add to tallies
-        for (candidate_idx, encrypted_value_bytes) in encrypted_vote.encrypted_vote_vector.iter().enumerate() {
-            // REAL FHE DESERIALIZATION: Convert client-encrypted ciphertext to our format
-            let encrypted_vote_cipher = match fhe_runtime.deserialize_ciphertext(encrypted_value_bytes) {
-                Ok(cipher) => cipher,
-                Err(e) => {
-                    eprintln!("    ❌ Failed to deserialize encrypted vote for candidate {}: {:?}", candidate_idx, e);
-                    continue; // Skip this invalid vote and continue processing
-                }
+        let submitted: Vec<&[Vec<u8>]> = input.encrypted_votes.iter().zip(&weights)
+            .filter(|(_, weight)| weight.is_some())
+            .map(|(vote, _)| vote.encrypted_vote_vector.as_slice())
+            .collect();
+        if let Err(e) = mixnet::verify_shuffle(&fhe_runtime, &submitted, &mix.ballots, &mix.proof, public_key) {
+            panic!("Invalid ballot mix: {}", e);
+        }
+        for (position, ballot) in mix.ballots.iter().enumerate() {
+            if fhe_runtime.deserialize_vote_vector(ballot, &election_key_fingerprint, &mut ballot_ciphers).is_err() {
+                panic!("Mixed ballot {} does not deserialize", position + 1);
+            }
+            input.scheme.accumulate(&mut tallies, &ballot_ciphers, 1);
+        }
+        eprintln!("🔀 [zkVM Guest] Shuffle proof of {} rounds checked; {} mixed ballots tallied", mix.proof.rounds.len(), mix.ballots.len());
+    }
+    
+    // Revocations: a voter takes back a tallied ballot, named by its tracking code, with the
+    // same key that authorized it; its ciphertexts are subtracted before anything is decrypted
+    let mut revoked: Vec<[u8; 32]> = Vec::new();
+    if !input.revocations.is_empty() {
+        let tracking_codes: Vec<[u8; 32]> = input.encrypted_votes.iter().map(ballot_set::ballot_leaf_hash).collect();
+        let members: Vec<_> = input.identity_roster.iter().filter_map(|bytes| CompressedRistretto(*bytes).decompress()).collect();
+        let election_domain = ballot_set::election_domain(&input.election.election_id);
+        for (r, revocation) in input.revocations.iter().enumerate() {
+            let Some(index) = tracking_codes.iter().position(|code| *code == revocation.tracking_code) else {
+                eprintln!("    ❌ Revocation {}: no ballot with that tracking code, ignored", r + 1);
+                continue;
             };
+            if weights[index].is_none() || revoked.contains(&revocation.tracking_code) {
+                eprintln!("    ❌ Revocation {}: ballot {} is not counted or already revoked, ignored", r + 1, index + 1);
+                continue;
+            }
+            let ballot = &input.encrypted_votes[index];
+            let ballot_key = ballot_set::voter_key(&ballot.authorization);
+            let message = ballot_set::revocation_message(&input.election.election_id, &revocation.tracking_code);
+            let key = credentials::authorized_key(&revocation.authorization, &input.registrar_public_key, &members, &identity_root, &election_domain, &message);
+            if key != Some(ballot_key) {
+                eprintln!("    ❌ Revocation {}: not authorized by the ballot's voter, ignored", r + 1);
+                continue;
+            }
             
-            match candidate_idx {
-                0 => {
-                    tally_option1 = tally_option1 + encrypted_vote_cipher;
-                    eprintln!("    ✅ Homomorphic addition completed for Option1 (real FHE)");
-                },
-                1 => {
-                    tally_option2 = tally_option2 + encrypted_vote_cipher;
-                    eprintln!("    ✅ Homomorphic addition completed for Option2 (real FHE)");
-                },
-                2 => {
-                    tally_option3 = tally_option3 + encrypted_vote_cipher;
-                    eprintln!("    ✅ Homomorphic addition completed for Option3 (real FHE)");
-                },
-                _ => eprintln!("    ❌ Invalid candidate index"),
+            // Tallied, so it deserializes again and has a weight
+            if fhe_runtime.deserialize_vote_vector(&ballot.encrypted_vote_vector, &election_key_fingerprint, &mut ballot_ciphers).is_err() {
+                panic!("Counted ballot {} no longer deserializes", index + 1);
+            }
+            let weight = weights[index].unwrap_or_else(|| panic!("Counted ballot {} has no weight", index + 1));
+            for (tally, cipher) in tallies.iter_mut().zip(&ballot_ciphers) {
+                if weight == 1 {
+                    tally.sub(cipher);
+                } else {
+                    tally.sub(&cipher.mul_plain(weight));
+                }
             }
+            tallied_ballots -= 1;
+            revoked.push(revocation.tracking_code);
+            eprintln!("    ↩️  Ballot {} revoked by its voter: subtracted homomorphically", index + 1);
         }
     }
+    // The committed audit log names the revoked ballots, so nobody can be revoked off the record
+    if revoked != audit_log::revoked_records(&input.audit_log) {
+        panic!("Revocations applied do not match the revocations in the audit log");
+    }
     
-    eprintln!("🔓 [zkVM Guest] Decrypting final FHE tallies with private key...");
+    let encrypted_tallies = tallies.map(TallyAccumulator::finish);
     
-    // REAL FHE decryption (only possible with private key inside secure zkVM)
-    let option1_plaintext = match fhe_runtime.decrypt(&tally_option1, &private_key) {
-        Ok(plaintext) => plaintext,
-        Err(e) => {
-            eprintln!("❌ [zkVM Guest] Failed to decrypt option1 tally: {:?}", e);
-            panic!("Critical FHE error: Cannot decrypt final tallies");
-        }
+    // Minimum anonymity set: with too few ballots the counts would give voters away, so
+    // only the turnout is revealed and the tallies are neither decrypted nor committed
+    // (the trustees could decrypt committed tallies)
+    let withheld = !input.interim && tallied_ballots < input.min_anonymity_set;
+    // The guest holds no key: final counts are decrypted with the trustees' shares, each
+    // checked against its trustee's committed key share, and interim tallies stay sealed
+    let decryption = if withheld || input.interim {
+        None
+    } else {
+        Some(input.decryption.as_ref().unwrap_or_else(|| panic!("Final tally needs the trustees' decryption shares")))
     };
-    let option2_plaintext = match fhe_runtime.decrypt(&tally_option2, &private_key) {
-        Ok(plaintext) => plaintext,
-        Err(e) => {
-            eprintln!("❌ [zkVM Guest] Failed to decrypt option2 tally: {:?}", e);
-            panic!("Critical FHE error: Cannot decrypt final tallies");
+    let [option1_count, option2_count, option3_count] = match decryption {
+        None => {
+            if withheld {
+                eprintln!("🙈 [zkVM Guest] {} ballots tallied, fewer than the anonymity set of {}: counts withheld",
+                          tallied_ballots, input.min_anonymity_set);
+            }
+            [0; 3]
+        }
+        Some(decryption) => {
+            if decryption.shares.len() != encrypted_tallies.len() {
+                panic!("Expected decryption shares for {} tallies, got {}", encrypted_tallies.len(), decryption.shares.len());
+            }
+            eprintln!("🔓 [zkVM Guest] Decrypting final FHE tallies with {}-of-{} trustee shares...",
+                      decryption.threshold, decryption.verification_keys.len());
+            let mut counts = [0u32; 3];
+            for (position, ((tally, shares), count)) in encrypted_tallies.iter().zip(&decryption.shares).zip(&mut counts).enumerate() {
+                let key_term = trustee_shares::combine(&tally.serialize(), shares, decryption)
+                    .unwrap_or_else(|e| panic!("Cannot decrypt tally {}: {}", position + 1, e));
+                *count = fhe_runtime.decrypt_combined(tally, key_term).val as u32;
+            }
+            counts
         }
     };
-    let option3_plaintext = match fhe_runtime.decrypt(&tally_option3, &private_key) {
-        Ok(plaintext) => plaintext,
-        Err(e) => {
-            eprintln!("❌ [zkVM Guest] Failed to decrypt option3 tally: {:?}", e);
-            panic!("Critical FHE error: Cannot decrypt final tallies");
+    // Names the trustees whose shares the counts rest on
+    let trustees_digest = decryption.map_or([0u8; 32], trustee_shares::trustees_digest);
+    let encrypted_tallies = if withheld { Vec::new() } else { encrypted_tallies.to_vec() };
+    
+    // Turnout against the registered voters: a claimed electorate smaller than the ballots
+    // tallied, or than the identity roster, is false and fails the proof
+    let electorate = input.electorate.unwrap_or_default();
+    if input.electorate.is_some() {
+        if tallied_ballots > electorate.registered_voters {
+            panic!("{} ballots tallied from {} registered voters", tallied_ballots, electorate.registered_voters);
+        }
+        if input.identity_roster.len() as u64 > u64::from(electorate.registered_voters) {
+            panic!("Identity roster of {} is larger than the {} registered voters", input.identity_roster.len(), electorate.registered_voters);
         }
+    }
+    let rejected_records = input.audit_log.iter().filter(|record| !record.accepted).count() as u32;
+    let turnout = Turnout {
+        electorate,
+        submissions: input.audit_log.len() as u32 - revoked.len() as u32,
+        rejected_submissions: rejected_records - revoked.len() as u32,
+        revoked_ballots: revoked.len() as u32,
+        ballots_tallied: tallied_ballots,
+        turnout_basis_points: electorate.turnout_basis_points(tallied_ballots),
     };
+    if input.electorate.is_some() {
+        eprintln!("🗳️  [zkVM Guest] Turnout: {} of {} registered voters ({}.{:02}%)", tallied_ballots, electorate.registered_voters,
+                  turnout.turnout_basis_points / 100, turnout.turnout_basis_points % 100);
+    }
     
-    let option1_count = option1_plaintext.val as u32;
-    let option2_count = option2_plaintext.val as u32;
-    let option3_count = option3_plaintext.val as u32;
-    let total_votes = option1_count + option2_count + option3_count;
+    // Published counts carry differentially private noise; the committed encrypted tallies stay exact
+    let [option1_count, option2_count, option3_count] = match &input.dp_noise {
+        Some(config) if !withheld => {
+            eprintln!("🎲 [zkVM Guest] Adding differentially private noise (epsilon {}/1000) to the published counts", config.epsilon_milli);
+            dp_noise::noised_counts(config, dp_noise::sensitivity(&input.scheme), &input.ballot_set_root, [option1_count, option2_count, option3_count])
+        }
+        _ => [option1_count, option2_count, option3_count],
+    };
+    // A withheld result still says how many ballots were tallied; otherwise the total is the sum
+    // of the published counts, so an exact sum beside noised counts cannot undo the noise
+    let total_votes = if withheld { tallied_ballots } else { option1_count + option2_count + option3_count };
     
     // Create a cryptographic hash of the computation for verification
     let computation_hash = create_computation_hash(option1_count, option2_count, option3_count);
     
     eprintln!("📊 [zkVM Guest] Final FHE decrypted counts: {} | {} | {}", 
               option1_count, option2_count, option3_count);
+    // The winner is decided here, from the published counts, under the tie-break fixed in the
+    // input, so no consumer reads a different result into the counts (withheld counts have none)
+    let outcome = Outcome::decide(&input.scheme, &[option1_count, option2_count, option3_count], input.election.tie_break);
+    match outcome.winner {
+        0 if outcome.leading.count_ones() > 1 => eprintln!("⚖️  [zkVM Guest] Tie between options with mask {:#05b}, declared", outcome.leading),
+        0 => eprintln!("🏆 [zkVM Guest] No winner"),
+        winner if outcome.leading.count_ones() > 1 => eprintln!("🏆 [zkVM Guest] Option {} wins the tie ({:?})", winner, input.election.tie_break),
+        winner => eprintln!("🏆 [zkVM Guest] Option {} wins", winner),
+    }
     eprintln!("🎯 [zkVM Guest] REAL homomorphic operations completed successfully!");
     
-    VoteTallyOutput {
+    let mut output = VoteTallyOutput {
         option1_count,
         option2_count,
         option3_count,
         total_votes,
+        election: input.election,
         computation_hash,
+        ballot_set_root: input.ballot_set_root,
+        identity_root,
+        superseded_ballots,
+        turnout,
+        outcome,
+        rejected_count: rejections.iter().sum(),
+        duplicate_count: count_rejections(&rejections, RejectionReason::is_duplicate),
+        invalid_signature_count: count_rejections(&rejections, RejectionReason::is_invalid_signature),
+        rejections_by_reason: rejections,
+        audit_log_head: input.audit_log_head,
+        chain_anchor: input.chain_anchor,
+        prior_tally_digest,
+        result_typed_digest: [0u8; 32],
+        trustees_digest,
+        encrypted_tally_frames: encrypted_tallies.len() as u32,
+    };
+    // Typed for the consuming contract, so a signer or verifier can check the result with Keccak alone
+    if let Some(domain) = &input.eip712_domain {
+        output.result_typed_digest = keccak::result_typed_digest(domain, &output);
+        eprintln!("✍️  [zkVM Guest] EIP-712 result digest computed for chain {}", domain.chain_id);
     }
+    (output, encrypted_tallies, counted_weight)
 }
 
 
-// Note: Removed simulation helper - now using real FHE deserialization
+// One of a voter's valid ballots
+struct VoterBallot {
+    sequence: u64,
+    index: usize, // Position in the ballot set
+    weight: u64,
+}
 
-fn create_computation_hash(count1: u32, count2: u32, count3: u32) -> String {
-    // Create a deterministic hash of the computation for verification
-    let combined = (count1 as u64) << 32 | (count2 as u64) << 16 | (count3 as u64);
+// Validate every ballot (structure, election, authorization, nonce and voting
+// scheme) and group the valid ones by voter, latest first, in order of each
+// voter's first ballot. Only the ciphertexts are left to check: the tally counts
+// the first of a voter's ballots that deserializes. Also returns the identity
+// roster root and the ballots excluded per `RejectionReason` code.
+fn select_latest_ballots(input: &VoteTallyInput) -> (Vec<Vec<VoterBallot>>, [u8; 32], [u32; REJECTION_REASONS]) {
+    // Validate vote vector structure
+    const EXPECTED_CANDIDATES: usize = 3;
+    const MAX_CIPHERTEXT_SIZE: usize = 1024; // Reasonable limit for each ciphertext
+    
+    let identity_root = credentials::identity_root(&input.identity_roster);
+    let election_domain = ballot_set::election_domain(&input.election.election_id);
+    let roster_members = input
+        .identity_roster
+        .iter()
+        .map(|bytes| CompressedRistretto(*bytes).decompress())
+        .collect::<Option<Vec<_>>>()
+        .unwrap_or_else(|| panic!("Identity roster contains a malformed commitment"));
+    
+    // Voter key (credential token or identity nullifier) -> position in `voters`
+    let mut positions: HashMap<[u8; 32], usize> = HashMap::new();
+    let mut voters: Vec<Vec<VoterBallot>> = Vec::new();
+    let mut nonces: HashSet<([u8; 32], [u8; 32])> = HashSet::new();
+    let mut rejections = [0u32; REJECTION_REASONS];
+    
+    for (i, encrypted_vote) in input.encrypted_votes.iter().enumerate() {
+        if encrypted_vote.encrypted_vote_vector.len() != EXPECTED_CANDIDATES {
+            eprintln!("    ❌ Ballot {}: invalid vote vector length: expected {}, got {}", 
+                     i + 1, EXPECTED_CANDIDATES, encrypted_vote.encrypted_vote_vector.len());
+            rejections[RejectionReason::Malformed as usize] += 1;
+            continue;
+        }
+        
+        // Validate each ciphertext size to prevent memory exhaustion
+        if let Some((idx, ciphertext_bytes)) = encrypted_vote.encrypted_vote_vector.iter().enumerate()
+            .find(|(_, bytes)| bytes.len() > MAX_CIPHERTEXT_SIZE) {
+            eprintln!("    ❌ Ballot {}: ciphertext {} too large: {} bytes (max: {})", 
+                     i + 1, idx, ciphertext_bytes.len(), MAX_CIPHERTEXT_SIZE);
+            rejections[RejectionReason::Malformed as usize] += 1;
+            continue;
+        }
+        
+        // A ballot captured from another election is refused before its authorization is checked
+        if encrypted_vote.election_id != input.election.election_id {
+            eprintln!("    ❌ Ballot {}: cast in election {}, rejected", i + 1, encrypted_vote.election_id);
+            rejections[RejectionReason::WrongElection as usize] += 1;
+            continue;
+        }
+        
+        // The host normalizes addresses at ingestion, so a non-canonical one was never checked there
+        if !address::is_canonical_address(&encrypted_vote.voter_address) {
+            eprintln!("    ❌ Ballot {}: voter address is not a checksummed address, rejected", i + 1);
+            rejections[RejectionReason::NonCanonicalAddress as usize] += 1;
+            continue;
+        }
+        
+        // Eligibility is proven anonymously: neither check reveals who cast the ballot
+        let message = ballot_set::ballot_message(&encrypted_vote.election_id, &encrypted_vote.voter_address, &encrypted_vote.nonce, &encrypted_vote.encrypted_vote_vector, encrypted_vote.sequence);
+        let voter_key = match &encrypted_vote.authorization {
+            BallotAuthorization::Credential { credential, ballot_signature } => {
+                if !credentials::verify_credential(credential, &input.registrar_public_key) {
+                    eprintln!("    ❌ Ballot {}: invalid registrar credential, rejected", i + 1);
+                    rejections[RejectionReason::InvalidCredential as usize] += 1;
+                    continue;
+                }
+                if !credentials::verify_ballot_signature(credential, ballot_signature, &message) {
+                    eprintln!("    ❌ Ballot {}: not signed by the credential holder, rejected", i + 1);
+                    rejections[RejectionReason::InvalidSignature as usize] += 1;
+                    continue;
+                }
+                credential.token
+            }
+            BallotAuthorization::Membership(proof) => {
+                if !credentials::verify_membership(proof, &roster_members, &identity_root, &election_domain, &message) {
+                    eprintln!("    ❌ Ballot {}: invalid membership proof, rejected", i + 1);
+                    rejections[RejectionReason::InvalidMembership as usize] += 1;
+                    continue;
+                }
+                proof.nullifier
+            }
+        };
+        
+        // Each of a voter's ballots carries a fresh nonce
+        if !nonces.insert((voter_key, encrypted_vote.nonce)) {
+            eprintln!("    ❌ Ballot {}: nonce already used by this voter, rejected", i + 1);
+            rejections[RejectionReason::DuplicateNonce as usize] += 1;
+            continue;
+        }
+        
+        // The election's voting scheme checks the ballot's shape and gives its weight
+        let weight = match input.scheme.validate_ballot(&encrypted_vote.voter_address, &encrypted_vote.encrypted_vote_vector, EXPECTED_CANDIDATES) {
+            Ok(weight) => weight,
+            Err(e) => {
+                eprintln!("    ❌ Ballot {}: rejected by the voting scheme: {}", i + 1, e);
+                rejections[RejectionReason::SchemeRejected as usize] += 1;
+                continue;
+            }
+        };
+        
+        let position = *positions.entry(voter_key).or_insert_with(|| {
+            voters.push(Vec::new());
+            voters.len() - 1
+        });
+        let ballots = &mut voters[position];
+        if ballots.iter().any(|ballot| ballot.sequence == encrypted_vote.sequence) {
+            eprintln!("    ❌ Ballot {}: sequence {} already used by this voter, rejected", i + 1, encrypted_vote.sequence);
+            rejections[RejectionReason::DuplicateSequence as usize] += 1;
+            continue;
+        }
+        ballots.push(VoterBallot { sequence: encrypted_vote.sequence, index: i, weight });
+    }
     
-    // Simple hash function (in real implementation, use proper crypto hash)
-    let hash = combined.wrapping_mul(0x9e3779b97f4a7c15);
-    format!("{:016x}", hash)
+    for ballots in &mut voters {
+        ballots.sort_unstable_by_key(|ballot| Reverse(ballot.sequence));
+    }
+    (voters, identity_root, rejections)
+}
+
+// Note: Removed simulation helper - now using real FHE deserialization
+
+// Excluded ballots over the reasons `matches` selects
+fn count_rejections(rejections: &[u32; REJECTION_REASONS], matches: fn(RejectionReason) -> bool) -> u32 {
+    RejectionReason::ALL.into_iter().filter(|&reason| matches(reason)).map(|reason| rejections[reason as usize]).sum()
+}
+
+fn create_computation_hash(count1: u32, count2: u32, count3: u32) -> Digest32 {
+    // SHA-256 of the counts on the zkVM accelerator
+    let mut buf = Vec::with_capacity(12);
+    for count in [count1, count2, count3] {
+        buf.extend_from_slice(&count.to_le_bytes());
+    }
+    Digest32((*Impl::hash_bytes(&buf)).into())
 }
@@ -0,0 +1,240 @@
+// Mixnet shuffle check (guest side, must match host `mixnet::verify_shuffle`).
+// With a `BallotMix` in the input, the host has permuted and re-randomized the
+// submitted vote vectors. The guest checks the cut-and-choose shuffle proof
+// against the vote vectors it authenticated and tallies the mixed ciphertexts in
+// their place, so the order it adds them in no longer follows the voters'.
+//
+// Each round opens one shadow shuffle towards the input or the output, as the
+// Fiat-Shamir challenge bit says: the permutation must be one, and every target
+// minus its source must be an encryption of zero under the election key with
+// the opened mask u, (Δ·(b*u mod t) + e, a*u mod t). Its noise e must stay below
+// `REENCRYPTION_NOISE_BOUND` (or, towards the output, a difference of two such
+// encryptions, within ±(`REENCRYPTION_NOISE_BOUND` + q mod t)).
+
+use risc0_zkvm::sha::{Impl, Sha256};
+use thiserror::Error;
+
+use crate::pure_rust_fhe::{public_polynomial, Cipher, FheError, PublicKey, PureRustFheRuntime, CIPHERTEXT_MODULUS, PLAINTEXT_MODULUS};
+use crate::types::{ShadowOpening, ShuffleProof};
+
+/// Fewest shadow shuffles accepted (soundness error 2^-40, must match host `SHUFFLE_PROOF_ROUNDS`).
+pub const SHUFFLE_PROOF_ROUNDS: usize = 40;
+
+// Noise bound of one re-encryption (must match host implementation)
+const REENCRYPTION_NOISE_BOUND: u64 = PLAINTEXT_MODULUS / 16;
+
+// Coefficients of every ciphertext in a vote vector
+type Randomizer = Vec<Vec<u64>>;
+
+#[derive(Error, Debug)]
+pub enum MixError {
+    #[error("Invalid ballot ciphertext: {0}")]
+    Ciphertext(#[from] FheError),
+    #[error("Vote vectors have inconsistent shapes")]
+    ShapeMismatch,
+    #[error("Shuffle proof has {actual} rounds, expected at least {expected}")]
+    TooFewRounds { expected: usize, actual: usize },
+    #[error("Shuffle proof round {round} failed: {reason}")]
+    RoundFailed { round: usize, reason: &'static str },
+}
+
+/// Check that `output` is a re-randomized permutation of `input`, every vote
+/// vector bound to and re-encrypted under the election key `public_key`.
+pub fn verify_shuffle<B: AsRef<[Vec<u8>]>>(
+    fhe_runtime: &PureRustFheRuntime,
+    input: &[B],
+    output: &[Vec<Vec<u8>>],
+    proof: &ShuffleProof,
+    public_key: &PublicKey,
+) -> Result<(), MixError> {
+    if proof.rounds.len() < SHUFFLE_PROOF_ROUNDS {
+        return Err(MixError::TooFewRounds { expected: SHUFFLE_PROOF_ROUNDS, actual: proof.rounds.len() });
+    }
+    let key_fingerprint = &public_key.fingerprint();
+    let zero_encryption = ZeroEncryption { fhe_runtime, public_key, public_polynomial: public_polynomial() };
+    let input_coeffs = decode_all(fhe_runtime, input, key_fingerprint)?;
+    let output_coeffs = decode_all(fhe_runtime, output, key_fingerprint)?;
+    if input_coeffs.len() != output_coeffs.len() || input_coeffs.first().map(Vec::len) != output_coeffs.first().map(Vec::len) {
+        return Err(MixError::ShapeMismatch);
+    }
+
+    let mut statement = b"fhe-voting/mixnet/shuffle".to_vec();
+    hash_ballots(&mut statement, input);
+    hash_ballots(&mut statement, output);
+    for round in &proof.rounds {
+        hash_ballots(&mut statement, &round.shadow);
+    }
+    let seed: [u8; 32] = (*Impl::hash_bytes(&statement)).into();
+
+    for (round, proof_round) in proof.rounds.iter().enumerate() {
+        let fail = |reason| MixError::RoundFailed { round, reason };
+        let shadow_coeffs = decode_all(fhe_runtime, &proof_round.shadow, key_fingerprint)?;
+        let open_to_output = challenge_bit(&seed, round);
+
+        let (from, to, permutation, masks) = match (&proof_round.opening, open_to_output) {
+            (ShadowOpening::FromInput { permutation, randomizers }, false) => (&input_coeffs, &shadow_coeffs, permutation, randomizers),
+            (ShadowOpening::ToOutput { permutation, randomizers }, true) => (&shadow_coeffs, &output_coeffs, permutation, randomizers),
+            _ => return Err(fail("opening does not match the challenge")),
+        };
+
+        if !is_permutation(permutation, from.len()) || masks.len() != from.len() {
+            return Err(fail("not a permutation"));
+        }
+        for ((&source, target), masks) in permutation.iter().zip(to).zip(masks) {
+            let source = &from[source as usize];
+            if source.len() != target.len() || masks.len() != target.len() {
+                return Err(fail("re-encryption does not match"));
+            }
+            for ((source, target), mask) in source.iter().zip(target).zip(masks) {
+                zero_encryption.check(source, target, mask, open_to_output).map_err(fail)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// Checks target - source against the encryption of zero with an opened mask
+struct ZeroEncryption<'a> {
+    fhe_runtime: &'a PureRustFheRuntime,
+    public_key: &'a PublicKey,
+    public_polynomial: Vec<u64>,
+}
+
+impl ZeroEncryption<'_> {
+    // target - source must be (Δ·(b*u mod t) + e, a*u mod t) with e below
+    // `REENCRYPTION_NOISE_BOUND`, or with `difference` the difference of two such
+    // encryptions, whose c1 stays within ±t and whose noise may carry r = q mod t
+    // from the wrap of (b*u1 mod t) - (b*u2 mod t) (must match host `check_reencryption`)
+    fn check(&self, source: &[u64], target: &[u64], u: &[u64], difference: bool) -> Result<(), &'static str> {
+        let degree = self.public_polynomial.len();
+        if source.len() != 2 * degree || target.len() != source.len() || u.len() != degree || u.iter().any(|&u| u >= PLAINTEXT_MODULUS) {
+            return Err("re-encryption does not match");
+        }
+        let scaling_factor = CIPHERTEXT_MODULUS / PLAINTEXT_MODULUS;
+        let noise_bound = if difference { REENCRYPTION_NOISE_BOUND + CIPHERTEXT_MODULUS % PLAINTEXT_MODULUS } else { REENCRYPTION_NOISE_BOUND };
+        let masks = self.fhe_runtime.multiply_plaintext_polynomials(self.public_key.coefficients(), u);
+        let c1_masks = self.fhe_runtime.multiply_plaintext_polynomials(&self.public_polynomial, u);
+        for i in 0..degree {
+            let noise = centered_mod_q(target[i].wrapping_sub(source[i]).wrapping_sub(masks[i] * scaling_factor));
+            if noise.unsigned_abs() >= noise_bound || (!difference && noise < 0) {
+                return Err("re-encryption noise out of bounds");
+            }
+            let c1 = centered_mod_q(target[degree + i].wrapping_sub(source[degree + i]));
+            let c1_ok = if difference {
+                c1.unsigned_abs() < PLAINTEXT_MODULUS && c1.rem_euclid(PLAINTEXT_MODULUS as i64) as u64 == c1_masks[i]
+            } else {
+                c1 == c1_masks[i] as i64
+            };
+            if !c1_ok {
+                return Err("re-encryption does not match");
+            }
+        }
+        Ok(())
+    }
+}
+
+// c mod q read as the integer in -q/2..q/2 it stands for
+fn centered_mod_q(c: u64) -> i64 {
+    let c = c & (CIPHERTEXT_MODULUS - 1);
+    if c >= CIPHERTEXT_MODULUS / 2 { c as i64 - CIPHERTEXT_MODULUS as i64 } else { c as i64 }
+}
+
+fn decode_all<B: AsRef<[Vec<u8>]>>(fhe_runtime: &PureRustFheRuntime, ballots: &[B], key_fingerprint: &[u8; 32]) -> Result<Vec<Randomizer>, MixError> {
+    let decoded = ballots
+        .iter()
+        .map(|ballot| {
+            let ballot = ballot.as_ref();
+            let mut ciphers = vec![Cipher::zeroed(); ballot.len()];
+            fhe_runtime.deserialize_vote_vector(ballot, key_fingerprint, &mut ciphers)?;
+            Ok(ciphers.iter().map(|cipher| cipher.coefficients().to_vec()).collect())
+        })
+        .collect::<Result<Vec<Randomizer>, MixError>>()?;
+
+    if decoded.windows(2).any(|pair| pair[0].len() != pair[1].len()) {
+        return Err(MixError::ShapeMismatch);
+    }
+    Ok(decoded)
+}
+
+fn is_permutation(permutation: &[u32], len: usize) -> bool {
+    let mut seen = vec![false; len];
+    permutation.len() == len
+        && permutation.iter().all(|&i| (i as usize) < len && !std::mem::replace(&mut seen[i as usize], true))
+}
+
+fn hash_ballots<B: AsRef<[Vec<u8>]>>(buf: &mut Vec<u8>, ballots: &[B]) {
+    buf.extend_from_slice(&(ballots.len() as u32).to_le_bytes());
+    for ballot in ballots {
+        let ballot = ballot.as_ref();
+        buf.extend_from_slice(&(ballot.len() as u32).to_le_bytes());
+        for ciphertext in ballot {
+            let digest: [u8; 32] = (*Impl::hash_bytes(ciphertext)).into();
+            buf.extend_from_slice(&digest);
+        }
+    }
+}
+
+// Fiat-Shamir: bit `round` of the hash chain seeded by the whole statement
+fn challenge_bit(seed: &[u8; 32], round: usize) -> bool {
+    let mut block = seed.to_vec();
+    block.extend_from_slice(&((round / 256) as u32).to_le_bytes());
+    let digest: [u8; 32] = (*Impl::hash_bytes(&block)).into();
+    let bit = round % 256;
+    digest[bit / 8] >> (bit % 8) & 1 == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pure_rust_fhe::Signed;
+    use crate::types::ShadowRound;
+
+    // A proof whose every shadow is the input itself, opened as the identity
+    fn identity_proof(input: &[Vec<Vec<u8>>], rounds: usize) -> ShuffleProof {
+        let mut statement = b"fhe-voting/mixnet/shuffle".to_vec();
+        hash_ballots(&mut statement, input);
+        hash_ballots(&mut statement, input);
+        for _ in 0..rounds {
+            hash_ballots(&mut statement, input);
+        }
+        let seed: [u8; 32] = (*Impl::hash_bytes(&statement)).into();
+        let permutation: Vec<u32> = (0..input.len() as u32).collect();
+        let randomizers = vec![vec![vec![0u64; 32]; 3]; input.len()];
+        let rounds = (0..rounds)
+            .map(|round| {
+                let (permutation, randomizers) = (permutation.clone(), randomizers.clone());
+                let opening = if challenge_bit(&seed, round) {
+                    ShadowOpening::ToOutput { permutation, randomizers }
+                } else {
+                    ShadowOpening::FromInput { permutation, randomizers }
+                };
+                ShadowRound { shadow: input.to_vec(), opening }
+            })
+            .collect();
+        ShuffleProof { rounds }
+    }
+
+    #[test]
+    fn test_shuffle_proof_is_checked() -> Result<(), Box<dyn std::error::Error>> {
+        let mut runtime = PureRustFheRuntime::new();
+        let (public_key, _) = runtime.generate_keys();
+        let fingerprint = public_key.fingerprint();
+        let encrypt_vote = |choice: usize| {
+            (0..3)
+                .map(|candidate| Ok(runtime.encrypt(Signed::from((candidate == choice) as i64), &public_key)?.serialize_bound(&fingerprint, false)))
+                .collect::<Result<Vec<_>, FheError>>()
+        };
+        let input = vec![encrypt_vote(0)?, encrypt_vote(2)?];
+        let other = encrypt_vote(1)?;
+
+        let proof = identity_proof(&input, SHUFFLE_PROOF_ROUNDS);
+        verify_shuffle(&runtime, &input, &input, &proof, &public_key)?;
+
+        let mut tampered = input.clone();
+        tampered[0] = other;
+        assert!(matches!(verify_shuffle(&runtime, &input, &tampered, &proof, &public_key), Err(MixError::RoundFailed { .. })));
+        let short = identity_proof(&input, SHUFFLE_PROOF_ROUNDS - 1);
+        assert!(matches!(verify_shuffle(&runtime, &input, &input, &short, &public_key), Err(MixError::TooFewRounds { .. })));
+        Ok(())
+    }
+}
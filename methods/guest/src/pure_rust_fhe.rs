@@ -2,32 +2,243 @@
 // This provides the same API as Sunscreen but works in RISC Zero zkVM
 
 use serde::{Serialize, Deserialize};
-use rand::Rng;
-use rand_distr::{Normal, Distribution};
+use std::cell::Cell;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use risc0_zkvm::sha::{Impl, Sha256};
 use thiserror::Error;
+use zeroize::Zeroize;
+
+pub use crate::noise_distribution::{NoiseDistribution, MAX_BINOMIAL_ETA};
 
 // Enhanced security parameters for BFV scheme
 // Balanced for demonstration with improved security over original
-const PLAINTEXT_MODULUS: u64 = 65537; // Prime modulus for better security
-const CIPHERTEXT_MODULUS: u64 = 288230376151711744; // 2^58 for enhanced security
+pub const PLAINTEXT_MODULUS: u64 = 65537; // Prime modulus for better security
+pub const CIPHERTEXT_MODULUS: u64 = 288230376151711744; // 2^58 for enhanced security
 const POLYNOMIAL_DEGREE: usize = 32; // Increased from 8, but manageable for serde
 
-// Additional security parameters
-const NOISE_STANDARD_DEVIATION: f64 = 3.19; // Optimized for security/correctness balance
-const MAX_NOISE_BOUND: u64 = PLAINTEXT_MODULUS / 16; // Tighter noise bound
+// q is a power of two, so reduction mod q is a mask instead of a division
+const CIPHERTEXT_MASK: u64 = CIPHERTEXT_MODULUS - 1;
+
+// Domain separation tag for per-encryption noise streams
+const ENCRYPTION_NOISE_TAG: u8 = 0x1e;
+
+// Domain separation tag for the public polynomial every key is built on (must match host implementation)
+const PUBLIC_POLYNOMIAL_TAG: u8 = 0x1f;
+
+// Δ = floor(q / t): plaintexts sit in the high bits of c0, noise in the low ones
+const SCALING_FACTOR: u64 = CIPHERTEXT_MODULUS / PLAINTEXT_MODULUS;
+
+/// Largest magnitude of an encoded signed value: plaintexts are centered mod t.
+pub const MAX_PLAINTEXT_MAGNITUDE: i64 = (PLAINTEXT_MODULUS / 2) as i64;
+
+
+/// Nonzero secret key coefficients by default: half of them, as in sparse
+/// ternary BFV secrets.
+pub const DEFAULT_SECRET_HAMMING_WEIGHT: u32 = POLYNOMIAL_DEGREE as u32 / 2;
+
+// Domain separation tag for key fingerprints (must match host implementation)
+const KEY_FINGERPRINT_TAG: u8 = 0x05;
+
+// Ballot ciphertexts are prefixed with the fingerprint of their encryption key
+const KEY_FINGERPRINT_LEN: usize = 32;
+
+// Domain separation tags for the ballot ciphertext header (must match host implementation)
+const PARAMETER_DIGEST_TAG: u8 = 0x0f;
+const CIPHERTEXT_CHECKSUM_TAG: u8 = 0x10;
+
+// A ballot ciphertext is key fingerprint || parameter digest || coefficients,
+// optionally followed by a checksum over everything before it
+const BOUND_HEADER_LEN: usize = KEY_FINGERPRINT_LEN + 32;
+const CIPHERTEXT_LEN: usize = POLYNOMIAL_DEGREE * 2 * 8;
+const CHECKSUM_LEN: usize = 8;
+
+// Vote vectors are one-hot, so a ballot adds at most 1 to any candidate's tally
+pub const MAX_BALLOT_WEIGHT: u64 = 1;
+
+/// Most ballots that can be tallied before a candidate's count could wrap
+/// modulo PLAINTEXT_MODULUS and decrypt to a small, wrong number.
+pub const fn max_tally_ballots() -> u64 {
+    (PLAINTEXT_MODULUS - 1) / MAX_BALLOT_WEIGHT
+}
+
+/// Most contributions of values up to `max_value` whose slot sums cannot wrap
+/// modulo PLAINTEXT_MODULUS (the vector sum guest's capacity).
+#[allow(dead_code)] // Used by the vector sum and auction guests
+pub const fn max_sum_contributions(max_value: u64) -> u64 {
+    (PLAINTEXT_MODULUS - 1) / max_value
+}
 
 #[derive(Error, Debug)]
 pub enum FheError {
     #[error("Invalid ciphertext length: expected {expected}, got {actual}")]
     InvalidCiphertextLength { expected: usize, actual: usize },
+    #[error("Invalid vote vector length: expected {expected}, got {actual}")]
+    InvalidVectorLength { expected: usize, actual: usize },
     #[error("Invalid byte slice conversion")]
     InvalidByteSlice,
+    #[error("Ciphertext is bound to a different public key")]
+    KeyMismatch,
     #[error("Encryption failed: {reason}")]
     EncryptionFailed { reason: String },
     #[error("Decryption failed: {reason}")]
     DecryptionFailed { reason: String },
     #[error("Key generation failed: {reason}")]
     KeyGenerationFailed { reason: String },
+    #[error("Invalid FHE parameters: {reason}")]
+    InvalidParameters { reason: String },
+    #[error("Ciphertext coefficient {index} is not below the ciphertext modulus")]
+    NonCanonicalCoefficient { index: usize },
+    #[error("Ciphertext was encrypted under different scheme parameters")]
+    ParameterMismatch,
+    #[error("Ciphertext checksum does not match its contents")]
+    ChecksumMismatch,
+    #[error("{values} values do not fit in the {max} plaintext coefficients")]
+    PackingOverflow { values: usize, max: usize },
+    #[error("Plaintext value {value} is outside the centered range of the plaintext modulus")]
+    PlaintextOutOfRange { value: i64 },
+}
+
+/// H(tag || t || q || N): the scheme parameters a ballot ciphertext declares
+/// in its header, so one from a client built with other parameters is refused.
+pub fn parameter_digest() -> [u8; 32] {
+    let mut buf = vec![PARAMETER_DIGEST_TAG];
+    buf.extend_from_slice(&PLAINTEXT_MODULUS.to_le_bytes());
+    buf.extend_from_slice(&CIPHERTEXT_MODULUS.to_le_bytes());
+    buf.extend_from_slice(&(POLYNOMIAL_DEGREE as u32).to_le_bytes());
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+// First bytes of H(tag || header || coefficients), an optional integrity check
+fn ciphertext_checksum(bound: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut buf = vec![CIPHERTEXT_CHECKSUM_TAG];
+    buf.extend_from_slice(bound);
+    let digest: [u8; 32] = (*Impl::hash_bytes(&buf)).into();
+    digest[..CHECKSUM_LEN].try_into().expect("checksum is shorter than the digest")
+}
+
+// Index of the first coefficient that is not below q, if any
+fn first_non_canonical(data: &[u8]) -> Option<usize> {
+    let (words, _) = data.as_chunks::<8>();
+    words.iter().position(|&word| u64::from_le_bytes(word) > CIPHERTEXT_MASK)
+}
+
+/// Scheme choices made within the fixed moduli and degree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FheParameters {
+    pub noise: NoiseDistribution,
+    /// Secret key coefficients are ternary, {-1, 0, 1}, with exactly this many nonzero.
+    pub secret_hamming_weight: u32,
+}
+
+impl Default for FheParameters {
+    fn default() -> Self {
+        FheParameters {
+            noise: NoiseDistribution::default(),
+            secret_hamming_weight: DEFAULT_SECRET_HAMMING_WEIGHT,
+        }
+    }
+}
+
+impl FheParameters {
+    pub fn validate(&self) -> Result<(), FheError> {
+        if let NoiseDistribution::CenteredBinomial { eta } = self.noise {
+            if !(1..=MAX_BINOMIAL_ETA).contains(&eta) {
+                return Err(FheError::InvalidParameters {
+                    reason: format!("centered binomial eta {} is not in 1..={}", eta, MAX_BINOMIAL_ETA),
+                });
+            }
+        }
+        if !(1..=POLYNOMIAL_DEGREE as u32).contains(&self.secret_hamming_weight) {
+            return Err(FheError::InvalidParameters {
+                reason: format!("secret Hamming weight {} is not in 1..={}", self.secret_hamming_weight, POLYNOMIAL_DEGREE),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A plaintext polynomial: N coefficients mod t. Built with the runtime's
+/// `encode_slice` or `encode_slots` and read back with the matching decoder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plaintext {
+    coefficients: Vec<u64>,
+}
+
+// Decrypted plaintexts and secret keys are wiped in place before the guest
+// commits, so no copy of either outlives the decryption
+impl Zeroize for Plaintext {
+    fn zeroize(&mut self) {
+        self.coefficients.zeroize();
+    }
+}
+
+impl Plaintext {
+    #[allow(dead_code)]
+    pub fn coefficients(&self) -> &[u64] {
+        &self.coefficients
+    }
+    
+    fn from_signed(values: &[i64]) -> Result<Self, FheError> {
+        if values.len() > POLYNOMIAL_DEGREE {
+            return Err(FheError::PackingOverflow { values: values.len(), max: POLYNOMIAL_DEGREE });
+        }
+        if let Some(&value) = values.iter().find(|value| value.unsigned_abs() > MAX_PLAINTEXT_MAGNITUDE as u64) {
+            return Err(FheError::PlaintextOutOfRange { value });
+        }
+        let residues: Vec<u64> = values.iter().map(|&value| value.rem_euclid(PLAINTEXT_MODULUS as i64) as u64).collect();
+        Ok(Self::from_residues(&residues))
+    }
+    
+    // Values already reduced mod t, zero-padded to N coefficients
+    fn from_residues(residues: &[u64]) -> Self {
+        let mut coefficients = residues.to_vec();
+        coefficients.resize(POLYNOMIAL_DEGREE, 0);
+        Plaintext { coefficients }
+    }
+}
+
+// The representative of c mod t in -t/2..=t/2
+fn centered(c: u64) -> i64 {
+    if c > PLAINTEXT_MODULUS / 2 { c as i64 - PLAINTEXT_MODULUS as i64 } else { c as i64 }
+}
+
+// c mod q read as the integer in -q/2..q/2 it stands for, reduced mod t: a
+// ternary key coefficient, or a c1 coefficient after additions and negations
+fn residue_mod_t(c: u64) -> u64 {
+    let signed = if c >= CIPHERTEXT_MODULUS / 2 { c as i64 - CIPHERTEXT_MODULUS as i64 } else { c as i64 };
+    signed.rem_euclid(PLAINTEXT_MODULUS as i64) as u64
+}
+
+// Round c / Δ to the nearest integer mod t, so noise of either sign is tolerated
+fn descale(c: u64) -> u64 {
+    PLAINTEXT_BARRETT.reduce((c + SCALING_FACTOR / 2) / SCALING_FACTOR)
+}
+
+/// The public polynomial a: N coefficients mod t hashed from a fixed tag, so
+/// anyone can rebuild it (must match host `public_polynomial`). A public key
+/// is b = a*s mod (X^N + 1, t).
+pub fn public_polynomial() -> Vec<u64> {
+    (0..POLYNOMIAL_DEGREE as u32)
+        .map(|i| {
+            let mut buf = vec![PUBLIC_POLYNOMIAL_TAG];
+            buf.extend_from_slice(&i.to_le_bytes());
+            let digest: [u8; 32] = (*Impl::hash_bytes(&buf)).into();
+            u64::from_le_bytes(digest[..8].try_into().unwrap()) % PLAINTEXT_MODULUS
+        })
+        .collect()
+}
+
+/// The weights m with sum_j m_j * s_j = (c1 * s)[0] mod t for any secret s, c1
+/// being a ciphertext's second half: a trustee's key term for coefficient 0 is
+/// its key share weighted by them (must match host `key_term_mask`).
+pub fn key_term_mask(c1: &[u64]) -> Vec<u64> {
+    let residues: Vec<u64> = c1.iter().map(|&c| residue_mod_t(c)).collect();
+    // X^N = -1: c1_j meets s_(N-j) in coefficient 0 with a minus sign
+    (0..residues.len())
+        .map(|j| if j == 0 { residues[0] } else { (PLAINTEXT_MODULUS - residues[residues.len() - j]) % PLAINTEXT_MODULUS })
+        .collect()
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -51,12 +262,35 @@ pub struct PublicKey {
     key_data: Vec<u64>,
 }
 
+impl PublicKey {
+    /// H(tag || coefficients), the key binding carried by every ballot ciphertext.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let mut buf = vec![KEY_FINGERPRINT_TAG];
+        for coeff in &self.key_data {
+            buf.extend_from_slice(&coeff.to_le_bytes());
+        }
+        (*Impl::hash_bytes(&buf)).into()
+    }
+    
+    /// The key polynomial b = a*s mod t.
+    #[allow(dead_code)]
+    pub fn coefficients(&self) -> &[u64] {
+        &self.key_data
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PrivateKey {
     // Use Vec for better serialization support
     secret_data: Vec<u64>,
 }
 
+impl Zeroize for PrivateKey {
+    fn zeroize(&mut self) {
+        self.secret_data.zeroize();
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Cipher<T> {
     // Use Vec for better serialization support
@@ -65,6 +299,11 @@ pub struct Cipher<T> {
 }
 
 impl<T> Cipher<T> {
+    /// c0 then c1, each reduced mod q.
+    pub fn coefficients(&self) -> &[u64] {
+        &self.ciphertext_data
+    }
+    
     pub fn serialize(&self) -> Vec<u8> {
         // Simplified serialization
         let mut result = Vec::new();
@@ -75,80 +314,344 @@ impl<T> Cipher<T> {
     }
 }
 
+impl Cipher<Signed> {
+    /// All-zero ciphertext, used as a reusable buffer for `deserialize_ciphertext_into`.
+    pub fn zeroed() -> Self {
+        Cipher {
+            ciphertext_data: vec![0u64; POLYNOMIAL_DEGREE * 2],
+            _phantom: std::marker::PhantomData,
+        }
+    }
+    
+    /// Ballot encoding under the key with `key_fingerprint`, with a checksum if asked.
+    #[allow(dead_code)]
+    pub fn serialize_bound(&self, key_fingerprint: &[u8; 32], checksum: bool) -> Vec<u8> {
+        let mut result = [key_fingerprint.as_slice(), &parameter_digest(), &self.serialize()].concat();
+        if checksum {
+            result.extend(ciphertext_checksum(&result));
+        }
+        result
+    }
+}
+
 // Trait for homomorphic addition
 impl std::ops::Add for Cipher<Signed> {
     type Output = Cipher<Signed>;
     
-    fn add(self, other: Cipher<Signed>) -> Cipher<Signed> {
+    fn add(mut self, other: Cipher<Signed>) -> Cipher<Signed> {
+        self += &other;
+        self
+    }
+}
+
+// In-place addition: reuses the left operand's buffer, no allocation
+impl std::ops::AddAssign<&Cipher<Signed>> for Cipher<Signed> {
+    fn add_assign(&mut self, other: &Cipher<Signed>) {
         // Real BFV: component-wise polynomial addition mod q
         // Simplified: element-wise addition mod ciphertext_modulus
-        let mut result_data = vec![0u64; POLYNOMIAL_DEGREE * 2];
-        let len = std::cmp::min(self.ciphertext_data.len(), other.ciphertext_data.len());
-        
-        for i in 0..len {
-            // Bounds checking: ensure no overflow even before modulus
-            let a = self.ciphertext_data[i];
-            let b = other.ciphertext_data[i];
-            
-            // Use checked arithmetic to prevent overflow
-            let sum = a.checked_add(b).unwrap_or_else(|| {
-                // If overflow would occur, use modular arithmetic
-                (a % CIPHERTEXT_MODULUS) + (b % CIPHERTEXT_MODULUS)
-            });
-            
-            result_data[i] = sum % CIPHERTEXT_MODULUS;
+        // Coefficients are kept reduced (< 2^58), so a + b cannot overflow u64
+        self.ciphertext_data.resize(POLYNOMIAL_DEGREE * 2, 0);
+        for (a, &b) in self.ciphertext_data.iter_mut().zip(&other.ciphertext_data) {
+            *a = (*a + b) & CIPHERTEXT_MASK;
         }
-        
+    }
+}
+
+// In-place subtraction: coefficient-wise difference mod q
+impl std::ops::SubAssign<&Cipher<Signed>> for Cipher<Signed> {
+    fn sub_assign(&mut self, other: &Cipher<Signed>) {
+        self.ciphertext_data.resize(POLYNOMIAL_DEGREE * 2, 0);
+        for (a, &b) in self.ciphertext_data.iter_mut().zip(&other.ciphertext_data) {
+            *a = a.wrapping_sub(b) & CIPHERTEXT_MASK;
+        }
+    }
+}
+
+// Plaintext-scalar and rotation operations of the circuit guest
+#[allow(dead_code)]
+impl Cipher<Signed> {
+    /// Multiply by a public scalar. q = 2^58 divides 2^64, so the wrapping
+    /// product reduced by the mask is the product mod q.
+    pub fn mul_plain(&self, scalar: u64) -> Self {
         Cipher {
-            ciphertext_data: result_data,
+            ciphertext_data: self.ciphertext_data.iter().map(|&c| c.wrapping_mul(scalar) & CIPHERTEXT_MASK).collect(),
             _phantom: std::marker::PhantomData,
         }
     }
+    
+    /// Multiply both polynomials by X^steps in Z_q[X]/(X^N + 1): coefficients move
+    /// up `steps` places and change sign as they wrap, so rotating by N negates.
+    pub fn rotate(&self, steps: u32) -> Self {
+        let steps = steps as usize % (2 * POLYNOMIAL_DEGREE);
+        let mut ciphertext_data = vec![0u64; POLYNOMIAL_DEGREE * 2];
+        for (input, output) in self.ciphertext_data.chunks(POLYNOMIAL_DEGREE).zip(ciphertext_data.chunks_mut(POLYNOMIAL_DEGREE)) {
+            for (i, &c) in input.iter().enumerate() {
+                let shifted = i + steps;
+                let negate = (shifted / POLYNOMIAL_DEGREE) % 2 == 1;
+                output[shifted % POLYNOMIAL_DEGREE] = if negate { c.wrapping_neg() & CIPHERTEXT_MASK } else { c };
+            }
+        }
+        Cipher { ciphertext_data, _phantom: std::marker::PhantomData }
+    }
+}
+
+/// Running sum of ciphertexts with reduction deferred to `finish`.
+/// Real BFV with an odd q: k additions of values < q fit in u64 while
+/// (k + 1) * q <= 2^64, so the sum must be reduced every k additions.
+/// Here q = 2^58 divides 2^64, and reduction mod q commutes with wrapping
+/// u64 addition, so the whole chain reduces once, however long it is.
+pub struct TallyAccumulator {
+    sums: Vec<u64>,
+}
+
+impl TallyAccumulator {
+    pub fn new(initial: Cipher<Signed>) -> Self {
+        let mut sums = initial.ciphertext_data;
+        sums.resize(POLYNOMIAL_DEGREE * 2, 0);
+        TallyAccumulator { sums }
+    }
+    
+    #[inline]
+    pub fn add(&mut self, ciphertext: &Cipher<Signed>) {
+        for (sum, &c) in self.sums.iter_mut().zip(&ciphertext.ciphertext_data) {
+            *sum = sum.wrapping_add(c);
+        }
+    }
+    
+    /// Take a ciphertext back out, as when a counted ballot is revoked.
+    #[allow(dead_code)] // Only the tally guest revokes
+    pub fn sub(&mut self, ciphertext: &Cipher<Signed>) {
+        for (sum, &c) in self.sums.iter_mut().zip(&ciphertext.ciphertext_data) {
+            *sum = sum.wrapping_sub(c);
+        }
+    }
+    
+    pub fn finish(mut self) -> Cipher<Signed> {
+        for sum in &mut self.sums {
+            *sum &= CIPHERTEXT_MASK;
+        }
+        Cipher {
+            ciphertext_data: self.sums,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+}
+
+/// Barrett reduction: x mod m with a multiply and a shift instead of a division.
+pub struct Barrett {
+    modulus: u64,
+    factor: u64, // floor(2^64 / modulus)
+}
+
+impl Barrett {
+    pub const fn new(modulus: u64) -> Self {
+        Barrett { modulus, factor: ((1u128 << 64) / modulus as u128) as u64 }
+    }
+    
+    #[inline]
+    pub fn reduce(&self, x: u64) -> u64 {
+        let quotient = ((x as u128 * self.factor as u128) >> 64) as u64;
+        let r = x - quotient * self.modulus;
+        if r >= self.modulus { r - self.modulus } else { r }
+    }
+    
+    #[inline]
+    pub fn mul(&self, a: u64, b: u64) -> u64 {
+        // Operands are < 2^17, so the product fits in u64
+        self.reduce(a * b)
+    }
+}
+
+const PLAINTEXT_BARRETT: Barrett = Barrett::new(PLAINTEXT_MODULUS);
+
+/// Twiddle factors for the negacyclic NTT over Z_t[X]/(X^N + 1).
+/// t = 65537 = 2^16 + 1 has 2N-th roots of unity, so plaintext polynomials
+/// multiply in O(N log N). Built once per guest run and shared by every runtime.
+// The tally program only adds; polynomial products are for programs that multiply.
+#[allow(dead_code)]
+pub struct NttTables {
+    psi_powers: Vec<u64>,     // psi^bitrev(i), psi a primitive 2N-th root of unity
+    psi_inv_powers: Vec<u64>, // psi^-bitrev(i)
+    degree_inv: u64,          // N^-1 mod t
+}
+
+#[allow(dead_code)]
+impl NttTables {
+    fn build() -> Self {
+        const GENERATOR: u64 = 3; // Generates Z_65537^*
+        let psi = pow_mod(GENERATOR, (PLAINTEXT_MODULUS - 1) / (2 * POLYNOMIAL_DEGREE as u64));
+        let psi_inv = pow_mod(psi, PLAINTEXT_MODULUS - 2);
+        let bits = POLYNOMIAL_DEGREE.trailing_zeros();
+        let bitrev = |i: usize| (i.reverse_bits() >> (usize::BITS - bits)) as u64;
+        
+        NttTables {
+            psi_powers: (0..POLYNOMIAL_DEGREE).map(|i| pow_mod(psi, bitrev(i))).collect(),
+            psi_inv_powers: (0..POLYNOMIAL_DEGREE).map(|i| pow_mod(psi_inv, bitrev(i))).collect(),
+            degree_inv: pow_mod(POLYNOMIAL_DEGREE as u64, PLAINTEXT_MODULUS - 2),
+        }
+    }
+    
+    pub fn get() -> &'static NttTables {
+        static TABLES: std::sync::OnceLock<NttTables> = std::sync::OnceLock::new();
+        TABLES.get_or_init(NttTables::build)
+    }
+    
+    /// In-place forward transform (Cooley-Tukey, bit-reversed output).
+    pub fn forward(&self, a: &mut [u64]) {
+        let n = a.len();
+        let mut t = n;
+        let mut m = 1;
+        while m < n {
+            t /= 2;
+            for i in 0..m {
+                let s = self.psi_powers[m + i];
+                for j in 2 * i * t..2 * i * t + t {
+                    let u = a[j];
+                    let v = PLAINTEXT_BARRETT.mul(a[j + t], s);
+                    a[j] = PLAINTEXT_BARRETT.reduce(u + v);
+                    a[j + t] = PLAINTEXT_BARRETT.reduce(u + PLAINTEXT_MODULUS - v);
+                }
+            }
+            m *= 2;
+        }
+    }
+    
+    /// In-place inverse transform (Gentleman-Sande, bit-reversed input).
+    pub fn inverse(&self, a: &mut [u64]) {
+        let n = a.len();
+        let mut t = 1;
+        let mut m = n;
+        while m > 1 {
+            let h = m / 2;
+            for i in 0..h {
+                let s = self.psi_inv_powers[h + i];
+                for j in 2 * i * t..2 * i * t + t {
+                    let u = a[j];
+                    let v = a[j + t];
+                    a[j] = PLAINTEXT_BARRETT.reduce(u + v);
+                    a[j + t] = PLAINTEXT_BARRETT.mul(PLAINTEXT_BARRETT.reduce(u + PLAINTEXT_MODULUS - v), s);
+                }
+            }
+            t *= 2;
+            m = h;
+        }
+        for x in a.iter_mut() {
+            *x = PLAINTEXT_BARRETT.mul(*x, self.degree_inv);
+        }
+    }
+}
+
+/// Sparse ternary secret (must match host implementation): `hamming_weight`
+/// distinct coefficients of ±1, stored mod q so -1 is q - 1, and the rest 0.
+fn sample_ternary_secret(rng: &mut impl Rng, hamming_weight: u32) -> Vec<u64> {
+    let mut secret = vec![0u64; POLYNOMIAL_DEGREE];
+    for position in rand::seq::index::sample(rng, POLYNOMIAL_DEGREE, hamming_weight as usize) {
+        secret[position] = if rng.gen() { 1 } else { CIPHERTEXT_MODULUS - 1 };
+    }
+    secret
+}
+
+fn pow_mod(mut base: u64, mut exp: u64) -> u64 {
+    let mut result = 1;
+    base = PLAINTEXT_BARRETT.reduce(base);
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = PLAINTEXT_BARRETT.mul(result, base);
+        }
+        base = PLAINTEXT_BARRETT.mul(base, base);
+        exp >>= 1;
+    }
+    result
+}
+
+// Seed of the `index`-th encryption's noise stream under `noise_seed`
+fn noise_stream_seed(noise_seed: &[u8; 32], index: u64) -> [u8; 32] {
+    let mut buf = vec![ENCRYPTION_NOISE_TAG];
+    buf.extend_from_slice(noise_seed);
+    buf.extend_from_slice(&index.to_le_bytes());
+    (*Impl::hash_bytes(&buf)).into()
 }
 
 pub struct PureRustFheRuntime {
     // Simplified runtime - in real BFV this would manage parameter sets
-    public_key: Option<PublicKey>,
-    private_key: Option<PrivateKey>,
-    noise_seed: u64,
+    noise_seed: [u8; 32], // Deterministic, so a run is reproducible from its input
+    encryptions: Cell<u64>, // Each encryption draws from its own stream
+    parameters: FheParameters,
+    parameter_digest: [u8; 32], // Hashed once, checked against every ballot header
+    public_polynomial: Vec<u64>, // a, hashed once
+    #[allow(dead_code)]
+    ntt: &'static NttTables,
 }
 
 impl PureRustFheRuntime {
+    /// Runtime for checking and evaluating ciphertexts. Anything it encrypts
+    /// has predictable noise; encrypt from `with_noise_seed` instead.
     pub fn new() -> Self {
+        Self::with_noise_seed([0; 32])
+    }
+    
+    /// Runtime whose i-th encryption draws its noise from a stream seeded with
+    /// H(tag || `noise_seed` || i), e.g. a seed bound to the election and run.
+    pub fn with_noise_seed(noise_seed: [u8; 32]) -> Self {
         PureRustFheRuntime {
-            public_key: None,
-            private_key: None,
-            noise_seed: 12345, // Fixed seed for deterministic behavior in demo
+            noise_seed,
+            encryptions: Cell::new(0),
+            parameters: FheParameters::default(),
+            parameter_digest: parameter_digest(),
+            public_polynomial: public_polynomial(),
+            ntt: NttTables::get(),
         }
     }
     
+    /// Encrypt with `parameters` instead of the defaults.
+    #[allow(dead_code)]
+    pub fn with_parameters(mut self, parameters: FheParameters) -> Result<Self, FheError> {
+        parameters.validate()?;
+        self.parameters = parameters;
+        Ok(self)
+    }
+    
+    /// Negacyclic product of two plaintext polynomials mod (X^N + 1, t).
+    /// Encryption and decryption use it for the key products b*u, a*u and c1*s.
+    /// Real BFV: the same transform, over an RNS basis of q, drives ciphertext
+    /// multiplication.
+    #[allow(dead_code)]
+    pub fn multiply_plaintext_polynomials(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut fa: Vec<u64> = a.iter().map(|&x| PLAINTEXT_BARRETT.reduce(x)).collect();
+        let mut fb: Vec<u64> = b.iter().map(|&x| PLAINTEXT_BARRETT.reduce(x)).collect();
+        fa.resize(POLYNOMIAL_DEGREE, 0);
+        fb.resize(POLYNOMIAL_DEGREE, 0);
+        self.ntt.forward(&mut fa);
+        self.ntt.forward(&mut fb);
+        let mut product: Vec<u64> = fa.iter().zip(&fb).map(|(&x, &y)| PLAINTEXT_BARRETT.mul(x, y)).collect();
+        self.ntt.inverse(&mut product);
+        product
+    }
+    
     pub fn generate_keys(&mut self) -> (PublicKey, PrivateKey) {
-        // Real BFV: Generate secret polynomial s, error polynomial e
-        // SECURITY FIX: Use cryptographically secure key generation
-        let mut secret_data = vec![0u64; POLYNOMIAL_DEGREE];
-        let mut key_data = vec![0u64; POLYNOMIAL_DEGREE];
-        
         // CRITICAL FIX: Use cryptographically secure random number generator
         // This replaces the predictable PRNG that was a major security vulnerability
-        let mut rng = rand::thread_rng();
-        for i in 0..POLYNOMIAL_DEGREE {
-            secret_data[i] = rng.gen_range(0..PLAINTEXT_MODULUS);
-            key_data[i] = rng.gen_range(0..CIPHERTEXT_MODULUS);
-        }
-        
-        let public_key = PublicKey { key_data };
-        let private_key = PrivateKey { secret_data };
-        
-        self.public_key = Some(public_key.clone());
-        self.private_key = Some(private_key.clone());
-        
-        (public_key, private_key)
+        self.generate_keys_with(&mut rand::thread_rng())
+    }
+    
+    /// Keys from a fixed seed. Only for reproducible test vectors: the key is
+    /// as predictable as the seed.
+    #[allow(dead_code)]
+    pub fn generate_keys_from_seed(&mut self, seed: u64) -> (PublicKey, PrivateKey) {
+        self.generate_keys_with(&mut StdRng::seed_from_u64(seed))
     }
     
-    pub fn encrypt(&self, plaintext: Signed, _public_key: &PublicKey) -> Result<Cipher<Signed>, FheError> {
-        // Real BFV: m + e + a*s where m=plaintext, e=error, a=random, s=secret
-        // SECURITY FIX: Use cryptographically secure random noise generation
+    fn generate_keys_with(&mut self, rng: &mut impl Rng) -> (PublicKey, PrivateKey) {
+        // Real BFV: pk = (-a*s + e, a) mod q. Simplified: b = a*s mod t, no error
+        let secret_data = sample_ternary_secret(rng, self.parameters.secret_hamming_weight);
+        let mut secret: Vec<u64> = secret_data.iter().map(|&s| residue_mod_t(s)).collect();
+        let key_data = self.multiply_plaintext_polynomials(&self.public_polynomial, &secret);
+        secret.zeroize();
         
+        (PublicKey { key_data }, PrivateKey { secret_data })
+    }
+    
+    pub fn encrypt(&self, plaintext: Signed, public_key: &PublicKey) -> Result<Cipher<Signed>, FheError> {
         // Input validation and bounds checking
         if plaintext.val < 0 {
             return Err(FheError::EncryptionFailed { 
@@ -164,85 +667,197 @@ impl PureRustFheRuntime {
             });
         }
         
-        let plaintext_val = plaintext_u64 % PLAINTEXT_MODULUS;
-        let mut ciphertext_data = vec![0u64; POLYNOMIAL_DEGREE * 2];
-        
-        // CRYPTOGRAPHICALLY SECURE FHE ENCRYPTION: Gaussian noise distribution
-        // Real BFV schemes use Gaussian noise for provable semantic security
-        let mut rng = rand::thread_rng();
-        
-        // Production-level Gaussian noise parameters
-        // This standard deviation provides 128-bit security with our modulus
-        let noise_std_dev = NOISE_STANDARD_DEVIATION;
-        let gaussian = Normal::new(0.0, noise_std_dev)
-            .map_err(|_| FheError::EncryptionFailed { 
-                reason: "Failed to create Gaussian distribution".to_string() 
-            })?;
-        
-        // Scale plaintext up to higher-order bits for noise tolerance
-        // This is essential for BFV schemes to separate signal from noise
-        let scaling_factor = CIPHERTEXT_MODULUS / PLAINTEXT_MODULUS;
-        let scaled_plaintext = plaintext_val * scaling_factor;
-        
-        // Sample Gaussian noise and add to scaled plaintext
-        // This provides provable semantic security against chosen plaintext attacks
-        let noise_sample: f64 = gaussian.sample(&mut rng);
-        let noise_magnitude = (noise_sample.abs() as u64) % MAX_NOISE_BOUND; // Tighter security bound
-        ciphertext_data[0] = (scaled_plaintext + noise_magnitude) % CIPHERTEXT_MODULUS;
-        
-        // Fill remaining polynomial coefficients with cryptographically secure randomness
-        // These represent the polynomial structure essential for FHE security
-        for i in 1..POLYNOMIAL_DEGREE * 2 {
-            // Each coefficient gets independent Gaussian noise
-            let coeff_noise: f64 = gaussian.sample(&mut rng);
-            let coeff_magnitude = (coeff_noise.abs() as u64) % CIPHERTEXT_MODULUS;
-            ciphertext_data[i] = coeff_magnitude;
+        self.encrypt_plaintext(&Plaintext::from_residues(&[plaintext_u64]), public_key)
+    }
+    
+    /// Encrypt a whole plaintext polynomial, e.g. a vote vector packed by
+    /// `encode_slice` so a single ciphertext carries it.
+    pub fn encrypt_plaintext(&self, plaintext: &Plaintext, public_key: &PublicKey) -> Result<Cipher<Signed>, FheError> {
+        if public_key.key_data.len() != POLYNOMIAL_DEGREE || public_key.key_data.iter().any(|&b| b >= PLAINTEXT_MODULUS) {
+            return Err(FheError::EncryptionFailed {
+                reason: format!("public key must be {} coefficients mod {}", POLYNOMIAL_DEGREE, PLAINTEXT_MODULUS),
+            });
         }
         
+        // Integer-only noise: no floating point, which is slow to emulate in the zkVM
+        // and would make runs depend on float behaviour
+        let noise = self.parameters.noise;
+        let mut rng = self.next_noise_stream();
+        
+        // Real BFV: c = (b*u + e0 + Δm, a*u + e1) mod q with ternary u. Simplified:
+        // u is uniform mod t and c1 = a*u mod t, so only the secret key takes the
+        // mask b*u = a*u*s back off
+        let mut u: Vec<u64> = (0..POLYNOMIAL_DEGREE).map(|_| rng.gen_range(0..PLAINTEXT_MODULUS)).collect();
+        let mut mask = self.multiply_plaintext_polynomials(&public_key.key_data, &u);
+        
+        // Scale the masked plaintext up to higher-order bits for noise tolerance,
+        // adding the noise with its sign, i.e. negative noise wraps to just below q
+        let mut ciphertext_data: Vec<u64> = plaintext
+            .coefficients
+            .iter()
+            .zip(&mask)
+            .map(|(&value, &mask)| (PLAINTEXT_BARRETT.reduce(value + mask) * SCALING_FACTOR).wrapping_add(noise.sample(&mut rng) as u64) & CIPHERTEXT_MASK)
+            .collect();
+        ciphertext_data.extend(self.multiply_plaintext_polynomials(&self.public_polynomial, &u));
+        u.zeroize();
+        mask.zeroize();
+        
         Ok(Cipher {
             ciphertext_data,
             _phantom: std::marker::PhantomData,
         })
     }
     
-    pub fn decrypt(&self, ciphertext: &Cipher<Signed>, _private_key: &PrivateKey) -> Result<Signed, FheError> {
-        // REALISTIC FHE DECRYPTION: Account for plaintext scaling and noise
-        // Real BFV: polynomial operations to recover m from (c0, c1) and secret s
-        
-        // Extract noisy scaled plaintext from first coefficient
-        let noisy_scaled_plaintext = ciphertext.ciphertext_data[0];
-        
-        // Descale: divide by the scaling factor to recover original plaintext range
-        let scaling_factor = CIPHERTEXT_MODULUS / PLAINTEXT_MODULUS;
-        let descaled_val = noisy_scaled_plaintext / scaling_factor;
-        
-        // Apply noise tolerance: round to nearest integer in plaintext space
-        let decrypted_val = descaled_val % PLAINTEXT_MODULUS;
-        
+    // The next encryption's noise stream
+    fn next_noise_stream(&self) -> StdRng {
+        let index = self.encryptions.get();
+        self.encryptions.set(index + 1);
+        StdRng::from_seed(noise_stream_seed(&self.noise_seed, index))
+    }
+    
+    pub fn decrypt(&self, ciphertext: &Cipher<Signed>, private_key: &PrivateKey) -> Result<Signed, FheError> {
+        // The plaintext is the first coefficient; the rest of the polynomial is wiped
+        let mut plaintext = self.decrypt_plaintext(ciphertext, private_key)?;
+        let decrypted_val = plaintext.coefficients[0];
+        plaintext.zeroize();
         Ok(Signed::from(decrypted_val as i64))
     }
     
+    /// Decrypt coefficient 0 with the trustees' combined key term
+    /// (`trustee_shares::combine`), (c1 * s)[0] mod t, instead of a private key:
+    /// the descaled c0 minus the key term, as the host's combiner does. A wrong
+    /// key term gives a wrong count.
+    pub fn decrypt_combined(&self, ciphertext: &Cipher<Signed>, key_term: u64) -> Signed {
+        let decrypted_val = (descale(ciphertext.ciphertext_data[0]) + PLAINTEXT_MODULUS - key_term % PLAINTEXT_MODULUS) % PLAINTEXT_MODULUS;
+        Signed::from(decrypted_val as i64)
+    }
+    
+    /// Decrypt the whole plaintext polynomial, e.g. every candidate's count from
+    /// a tally of packed vote vectors (read back with `decode`).
+    pub fn decrypt_plaintext(&self, ciphertext: &Cipher<Signed>, private_key: &PrivateKey) -> Result<Plaintext, FheError> {
+        if private_key.secret_data.len() != POLYNOMIAL_DEGREE {
+            return Err(FheError::DecryptionFailed {
+                reason: format!("private key has {} coefficients, expected {}", private_key.secret_data.len(), POLYNOMIAL_DEGREE),
+            });
+        }
+        // m = round(c0 / Δ) - c1*s mod t: descaling tolerates noise of either sign
+        // (e.g. after a homomorphic subtraction), and c1*s takes off the mask
+        let mut secret: Vec<u64> = private_key.secret_data.iter().map(|&s| residue_mod_t(s)).collect();
+        let c1: Vec<u64> = ciphertext.ciphertext_data[POLYNOMIAL_DEGREE..].iter().map(|&c| residue_mod_t(c)).collect();
+        let mut key_terms = self.multiply_plaintext_polynomials(&c1, &secret);
+        let coefficients = ciphertext.ciphertext_data[..POLYNOMIAL_DEGREE]
+            .iter()
+            .zip(&key_terms)
+            .map(|(&c, &key_term)| (descale(c) + PLAINTEXT_MODULUS - key_term) % PLAINTEXT_MODULUS)
+            .collect();
+        secret.zeroize();
+        key_terms.zeroize();
+        Ok(Plaintext { coefficients })
+    }
+    
+    /// Coefficient encoding: value i becomes coefficient i, so ciphertext sums
+    /// add value by value and `Cipher::rotate` shifts the values. Values are
+    /// signed, at most N of them, each within ±`MAX_PLAINTEXT_MAGNITUDE`.
+    pub fn encode_slice(&self, values: &[i64]) -> Result<Plaintext, FheError> {
+        Plaintext::from_signed(values)
+    }
+    
+    /// Inverse of `encode_slice`: every coefficient as its centered value mod t.
+    pub fn decode(&self, plaintext: &Plaintext) -> Vec<i64> {
+        plaintext.coefficients.iter().map(|&c| centered(c)).collect()
+    }
+    
+    /// Slot encoding: value i becomes the evaluation at the i-th 2N-th root of
+    /// unity (in the NTT's bit-reversed order), so a product of two
+    /// plaintexts (`multiply_plaintext_polynomials`) multiplies value by value.
+    #[allow(dead_code)]
+    pub fn encode_slots(&self, values: &[i64]) -> Result<Plaintext, FheError> {
+        let mut plaintext = Plaintext::from_signed(values)?;
+        self.ntt.inverse(&mut plaintext.coefficients);
+        Ok(plaintext)
+    }
+    
+    /// Inverse of `encode_slots`.
+    #[allow(dead_code)]
+    pub fn decode_slots(&self, plaintext: &Plaintext) -> Vec<i64> {
+        let mut slots = plaintext.coefficients.clone();
+        self.ntt.forward(&mut slots);
+        slots.into_iter().map(centered).collect()
+    }
+    
+    // Used by the aggregation guest; the tally loop decodes whole ballots via `deserialize_vote_vector`
+    #[allow(dead_code)]
     pub fn deserialize_ciphertext(&self, data: &[u8]) -> Result<Cipher<Signed>, FheError> {
-        let expected_len = POLYNOMIAL_DEGREE * 2 * 8;
-        if data.len() != expected_len {
+        let mut ciphertext = Cipher::zeroed();
+        self.deserialize_ciphertext_into(data, &mut ciphertext)?;
+        Ok(ciphertext)
+    }
+    
+    /// Deserialize into an existing ciphertext, reusing its buffer. Every
+    /// coefficient must already be reduced mod q, so later additions stay in
+    /// range. On error `out` is left unchanged.
+    pub fn deserialize_ciphertext_into(&self, data: &[u8], out: &mut Cipher<Signed>) -> Result<(), FheError> {
+        if data.len() != CIPHERTEXT_LEN {
             return Err(FheError::InvalidCiphertextLength {
-                expected: expected_len,
+                expected: CIPHERTEXT_LEN,
                 actual: data.len(),
             });
         }
+        if let Some(index) = first_non_canonical(data) {
+            return Err(FheError::NonCanonicalCoefficient { index });
+        }
         
-        let mut ciphertext_data = vec![0u64; POLYNOMIAL_DEGREE * 2];
-        for i in 0..POLYNOMIAL_DEGREE * 2 {
+        out.ciphertext_data.resize(POLYNOMIAL_DEGREE * 2, 0);
+        for (i, coefficient) in out.ciphertext_data.iter_mut().enumerate() {
             let start = i * 8;
             let end = start + 8;
             let bytes: [u8; 8] = data[start..end].try_into().map_err(|_| FheError::InvalidByteSlice)?;
-            ciphertext_data[i] = u64::from_le_bytes(bytes);
+            *coefficient = u64::from_le_bytes(bytes);
+        }
+        Ok(())
+    }
+    
+    /// Deserialize a whole vote vector into `out`, one ciphertext per candidate.
+    /// Every ciphertext must be bound to the key with `key_fingerprint`, declare
+    /// this build's scheme parameters, carry only canonical coefficients and,
+    /// if it embeds a checksum, match it. All of that is checked before
+    /// anything is decoded, so a malformed, corrupt or foreign ballot is
+    /// rejected as a unit and `out` is left unchanged.
+    pub fn deserialize_vote_vector(&self, vector: &[Vec<u8>], key_fingerprint: &[u8; 32], out: &mut [Cipher<Signed>]) -> Result<(), FheError> {
+        if vector.len() != out.len() {
+            return Err(FheError::InvalidVectorLength {
+                expected: out.len(),
+                actual: vector.len(),
+            });
+        }
+        let expected_len = BOUND_HEADER_LEN + CIPHERTEXT_LEN;
+        if let Some(bytes) = vector.iter().find(|bytes| bytes.len() != expected_len && bytes.len() != expected_len + CHECKSUM_LEN) {
+            return Err(FheError::InvalidCiphertextLength {
+                expected: expected_len,
+                actual: bytes.len(),
+            });
+        }
+        // Adding ciphertexts under different keys would silently corrupt the tally
+        if vector.iter().any(|bytes| bytes[..KEY_FINGERPRINT_LEN] != key_fingerprint[..]) {
+            return Err(FheError::KeyMismatch);
+        }
+        if vector.iter().any(|bytes| bytes[KEY_FINGERPRINT_LEN..BOUND_HEADER_LEN] != self.parameter_digest[..]) {
+            return Err(FheError::ParameterMismatch);
+        }
+        for bytes in vector {
+            let (bound, checksum) = bytes.split_at(expected_len);
+            if !checksum.is_empty() && checksum != ciphertext_checksum(bound) {
+                return Err(FheError::ChecksumMismatch);
+            }
+            if let Some(index) = first_non_canonical(&bound[BOUND_HEADER_LEN..]) {
+                return Err(FheError::NonCanonicalCoefficient { index });
+            }
         }
         
-        Ok(Cipher {
-            ciphertext_data,
-            _phantom: std::marker::PhantomData,
-        })
+        for (bytes, cipher) in vector.iter().zip(out.iter_mut()) {
+            self.deserialize_ciphertext_into(&bytes[BOUND_HEADER_LEN..expected_len], cipher)?;
+        }
+        Ok(())
     }
 }
 
@@ -264,40 +879,456 @@ mod tests {
     use super::*;
     
     #[test]
-    fn test_basic_fhe_operations() -> Result<(), FheError> {
+    fn test_ntt_multiplication_matches_schoolbook() {
+        let runtime = PureRustFheRuntime::new();
+        let a: Vec<u64> = (0..POLYNOMIAL_DEGREE as u64).map(|i| (i * 7919 + 3) % PLAINTEXT_MODULUS).collect();
+        let b: Vec<u64> = (0..POLYNOMIAL_DEGREE as u64).map(|i| (i * i * 104729 + 11) % PLAINTEXT_MODULUS).collect();
+        
+        // Negacyclic convolution: X^N = -1
+        let mut expected = vec![0u64; POLYNOMIAL_DEGREE];
+        for (i, &ai) in a.iter().enumerate() {
+            for (j, &bj) in b.iter().enumerate() {
+                let term = ai * bj % PLAINTEXT_MODULUS;
+                let k = (i + j) % POLYNOMIAL_DEGREE;
+                expected[k] = if i + j < POLYNOMIAL_DEGREE {
+                    (expected[k] + term) % PLAINTEXT_MODULUS
+                } else {
+                    (expected[k] + PLAINTEXT_MODULUS - term) % PLAINTEXT_MODULUS
+                };
+            }
+        }
+        
+        assert_eq!(runtime.multiply_plaintext_polynomials(&a, &b), expected);
+        assert_eq!(PLAINTEXT_BARRETT.reduce(u64::MAX), u64::MAX % PLAINTEXT_MODULUS);
+    }
+    
+    #[test]
+    fn test_lazy_accumulation_matches_reduced_addition() -> Result<(), FheError> {
+        let mut runtime = PureRustFheRuntime::new();
+        let (public_key, private_key) = runtime.generate_keys();
+        
+        let zero = runtime.encrypt(Signed::from(0), &public_key)?;
+        let mut accumulator = TallyAccumulator::new(zero.clone());
+        let mut reduced = zero;
+        for _ in 0..200 {
+            // Far more additions than u64 headroom would allow without wrapping
+            let mut vote = runtime.encrypt(Signed::from(1), &public_key)?;
+            vote.ciphertext_data[1] = CIPHERTEXT_MASK;
+            accumulator.add(&vote);
+            reduced += &vote;
+        }
+        
+        // A revoked vote comes back out of the wrapping sums
+        let revoked = runtime.encrypt(Signed::from(1), &public_key)?;
+        accumulator.add(&revoked);
+        accumulator.sub(&revoked);
+        
+        let lazy = accumulator.finish();
+        assert_eq!(lazy.ciphertext_data, reduced.ciphertext_data);
+        assert_eq!(runtime.decrypt(&lazy, &private_key)?.val, 200);
+        Ok(())
+    }
+    
+    #[test]
+    fn test_tally_capacity_is_the_wrap_point() -> Result<(), FheError> {
+        let mut runtime = PureRustFheRuntime::new();
+        let (public_key, private_key) = runtime.generate_keys();
+        
+        let vote = runtime.encrypt(Signed::from(MAX_BALLOT_WEIGHT as i64), &public_key)?;
+        let mut accumulator = TallyAccumulator::new(runtime.encrypt(Signed::from(0), &public_key)?);
+        for _ in 0..max_tally_ballots() {
+            accumulator.add(&vote);
+        }
+        let full = accumulator.finish();
+        assert_eq!(runtime.decrypt(&full, &private_key)?.val as u64, max_tally_ballots());
+        
+        // One more ballot wraps the count back to zero
+        let mut overflowing = TallyAccumulator::new(full);
+        overflowing.add(&vote);
+        assert_eq!(runtime.decrypt(&overflowing.finish(), &private_key)?.val, 0);
+        Ok(())
+    }
+    
+    #[test]
+    fn test_decryption_secrets_are_wiped() -> Result<(), FheError> {
         let mut runtime = PureRustFheRuntime::new();
+        let (public_key, mut private_key) = runtime.generate_keys();
+        let tally = runtime.encrypt(Signed::from(5), &public_key)?;
+        
+        let mut plaintext = runtime.decrypt_plaintext(&tally, &private_key)?;
+        assert_eq!(plaintext.coefficients[0], 5);
+        let coefficients = wiped_words(&mut plaintext.coefficients, |coefficients| coefficients.zeroize());
+        assert!(plaintext.coefficients.is_empty());
+        assert!(coefficients.iter().all(|&c| c == 0));
+        
+        assert_eq!(runtime.decrypt(&tally, &private_key)?.val, 5);
+        assert!(private_key.secret_data.iter().any(|&s| s != 0));
+        let secret = wiped_words(&mut private_key.secret_data, |secret| secret.zeroize());
+        assert!(secret.iter().all(|&s| s == 0));
+        
+        // Merely emptying the vectors would leave the words in place
+        let mut kept = vec![5u64; 4];
+        assert_eq!(wiped_words(&mut kept, Vec::clear), [5; 4]);
+        Ok(())
+    }
+    
+    // The words `words` held, read back from its buffer after `wipe`. `Vec::zeroize`
+    // empties the vector, so only its buffer shows whether the words were wiped
+    fn wiped_words(words: &mut Vec<u64>, wipe: impl FnOnce(&mut Vec<u64>)) -> Vec<u64> {
+        let (buffer, len, capacity) = (words.as_ptr(), words.len(), words.capacity());
+        wipe(words);
+        assert_eq!((words.as_ptr(), words.capacity()), (buffer, capacity), "the buffer must stay allocated");
+        // SAFETY: the vector still owns the buffer (same pointer and capacity) and its
+        // first `len` words were initialized; u64 has no invalid bit patterns
+        unsafe { core::slice::from_raw_parts(buffer, len) }.to_vec()
+    }
+    
+    #[test]
+    fn test_noise_is_signed_and_drawn_per_encryption() -> Result<(), FheError> {
+        let mut runtime = PureRustFheRuntime::with_noise_seed([7; 32]);
         let (public_key, private_key) = runtime.generate_keys();
+        let zeros = (0..16).map(|_| runtime.encrypt(Signed::from(0), &public_key)).collect::<Result<Vec<_>, _>>()?;
+        // Negative noise leaves c0 just below a multiple of Δ, and still decrypts to 0
+        let residual = |c: u64| c.wrapping_sub(descale(c) * SCALING_FACTOR) & CIPHERTEXT_MASK;
+        assert!(zeros.iter().any(|zero| residual(zero.ciphertext_data[0]) > CIPHERTEXT_MODULUS / 2));
+        for zero in &zeros {
+            assert_eq!(runtime.decrypt(zero, &private_key)?.val, 0);
+        }
+        assert_ne!(zeros[0].ciphertext_data, zeros[1].ciphertext_data);
         
-        // Encrypt two values
-        let plaintext1 = Signed::from(5);
-        let plaintext2 = Signed::from(3);
+        // The same seed gives the same encryptions in order; another seed does not
+        let replay = PureRustFheRuntime::with_noise_seed([7; 32]).encrypt(Signed::from(0), &public_key)?;
+        assert_eq!(replay.ciphertext_data, zeros[0].ciphertext_data);
+        let other = PureRustFheRuntime::with_noise_seed([8; 32]).encrypt(Signed::from(0), &public_key)?;
+        assert_ne!(other.ciphertext_data, zeros[0].ciphertext_data);
+        Ok(())
+    }
+    
+    #[test]
+    fn test_combined_key_term_unmasks_the_count() -> Result<(), FheError> {
+        let mut runtime = PureRustFheRuntime::with_noise_seed([3; 32]);
+        let (public_key, private_key) = runtime.generate_keys();
+        let mut tally = runtime.encrypt(Signed::from(0), &public_key)?;
+        for _ in 0..5 {
+            tally += &runtime.encrypt(Signed::from(1), &public_key)?;
+        }
+        tally -= &runtime.encrypt(Signed::from(1), &public_key)?;
         
-        let ciphertext1 = runtime.encrypt(plaintext1, &public_key)?;
-        let ciphertext2 = runtime.encrypt(plaintext2, &public_key)?;
+        // The key term the trustees' shares combine to: <mask, s> = (c1 * s)[0] mod t
+        let mask = key_term_mask(&tally.ciphertext_data[POLYNOMIAL_DEGREE..]);
+        let key_term = mask.iter().zip(&private_key.secret_data).fold(0, |acc, (&m, &s)| (acc + m * residue_mod_t(s)) % PLAINTEXT_MODULUS);
+        assert_eq!(runtime.decrypt_combined(&tally, key_term).val, 4);
+        assert_eq!(runtime.decrypt(&tally, &private_key)?.val, 4);
         
-        // Homomorphic addition
-        let result_cipher = ciphertext1 + ciphertext2;
+        // Without the right key term the count stays masked
+        assert_eq!(runtime.decrypt_combined(&tally, key_term + 1).val, 3);
+        assert_ne!(runtime.decrypt_combined(&tally, 0).val, 4);
+        Ok(())
+    }
+    
+    #[test]
+    fn test_centered_binomial_noise_decrypts() -> Result<(), FheError> {
+        let binomial = |eta| FheParameters { noise: NoiseDistribution::CenteredBinomial { eta }, ..FheParameters::default() };
+        let mut runtime = PureRustFheRuntime::new().with_parameters(binomial(20))?;
+        let (public_key, private_key) = runtime.generate_keys();
         
-        // Decrypt and verify
-        let result_plain = runtime.decrypt(&result_cipher, &private_key)?;
-        assert_eq!(result_plain.val, 8); // 5 + 3 = 8
+        let mut sum = Cipher::zeroed();
+        for value in [1, 2, 3] {
+            sum += &runtime.encrypt(Signed::from(value), &public_key)?;
+        }
+        assert_eq!(runtime.decrypt(&sum, &private_key)?.val, 6);
+        assert!(matches!(PureRustFheRuntime::new().with_parameters(binomial(0)), Err(FheError::InvalidParameters { .. })));
         Ok(())
     }
     
     #[test]
-    fn test_serialization() -> Result<(), FheError> {
+    fn test_packed_vote_vectors_tally_by_coefficient() -> Result<(), FheError> {
         let mut runtime = PureRustFheRuntime::new();
-        let (public_key, _private_key) = runtime.generate_keys();
+        let (public_key, private_key) = runtime.generate_keys();
+        let mut tally = Cipher::zeroed();
+        for choice in [0, 2, 2, 1, 2] {
+            let mut vote = [0; 3];
+            vote[choice] = 1;
+            tally += &runtime.encrypt_plaintext(&runtime.encode_slice(&vote)?, &public_key)?;
+        }
+        assert_eq!(runtime.decode(&runtime.decrypt_plaintext(&tally, &private_key)?)[..3], [1, 1, 3]);
+        // Rotating by one moves every count up a coefficient
+        assert_eq!(runtime.decode(&runtime.decrypt_plaintext(&tally.rotate(1), &private_key)?)[..4], [0, 1, 1, 3]);
+        assert!(matches!(runtime.encode_slice(&[0; POLYNOMIAL_DEGREE + 1]), Err(FheError::PackingOverflow { .. })));
+        Ok(())
+    }
+    
+    #[test]
+    fn test_slot_encoding_multiplies_value_by_value() -> Result<(), FheError> {
+        let runtime = PureRustFheRuntime::new();
+        let (a, b) = ([3, -4, 5, 0, 7], [2, 2, -9, 1, -1]);
+        let product = runtime.multiply_plaintext_polynomials(runtime.encode_slots(&a)?.coefficients(), runtime.encode_slots(&b)?.coefficients());
+        let slots = runtime.decode_slots(&Plaintext { coefficients: product });
+        assert_eq!(slots[..5], [6, -8, -45, 0, -7]);
+        assert!(slots[5..].iter().all(|&slot| slot == 0));
         
-        let plaintext = Signed::from(42);
-        let ciphertext = runtime.encrypt(plaintext, &public_key)?;
+        let signed = [-MAX_PLAINTEXT_MAGNITUDE, -1, 0, MAX_PLAINTEXT_MAGNITUDE];
+        assert_eq!(runtime.decode(&runtime.encode_slice(&signed)?)[..4], signed);
+        assert!(matches!(runtime.encode_slice(&[MAX_PLAINTEXT_MAGNITUDE + 1]), Err(FheError::PlaintextOutOfRange { .. })));
+        Ok(())
+    }
+    
+    #[test]
+    fn test_ballot_headers_are_checked_before_decoding() -> Result<(), FheError> {
+        let mut runtime = PureRustFheRuntime::new();
+        let (public_key, _) = runtime.generate_keys();
+        let fingerprint = public_key.fingerprint();
+        let ciphertext = runtime.encrypt(Signed::from(1), &public_key)?;
+        let mut out = [Cipher::zeroed()];
+        let decode = |bytes: Vec<u8>, out: &mut [Cipher<Signed>]| runtime.deserialize_vote_vector(&[bytes], &fingerprint, out);
         
-        // Serialize and deserialize
-        let serialized = ciphertext.serialize();
-        let deserialized = runtime.deserialize_ciphertext(&serialized)?;
+        let checked = ciphertext.serialize_bound(&fingerprint, true);
+        let mut corrupt = checked.clone();
+        corrupt[BOUND_HEADER_LEN] ^= 1;
+        assert!(matches!(decode(corrupt, &mut out), Err(FheError::ChecksumMismatch)));
+        let mut foreign = ciphertext.serialize_bound(&fingerprint, false);
+        foreign[KEY_FINGERPRINT_LEN] ^= 1;
+        assert!(matches!(decode(foreign, &mut out), Err(FheError::ParameterMismatch)));
+        let mut oversized = ciphertext.serialize_bound(&fingerprint, false);
+        oversized[BOUND_HEADER_LEN + 8 * 3 + 7] |= 0x80;
+        assert!(matches!(decode(oversized, &mut out), Err(FheError::NonCanonicalCoefficient { index: 3 })));
+        assert_eq!(out[0].ciphertext_data, Cipher::zeroed().ciphertext_data);
         
-        // Should be equal
-        assert_eq!(ciphertext.ciphertext_data, deserialized.ciphertext_data);
+        decode(checked, &mut out)?;
+        assert_eq!(out[0].ciphertext_data, ciphertext.ciphertext_data);
         Ok(())
     }
-}
\ No newline at end of file
+    
+    // Known-answer vectors in test_vectors/fhe_kat.json: (key seed, noise seed byte, choices)
+    const KAT_CASES: [(u64, u8, &[usize]); 2] = [(1, 2, &[1, 2, 3, 1]), (3, 4, &[2, 2, 2])];
+    
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+    
+    fn coefficients_hex(coefficients: &[u64]) -> String {
+        to_hex(&coefficients.iter().flat_map(|c| c.to_le_bytes()).collect::<Vec<_>>())
+    }
+    
+    fn known_answer_vectors() -> Result<serde_json::Value, FheError> {
+        let mut cases = Vec::new();
+        for (key_seed, noise_seed, choices) in KAT_CASES {
+            let noise_seed = [noise_seed; 32];
+            let mut runtime = PureRustFheRuntime::with_noise_seed(noise_seed);
+            let (public_key, private_key) = runtime.generate_keys_from_seed(key_seed);
+            let fingerprint = public_key.fingerprint();
+            
+            // Tallies start from the all-zero ciphertext, a noiseless encryption of 0
+            let mut tally = [Cipher::zeroed(), Cipher::zeroed(), Cipher::zeroed()];
+            let mut ballots = Vec::new();
+            for &choice in choices {
+                let mut ciphertexts = Vec::new();
+                for (candidate, sum) in tally.iter_mut().enumerate() {
+                    let cipher = runtime.encrypt(Signed::from((candidate + 1 == choice) as i64), &public_key)?;
+                    ciphertexts.push(to_hex(&cipher.serialize_bound(&fingerprint, false)));
+                    *sum += &cipher;
+                }
+                ballots.push(serde_json::json!({ "choice": choice, "ciphertexts": ciphertexts }));
+            }
+            let counts = tally.iter().map(|sum| runtime.decrypt(sum, &private_key).map(|count| count.val)).collect::<Result<Vec<_>, _>>()?;
+            
+            cases.push(serde_json::json!({
+                "key_seed": key_seed,
+                "noise_seed": to_hex(&noise_seed),
+                "public_key": coefficients_hex(&public_key.key_data),
+                "private_key": coefficients_hex(&private_key.secret_data),
+                "key_fingerprint": to_hex(&fingerprint),
+                "ballots": ballots,
+                "tally": tally.iter().map(|sum| to_hex(&sum.serialize())).collect::<Vec<_>>(),
+                "counts": counts,
+            }));
+        }
+        Ok(serde_json::json!({
+            "parameters": {
+                "plaintext_modulus": PLAINTEXT_MODULUS,
+                "ciphertext_modulus_bits": CIPHERTEXT_MODULUS.trailing_zeros(),
+                "polynomial_degree": POLYNOMIAL_DEGREE,
+                "key_fingerprint_tag": KEY_FINGERPRINT_TAG,
+                "parameter_digest_tag": PARAMETER_DIGEST_TAG,
+                "parameter_digest": to_hex(&parameter_digest()),
+                "secret_hamming_weight": DEFAULT_SECRET_HAMMING_WEIGHT,
+            },
+            "cases": cases,
+        }))
+    }
+    
+    // Fails on any change to keys, encryption, serialization or addition.
+    // Regenerate with FHE_VOTING_UPDATE_KAT=1 only for an intended format change.
+    #[test]
+    fn test_known_answer_vectors() -> Result<(), Box<dyn std::error::Error>> {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../test_vectors/fhe_kat.json");
+        let generated = known_answer_vectors()?;
+        if std::env::var("FHE_VOTING_UPDATE_KAT").is_ok_and(|v| v == "1") {
+            std::fs::write(path, serde_json::to_string_pretty(&generated)? + "\n")?;
+            return Ok(());
+        }
+        let shipped: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        assert_eq!(generated, shipped, "FHE output no longer matches the shipped test vectors");
+        Ok(())
+    }
+}
+
+// Property tests: homomorphic correctness over generated plaintexts, noise
+// seeds and operation sequences, and serialization round-trips
+#[cfg(test)]
+mod proptests {
+    use super::*;
+    use proptest::prelude::*;
+    
+    // Operands stay below 256 and sequences short, so no sum reaches PLAINTEXT_MODULUS
+    const MAX_OPERAND: u64 = 256;
+    
+    #[derive(Debug, Clone)]
+    enum Op {
+        Add(u64),
+        AddAssign(u64),
+        Accumulate(Vec<u64>),
+        RoundTrip,
+    }
+    
+    fn plaintext() -> impl Strategy<Value = u64> {
+        prop_oneof![0..PLAINTEXT_MODULUS, Just(0), Just(PLAINTEXT_MODULUS - 1)]
+    }
+    
+    fn op() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            (0..MAX_OPERAND).prop_map(Op::Add),
+            (0..MAX_OPERAND).prop_map(Op::AddAssign),
+            prop::collection::vec(0..MAX_OPERAND, 0..8).prop_map(Op::Accumulate),
+            Just(Op::RoundTrip),
+        ]
+    }
+    
+    fn setup(noise_seed: [u8; 32]) -> (PureRustFheRuntime, PublicKey, PrivateKey) {
+        let mut runtime = PureRustFheRuntime::with_noise_seed(noise_seed);
+        let (public_key, private_key) = runtime.generate_keys();
+        (runtime, public_key, private_key)
+    }
+    
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(128))]
+        
+        #[test]
+        fn encryption_round_trips(value in plaintext(), noise_seed in any::<[u8; 32]>()) {
+            let (runtime, public_key, private_key) = setup(noise_seed);
+            let ciphertext = runtime.encrypt(Signed::from(value as i64), &public_key)?;
+            prop_assert_eq!(runtime.decrypt(&ciphertext, &private_key)?.val, value as i64);
+        }
+        
+        #[test]
+        fn out_of_range_plaintexts_are_rejected(value in PLAINTEXT_MODULUS..=i64::MAX as u64, noise_seed in any::<[u8; 32]>()) {
+            let (runtime, public_key, _) = setup(noise_seed);
+            prop_assert!(runtime.encrypt(Signed::from(value as i64), &public_key).is_err());
+            prop_assert!(runtime.encrypt(Signed::from(-(value as i64)), &public_key).is_err());
+        }
+        
+        #[test]
+        fn decrypted_sum_matches_plaintext_sum(
+            initial in 0..MAX_OPERAND,
+            ops in prop::collection::vec(op(), 0..24),
+            noise_seed in any::<[u8; 32]>(),
+        ) {
+            let (runtime, public_key, private_key) = setup(noise_seed);
+            let mut sum = runtime.encrypt(Signed::from(initial as i64), &public_key)?;
+            let mut expected = initial;
+            for op in ops {
+                match op {
+                    Op::Add(value) => {
+                        sum = sum + runtime.encrypt(Signed::from(value as i64), &public_key)?;
+                        expected += value;
+                    }
+                    Op::AddAssign(value) => {
+                        sum += &runtime.encrypt(Signed::from(value as i64), &public_key)?;
+                        expected += value;
+                    }
+                    Op::Accumulate(values) => {
+                        let mut accumulator = TallyAccumulator::new(sum);
+                        for &value in &values {
+                            accumulator.add(&runtime.encrypt(Signed::from(value as i64), &public_key)?);
+                        }
+                        sum = accumulator.finish();
+                        expected += values.iter().sum::<u64>();
+                    }
+                    Op::RoundTrip => {
+                        sum = runtime.deserialize_ciphertext(&sum.serialize())?;
+                    }
+                }
+            }
+            prop_assert_eq!(runtime.decrypt(&sum, &private_key)?.val, expected as i64);
+        }
+        
+        #[test]
+        fn serialization_round_trips(value in plaintext(), noise_seed in any::<[u8; 32]>()) {
+            let (runtime, public_key, _) = setup(noise_seed);
+            let ciphertext = runtime.encrypt(Signed::from(value as i64), &public_key)?;
+            let serialized = ciphertext.serialize();
+            prop_assert_eq!(serialized.len(), POLYNOMIAL_DEGREE * 2 * 8);
+            prop_assert_eq!(&runtime.deserialize_ciphertext(&serialized)?.ciphertext_data, &ciphertext.ciphertext_data);
+            
+            // Reusing a buffer gives the same result, and a bad length leaves it intact
+            let mut buffer = Cipher::zeroed();
+            runtime.deserialize_ciphertext_into(&serialized, &mut buffer)?;
+            prop_assert_eq!(&buffer.ciphertext_data, &ciphertext.ciphertext_data);
+            prop_assert!(runtime.deserialize_ciphertext_into(&serialized[8..], &mut buffer).is_err());
+            prop_assert_eq!(&buffer.ciphertext_data, &ciphertext.ciphertext_data);
+        }
+        
+        #[test]
+        fn untrusted_bytes_are_canonical_or_rejected(bytes in prop::collection::vec(any::<u8>(), POLYNOMIAL_DEGREE * 2 * 8)) {
+            let runtime = PureRustFheRuntime::new();
+            let canonical = bytes.chunks(8).all(|word| u64::from_le_bytes(word.try_into().unwrap()) < CIPHERTEXT_MODULUS);
+            match runtime.deserialize_ciphertext(&bytes) {
+                Ok(ciphertext) => {
+                    prop_assert!(canonical);
+                    prop_assert_eq!(ciphertext.serialize(), bytes);
+                }
+                Err(e) => {
+                    let non_canonical = matches!(e, FheError::NonCanonicalCoefficient { .. });
+                    prop_assert!(!canonical && non_canonical);
+                }
+            }
+        }
+        
+        #[test]
+        fn vote_vectors_are_decoded_as_a_unit(
+            values in prop::collection::vec(0..2u64, 1..5),
+            bad_entry in any::<prop::sample::Index>(),
+            truncate in any::<bool>(),
+            noise_seed in any::<[u8; 32]>(),
+        ) {
+            let (runtime, public_key, private_key) = setup(noise_seed);
+            let fingerprint = public_key.fingerprint();
+            let vector = values
+                .iter()
+                .map(|&value| {
+                    let ciphertext = runtime.encrypt(Signed::from(value as i64), &public_key)?;
+                    Ok(ciphertext.serialize_bound(&fingerprint, false))
+                })
+                .collect::<Result<Vec<_>, FheError>>()?;
+            let mut out: Vec<Cipher<Signed>> = values.iter().map(|_| Cipher::zeroed()).collect();
+            
+            // One truncated or foreign-key entry rejects the whole vector
+            let mut tampered = vector.clone();
+            let entry = &mut tampered[bad_entry.index(values.len())];
+            if truncate {
+                entry.truncate(entry.len() - 8);
+            } else {
+                entry[0] ^= 1;
+            }
+            let result = runtime.deserialize_vote_vector(&tampered, &fingerprint, &mut out);
+            prop_assert!(result.is_err());
+            prop_assert!(truncate || matches!(result, Err(FheError::KeyMismatch)));
+            prop_assert!(out.iter().all(|cipher| cipher.ciphertext_data.iter().all(|&c| c == 0)));
+            
+            runtime.deserialize_vote_vector(&vector, &fingerprint, &mut out)?;
+            for (cipher, &value) in out.iter().zip(&values) {
+                prop_assert_eq!(runtime.decrypt(cipher, &private_key)?.val, value as i64);
+            }
+        }
+    }
+}
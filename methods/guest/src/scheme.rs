@@ -0,0 +1,335 @@
+// Voting rules, shared by the guest and the host (which includes this file by
+// path, so there is one implementation to keep in step).
+// A `VoteScheme` decides which ballots are well formed and what weight they
+// carry, how a ballot adds to the per-candidate tallies and who wins once the
+// tallies are decrypted. The guest runs it over ciphertexts and the host's test
+// harness over plaintexts, so a new rule is one more implementation of the
+// trait, not an edit to the tally loop.
+//
+//   Plurality  one choice per ballot, weight 1
+//   Approval   any number of approved candidates, each counted once
+//   Weighted   one choice, counted `weight` times; only voters in the weight
+//              table may vote
+//
+// Under encryption a scheme sees a ballot's public shape only: its number of
+// entries and the voter. Entries in the clear are checked as well (one-hot for
+// plurality and weighted ballots, 0 or 1 each for approval ballots). No
+// candidate gains more than a ballot's weight from one ballot, so the tally
+// stays below the plaintext modulus while the counted weight does.
+//
+// A tie for first place is settled by the election's `TieBreak`, part of the
+// `ElectionContext` verifiers pin before the count and committed with the
+// outcome:
+//
+//   Declare      no winner; the tie is the outcome (a runoff follows, say)
+//   FirstListed  the tied candidate listed first on the ballot
+//   Lot(seed)    drawn among the tied: H(tag || seed || counts) mod ties, with
+//                a seed nobody knows at the close (a later block hash, a
+//                randomness beacon round)
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+// Domain separation tags for drawing lots and for the scheme digest
+const LOT_TAG: u8 = 0x1a;
+const SCHEME_TAG: u8 = 0x1c;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum SchemeError {
+    #[error("Ballot has {entries} entries for {candidates} candidates")]
+    WrongLength { entries: usize, candidates: usize },
+    #[error("Ballot must choose exactly one candidate")]
+    NotOneHot,
+    #[error("Ballot entries must each be 0 or 1")]
+    NotApproval,
+    #[error("Voter {voter} has no weight in this election")]
+    Unweighted { voter: String },
+    #[error("Weight table is not strictly ascending by voter address at {voter}")]
+    UnsortedWeights { voter: String },
+}
+
+/// One entry of a ballot: a ciphertext, or a plaintext in the test harness.
+pub trait BallotEntry {
+    /// The entry's value when it is in the clear.
+    fn plaintext(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// A per-candidate tally a ballot entry can be added to.
+pub trait Accumulator<E> {
+    fn add_weighted(&mut self, entry: &E, weight: u64);
+}
+
+impl BallotEntry for u64 {
+    fn plaintext(&self) -> Option<u64> {
+        Some(*self)
+    }
+}
+
+impl Accumulator<u64> for u64 {
+    fn add_weighted(&mut self, entry: &u64, weight: u64) {
+        *self += entry * weight;
+    }
+}
+
+pub trait VoteScheme {
+    /// Check `entries` is a well-formed ballot from `voter` for `candidates`
+    /// candidates and return the weight it counts with.
+    fn validate_ballot<E: BallotEntry>(&self, voter: &str, entries: &[E], candidates: usize) -> Result<u64, SchemeError>;
+
+    /// Add a validated ballot to the tallies.
+    fn accumulate<E, A: Accumulator<E>>(&self, tallies: &mut [A], entries: &[E], weight: u64) {
+        for (tally, entry) in tallies.iter_mut().zip(entries) {
+            tally.add_weighted(entry, weight);
+        }
+    }
+
+    /// The winning candidates (indices, more than one on a tie) from the decrypted counts.
+    fn finalize(&self, counts: &[u64]) -> Vec<usize> {
+        let top = counts.iter().copied().max().unwrap_or(0);
+        if top == 0 {
+            return Vec::new();
+        }
+        counts.iter().enumerate().filter(|(_, &count)| count == top).map(|(index, _)| index).collect()
+    }
+}
+
+fn check_length<E>(entries: &[E], candidates: usize) -> Result<(), SchemeError> {
+    if entries.len() != candidates {
+        return Err(SchemeError::WrongLength { entries: entries.len(), candidates });
+    }
+    Ok(())
+}
+
+fn check_one_hot<E: BallotEntry>(entries: &[E]) -> Result<(), SchemeError> {
+    let values: Option<Vec<u64>> = entries.iter().map(BallotEntry::plaintext).collect();
+    match values {
+        Some(values) if values.iter().any(|&v| v > 1) || values.iter().sum::<u64>() != 1 => Err(SchemeError::NotOneHot),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Plurality;
+
+impl VoteScheme for Plurality {
+    fn validate_ballot<E: BallotEntry>(&self, _voter: &str, entries: &[E], candidates: usize) -> Result<u64, SchemeError> {
+        check_length(entries, candidates)?;
+        check_one_hot(entries)?;
+        Ok(1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Approval;
+
+impl VoteScheme for Approval {
+    fn validate_ballot<E: BallotEntry>(&self, _voter: &str, entries: &[E], candidates: usize) -> Result<u64, SchemeError> {
+        check_length(entries, candidates)?;
+        if entries.iter().filter_map(BallotEntry::plaintext).any(|v| v > 1) {
+            return Err(SchemeError::NotApproval);
+        }
+        Ok(1)
+    }
+}
+
+/// A voter's weight under `Weighted`, e.g. a token balance at the snapshot block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoterWeight {
+    pub voter_address: String,
+    pub weight: u64,
+}
+
+/// Weighted voting over a weight table sorted by voter address.
+#[derive(Debug, Clone, Default)]
+pub struct Weighted {
+    weights: Vec<VoterWeight>,
+}
+
+impl Weighted {
+    pub fn new(mut weights: Vec<VoterWeight>) -> Self {
+        weights.sort_by(|a, b| a.voter_address.cmp(&b.voter_address));
+        Weighted { weights }
+    }
+}
+
+impl VoteScheme for Weighted {
+    fn validate_ballot<E: BallotEntry>(&self, voter: &str, entries: &[E], candidates: usize) -> Result<u64, SchemeError> {
+        validate_weighted(&self.weights, voter, entries, candidates)
+    }
+}
+
+// Weights are looked up by binary search, so one lookup per ballot stays cheap
+fn validate_weighted<E: BallotEntry>(weights: &[VoterWeight], voter: &str, entries: &[E], candidates: usize) -> Result<u64, SchemeError> {
+    check_length(entries, candidates)?;
+    check_one_hot(entries)?;
+    weights
+        .binary_search_by(|entry| entry.voter_address.as_str().cmp(voter))
+        .ok()
+        .map(|index| weights[index].weight)
+        .filter(|&weight| weight > 0)
+        .ok_or_else(|| SchemeError::Unweighted { voter: voter.to_string() })
+}
+
+/// The scheme an election is tallied under, as carried in the tally input.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TallyScheme {
+    #[default]
+    Plurality,
+    Approval,
+    Weighted(Vec<VoterWeight>), // Strictly ascending by voter address (see `check`)
+}
+
+impl TallyScheme {
+    /// Weighted voting over `weights`, sorted as the guest looks them up.
+    #[allow(dead_code)]
+    pub fn weighted(weights: Vec<VoterWeight>) -> Self {
+        TallyScheme::Weighted(Weighted::new(weights).weights)
+    }
+
+    /// Digest the election context binds the scheme with: zero for plurality,
+    /// otherwise H(tag || rule || weight table), so neither the rule nor any
+    /// weight can be swapped for another under the same context.
+    pub fn digest(&self) -> [u8; 32] {
+        let rule = match self {
+            TallyScheme::Plurality => return [0u8; 32],
+            TallyScheme::Approval => 1u8,
+            TallyScheme::Weighted(_) => 2,
+        };
+        let mut buf = vec![SCHEME_TAG, rule];
+        if let TallyScheme::Weighted(weights) = self {
+            buf.extend_from_slice(&(weights.len() as u32).to_le_bytes());
+            for entry in weights {
+                buf.extend_from_slice(&(entry.voter_address.len() as u32).to_le_bytes());
+                buf.extend_from_slice(entry.voter_address.as_bytes());
+                buf.extend_from_slice(&entry.weight.to_le_bytes());
+            }
+        }
+        (*Impl::hash_bytes(&buf)).into()
+    }
+
+    /// Check the scheme is usable as loaded: a weight table must be strictly
+    /// ascending by voter address, or its binary search could find any entry
+    /// (or none) for a voter listed twice or out of order.
+    #[allow(dead_code)]
+    pub fn check(&self) -> Result<(), SchemeError> {
+        match self {
+            TallyScheme::Weighted(weights) => match weights.windows(2).find(|pair| pair[0].voter_address >= pair[1].voter_address) {
+                Some(pair) => Err(SchemeError::UnsortedWeights { voter: pair[1].voter_address.clone() }),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+impl VoteScheme for TallyScheme {
+    fn validate_ballot<E: BallotEntry>(&self, voter: &str, entries: &[E], candidates: usize) -> Result<u64, SchemeError> {
+        match self {
+            TallyScheme::Plurality => Plurality.validate_ballot(voter, entries, candidates),
+            TallyScheme::Approval => Approval.validate_ballot(voter, entries, candidates),
+            TallyScheme::Weighted(weights) => validate_weighted(weights, voter, entries, candidates),
+        }
+    }
+}
+
+/// How a tie for first place is settled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TieBreak {
+    #[default]
+    Declare,
+    FirstListed,
+    Lot([u8; 32]), // Public seed the lot is drawn with
+}
+
+impl TieBreak {
+    /// The winner among `leading` (the candidates with the top count, in ballot order).
+    pub fn winner(&self, leading: &[usize], counts: &[u64]) -> Option<usize> {
+        match (leading, self) {
+            ([], _) => None,
+            ([only], _) => Some(*only),
+            (_, TieBreak::Declare) => None,
+            (_, TieBreak::FirstListed) => leading.first().copied(),
+            (_, TieBreak::Lot(seed)) => {
+                let mut buf = vec![LOT_TAG];
+                buf.extend_from_slice(seed);
+                for count in counts {
+                    buf.extend_from_slice(&count.to_le_bytes());
+                }
+                let digest = Impl::hash_bytes(&buf);
+                let mut word = [0u8; 8];
+                word.copy_from_slice(&digest.as_bytes()[..8]);
+                Some(leading[(u64::from_le_bytes(word) % leading.len() as u64) as usize])
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Plaintext ballots through the same rules the guest applies to ciphertexts
+    fn tally(scheme: &impl VoteScheme, ballots: &[(&str, [u64; 3])]) -> Result<[u64; 3], SchemeError> {
+        let mut tallies = [0u64; 3];
+        for (voter, entries) in ballots {
+            let weight = scheme.validate_ballot(voter, entries, 3)?;
+            scheme.accumulate(&mut tallies, entries, weight);
+        }
+        Ok(tallies)
+    }
+
+    #[test]
+    fn test_schemes_validate_accumulate_and_finalize() -> Result<(), SchemeError> {
+        let plurality = tally(&TallyScheme::Plurality, &[("a", [1, 0, 0]), ("b", [0, 1, 0]), ("c", [1, 0, 0])])?;
+        assert_eq!((plurality, TallyScheme::Plurality.finalize(&plurality)), ([2, 1, 0], vec![0]));
+        assert_eq!(tally(&Plurality, &[("a", [1, 1, 0])]), Err(SchemeError::NotOneHot));
+        assert_eq!(tally(&Plurality, &[("a", [0, 0, 0])]), Err(SchemeError::NotOneHot));
+
+        let approval = tally(&Approval, &[("a", [1, 1, 0]), ("b", [0, 1, 1]), ("c", [1, 0, 0])])?;
+        assert_eq!((approval, Approval.finalize(&approval)), ([2, 2, 1], vec![0, 1]));
+        assert_eq!(tally(&Approval, &[("a", [2, 0, 0])]), Err(SchemeError::NotApproval));
+
+        let weights = vec![VoterWeight { voter_address: "b".to_string(), weight: 3 }, VoterWeight { voter_address: "a".to_string(), weight: 5 }];
+        let weighted = TallyScheme::weighted(weights);
+        let counts = tally(&weighted, &[("a", [0, 0, 1]), ("b", [1, 0, 0]), ("b", [1, 0, 0])])?;
+        assert_eq!((counts, weighted.finalize(&counts)), ([6, 0, 5], vec![0]));
+        assert_eq!(tally(&weighted, &[("c", [1, 0, 0])]), Err(SchemeError::Unweighted { voter: "c".to_string() }));
+
+        // The digest names the rule and every weight
+        assert_eq!(TallyScheme::Plurality.digest(), [0u8; 32]);
+        let digests = [TallyScheme::Approval.digest(), weighted.digest(), TallyScheme::weighted(vec![VoterWeight { voter_address: "a".to_string(), weight: 6 }]).digest()];
+        assert!(digests[0] != [0u8; 32] && digests[0] != digests[1] && digests[1] != digests[2]);
+
+        // A weight table out of order, or listing a voter twice, is refused when loaded
+        assert_eq!(weighted.check(), Ok(()));
+        let weight = |voter: &str, weight| VoterWeight { voter_address: voter.to_string(), weight };
+        assert_eq!(TallyScheme::Weighted(vec![weight("b", 3), weight("a", 5)]).check(), Err(SchemeError::UnsortedWeights { voter: "a".to_string() }));
+        assert_eq!(TallyScheme::weighted(vec![weight("a", 1), weight("b", 3), weight("a", 5)]).check(), Err(SchemeError::UnsortedWeights { voter: "a".to_string() }));
+
+        // Encrypted entries are checked for shape only
+        struct Sealed;
+        impl BallotEntry for Sealed {}
+        assert_eq!(Plurality.validate_ballot("a", &[Sealed, Sealed, Sealed], 3), Ok(1));
+        assert_eq!(Plurality.validate_ballot("a", &[Sealed, Sealed], 3), Err(SchemeError::WrongLength { entries: 2, candidates: 3 }));
+        assert!(Plurality.finalize(&[0, 0, 0]).is_empty());
+
+        // A tie is settled by the rule fixed beforehand, the same way every time
+        let tied = Approval.finalize(&approval);
+        assert_eq!(TieBreak::Declare.winner(&tied, &approval), None);
+        assert_eq!(TieBreak::FirstListed.winner(&tied, &approval), Some(0));
+        let drawn = TieBreak::Lot([7u8; 32]).winner(&tied, &approval);
+        assert!(drawn.is_some_and(|index| tied.contains(&index)));
+        assert_eq!(TieBreak::Lot([7u8; 32]).winner(&tied, &approval), drawn);
+        let draws: Vec<_> = (0..16u8).filter_map(|seed| TieBreak::Lot([seed; 32]).winner(&tied, &approval)).collect();
+        assert!(draws.contains(&0) && draws.contains(&1));
+        assert_eq!(TieBreak::Declare.winner(&[2], &[0, 1, 5]), Some(2));
+        Ok(())
+    }
+}
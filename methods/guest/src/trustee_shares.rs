@@ -0,0 +1,218 @@
+// Trustee decryption shares (guest side, must match host `decryption_shares`).
+// The guest holds no secret key of its own. A final tally is decrypted only
+// with the partial decryptions of `threshold` trustees, each carrying a Schnorr
+// proof that its key term was computed from the key share the trustee
+// committed to in its `ShareVerificationKey`. The digest of the threshold and
+// every verification key is committed, so a verifier knows whose shares the
+// published counts rest on.
+//
+// Each key term is the trustee's share of (c1 * s)[0], its key share weighted
+// by `key_term_mask`. Combined with Lagrange coefficients they give (c1 * s)[0]
+// mod t, which `decrypt_combined` takes off the descaled c0: without the shares
+// the count is masked, and a wrong key term gives a wrong count. Real BFV
+// combines the terms mod q, before descaling.
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::VartimeMultiscalarMul;
+use risc0_zkvm::sha::{Impl, Sha256};
+use thiserror::Error;
+
+use crate::credentials::wide_hash;
+use crate::pure_rust_fhe::{key_term_mask, PLAINTEXT_MODULUS};
+use crate::types::{PartialDecryption, ShareVerificationKey, TrusteeDecryption};
+
+// Domain separation tag (must match host implementation)
+const TRUSTEES_TAG: u8 = 0x1d;
+
+#[derive(Error, Debug)]
+pub enum ShareError {
+    #[error("Malformed verification key for trustee {index}")]
+    MalformedVerificationKey { index: u32 },
+    #[error("No verification key published for trustee {index}")]
+    UnknownTrustee { index: u32 },
+    #[error("Share from trustee {index} was computed for a different ciphertext")]
+    CiphertextMismatch { index: u32 },
+    #[error("Invalid decryption proof from trustee {index}")]
+    InvalidProof { index: u32 },
+    #[error("Duplicate decryption share from trustee {index}")]
+    DuplicateShare { index: u32 },
+    #[error("Not enough valid decryption shares: have {have}, need {need}")]
+    NotEnoughShares { have: usize, need: usize },
+}
+
+/// H(tag || threshold || count || per key: index || count || commitments),
+/// committed as `trustees_digest` (must match host `trustees_digest`).
+pub fn trustees_digest(decryption: &TrusteeDecryption) -> [u8; 32] {
+    let mut buf = vec![TRUSTEES_TAG];
+    buf.extend_from_slice(&decryption.threshold.to_le_bytes());
+    buf.extend_from_slice(&(decryption.verification_keys.len() as u32).to_le_bytes());
+    for key in &decryption.verification_keys {
+        buf.extend_from_slice(&key.index.to_le_bytes());
+        buf.extend_from_slice(&(key.coefficient_commitments.len() as u32).to_le_bytes());
+        for commitment in &key.coefficient_commitments {
+            buf.extend_from_slice(commitment);
+        }
+    }
+    (*Impl::hash_bytes(&buf)).into()
+}
+
+/// Check every share for `ciphertext` (a serialized tally: c0 then c1) and
+/// combine `threshold` of them into the key term, sum_i lambda_i * k_i mod t.
+pub fn combine(ciphertext: &[u8], shares: &[PartialDecryption], decryption: &TrusteeDecryption) -> Result<u64, ShareError> {
+    let digest: [u8; 32] = (*Impl::hash_bytes(ciphertext)).into();
+    let words: Vec<u64> = ciphertext.chunks_exact(8).map(|word| u64::from_le_bytes(word.try_into().unwrap())).collect();
+    let mask = key_term_mask(&words[words.len() / 2..]);
+    let mut accepted: Vec<&PartialDecryption> = Vec::new();
+    for share in shares {
+        let index = share.trustee_index;
+        if accepted.iter().any(|accepted| accepted.trustee_index == index) {
+            return Err(ShareError::DuplicateShare { index });
+        }
+        let verification_key = decryption
+            .verification_keys
+            .iter()
+            .find(|key| key.index == index)
+            .ok_or(ShareError::UnknownTrustee { index })?;
+        if share.ciphertext_digest != digest {
+            return Err(ShareError::CiphertextMismatch { index });
+        }
+        verify_share(share, verification_key, &mask)?;
+        accepted.push(share);
+    }
+
+    if accepted.len() < decryption.threshold as usize {
+        return Err(ShareError::NotEnoughShares { have: accepted.len(), need: decryption.threshold as usize });
+    }
+    accepted.truncate(decryption.threshold as usize);
+    let trustees: Vec<u32> = accepted.iter().map(|share| share.trustee_index).collect();
+    Ok(accepted.iter().fold(0u64, |acc, share| {
+        let lambda = lagrange_coefficient(share.trustee_index, &trustees);
+        (acc + lambda * (share.key_term % PLAINTEXT_MODULUS)) % PLAINTEXT_MODULUS
+    }))
+}
+
+// sum_j c_j * C_j - k*G == blinding*H, by the trustee's Schnorr proof (must match host `verify_partial_decryption`)
+fn verify_share(share: &PartialDecryption, verification_key: &ShareVerificationKey, mask: &[u64]) -> Result<(), ShareError> {
+    let index = share.trustee_index;
+    if verification_key.coefficient_commitments.len() != mask.len() {
+        return Err(ShareError::MalformedVerificationKey { index });
+    }
+    let points = verification_key
+        .coefficient_commitments
+        .iter()
+        .map(|bytes| CompressedRistretto(*bytes).decompress())
+        .collect::<Option<Vec<_>>>()
+        .ok_or(ShareError::MalformedVerificationKey { index })?;
+    let combined = RistrettoPoint::vartime_multiscalar_mul(mask.iter().map(|&c| Scalar::from(c)), &points);
+    let target = combined - Scalar::from(share.key_term) * RISTRETTO_BASEPOINT_POINT;
+
+    let nonce_commitment = CompressedRistretto(share.proof.nonce_commitment);
+    let nonce_point = nonce_commitment.decompress().ok_or(ShareError::InvalidProof { index })?;
+    let response = Option::<Scalar>::from(Scalar::from_canonical_bytes(share.proof.response))
+        .ok_or(ShareError::InvalidProof { index })?;
+    let challenge = proof_challenge(index, &share.ciphertext_digest, share.key_term, &nonce_commitment);
+
+    if response * pedersen_h() != nonce_point + challenge * target {
+        return Err(ShareError::InvalidProof { index });
+    }
+    Ok(())
+}
+
+/// Lagrange coefficient at x = 0 for `index` within `indices`, over GF(t)
+/// (must match host `dkg::lagrange_coefficient`).
+fn lagrange_coefficient(index: u32, indices: &[u32]) -> u64 {
+    let p = PLAINTEXT_MODULUS;
+    let (mut num, mut den) = (1u64, 1u64);
+    for &other in indices.iter().filter(|&&other| other != index) {
+        num = num * (other as u64) % p;
+        den = den * ((other as u64 + p - index as u64) % p) % p;
+    }
+    // den^(p - 2) is its inverse, p being prime
+    let (mut inverse, mut base, mut exp) = (1u64, den, p - 2);
+    while exp > 0 {
+        if exp & 1 == 1 {
+            inverse = inverse * base % p;
+        }
+        base = base * base % p;
+        exp >>= 1;
+    }
+    num * inverse % p
+}
+
+fn pedersen_h() -> RistrettoPoint {
+    RistrettoPoint::from_uniform_bytes(&wide_hash(b"fhe-voting/pedersen/h"))
+}
+
+fn proof_challenge(index: u32, digest: &[u8; 32], key_term: u64, nonce_commitment: &CompressedRistretto) -> Scalar {
+    let mut buf = b"fhe-voting/decryption-share".to_vec();
+    buf.extend_from_slice(&index.to_le_bytes());
+    buf.extend_from_slice(digest);
+    buf.extend_from_slice(&key_term.to_le_bytes());
+    buf.extend_from_slice(nonce_commitment.as_bytes());
+    Scalar::from_bytes_mod_order_wide(&wide_hash(&buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ShareProof;
+
+    // A serialized tally ciphertext: 32 words of c0, then 32 of c1
+    fn ciphertext() -> Vec<u8> {
+        (0..64u64).flat_map(|i| (i * 1_000_003 + 17).to_le_bytes()).collect()
+    }
+
+    // Trustee `index` with its key share and Pedersen blinding, as the host's `TrusteeDecryptor`
+    fn trustee(index: u32) -> (Vec<u64>, Vec<Scalar>, ShareVerificationKey) {
+        let secret: Vec<u64> = (0..32u64).map(|j| (j * 7 + index as u64) % PLAINTEXT_MODULUS).collect();
+        let blinding: Vec<Scalar> = (0..32u64).map(|j| Scalar::from(j + 100 * index as u64 + 1)).collect();
+        let coefficient_commitments = secret
+            .iter()
+            .zip(&blinding)
+            .map(|(&s, r)| (Scalar::from(s) * RISTRETTO_BASEPOINT_POINT + r * pedersen_h()).compress().to_bytes())
+            .collect();
+        (secret, blinding, ShareVerificationKey { index, coefficient_commitments })
+    }
+
+    fn share(index: u32, ciphertext: &[u8]) -> PartialDecryption {
+        let (secret, blinding, _) = trustee(index);
+        let words: Vec<u64> = ciphertext.chunks_exact(8).map(|word| u64::from_le_bytes(word.try_into().unwrap())).collect();
+        let mask = key_term_mask(&words[32..]);
+        let key_term: u64 = mask.iter().zip(&secret).map(|(&c, &s)| c * s).sum();
+        let blinding_term: Scalar = mask.iter().zip(&blinding).map(|(&c, r)| Scalar::from(c) * r).sum();
+        let nonce = Scalar::from(9u64 + index as u64);
+        let nonce_commitment = (nonce * pedersen_h()).compress();
+        let ciphertext_digest: [u8; 32] = (*Impl::hash_bytes(ciphertext)).into();
+        let challenge = proof_challenge(index, &ciphertext_digest, key_term, &nonce_commitment);
+        PartialDecryption {
+            trustee_index: index,
+            ciphertext_digest,
+            key_term,
+            proof: ShareProof { nonce_commitment: nonce_commitment.to_bytes(), response: (nonce + challenge * blinding_term).to_bytes() },
+        }
+    }
+
+    #[test]
+    fn test_shares_are_checked_before_combining() -> Result<(), ShareError> {
+        let ciphertext = ciphertext();
+        let decryption = TrusteeDecryption { threshold: 2, verification_keys: (1..=3).map(|index| trustee(index).2).collect(), shares: Vec::new() };
+        combine(&ciphertext, &[share(1, &ciphertext), share(3, &ciphertext)], &decryption)?;
+
+        let mut forged = share(2, &ciphertext);
+        forged.key_term += 1;
+        assert!(matches!(combine(&ciphertext, &[share(1, &ciphertext), forged], &decryption), Err(ShareError::InvalidProof { index: 2 })));
+        assert!(matches!(combine(&ciphertext, &[share(1, &ciphertext)], &decryption), Err(ShareError::NotEnoughShares { have: 1, need: 2 })));
+        assert!(matches!(combine(&ciphertext, &[share(1, &ciphertext), share(1, &ciphertext)], &decryption), Err(ShareError::DuplicateShare { index: 1 })));
+        assert!(matches!(combine(&ciphertext, &[share(1, &ciphertext), share(4, &ciphertext)], &decryption), Err(ShareError::UnknownTrustee { index: 4 })));
+        let mut other = ciphertext.clone();
+        other[0] ^= 1;
+        assert!(matches!(combine(&other, &[share(1, &ciphertext), share(2, &ciphertext)], &decryption), Err(ShareError::CiphertextMismatch { index: 1 })));
+
+        // The committed digest names the threshold and every trustee's key
+        let lower = TrusteeDecryption { threshold: 1, ..decryption.clone() };
+        assert_ne!(trustees_digest(&lower), trustees_digest(&decryption));
+        Ok(())
+    }
+}
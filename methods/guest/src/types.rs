@@ -1,4 +1,9 @@
-use serde::{Serialize, Deserialize};
+use std::fmt;
+
+use serde::{Serialize, Deserialize, Deserializer, Serializer};
+
+use crate::pure_rust_fhe::PublicKey;
+use crate::scheme::{TallyScheme, TieBreak, VoteScheme};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum VoteOption {
@@ -20,25 +25,613 @@ impl VoteOption {
 #[derive(Serialize, Deserialize)]
 pub struct VoteTallyInput {
     pub encrypted_votes: Vec<EncryptedVote>,
+    pub ballot_set_root: [u8; 32], // Merkle root over encrypted_votes, recomputed by the guest
+    pub election_public_key: PublicKey, // Combined trustee key from the DKG
+    pub registrar_public_key: [u8; 32], // Ristretto key that blind-signs voter credentials
+    pub identity_roster: Vec<[u8; 32]>, // Registered identity commitments for anonymous ballots
+    pub audit_log: Vec<AuditRecord>, // Every submission, accepted or rejected, in arrival order
+    pub audit_log_head: [u8; 32], // Hash-chain head over audit_log, recomputed by the guest
+    pub chain_anchor: Option<ChainAnchor>, // Set when the ballots were read from a chain; committed as is
+    #[serde(default)]
+    pub journal_profile: JournalProfile, // Layout of the committed journal
+    #[serde(default)]
+    pub interim: bool, // Prove the encrypted tally so far; commits InterimTallyOutput and no counts
+    #[serde(default)]
+    pub prior_tally: Option<PriorTally>, // Interim tally this proof continues; its receipt is an assumption
+    #[serde(default)]
+    pub eip712_domain: Option<Eip712Domain>, // Contract the result is typed for; the guest commits its EIP-712 digest
+    #[serde(default)]
+    pub election: ElectionContext, // Committed as is, so the receipt only stands for this election
+    #[serde(default)]
+    pub candidates: Vec<Candidate>, // Ballot text, one per option; hashes to election.candidates_digest
+    #[serde(default)]
+    pub scheme: TallyScheme, // Voting rule the guest validates and accumulates ballots under; hashes to election.scheme_digest
+    #[serde(default)]
+    pub min_anonymity_set: u32, // Final counts are revealed only if at least this many ballots were tallied
+    #[serde(default)]
+    pub dp_noise: Option<DpNoise>, // Publish the final counts with differentially private noise
+    #[serde(default)]
+    pub revocations: Vec<BallotRevocation>, // Counted ballots their voters took back; subtracted before decryption
+    #[serde(default)]
+    pub electorate: Option<Electorate>, // Registered voters turnout is reported against; committed as is
+    #[serde(default)]
+    pub decryption: Option<TrusteeDecryption>, // Trustees' shares of the final tallies; the guest holds no key of its own
+    #[serde(default)]
+    pub mix: Option<BallotMix>, // Shuffled, re-encrypted vote vectors tallied in place of the submitted ones
 }
 
-#[derive(Serialize, Deserialize)]
+/// Journal layout the guest commits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JournalProfile {
+    #[default]
+    Full, // VoteTallyOutput followed by the encrypted tally frames
+    Compact, // Fixed-width counts and 32-byte commitments only; tally frames go to the host out of band
+}
+
+/// Differential privacy for the published counts (see dp_noise.rs).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DpNoise {
+    pub epsilon_milli: u32, // Privacy budget epsilon, in thousandths
+    pub seed: [u8; 32], // Private noise seed; never committed
+}
+
+/// The registered voters of an election, as closed at registration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Electorate {
+    pub registered_voters: u32,
+    pub registry_root: [u8; 32], // Root of the closed voter registry counted; zero when only a count is claimed
+}
+
+impl Electorate {
+    /// Share of the registered voters `ballots` is, in basis points (10000 is everyone).
+    #[allow(dead_code)] // Only the tally guest reports turnout
+    pub fn turnout_basis_points(&self, ballots: u32) -> u32 {
+        if self.registered_voters == 0 {
+            return 0;
+        }
+        (u64::from(ballots) * 10_000 / u64::from(self.registered_voters)) as u32
+    }
+}
+
+/// Participation in a final tally, committed with the result.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Turnout {
+    pub electorate: Electorate, // From the input; zero when none was given
+    pub submissions: u32, // Ballots submitted, accepted or not (audit log records other than revocations)
+    pub rejected_submissions: u32,
+    pub revoked_ballots: u32, // Counted ballots their voters took back
+    pub ballots_tallied: u32, // One per voter whose latest ballot counts, after revocations
+    pub turnout_basis_points: u32, // ballots_tallied per 10000 registered voters; zero without an electorate
+}
+
+/// Who won a final tally, committed so the counts need no interpreting afterwards.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Outcome {
+    pub leading: u32, // Bit i set for each candidate index i with the top published count
+    pub winner: u32, // 1-based winning option; 0 for none (no votes, withheld counts or a declared tie)
+    pub tie_break: TieBreak, // The election's rule, whether or not a tie needed it
+}
+
+impl Outcome {
+    /// The outcome of the published `counts` under `scheme`, settling a tie by `tie_break`.
+    #[allow(dead_code)] // Only the tally guest decides winners
+    pub fn decide(scheme: &TallyScheme, counts: &[u32], tie_break: TieBreak) -> Self {
+        let counts: Vec<u64> = counts.iter().map(|&count| u64::from(count)).collect();
+        let leading = scheme.finalize(&counts);
+        Outcome {
+            leading: leading.iter().fold(0, |mask, &index| mask | 1 << index),
+            winner: tie_break.winner(&leading, &counts).map_or(0, |index| index as u32 + 1),
+            tie_break,
+        }
+    }
+}
+
+/// An earlier interim tally of the same election, verified inside the guest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriorTally {
+    pub image_id: [u32; 8], // Image the prior receipt was proven with
+    pub journal: Vec<u8>,
+}
+
+/// Published commitments to one trustee's key share coefficients.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareVerificationKey {
+    pub index: u32,
+    pub coefficient_commitments: Vec<[u8; 32]>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareProof {
+    pub nonce_commitment: [u8; 32],
+    pub response: [u8; 32],
+}
+
+/// One trustee's contribution to decrypting a tally ciphertext.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialDecryption {
+    pub trustee_index: u32,
+    pub ciphertext_digest: [u8; 32],
+    pub key_term: u64,
+    pub proof: ShareProof,
+}
+
+/// The trustees' decryption of a final tally, checked share by share inside
+/// the guest. The tally ciphertexts are deterministic in the input, so the
+/// trustees can decrypt them before the proof (see `decryption_shares`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrusteeDecryption {
+    pub threshold: u32,
+    pub verification_keys: Vec<ShareVerificationKey>, // Published by every trustee; hash to the committed trustees_digest
+    pub shares: Vec<Vec<PartialDecryption>>, // At least `threshold` per tally ciphertext, in candidate order
+}
+
+/// One shadow shuffle of a mixnet shuffle proof, opened on one side only.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ShadowOpening {
+    /// shadow[j] = input[permutation[j]] + Enc(0; randomizers[j]), one mask u per ciphertext
+    FromInput { permutation: Vec<u32>, randomizers: Vec<Vec<Vec<u64>>> },
+    /// output[j] = shadow[permutation[j]] + Enc(0; randomizers[j]), a difference of masks
+    ToOutput { permutation: Vec<u32>, randomizers: Vec<Vec<Vec<u64>>> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowRound {
+    pub shadow: Vec<Vec<Vec<u8>>>,
+    pub opening: ShadowOpening,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShuffleProof {
+    pub rounds: Vec<ShadowRound>,
+}
+
+/// Every counted ballot's vote vector after the mixnet, in mixed order, with the
+/// proof that it is a re-randomized permutation of the counted vote vectors.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BallotMix {
+    pub ballots: Vec<Vec<Vec<u8>>>,
+    pub proof: ShuffleProof,
+}
+
+/// Which election a tally is for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ElectionContext {
+    pub election_id: Digest32,
+    pub chain_id: u64, // Chain the result is consumed on, or an operator-chosen domain tag off-chain
+    pub closes_at: u64, // Unix seconds the ballot box closes
+    #[serde(default)]
+    pub candidates_digest: Digest32, // Binds the counts to the ballot text; zero when no list is bound
+    #[serde(default)]
+    pub tie_break: TieBreak, // How a tie for first place is settled, fixed with the election
+    #[serde(default)]
+    pub scheme_digest: Digest32, // Binds the voting rule and weight table (`TallyScheme::digest`); zero for plurality
+}
+
+/// An option as printed on the ballot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Candidate {
+    pub id: u8, // The VoteOption it is counted under
+    pub label: String,
+}
+
+/// EIP-712 signing domain of the contract that consumes the result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Eip712Domain {
+    pub chain_id: u64,
+    pub verifying_contract: [u8; 20],
+}
+
+/// Chain state an on-chain election's ballot set was read at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainAnchor {
+    pub close_block: u64,
+    pub block_hash: [u8; 32], // Hash of the close block on the finalized chain
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct EncryptedVote {
     pub voter_address: String,
+    pub election_id: Digest32, // Election the ballot was cast in; bound by its authorization
+    pub nonce: [u8; 32], // Fresh per ballot from the voter; bound by its authorization
     // PRIVACY FIX: Rick Weber @ Sunscreen.tech feedback
     // Instead of revealing vote choice, encrypt full vote vector
-    pub encrypted_vote_vector: Vec<Vec<u8>>, // [encrypt(1|0), encrypt(1|0), encrypt(1|0)] for each candidate
+    pub encrypted_vote_vector: Vec<Vec<u8>>, // [encrypt(1|0), encrypt(1|0), encrypt(1|0)], each prefixed with the election key fingerprint
     pub signature: String, // Voter signature for authentication
+    pub authorization: BallotAuthorization, // Anonymous eligibility proof
+    pub sequence: u64, // Re-voting: only the voter's highest-sequence valid ballot counts
     
     // Keep for demo purposes to verify correctness, but this would be removed in production
     pub actual_choice: VoteOption, // Only for verification - NOT sent in real system
 }
 
+/// How a ballot proves the voter is eligible, without naming them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum BallotAuthorization {
+    // Blind-signed by the registrar; the ballot is signed with the credential's key
+    Credential { credential: VoterCredential, ballot_signature: SchnorrSignature },
+    Membership(MembershipProof), // Semaphore-style proof against the identity roster
+}
+
+/// Registrar-signed credential token, unlinkable to the voter who requested it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoterCredential {
+    pub token: [u8; 32], // Compressed Ristretto public key of the voter's ballot key
+    pub signature: SchnorrSignature, // Registrar's unblinded signature on the token
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchnorrSignature {
+    pub commitment: [u8; 32], // Compressed Ristretto point R'
+    pub response: [u8; 32],   // Scalar s'
+}
+
+/// One-out-of-many proof of knowing the secret behind some roster commitment,
+/// plus the per-election nullifier that makes the vote unique.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MembershipProof {
+    pub nullifier: [u8; 32],
+    pub challenges: Vec<[u8; 32]>, // One per roster entry, summing to the Fiat-Shamir challenge
+    pub responses: Vec<[u8; 32]>,
+}
+
+/// A voter's signed request to take back one of their counted ballots.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BallotRevocation {
+    pub tracking_code: [u8; 32], // Ballot set leaf hash of the ballot to revoke
+    pub authorization: BallotAuthorization, // By the revoked ballot's voter key, over its revocation message
+}
+
+/// One submission in the hash-chained audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub ballot_hash: [u8; 32], // Ballot set leaf hash of the submitted ballot
+    pub accepted: bool,
+    pub reason: String, // Why the submission was rejected; empty when accepted
+}
+
+/// A 32-byte digest: raw bytes in the journal and bincode, lowercase hex in
+/// JSON and when displayed.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Digest32(pub [u8; 32]);
+
+impl Digest32 {
+    pub const ZERO: Digest32 = Digest32([0u8; 32]);
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Parse 64 hex digits, with or without a 0x prefix.
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
+        if hex.len() != 64 {
+            return None;
+        }
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(hex.get(2 * i..2 * i + 2)?, 16).ok()?;
+        }
+        Some(Digest32(bytes))
+    }
+}
+
+impl From<[u8; 32]> for Digest32 {
+    fn from(bytes: [u8; 32]) -> Self {
+        Digest32(bytes)
+    }
+}
+
+impl From<Digest32> for [u8; 32] {
+    fn from(digest: Digest32) -> Self {
+        digest.0
+    }
+}
+
+impl PartialEq<[u8; 32]> for Digest32 {
+    fn eq(&self, other: &[u8; 32]) -> bool {
+        self.0 == *other
+    }
+}
+
+impl fmt::Display for Digest32 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+impl fmt::Debug for Digest32 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Digest32({})", self)
+    }
+}
+
+impl Serialize for Digest32 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Digest32 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DigestVisitor;
+
+        impl serde::de::Visitor<'_> for DigestVisitor {
+            type Value = Digest32;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a 32-byte digest")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, hex: &str) -> Result<Digest32, E> {
+                Digest32::from_hex(hex).ok_or_else(|| E::invalid_value(serde::de::Unexpected::Str(hex), &self))
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, bytes: &[u8]) -> Result<Digest32, E> {
+                bytes.try_into().map(Digest32).map_err(|_| E::invalid_length(bytes.len(), &self))
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(DigestVisitor)
+        } else {
+            deserializer.deserialize_bytes(DigestVisitor)
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct VoteTallyOutput {
     pub option1_count: u32,
     pub option2_count: u32, 
     pub option3_count: u32,
     pub total_votes: u32,
-    pub computation_hash: String, // Hash of the computation for verification
+    pub election: ElectionContext,
+    pub computation_hash: Digest32, // SHA-256 of the decrypted counts
+    pub ballot_set_root: [u8; 32], // Merkle root of the ballot set that was tallied
+    pub identity_root: [u8; 32], // Merkle root of the identity roster anonymous ballots prove against
+    pub superseded_ballots: u32, // Valid ballots replaced by the same voter's later counted ballot
+    pub turnout: Turnout, // Participation against the registered voters
+    pub outcome: Outcome, // Winner decided from the published counts
+    pub rejected_count: u32, // Ballots in the set the guest excluded (superseded ballots are not excluded)
+    pub duplicate_count: u32, // Of those, replays: a nonce or sequence the voter had already used
+    pub invalid_signature_count: u32, // Of those, a credential, ballot signature or membership proof that failed
+    pub rejections_by_reason: [u32; REJECTION_REASONS], // Excluded ballots per RejectionReason code
+    pub audit_log_head: [u8; 32], // Head of the submission log the tallied ballots were accepted in
+    pub chain_anchor: Option<ChainAnchor>, // Chain state the ballot set was read at, for on-chain elections
+    pub prior_tally_digest: [u8; 32], // Binds the prior tally this proof continues; zero when there is none
+    pub result_typed_digest: [u8; 32], // EIP-712 digest of the result under eip712_domain; zero when there is none
+    pub trustees_digest: [u8; 32], // Threshold and share verification keys the counts were decrypted with; zero when withheld
+    pub encrypted_tally_frames: u32, // Final tally ciphertexts follow as this many journal frames
+}
+
+/// Why the guest excluded a ballot from the tally; the code indexes
+/// `VoteTallyOutput::rejections_by_reason`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectionReason {
+    Malformed = 0, // Wrong number of ciphertexts, or one oversized or undecodable
+    WrongElection = 1,
+    NonCanonicalAddress = 2,
+    InvalidCredential = 3,
+    InvalidSignature = 4, // Not signed by the credential holder
+    InvalidMembership = 5,
+    DuplicateNonce = 6,
+    DuplicateSequence = 7,
+    SchemeRejected = 8, // Not a well-formed ballot under the election's voting scheme
+}
+
+/// Number of `RejectionReason` codes.
+pub const REJECTION_REASONS: usize = 9;
+
+#[allow(dead_code)] // Only the tally guest excludes ballots
+impl RejectionReason {
+    pub fn is_duplicate(self) -> bool {
+        matches!(self, RejectionReason::DuplicateNonce | RejectionReason::DuplicateSequence)
+    }
+
+    pub fn is_invalid_signature(self) -> bool {
+        matches!(self, RejectionReason::InvalidCredential | RejectionReason::InvalidSignature | RejectionReason::InvalidMembership)
+    }
+
+    pub const ALL: [RejectionReason; REJECTION_REASONS] = [
+        RejectionReason::Malformed,
+        RejectionReason::WrongElection,
+        RejectionReason::NonCanonicalAddress,
+        RejectionReason::InvalidCredential,
+        RejectionReason::InvalidSignature,
+        RejectionReason::InvalidMembership,
+        RejectionReason::DuplicateNonce,
+        RejectionReason::DuplicateSequence,
+        RejectionReason::SchemeRejected,
+    ];
+}
+
+/// First journal word of an interim tally, where a final journal has its first count.
+pub const INTERIM_MARKER: u32 = u32::MAX;
+
+/// Journal of an interim tally: the encrypted tallies so far, with the counts sealed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterimTallyOutput {
+    pub marker: u32, // Always INTERIM_MARKER
+    pub ballots: u32, // Ballots in the set tallied so far
+    pub superseded_ballots: u32,
+    pub counted_weight: u64, // Bound on what the tallies hold: each voter's heaviest valid ballot, summed
+    pub election: ElectionContext,
+    pub ballot_set_root: [u8; 32],
+    pub identity_root: [u8; 32],
+    pub election_key_fingerprint: [u8; 32], // Key the encrypted tallies are under
+    pub audit_log_head: [u8; 32], // Head of the submission log prefix the ballots were accepted in
+    pub voter_keys_digest: [u8; 32], // Digest of the ballot set's sorted voter keys (credential tokens or nullifiers)
+    pub prior_tally_digest: [u8; 32], // Binds the prior tally this proof continues; zero when there is none
+    pub encrypted_tally_frames: u32, // Encrypted tallies follow as this many journal frames
+}
+
+/// Input of the aggregation guest: batch tally journals to verify and combine.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregateInput {
+    pub tally_image_id: [u32; 8], // Image the batch receipts were proven with
+    pub batch_journals: Vec<Vec<u8>>, // Interim tally journals, one per batch
+    pub batch_voter_keys: Vec<Vec<[u8; 32]>>, // Each batch's sorted voter keys, which no two batches may share
+}
+
+/// Journal of the aggregation guest: the combined encrypted tallies of every batch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregateTallyOutput {
+    pub tally_image_id: [u32; 8],
+    pub batches: u32,
+    pub ballots: u32, // Ballots in all batch sets together
+    pub superseded_ballots: u32, // Superseded within a batch; a voter with ballots in two batches fails the aggregation
+    pub election: ElectionContext,
+    pub identity_root: [u8; 32],
+    pub election_key_fingerprint: [u8; 32],
+    pub batch_chain_head: [u8; 32], // Hash chain over every batch's ballot set root and audit log head
+    pub encrypted_tally_frames: u32, // Summed encrypted tallies follow as this many journal frames
+}
+
+/// Input of the encrypted vector sum guest: integer vectors of one length,
+/// each slot encrypted and bound to `public_key` like a ballot ciphertext.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VectorSumInput {
+    pub public_key: PublicKey,
+    pub vector_len: u32,
+    pub max_value: u64, // Declared bound on every plaintext slot; caps the contributions
+    pub contributions: Vec<Vec<Vec<u8>>>, // One ciphertext per slot, per contributor
+}
+
+/// Journal of the encrypted vector sum guest: the slot-wise encrypted sums follow as frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorSumOutput {
+    pub vector_len: u32,
+    pub contributions: u32,
+    pub max_value: u64,
+    pub key_fingerprint: [u8; 32],
+    pub contributions_digest: [u8; 32], // Hash over every contribution's ciphertexts, in order
+    pub encrypted_sum_frames: u32,
+}
+
+/// One bidder's sealed bid: for every price level, an encryption of 1 if the
+/// bid reaches that level and 0 otherwise, bound to the auction key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealedBid {
+    pub bidder: String,
+    pub encrypted_levels: Vec<Vec<u8>>,
+}
+
+/// Input of the sealed-bid auction guest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuctionInput {
+    pub public_key: PublicKey,
+    pub price_levels: Vec<u64>, // Public price ladder, strictly ascending
+    pub bids: Vec<SealedBid>,
+}
+
+/// Journal of the sealed-bid auction guest: the encrypted demand at every
+/// price level (bidders bidding at least that price) follows as frames.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuctionOutput {
+    pub price_levels: Vec<u64>,
+    pub bids: u32,
+    pub key_fingerprint: [u8; 32],
+    pub bids_digest: [u8; 32], // Hash over every bidder and its ciphertexts, in order
+    pub encrypted_demand_frames: u32,
+}
+
+/// One instruction of the circuit guest, over named ciphertext registers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CircuitOp {
+    Add { out: String, lhs: String, rhs: String },
+    Sub { out: String, lhs: String, rhs: String },
+    MulPlain { out: String, input: String, scalar: u64 }, // Scalar below the plaintext modulus
+    Rotate { out: String, input: String, steps: u32 }, // Multiply by X^steps; N steps negate
+}
+
+/// A named input ciphertext of the circuit guest, bound to the circuit key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitRegister {
+    pub name: String,
+    pub ciphertext: Vec<u8>,
+}
+
+/// Input of the circuit guest: a small FHE program and the ciphertexts it runs on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitInput {
+    pub public_key: PublicKey,
+    pub inputs: Vec<CircuitRegister>,
+    pub ops: Vec<CircuitOp>,
+    pub outputs: Vec<String>, // Registers committed as journal frames, in this order
+}
+
+/// Journal of the circuit guest: the output registers follow as frames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CircuitOutput {
+    pub key_fingerprint: [u8; 32],
+    pub circuit_digest: [u8; 32], // Hash over the op list and output names
+    pub inputs_digest: [u8; 32], // Hash over every input register, in order
+    pub ops: u32,
+    pub encrypted_output_frames: u32,
+}
+
+/// Input of the challenge guest, as the external challenger sends it to the
+/// prover. The challenge plaintexts and the list of deliberately invalid
+/// ciphertexts stay with the challenger.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeInput {
+    pub public_key: PublicKey,
+    pub challenge_ciphertexts: Vec<Vec<u8>>, // Bound to the challenger's key
+    pub circuit: ChallengeCircuit,
+    pub test_metadata: ChallengeMetadata,
+}
+
+/// The computation the challenger asks for. Challenge i is input register
+/// `c{i}`; rejected challenges have no register.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeCircuit {
+    pub ops: Vec<CircuitOp>,
+    pub outputs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeMetadata {
+    pub test_id: String,
+    pub num_challenges: usize,
+    pub expected_operations: Vec<String>,
+    pub timestamp: u64,
+}
+
+/// Why the challenge guest left a challenge ciphertext out of the circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChallengeRejectionReason {
+    Malformed, // Wrong length, non-canonical coefficient, foreign parameters or bad checksum
+    Duplicate, // Same coefficients as an earlier accepted challenge
+    WrongKey, // Bound to a key other than the challenger's
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChallengeRejection {
+    pub index: u32,
+    pub reason: ChallengeRejectionReason,
+}
+
+/// Journal of the challenge guest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChallengeOutput {
+    pub test_id: String,
+    pub operations_performed: Vec<String>,
+    pub circuit_digest: [u8; 32], // Digest of the circuit executed, as the circuit guest commits it
+    pub result_ciphertexts: Vec<Vec<u8>>, // Serialized output registers, in circuit output order
+    pub intermediate_results: Vec<Vec<u8>>, // The register each op wrote, in op order
+    pub fhe_operation_log: Vec<String>,
+    pub rejection_report: Vec<ChallengeRejection>, // Challenges left out of the circuit, in input order
+    pub challenge_proof_complete: bool,
+}
+
+/// One chunk of streamed journal output, committed after `VoteTallyOutput`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalFrame {
+    pub index: u32,
+    pub payload: Vec<u8>,
+    pub chunk_hash: [u8; 32], // Chained over every earlier frame, so frames cannot be reordered
 }
\ No newline at end of file
@@ -0,0 +1,28 @@
+// The election public key as plain data.
+// Journal and input types name the key, but verifying a result never encrypts,
+// so this stands in for the host's `fhe_client` without any of the scheme.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use risc0_zkvm::sha::{Impl, Sha256};
+use serde::{Serialize, Deserialize};
+
+// Domain separation tag for key fingerprints (must match host implementation)
+const KEY_FINGERPRINT_TAG: u8 = 0x05;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublicKey {
+    pub key_data: Vec<u64>,
+}
+
+impl PublicKey {
+    /// H(tag || coefficients), as committed in the journals' `key_fingerprint`.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let mut buf = vec![KEY_FINGERPRINT_TAG];
+        for coeff in &self.key_data {
+            buf.extend_from_slice(&coeff.to_le_bytes());
+        }
+        (*Impl::hash_bytes(&buf)).into()
+    }
+}
@@ -0,0 +1,12 @@
+// Guest image ids receipts are checked against.
+// By default these are the ids published in `published_image_ids.rs`, which
+// the host's `image_ids` test keeps in step with the guest build, so the
+// crate needs neither the guests nor the FHE code. With the opt-in
+// `guest-build` feature they are the `methods` crate's own constants, computed
+// from the guest ELFs as they are built.
+
+#[cfg(feature = "guest-build")]
+pub use methods::{AGGREGATE_ID, AUCTION_ID, CHALLENGE_ID, CIRCUIT_ID, FHE_VOTING_ID, VECTOR_SUM_ID};
+
+#[cfg(not(feature = "guest-build"))]
+include!("published_image_ids.rs");
@@ -0,0 +1,246 @@
+// Third-party verifier toolkit.
+// Everything an auditor needs to check a published result and nothing more:
+// receipt verification against the published guest image ids and the readers
+// for every guest's journal. It depends on neither the prover nor the FHE
+// scheme, so checking a result pulls in a small dependency surface.
+//
+// The output types and the journal reader are the host's own sources, compiled
+// here by path (as the guest compiles its copy of the types), so the two
+// decoders cannot drift apart. The only FHE value the outputs carry is the
+// election public key, which `fhe_client` holds as plain data. `image_ids`
+// holds the published ids, or re-exports the guest build's with the opt-in
+// `guest-build` feature.
+//
+// With default features off the crate is `no_std` and needs only an allocator,
+// so a receipt can be checked on an embedded device or inside another guest.
+// The shared sources take `String` and `Vec` from `alloc` for that reason.
+// Dev mode is then only what the `VerifierContext` says, as there is no
+// environment to read `RISC0_DEV_MODE` from.
+//
+// A receipt proves a tally of some election; the tally, interim and aggregate
+// readers take the `ElectionContext` the auditor expects and refuse a receipt
+// that commits any other, so a valid proof of one election is never accepted
+// as the result of another.
+//
+// A build that skips the guests (RISC0_SKIP_BUILD), or published ids not yet
+// regenerated, leaves all-zero placeholder ids. Every reader refuses to verify
+// against one, even in dev mode, so an unconfigured verifier cannot accept fake
+// receipts claiming the placeholder image.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[path = "../../host/src/types.rs"]
+pub mod types;
+#[path = "../../host/src/journal.rs"]
+pub mod journal;
+#[path = "../../methods/guest/src/scheme.rs"]
+pub mod scheme;
+pub mod fhe_client;
+pub mod image_ids;
+
+use alloc::string::{String, ToString};
+
+use risc0_zkvm::{Receipt, VerifierContext};
+use thiserror::Error;
+
+use image_ids::{AGGREGATE_ID, AUCTION_ID, CHALLENGE_ID, CIRCUIT_ID, FHE_VOTING_ID, VECTOR_SUM_ID};
+use journal::{read_aggregate_journal, read_auction_journal, read_challenge_journal, read_circuit_journal, read_interim_journal, read_tally_journal, read_vector_sum_journal};
+use journal::{AggregateJournal, AuctionJournal, CircuitJournal, InterimJournal, JournalError, TallyJournal, VectorSumJournal};
+use types::{ChallengeOutput, ElectionContext, JournalProfile};
+
+#[derive(Error, Debug)]
+pub enum VerifierError {
+    #[error("Receipt verification failed: {reason}")]
+    Receipt { reason: String },
+    #[error("Journal decoding failed: {0}")]
+    Journal(#[from] JournalError),
+    #[error("Receipt is for another election: its {field} differs")]
+    ElectionMismatch { field: &'static str },
+    #[error("Image id is an all-zero placeholder; build the guests or regenerate published_image_ids.rs")]
+    UnpublishedImageId,
+}
+
+/// Verify that `receipt` proves a run of the guest with `image_id`.
+pub fn verify_receipt(ctx: &VerifierContext, receipt: &Receipt, image_id: [u32; 8]) -> Result<(), VerifierError> {
+    if image_id == [0; 8] {
+        return Err(VerifierError::UnpublishedImageId);
+    }
+    receipt.verify_with_context(ctx, image_id).map_err(|e| VerifierError::Receipt { reason: e.to_string() })
+}
+
+/// Check that a journal commits the `expected` election, naming the first field that differs.
+pub fn check_election(expected: &ElectionContext, committed: &ElectionContext) -> Result<(), VerifierError> {
+    let field = if committed.election_id != expected.election_id {
+        "election id"
+    } else if committed.chain_id != expected.chain_id {
+        "chain id"
+    } else if committed.closes_at != expected.closes_at {
+        "ballot close time"
+    } else if committed.candidates_digest != expected.candidates_digest {
+        "candidate list"
+    } else if committed.tie_break != expected.tie_break {
+        "tie-break rule"
+    } else if committed.scheme_digest != expected.scheme_digest {
+        "voting scheme"
+    } else {
+        return Ok(());
+    };
+    Err(VerifierError::ElectionMismatch { field })
+}
+
+/// Verify a tally receipt for the `expected` election and decode its result;
+/// `detached_frames` is the guest's stdout for a compact journal and ignored
+/// for a full one.
+pub fn verify_tally(ctx: &VerifierContext, receipt: &Receipt, expected: &ElectionContext, profile: JournalProfile, detached_frames: &[u8]) -> Result<TallyJournal, VerifierError> {
+    verify_receipt(ctx, receipt, FHE_VOTING_ID)?;
+    let journal = read_tally_journal(profile, &receipt.journal.bytes, detached_frames)?;
+    check_election(expected, &journal.output.election)?;
+    Ok(journal)
+}
+
+/// Verify an interim tally receipt (from the tally guest) for the `expected` election and decode it.
+pub fn verify_interim(ctx: &VerifierContext, receipt: &Receipt, expected: &ElectionContext) -> Result<InterimJournal, VerifierError> {
+    verify_receipt(ctx, receipt, FHE_VOTING_ID)?;
+    let journal = read_interim_journal(&receipt.journal.bytes)?;
+    check_election(expected, &journal.output.election)?;
+    Ok(journal)
+}
+
+pub fn verify_aggregate(ctx: &VerifierContext, receipt: &Receipt, expected: &ElectionContext) -> Result<AggregateJournal, VerifierError> {
+    verify_receipt(ctx, receipt, AGGREGATE_ID)?;
+    let journal = read_aggregate_journal(&receipt.journal.bytes)?;
+    check_election(expected, &journal.output.election)?;
+    Ok(journal)
+}
+
+pub fn verify_vector_sum(ctx: &VerifierContext, receipt: &Receipt) -> Result<VectorSumJournal, VerifierError> {
+    verify_receipt(ctx, receipt, VECTOR_SUM_ID)?;
+    Ok(read_vector_sum_journal(&receipt.journal.bytes)?)
+}
+
+pub fn verify_auction(ctx: &VerifierContext, receipt: &Receipt) -> Result<AuctionJournal, VerifierError> {
+    verify_receipt(ctx, receipt, AUCTION_ID)?;
+    Ok(read_auction_journal(&receipt.journal.bytes)?)
+}
+
+pub fn verify_circuit(ctx: &VerifierContext, receipt: &Receipt) -> Result<CircuitJournal, VerifierError> {
+    verify_receipt(ctx, receipt, CIRCUIT_ID)?;
+    Ok(read_circuit_journal(&receipt.journal.bytes)?)
+}
+
+/// Verify a receipt of the challenge guest, for the external challenger, and decode its journal.
+pub fn verify_challenge(ctx: &VerifierContext, receipt: &Receipt) -> Result<ChallengeOutput, VerifierError> {
+    verify_receipt(ctx, receipt, CHALLENGE_ID)?;
+    Ok(read_challenge_journal(&receipt.journal.bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use risc0_zkvm::{FakeReceipt, InnerReceipt, ReceiptClaim};
+
+    use journal::chain_frame;
+
+    // Stands in for a published image id: the generated ones may still be placeholders
+    const TEST_ID: [u32; 8] = [0x1d; 8];
+    use types::{ChallengeRejection, ChallengeRejectionReason, CircuitOutput, Digest32, InterimTallyOutput, JournalFrame, INTERIM_MARKER};
+
+    fn fake_receipt(image_id: [u32; 8], words: Vec<u32>) -> Receipt {
+        let journal: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+        Receipt::new(InnerReceipt::Fake(FakeReceipt::new(ReceiptClaim::ok(image_id, journal.clone()))), journal)
+    }
+
+    #[test]
+    fn test_circuit_receipt_verifies_against_its_image_id() -> Result<(), Box<dyn std::error::Error>> {
+        let output = CircuitOutput { key_fingerprint: [1; 32], circuit_digest: [2; 32], inputs_digest: [3; 32], ops: 4, encrypted_output_frames: 1 };
+        let payload = vec![7u8; 16];
+        let mut words = risc0_zkvm::serde::to_vec(&output)?;
+        words.extend(risc0_zkvm::serde::to_vec(&JournalFrame { index: 0, payload: payload.clone(), chunk_hash: chain_frame(&[0; 32], 0, &payload) })?);
+        let receipt = fake_receipt(TEST_ID, words);
+
+        // Fake receipts only verify in dev mode, and only for their own image id
+        let ctx = VerifierContext::default().with_dev_mode(true);
+        verify_receipt(&ctx, &receipt, TEST_ID)?;
+        let verified = read_circuit_journal(&receipt.journal.bytes)?;
+        assert_eq!((verified.output.ops, verified.encrypted_outputs), (4, vec![payload]));
+        assert!(matches!(verify_receipt(&VerifierContext::default().with_dev_mode(false), &receipt, TEST_ID), Err(VerifierError::Receipt { .. })));
+        assert!(matches!(verify_receipt(&ctx, &receipt, [0x2e; 8]), Err(VerifierError::Receipt { .. })));
+        assert!(read_auction_journal(&receipt.journal.bytes).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_placeholder_image_ids_are_refused() {
+        // A fake receipt claiming the placeholder image, even in dev mode
+        let receipt = fake_receipt([0; 8], Vec::new());
+        let ctx = VerifierContext::default().with_dev_mode(true);
+        assert!(matches!(verify_receipt(&ctx, &receipt, [0; 8]), Err(VerifierError::UnpublishedImageId)));
+        for id in [FHE_VOTING_ID, AGGREGATE_ID, VECTOR_SUM_ID, AUCTION_ID, CIRCUIT_ID, CHALLENGE_ID] {
+            if id == [0; 8] {
+                assert!(matches!(verify_receipt(&ctx, &fake_receipt(id, Vec::new()), id), Err(VerifierError::UnpublishedImageId)));
+            }
+        }
+        assert!(matches!(verify_circuit(&ctx, &receipt), Err(VerifierError::UnpublishedImageId | VerifierError::Receipt { .. })));
+    }
+
+    #[test]
+    fn test_challenge_journal_carries_the_rejection_report() -> Result<(), Box<dyn std::error::Error>> {
+        let output = ChallengeOutput {
+            test_id: "negative".into(),
+            operations_performed: vec!["DeserializeChallenges".into()],
+            circuit_digest: [2; 32],
+            result_ciphertexts: vec![vec![7; 16]],
+            intermediate_results: Vec::new(),
+            fhe_operation_log: Vec::new(),
+            rejection_report: vec![ChallengeRejection { index: 3, reason: ChallengeRejectionReason::WrongKey }],
+            challenge_proof_complete: true,
+        };
+        let mut words = risc0_zkvm::serde::to_vec(&output)?;
+        let ctx = VerifierContext::default().with_dev_mode(true);
+        let receipt = fake_receipt(TEST_ID, words.clone());
+        verify_receipt(&ctx, &receipt, TEST_ID)?;
+        let verified = read_challenge_journal(&receipt.journal.bytes)?;
+        assert_eq!((verified.circuit_digest, verified.rejection_report), (output.circuit_digest, output.rejection_report));
+
+        words.push(0);
+        let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+        assert!(matches!(read_challenge_journal(&bytes), Err(JournalError::TrailingData { .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn test_receipt_for_another_election_is_refused() -> Result<(), Box<dyn std::error::Error>> {
+        let election = ElectionContext { election_id: Digest32([1; 32]), chain_id: 1, closes_at: 1_700_000_000, ..ElectionContext::default() };
+        let output = InterimTallyOutput {
+            marker: INTERIM_MARKER,
+            ballots: 2,
+            superseded_ballots: 0,
+            counted_weight: 0,
+            election,
+            ballot_set_root: [2; 32],
+            identity_root: [3; 32],
+            election_key_fingerprint: [4; 32],
+            audit_log_head: [5; 32],
+            voter_keys_digest: [6; 32],
+            prior_tally_digest: [0; 32],
+            encrypted_tally_frames: 0,
+        };
+        let receipt = fake_receipt(TEST_ID, risc0_zkvm::serde::to_vec(&output)?);
+        verify_receipt(&VerifierContext::default().with_dev_mode(true), &receipt, TEST_ID)?;
+        let committed = read_interim_journal(&receipt.journal.bytes)?.output.election;
+        check_election(&election, &committed)?;
+
+        // The same proof presented for another election, chain, close time or voting scheme
+        let other = ElectionContext { election_id: Digest32([9; 32]), ..election };
+        assert!(matches!(check_election(&other, &committed), Err(VerifierError::ElectionMismatch { field: "election id" })));
+        let other = ElectionContext { chain_id: 10, ..election };
+        assert!(matches!(check_election(&other, &committed), Err(VerifierError::ElectionMismatch { field: "chain id" })));
+        let other = ElectionContext { closes_at: 0, ..election };
+        assert!(matches!(check_election(&other, &committed), Err(VerifierError::ElectionMismatch { field: "ballot close time" })));
+        let other = ElectionContext { scheme_digest: Digest32([3; 32]), ..election };
+        assert!(matches!(check_election(&other, &committed), Err(VerifierError::ElectionMismatch { field: "voting scheme" })));
+        Ok(())
+    }
+}
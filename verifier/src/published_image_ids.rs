@@ -0,0 +1,9 @@
+// Guest image ids, generated from the guest build by host/tests/image_ids.rs.
+// Do not edit; rerun that test with FHE_VOTING_UPDATE_IMAGE_IDS=1 instead.
+
+pub const FHE_VOTING_ID: [u32; 8] = [0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000];
+pub const AGGREGATE_ID: [u32; 8] = [0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000];
+pub const VECTOR_SUM_ID: [u32; 8] = [0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000];
+pub const AUCTION_ID: [u32; 8] = [0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000];
+pub const CIRCUIT_ID: [u32; 8] = [0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000];
+pub const CHALLENGE_ID: [u32; 8] = [0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000];
@@ -0,0 +1,89 @@
+// Browser receipt verifier.
+// wasm-bindgen bindings over `fhe-voting-verifier`, so anyone can drop a
+// receipt file into `www/index.html` and check a published result without
+// installing anything: the receipt is verified against the published guest
+// image ids and the journal decoded entirely in the page.
+//
+// Receipts are read in the host's `receipt.bin` encoding (bincode, as written
+// to archives). Results are returned as JSON: the guest's committed output
+// and its encrypted frames, hex-encoded. Dev-mode receipts are always
+// rejected, whatever the page's environment.
+//
+// Tally, interim and aggregate receipts are checked against the election the
+// user expects, given as the published `ElectionContext` JSON, so a valid
+// receipt of another election is refused rather than displayed.
+
+use risc0_zkvm::{Receipt, VerifierContext};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use fhe_voting_verifier::types::{ElectionContext, JournalProfile};
+use fhe_voting_verifier::{verify_aggregate, verify_auction, verify_circuit, verify_interim, verify_tally, verify_vector_sum};
+
+/// Guest names accepted by `verify_receipt_file`.
+pub const GUESTS: [&str; 7] = ["tally", "tally-compact", "interim", "aggregate", "vector-sum", "auction", "circuit"];
+
+#[derive(Serialize)]
+struct VerifiedResult<T: Serialize> {
+    guest: String,
+    output: T,
+    encrypted_frames: Vec<String>,
+}
+
+/// Verify a receipt of `guest` (one of `GUESTS`) and return its decoded
+/// journal as JSON. `expected_election` is the election's `ElectionContext`
+/// as JSON, required by the tally, interim and aggregate guests and ignored
+/// by the others. `detached_frames` is the guest's stdout for `tally-compact`
+/// and ignored otherwise.
+#[wasm_bindgen(js_name = verifyReceiptFile)]
+pub fn verify_receipt_file(guest: &str, receipt_bytes: &[u8], expected_election: &str, detached_frames: &[u8]) -> Result<String, JsError> {
+    verify_to_json(guest, receipt_bytes, expected_election, detached_frames).map_err(|e| JsError::new(&e))
+}
+
+/// Guest names, for the page's selector.
+#[wasm_bindgen(js_name = supportedGuests)]
+pub fn supported_guests() -> Vec<String> {
+    GUESTS.iter().map(|guest| guest.to_string()).collect()
+}
+
+fn verify_to_json(guest: &str, receipt_bytes: &[u8], expected_election: &str, detached_frames: &[u8]) -> Result<String, String> {
+    let receipt: Receipt = bincode::deserialize(receipt_bytes).map_err(|e| format!("Not a receipt file: {}", e))?;
+    let ctx = VerifierContext::default().with_dev_mode(false);
+    let election = || -> Result<ElectionContext, String> {
+        serde_json::from_str(expected_election).map_err(|e| format!("Expected election is not an election context: {}", e))
+    };
+    let json = match guest {
+        "tally" | "tally-compact" => {
+            let profile = if guest == "tally" { JournalProfile::Full } else { JournalProfile::Compact };
+            let journal = verify_tally(&ctx, &receipt, &election()?, profile, detached_frames).map_err(|e| e.to_string())?;
+            to_json(guest, journal.output, &journal.encrypted_tallies)
+        }
+        "interim" => {
+            let journal = verify_interim(&ctx, &receipt, &election()?).map_err(|e| e.to_string())?;
+            to_json(guest, journal.output, &journal.encrypted_tallies)
+        }
+        "aggregate" => {
+            let journal = verify_aggregate(&ctx, &receipt, &election()?).map_err(|e| e.to_string())?;
+            to_json(guest, journal.output, &journal.encrypted_tallies)
+        }
+        "vector-sum" => {
+            let journal = verify_vector_sum(&ctx, &receipt).map_err(|e| e.to_string())?;
+            to_json(guest, journal.output, &journal.encrypted_sums)
+        }
+        "auction" => {
+            let journal = verify_auction(&ctx, &receipt).map_err(|e| e.to_string())?;
+            to_json(guest, journal.output, &journal.encrypted_demand)
+        }
+        "circuit" => {
+            let journal = verify_circuit(&ctx, &receipt).map_err(|e| e.to_string())?;
+            to_json(guest, journal.output, &journal.encrypted_outputs)
+        }
+        _ => return Err(format!("Unknown guest {}; expected one of {}", guest, GUESTS.join(", "))),
+    };
+    json.map_err(|e| format!("Result encoding failed: {}", e))
+}
+
+fn to_json<T: Serialize>(guest: &str, output: T, frames: &[Vec<u8>]) -> serde_json::Result<String> {
+    let encrypted_frames = frames.iter().map(|frame| frame.iter().map(|b| format!("{:02x}", b)).collect()).collect();
+    serde_json::to_string(&VerifiedResult { guest: guest.to_string(), output, encrypted_frames })
+}
@@ -0,0 +1,72 @@
+// Built by `wasm-pack build --target web` into ../pkg
+import init, { supportedGuests, verifyReceiptFile } from "../pkg/fhe_voting_verifier_wasm.js";
+
+const guest = document.getElementById("guest");
+const election = document.getElementById("election");
+const receiptDrop = document.getElementById("receipt-drop");
+const framesDrop = document.getElementById("frames-drop");
+const status = document.getElementById("status");
+const result = document.getElementById("result");
+
+let receipt = null;
+let frames = new Uint8Array();
+
+// Guests whose journal commits an election context
+const ELECTION_GUESTS = ["tally", "tally-compact", "interim", "aggregate"];
+
+function onDrop(zone, load) {
+  zone.addEventListener("dragover", (event) => {
+    event.preventDefault();
+    zone.classList.add("over");
+  });
+  zone.addEventListener("dragleave", () => zone.classList.remove("over"));
+  zone.addEventListener("drop", async (event) => {
+    event.preventDefault();
+    zone.classList.remove("over");
+    const file = event.dataTransfer.files[0];
+    if (file) {
+      zone.textContent = file.name;
+      load(new Uint8Array(await file.arrayBuffer()));
+      verify();
+    }
+  });
+}
+
+function verify() {
+  if (!receipt) {
+    return;
+  }
+  if (guest.value === "tally-compact" && frames.length === 0) {
+    status.className = "";
+    status.textContent = "A compact tally also needs its detached frames.";
+    return;
+  }
+  if (ELECTION_GUESTS.includes(guest.value) && election.value.trim() === "") {
+    status.className = "";
+    status.textContent = "Enter the election the receipt should be for.";
+    return;
+  }
+  try {
+    const verified = JSON.parse(verifyReceiptFile(guest.value, receipt, election.value, frames));
+    status.className = "ok";
+    status.textContent = `Receipt verifies for the ${verified.guest} guest.`;
+    result.textContent = JSON.stringify(verified.output, null, 2);
+    result.hidden = false;
+  } catch (error) {
+    status.className = "error";
+    status.textContent = error.message ?? String(error);
+    result.hidden = true;
+  }
+}
+
+await init();
+for (const name of supportedGuests()) {
+  guest.add(new Option(name, name));
+}
+guest.addEventListener("change", () => {
+  framesDrop.hidden = guest.value !== "tally-compact";
+  verify();
+});
+election.addEventListener("change", verify);
+onDrop(receiptDrop, (bytes) => (receipt = bytes));
+onDrop(framesDrop, (bytes) => (frames = bytes));